@@ -0,0 +1,136 @@
+//! Benchmarks for the hot paths of comment sync and reply generation.
+//!
+//! Run with `cargo bench`; compare against a saved baseline with
+//! `cargo bench -- --save-baseline main` and `cargo bench -- --baseline main`.
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use tokio::runtime::Runtime;
+
+use youtube_commenter::api::export::CsvExport;
+use youtube_commenter::db;
+use youtube_commenter::models::ai::ReplyGenerationRequest;
+use youtube_commenter::models::{Comment, CommentBuilder};
+use youtube_commenter::services::prompts::PromptSet;
+use youtube_commenter::services::{ai, keywords::TermCounter, sentiment, tones};
+
+/// Comments in one YouTube `commentThreads` page
+const PAGE_SIZE: usize = 100;
+
+const SAMPLE_TEXTS: [&str; 4] = [
+    "Loved the Rust tutorial! Can you do async next? #RustLang",
+    "This was confusing and way too fast, not helpful at all",
+    "Great explanation of lifetimes, finally clicked for me 🙏 https://example.com",
+    "What microphone do you use? The audio quality is amazing",
+];
+
+fn sample_comments(video_id: &str, count: usize) -> Vec<Comment> {
+    (0..count)
+        .map(|i| {
+            CommentBuilder::new(video_id, &format!("{}-comment-{}", video_id, i))
+                .author(&format!("Viewer {}", i))
+                .author_channel(&format!("channel-{}", i))
+                .text(SAMPLE_TEXTS[i % SAMPLE_TEXTS.len()])
+                .likes(i as i32)
+                .build()
+        })
+        .collect()
+}
+
+fn bench_text_analysis(c: &mut Criterion) {
+    let comments = sample_comments("bench", PAGE_SIZE);
+
+    c.bench_function("sentiment_score_page", |b| {
+        b.iter(|| {
+            for comment in &comments {
+                black_box(sentiment::score(&comment.text));
+            }
+        })
+    });
+
+    c.bench_function("term_counter_page", |b| {
+        b.iter(|| {
+            let mut counter = TermCounter::default();
+            for comment in &comments {
+                counter.add(&comment.text);
+            }
+            black_box(counter.top_keywords(20))
+        })
+    });
+}
+
+fn bench_prompt_building(c: &mut Criterion) {
+    let request = ReplyGenerationRequest {
+        comment_text: SAMPLE_TEXTS[0].to_string(),
+        comment_author: "Viewer".to_string(),
+        video_title: "Rust in 100 seconds".to_string(),
+        video_id: "bench".to_string(),
+        previous_interactions: (0..5).map(|i| format!("Replied to comment {}", i)).collect(),
+        thread_replies: (0..3).map(|i| format!("Viewer{}: \"Same question here\"", i)).collect(),
+        timestamps: Vec::new(),
+        tone: "friendly".to_string(),
+        persona: None,
+        reply_language: None,
+        template: None,
+        de_escalate: false,
+        follow_up: None,
+        additional_instructions: Some("Mention the next video".to_string()),
+        max_length: None,
+        parameter_overrides: None,
+    };
+
+    let prompts = PromptSet::builtin();
+    let tone = tones::builtin_tones().into_iter().find(|tone| tone.tone_id == request.tone);
+
+    c.bench_function("build_prompt", |b| {
+        b.iter(|| {
+            black_box(prompts.system_message(tone.as_ref(), request.persona.as_deref()));
+            black_box(ai::build_user_message(&request));
+        })
+    });
+}
+
+fn bench_export(c: &mut Criterion) {
+    let comments = sample_comments("bench", PAGE_SIZE);
+
+    c.bench_function("csv_rows_page", |b| b.iter(|| black_box(comments.csv_rows())));
+}
+
+fn bench_database(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let db = runtime.block_on(db::init_db()).unwrap();
+
+    // Each batch writes a fresh video so the table grows the way it does during a sync
+    let mut batch = 0;
+    c.bench_function("save_comments_page", |b| {
+        b.to_async(&runtime).iter_batched(
+            || {
+                batch += 1;
+                sample_comments(&format!("write-bench-{}", batch), PAGE_SIZE)
+            },
+            |comments| {
+                let db = db.clone();
+                async move {
+                    let video_id = comments[0].video_id.clone();
+                    db.save_comments(&video_id, &comments).await.unwrap();
+                }
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    runtime.block_on(db.save_comments("read-bench", &sample_comments("read-bench", PAGE_SIZE))).unwrap();
+
+    c.bench_function("get_comments_page", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { black_box(db.get_comments("read-bench").await.unwrap()) })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_text_analysis,
+    bench_prompt_building,
+    bench_export,
+    bench_database
+);
+criterion_main!(benches);
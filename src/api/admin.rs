@@ -0,0 +1,259 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use super::handlers::AppState;
+use crate::error::{AppError, AppResult};
+use crate::models::ai::{AiTask, ModelRoute};
+use crate::models::prompt::{PromptKind, PromptTemplate};
+use crate::models::settings::{RuntimeSettings, RuntimeSettingsPatch};
+use crate::services::ai::DEFAULT_MODEL;
+use crate::services::retention::{PruneMode, PruneReport};
+use crate::utils::http_log::ALL_ROUTES;
+
+/// Check the `x-admin-token` header against `ADMIN_TOKEN`; the admin API is disabled when it is unset
+pub(crate) fn require_admin(headers: &HeaderMap) -> AppResult<()> {
+    let expected = env::var("ADMIN_TOKEN").ok().filter(|token| !token.is_empty());
+    let given = headers.get("x-admin-token").and_then(|v| v.to_str().ok());
+
+    match (expected, given) {
+        (Some(expected), Some(given)) if expected == given => Ok(()),
+        _ => Err(AppError::Forbidden),
+    }
+}
+
+/// Edited prompt templates and the version generations currently use
+#[derive(Debug, Serialize)]
+pub struct PromptsResponse {
+    /// Version of the prompts in use
+    pub version: u64,
+
+    /// Templates edited through the admin API
+    pub templates: Vec<PromptTemplate>,
+}
+
+/// The prompt version after an edit or reload
+#[derive(Debug, Serialize)]
+pub struct PromptVersionResponse {
+    /// Version of the prompts in use
+    pub version: u64,
+}
+
+/// New text for a prompt template
+#[derive(Debug, Deserialize)]
+pub struct PutPromptRequest {
+    /// The prompt text
+    pub text: String,
+}
+
+pub async fn get_prompts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<PromptsResponse>> {
+    require_admin(&headers)?;
+
+    let templates = state.prompt_library.templates().await?;
+    Ok(Json(PromptsResponse {
+        version: state.prompt_library.current().version,
+        templates,
+    }))
+}
+
+pub async fn put_prompt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((kind, name)): Path<(PromptKind, String)>,
+    Json(request): Json<PutPromptRequest>,
+) -> AppResult<Json<PromptVersionResponse>> {
+    require_admin(&headers)?;
+
+    if request.text.trim().is_empty() {
+        return Err(AppError::Validation("Prompt text must not be empty".to_string()));
+    }
+
+    let version = state.prompt_library.save(kind, &name, request.text.trim()).await?;
+    Ok(Json(PromptVersionResponse { version }))
+}
+
+pub async fn delete_prompt(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path((kind, name)): Path<(PromptKind, String)>,
+) -> AppResult<Json<PromptVersionResponse>> {
+    require_admin(&headers)?;
+
+    let version = state.prompt_library.delete(kind, &name).await?;
+    Ok(Json(PromptVersionResponse { version }))
+}
+
+/// Reload the prompts now, e.g. after editing files in `PROMPTS_DIR`
+pub async fn reload_prompts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<PromptVersionResponse>> {
+    require_admin(&headers)?;
+
+    let version = state.prompt_library.reload().await?;
+    Ok(Json(PromptVersionResponse { version }))
+}
+
+/// Routes whose request and response bodies are logged
+#[derive(Debug, Serialize)]
+pub struct HttpLogResponse {
+    /// Route patterns, or `*` for every route
+    pub routes: Vec<String>,
+}
+
+/// Turn body logging on or off for a route
+#[derive(Debug, Deserialize)]
+pub struct PutHttpLogRequest {
+    /// Route pattern as registered, e.g. `/api/comments/:video_id`, or `*` for every route
+    pub route: String,
+
+    /// Whether to log the route's bodies
+    pub enabled: bool,
+}
+
+pub async fn get_http_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<HttpLogResponse>> {
+    require_admin(&headers)?;
+
+    Ok(Json(HttpLogResponse { routes: state.http_log.routes() }))
+}
+
+pub async fn put_http_log(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PutHttpLogRequest>,
+) -> AppResult<Json<HttpLogResponse>> {
+    require_admin(&headers)?;
+
+    if request.route != ALL_ROUTES && !request.route.starts_with('/') {
+        return Err(AppError::Validation("Route must start with / or be *".to_string()));
+    }
+
+    state.http_log.set(&request.route, request.enabled);
+    Ok(Json(HttpLogResponse { routes: state.http_log.routes() }))
+}
+
+pub async fn get_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<RuntimeSettings>> {
+    require_admin(&headers)?;
+
+    Ok(Json((*state.settings.current()).clone()))
+}
+
+/// Change runtime settings; they are stored, so they survive a restart
+pub async fn patch_settings(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(patch): Json<RuntimeSettingsPatch>,
+) -> AppResult<Json<RuntimeSettings>> {
+    require_admin(&headers)?;
+
+    let settings = state.settings.update(patch).await?;
+    Ok(Json((*settings).clone()))
+}
+
+/// The model a task uses by default
+#[derive(Debug, Serialize)]
+pub struct ModelRouteResponse {
+    pub task: AiTask,
+
+    /// ID of the model the task uses
+    pub model_id: String,
+
+    /// Whether the model comes from `model_routing` rather than the built-in default
+    pub routed: bool,
+}
+
+/// New model for a task
+#[derive(Debug, Deserialize)]
+pub struct PutModelRouteRequest {
+    /// ID of a configured AI model
+    pub model_id: String,
+}
+
+/// The default model of every task, routed or not
+pub async fn get_model_routing(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<ModelRouteResponse>>> {
+    require_admin(&headers)?;
+
+    let routes = state.db.get_model_routes().await?;
+    let response = AiTask::ALL
+        .iter()
+        .map(|&task| match routes.iter().find(|route| route.task == task) {
+            Some(route) => ModelRouteResponse { task, model_id: route.model_id.clone(), routed: true },
+            None => ModelRouteResponse { task, model_id: DEFAULT_MODEL.to_string(), routed: false },
+        })
+        .collect();
+    Ok(Json(response))
+}
+
+pub async fn put_model_route(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(task): Path<AiTask>,
+    Json(request): Json<PutModelRouteRequest>,
+) -> AppResult<Json<ModelRouteResponse>> {
+    require_admin(&headers)?;
+
+    let model_id = request.model_id.trim();
+    if model_id.is_empty() {
+        return Err(AppError::Validation("Model ID must not be empty".to_string()));
+    }
+    if state.db.get_ai_model(model_id).await?.is_none() {
+        return Err(AppError::Validation(format!("Unknown AI model {}", model_id)));
+    }
+
+    let route = ModelRoute {
+        task,
+        model_id: model_id.to_string(),
+        updated_at: Utc::now(),
+    };
+    state.db.save_model_route(&route).await?;
+    Ok(Json(ModelRouteResponse { task, model_id: route.model_id, routed: true }))
+}
+
+/// Send a task back to the built-in default model
+pub async fn delete_model_route(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(task): Path<AiTask>,
+) -> AppResult<Json<ModelRouteResponse>> {
+    require_admin(&headers)?;
+
+    state.db.delete_model_route(task).await?;
+    Ok(Json(ModelRouteResponse { task, model_id: DEFAULT_MODEL.to_string(), routed: false }))
+}
+
+/// Options of a pruning run started through the admin API
+#[derive(Debug, Default, Deserialize)]
+pub struct PruneRequest {
+    /// Only count what would be pruned, whatever `RETENTION_MODE` says
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Prune records older than their retention period now, instead of waiting for the daily run
+pub async fn prune_records(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<PruneRequest>,
+) -> AppResult<Json<Vec<PruneReport>>> {
+    require_admin(&headers)?;
+
+    let mode = if request.dry_run { PruneMode::DryRun } else { state.retention.mode() };
+    Ok(Json(state.retention.run(mode).await?))
+}
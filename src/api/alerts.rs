@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::alert::AlertRule;
+use crate::services::alerts::{self, MAX_ALERT_RULES_PER_USER};
+
+/// Create or replace an alert rule
+#[derive(Debug, Deserialize)]
+pub struct AlertRuleRequest {
+    /// Name shown in the rule list and in alerts
+    pub name: String,
+
+    /// Whether the rule is applied
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Words or phrases that trigger an alert (case-insensitive)
+    #[serde(default)]
+    pub phrases: Vec<String>,
+
+    /// Whether comments phrased as questions trigger an alert
+    #[serde(default)]
+    pub questions: bool,
+
+    /// Only alert on comments on this video
+    #[serde(default)]
+    pub video_id: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// List the authenticated user's alert rules with their hit counters
+pub async fn get_alert_rules(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<AlertRule>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.db.get_alert_rules(&user_id).await?))
+}
+
+/// Create an alert rule, applied to comments received from now on
+pub async fn create_alert_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<AlertRuleRequest>,
+) -> AppResult<(StatusCode, Json<AlertRule>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if state.db.get_alert_rules(&user_id).await?.len() >= MAX_ALERT_RULES_PER_USER {
+        return Err(AppError::Validation(format!("At most {} alert rules are allowed", MAX_ALERT_RULES_PER_USER)));
+    }
+
+    let mut rule = AlertRule::new(&user_id, request.name.trim(), request.phrases, request.questions, request.video_id);
+    rule.enabled = request.enabled;
+    alerts::validate(&rule)?;
+
+    state.db.save_alert_rule(&rule).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+/// Replace an alert rule, keeping its hit counter
+pub async fn update_alert_rule(
+    Path(rule_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<AlertRuleRequest>,
+) -> AppResult<Json<AlertRule>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut rule = owned_alert_rule(&state, &user_id, &rule_id).await?;
+    rule.name = request.name.trim().to_string();
+    rule.enabled = request.enabled;
+    rule.phrases = request.phrases;
+    rule.questions = request.questions;
+    rule.video_id = request.video_id;
+    rule.updated_at = Utc::now();
+    alerts::validate(&rule)?;
+
+    state.db.save_alert_rule(&rule).await?;
+    Ok(Json(rule))
+}
+
+/// Delete an alert rule
+pub async fn delete_alert_rule(
+    Path(rule_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    owned_alert_rule(&state, &user_id, &rule_id).await?;
+    state.db.delete_alert_rule(&rule_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// An alert rule of the user; other users' rules are reported as missing
+async fn owned_alert_rule(state: &AppState, user_id: &str, rule_id: &str) -> AppResult<AlertRule> {
+    state
+        .db
+        .get_alert_rule(rule_id)
+        .await?
+        .filter(|rule| rule.user_id == user_id)
+        .ok_or_else(|| AppError::NotFound(format!("Alert rule {}", rule_id)))
+}
@@ -0,0 +1,230 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::Response,
+    Json,
+};
+use serde::Deserialize;
+
+use super::export::{respond, respond_rows, ExportFormat};
+use super::handlers::{get_user_id_from_headers, parse_video_id, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::analytics::Granularity;
+use crate::models::job::{Job, JobKind};
+use crate::models::video::VideoFormat;
+use crate::services::jobs::JobHandle;
+
+/// Longest period the analytics endpoints will compute (in days)
+const MAX_ANALYTICS_DAYS: u32 = 365;
+
+/// Most videos that can be compared at once
+const MAX_COMPARED_VIDEOS: usize = 10;
+
+/// Query parameters for the analytics overview
+#[derive(Debug, Deserialize)]
+pub struct OverviewParams {
+    /// Number of days to cover, including today
+    #[serde(default = "default_days")]
+    pub days: u32,
+    
+    /// Only cover Shorts (`short`) or regular uploads (`long_form`); the AI report ignores this
+    pub video_format: Option<VideoFormat>,
+    
+    /// Output format (`json`, `ndjson` or `csv`)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+fn default_days() -> u32 {
+    30
+}
+
+/// Get the engagement overview for the authenticated user
+pub async fn get_overview(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<OverviewParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let days = params.days.clamp(1, MAX_ANALYTICS_DAYS);
+    
+    let overview = state.analytics_service.overview(&user_id, days, params.video_format).await?;
+    Ok(respond(params.format, "analytics-overview", overview))
+}
+
+/// Get AI cost, token usage, latency and error rates for the authenticated user
+pub async fn get_ai_usage(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<OverviewParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let days = params.days.clamp(1, MAX_ANALYTICS_DAYS);
+    
+    let report = state.analytics_service.ai_usage(&user_id, days).await?;
+    Ok(respond(params.format, "analytics-ai", report))
+}
+
+/// Query parameters for the time-series endpoints
+#[derive(Debug, Deserialize)]
+pub struct TimeSeriesParams {
+    /// Bucket size
+    #[serde(default = "default_granularity")]
+    pub granularity: Granularity,
+    
+    /// Number of days to cover
+    #[serde(default = "default_days")]
+    pub days: u32,
+    
+    /// Limit the trend to a single video
+    pub video_id: Option<String>,
+    
+    /// Limit the trend to Shorts (`short`) or regular uploads (`long_form`)
+    pub video_format: Option<VideoFormat>,
+    
+    /// Output format (`json`, `ndjson` or `csv`)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+fn default_granularity() -> Granularity {
+    Granularity::Day
+}
+
+/// Get comment sentiment over time for the authenticated user's channel and videos
+pub async fn get_sentiment(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<TimeSeriesParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let days = params.days.clamp(1, MAX_ANALYTICS_DAYS);
+    
+    let trend = state
+        .analytics_service
+        .sentiment_trend(&user_id, params.granularity, days, params.video_id.as_deref(), params.video_format)
+        .await?;
+    Ok(respond(params.format, "analytics-sentiment", trend))
+}
+
+/// Get comment volume over time for the authenticated user's channel and videos
+pub async fn get_volume(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<TimeSeriesParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    if params.granularity.surreal_duration().is_none() {
+        return Err(AppError::Validation("Unsupported granularity for comment volume".to_string()));
+    }
+    
+    let days = params.days.clamp(1, MAX_ANALYTICS_DAYS);
+    
+    let volume = state
+        .analytics_service
+        .comment_volume(&user_id, params.granularity, days, params.video_id.as_deref(), params.video_format)
+        .await?;
+    Ok(respond(params.format, "analytics-volume", volume))
+}
+
+/// Query parameters for keyword stats
+#[derive(Debug, Deserialize)]
+pub struct KeywordParams {
+    /// Get the stats for a single video instead of the whole channel
+    pub video_id: Option<String>,
+    
+    /// Output format (`json`, `ndjson` or `csv`)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Get keyword and hashtag frequencies for the channel or a single video
+pub async fn get_keywords(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<KeywordParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    match state.analytics_service.keyword_stats(&user_id, params.video_id.as_deref()).await? {
+        Some(stats) => Ok(respond(params.format, "analytics-keywords", stats)),
+        // Not analyzed yet; the client should start a refresh job
+        None => Err(AppError::NotFound("Keyword stats".to_string())),
+    }
+}
+
+/// Start a job recomputing keyword and hashtag frequencies from stored comments
+pub async fn refresh_keywords(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let total = state.analytics_service.video_count(&user_id).await?;
+    
+    let analytics_service = state.analytics_service.clone();
+    let job_user_id = user_id.clone();
+    let job = state.job_service.start(&user_id, JobKind::KeywordAnalysis, total, move |handle: JobHandle| async move {
+        analytics_service.refresh_keyword_stats(&job_user_id, &handle).await
+    }).await;
+    
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
+
+/// Query parameters for the video comparison
+#[derive(Debug, Deserialize)]
+pub struct CompareParams {
+    /// Comma-separated video IDs
+    pub videos: String,
+    
+    /// Output format (`json`, `ndjson` or `csv`)
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Compare engagement, sentiment, reply rate and unanswered counts of selected videos
+pub async fn compare_videos(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CompareParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let mut video_ids: Vec<String> = Vec::new();
+    for id in params.videos.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+        video_ids.push(parse_video_id(&state.db, id).await?);
+    }
+    
+    if video_ids.is_empty() || video_ids.len() > MAX_COMPARED_VIDEOS {
+        return Err(AppError::Validation(format!("Compare between 1 and {} videos", MAX_COMPARED_VIDEOS)));
+    }
+    
+    let comparisons = state.analytics_service.compare_videos(&user_id, &video_ids).await?;
+    Ok(respond_rows(params.format, "analytics-compare", comparisons))
+}
+
+/// Export one row per day combining engagement, sentiment and AI spend
+pub async fn export_analytics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<OverviewParams>,
+) -> AppResult<Response> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let days = params.days.clamp(1, MAX_ANALYTICS_DAYS);
+    
+    let rows = state.analytics_service.daily_export(&user_id, days).await?;
+    Ok(respond_rows(params.format, "analytics-export", rows))
+}
@@ -0,0 +1,73 @@
+use axum::{extract::State, http::HeaderMap, Json};
+
+use super::admin::require_admin;
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::bootstrap::{Bootstrap, FeatureFlags, MonitorStatus, Permissions, Role};
+
+/// Notification channels compiled into this server
+fn notification_channels() -> Vec<&'static str> {
+    let mut channels = Vec::new();
+    if cfg!(feature = "email") {
+        channels.push("email");
+    }
+    if cfg!(feature = "slack") {
+        channels.push("slack");
+    }
+    if cfg!(feature = "telegram") {
+        channels.push("telegram");
+    }
+    if cfg!(feature = "matrix") {
+        channels.push("matrix");
+    }
+    channels
+}
+
+/// Everything a client needs at startup: the user, their role and permissions,
+/// feature flags, monitor status and what in the inbox awaits them
+pub async fn get_bootstrap(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Bootstrap>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+    let connected = !user.is_disconnected();
+    let reconnect_url = (!connected).then(|| state.auth_service.get_reconnect_url(&user));
+
+    let org = state.db.get_user_organization(&user_id).await?;
+    let role = Role::of(&user_id, org.as_ref());
+    let settings = state.settings.current();
+    let policy = org.as_ref().map(|org| org.policy.clone()).unwrap_or_default();
+    let permissions = Permissions::new(role, &policy, &user.preferences, &settings, connected, require_admin(&headers).is_ok());
+
+    let videos = state.db.get_user_videos(&user_id).await?;
+    let monitor = MonitorStatus {
+        paused: settings.monitor_paused,
+        running: state.monitor.status(&user_id).running,
+        monitored_videos: videos.iter().filter(|video| video.monitor.enabled).count(),
+        last_checked_at: videos.iter().filter_map(|video| video.last_checked_at).max(),
+    };
+
+    let (unread_count, follow_up_count) = state.db.count_inbox_attention(&user_id).await?;
+
+    Ok(Json(Bootstrap {
+        user,
+        connected,
+        reconnect_url,
+        role,
+        organization: org.map(|org| org.name),
+        permissions,
+        features: FeatureFlags {
+            ai_enabled: settings.ai_enabled,
+            dry_run: settings.dry_run,
+            mask_sensitive_text: settings.mask_sensitive_text,
+            notification_channels: notification_channels(),
+        },
+        monitor,
+        unread_count,
+        follow_up_count,
+    }))
+}
@@ -0,0 +1,211 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+use super::handlers::{
+    default_tone, generate_personalized_reply, get_user_id_from_headers, parse_video_id, post_reply_to_comment, AppState,
+    GenerateReplyRequest, PostReplyRequest,
+};
+use crate::error::{AppError, AppResult};
+use crate::models::ai::ReplyGenerationRequest;
+use crate::models::job::{Job, JobItemResult, JobKind};
+use crate::services::clustering::{self, CommentCluster, DEFAULT_THRESHOLD, MAX_CLUSTERED};
+use crate::services::jobs::JobHandle;
+use crate::services::organizations;
+
+/// Query parameters of the cluster list
+#[derive(Debug, Deserialize)]
+pub struct ClusterParams {
+    /// Similarity, between 0 and 1, comments need to be grouped
+    #[serde(default = "default_threshold")]
+    pub threshold: f32,
+
+    /// Also cluster comments that were already answered
+    #[serde(default)]
+    pub include_answered: bool,
+}
+
+fn default_threshold() -> f32 {
+    DEFAULT_THRESHOLD
+}
+
+/// Group a video's comments that ask the same thing, largest groups first
+pub async fn get_clusters(
+    Path(video_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ClusterParams>,
+) -> AppResult<Json<Vec<CommentCluster>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+
+    if !(0.0..=1.0).contains(&params.threshold) {
+        return Err(AppError::Validation("threshold must be between 0 and 1".to_string()));
+    }
+
+    match state.db.get_video(&video_id).await? {
+        Some(video) if video.user_id == user_id => {}
+        _ => return Err(AppError::NotFound(format!("Video {}", video_id))),
+    }
+
+    let mut comments: Vec<_> = state.db.get_comments(&video_id).await?
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| !c.is_hidden() && (params.include_answered || (!c.replied_to && c.triage.is_open())))
+        .collect();
+    comments.sort_by_key(|c| std::cmp::Reverse(c.published_at));
+    comments.truncate(MAX_CLUSTERED);
+
+    Ok(Json(clustering::cluster_comments(state.ai_service.as_ref(), &user_id, &comments, params.threshold).await?))
+}
+
+/// Answer a cluster of similar comments
+#[derive(Debug, Deserialize)]
+pub struct ClusterReplyRequest {
+    /// The comments to answer, e.g. a cluster's `comment_ids`
+    pub comment_ids: Vec<String>,
+
+    /// ID of the tone preset to use for the replies; defaults to the video's, then the user's preferred one
+    #[serde(default)]
+    pub tone: Option<String>,
+
+    /// The persona to write as; defaults to the video's
+    #[serde(default)]
+    pub persona: Option<String>,
+
+    /// Additional instructions for the AI
+    pub additional_instructions: Option<String>,
+
+    /// Post the replies; if `false` they are only saved as drafts
+    #[serde(default = "default_post")]
+    pub post: bool,
+
+    /// Only log and record the replies instead of posting them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_post() -> bool {
+    true
+}
+
+/// Start a job writing one answer for the cluster and replying to each comment with a personalized variant
+pub async fn reply_to_cluster(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ClusterReplyRequest>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if request.comment_ids.is_empty() {
+        return Err(AppError::Validation("comment_ids must not be empty".to_string()));
+    }
+    if !state.settings.current().ai_enabled {
+        return Err(AppError::Unavailable("AI reply generation is switched off".to_string()));
+    }
+    if let Some(tone) = &request.tone {
+        state.tones.get(&user_id, tone).await?;
+    }
+
+    // Other users' comments are reported as missing
+    let mut comments = Vec::with_capacity(request.comment_ids.len());
+    let mut videos = HashMap::new();
+    for comment_id in &request.comment_ids {
+        let comment = state.db.get_comment(comment_id).await?
+            .ok_or_else(|| AppError::NotFound(format!("Comment {}", comment_id)))?;
+        if !videos.contains_key(&comment.video_id) {
+            match state.db.get_video(&comment.video_id).await? {
+                Some(video) if video.user_id == user_id => videos.insert(comment.video_id.clone(), video),
+                _ => return Err(AppError::NotFound(format!("Comment {}", comment_id))),
+            };
+        }
+        comments.push(comment);
+    }
+    let (video_title, reply_defaults) = videos
+        .remove(&comments[0].video_id)
+        .map(|video| (video.title, video.reply_defaults))
+        .unwrap_or_default();
+
+    let job_state = state.clone();
+    let job_user_id = user_id.clone();
+    let job = state.job_service.start(&user_id, JobKind::ClusterReply, comments.len(), move |handle: JobHandle| async move {
+        // One canonical answer covering every question in the cluster
+        let preferences = job_state.db.get_user(&job_user_id).await?.map(|user| user.preferences);
+        let reply_language = preferences.as_ref().and_then(|p| p.preferred_reply_language.clone());
+        let preferred_tone = default_tone(preferences.as_ref());
+        let org_policy = organizations::policy(&job_state.db, &job_user_id).await?;
+        let reply_policy = org_policy.restrict(preferences.map(|p| p.reply_policy).unwrap_or_default());
+        let questions: Vec<String> = comments.iter().map(|c| format!("- {}", c.original_text())).collect();
+        let mut instructions = format!(
+            "Several viewers asked the same thing:\n{}\nWrite one answer that works for all of them, without addressing anyone by name.",
+            questions.join("\n")
+        );
+        if let Some(extra) = &request.additional_instructions {
+            instructions.push_str(&format!(" {}", extra));
+        }
+
+        let canonical = job_state.ai_service.generate_reply(&job_user_id, &ReplyGenerationRequest {
+            comment_text: comments[0].original_text().to_string(),
+            comment_author: "several viewers".to_string(),
+            video_title,
+            video_id: comments[0].video_id.clone(),
+            previous_interactions: Vec::new(),
+            thread_replies: Vec::new(),
+            follow_up: None,
+            timestamps: Vec::new(),
+            tone: request.tone.clone().or(reply_defaults.tone).unwrap_or(preferred_tone),
+            persona: org_policy.persona.clone().or(request.persona.clone()).or(reply_defaults.persona),
+            reply_language,
+            template: None,
+            de_escalate: false,
+            additional_instructions: reply_policy.instructions(org_policy.instructions(Some(&instructions)).as_deref()),
+            max_length: None,
+            parameter_overrides: None,
+        }).await?;
+
+        // A variant of it for each comment
+        for comment in comments {
+            let generate_request = GenerateReplyRequest {
+                comment_id: comment.comment_id.clone(),
+                tone: request.tone.clone(),
+                persona: request.persona.clone(),
+                template_id: None,
+                additional_instructions: request.additional_instructions.clone(),
+                parameters: None,
+            };
+
+            let result = match generate_personalized_reply(&job_state, &job_user_id, &generate_request, Some(canonical.reply_text.clone())).await {
+                Ok(Some(generated)) if request.post => {
+                    let reply_request = PostReplyRequest {
+                        comment_id: comment.comment_id.clone(),
+                        reply_text: generated.reply_text,
+                        ai_generated: true,
+                        ai_model: Some(generated.model),
+                        template_id: None,
+                        dry_run: request.dry_run,
+                        send_at: None,
+                        confirm_hostile: false,
+                    };
+                    match post_reply_to_comment(&job_state, &job_user_id, reply_request).await {
+                        Ok(reply) => JobItemResult::success(&comment.comment_id, json!({ "reply_id": reply.reply_id })),
+                        Err(e) => JobItemResult::failure(&comment.comment_id, e),
+                    }
+                }
+                Ok(Some(generated)) => JobItemResult::success(&comment.comment_id, json!({ "reply_text": generated.reply_text })),
+                Ok(None) => JobItemResult::failure(&comment.comment_id, AppError::NotFound(format!("Comment {}", comment.comment_id))),
+                Err(e) => JobItemResult::failure(&comment.comment_id, e),
+            };
+            handle.record(result).await;
+        }
+        Ok(())
+    }).await;
+
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
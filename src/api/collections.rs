@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::handlers::{get_user_id_from_headers, parse_video_id, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::collection::{CollectionDefaults, VideoCollection};
+use crate::models::video::Video;
+use crate::services::collections::{self, MAX_COLLECTIONS_PER_USER};
+
+/// Create or replace a video collection
+#[derive(Debug, Deserialize)]
+pub struct CollectionRequest {
+    /// Name shown in the collection list
+    pub name: String,
+
+    /// Settings given to videos as they are added
+    #[serde(default)]
+    pub defaults: CollectionDefaults,
+}
+
+/// A collection with its videos
+#[derive(Debug, Serialize)]
+pub struct CollectionResponse {
+    #[serde(flatten)]
+    pub collection: VideoCollection,
+
+    /// The videos in the collection, newest first
+    pub videos: Vec<Video>,
+}
+
+/// List the authenticated user's collections
+pub async fn get_collections(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<VideoCollection>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.db.get_collections(&user_id).await?))
+}
+
+/// Create a collection
+pub async fn create_collection(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CollectionRequest>,
+) -> AppResult<(StatusCode, Json<VideoCollection>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if state.db.get_collections(&user_id).await?.len() >= MAX_COLLECTIONS_PER_USER {
+        return Err(AppError::Validation(format!("At most {} collections are allowed", MAX_COLLECTIONS_PER_USER)));
+    }
+
+    let collection = VideoCollection::new(&user_id, request.name.trim(), request.defaults);
+    collections::validate(&collection)?;
+
+    state.db.save_collection(&collection).await?;
+    Ok((StatusCode::CREATED, Json(collection)))
+}
+
+/// Get a collection and its videos
+pub async fn get_collection(
+    Path(collection_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<CollectionResponse>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let collection = state.collections.get(&user_id, &collection_id).await?;
+    let videos = state.db.get_collection_videos(&collection_id).await?;
+    Ok(Json(CollectionResponse { collection, videos }))
+}
+
+/// Rename a collection or change its defaults; videos already in it keep their settings
+pub async fn update_collection(
+    Path(collection_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CollectionRequest>,
+) -> AppResult<Json<VideoCollection>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut collection = state.collections.get(&user_id, &collection_id).await?;
+    collection.name = request.name.trim().to_string();
+    collection.defaults = request.defaults;
+    collection.updated_at = Utc::now();
+    collections::validate(&collection)?;
+
+    state.db.save_collection(&collection).await?;
+    Ok(Json(collection))
+}
+
+/// Delete a collection; its videos keep their settings
+pub async fn delete_collection(
+    Path(collection_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    state.collections.get(&user_id, &collection_id).await?;
+    state.db.delete_collection(&collection_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Add a video to a collection, giving it the collection's defaults
+pub async fn add_collection_video(
+    Path((collection_id, video_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Video>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+
+    Ok(Json(state.collections.add_video(&user_id, &collection_id, &video_id).await?))
+}
+
+/// Take a video out of a collection
+pub async fn remove_collection_video(
+    Path((collection_id, video_id)): Path<(String, String)>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+
+    state.collections.remove_video(&user_id, &collection_id, &video_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
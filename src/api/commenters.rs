@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::commenter::{CommenterPatch, CommenterProfile, Exchange};
+use crate::models::event::UserEvent;
+use crate::services::commenters::MAX_HISTORY;
+
+/// Most tags on one commenter
+const MAX_TAGS: usize = 20;
+
+/// Longest tag
+const MAX_TAG_LENGTH: usize = 32;
+
+/// Longest notes on a commenter
+const MAX_NOTES_LENGTH: usize = 2000;
+
+/// Get the user's notes and tags on a commenter; empty if none were added
+pub async fn get_commenter(
+    Path(channel_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<CommenterProfile>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let profile = state.db.get_commenter_profile(&user_id, &channel_id).await?
+        .unwrap_or_else(|| CommenterProfile::new(&user_id, &channel_id));
+    Ok(Json(profile))
+}
+
+/// Query parameters of the commenter history
+#[derive(Debug, Deserialize)]
+pub struct HistoryParams {
+    /// Most exchanges returned, newest first
+    #[serde(default = "default_history_limit")]
+    pub limit: usize,
+}
+
+fn default_history_limit() -> usize {
+    MAX_HISTORY
+}
+
+/// List a commenter's comments across the user's videos with the user's replies
+pub async fn get_commenter_history(
+    Path(channel_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<HistoryParams>,
+) -> AppResult<Json<Vec<Exchange>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.commenters.history(&user_id, &channel_id, params.limit).await?))
+}
+
+/// Change the user's notes or tags on a commenter
+pub async fn update_commenter(
+    Path(channel_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(patch): Json<CommenterPatch>,
+) -> AppResult<Json<CommenterProfile>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut profile = state.db.get_commenter_profile(&user_id, &channel_id).await?
+        .unwrap_or_else(|| CommenterProfile::new(&user_id, &channel_id));
+
+    if let Some(notes) = patch.notes {
+        if notes.chars().count() > MAX_NOTES_LENGTH {
+            return Err(AppError::Validation(format!("notes must be at most {} characters", MAX_NOTES_LENGTH)));
+        }
+        profile.notes = Some(notes.trim().to_string()).filter(|n| !n.is_empty());
+    }
+
+    if let Some(tags) = patch.tags {
+        profile.tags = normalize_tags(tags)?;
+    }
+
+    profile.updated_at = Utc::now();
+    state.db.save_commenter_profile(&profile).await?;
+    state.events.publish(&user_id, UserEvent::CommenterUpdated { channel_id });
+    Ok(Json(profile))
+}
+
+/// Trim, lowercase and deduplicate tags, keeping their order
+fn normalize_tags(tags: Vec<String>) -> AppResult<Vec<String>> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() || normalized.contains(&tag) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH || tag.contains(',') {
+            return Err(AppError::Validation(format!(
+                "Tags must be at most {} characters, without commas",
+                MAX_TAG_LENGTH
+            )));
+        }
+        normalized.push(tag);
+    }
+
+    if normalized.len() > MAX_TAGS {
+        return Err(AppError::Validation(format!("At most {} tags are allowed", MAX_TAGS)));
+    }
+
+    Ok(normalized)
+}
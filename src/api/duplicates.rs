@@ -0,0 +1,136 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use super::handlers::{get_user_id_from_headers, post_reply_to_comment, AppState, PostReplyRequest};
+use crate::error::{AppError, AppResult};
+use crate::models::Comment;
+use crate::models::duplicate::DuplicateGroup;
+use crate::models::event::UserEvent;
+use crate::models::job::{Job, JobItemResult, JobKind};
+use crate::services::jobs::JobHandle;
+
+/// Query parameters of the duplicate group list
+#[derive(Debug, Deserialize)]
+pub struct DuplicateParams {
+    /// Smallest group listed
+    #[serde(default = "default_min_comments")]
+    pub min_comments: usize,
+}
+
+fn default_min_comments() -> usize {
+    3
+}
+
+/// List groups of near-identical comments across the user's videos, largest first
+pub async fn get_duplicate_groups(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<DuplicateParams>,
+) -> AppResult<Json<Vec<DuplicateGroup>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.duplicates.groups(&user_id, params.min_comments).await?))
+}
+
+/// List the comments of a duplicate group
+pub async fn get_duplicate_group(
+    Path(group_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<Comment>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.duplicates.members(&user_id, &group_id).await?))
+}
+
+/// Mark every comment of a duplicate group as spam
+pub async fn mark_group_spam(
+    Path(group_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Value>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let marked = state.duplicates.mark_spam(&user_id, &group_id).await?;
+    for comment in &marked {
+        state.events.publish(&user_id, UserEvent::CommentChanged {
+            video_id: comment.video_id.clone(),
+            comment_id: comment.comment_id.clone(),
+        });
+    }
+    Ok(Json(json!({ "group_id": group_id, "marked": marked.len() })))
+}
+
+/// Reply to every comment of a duplicate group with the same text
+#[derive(Debug, Deserialize)]
+pub struct GroupReplyRequest {
+    /// The reply text
+    #[serde(default)]
+    pub reply_text: String,
+
+    /// Saved reply to expand for each comment instead of `reply_text`
+    #[serde(default)]
+    pub template_id: Option<String>,
+
+    /// Only log and record the replies instead of posting them
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Start a job posting one reply to each unanswered comment of a duplicate group
+pub async fn reply_to_group(
+    Path(group_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<GroupReplyRequest>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if request.template_id.is_none() && request.reply_text.trim().is_empty() {
+        return Err(AppError::Validation("reply_text or template_id is required".to_string()));
+    }
+
+    let comment_ids: Vec<String> = state
+        .duplicates
+        .members(&user_id, &group_id)
+        .await?
+        .into_iter()
+        .filter(|comment| !comment.replied_to)
+        .map(|comment| comment.comment_id)
+        .collect();
+
+    let job_state = state.clone();
+    let job_user_id = user_id.clone();
+    let job = state.job_service.start(&user_id, JobKind::BulkPost, comment_ids.len(), move |handle: JobHandle| async move {
+        for comment_id in comment_ids {
+            let reply_request = PostReplyRequest {
+                comment_id: comment_id.clone(),
+                reply_text: request.reply_text.clone(),
+                ai_generated: false,
+                ai_model: None,
+                template_id: request.template_id.clone(),
+                dry_run: request.dry_run,
+                send_at: None,
+                confirm_hostile: false,
+            };
+
+            let result = match post_reply_to_comment(&job_state, &job_user_id, reply_request).await {
+                Ok(reply) => JobItemResult::success(&comment_id, json!({ "reply_id": reply.reply_id })),
+                Err(e) => JobItemResult::failure(&comment_id, e),
+            };
+            handle.record(result).await;
+        }
+        Ok(())
+    }).await;
+
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
@@ -0,0 +1,41 @@
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures::Stream;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+
+/// Stream the authenticated user's events as server-sent events while the connection is open.
+///
+/// Each event is named after its type, with the event as JSON data. A client
+/// too slow to keep up is sent a `lagged` event with how many it missed.
+pub async fn stream_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, axum::Error>>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let receiver = state.events.subscribe(&user_id);
+    let stream = futures::stream::unfold(receiver, move |mut receiver| {
+        let user_id = user_id.clone();
+        async move {
+            let event = match receiver.recv().await {
+                Ok(event) => Event::default().event(event.name()).json_data(&event),
+                Err(RecvError::Lagged(missed)) => {
+                    warn!("Event stream of user {} missed {} events", user_id, missed);
+                    Ok(Event::default().event("lagged").data(missed.to_string()))
+                }
+                Err(RecvError::Closed) => return None,
+            };
+            Some((event, receiver))
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,447 @@
+use axum::{
+    body::{Body, Bytes},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{stream, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+
+use crate::models::Comment;
+use crate::models::analytics::{
+    AiAnalytics, AnalyticsOverview, CommentVolume, DailyExportRow, KeywordStats, SentimentTrend, VideoComparison,
+    VolumeBucket,
+};
+
+/// Output format for analytics endpoints
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    /// Newline-delimited JSON, one object per line
+    Ndjson,
+}
+
+/// A report that can be written as CSV rows
+pub trait CsvExport {
+    /// Column names
+    fn csv_header(&self) -> Vec<&'static str>;
+
+    /// One vector of cells per row, in header order
+    fn csv_rows(&self) -> Vec<Vec<String>>;
+}
+
+/// Respond with a report as JSON or as a streamed CSV download
+pub fn respond<T: Serialize + CsvExport + Send + 'static>(format: ExportFormat, filename: &str, report: T) -> Response {
+    match format {
+        ExportFormat::Json => Json(report).into_response(),
+        ExportFormat::Csv => csv_response(filename, &report),
+        ExportFormat::Ndjson => ndjson_response(vec![report]),
+    }
+}
+
+/// Respond with a list of rows, streamed in any format.
+///
+/// Lists can run to tens of thousands of items, so rows are serialized one chunk
+/// at a time as the body is sent rather than into a single buffer up front.
+pub fn respond_rows<T>(format: ExportFormat, filename: &str, rows: Vec<T>) -> Response
+where
+    T: Serialize + Send + 'static,
+    Vec<T>: CsvExport,
+{
+    match format {
+        ExportFormat::Json => json_array_response(rows),
+        ExportFormat::Csv => csv_response(filename, &rows),
+        ExportFormat::Ndjson => ndjson_response(rows),
+    }
+}
+
+/// Respond with rows read from a stream, e.g. chunks of a database query, in any format.
+///
+/// Rows are pulled as the body is sent, so only what the stream buffers is in
+/// memory at once. An error partway through ends the body early.
+pub fn stream_rows_response<S, T>(format: ExportFormat, filename: &str, rows: S) -> Response
+where
+    S: Stream<Item = anyhow::Result<T>> + Send + 'static,
+    T: Serialize + Send + 'static,
+    Vec<T>: CsvExport,
+{
+    match format {
+        ExportFormat::Json => {
+            let items = rows.enumerate().map(|(i, row)| {
+                let mut chunk = if i == 0 { Vec::new() } else { vec![b','] };
+                serde_json::to_writer(&mut chunk, &row?)?;
+                Ok::<_, anyhow::Error>(Bytes::from(chunk))
+            });
+            let chunks = stream::once(async { Ok(Bytes::from_static(b"[")) })
+                .chain(items)
+                .chain(stream::once(async { Ok(Bytes::from_static(b"]")) }));
+
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/json")],
+                Body::from_stream(chunks),
+            )
+                .into_response()
+        }
+        ExportFormat::Ndjson => {
+            let lines = rows.map(|row| {
+                let mut line = serde_json::to_vec(&row?)?;
+                line.push(b'\n');
+                Ok::<_, anyhow::Error>(Bytes::from(line))
+            });
+
+            (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/x-ndjson")],
+                Body::from_stream(lines),
+            )
+                .into_response()
+        }
+        ExportFormat::Csv => {
+            let header_line = csv_line(Vec::<T>::new().csv_header().into_iter().map(String::from));
+            let lines = rows.map(|row| {
+                let line: String = vec![row?].csv_rows().into_iter().map(csv_line).collect();
+                Ok::<_, anyhow::Error>(Bytes::from(line))
+            });
+            let lines = stream::once(async move { Ok(Bytes::from(header_line)) }).chain(lines);
+
+            (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.csv\"", filename)),
+                ],
+                Body::from_stream(lines),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Stream rows as a JSON array, one chunk per row
+pub fn json_array_response<T: Serialize + Send + 'static>(rows: Vec<T>) -> Response {
+    let items = rows.into_iter().enumerate().map(|(i, row)| {
+        let mut chunk = if i == 0 { Vec::new() } else { vec![b','] };
+        serde_json::to_writer(&mut chunk, &row)?;
+        Ok::<_, serde_json::Error>(Bytes::from(chunk))
+    });
+
+    let chunks = std::iter::once(Ok(Bytes::from_static(b"[")))
+        .chain(items)
+        .chain(std::iter::once(Ok(Bytes::from_static(b"]"))));
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/json")],
+        Body::from_stream(futures::stream::iter(chunks)),
+    )
+        .into_response()
+}
+
+/// Stream rows as newline-delimited JSON, one chunk per row
+pub fn ndjson_response<T: Serialize + Send + 'static>(rows: Vec<T>) -> Response {
+    let lines = rows.into_iter().map(|row| {
+        let mut line = serde_json::to_vec(&row)?;
+        line.push(b'\n');
+        Ok::<_, serde_json::Error>(Bytes::from(line))
+    });
+
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(futures::stream::iter(lines)),
+    )
+        .into_response()
+}
+
+/// Stream a report as a CSV attachment, one chunk per row
+pub fn csv_response(filename: &str, report: &impl CsvExport) -> Response {
+    let header_line = csv_line(report.csv_header().into_iter().map(String::from));
+    let lines = std::iter::once(header_line).chain(report.csv_rows().into_iter().map(csv_line));
+    let stream = futures::stream::iter(lines.map(|line| Ok::<_, Infallible>(Bytes::from(line))));
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.csv\"", filename)),
+        ],
+        Body::from_stream(stream),
+    )
+        .into_response()
+}
+
+/// Format one CSV line, escaping cells as needed
+fn csv_line(cells: impl IntoIterator<Item = String>) -> String {
+    let mut line = cells.into_iter().map(|cell| escape_cell(&cell)).collect::<Vec<_>>().join(",");
+    line.push_str("\r\n");
+    line
+}
+
+/// Quote a cell if needed, and defuse values a spreadsheet would evaluate as a formula
+fn escape_cell(cell: &str) -> String {
+    let cell = if cell.starts_with(['=', '+', '-', '@']) && cell.parse::<f64>().is_err() {
+        format!("'{}", cell)
+    } else {
+        cell.to_string()
+    };
+
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
+
+fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+impl CsvExport for Vec<Comment> {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "video_id",
+            "comment_id",
+            "author",
+            "author_channel_id",
+            "text",
+            "like_count",
+            "published_at",
+            "replies",
+            "replied_to",
+            "triage",
+            "sentiment",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.iter()
+            .map(|c| {
+                vec![
+                    c.video_id.clone(),
+                    c.comment_id.clone(),
+                    c.author.clone(),
+                    c.author_channel_id.clone(),
+                    c.text.clone(),
+                    c.like_count.to_string(),
+                    c.published_at.to_rfc3339(),
+                    c.replies.len().to_string(),
+                    c.replied_to.to_string(),
+                    c.triage.as_str().to_string(),
+                    opt(c.sentiment.map(|s| format!("{:.4}", s))),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl CsvExport for AnalyticsOverview {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["day", "comments_received", "replies_posted", "ai_replies", "manual_replies", "median_time_to_reply_secs"]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.daily
+            .iter()
+            .map(|d| {
+                vec![
+                    d.day.to_string(),
+                    d.comments_received.to_string(),
+                    d.replies_posted.to_string(),
+                    d.ai_replies.to_string(),
+                    d.manual_replies.to_string(),
+                    opt(d.median_time_to_reply_secs),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl CsvExport for SentimentTrend {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["video_id", "start", "comments", "average_sentiment", "positive", "neutral", "negative"]
+    }
+
+    /// Channel-wide rows have an empty video ID
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        let scopes = std::iter::once(("", &self.channel)).chain(self.videos.iter().map(|(id, b)| (id.as_str(), b)));
+
+        scopes
+            .flat_map(|(video_id, buckets)| {
+                buckets.iter().map(move |b| {
+                    vec![
+                        video_id.to_string(),
+                        b.start.to_rfc3339(),
+                        b.comments.to_string(),
+                        format!("{:.4}", b.average_sentiment),
+                        b.positive.to_string(),
+                        b.neutral.to_string(),
+                        b.negative.to_string(),
+                    ]
+                })
+            })
+            .collect()
+    }
+}
+
+impl CsvExport for CommentVolume {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["video_id", "start", "comments"]
+    }
+
+    /// Channel-wide rows have an empty video ID
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        let scopes = std::iter::once(("", &self.channel)).chain(self.videos.iter().map(|(id, b)| (id.as_str(), b)));
+
+        scopes
+            .flat_map(|(video_id, buckets): (&str, &Vec<VolumeBucket>)| {
+                buckets
+                    .iter()
+                    .map(move |b| vec![video_id.to_string(), b.start.to_rfc3339(), b.comments.to_string()])
+            })
+            .collect()
+    }
+}
+
+impl CsvExport for KeywordStats {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec!["kind", "term", "count"]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        let keywords = self.keywords.iter().map(|t| vec!["keyword".to_string(), t.term.clone(), t.count.to_string()]);
+        let hashtags = self.hashtags.iter().map(|t| vec!["hashtag".to_string(), t.term.clone(), t.count.to_string()]);
+        keywords.chain(hashtags).collect()
+    }
+}
+
+impl CsvExport for AiAnalytics {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "model",
+            "requests",
+            "errors",
+            "error_rate",
+            "prompt_tokens",
+            "completion_tokens",
+            "total_tokens",
+            "cost_usd",
+            "p50_latency_ms",
+            "p95_latency_ms",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.models
+            .iter()
+            .map(|m| {
+                vec![
+                    m.model.clone(),
+                    m.requests.to_string(),
+                    m.errors.to_string(),
+                    format!("{:.4}", m.error_rate),
+                    m.prompt_tokens.to_string(),
+                    m.completion_tokens.to_string(),
+                    m.total_tokens.to_string(),
+                    format!("{:.6}", m.cost_usd),
+                    opt(m.p50_latency_ms),
+                    opt(m.p95_latency_ms),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl CsvExport for Vec<VideoComparison> {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "video_id",
+            "title",
+            "published_at",
+            "comments",
+            "replies",
+            "average_likes",
+            "average_sentiment",
+            "positive_share",
+            "negative_share",
+            "reply_rate",
+            "unanswered",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.iter()
+            .map(|v| {
+                vec![
+                    v.video_id.clone(),
+                    v.title.clone(),
+                    v.published_at.to_rfc3339(),
+                    v.comments.to_string(),
+                    v.replies.to_string(),
+                    format!("{:.2}", v.average_likes),
+                    format!("{:.4}", v.average_sentiment),
+                    format!("{:.4}", v.positive_share),
+                    format!("{:.4}", v.negative_share),
+                    format!("{:.4}", v.reply_rate),
+                    v.unanswered.to_string(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl CsvExport for Vec<DailyExportRow> {
+    fn csv_header(&self) -> Vec<&'static str> {
+        vec![
+            "day",
+            "comments_received",
+            "replies_posted",
+            "ai_replies",
+            "manual_replies",
+            "median_time_to_reply_secs",
+            "average_sentiment",
+            "positive_comments",
+            "negative_comments",
+            "ai_requests",
+            "ai_cost_usd",
+        ]
+    }
+
+    fn csv_rows(&self) -> Vec<Vec<String>> {
+        self.iter()
+            .map(|d| {
+                vec![
+                    d.day.to_string(),
+                    d.comments_received.to_string(),
+                    d.replies_posted.to_string(),
+                    d.ai_replies.to_string(),
+                    d.manual_replies.to_string(),
+                    opt(d.median_time_to_reply_secs),
+                    opt(d.average_sentiment.map(|s| format!("{:.4}", s))),
+                    d.positive_comments.to_string(),
+                    d.negative_comments.to_string(),
+                    d.ai_requests.to_string(),
+                    format!("{:.6}", d.ai_cost_usd),
+                ]
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_cell() {
+        assert_eq!(escape_cell("plain"), "plain");
+        assert_eq!(escape_cell("a, b"), "\"a, b\"");
+        assert_eq!(escape_cell("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_cell("-0.25"), "-0.25");
+        assert_eq!(escape_cell("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+    }
+}
@@ -1,30 +1,106 @@
-use axum::
-    extract::{Path, State, Query, Json as AxumJson},
+use axum::{
+    extract::{ConnectInfo, Path, State, Query, Json as AxumJson},
     http::{StatusCode, HeaderMap, Request},
     response::{IntoResponse, Response, Redirect},
     Json,
 };
+use futures::stream::{self, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
-use crate::db::Database;
-use crate::models::{Comment, InteractionRecord, InteractionType, ai::ReplyGenerationRequest};
-use crate::services::{auth::AuthService, youtube::YouTubeService, ai::AiService};
+use super::export::{respond_rows, stream_rows_response, ExportFormat};
+use super::highlights::owned_comment;
+use crate::db::{self, Database};
+use crate::error::{AppError, AppResult};
+use crate::i18n::Locale;
+use crate::utils::{self, http_log::HttpLog, time_zone, upstream::{CircuitState, Upstreams}};
+use crate::models::{Comment, InteractionRecord, InteractionType, TriageState, ai::{ParameterOverrides, ReplyGenerationRequest}, event::UserEvent, notification::{is_slack_webhook_url, SLACK_WEBHOOK_PREFIX}, auth::{AiDisclosure, ReplyPolicy, ReplyTone, RetentionPolicy, UserPreferences}, tone::TonePreset, commenter::{CommenterProfile, COMMENTER_NOTES_KEY, COMMENTER_TAGS_KEY}, conversation::Conversation, video::{MonitorSettings, ReplyDefaults, Video, VideoCursor, VideoFormat, MIN_MONITOR_INTERVAL_SECS}, job::{Job, JobItemResult, JobKind}, draft::ReplyDraft, dashboard::{Capacity, Dashboard}, outbox::QueuedReply, preflight::{PreflightCheck, PreflightReport}};
+use crate::services::{auth::{AuthApi, RECONNECT_STATE_PREFIX}, youtube::YouTubeApi, ai::{self, AiApi}, jobs::{JobService, JobHandle}, masking, analytics::AnalyticsService, collections::CollectionService, commenters::CommenterService, conversations::{self, OwnReplies}, dashboard::DashboardService, dry_run, duplicates::DuplicateService, edits::ReplyDiff, events::EventBus, history, inbox::InboxProjection, live::LiveFeed, monitor::CommentMonitor, mutes::MuteService, notifications::NotificationService, organizations, outbox::Outbox, prompts::{self, PromptLibrary}, reply_checks::ReplyChecker, retention::Pruner, rules::{link_pattern, mention_pattern, MAX_REPLY_LENGTH}, saved_replies::SavedReplyService, sentiment, settings::SettingsService, sharing::ShareLinks, spam::SpamService, tones::ToneService};
 
 /// Application state
+#[derive(Clone)]
 pub struct AppState {
     pub db: Database,
-    pub auth_service: Arc<AuthService>,
-    pub youtube_service: Arc<YouTubeService>,
-    pub ai_service: Arc<AiService>,
+    pub http_client: reqwest::Client,
+    pub upstreams: Upstreams,
+    pub auth_service: Arc<dyn AuthApi>,
+    pub youtube_service: Arc<dyn YouTubeApi>,
+    pub ai_service: Arc<dyn AiApi>,
+    pub job_service: Arc<JobService>,
+    pub analytics_service: Arc<AnalyticsService>,
+    pub dashboard_service: Arc<DashboardService>,
+    pub notification_service: Arc<NotificationService>,
+    pub prompt_library: Arc<PromptLibrary>,
+    pub http_log: Arc<HttpLog>,
+    pub settings: Arc<SettingsService>,
+    pub saved_replies: Arc<SavedReplyService>,
+    pub duplicates: Arc<DuplicateService>,
+    pub outbox: Arc<Outbox>,
+    pub commenters: Arc<CommenterService>,
+    pub spam: Arc<SpamService>,
+    pub collections: Arc<CollectionService>,
+    pub events: Arc<EventBus>,
+    pub inbox: Arc<InboxProjection>,
+    pub preflight: Arc<PreflightReport>,
+    pub tones: Arc<ToneService>,
+    pub mutes: Arc<MuteService>,
+    pub reply_checker: Arc<ReplyChecker>,
+    pub retention: Arc<Pruner>,
+    pub live: Arc<LiveFeed>,
+    pub monitor: Arc<CommentMonitor>,
+    pub share_links: Arc<ShareLinks>,
 }
 
-/// Health check endpoint
-pub async fn health_check() -> Json<Value> {
-    Json(json!({ "status": "ok" }))
+/// Health check endpoint.
+///
+/// Problems the startup checks found are listed; the status is `degraded` if one of them failed outright,
+/// or while the AI provider's circuit is open, when only AI features are `unavailable`.
+pub async fn health_check(State(state): State<AppState>) -> Json<Value> {
+    let ai_unavailable = state.upstreams.openai.state() == CircuitState::Open;
+    let problems: Vec<&PreflightCheck> = state.preflight.problems().collect();
+    if problems.is_empty() && !ai_unavailable {
+        return Json(json!({ "status": "ok" }));
+    }
+
+    let status = if state.preflight.has_failures() || ai_unavailable { "degraded" } else { "ok" };
+    let mut health = json!({ "status": status, "problems": problems, "checked_at": state.preflight.checked_at });
+    if ai_unavailable {
+        health["unavailable"] = json!(["ai"]);
+    }
+    Json(health)
+}
+
+/// Upstream circuit breaker states and database statement stats, in the Prometheus text format
+pub async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let mut body = state.upstreams.render_metrics();
+    body.push_str(&crate::db::queries::render_metrics());
+    
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        body,
+    )
+}
+
+/// Query parameters for listing comments
+#[derive(Debug, Deserialize)]
+pub struct CommentListParams {
+    /// Output format (`json`, `ndjson` or `csv`)
+    #[serde(default)]
+    pub format: ExportFormat,
+    
+    /// Include comments hidden or marked as spam by filter rules
+    #[serde(default)]
+    pub include_hidden: bool,
+    
+    /// Only comments by commenters with this tag
+    pub tag: Option<String>,
+    
+    /// Only comments in this triage state
+    pub triage: Option<TriageState>,
 }
 
 /// Get comments for a YouTube video
@@ -32,76 +108,366 @@ pub async fn get_comments(
     Path(video_id): Path<String>,
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<Vec<Comment>>, StatusCode> {
+    Query(params): Query<CommentListParams>,
+) -> AppResult<Response> {
     info!("Fetching comments for video: {}", video_id);
     
     // Get user ID from session
-    let user_id = get_user_id_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+    owned_video(&state, &user_id, &video_id).await?;
+    let filename = format!("comments-{}", video_id);
     
-    // First, try to get comments from the database
-    match state.db.get_comments(&video_id).await {
-        Ok(Some(comments)) => {
-            info!("Found {} comments in database", comments.len());
-            return Ok(Json(comments));
-        }
-        Ok(None) => {
-            info!("No comments found in database, fetching from YouTube API");
-        }
-        Err(e) => {
-            error!("Error fetching comments from database: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+    // First, try to get comments from the database, a chunk at a time so big videos don't fill memory
+    let mut chunks = state.db.stream_comments(&video_id, db::COMMENT_CHUNK_SIZE);
+    if let Some(first) = chunks.try_next().await? {
+        info!("Streaming comments from database");
+        let format = params.format;
+        let view = InboxView::load(&state, &user_id, params).await?;
+        let rows = stream::once(async { Ok(first) })
+            .chain(chunks)
+            .map_ok(move |chunk| stream::iter(view.prepare(chunk).into_iter().map(Ok)))
+            .try_flatten();
+        return Ok(stream_rows_response(format, &filename, rows));
     }
+    info!("No comments found in database, fetching from YouTube API");
 
     // If not in database, fetch from YouTube API
-    match state.youtube_service.fetch_comments(&user_id, &video_id).await {
-        Ok(comments) => {
-            info!("Fetched {} comments from YouTube API", comments.len());
-            Ok(Json(comments))
-        }
-        Err(e) => {
-            error!("Error fetching comments from YouTube API: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    let comments = state.youtube_service.fetch_comments(&user_id, &video_id).await?;
+    info!("Fetched {} comments from YouTube API", comments.len());
+    
+    // Only inline replies were fetched; complete the longer threads in the background
+    let youtube_service = state.youtube_service.clone();
+    let backfill_user_id = user_id.clone();
+    let backfill_video_id = video_id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = youtube_service.backfill_replies(&backfill_user_id, &backfill_video_id).await {
+            error!("Error backfilling replies for video {}: {}", backfill_video_id, e);
         }
+    });
+    
+    let format = params.format;
+    let comments = InboxView::load(&state, &user_id, params).await?.prepare(comments);
+    Ok(respond_rows(format, &filename, comments))
+}
+
+/// How comments are prepared for the inbox.
+///
+/// Comments filter rules hid are dropped unless asked for, and the user's notes
+/// and tags on each commenter are shown in the comment metadata. Sensitive
+/// text is masked if the runtime settings ask for it.
+struct InboxView {
+    profiles: HashMap<String, CommenterProfile>,
+    mask: bool,
+    params: CommentListParams,
+}
+
+impl InboxView {
+    async fn load(state: &AppState, user_id: &str, params: CommentListParams) -> anyhow::Result<Self> {
+        let profiles = state.db.get_commenter_profiles(user_id).await?
+            .into_iter()
+            .map(|profile| (profile.channel_id.clone(), profile))
+            .collect();
+        // Comments stored before masking was turned on are masked on the way out
+        let mask = state.settings.current().mask_sensitive_text;
+        
+        Ok(Self { profiles, mask, params })
+    }
+    
+    /// Prepare one batch of comments, e.g. a chunk streamed from the database
+    fn prepare(&self, comments: Vec<Comment>) -> Vec<Comment> {
+        let params = &self.params;
+        comments
+            .into_iter()
+            .filter(|c| params.include_hidden || !c.is_hidden())
+            .filter(|c| params.triage.is_none_or(|triage| c.triage == triage))
+            .filter(|c| match &params.tag {
+                Some(tag) => self.profiles.get(&c.author_channel_id).is_some_and(|p| p.has_tag(tag)),
+                None => true,
+            })
+            .map(|mut c| {
+                if let Some(profile) = self.profiles.get(&c.author_channel_id) {
+                    if !profile.tags.is_empty() {
+                        c.metadata.insert(COMMENTER_TAGS_KEY.to_string(), profile.tags.join(","));
+                    }
+                    if let Some(notes) = &profile.notes {
+                        c.metadata.insert(COMMENTER_NOTES_KEY.to_string(), notes.clone());
+                    }
+                }
+                if self.mask {
+                    masking::mask_comment(&mut c);
+                }
+                c
+            })
+            .collect()
     }
 }
 
-/// Get videos for the authenticated user
+/// Set the triage state of a comment
+#[derive(Debug, Deserialize)]
+pub struct TriageRequest {
+    /// The new state
+    pub state: TriageState,
+}
+
+pub async fn update_comment_triage(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<TriageRequest>,
+) -> AppResult<Json<Comment>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let mut comment = owned_comment(&state, &user_id, &comment_id).await?;
+    
+    state.db.set_comment_triage(&comment_id, request.state).await?;
+    comment.triage = request.state;
+    state.events.publish(&user_id, UserEvent::CommentChanged {
+        video_id: comment.video_id.clone(),
+        comment_id: comment_id.clone(),
+    });
+    Ok(Json(comment))
+}
+
+/// Get every reply in a comment thread, fetching them on demand if needed
+pub async fn get_thread_replies(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<crate::models::Reply>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    owned_comment(&state, &user_id, &comment_id).await?;
+    state.youtube_service.get_thread_replies(&user_id, &comment_id).await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Comment {}", comment_id)))
+}
+
+/// Get a comment thread as an exchange between the commenter and the user, with any follow-up awaiting an answer
+pub async fn get_conversation(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Conversation>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let comment = owned_comment(&state, &user_id, &comment_id).await?;
+    let replies = state.youtube_service.get_thread_replies(&user_id, &comment_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("Comment {}", comment_id)))?;
+    let own = OwnReplies::load(&state.db, &user_id, &comment.video_id).await?;
+    
+    Ok(Json(conversations::conversation(&comment, &replies, &own)))
+}
+
+/// How long the stored videos are served before the first page refreshes them from YouTube
+const VIDEO_LIST_MAX_AGE_MINS: i64 = 5;
+
+/// Query parameters of the video list
+#[derive(Debug, Deserialize)]
+pub struct VideosParams {
+    /// `next_cursor` of the previous page; the first page if absent
+    pub cursor: Option<String>,
+    
+    /// Most videos on the page
+    #[serde(default = "default_videos_limit")]
+    pub limit: usize,
+}
+
+fn default_videos_limit() -> usize {
+    50
+}
+
+/// Most videos a page may hold
+const MAX_VIDEOS_LIMIT: usize = 200;
+
+/// A page of the user's videos, newest first
+#[derive(Debug, Serialize)]
+pub struct VideoPage {
+    pub videos: Vec<Video>,
+    
+    /// Cursor of the next page, absent on the last one
+    pub next_cursor: Option<String>,
+}
+
+/// Get a page of videos for the authenticated user.
+///
+/// Pages come from the stored videos; the first page refreshes them from
+/// YouTube when they are stale, and serves them anyway if that fails.
 pub async fn get_videos(
     State(state): State<AppState>,
     headers: HeaderMap,
-) -> Result<Json<Vec<crate::services::youtube::YouTubeVideo>>, StatusCode> {
+    Query(params): Query<VideosParams>,
+) -> AppResult<Json<VideoPage>> {
     // Get user ID from session
-    let user_id = get_user_id_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
     
-    match state.youtube_service.get_channel_videos(&user_id).await {
-        Ok(videos) => {
-            info!("Fetched {} videos for user", videos.len());
-            Ok(Json(videos))
+    if params.limit == 0 || params.limit > MAX_VIDEOS_LIMIT {
+        return Err(AppError::Validation(format!("limit must be between 1 and {}", MAX_VIDEOS_LIMIT)));
+    }
+    let cursor = match &params.cursor {
+        Some(token) => Some(VideoCursor::decode(token).ok_or_else(|| AppError::Validation("Invalid cursor".to_string()))?),
+        None => None,
+    };
+    
+    if cursor.is_none() {
+        let synced_at = state.db.get_videos_synced_at(&user_id).await?;
+        let stale = synced_at.is_none_or(|at| chrono::Utc::now() - at > chrono::Duration::minutes(VIDEO_LIST_MAX_AGE_MINS));
+        if stale {
+            match state.youtube_service.get_channel_videos(&user_id).await {
+                Ok(videos) => info!("Fetched {} videos for user", videos.len()),
+                Err(e) if synced_at.is_some() => error!("Error refreshing videos, serving stored ones: {}", e),
+                Err(e) => return Err(e.into()),
+            }
         }
-        Err(e) => {
-            error!("Error fetching videos: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+    
+    // One more than the page holds tells whether there is a next page
+    let mut videos = state.db.get_user_videos_page(&user_id, cursor.as_ref(), params.limit + 1).await?;
+    let next_cursor = if videos.len() > params.limit {
+        videos.truncate(params.limit);
+        videos.last().map(|video| VideoCursor::after(video).encode())
+    } else {
+        None
+    };
+    
+    Ok(Json(VideoPage { videos, next_cursor }))
+}
+
+/// Get the monitor settings for a video
+pub async fn get_video_monitor(
+    Path(video_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<MonitorSettings>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+    
+    match state.db.get_video(&video_id).await? {
+        Some(video) if video.user_id == user_id => Ok(Json(video.monitor)),
+        _ => Err(AppError::NotFound(format!("Video {}", video_id))),
+    }
+}
+
+/// Update the monitor settings for a video
+#[derive(Debug, Deserialize)]
+pub struct UpdateMonitorSettingsRequest {
+    /// Whether the video is monitored for new comments
+    pub enabled: Option<bool>,
+    
+    /// How frequently to check the video for new comments (in seconds)
+    pub interval_secs: Option<u64>,
+    
+    /// Whether new comments on this video may be replied to automatically
+    pub auto_reply: Option<bool>,
+}
+
+pub async fn update_video_monitor(
+    Path(video_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<UpdateMonitorSettingsRequest>,
+) -> AppResult<Json<MonitorSettings>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+    
+    let video = match state.db.get_video(&video_id).await? {
+        Some(video) if video.user_id == user_id => video,
+        _ => return Err(AppError::NotFound(format!("Video {}", video_id))),
+    };
+    
+    let mut monitor = video.monitor;
+    if let Some(enabled) = request.enabled {
+        monitor.enabled = enabled;
+    }
+    if let Some(interval_secs) = request.interval_secs {
+        if interval_secs < MIN_MONITOR_INTERVAL_SECS {
+            return Err(AppError::Validation(format!(
+                "interval_secs must be at least {}",
+                MIN_MONITOR_INTERVAL_SECS
+            )));
         }
+        monitor.interval_secs = interval_secs;
+    }
+    if let Some(auto_reply) = request.auto_reply {
+        monitor.auto_reply = auto_reply;
+    }
+    // Stop following the age-based defaults once the user has changed anything
+    monitor.customized = true;
+    
+    state.db.update_video_monitor(&video_id, &monitor).await?;
+    Ok(Json(monitor))
+}
+
+/// Get the reply persona and tone for a video
+pub async fn get_video_reply_defaults(
+    Path(video_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<ReplyDefaults>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+    
+    match state.db.get_video(&video_id).await? {
+        Some(video) if video.user_id == user_id => Ok(Json(video.reply_defaults)),
+        _ => Err(AppError::NotFound(format!("Video {}", video_id))),
     }
 }
 
+/// Replace the reply persona and tone for a video, e.g. those its collection gave it
+pub async fn update_video_reply_defaults(
+    Path(video_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(reply_defaults): AxumJson<ReplyDefaults>,
+) -> AppResult<Json<ReplyDefaults>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+    
+    match state.db.get_video(&video_id).await? {
+        Some(video) if video.user_id == user_id => {}
+        _ => return Err(AppError::NotFound(format!("Video {}", video_id))),
+    }
+    
+    state.db.update_video_reply_defaults(&video_id, &reply_defaults).await?;
+    Ok(Json(reply_defaults))
+}
+
 /// Generate an AI reply to a comment
 #[derive(Debug, Deserialize)]
 pub struct GenerateReplyRequest {
     /// The comment ID to reply to
     pub comment_id: String,
     
-    /// The tone to use for the reply
-    #[serde(default = "default_tone")]
-    pub tone: String,
+    /// ID of the tone preset to use for the reply; defaults to the video's, then the user's preferred one
+    #[serde(default)]
+    pub tone: Option<String>,
+    
+    /// The persona to write as; defaults to the video's
+    #[serde(default)]
+    pub persona: Option<String>,
+    
+    /// Saved reply for the AI to personalize instead of writing freely
+    #[serde(default)]
+    pub template_id: Option<String>,
     
     /// Additional instructions for the AI
     pub additional_instructions: Option<String>,
+    
+    /// Model and parameters to generate with instead of the defaults, within the model's limits
+    #[serde(default)]
+    pub parameters: Option<ParameterOverrides>,
 }
 
-fn default_tone() -> String {
-    "friendly".to_string()
+/// The tone replies use when neither the request nor the video sets one
+pub(crate) fn default_tone(preferences: Option<&UserPreferences>) -> String {
+    preferences.map_or_else(|| ReplyTone::default().0, |preferences| preferences.reply_tone.0.clone())
 }
 
 #[derive(Debug, Serialize)]
@@ -111,79 +477,187 @@ pub struct GenerateReplyResponse {
     
     /// The model used to generate the reply
     pub model: String,
+    
+    /// Whether the AI was unavailable, so the saved reply is used as it is
+    pub degraded: bool,
+    
+    /// Whether the thread looks hostile: the reply de-escalates, and posting it needs `confirm_hostile`
+    pub hostile: bool,
 }
 
 pub async fn generate_reply(
     State(state): State<AppState>,
     headers: HeaderMap,
     AxumJson(request): AxumJson<GenerateReplyRequest>,
-) -> Result<Json<GenerateReplyResponse>, StatusCode> {
+) -> AppResult<Json<GenerateReplyResponse>> {
     // Get user ID from session
-    let user_id = get_user_id_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
-    
-    // Get the comment from the database
-    let comment = match state.db.get_comment(&request.comment_id).await {
-        Ok(Some(comment)) => comment,
-        Ok(None) => {
-            error!("Comment not found: {}", request.comment_id);
-            return Err(StatusCode::NOT_FOUND);
-        }
-        Err(e) => {
-            error!("Error fetching comment: {}", e);
-            return Err(StatusCode::INTERNAL_SERVER_ERROR);
-        }
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    generate_reply_for_comment(&state, &user_id, &request).await?
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("Comment {}", request.comment_id)))
+}
+
+/// Generate a reply for a stored comment and record the interaction.
+///
+/// Returns `None` if the comment is not on one of the user's videos.
+pub(crate) async fn generate_reply_for_comment(
+    state: &AppState,
+    user_id: &str,
+    request: &GenerateReplyRequest,
+) -> anyhow::Result<Option<GenerateReplyResponse>> {
+    generate_personalized_reply(state, user_id, request, None).await
+}
+
+/// Generate a reply for a stored comment that personalizes `base`, or the
+/// request's saved reply if `base` is `None`.
+///
+/// Returns `None` if the comment is not on one of the user's videos.
+pub(crate) async fn generate_personalized_reply(
+    state: &AppState,
+    user_id: &str,
+    request: &GenerateReplyRequest,
+    base: Option<String>,
+) -> anyhow::Result<Option<GenerateReplyResponse>> {
+    if !state.settings.current().ai_enabled {
+        return Err(AppError::Unavailable("AI reply generation is switched off".to_string()).into());
+    }
+    
+    // Get the comment from the database; other users' comments are treated as missing
+    let comment = match owned_comment(state, user_id, &request.comment_id).await {
+        Ok(comment) => comment,
+        Err(AppError::NotFound(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
     };
     
-    // Get previous interactions with this commenter
-    let previous_interactions = Vec::new(); // TODO: Implement this
+    // Get previous interactions with this commenter; the reply can do without them
+    let previous_interactions = state.commenters.previous_interactions(user_id, &comment).await
+        .unwrap_or_else(|e| {
+            error!("Error loading history with commenter {}: {}", comment.author_channel_id, e);
+            Vec::new()
+        });
+    
+    // A tone the user can't write in is refused rather than quietly replaced
+    if let Some(tone) = &request.tone {
+        state.tones.get(user_id, tone).await?;
+    }
+    
+    let preferences = state.db.get_user(user_id).await?.map(|user| user.preferences);
+    let reply_language = preferences.as_ref().and_then(|p| p.preferred_reply_language.clone());
+    let preferred_tone = default_tone(preferences.as_ref());
+    // The user's organization can forbid more and fix the persona
+    let org_policy = organizations::policy(&state.db, user_id).await?;
+    let reply_policy = org_policy.restrict(preferences.map(|p| p.reply_policy).unwrap_or_default());
+    
+    // The video's persona and tone apply unless the request sets its own; Shorts get short replies
+    let (reply_defaults, format) = state.db.get_video(&comment.video_id).await?
+        .map(|video| (video.reply_defaults, video.format))
+        .unwrap_or_default();
+    let is_short = format == VideoFormat::Short;
+    let default_persona = is_short.then(|| prompts::SHORTS_PERSONA.to_string());
+    
+    let template = match (base, &request.template_id) {
+        (Some(base), _) => Some(base),
+        (None, Some(template_id)) => Some(state.saved_replies.render(user_id, template_id, &comment.comment_id).await?),
+        (None, None) => None,
+    };
+    
+    // A follow-up is answered with the whole exchange, the user's replies marked as theirs
+    let own_replies = OwnReplies::load(&state.db, user_id, &comment.video_id).await?;
+    let follow_up = comment.follow_up.as_ref()
+        .and_then(|follow_up| comment.replies.iter().find(|reply| reply.reply_id == follow_up.reply_id))
+        .map(|reply| format!("{}: \"{}\"", reply.author, reply.original_text()));
+    
+    // Heated threads get a short, calm reply
+    let heated = sentiment::is_heated(&comment);
     
     // Create AI request
     let ai_request = ReplyGenerationRequest {
-        comment_text: comment.text.clone(),
+        comment_text: comment.original_text().to_string(),
         comment_author: comment.author.clone(),
         video_title: "YouTube Video".to_string(), // TODO: Get actual video title
         video_id: comment.video_id.clone(),
         previous_interactions,
-        tone: request.tone,
-        additional_instructions: request.additional_instructions,
-        max_length: None,
-        parameter_overrides: None,
+        thread_replies: ai::thread_context(&comment.replies, &own_replies),
+        follow_up,
+        timestamps: comment.timestamps.clone(),
+        tone: request.tone.clone().or(reply_defaults.tone).unwrap_or(preferred_tone),
+        persona: org_policy.persona.clone().or(request.persona.clone()).or(reply_defaults.persona).or(default_persona),
+        reply_language,
+        template,
+        de_escalate: heated,
+        additional_instructions: reply_policy.instructions(org_policy.instructions(request.additional_instructions.as_deref()).as_deref()),
+        max_length: [is_short.then_some(ai::SHORTS_MAX_TOKENS), heated.then_some(ai::DE_ESCALATION_MAX_TOKENS)].into_iter().flatten().min(),
+        parameter_overrides: request.parameters.clone(),
     };
     
-    // Generate reply
-    match state.ai_service.generate_reply(&ai_request).await {
-        Ok(response) => {
-            // Record the interaction
-            let interaction = InteractionRecord {
-                id: uuid::Uuid::new_v4().to_string(),
-                user_id: user_id.clone(),
-                video_id: comment.video_id.clone(),
-                comment_id: comment.comment_id.clone(),
-                reply_id: None,
-                interaction_type: InteractionType::ReplyGenerated,
-                timestamp: chrono::Utc::now(),
-                data: {
-                    let mut data = HashMap::new();
-                    data.insert("reply_text".to_string(), response.reply_text.clone());
-                    data.insert("model".to_string(), response.model.clone());
-                    data
-                },
-            };
-            
-            if let Err(e) = state.db.record_interaction(&interaction).await {
-                error!("Error recording interaction: {}", e);
+    // Generate reply, signed off as the user asked; while the AI is unavailable a saved reply is used as it is
+    let mut response = match (state.ai_service.generate_reply(user_id, &ai_request).await, &ai_request.template) {
+        (Ok(response), _) => response,
+        (Err(e), Some(template)) if ai::is_unavailable(&e) => {
+            warn!("AI unavailable, using the saved reply for comment {} as it is: {}", comment.comment_id, e);
+            ai::template_reply(template)
+        }
+        (Err(e), _) => return Err(e),
+    };
+    let degraded = response.model == ai::TEMPLATE_MODEL;
+    response.reply_text = reply_policy.sign(&response.reply_text);
+    
+    // Record the interaction
+    let interaction = InteractionRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        video_id: comment.video_id.clone(),
+        comment_id: comment.comment_id.clone(),
+        reply_id: None,
+        interaction_type: InteractionType::ReplyGenerated,
+        timestamp: chrono::Utc::now(),
+        data: {
+            let mut data = HashMap::new();
+            data.insert("reply_text".to_string(), response.reply_text.clone());
+            data.insert("model".to_string(), response.model.clone());
+            if let Some(template_id) = &request.template_id {
+                data.insert("template_id".to_string(), template_id.clone());
             }
-            
-            Ok(Json(GenerateReplyResponse {
-                reply_text: response.reply_text,
-                model: response.model,
-            }))
+            data
+        },
+    };
+    
+    if let Err(e) = state.db.record_interaction(&interaction).await {
+        error!("Error recording interaction: {}", e);
+    }
+    
+    // Queue the reply for review
+    let mut draft = ReplyDraft::new(user_id, &comment.video_id, &comment.comment_id, &response.reply_text, Some(response.model.clone()));
+    draft.persona = ai_request.persona.clone();
+    match state.db.save_draft(&draft).await {
+        Ok(()) => {
+            let data = HashMap::from([("draft_id".to_string(), draft.draft_id.clone())]);
+            history::record(&state.db, user_id, &comment.video_id, &comment.comment_id, InteractionType::DraftCreated, data).await;
         }
-        Err(e) => {
-            error!("Error generating reply: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        Err(e) => error!("Error saving draft: {}", e),
+    }
+    
+    // A drafted reply means the comment is being worked on
+    if matches!(comment.triage, TriageState::New | TriageState::NeedsReply) {
+        if let Err(e) = state.db.set_comment_triage(&comment.comment_id, TriageState::InProgress).await {
+            error!("Error updating triage state: {}", e);
         }
     }
+    
+    state.events.publish(user_id, UserEvent::ReplyGenerated {
+        video_id: comment.video_id.clone(),
+        comment_id: comment.comment_id.clone(),
+        reply_text: response.reply_text.clone(),
+        model: response.model.clone(),
+    });
+    
+    Ok(Some(GenerateReplyResponse {
+        reply_text: response.reply_text,
+        model: response.model,
+        degraded,
+        hostile: heated,
+    }))
 }
 
 /// Post a reply to a comment
@@ -193,6 +667,7 @@ pub struct PostReplyRequest {
     pub comment_id: String,
     
     /// The reply text
+    #[serde(default)]
     pub reply_text: String,
     
     /// Whether this reply was generated by AI
@@ -201,80 +676,858 @@ pub struct PostReplyRequest {
     
     /// The AI model used to generate this reply, if applicable
     pub ai_model: Option<String>,
+    
+    /// Saved reply to expand for the comment instead of `reply_text`
+    #[serde(default)]
+    pub template_id: Option<String>,
+    
+    /// Only log and record the reply instead of posting it
+    #[serde(default)]
+    pub dry_run: bool,
+    
+    /// Local time, in the user's time zone, to send the reply at instead of now (e.g. `2024-06-01T09:00:00`)
+    #[serde(default)]
+    pub send_at: Option<chrono::NaiveDateTime>,
+    
+    /// Post even though the thread looks hostile; such replies are refused until confirmed
+    #[serde(default)]
+    pub confirm_hostile: bool,
 }
 
+/// Post a reply, or queue it in the outbox until its scheduled time or for its undo window if one is configured
 pub async fn post_reply(
     State(state): State<AppState>,
     headers: HeaderMap,
     AxumJson(request): AxumJson<PostReplyRequest>,
-) -> Result<Json<Reply>, StatusCode> {
+) -> AppResult<Response> {
     // Get user ID from session
-    let user_id = get_user_id_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    // Dry runs have nothing to undo or wait for
+    let queue = state.outbox.is_delayed() || request.send_at.is_some();
+    if queue && !dry_run::is_dry_run(&state.settings.current(), request.dry_run) {
+        if request.template_id.is_none() && request.reply_text.trim().is_empty() {
+            return Err(AppError::Validation("reply_text or template_id is required".to_string()));
+        }
+        owned_comment(&state, &user_id, &request.comment_id).await?;
+        // Checked again when sent, but a reply that can't be sent shouldn't wait in the queue
+        check_reply_policy(&state, &user_id, &request.reply_text).await?;
+        if !request.confirm_hostile {
+            check_not_heated(&state, &request.comment_id).await?;
+        }
+        
+        let send_at = match request.send_at {
+            Some(local) => {
+                let user = state.db.get_user(&user_id).await?
+                    .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+                let send_at = time_zone::to_utc(local, user.preferences.time_zone());
+                if send_at <= chrono::Utc::now() {
+                    return Err(AppError::Validation("send_at must be in the future".to_string()));
+                }
+                Some(send_at)
+            }
+            None => None,
+        };
+        
+        let mut queued = QueuedReply::new(&user_id, &request.comment_id, &request.reply_text, chrono::Utc::now());
+        queued.ai_generated = request.ai_generated;
+        queued.ai_model = request.ai_model;
+        queued.template_id = request.template_id;
+        let queued = match send_at {
+            Some(send_at) => state.outbox.schedule(queued, send_at).await?,
+            None => state.outbox.enqueue(queued).await?,
+        };
+        return Ok((StatusCode::ACCEPTED, Json(queued)).into_response());
+    }
+    
+    let reply = post_reply_to_comment(&state, &user_id, request).await?;
+    Ok(Json(Reply {
+        dry_run: dry_run::is_simulated(&reply),
+        reply_id: reply.reply_id,
+        parent_id: reply.parent_id,
+        author: reply.author,
+        text: reply.text,
+        ai_generated: reply.ai_generated,
+        ai_model: reply.ai_model,
+    }).into_response())
+}
+
+/// Post a reply to YouTube and record the interaction.
+///
+/// In a dry run the reply is only recorded, and the returned reply is simulated.
+/// Comments on other users' videos are reported as missing.
+pub(crate) async fn post_reply_to_comment(
+    state: &AppState,
+    user_id: &str,
+    request: PostReplyRequest,
+) -> anyhow::Result<crate::models::Reply> {
+    owned_comment(state, user_id, &request.comment_id).await?;
+    
+    let reply_text = match &request.template_id {
+        Some(template_id) => state.saved_replies.render(user_id, template_id, &request.comment_id).await?,
+        None if request.reply_text.trim().is_empty() => {
+            return Err(AppError::Validation("reply_text or template_id is required".to_string()).into());
+        }
+        None => request.reply_text.clone(),
+    };
+    
+    // AI-generated replies say so if the user asked for disclosure
+    let reply_text = if request.ai_generated {
+        disclose_ai(state, user_id, &reply_text).await?
+    } else {
+        reply_text
+    };
+    check_reply_policy(state, user_id, &reply_text).await?;
+    if !request.confirm_hostile {
+        check_not_heated(state, &request.comment_id).await?;
+    }
+    
+    if dry_run::is_dry_run(&state.settings.current(), request.dry_run) {
+        let mut data = HashMap::from([("reply_text".to_string(), reply_text.clone())]);
+        if let Some(template_id) = &request.template_id {
+            data.insert("template_id".to_string(), template_id.clone());
+        }
+        dry_run::record(&state.db, user_id, &request.comment_id, dry_run::WOULD_REPLY, data).await?;
+        
+        let mut reply = dry_run::simulated_reply(&request.comment_id, &reply_text);
+        reply.ai_generated = request.ai_generated;
+        reply.ai_model = request.ai_model;
+        state.events.publish(user_id, UserEvent::ReplyPosted {
+            comment_id: request.comment_id.clone(),
+            reply_id: reply.reply_id.clone(),
+            dry_run: true,
+        });
+        return Ok(reply);
+    }
+    
+    // Looked up before posting, which marks the comment's drafts as posted
+    let draft = match state.db.get_latest_pending_draft(&request.comment_id).await {
+        Ok(draft) => draft.filter(|draft| draft.user_id == user_id),
+        Err(e) => {
+            error!("Error loading draft: {}", e);
+            None
+        }
+    };
     
     // Post the reply to YouTube
-    match state.youtube_service.post_reply(&user_id, &request.comment_id, &request.reply_text).await {
-        Ok(mut reply) => {
-            // Update AI-generated flag if needed
-            if request.ai_generated {
-                reply.ai_generated = true;
-                reply.ai_model = request.ai_model;
+    let mut reply = state.youtube_service.post_reply(user_id, &request.comment_id, &reply_text).await?;
+    
+    if let Some(template_id) = &request.template_id {
+        state.saved_replies.record_use(template_id).await;
+    }
+    
+    // Update AI-generated flag if needed
+    if request.ai_generated {
+        reply.ai_generated = true;
+        reply.ai_model = request.ai_model;
+    }
+    
+    // Record the interaction
+    let interaction = InteractionRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        video_id: "".to_string(), // We don't have the video ID here
+        comment_id: request.comment_id.clone(),
+        reply_id: Some(reply.reply_id.clone()),
+        interaction_type: InteractionType::ReplyPosted,
+        timestamp: chrono::Utc::now(),
+        data: {
+            let mut data = HashMap::new();
+            data.insert("reply_text".to_string(), reply.text.clone());
+            if let Some(model) = &reply.ai_model {
+                data.insert("ai_model".to_string(), model.clone());
             }
-            
-            // Record the interaction
-            let interaction = InteractionRecord {
-                id: uuid::Uuid::new_v4().to_string(),
-                user_id: user_id.clone(),
-                video_id: "".to_string(), // We don't have the video ID here
-                comment_id: request.comment_id.clone(),
-                reply_id: Some(reply.reply_id.clone()),
-                interaction_type: InteractionType::ReplyPosted,
-                timestamp: chrono::Utc::now(),
-                data: {
-                    let mut data = HashMap::new();
-                    data.insert("reply_text".to_string(), reply.text.clone());
-                    if let Some(model) = &reply.ai_model {
-                        data.insert("ai_model".to_string(), model.clone());
+            if let Some(template_id) = &request.template_id {
+                data.insert("template_id".to_string(), template_id.clone());
+            }
+            data
+        },
+    };
+    
+    if let Err(e) = state.db.record_interaction(&interaction).await {
+        error!("Error recording interaction: {}", e);
+    }
+    
+    // The comment is answered, so its drafts no longer need review
+    if let Err(e) = state.db.mark_comment_drafts_posted(&request.comment_id).await {
+        error!("Error updating drafts: {}", e);
+    }
+    
+    // Posting a draft approves it, as generated or edited; edits keep both texts and the
+    // diff between them, by model and persona, to show how much each needs changing
+    if let Some(draft) = draft {
+        let mut data = HashMap::from([("draft_id".to_string(), draft.draft_id.clone())]);
+        let interaction_type = if draft.text.trim() == request.reply_text.trim() {
+            InteractionType::DraftApproved
+        } else {
+            data.extend(ReplyDiff::between(&draft.text, &request.reply_text).to_data());
+            if let Some(model) = &draft.model {
+                data.insert("ai_model".to_string(), model.clone());
+            }
+            if let Some(persona) = &draft.persona {
+                data.insert("persona".to_string(), persona.clone());
+            }
+            data.insert("draft_text".to_string(), draft.text);
+            data.insert("reply_text".to_string(), reply.text.clone());
+            InteractionType::ReplyEdited
+        };
+        history::record(&state.db, user_id, &draft.video_id, &request.comment_id, interaction_type, data).await;
+    }
+    
+    state.events.publish(user_id, UserEvent::ReplyPosted {
+        comment_id: request.comment_id.clone(),
+        reply_id: reply.reply_id.clone(),
+        dry_run: false,
+    });
+    Ok(reply)
+}
+
+/// Correct a posted reply
+#[derive(Debug, Deserialize)]
+pub struct UpdateReplyRequest {
+    /// The new reply text
+    #[serde(default)]
+    pub reply_text: String,
+    
+    /// Only log and record the edit instead of sending it
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Replace the text of one of the user's posted replies on YouTube, keeping the previous text in the history
+pub async fn update_posted_reply(
+    Path(reply_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<UpdateReplyRequest>,
+) -> AppResult<Json<Reply>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let reply_text = request.reply_text.trim();
+    if reply_text.is_empty() {
+        return Err(AppError::Validation("reply_text is required".to_string()));
+    }
+    if reply_text.chars().count() > MAX_REPLY_LENGTH {
+        return Err(AppError::Validation(format!("reply_text must be at most {} characters", MAX_REPLY_LENGTH)));
+    }
+    check_reply_policy(&state, &user_id, reply_text).await?;
+    
+    // Reply IDs are the parent comment's ID, a dot and the reply's own ID
+    let not_found = || AppError::NotFound(format!("Reply {}", reply_id));
+    let (parent_id, _) = reply_id.split_once('.').ok_or_else(not_found)?;
+    let mut comment = owned_comment(&state, &user_id, parent_id).await
+        .map_err(|_| not_found())?;
+    
+    // A reply posted here may not be synced yet, so the history counts as well as the thread
+    let own = OwnReplies::load(&state.db, &user_id, &comment.video_id).await?;
+    let previous_text = match comment.replies.iter().find(|reply| reply.reply_id == reply_id) {
+        Some(reply) if own.contains(reply) => reply.original_text().to_string(),
+        Some(_) => return Err(not_found()),
+        None => state.db.get_comment_interactions(parent_id).await?
+            .into_iter()
+            .rfind(|interaction| {
+                interaction.user_id == user_id
+                    && interaction.reply_id.as_deref() == Some(reply_id.as_str())
+                    && matches!(interaction.interaction_type, InteractionType::ReplyPosted | InteractionType::ReplyUpdated)
+            })
+            .and_then(|interaction| interaction.data.get("reply_text").cloned())
+            .ok_or_else(not_found)?,
+    };
+    
+    if dry_run::is_dry_run(&state.settings.current(), request.dry_run) {
+        let data = HashMap::from([
+            ("reply_id".to_string(), reply_id.clone()),
+            ("reply_text".to_string(), reply_text.to_string()),
+        ]);
+        dry_run::record(&state.db, &user_id, parent_id, dry_run::WOULD_EDIT, data).await?;
+        return Ok(Json(Reply {
+            reply_id: reply_id.clone(),
+            parent_id: parent_id.to_string(),
+            author: String::new(),
+            text: reply_text.to_string(),
+            ai_generated: false,
+            ai_model: None,
+            dry_run: true,
+        }));
+    }
+    
+    let mut updated = state.youtube_service.update_reply(&user_id, &reply_id, parent_id, reply_text).await?;
+    info!("Updated reply {}", reply_id);
+    
+    if let Some(stored) = comment.replies.iter_mut().find(|reply| reply.reply_id == reply_id) {
+        updated.ai_generated = stored.ai_generated;
+        updated.ai_model = stored.ai_model.clone();
+        *stored = updated.clone();
+        if let Err(e) = state.db.update_comment_replies(parent_id, &comment.replies).await {
+            error!("Error storing the updated reply: {}", e);
+        }
+    }
+    
+    let mut data = ReplyDiff::between(&previous_text, reply_text).to_data();
+    data.insert("previous_text".to_string(), previous_text);
+    data.insert("reply_text".to_string(), updated.original_text().to_string());
+    let interaction = InteractionRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: user_id.clone(),
+        video_id: comment.video_id.clone(),
+        comment_id: parent_id.to_string(),
+        reply_id: Some(reply_id.clone()),
+        interaction_type: InteractionType::ReplyUpdated,
+        timestamp: chrono::Utc::now(),
+        data,
+    };
+    if let Err(e) = state.db.record_interaction(&interaction).await {
+        error!("Error recording interaction: {}", e);
+    }
+    
+    state.events.publish(&user_id, UserEvent::CommentChanged {
+        video_id: comment.video_id.clone(),
+        comment_id: parent_id.to_string(),
+    });
+    Ok(Json(Reply {
+        reply_id: updated.reply_id,
+        parent_id: updated.parent_id,
+        author: updated.author,
+        text: updated.text,
+        ai_generated: updated.ai_generated,
+        ai_model: updated.ai_model,
+        dry_run: false,
+    }))
+}
+
+/// Fail if reply text has links or @-mentions the user's reply policy doesn't allow
+pub(crate) async fn check_reply_policy(state: &AppState, user_id: &str, reply_text: &str) -> anyhow::Result<()> {
+    let org_policy = organizations::policy(&state.db, user_id).await?;
+    let policy = org_policy.restrict(state.db.get_user(user_id).await?
+        .map(|user| user.preferences.reply_policy)
+        .unwrap_or_default());
+    
+    if let Some(phrase) = org_policy.blocked_phrase(reply_text) {
+        return Err(AppError::Validation(format!("The reply contains {:?}, which your organization doesn't allow", phrase)).into());
+    }
+    if !policy.allow_links && link_pattern().is_match(reply_text) {
+        return Err(AppError::Validation("The reply contains a link, which your reply policy doesn't allow".to_string()).into());
+    }
+    if !policy.allow_mentions && mention_pattern().is_match(reply_text) {
+        return Err(AppError::Validation("The reply @-mentions someone, which your reply policy doesn't allow".to_string()).into());
+    }
+    
+    Ok(())
+}
+
+/// Fail if the comment's thread looks hostile, so a reply to it is only posted once the user confirms it
+async fn check_not_heated(state: &AppState, comment_id: &str) -> anyhow::Result<()> {
+    if state.db.get_comment(comment_id).await?.is_some_and(|comment| sentiment::is_heated(&comment)) {
+        return Err(AppError::Conflict(
+            "The comment looks hostile; read the reply again and post it with confirm_hostile to send it".to_string(),
+        ).into());
+    }
+    
+    Ok(())
+}
+
+/// Add the user's AI disclosure note to reply text
+async fn disclose_ai(state: &AppState, user_id: &str, reply_text: &str) -> anyhow::Result<String> {
+    let disclosure = state.db.get_user(user_id).await?
+        .map(|user| user.preferences.ai_disclosure)
+        .unwrap_or_default();
+    
+    let text = disclosure.apply(reply_text);
+    if text.chars().count() > MAX_REPLY_LENGTH {
+        return Err(AppError::Validation(format!("Reply text with the AI disclosure must be at most {} characters", MAX_REPLY_LENGTH)).into());
+    }
+    Ok(text)
+}
+
+/// List the drafts awaiting review
+pub async fn get_pending_drafts(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<ReplyDraft>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    Ok(Json(state.db.get_pending_drafts(&user_id).await?))
+}
+
+/// Get the dashboard numbers for the home screen
+pub async fn get_dashboard(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Dashboard>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    Ok(Json(state.dashboard_service.get(&user_id).await?))
+}
+
+/// Get the YouTube quota, AI spend, outbox backlog and rate limiter state, to plan big jobs
+pub async fn get_capacity(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Capacity>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let rate_limiter = state.youtube_service.rate_limit().await;
+    Ok(Json(state.dashboard_service.capacity(&user_id, rate_limiter).await?))
+}
+
+/// Language preferences of a user
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LanguagePreferences {
+    /// Language for notifications and digests (`en`, `es`, `fr`, `de` or `pt`)
+    pub language: Option<String>,
+    
+    /// Language AI replies are written in, as a language tag; the comment's own language if unset
+    pub preferred_reply_language: Option<String>,
+}
+
+/// Longest language tag accepted
+const MAX_LANGUAGE_TAG_LENGTH: usize = 35;
+
+/// Set the language of the authenticated user's notifications and AI replies
+pub async fn update_language_preferences(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<LanguagePreferences>,
+) -> AppResult<Json<LanguagePreferences>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    if let Some(language) = &request.language {
+        if Locale::parse(language).is_none() {
+            return Err(AppError::Validation(format!("Unsupported language: {}", language)));
+        }
+    }
+    if let Some(language) = &request.preferred_reply_language {
+        if language.trim().is_empty() || language.len() > MAX_LANGUAGE_TAG_LENGTH {
+            return Err(AppError::Validation("preferred_reply_language must be a language tag".to_string()));
+        }
+    }
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.language = request.language.clone();
+    user.preferences.preferred_reply_language = request.preferred_reply_language.clone();
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(request))
+}
+
+/// Time zone preference of a user
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeZonePreference {
+    /// IANA time zone, e.g. `Europe/Berlin`; UTC if unset
+    pub time_zone: Option<String>,
+}
+
+/// Set the time zone the authenticated user's days, digests and scheduled replies follow
+pub async fn update_time_zone(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<TimeZonePreference>,
+) -> AppResult<Json<TimeZonePreference>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let time_zone = match &request.time_zone {
+        Some(name) => match time_zone::parse(name) {
+            Some(tz) => Some(tz.name().to_string()),
+            None => return Err(AppError::Validation(format!("Unknown time zone: {}", name))),
+        },
+        None => None,
+    };
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.time_zone = time_zone.clone();
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(TimeZonePreference { time_zone }))
+}
+
+/// Where the authenticated user's Slack notifications are posted
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SlackPreference {
+    /// Slack incoming webhook URL, which must start with `https://hooks.slack.com/`
+    pub slack_webhook_url: Option<String>,
+
+    /// Channel ID the bot posts to if no webhook is set
+    pub slack_channel: Option<String>,
+}
+
+/// Set where the authenticated user's Slack notifications are posted
+pub async fn update_slack_preferences(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<SlackPreference>,
+) -> AppResult<Json<SlackPreference>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let slack_webhook_url = request.slack_webhook_url.map(|url| url.trim().to_string()).filter(|url| !url.is_empty());
+    let slack_channel = request.slack_channel.map(|channel| channel.trim().to_string()).filter(|channel| !channel.is_empty());
+    
+    // The server posts to this URL, so it may only ever point at Slack
+    if slack_webhook_url.as_deref().is_some_and(|url| !is_slack_webhook_url(url)) {
+        return Err(AppError::Validation(format!("Slack webhook URL must start with {}", SLACK_WEBHOOK_PREFIX)));
+    }
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.notifications.slack_webhook_url = slack_webhook_url.clone();
+    user.preferences.notifications.slack_channel = slack_channel.clone();
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(SlackPreference { slack_webhook_url, slack_channel }))
+}
+
+/// Longest AI disclosure note accepted
+const MAX_DISCLOSURE_LENGTH: usize = 100;
+
+/// Set whether and how the authenticated user's AI-generated replies disclose it
+pub async fn update_ai_disclosure(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<AiDisclosure>,
+) -> AppResult<Json<AiDisclosure>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    if request.enabled && request.text.trim().is_empty() {
+        return Err(AppError::Validation("text must not be empty".to_string()));
+    }
+    if request.text.chars().count() > MAX_DISCLOSURE_LENGTH {
+        return Err(AppError::Validation(format!("text must be at most {} characters", MAX_DISCLOSURE_LENGTH)));
+    }
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.ai_disclosure = request.clone();
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(request))
+}
+
+/// Longest reply sign-off accepted
+const MAX_SIGNATURE_LENGTH: usize = 100;
+
+/// Set what the authenticated user's replies may contain and how generated ones sign off
+pub async fn update_reply_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(mut request): AxumJson<ReplyPolicy>,
+) -> AppResult<Json<ReplyPolicy>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    request.signature = request.signature.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    if let Some(signature) = &request.signature {
+        if signature.chars().count() > MAX_SIGNATURE_LENGTH {
+            return Err(AppError::Validation(format!("signature must be at most {} characters", MAX_SIGNATURE_LENGTH)));
+        }
+        // The sign-off is added to every generated reply, so it must pass the policy itself
+        if (!request.allow_links && link_pattern().is_match(signature))
+            || (!request.allow_mentions && mention_pattern().is_match(signature))
+        {
+            return Err(AppError::Validation("signature must not contain what the policy forbids".to_string()));
+        }
+    }
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.reply_policy = request.clone();
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(request))
+}
+
+/// Set how many days the authenticated user's interactions and comments are kept; unset uses the server's
+pub async fn update_retention_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<RetentionPolicy>,
+) -> AppResult<Json<RetentionPolicy>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    if request.interactions_days == Some(0) || request.comments_days == Some(0) {
+        return Err(AppError::Validation("Records must be kept for at least 1 day".to_string()));
+    }
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.retention = request;
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(request))
+}
+
+/// Set the tone preset the authenticated user's generated replies use by default
+#[derive(Debug, Deserialize)]
+pub struct ReplyToneRequest {
+    /// ID of a built-in tone or one of the user's own
+    pub tone_id: String,
+}
+
+pub async fn update_reply_tone(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<ReplyToneRequest>,
+) -> AppResult<Json<TonePreset>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let tone = state.tones.get(&user_id, &request.tone_id).await?;
+    
+    let mut user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+    user.preferences.reply_tone = ReplyTone(tone.tone_id.clone());
+    user.updated_at = chrono::Utc::now();
+    state.db.save_user(&user).await?;
+    
+    Ok(Json(tone))
+}
+
+/// Start a backfill job fetching all comments for a set of videos
+#[derive(Debug, Deserialize)]
+pub struct BackfillRequest {
+    /// The videos to backfill; defaults to all stored videos of the user
+    pub video_ids: Option<Vec<String>>,
+}
+
+pub async fn start_backfill(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<BackfillRequest>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let video_ids = match request.video_ids {
+        Some(video_ids) => {
+            let mut parsed = Vec::with_capacity(video_ids.len());
+            for video_id in &video_ids {
+                let video_id = parse_video_id(&state.db, video_id).await?;
+                owned_video(&state, &user_id, &video_id).await?;
+                parsed.push(video_id);
+            }
+            parsed
+        }
+        None => state.db.get_user_videos(&user_id).await?
+            .into_iter()
+            .map(|v| v.video_id)
+            .collect(),
+    };
+    
+    let job_state = state.clone();
+    let job_user_id = user_id.clone();
+    let job = state.job_service.start(&user_id, JobKind::Backfill, video_ids.len(), move |handle: JobHandle| async move {
+        for video_id in video_ids {
+            let result = match job_state.youtube_service.sync_comments(&job_user_id, &video_id).await {
+                Ok(count) => {
+                    if let Err(e) = job_state.db.mark_video_checked(&video_id, chrono::Utc::now()).await {
+                        error!("Error updating video {}: {}", video_id, e);
                     }
-                    data
-                },
+                    JobItemResult::success(&video_id, json!({ "comments": count }))
+                }
+                Err(e) => JobItemResult::failure(&video_id, e),
+            };
+            handle.record(result).await;
+        }
+        Ok(())
+    }).await;
+    
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
+
+/// Start a job checking that the user's replies from the last week are still on YouTube.
+///
+/// Each item is a reply; its result says whether YouTube still shows it.
+pub async fn start_reply_check(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let replies = state.reply_checker.pending(&user_id).await?;
+    
+    let checker = state.reply_checker.clone();
+    let job_user_id = user_id.clone();
+    let job = state.job_service.start(&user_id, JobKind::VerifyReplies, replies.len(), move |handle: JobHandle| async move {
+        for check in checker.check(&job_user_id, &replies).await? {
+            handle.record(JobItemResult::success(&check.reply_id, json!({
+                "comment_id": check.comment_id,
+                "visible": check.visible,
+            }))).await;
+        }
+        Ok(())
+    }).await;
+    
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
+
+/// Start a job generating AI replies for many comments
+#[derive(Debug, Deserialize)]
+pub struct BatchGenerateRequest {
+    /// The comment IDs to reply to
+    pub comment_ids: Vec<String>,
+    
+    /// ID of the tone preset to use for the replies; defaults to each video's, then the user's preferred one
+    #[serde(default)]
+    pub tone: Option<String>,
+    
+    /// The persona to write as; defaults to each video's
+    #[serde(default)]
+    pub persona: Option<String>,
+    
+    /// Saved reply for the AI to personalize for each comment
+    #[serde(default)]
+    pub template_id: Option<String>,
+    
+    /// Additional instructions for the AI
+    pub additional_instructions: Option<String>,
+}
+
+pub async fn batch_generate_replies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<BatchGenerateRequest>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    if let Some(tone) = &request.tone {
+        state.tones.get(&user_id, tone).await?;
+    }
+    for comment_id in &request.comment_ids {
+        owned_comment(&state, &user_id, comment_id).await?;
+    }
+    
+    let job_state = state.clone();
+    let job_user_id = user_id.clone();
+    let total = request.comment_ids.len();
+    let job = state.job_service.start(&user_id, JobKind::BatchGenerate, total, move |handle: JobHandle| async move {
+        for comment_id in request.comment_ids {
+            let generate_request = GenerateReplyRequest {
+                comment_id: comment_id.clone(),
+                tone: request.tone.clone(),
+                persona: request.persona.clone(),
+                template_id: request.template_id.clone(),
+                additional_instructions: request.additional_instructions.clone(),
+                parameters: None,
             };
             
-            if let Err(e) = state.db.record_interaction(&interaction).await {
-                error!("Error recording interaction: {}", e);
-            }
-            
-            Ok(Json(reply))
+            let result = match generate_reply_for_comment(&job_state, &job_user_id, &generate_request).await {
+                Ok(Some(response)) => JobItemResult::success(&comment_id, json!(response)),
+                Ok(None) => JobItemResult::failure(&comment_id, "Comment not found"),
+                Err(e) => JobItemResult::failure(&comment_id, e),
+            };
+            handle.record(result).await;
         }
-        Err(e) => {
-            error!("Error posting reply: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        Ok(())
+    }).await;
+    
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
+
+/// Start a job posting many replies to YouTube
+#[derive(Debug, Deserialize)]
+pub struct BulkPostRequest {
+    /// The replies to post
+    pub replies: Vec<PostReplyRequest>,
+}
+
+pub async fn bulk_post_replies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    AxumJson(request): AxumJson<BulkPostRequest>,
+) -> AppResult<(StatusCode, Json<Job>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    for reply_request in &request.replies {
+        owned_comment(&state, &user_id, &reply_request.comment_id).await?;
+    }
+    
+    let job_state = state.clone();
+    let job_user_id = user_id.clone();
+    let total = request.replies.len();
+    let job = state.job_service.start(&user_id, JobKind::BulkPost, total, move |handle: JobHandle| async move {
+        for reply_request in request.replies {
+            let comment_id = reply_request.comment_id.clone();
+            let result = match post_reply_to_comment(&job_state, &job_user_id, reply_request).await {
+                Ok(reply) => JobItemResult::success(&comment_id, json!({ "reply_id": reply.reply_id })),
+                Err(e) => {
+                    job_state.notification_service.auto_reply_failed(&job_user_id, &comment_id, &e.to_string()).await;
+                    JobItemResult::failure(&comment_id, e)
+                }
+            };
+            handle.record(result).await;
+        }
+        Ok(())
+    }).await;
+    
+    Ok((StatusCode::ACCEPTED, Json(job?)))
+}
+
+/// Job status response with computed progress
+#[derive(Debug, Serialize)]
+pub struct JobResponse {
+    #[serde(flatten)]
+    pub job: Job,
+    
+    /// Fraction of items processed so far, between 0.0 and 1.0
+    pub progress: f64,
+}
+
+/// Get the status, progress and results of a job
+pub async fn get_job(
+    Path(job_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<JobResponse>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    match state.job_service.get_job(&job_id).await? {
+        Some(job) if job.user_id == user_id => {
+            let progress = job.progress();
+            Ok(Json(JobResponse { job, progress }))
         }
+        _ => Err(AppError::NotFound(format!("Job {}", job_id))),
     }
 }
 
-/// Get interaction history
+/// Get interaction history, optionally only the comma-separated types in `type`
 pub async fn get_history(
     State(state): State<AppState>,
     headers: HeaderMap,
     Query(params): Query<HashMap<String, String>>,
-) -> Result<Json<Vec<InteractionRecord>>, StatusCode> {
+) -> AppResult<Json<Vec<InteractionRecord>>> {
     // Get user ID from session
-    let user_id = get_user_id_from_headers(&headers).ok_or(StatusCode::UNAUTHORIZED)?;
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
     
     // Get limit parameter
     let limit = params.get("limit")
         .and_then(|l| l.parse::<usize>().ok())
         .unwrap_or(100);
     
-    match state.db.get_user_interactions(&user_id, limit).await {
-        Ok(interactions) => {
-            Ok(Json(interactions))
-        }
-        Err(e) => {
-            error!("Error fetching interaction history: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    let types = match params.get("type") {
+        Some(list) => history::parse_types(list)?,
+        None => Vec::new(),
+    };
+    
+    Ok(Json(state.db.get_user_interactions(&user_id, &types, limit).await?))
 }
 
 /// OAuth callback handler
@@ -288,46 +1541,36 @@ pub async fn oauth_callback(
     State(state): State<AppState>,
     Query(params): Query<OAuthCallbackParams>,
     req: Request<axum::body::Body>,
-) -> impl IntoResponse {
+) -> AppResult<Redirect> {
     // Exchange the code for tokens
-    match state.auth_service.exchange_code(&params.code).await {
-        Ok(token) => {
-            // Create or update user
-            match state.auth_service.create_or_update_user(&token).await {
-                Ok(user) => {
-                    // Create a session
-                    let ip = req.remote_addr()
-                        .map(|addr| addr.to_string())
-                        .unwrap_or_else(|| "unknown".to_string());
-                    
-                    let user_agent = req.headers()
-                        .get("user-agent")
-                        .and_then(|v| v.to_str().ok())
-                        .unwrap_or("unknown")
-                        .to_string();
-                    
-                    match state.auth_service.create_session(&user.id, &ip, &user_agent).await {
-                        Ok(session) => {
-                            // Redirect to frontend with session ID
-                            Redirect::to(&format!("/auth/success?session_id={}", session.id)).into_response()
-                        }
-                        Err(e) => {
-                            error!("Error creating session: {}", e);
-                            StatusCode::INTERNAL_SERVER_ERROR.into_response()
-                        }
-                    }
-                }
-                Err(e) => {
-                    error!("Error creating user: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
-                }
-            }
-        }
-        Err(e) => {
-            error!("Error exchanging code: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
-        }
-    }
+    let token = state.auth_service.exchange_code(&params.code).await?;
+    
+    // Reconnect the user the consent was asked for, or create or update the one who signed in
+    let reconnecting = params.state
+        .strip_prefix(RECONNECT_STATE_PREFIX)
+        .and_then(|rest| rest.split_once('.'))
+        .map(|(user_id, _)| user_id);
+    let user = match reconnecting {
+        Some(user_id) => state.auth_service.reconnect_user(user_id, &token).await?,
+        None => state.auth_service.create_or_update_user(&token).await?,
+    };
+    
+    // Create a session
+    let ip = req.extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    let user_agent = req.headers()
+        .get("user-agent")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("unknown")
+        .to_string();
+    
+    let session = state.auth_service.create_session(&user.id, &ip, &user_agent).await?;
+    
+    // Redirect to frontend with session ID
+    Ok(Redirect::to(&format!("/auth/success?session_id={}", session.id)))
 }
 
 /// Get authorization URL
@@ -338,8 +1581,74 @@ pub async fn get_auth_url(
     Json(json!({ "url": url }))
 }
 
+/// Get the consent screen URL the authenticated user grants access again through
+pub async fn get_reconnect_url(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Value>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+    let url = state.auth_service.get_reconnect_url(&user);
+    Ok(Json(json!({ "url": url })))
+}
+
+/// Get the authenticated user, with whether Google still accepts their grant and,
+/// if not, where to reconnect
+pub async fn get_me(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Value>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    
+    let user = state.db.get_user(&user_id).await?
+        .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+    let connected = !user.is_disconnected();
+    let reconnect_url = (!connected).then(|| state.auth_service.get_reconnect_url(&user));
+    Ok(Json(json!({
+        "user": user,
+        "connected": connected,
+        "reconnect_url": reconnect_url,
+    })))
+}
+
+/// The video ID in what a user gave for a video: a YouTube video ID or URL, or the ID of a stored video.
+///
+/// Videos of other comment sources don't have YouTube's IDs, so any other token is
+/// only taken as an ID if a video with it is stored; anything else is refused with
+/// guidance, rather than looked up as a video that can't exist.
+pub(crate) async fn parse_video_id(db: &Database, input: &str) -> AppResult<String> {
+    let input = input.trim();
+    if let Some(video_id) = utils::extract_video_id(input) {
+        return Ok(video_id);
+    }
+    
+    let is_token = !input.is_empty() && input.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if is_token && db.get_video(input).await?.is_some() {
+        return Ok(input.to_string());
+    }
+    
+    Err(AppError::Unprocessable(format!(
+        "\"{}\" is neither a video ID nor a link to a YouTube video; use the 11-character ID (e.g. dQw4w9WgXcQ) \
+         or a link like https://www.youtube.com/watch?v=dQw4w9WgXcQ, https://youtu.be/dQw4w9WgXcQ \
+         or https://www.youtube.com/shorts/dQw4w9WgXcQ",
+        input
+    )))
+}
+
+/// A stored video of the user; other users' videos are reported as missing
+pub(crate) async fn owned_video(state: &AppState, user_id: &str, video_id: &str) -> AppResult<Video> {
+    match state.db.get_video(video_id).await? {
+        Some(video) if video.user_id == user_id => Ok(video),
+        _ => Err(AppError::NotFound(format!("Video {}", video_id))),
+    }
+}
+
 /// Helper function to get user ID from headers
-fn get_user_id_from_headers(headers: &HeaderMap) -> Option<String> {
+pub(crate) fn get_user_id_from_headers(headers: &HeaderMap) -> Option<String> {
     headers.get("x-session-id")
         .and_then(|v| v.to_str().ok())
         .map(|s| s.to_string())
@@ -365,4 +1674,7 @@ pub struct Reply {
     
     /// The AI model used to generate this reply, if applicable
     pub ai_model: Option<String>,
+    
+    /// Whether the reply was only recorded, not posted
+    pub dry_run: bool,
 }
@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::handlers::{get_user_id_from_headers, parse_video_id, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::{Comment, InteractionType};
+use crate::models::event::UserEvent;
+use crate::services::{highlights, history};
+
+/// Record that a comment was pinned or highlighted in YouTube Studio, or no longer is
+#[derive(Debug, Deserialize)]
+pub struct HighlightRequest {
+    /// Whether the comment is pinned; unchanged if unset
+    pub pinned: Option<bool>,
+
+    /// Whether the comment is highlighted; unchanged if unset
+    pub highlighted: Option<bool>,
+}
+
+/// The recorded comment, with where to make the change on YouTube
+#[derive(Debug, Serialize)]
+pub struct HighlightResponse {
+    pub comment: Comment,
+
+    /// What still has to be done in YouTube Studio, since the API can't
+    pub reminder: Option<String>,
+
+    /// The video's comments in YouTube Studio
+    pub studio_url: String,
+}
+
+/// Record a comment as pinned or highlighted.
+///
+/// Pinning a comment unpins the video's other comments, as on YouTube.
+pub async fn update_highlight(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<HighlightRequest>,
+) -> AppResult<Json<HighlightResponse>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut comment = owned_comment(&state, &user_id, &comment_id).await?;
+    let now = Utc::now();
+    let mut actions = Vec::new();
+
+    if let Some(pinned) = request.pinned {
+        if pinned != comment.highlight.pinned_at.is_some() {
+            if pinned {
+                state.db.clear_video_pins(&comment.video_id).await?;
+            }
+            comment.highlight.pinned_at = pinned.then_some(now);
+            actions.push(if pinned { highlights::PINNED } else { highlights::UNPINNED });
+        }
+    }
+    if let Some(highlighted) = request.highlighted {
+        if highlighted != comment.highlight.highlighted_at.is_some() {
+            comment.highlight.highlighted_at = highlighted.then_some(now);
+            actions.push(if highlighted { highlights::HIGHLIGHTED } else { highlights::UNHIGHLIGHTED });
+        }
+    }
+
+    if !actions.is_empty() {
+        state.db.set_comment_highlight(&comment_id, &comment.highlight).await?;
+    }
+    for action in &actions {
+        let data = HashMap::from([("action".to_string(), action.to_string())]);
+        history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommentFeatured, data).await;
+    }
+    if !actions.is_empty() {
+        state.events.publish(&user_id, UserEvent::CommentChanged {
+            video_id: comment.video_id.clone(),
+            comment_id: comment_id.clone(),
+        });
+    }
+
+    let reminder = (!actions.is_empty()).then(|| {
+        format!("Recorded as {}. The YouTube API can't do this, so make the same change in YouTube Studio.", actions.join(" and "))
+    });
+    let studio_url = highlights::studio_url(&comment.video_id);
+    Ok(Json(HighlightResponse { comment, reminder, studio_url }))
+}
+
+/// Record that a comment was hearted or liked on YouTube, or no longer is
+#[derive(Debug, Deserialize)]
+pub struct ReactionRequest {
+    /// Whether the comment has the creator's heart; unchanged if unset
+    pub hearted: Option<bool>,
+
+    /// Whether the comment is liked from the channel; unchanged if unset
+    pub liked: Option<bool>,
+}
+
+/// Record a comment as hearted or liked.
+///
+/// The YouTube Data API has no way to heart or like a comment, so the response
+/// says what to do in YouTube Studio, as for highlights.
+pub async fn update_reaction(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ReactionRequest>,
+) -> AppResult<Json<HighlightResponse>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut comment = owned_comment(&state, &user_id, &comment_id).await?;
+    let now = Utc::now();
+    let mut actions = Vec::new();
+
+    if let Some(hearted) = request.hearted {
+        if hearted != comment.reaction.hearted_at.is_some() {
+            comment.reaction.hearted_at = hearted.then_some(now);
+            actions.push(if hearted { highlights::HEARTED } else { highlights::UNHEARTED });
+        }
+    }
+    if let Some(liked) = request.liked {
+        if liked != comment.reaction.liked_at.is_some() {
+            comment.reaction.liked_at = liked.then_some(now);
+            actions.push(if liked { highlights::LIKED } else { highlights::UNLIKED });
+        }
+    }
+
+    if !actions.is_empty() {
+        state.db.set_comment_reaction(&comment_id, &comment.reaction).await?;
+        for action in &actions {
+            let data = HashMap::from([("action".to_string(), action.to_string())]);
+            history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommentReacted, data).await;
+        }
+        state.events.publish(&user_id, UserEvent::CommentChanged {
+            video_id: comment.video_id.clone(),
+            comment_id: comment_id.clone(),
+        });
+    }
+
+    let reminder = (!actions.is_empty()).then(|| {
+        format!("Recorded as {}. The YouTube API can't do this, so make the same change in YouTube Studio.", actions.join(" and "))
+    });
+    let studio_url = highlights::studio_url(&comment.video_id);
+    Ok(Json(HighlightResponse { comment, reminder, studio_url }))
+}
+
+/// Query parameters of the pin candidate list
+#[derive(Debug, Deserialize)]
+pub struct CandidateParams {
+    /// Most comments listed
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    5
+}
+
+/// Suggest comments on a video worth pinning: well-liked, positive and not pinned yet
+pub async fn get_pin_candidates(
+    Path(video_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<CandidateParams>,
+) -> AppResult<Json<Vec<Comment>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+    let video_id = parse_video_id(&state.db, &video_id).await?;
+
+    match state.db.get_video(&video_id).await? {
+        Some(video) if video.user_id == user_id => {}
+        _ => return Err(AppError::NotFound(format!("Video {}", video_id))),
+    }
+
+    let comments = state.db.get_comments(&video_id).await?.unwrap_or_default();
+    Ok(Json(highlights::pin_candidates(comments, params.limit)))
+}
+
+/// A comment on one of the user's videos; others are reported as missing
+pub(crate) async fn owned_comment(state: &AppState, user_id: &str, comment_id: &str) -> AppResult<Comment> {
+    let comment = state
+        .db
+        .get_comment(comment_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Comment {}", comment_id)))?;
+
+    match state.db.get_video(&comment.video_id).await? {
+        Some(video) if video.user_id == user_id => Ok(comment),
+        _ => Err(AppError::NotFound(format!("Comment {}", comment_id))),
+    }
+}
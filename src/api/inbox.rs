@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use super::handlers::{get_user_id_from_headers, parse_video_id, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::TriageState;
+use crate::models::inbox::InboxEntry;
+use crate::services::masking;
+
+/// Most entries listed at once
+const MAX_INBOX_LIMIT: usize = 500;
+
+/// Query parameters of the inbox
+#[derive(Debug, Deserialize)]
+pub struct InboxParams {
+    /// Only comments on this video
+    pub video_id: Option<String>,
+
+    /// Only comments in this triage state
+    pub triage: Option<TriageState>,
+
+    /// Only comments by commenters with this tag
+    pub tag: Option<String>,
+
+    /// Include comments hidden or marked as spam by filter rules
+    #[serde(default)]
+    pub include_hidden: bool,
+
+    /// Only comments with a follow-up awaiting the user's answer
+    #[serde(default)]
+    pub follow_ups: bool,
+
+    /// Most entries listed
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+/// List the comments on the user's videos, newest first, from the inbox projection.
+///
+/// The projection follows the user's events, so a change may take a moment to show.
+pub async fn get_inbox(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<InboxParams>,
+) -> AppResult<Json<Vec<InboxEntry>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if params.limit == 0 || params.limit > MAX_INBOX_LIMIT {
+        return Err(AppError::Validation(format!("limit must be between 1 and {}", MAX_INBOX_LIMIT)));
+    }
+
+    let video_id = match params.video_id.as_deref() {
+        Some(video_id) => Some(parse_video_id(&state.db, video_id).await?),
+        None => None,
+    };
+    let tag = params.tag.map(|tag| tag.trim().to_lowercase()).filter(|tag| !tag.is_empty());
+    let mut entries = state.db.get_inbox_entries(
+        &user_id,
+        video_id.as_deref(),
+        params.triage,
+        tag.as_deref(),
+        params.include_hidden,
+        params.follow_ups,
+        params.limit,
+    ).await?;
+
+    // Entries projected before masking was turned on are masked on the way out
+    if state.settings.current().mask_sensitive_text {
+        for entry in &mut entries {
+            masking::mask_comment(&mut entry.comment);
+        }
+    }
+
+    Ok(Json(entries))
+}
+
+/// How much of the inbox projection was rebuilt
+#[derive(Debug, Serialize)]
+pub struct RebuildResponse {
+    /// Videos whose entries were rebuilt
+    pub videos: usize,
+}
+
+/// Rebuild the user's inbox projection from the stored comments, e.g. after it fell behind
+pub async fn rebuild_inbox(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<RebuildResponse>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let videos = state.inbox.project_user(&user_id).await?;
+    Ok(Json(RebuildResponse { videos }))
+}
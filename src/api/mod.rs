@@ -1,3 +1,178 @@
-mod handlers;
+pub mod handlers;
+pub mod admin;
+pub mod alerts;
+pub mod analytics;
+pub mod bootstrap;
+pub mod clusters;
+pub mod collections;
+pub mod commenters;
+pub mod duplicates;
+pub mod events;
+pub mod export;
+pub mod highlights;
+pub mod inbox;
+pub mod moderation;
+pub mod monitor;
+pub mod mutes;
+pub mod organizations;
+pub mod outbox;
+pub mod quarantine;
+pub mod rules;
+pub mod saved_replies;
+pub mod search;
+pub mod share;
+#[cfg(feature = "telegram")]
+pub mod telegram;
+pub mod tones;
 
 pub use handlers::*;
+
+use axum::{
+    middleware,
+    routing::{delete, get, patch, post, put},
+    Router,
+};
+
+use crate::i18n;
+use crate::utils::http_log;
+
+/// All API routes, without middleware, so tests can drive them with fake services
+pub fn router(state: AppState) -> Router {
+    let router = Router::new()
+        .route("/", get(|| async { "YouTube Commenter API" }))
+        .route("/api/health", get(handlers::health_check))
+        .route("/metrics", get(handlers::metrics))
+        .route("/api/auth/url", get(handlers::get_auth_url))
+        .route("/api/auth/callback", get(handlers::oauth_callback))
+        .route("/api/auth/reconnect", get(handlers::get_reconnect_url))
+        .route("/api/me", get(handlers::get_me))
+        .route("/api/bootstrap", get(bootstrap::get_bootstrap))
+        .route(
+            "/api/monitor",
+            get(monitor::get_monitor).post(monitor::start_monitor).delete(monitor::stop_monitor),
+        )
+        .route("/api/videos", get(handlers::get_videos))
+        .route(
+            "/api/videos/:video_id/monitor",
+            get(handlers::get_video_monitor).put(handlers::update_video_monitor),
+        )
+        .route(
+            "/api/videos/:video_id/reply-defaults",
+            get(handlers::get_video_reply_defaults).put(handlers::update_video_reply_defaults),
+        )
+        .route("/api/videos/:video_id/clusters", get(clusters::get_clusters))
+        .route("/api/videos/:video_id/pin-candidates", get(highlights::get_pin_candidates))
+        .route("/api/collections", get(collections::get_collections).post(collections::create_collection))
+        .route(
+            "/api/collections/:collection_id",
+            get(collections::get_collection)
+                .put(collections::update_collection)
+                .delete(collections::delete_collection),
+        )
+        .route(
+            "/api/collections/:collection_id/videos/:video_id",
+            put(collections::add_collection_video).delete(collections::remove_collection_video),
+        )
+        .route("/api/comments/search", get(search::search_comments))
+        .route("/api/comments/:video_id", get(handlers::get_comments))
+        // The router needs the segment named as above; it is the comment ID here
+        .route("/api/comments/:video_id/share", post(share::create_share_link))
+        .route("/api/comments/:video_id/moderate", post(moderation::moderate_comment))
+        .route("/api/comments/:video_id/react", put(highlights::update_reaction))
+        .route("/share/:token", get(share::view_shared_thread))
+        .route("/api/threads/:comment_id/replies", get(handlers::get_thread_replies))
+        .route("/api/threads/:comment_id/conversation", get(handlers::get_conversation))
+        .route("/api/threads/:comment_id/triage", put(handlers::update_comment_triage))
+        .route("/api/threads/:comment_id/highlight", put(highlights::update_highlight))
+        .route("/api/reply/generate", post(handlers::generate_reply))
+        .route("/api/reply/post", post(handlers::post_reply))
+        .route("/api/reply/generate/batch", post(handlers::batch_generate_replies))
+        .route("/api/reply/post/batch", post(handlers::bulk_post_replies))
+        .route("/api/reply/verify", post(handlers::start_reply_check))
+        .route("/api/reply/queue", get(outbox::get_reply_queue))
+        .route("/api/reply/queue/:queue_id", delete(outbox::cancel_queued_reply))
+        .route("/api/reply/:reply_id", patch(handlers::update_posted_reply))
+        .route("/api/backfill", post(handlers::start_backfill))
+        .route("/api/jobs/:job_id", get(handlers::get_job))
+        .route("/api/history", get(handlers::get_history))
+        .route("/api/events", get(events::stream_events))
+        .route("/api/inbox", get(inbox::get_inbox))
+        .route("/api/inbox/rebuild", post(inbox::rebuild_inbox))
+        .route("/api/drafts", get(handlers::get_pending_drafts))
+        .route("/api/dashboard", get(handlers::get_dashboard))
+        .route("/api/status/capacity", get(handlers::get_capacity))
+        .route("/api/commenters/:channel_id", get(commenters::get_commenter).patch(commenters::update_commenter))
+        .route("/api/commenters/:channel_id/history", get(commenters::get_commenter_history))
+        .route("/api/clusters/reply", post(clusters::reply_to_cluster))
+        .route("/api/duplicates", get(duplicates::get_duplicate_groups))
+        .route("/api/duplicates/:group_id", get(duplicates::get_duplicate_group))
+        .route("/api/duplicates/:group_id/spam", post(duplicates::mark_group_spam))
+        .route("/api/duplicates/:group_id/reply", post(duplicates::reply_to_group))
+        .route("/api/quarantine", get(quarantine::get_quarantine))
+        .route("/api/quarantine/classifier", get(quarantine::get_spam_classifier))
+        .route("/api/quarantine/:comment_id/restore", post(quarantine::restore_comment))
+        .route("/api/quarantine/:comment_id/confirm", post(quarantine::confirm_spam))
+        .route("/api/rules", get(rules::get_rules).post(rules::create_rule))
+        .route("/api/rules/:rule_id", put(rules::update_rule).delete(rules::delete_rule))
+        .route("/api/alerts", get(alerts::get_alert_rules).post(alerts::create_alert_rule))
+        .route("/api/alerts/:rule_id", put(alerts::update_alert_rule).delete(alerts::delete_alert_rule))
+        .route(
+            "/api/saved-replies",
+            get(saved_replies::get_saved_replies).post(saved_replies::create_saved_reply),
+        )
+        .route(
+            "/api/saved-replies/:template_id",
+            put(saved_replies::update_saved_reply).delete(saved_replies::delete_saved_reply),
+        )
+        .route("/api/mutes", get(mutes::get_mutes).post(mutes::create_mute))
+        .route("/api/mutes/:mute_id", delete(mutes::delete_mute))
+        .route("/api/tones", get(tones::get_tones).post(tones::create_tone))
+        .route("/api/tones/:tone_id", put(tones::update_tone).delete(tones::delete_tone))
+        .route(
+            "/api/org",
+            get(organizations::get_organization).post(organizations::create_organization),
+        )
+        .route("/api/org/join", post(organizations::join_organization))
+        .route("/api/org/members/:user_id", delete(organizations::remove_org_member))
+        .route("/api/org/policy", put(organizations::update_org_policy))
+        .route("/api/org/inbox", get(organizations::get_org_inbox))
+        .route("/api/preferences/language", put(handlers::update_language_preferences))
+        .route("/api/preferences/ai-disclosure", put(handlers::update_ai_disclosure))
+        .route("/api/preferences/reply-policy", put(handlers::update_reply_policy))
+        .route("/api/preferences/retention", put(handlers::update_retention_policy))
+        .route("/api/preferences/tone", put(handlers::update_reply_tone))
+        .route("/api/preferences/time-zone", put(handlers::update_time_zone))
+        .route("/api/preferences/slack", put(handlers::update_slack_preferences))
+        .route("/api/analytics/overview", get(analytics::get_overview))
+        .route("/api/analytics/sentiment", get(analytics::get_sentiment))
+        .route("/api/analytics/volume", get(analytics::get_volume))
+        .route("/api/analytics/keywords", get(analytics::get_keywords))
+        .route("/api/analytics/keywords/refresh", post(analytics::refresh_keywords))
+        .route("/api/analytics/ai", get(analytics::get_ai_usage))
+        .route("/api/analytics/compare", get(analytics::compare_videos))
+        .route("/api/export/analytics", get(analytics::export_analytics))
+        .route("/api/admin/prompts", get(admin::get_prompts))
+        .route("/api/admin/prompts/reload", post(admin::reload_prompts))
+        .route(
+            "/api/admin/prompts/:kind/:name",
+            put(admin::put_prompt).delete(admin::delete_prompt),
+        )
+        .route("/api/admin/http-log", get(admin::get_http_log).put(admin::put_http_log))
+        .route("/api/admin/settings", get(admin::get_settings).patch(admin::patch_settings))
+        .route("/api/admin/model-routing", get(admin::get_model_routing))
+        .route(
+            "/api/admin/model-routing/:task",
+            put(admin::put_model_route).delete(admin::delete_model_route),
+        )
+        .route("/api/admin/retention/prune", post(admin::prune_records));
+
+    #[cfg(feature = "telegram")]
+    let router = router.route("/api/telegram/webhook", post(telegram::telegram_webhook));
+
+    // Added here rather than in main so the matched route is known
+    let http_log = state.http_log.clone();
+    router
+        .layer(middleware::from_fn_with_state(http_log, http_log::log_bodies))
+        .layer(middleware::from_fn(i18n::scope_locale))
+        .with_state(state)
+}
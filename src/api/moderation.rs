@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::info;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use super::highlights::owned_comment;
+use super::quarantine::ModerationParams;
+use crate::error::{AppError, AppResult};
+use crate::models::{Comment, InteractionType, TriageState};
+use crate::models::event::UserEvent;
+use crate::models::moderation::ModerationAction;
+use crate::services::{dry_run, history};
+
+/// Moderate a comment on YouTube
+#[derive(Debug, Deserialize)]
+pub struct ModerateRequest {
+    pub action: ModerationAction,
+
+    /// Also ban the comment's author; only with `reject`
+    #[serde(default)]
+    pub ban_author: bool,
+}
+
+/// Hold, publish, reject or report one of the user's comments on YouTube.
+///
+/// Comments hidden this way are ignored in the inbox. A dry run only records the action.
+pub async fn moderate_comment(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ModerationParams>,
+    Json(request): Json<ModerateRequest>,
+) -> AppResult<Json<Comment>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if request.ban_author && request.action != ModerationAction::Reject {
+        return Err(AppError::Validation("ban_author only applies to the reject action".to_string()));
+    }
+
+    let mut comment = owned_comment(&state, &user_id, &comment_id).await?;
+    if dry_run::is_dry_run(&state.settings.current(), params.dry_run) {
+        let mut data = HashMap::from([("moderation".to_string(), request.action.as_str().to_string())]);
+        if request.ban_author {
+            data.insert("ban_author".to_string(), comment.author_channel_id.clone());
+        }
+        dry_run::record(&state.db, &user_id, &comment_id, dry_run::WOULD_MODERATE, data).await?;
+        return Ok(Json(comment));
+    }
+
+    match request.action.status() {
+        Some(status) => {
+            state.youtube_service.set_moderation_status(&user_id, &comment_id, status, request.ban_author).await?
+        }
+        None => state.youtube_service.mark_as_spam(&user_id, &comment_id).await?,
+    }
+    info!("Moderated comment {}: {}", comment_id, request.action.as_str());
+
+    if request.action != ModerationAction::Publish && comment.triage != TriageState::Ignored {
+        state.db.set_comment_triage(&comment_id, TriageState::Ignored).await?;
+        comment.triage = TriageState::Ignored;
+    }
+
+    let moderated = match request.action {
+        ModerationAction::Hold => Some(history::MODERATION_HELD),
+        ModerationAction::Publish => Some(history::MODERATION_PUBLISHED),
+        ModerationAction::Reject => Some(history::MODERATION_REJECTED),
+        ModerationAction::Spam => None,
+    };
+    match moderated {
+        Some(action) => {
+            let data = HashMap::from([("action".to_string(), action.to_string())]);
+            history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommentModerated, data).await;
+        }
+        None => {
+            history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommentMarkedSpam, HashMap::new()).await;
+        }
+    }
+    if request.ban_author {
+        let data = HashMap::from([
+            ("channel_id".to_string(), comment.author_channel_id.clone()),
+            ("author".to_string(), comment.author.clone()),
+        ]);
+        history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommenterBanned, data).await;
+    }
+
+    state.events.publish(&user_id, UserEvent::CommentChanged {
+        video_id: comment.video_id.clone(),
+        comment_id: comment_id.clone(),
+    });
+    Ok(Json(comment))
+}
@@ -0,0 +1,39 @@
+use axum::{extract::State, http::HeaderMap, Json};
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::services::monitor::MonitorState;
+
+/// The state of the authenticated user's comment monitor
+pub async fn get_monitor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<MonitorState>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.monitor.status(&user_id)))
+}
+
+/// Start the user's comment monitor, or restart it so a changed polling interval applies
+pub async fn start_monitor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<MonitorState>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.monitor.start(&user_id).await?))
+}
+
+/// Stop the user's comment monitor
+pub async fn stop_monitor(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<MonitorState>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    state.monitor.stop(&user_id);
+    Ok(Json(state.monitor.status(&user_id)))
+}
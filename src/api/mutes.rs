@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::mute::{Mute, MuteKind};
+use crate::services::mutes::{self, MAX_MUTES_PER_USER};
+
+/// Query parameters of the mute list
+#[derive(Debug, Deserialize)]
+pub struct MuteParams {
+    /// Only mutes of videos or only of commenters
+    pub kind: Option<MuteKind>,
+}
+
+/// Mute a video or commenter
+#[derive(Debug, Deserialize)]
+pub struct MuteRequest {
+    pub kind: MuteKind,
+
+    /// The video ID, or the commenter's YouTube channel ID
+    pub target_id: String,
+
+    /// Why it is muted, for the user's own reference
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// List the videos and commenters the authenticated user muted, most recent first
+pub async fn get_mutes(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<MuteParams>,
+) -> AppResult<Json<Vec<Mute>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mutes = state.db.get_mutes(&user_id).await?
+        .into_iter()
+        .filter(|mute| params.kind.is_none_or(|kind| mute.kind == kind))
+        .collect();
+    Ok(Json(mutes))
+}
+
+/// Mute one of the user's videos or a commenter
+pub async fn create_mute(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<MuteRequest>,
+) -> AppResult<(StatusCode, Json<Mute>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let target_id = request.target_id.trim();
+    let mut mute = Mute::new(&user_id, request.kind, target_id);
+    mute.reason = request.reason.map(|reason| reason.trim().to_string()).filter(|reason| !reason.is_empty());
+    mutes::validate(&mute)?;
+
+    if state.db.find_mute(&user_id, request.kind, target_id).await?.is_some() {
+        return Err(AppError::Conflict(format!("{} is already muted", target_id)));
+    }
+    if state.db.get_mutes(&user_id).await?.len() >= MAX_MUTES_PER_USER {
+        return Err(AppError::Validation(format!("At most {} mutes are allowed", MAX_MUTES_PER_USER)));
+    }
+
+    mute.label = state.mutes.label(&user_id, request.kind, target_id).await?;
+    state.db.save_mute(&mute).await?;
+    Ok((StatusCode::CREATED, Json(mute)))
+}
+
+/// Unmute a video or commenter; comments that arrived while muted show up again
+pub async fn delete_mute(
+    Path(mute_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    state.mutes.get(&user_id, &mute_id).await?;
+    state.db.delete_mute(&mute_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,184 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::organization::{InboxItem, Organization, OrgPolicy};
+use crate::services::organizations::{self, MAX_ORG_MEMBERS};
+
+/// Create an organization
+#[derive(Debug, Deserialize)]
+pub struct CreateOrganizationRequest {
+    /// Name shown to members
+    pub name: String,
+}
+
+/// Create an organization managed by the authenticated user, who becomes its first member
+pub async fn create_organization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateOrganizationRequest>,
+) -> AppResult<(StatusCode, Json<Organization>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if state.db.get_user_organization(&user_id).await?.is_some() {
+        return Err(AppError::Conflict("You already belong to an organization".to_string()));
+    }
+
+    let org = Organization::new(request.name.trim(), &user_id);
+    organizations::validate(&org)?;
+
+    state.db.save_organization(&org).await?;
+    Ok((StatusCode::CREATED, Json(org)))
+}
+
+/// Get the authenticated user's organization; only the admin sees the invite code
+pub async fn get_organization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Organization>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut org = member_organization(&state, &user_id).await?;
+    if !org.is_admin(&user_id) {
+        org.invite_code.clear();
+    }
+    Ok(Json(org))
+}
+
+/// Join an organization
+#[derive(Debug, Deserialize)]
+pub struct JoinOrganizationRequest {
+    /// The code the organization's admin shared
+    pub invite_code: String,
+}
+
+/// Add the authenticated user's channel to the organization an invite code belongs to
+pub async fn join_organization(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<JoinOrganizationRequest>,
+) -> AppResult<Json<Organization>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if state.db.get_user_organization(&user_id).await?.is_some() {
+        return Err(AppError::Conflict("You already belong to an organization".to_string()));
+    }
+
+    let mut org = state.db.get_organization_by_invite(request.invite_code.trim()).await?
+        .ok_or_else(|| AppError::NotFound("Invite code".to_string()))?;
+    if org.members.len() >= MAX_ORG_MEMBERS {
+        return Err(AppError::Validation(format!("An organization can have at most {} members", MAX_ORG_MEMBERS)));
+    }
+
+    org.members.push(user_id);
+    org.updated_at = Utc::now();
+    state.db.save_organization(&org).await?;
+
+    org.invite_code.clear();
+    Ok(Json(org))
+}
+
+/// Remove a member from the authenticated user's organization.
+///
+/// The admin can remove anyone else, and members can remove themselves; the
+/// admin can't leave the organization they manage.
+pub async fn remove_org_member(
+    Path(member_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut org = member_organization(&state, &user_id).await?;
+    if !org.is_admin(&user_id) && member_id != user_id {
+        return Err(AppError::Forbidden);
+    }
+    if org.is_admin(&member_id) {
+        return Err(AppError::Validation("The admin can't leave the organization".to_string()));
+    }
+    if !org.members.contains(&member_id) {
+        return Err(AppError::NotFound(format!("Member {}", member_id)));
+    }
+
+    org.members.retain(|member| *member != member_id);
+    org.updated_at = Utc::now();
+    state.db.save_organization(&org).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Set the persona and compliance rules every member's replies follow
+pub async fn update_org_policy(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut policy): Json<OrgPolicy>,
+) -> AppResult<Json<OrgPolicy>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut org = admin_organization(&state, &user_id).await?;
+    policy.persona = policy.persona.map(|p| p.trim().to_string()).filter(|p| !p.is_empty());
+    policy.blocked_phrases = policy.blocked_phrases.iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+    policy.blocked_phrases.dedup();
+
+    org.policy = policy.clone();
+    org.updated_at = Utc::now();
+    organizations::validate(&org)?;
+
+    state.db.save_organization(&org).await?;
+    Ok(Json(policy))
+}
+
+/// Query parameters of the organization inbox
+#[derive(Debug, Deserialize)]
+pub struct InboxParams {
+    /// Most comments listed, at most [`organizations::MAX_INBOX_LIMIT`]
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+/// List the unanswered comments on every member's channel, newest first
+pub async fn get_org_inbox(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<InboxParams>,
+) -> AppResult<Json<Vec<InboxItem>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let org = admin_organization(&state, &user_id).await?;
+    let mask = state.settings.current().mask_sensitive_text;
+    let limit = params.limit.clamp(1, organizations::MAX_INBOX_LIMIT);
+    Ok(Json(organizations::inbox(&state.db, &org, limit, mask).await?))
+}
+
+/// The organization the user belongs to
+async fn member_organization(state: &AppState, user_id: &str) -> AppResult<Organization> {
+    state
+        .db
+        .get_user_organization(user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Organization".to_string()))
+}
+
+/// The organization the user manages; members who don't manage it are refused
+async fn admin_organization(state: &AppState, user_id: &str) -> AppResult<Organization> {
+    let org = member_organization(state, user_id).await?;
+    if !org.is_admin(user_id) {
+        return Err(AppError::Forbidden);
+    }
+    Ok(org)
+}
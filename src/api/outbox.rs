@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use std::collections::HashMap;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use super::handlers::{get_user_id_from_headers, post_reply_to_comment, AppState, PostReplyRequest};
+use crate::error::{AppError, AppResult};
+use crate::models::InteractionType;
+use crate::models::outbox::QueuedReply;
+use crate::services::history;
+use crate::services::outbox::{INTERRUPTED_SEND_ERROR, SEND_CHECK_INTERVAL};
+
+/// List the authenticated user's replies still in their undo window
+pub async fn get_reply_queue(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<QueuedReply>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.outbox.pending(&user_id).await?))
+}
+
+/// Cancel a queued reply before it is sent
+pub async fn cancel_queued_reply(
+    Path(queue_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let queued = state.outbox.cancel(&user_id, &queue_id).await?;
+    info!("Cancelled queued reply {}", queue_id);
+
+    let data = HashMap::from([
+        ("queue_id".to_string(), queue_id),
+        ("reply_text".to_string(), queued.reply_text),
+    ]);
+    history::record(&state.db, &user_id, "", &queued.comment_id, InteractionType::ReplyDeleted, data).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Send queued replies to YouTube once their undo window is over, after failing those a previous run left mid-send
+pub fn spawn_sender(state: AppState) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        fail_interrupted(&state).await;
+
+        let mut interval = tokio::time::interval(SEND_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let due = match state.outbox.claim_due().await {
+                Ok(due) => due,
+                Err(e) => {
+                    error!("Error claiming queued replies: {}", e);
+                    continue;
+                }
+            };
+
+            for queued in due {
+                send(&state, queued).await;
+            }
+        }
+    })
+}
+
+/// Mark replies left mid-send as failed and tell their users to check whether they were posted
+async fn fail_interrupted(state: &AppState) {
+    let interrupted = match state.outbox.fail_interrupted().await {
+        Ok(interrupted) => interrupted,
+        Err(e) => {
+            error!("Error failing interrupted replies: {}", e);
+            return;
+        }
+    };
+
+    for queued in interrupted {
+        warn!("Queued reply {} was interrupted while sending", queued.queue_id);
+        state.notification_service.auto_reply_failed(&queued.user_id, &queued.comment_id, INTERRUPTED_SEND_ERROR).await;
+    }
+}
+
+/// Post a claimed reply and record the outcome
+async fn send(state: &AppState, queued: QueuedReply) {
+    let request = PostReplyRequest {
+        comment_id: queued.comment_id.clone(),
+        reply_text: queued.reply_text.clone(),
+        ai_generated: queued.ai_generated,
+        ai_model: queued.ai_model.clone(),
+        template_id: queued.template_id.clone(),
+        dry_run: false,
+        send_at: None,
+        // Confirmed when it was queued
+        confirm_hostile: true,
+    };
+
+    let result = match post_reply_to_comment(state, &queued.user_id, request).await {
+        Ok(reply) => state.outbox.mark_sent(&queued.queue_id, &reply.reply_id).await,
+        Err(e) => {
+            error!("Error sending queued reply {}: {}", queued.queue_id, e);
+            state.notification_service.auto_reply_failed(&queued.user_id, &queued.comment_id, &e.to_string()).await;
+            state.outbox.mark_failed(&queued.queue_id, &e.to_string()).await
+        }
+    };
+
+    if let Err(e) = result {
+        error!("Error updating queued reply {}: {}", queued.queue_id, e);
+    }
+}
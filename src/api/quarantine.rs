@@ -0,0 +1,104 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    Json,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::info;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::{Comment, InteractionType};
+use crate::models::event::UserEvent;
+use crate::models::spam::SpamSettings;
+use crate::services::{dry_run, history};
+
+/// Query parameters of moderation actions
+#[derive(Debug, Deserialize)]
+pub struct ModerationParams {
+    /// Only log and record the action instead of sending it to YouTube
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// List the comments held as spam and waiting for review, newest first
+pub async fn get_quarantine(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<Comment>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.spam.quarantine(&user_id).await?))
+}
+
+/// Show the spam classifier's threshold and review counts
+pub async fn get_spam_classifier(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<SpamSettings>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.spam.settings(&user_id).await?))
+}
+
+/// Put a quarantined comment back in the inbox as not spam
+pub async fn restore_comment(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Comment>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let comment = state.spam.restore(&user_id, &comment_id).await?;
+    info!("Restored comment {} from quarantine", comment_id);
+
+    let data = HashMap::from([("action".to_string(), history::MODERATION_RESTORED.to_string())]);
+    history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommentModerated, data).await;
+    state.events.publish(&user_id, UserEvent::CommentChanged {
+        video_id: comment.video_id.clone(),
+        comment_id: comment_id.clone(),
+    });
+    Ok(Json(comment))
+}
+
+/// Confirm a quarantined comment as spam: reject it on YouTube and ban its author.
+///
+/// A dry run only records the action and leaves the comment in quarantine.
+pub async fn confirm_spam(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ModerationParams>,
+) -> AppResult<Json<Comment>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let comment = state.spam.quarantined(&user_id, &comment_id).await?;
+    if dry_run::is_dry_run(&state.settings.current(), params.dry_run) {
+        let data = HashMap::from([("ban_author".to_string(), comment.author_channel_id.clone())]);
+        dry_run::record(&state.db, &user_id, &comment_id, dry_run::WOULD_REJECT, data).await?;
+        return Ok(Json(comment));
+    }
+
+    state.youtube_service.reject_comment(&user_id, &comment_id, true).await?;
+
+    let comment = state.spam.confirm(&user_id, comment).await?;
+    info!("Confirmed comment {} as spam and banned {}", comment_id, comment.author_channel_id);
+
+    let data = HashMap::from([("action".to_string(), history::MODERATION_REJECTED.to_string())]);
+    history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommentModerated, data).await;
+    let data = HashMap::from([
+        ("channel_id".to_string(), comment.author_channel_id.clone()),
+        ("author".to_string(), comment.author.clone()),
+    ]);
+    history::record(&state.db, &user_id, &comment.video_id, &comment_id, InteractionType::CommenterBanned, data).await;
+    state.events.publish(&user_id, UserEvent::CommentChanged {
+        video_id: comment.video_id.clone(),
+        comment_id: comment_id.clone(),
+    });
+    Ok(Json(comment))
+}
@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::rule::{FilterRule, RuleAction, RuleConditions};
+use crate::services::rules::{self, MAX_RULES_PER_USER};
+
+/// Create or replace a filter rule
+#[derive(Debug, Deserialize)]
+pub struct RuleRequest {
+    /// Name shown in the rule list
+    pub name: String,
+
+    /// Whether the rule is applied
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// What a comment must look like for the rule to apply
+    pub conditions: RuleConditions,
+
+    /// What happens to matching comments
+    pub action: RuleAction,
+
+    /// Reply posted by `auto_reply` rules
+    pub reply_text: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// List the authenticated user's filter rules with their hit counters
+pub async fn get_rules(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<FilterRule>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.db.get_filter_rules(&user_id).await?))
+}
+
+/// Create a filter rule, applied to comments saved from now on
+pub async fn create_rule(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<RuleRequest>,
+) -> AppResult<(StatusCode, Json<FilterRule>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if state.db.get_filter_rules(&user_id).await?.len() >= MAX_RULES_PER_USER {
+        return Err(AppError::Validation(format!("At most {} rules are allowed", MAX_RULES_PER_USER)));
+    }
+
+    let mut rule = FilterRule::new(&user_id, request.name.trim(), request.conditions, request.action, request.reply_text);
+    rule.enabled = request.enabled;
+    rules::validate(&rule)?;
+
+    state.db.save_filter_rule(&rule).await?;
+    Ok((StatusCode::CREATED, Json(rule)))
+}
+
+/// Replace a filter rule, keeping its hit counter
+pub async fn update_rule(
+    Path(rule_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<RuleRequest>,
+) -> AppResult<Json<FilterRule>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut rule = owned_rule(&state, &user_id, &rule_id).await?;
+    rule.name = request.name.trim().to_string();
+    rule.enabled = request.enabled;
+    rule.conditions = request.conditions;
+    rule.action = request.action;
+    rule.reply_text = request.reply_text;
+    rule.updated_at = Utc::now();
+    rules::validate(&rule)?;
+
+    state.db.save_filter_rule(&rule).await?;
+    Ok(Json(rule))
+}
+
+/// Delete a filter rule; comments it already matched keep their flags
+pub async fn delete_rule(
+    Path(rule_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    owned_rule(&state, &user_id, &rule_id).await?;
+    state.db.delete_filter_rule(&rule_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// A rule of the user; other users' rules are reported as missing
+async fn owned_rule(state: &AppState, user_id: &str, rule_id: &str) -> AppResult<FilterRule> {
+    state
+        .db
+        .get_filter_rule(rule_id)
+        .await?
+        .filter(|rule| rule.user_id == user_id)
+        .ok_or_else(|| AppError::NotFound(format!("Rule {}", rule_id)))
+}
@@ -0,0 +1,87 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::saved_reply::SavedReply;
+use crate::services::saved_replies::{self, MAX_SAVED_REPLIES_PER_USER};
+
+/// Create or replace a saved reply template
+#[derive(Debug, Deserialize)]
+pub struct SavedReplyRequest {
+    /// Name shown in the template list
+    pub name: String,
+
+    /// Reply text, with `{{author}}`, `{{video_title}}` and `{{timestamp}}` placeholders
+    pub text: String,
+}
+
+/// List the authenticated user's templates with their usage counts, most used first
+pub async fn get_saved_replies(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<SavedReply>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.db.get_saved_replies(&user_id).await?))
+}
+
+/// Save a reply template
+pub async fn create_saved_reply(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SavedReplyRequest>,
+) -> AppResult<(StatusCode, Json<SavedReply>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    if state.db.get_saved_replies(&user_id).await?.len() >= MAX_SAVED_REPLIES_PER_USER {
+        return Err(AppError::Validation(format!("At most {} templates are allowed", MAX_SAVED_REPLIES_PER_USER)));
+    }
+
+    let template = SavedReply::new(&user_id, request.name.trim(), &request.text);
+    saved_replies::validate(&template)?;
+
+    state.db.save_saved_reply(&template).await?;
+    Ok((StatusCode::CREATED, Json(template)))
+}
+
+/// Replace a reply template, keeping its usage count
+pub async fn update_saved_reply(
+    Path(template_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<SavedReplyRequest>,
+) -> AppResult<Json<SavedReply>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut template = state.saved_replies.get(&user_id, &template_id).await?;
+    template.name = request.name.trim().to_string();
+    template.text = request.text;
+    template.updated_at = Utc::now();
+    saved_replies::validate(&template)?;
+
+    state.db.save_saved_reply(&template).await?;
+    Ok(Json(template))
+}
+
+/// Delete a reply template
+pub async fn delete_saved_reply(
+    Path(template_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    state.saved_replies.get(&user_id, &template_id).await?;
+    state.db.delete_saved_reply(&template_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    Json,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, parse_video_id, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::TriageState;
+use crate::models::search::{CommentSearchFilters, CommentSearchHit};
+use crate::services::masking;
+
+/// Most hits returned at once
+const MAX_SEARCH_LIMIT: usize = 200;
+
+/// Query parameters of a comment search
+#[derive(Debug, Deserialize)]
+pub struct SearchParams {
+    /// Words to look for; matched after stemming, so `review` also finds `reviews`
+    #[serde(default)]
+    pub q: String,
+
+    /// Only comments on this video
+    pub video_id: Option<String>,
+
+    /// Only comments published at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only comments published before this time
+    pub until: Option<DateTime<Utc>>,
+
+    /// Only comments in this triage state
+    pub triage: Option<TriageState>,
+
+    /// Most hits returned
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    50
+}
+
+/// Search the comments stored for the user's videos, best matches first
+pub async fn search_comments(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<SearchParams>,
+) -> AppResult<Json<Vec<CommentSearchHit>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let query = params.q.trim();
+    if query.is_empty() {
+        return Err(AppError::Validation("q must not be empty".to_string()));
+    }
+    if params.limit == 0 || params.limit > MAX_SEARCH_LIMIT {
+        return Err(AppError::Validation(format!("limit must be between 1 and {}", MAX_SEARCH_LIMIT)));
+    }
+
+    let wanted = match params.video_id.as_deref() {
+        Some(video_id) => Some(parse_video_id(&state.db, video_id).await?),
+        None => None,
+    };
+
+    // Only the user's own videos are searched
+    let video_ids: Vec<String> = state
+        .db
+        .get_user_videos(&user_id)
+        .await?
+        .into_iter()
+        .map(|video| video.video_id)
+        .filter(|id| wanted.as_ref().is_none_or(|wanted| wanted == id))
+        .collect();
+    if video_ids.is_empty() {
+        return Ok(Json(Vec::new()));
+    }
+
+    let filters = CommentSearchFilters {
+        video_ids,
+        since: params.since,
+        until: params.until,
+        triage: params.triage,
+        limit: params.limit,
+    };
+    let mut hits = state.db.search_comments(query, &filters).await?;
+
+    // Comments stored before masking was turned on are masked on the way out
+    if state.settings.current().mask_sensitive_text {
+        for hit in &mut hits {
+            masking::mask_comment(&mut hit.comment);
+            hit.highlight = masking::mask(&hit.highlight);
+        }
+    }
+
+    Ok(Json(hits))
+}
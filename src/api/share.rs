@@ -0,0 +1,101 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap},
+    response::{Html, IntoResponse},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use super::highlights::owned_comment;
+use crate::error::{AppError, AppResult};
+use crate::models::{draft::ReplyDraft, Comment};
+use crate::services::conversations::OwnReplies;
+use crate::services::sharing::{ShareLink, DEFAULT_SHARE_HOURS, MAX_SHARE_HOURS};
+
+/// Share a comment thread
+#[derive(Debug, Default, Deserialize)]
+pub struct ShareRequest {
+    /// How long the link works; 72 hours if unset
+    #[serde(default)]
+    pub expires_in_hours: Option<u32>,
+}
+
+/// Create a signed, read-only link to the thread of one of the user's comments and their draft reply
+pub async fn create_share_link(
+    Path(comment_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ShareRequest>,
+) -> AppResult<Json<ShareLink>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let hours = request.expires_in_hours.unwrap_or(DEFAULT_SHARE_HOURS);
+    if hours == 0 || hours > MAX_SHARE_HOURS {
+        return Err(AppError::Validation(format!("expires_in_hours must be between 1 and {}", MAX_SHARE_HOURS)));
+    }
+
+    owned_comment(&state, &user_id, &comment_id).await?;
+    Ok(Json(state.share_links.create(&user_id, &comment_id, hours)?))
+}
+
+/// The thread a share link points to, as a page anyone with the link can read
+pub async fn view_shared_thread(
+    Path(token): Path<String>,
+    State(state): State<AppState>,
+) -> AppResult<impl IntoResponse> {
+    // Expired, forged and revoked links all look the same
+    let claims = state.share_links.verify(&token, Utc::now())
+        .ok_or_else(|| AppError::NotFound("Share link".to_string()))?;
+
+    let comment = owned_comment(&state, &claims.user_id, &claims.comment_id).await
+        .map_err(|_| AppError::NotFound("Share link".to_string()))?;
+    let own = OwnReplies::load(&state.db, &claims.user_id, &comment.video_id).await?;
+    let draft = state.db.get_latest_pending_draft(&comment.comment_id).await?
+        .filter(|draft| draft.user_id == claims.user_id);
+
+    Ok((
+        [
+            (header::CACHE_CONTROL, "no-store"),
+            (header::REFERRER_POLICY, "no-referrer"),
+            (header::HeaderName::from_static("x-robots-tag"), "noindex"),
+        ],
+        Html(render_thread(&comment, &own, draft.as_ref())),
+    ))
+}
+
+/// The thread, oldest message first, and the draft as a plain page; the texts are escaped
+fn render_thread(comment: &Comment, own: &OwnReplies, draft: Option<&ReplyDraft>) -> String {
+    let mut replies: Vec<_> = comment.replies.iter().collect();
+    replies.sort_by_key(|reply| reply.published_at);
+
+    let mut messages = vec![message(&comment.author, comment.original_text(), false)];
+    messages.extend(replies.into_iter().map(|reply| message(&reply.author, reply.original_text(), own.contains(reply))));
+
+    let draft = match draft {
+        Some(draft) => format!("<h2>Draft reply</h2>\n<div class=\"draft\">{}</div>\n", escape_html(&draft.text)),
+        None => "<p><em>No draft reply yet.</em></p>\n".to_string(),
+    };
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"robots\" content=\"noindex\">\n\
+         <title>How should I answer this?</title>\n</head>\n<body>\n<h1>How should I answer this?</h1>\n{}{}</body>\n</html>\n",
+        messages.concat(),
+        draft,
+    )
+}
+
+fn message(author: &str, text: &str, mine: bool) -> String {
+    let author = if mine { format!("{} (me)", escape_html(author)) } else { escape_html(author) };
+    format!("<div class=\"message\">\n<strong>{}</strong>\n<p>{}</p>\n</div>\n", author, escape_html(text))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,157 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use tracing::{error, info};
+
+use super::handlers::{
+    generate_reply_for_comment, post_reply_to_comment, AppState, GenerateReplyRequest, PostReplyRequest,
+};
+use crate::models::TriageState;
+use crate::models::event::UserEvent;
+use crate::services::{dry_run, sentiment};
+use crate::services::telegram::{TelegramAction, TelegramNotifier, TelegramUpdate};
+
+/// Receive button presses from the Telegram bot.
+///
+/// Telegram retries updates that aren't acknowledged with a success status, so
+/// failures are reported to the user in the chat rather than as an error status.
+pub async fn telegram_webhook(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(update): Json<TelegramUpdate>,
+) -> Result<StatusCode, StatusCode> {
+    let telegram = state.notification_service.telegram().ok_or(StatusCode::NOT_FOUND)?;
+
+    let secret = headers
+        .get("x-telegram-bot-api-secret-token")
+        .and_then(|v| v.to_str().ok());
+    if !telegram.verify_webhook(secret) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let query = match update.callback_query {
+        Some(query) => query,
+        None => return Ok(StatusCode::OK),
+    };
+
+    let chat_id = query.message.as_ref().map(|m| m.chat.id);
+    let action = query.data.as_deref().and_then(TelegramAction::parse);
+
+    let toast = match (chat_id, action) {
+        (Some(chat_id), Some((action, comment_id))) => {
+            match handle_action(&state, telegram, chat_id, action, comment_id).await {
+                Ok(toast) => toast,
+                Err(e) => {
+                    error!("Error handling Telegram {:?} for comment {}: {}", action, comment_id, e);
+                    "Something went wrong, please try again"
+                }
+            }
+        }
+        _ => "Unknown action",
+    };
+
+    if let Err(e) = telegram.answer_callback(&query.id, toast).await {
+        error!("Error answering Telegram callback: {}", e);
+    }
+
+    Ok(StatusCode::OK)
+}
+
+/// Run a quick action for the user linked to a chat, returning the text to show them
+async fn handle_action(
+    state: &AppState,
+    telegram: &TelegramNotifier,
+    chat_id: i64,
+    action: TelegramAction,
+    comment_id: &str,
+) -> anyhow::Result<&'static str> {
+    let user = match state.db.get_user_by_telegram_chat(chat_id).await? {
+        Some(user) => user,
+        None => return Ok("This chat isn't linked to an account"),
+    };
+
+    // Only act on comments on the user's own videos
+    let comment = match state.db.get_comment(comment_id).await? {
+        Some(comment) => comment,
+        None => return Ok("Comment not found"),
+    };
+    match state.db.get_video(&comment.video_id).await? {
+        Some(video) if video.user_id == user.id => {}
+        _ => return Ok("Comment not found"),
+    }
+
+    info!("Telegram {:?} for comment {} by user {}", action, comment_id, user.id);
+
+    match action {
+        TelegramAction::Generate => {
+            let request = GenerateReplyRequest {
+                comment_id: comment_id.to_string(),
+                tone: None,
+                persona: None,
+                template_id: None,
+                additional_instructions: None,
+                parameters: None,
+            };
+
+            match generate_reply_for_comment(state, &user.id, &request).await? {
+                Some(response) => {
+                    let text = format!("Draft reply to {}:\n\n{}", comment.author, response.reply_text);
+                    telegram
+                        .send_message(
+                            chat_id,
+                            &text,
+                            Some(comment_id),
+                            &[TelegramAction::ApprovePost, TelegramAction::Ignore],
+                        )
+                        .await?;
+                    Ok("Draft ready")
+                }
+                None => Ok("Comment not found"),
+            }
+        }
+        TelegramAction::ApprovePost if sentiment::is_heated(&comment) => {
+            // Posting to a hostile thread needs a second, deliberate press
+            if state.db.get_latest_pending_draft(comment_id).await?.is_none() {
+                return Ok("No draft to post");
+            }
+            let text = format!("{}'s comment looks hostile. Read the draft again before posting it.", comment.author);
+            telegram
+                .send_message(chat_id, &text, Some(comment_id), &[TelegramAction::ConfirmPost, TelegramAction::Ignore])
+                .await?;
+            Ok("Confirm to post")
+        }
+        TelegramAction::ApprovePost | TelegramAction::ConfirmPost => {
+            let draft = match state.db.get_latest_pending_draft(comment_id).await? {
+                Some(draft) => draft,
+                None => return Ok("No draft to post"),
+            };
+
+            let request = PostReplyRequest {
+                comment_id: comment_id.to_string(),
+                reply_text: draft.text,
+                ai_generated: draft.model.is_some(),
+                ai_model: draft.model,
+                template_id: None,
+                dry_run: false,
+                send_at: None,
+                confirm_hostile: action == TelegramAction::ConfirmPost,
+            };
+            let reply = post_reply_to_comment(state, &user.id, request).await?;
+            if dry_run::is_simulated(&reply) {
+                return Ok("Dry run: reply recorded, not posted");
+            }
+            Ok("Reply posted")
+        }
+        TelegramAction::Ignore => {
+            state.db.discard_comment_drafts(comment_id).await?;
+            state.db.set_comment_triage(comment_id, TriageState::Ignored).await?;
+            state.events.publish(&user.id, UserEvent::CommentChanged {
+                video_id: comment.video_id.clone(),
+                comment_id: comment_id.to_string(),
+            });
+            Ok("Ignored")
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use chrono::Utc;
+use serde::Deserialize;
+
+use super::handlers::{get_user_id_from_headers, AppState};
+use crate::error::{AppError, AppResult};
+use crate::models::auth::ReplyTone;
+use crate::models::tone::TonePreset;
+use crate::services::tones::{self, MAX_TONES_PER_USER};
+
+/// Create or replace a tone
+#[derive(Debug, Deserialize)]
+pub struct ToneRequest {
+    /// Name shown in the tone list
+    pub name: String,
+
+    /// How the AI should write in this tone
+    pub instructions: String,
+
+    /// Replies in this tone, shown to the AI as examples
+    #[serde(default)]
+    pub examples: Vec<String>,
+
+    /// Sampling temperature, between 0 and 2; the model's own if unset
+    #[serde(default)]
+    pub temperature: Option<f32>,
+}
+
+/// List the built-in tones and the authenticated user's own
+pub async fn get_tones(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<Json<Vec<TonePreset>>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(state.db.get_tone_presets(&user_id).await?))
+}
+
+/// Create a tone
+pub async fn create_tone(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ToneRequest>,
+) -> AppResult<(StatusCode, Json<TonePreset>)> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let own = state.db.get_tone_presets(&user_id).await?
+        .into_iter()
+        .filter(|tone| !tone.is_builtin())
+        .count();
+    if own >= MAX_TONES_PER_USER {
+        return Err(AppError::Validation(format!("At most {} tones are allowed", MAX_TONES_PER_USER)));
+    }
+
+    let mut tone = TonePreset::new(&user_id, request.name.trim(), request.instructions.trim());
+    tone.examples = request.examples;
+    tone.temperature = request.temperature;
+    tones::validate(&tone)?;
+
+    state.db.save_tone_preset(&tone).await?;
+    Ok((StatusCode::CREATED, Json(tone)))
+}
+
+/// Replace one of the authenticated user's tones
+pub async fn update_tone(
+    Path(tone_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<ToneRequest>,
+) -> AppResult<Json<TonePreset>> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    let mut tone = state.tones.get_own(&user_id, &tone_id).await?;
+    tone.name = request.name.trim().to_string();
+    tone.instructions = request.instructions.trim().to_string();
+    tone.examples = request.examples;
+    tone.temperature = request.temperature;
+    tone.updated_at = Utc::now();
+    tones::validate(&tone)?;
+
+    state.db.save_tone_preset(&tone).await?;
+    Ok(Json(tone))
+}
+
+/// Delete one of the authenticated user's tones; if it was their preferred
+/// tone, replies go back to the default one
+pub async fn delete_tone(
+    Path(tone_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> AppResult<StatusCode> {
+    // Get user ID from session
+    let user_id = get_user_id_from_headers(&headers).ok_or(AppError::Unauthorized)?;
+
+    state.tones.get_own(&user_id, &tone_id).await?;
+    state.db.delete_tone_preset(&tone_id).await?;
+
+    if let Some(mut user) = state.db.get_user(&user_id).await? {
+        if user.preferences.reply_tone.0 == tone_id {
+            user.preferences.reply_tone = ReplyTone::default();
+            user.updated_at = Utc::now();
+            state.db.save_user(&user).await?;
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
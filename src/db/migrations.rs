@@ -0,0 +1,369 @@
+//! Versioned, up-only schema migrations.
+//!
+//! Each migration runs once per database, in version order, and is recorded
+//! in the `migrations` table when it succeeds. To change the schema, append a
+//! migration with the next version; never edit one that has shipped, as
+//! databases that already applied it won't run it again.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::Database;
+
+/// What a migration does
+pub enum Step {
+    /// SurrealQL statements, run in one transaction together with recording the migration
+    Sql(&'static str),
+
+    /// Code for what SurrealQL can't express, e.g. rewriting records; recorded once it returns
+    Code(fn(&Database) -> BoxFuture<'_, Result<()>>),
+}
+
+/// One change to the schema
+pub struct Migration {
+    /// Position in the order migrations run in; versions start at 1 and have no gaps
+    pub version: u32,
+
+    /// Short description, stored with the record of the migration
+    pub name: &'static str,
+
+    pub step: Step,
+}
+
+/// Every migration, oldest first
+pub static MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, name: "baseline", step: Step::Code(baseline) },
+    Migration {
+        version: 2,
+        name: "model_routing",
+        step: Step::Sql(r#"
+            DEFINE TABLE model_routing SCHEMAFULL;
+            DEFINE FIELD task ON TABLE model_routing TYPE string;
+            DEFINE FIELD model_id ON TABLE model_routing TYPE string;
+            DEFINE FIELD updated_at ON TABLE model_routing TYPE datetime;
+            DEFINE INDEX model_routing_task_idx ON TABLE model_routing COLUMNS task UNIQUE;
+        "#),
+    },
+    Migration {
+        version: 3,
+        name: "draft_persona",
+        step: Step::Sql("DEFINE FIELD persona ON TABLE drafts TYPE option<string>;"),
+    },
+    Migration { version: 4, name: "unique_comments", step: Step::Code(unique_comments) },
+    Migration {
+        version: 5,
+        name: "video_synced_at",
+        step: Step::Sql(r#"
+            DEFINE FIELD synced_at ON TABLE videos TYPE option<datetime>;
+            DEFINE INDEX video_user_published_idx ON TABLE videos COLUMNS user_id, published_at;
+        "#),
+    },
+    Migration {
+        version: 6,
+        name: "comment_search",
+        step: Step::Sql(r#"
+            DEFINE ANALYZER comment_text TOKENIZERS blank, class, punct FILTERS lowercase, ascii, snowball(english);
+            DEFINE INDEX comment_text_search ON TABLE comments COLUMNS text SEARCH ANALYZER comment_text BM25 HIGHLIGHTS;
+        "#),
+    },
+    Migration {
+        version: 7,
+        name: "retention",
+        step: Step::Sql(r#"
+            DEFINE TABLE archived_interactions SCHEMALESS;
+            DEFINE INDEX archived_interaction_user_id_idx ON TABLE archived_interactions COLUMNS user_id;
+            DEFINE TABLE archived_comments SCHEMALESS;
+            DEFINE INDEX archived_comment_video_id_idx ON TABLE archived_comments COLUMNS video_id;
+            DEFINE INDEX interaction_user_timestamp_idx ON TABLE interactions COLUMNS user_id, timestamp;
+        "#),
+    },
+    Migration {
+        version: 8,
+        name: "comment_follow_up",
+        step: Step::Sql(r#"
+            DEFINE FIELD follow_up ON TABLE comments TYPE option<object>;
+            DEFINE FIELD follow_up.reply_id ON TABLE comments TYPE string;
+            DEFINE FIELD follow_up.in_reply_to ON TABLE comments TYPE string;
+            DEFINE FIELD follow_up.detected_at ON TABLE comments TYPE datetime;
+        "#),
+    },
+    Migration {
+        version: 9,
+        name: "comment_sync",
+        step: Step::Sql(r#"
+            DEFINE TABLE comment_sync SCHEMAFULL;
+            DEFINE FIELD video_id ON TABLE comment_sync TYPE string;
+            DEFINE FIELD newest_published_at ON TABLE comment_sync TYPE option<datetime>;
+            DEFINE FIELD full_synced_at ON TABLE comment_sync TYPE option<datetime>;
+        "#),
+    },
+    Migration {
+        version: 10,
+        name: "comment_reaction",
+        step: Step::Sql(r#"
+            DEFINE FIELD reaction ON TABLE comments TYPE object DEFAULT {};
+            DEFINE FIELD reaction.hearted_at ON TABLE comments TYPE option<datetime>;
+            DEFINE FIELD reaction.liked_at ON TABLE comments TYPE option<datetime>;
+        "#),
+    },
+    Migration {
+        version: 11,
+        name: "schemaless_tables",
+        // A SCHEMAFULL table drops every field it doesn't define, which emptied nested records like
+        // preferences and replies; the defined fields keep their types
+        step: Step::Sql(r#"
+            DEFINE TABLE comments SCHEMALESS;
+            DEFINE TABLE users SCHEMALESS;
+            DEFINE TABLE auth_tokens SCHEMALESS;
+            DEFINE TABLE sessions SCHEMALESS;
+            DEFINE TABLE interactions SCHEMALESS;
+            DEFINE TABLE ai_models SCHEMALESS;
+            DEFINE TABLE videos SCHEMALESS;
+            DEFINE TABLE collections SCHEMALESS;
+            DEFINE TABLE jobs SCHEMALESS;
+            DEFINE TABLE analytics_daily SCHEMALESS;
+            DEFINE TABLE keyword_stats SCHEMALESS;
+            DEFINE TABLE ai_usage SCHEMALESS;
+            DEFINE TABLE drafts SCHEMALESS;
+            DEFINE TABLE quota_usage SCHEMALESS;
+            DEFINE TABLE prompt_templates SCHEMALESS;
+            DEFINE TABLE filter_rules SCHEMALESS;
+            DEFINE TABLE alert_rules SCHEMALESS;
+            DEFINE TABLE organizations SCHEMALESS;
+            DEFINE TABLE outbox SCHEMALESS;
+            DEFINE TABLE commenter_profiles SCHEMALESS;
+            DEFINE TABLE inbox_items SCHEMALESS;
+            DEFINE TABLE spam_settings SCHEMALESS;
+            DEFINE TABLE saved_replies SCHEMALESS;
+            DEFINE TABLE tone_presets SCHEMALESS;
+            DEFINE TABLE mutes SCHEMALESS;
+            DEFINE TABLE runtime_settings SCHEMALESS;
+            DEFINE TABLE stream_cursor SCHEMALESS;
+            DEFINE TABLE model_routing SCHEMALESS;
+            DEFINE TABLE comment_sync SCHEMALESS;
+        "#),
+    },
+    Migration {
+        version: 12,
+        name: "timestamp_strings",
+        // chrono writes timestamps as RFC 3339 strings, which SurrealDB's datetime type rejects,
+        // and queries bind them as strings too; store every timestamp as one
+        step: Step::Sql(r#"
+            DEFINE FIELD published_at ON TABLE comments TYPE string;
+            DEFINE FIELD highlight.pinned_at ON TABLE comments TYPE option<string>;
+            DEFINE FIELD highlight.highlighted_at ON TABLE comments TYPE option<string>;
+            DEFINE FIELD created_at ON TABLE users TYPE string;
+            DEFINE FIELD updated_at ON TABLE users TYPE string;
+            DEFINE FIELD connection.disconnected_at ON TABLE users TYPE option<string>;
+            DEFINE FIELD expires_at ON TABLE auth_tokens TYPE string;
+            DEFINE FIELD created_at ON TABLE sessions TYPE string;
+            DEFINE FIELD expires_at ON TABLE sessions TYPE string;
+            DEFINE FIELD timestamp ON TABLE interactions TYPE string;
+            DEFINE FIELD published_at ON TABLE videos TYPE string;
+            DEFINE FIELD last_checked_at ON TABLE videos TYPE option<string>;
+            DEFINE FIELD created_at ON TABLE collections TYPE string;
+            DEFINE FIELD updated_at ON TABLE collections TYPE string;
+            DEFINE FIELD created_at ON TABLE jobs TYPE string;
+            DEFINE FIELD updated_at ON TABLE jobs TYPE string;
+            DEFINE FIELD finished_at ON TABLE jobs TYPE option<string>;
+            DEFINE FIELD computed_at ON TABLE analytics_daily TYPE string;
+            DEFINE FIELD computed_at ON TABLE keyword_stats TYPE string;
+            DEFINE FIELD created_at ON TABLE ai_usage TYPE string;
+            DEFINE FIELD created_at ON TABLE drafts TYPE string;
+            DEFINE FIELD updated_at ON TABLE drafts TYPE string;
+            DEFINE FIELD updated_at ON TABLE prompt_templates TYPE string;
+            DEFINE FIELD last_hit_at ON TABLE filter_rules TYPE option<string>;
+            DEFINE FIELD created_at ON TABLE filter_rules TYPE string;
+            DEFINE FIELD updated_at ON TABLE filter_rules TYPE string;
+            DEFINE FIELD last_hit_at ON TABLE alert_rules TYPE option<string>;
+            DEFINE FIELD created_at ON TABLE alert_rules TYPE string;
+            DEFINE FIELD updated_at ON TABLE alert_rules TYPE string;
+            DEFINE FIELD created_at ON TABLE organizations TYPE string;
+            DEFINE FIELD updated_at ON TABLE organizations TYPE string;
+            DEFINE FIELD send_at ON TABLE outbox TYPE string;
+            DEFINE FIELD created_at ON TABLE outbox TYPE string;
+            DEFINE FIELD updated_at ON TABLE commenter_profiles TYPE string;
+            DEFINE FIELD published_at ON TABLE inbox_items TYPE string;
+            DEFINE FIELD last_interaction_at ON TABLE inbox_items TYPE option<string>;
+            DEFINE FIELD projected_at ON TABLE inbox_items TYPE string;
+            DEFINE FIELD updated_at ON TABLE spam_settings TYPE string;
+            DEFINE FIELD last_used_at ON TABLE saved_replies TYPE option<string>;
+            DEFINE FIELD created_at ON TABLE saved_replies TYPE string;
+            DEFINE FIELD updated_at ON TABLE saved_replies TYPE string;
+            DEFINE FIELD created_at ON TABLE tone_presets TYPE string;
+            DEFINE FIELD updated_at ON TABLE tone_presets TYPE string;
+            DEFINE FIELD created_at ON TABLE mutes TYPE string;
+            DEFINE FIELD updated_at ON TABLE runtime_settings TYPE option<string>;
+            DEFINE FIELD published_until ON TABLE stream_cursor TYPE string;
+            DEFINE FIELD updated_at ON TABLE model_routing TYPE string;
+            DEFINE FIELD synced_at ON TABLE videos TYPE option<string>;
+            DEFINE FIELD newest_published_at ON TABLE comment_sync TYPE option<string>;
+            DEFINE FIELD full_synced_at ON TABLE comment_sync TYPE option<string>;
+            DEFINE FIELD reaction.hearted_at ON TABLE comments TYPE option<string>;
+            DEFINE FIELD reaction.liked_at ON TABLE comments TYPE option<string>;
+        "#),
+    },
+    Migration {
+        version: 13,
+        name: "record_ids_and_optional_fields",
+        // A record's `id` is its record ID rather than a string field, and fields the models leave out
+        // may be missing
+        step: Step::Sql(r#"
+            REMOVE FIELD id ON TABLE users;
+            REMOVE FIELD id ON TABLE sessions;
+            REMOVE FIELD id ON TABLE interactions;
+            DEFINE FIELD email ON TABLE users TYPE option<string>;
+            DEFINE FIELD profile_picture_url ON TABLE users TYPE option<string>;
+            DEFINE FIELD reply_id ON TABLE interactions TYPE option<string>;
+            DEFINE FIELD follow_up.reply_id ON TABLE comments TYPE option<string>;
+            DEFINE FIELD follow_up.in_reply_to ON TABLE comments TYPE option<string>;
+            DEFINE FIELD follow_up.detected_at ON TABLE comments TYPE option<string>;
+        "#),
+    },
+    Migration {
+        version: 14,
+        name: "outbox_claimed_at",
+        step: Step::Sql("DEFINE FIELD claimed_at ON TABLE outbox TYPE option<string>;"),
+    },
+];
+
+/// Record of a migration applied to the database
+#[derive(Debug, Serialize, Deserialize)]
+struct AppliedMigration {
+    version: u32,
+    name: String,
+    applied_at: DateTime<Utc>,
+}
+
+/// The schema as it was before migrations; defining it is a no-op where it already exists
+fn baseline(db: &Database) -> BoxFuture<'_, Result<()>> {
+    Box::pin(super::define_schema(db))
+}
+
+/// Row of the comment copies query
+#[derive(Debug, Deserialize)]
+struct CommentCopies {
+    comment_id: String,
+    copies: usize,
+}
+
+/// Comments used to be stored again on every fetch; merge each one's copies, keeping
+/// `replied_to` if any copy had it, and index comments by `comment_id` uniquely
+fn unique_comments(db: &Database) -> BoxFuture<'_, Result<()>> {
+    Box::pin(async move {
+        let mut result = db.query("SELECT comment_id, count() AS copies FROM comments GROUP BY comment_id").await?;
+        let rows: Vec<CommentCopies> = result.take(0)?;
+
+        for row in rows.iter().filter(|row| row.copies > 1) {
+            db.query(r#"
+                LET $copies = (SELECT id, replied_to FROM comments WHERE comment_id = $comment_id);
+                UPDATE $copies[0].id SET replied_to = $copies.replied_to CONTAINS true;
+                DELETE array::slice($copies.id, 1);
+            "#)
+            .bind(("comment_id", &row.comment_id))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to merge the copies of comment {}", row.comment_id))?;
+        }
+
+        db.query(r#"
+            REMOVE INDEX comment_id_idx ON TABLE comments;
+            DEFINE INDEX comment_id_idx ON TABLE comments COLUMNS comment_id UNIQUE;
+        "#)
+        .await?
+        .check()?;
+
+        Ok(())
+    })
+}
+
+/// Apply the migrations the database hasn't seen yet, returning how many were applied.
+///
+/// A database migrated by a newer server, with versions this one doesn't know,
+/// is refused rather than misread.
+pub async fn migrate(db: &Database) -> Result<usize> {
+    db.query(r#"
+        DEFINE TABLE migrations SCHEMAFULL;
+        DEFINE FIELD version ON TABLE migrations TYPE int;
+        DEFINE FIELD name ON TABLE migrations TYPE string;
+        DEFINE FIELD applied_at ON TABLE migrations TYPE string;
+        DEFINE INDEX version_idx ON TABLE migrations COLUMNS version UNIQUE;
+    "#).await?;
+
+    let mut result = db.query("SELECT * FROM migrations ORDER BY version").await?;
+    let applied: Vec<AppliedMigration> = result.take(0)?;
+
+    let current = applied.last().map_or(0, |migration| migration.version);
+    let latest = MIGRATIONS.last().map_or(0, |migration| migration.version);
+    if current > latest {
+        anyhow::bail!(
+            "The database was migrated to schema version {}, newer than this server's {}; upgrade the server",
+            current,
+            latest
+        );
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|migration| migration.version > current).collect();
+    if pending.is_empty() {
+        info!("Database schema is at version {}", current);
+        return Ok(0);
+    }
+    if current > 0 {
+        warn!("Migrating the database from schema version {} to {}", current, latest);
+    }
+
+    for migration in &pending {
+        info!("Applying migration {} ({})", migration.version, migration.name);
+        let record = AppliedMigration {
+            version: migration.version,
+            name: migration.name.to_string(),
+            applied_at: Utc::now(),
+        };
+
+        match migration.step {
+            Step::Sql(statements) => {
+                db.with_transaction(&format!("{} CREATE migrations CONTENT $record;", statements))
+                .bind(("record", record))
+                .await
+                .and_then(|response| response.check())
+                .with_context(|| format!("Migration {} ({}) failed", migration.version, migration.name))?;
+            }
+            Step::Code(up) => {
+                up(db)
+                    .await
+                    .with_context(|| format!("Migration {} ({}) failed", migration.version, migration.name))?;
+                db.query("CREATE migrations CONTENT $record")
+                    .bind(("record", record))
+                    .await?
+                    .check()
+                    .with_context(|| format!("Failed to record migration {}", migration.version))?;
+            }
+        }
+    }
+
+    Ok(pending.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_consecutive() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, i + 1, "migration {} is out of order", migration.name);
+        }
+    }
+
+    #[test]
+    fn test_sql_steps_parse() {
+        for migration in MIGRATIONS {
+            if let Step::Sql(statements) = migration.step {
+                assert!(surrealdb::sql::parse(statements).is_ok(), "migration {} doesn't parse", migration.name);
+            }
+        }
+    }
+}
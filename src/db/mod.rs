@@ -1,24 +1,272 @@
 use anyhow::{Context, Result};
-use surrealdb::{
-    engine::local::{Db, Mem},
-    Surreal,
-};
+use chrono::{DateTime, NaiveDate, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fmt;
+use std::path::PathBuf;
+use surrealdb::engine::any::{self, Any};
+use surrealdb::method::Query;
+#[cfg(feature = "remote")]
+use surrealdb::opt::auth::Root;
+use surrealdb::Surreal;
 use tracing::info;
 
-use crate::models::{Comment, InteractionRecord, auth::{User, Session, AuthToken}, ai::AiModelConfig};
+use crate::models::{Comment, CommentState, HighlightState, InteractionRecord, InteractionType, ReactionState, Reply, TriageState, alert::AlertRule, auth::{User, Session, AuthToken}, ai::{AiModelConfig, AiTask, AiUsageRecord, ModelRoute}, video::{Video, VideoCursor, CommentSyncState, MonitorSettings, ReplyDefaults, VideoTimestamp}, collection::VideoCollection, job::{Job, JobItemResult, JobStatus}, analytics::{DailyRollup, KeywordStats, VideoVolumeRow, VolumeBucket}, commenter::CommenterProfile, conversation::FollowUp, draft::{DraftStatus, ReplyDraft}, inbox::InboxEntry, mute::{Mute, MuteKind}, outbox::{QueueStatus, QueuedReply}, duplicate::DuplicateGroup, prompt::{PromptKind, PromptTemplate}, organization::Organization, rule::FilterRule, saved_reply::SavedReply, search::{CommentSearchFilters, CommentSearchHit}, settings::RuntimeSettings, spam::{SpamReview, SpamSettings}, stream::StreamCursor, tone::TonePreset};
 
-pub type Database = Surreal<Db>;
+pub mod migrations;
+pub mod queries;
 
-/// Initialize the SurrealDB database
+/// A connection to the embedded engine or a SurrealDB server, whichever [`Storage`] selected.
+///
+/// Derefs to the SurrealDB client for queries; the operations the app needs are methods on it.
+#[derive(Clone)]
+pub struct Database(Surreal<Any>);
+
+impl std::ops::Deref for Database {
+    type Target = Surreal<Any>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// A video's comments a chunk at a time; see [`Database::stream_comments`]
+pub type CommentChunks = BoxStream<'static, Result<Vec<Comment>>>;
+
+/// Comments per chunk when streaming a video's comments
+pub const COMMENT_CHUNK_SIZE: usize = 500;
+
+/// Row of the duplicate groups query; the same comment may be stored more than once
+#[derive(Debug, Deserialize)]
+struct DuplicateGroupRow {
+    group_id: String,
+    comment_ids: Vec<String>,
+    authors: Vec<String>,
+    video_ids: Vec<String>,
+    sample_text: String,
+    first_seen: DateTime<Utc>,
+    last_seen: DateTime<Utc>,
+}
+
+/// Row of the comment state query
+#[derive(Debug, Deserialize)]
+struct CommentStateRow {
+    comment_id: String,
+    #[serde(flatten)]
+    state: CommentState,
+}
+
+/// What a fetch refreshes on a stored comment; the rest is what the user did with it
+#[derive(Debug, Serialize)]
+struct FetchedComment<'a> {
+    video_id: &'a str,
+    author: &'a str,
+    author_channel_id: &'a str,
+    text: &'a str,
+    text_original: &'a Option<String>,
+    like_count: i32,
+    published_at: DateTime<Utc>,
+    replies: &'a [Reply],
+    reply_count: i32,
+    timestamps: &'a [VideoTimestamp],
+    metadata: &'a HashMap<String, String>,
+}
+
+impl<'a> From<&'a Comment> for FetchedComment<'a> {
+    fn from(comment: &'a Comment) -> Self {
+        Self {
+            video_id: &comment.video_id,
+            author: &comment.author,
+            author_channel_id: &comment.author_channel_id,
+            text: &comment.text,
+            text_original: &comment.text_original,
+            like_count: comment.like_count,
+            published_at: comment.published_at,
+            replies: &comment.replies,
+            reply_count: comment.reply_count,
+            timestamps: &comment.timestamps,
+            metadata: &comment.metadata,
+        }
+    }
+}
+
+/// Row of the per-video comment count query
+#[derive(Debug, Deserialize)]
+struct VideoCountRow {
+    video_id: String,
+    comments: usize,
+}
+
+#[cfg(not(any(feature = "kv-mem", feature = "kv-rocksdb", feature = "remote")))]
+compile_error!("No storage engine is enabled; build with the `kv-mem`, `kv-rocksdb` or `remote` feature");
+
+/// Namespace the data is kept in unless `SURREALDB_NS` says otherwise
+const DEFAULT_NAMESPACE: &str = "youtube_commenter";
+
+/// Database the data is kept in unless `SURREALDB_DB` says otherwise
+const DEFAULT_DATABASE: &str = "main";
+
+/// Where the database keeps its data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Storage {
+    /// In memory; everything is lost on restart
+    Memory,
+
+    /// In a RocksDB data directory, kept across restarts
+    RocksDb(PathBuf),
+
+    /// On a SurrealDB server, which several instances of the app can share
+    Remote(RemoteStorage),
+}
+
+/// How to reach a SurrealDB server
+#[derive(Clone, PartialEq, Eq)]
+pub struct RemoteStorage {
+    /// WebSocket endpoint, e.g. `ws://db.internal:8000` or `wss://...`
+    pub url: String,
+
+    /// Root user to sign in as; the server must allow anonymous access without one
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    pub namespace: String,
+    pub database: String,
+}
+
+/// Written by hand so the password never ends up in a log line
+impl fmt::Debug for RemoteStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteStorage")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "[REDACTED]"))
+            .field("namespace", &self.namespace)
+            .field("database", &self.database)
+            .finish()
+    }
+}
+
+impl Storage {
+    /// The storage the environment selects.
+    ///
+    /// `SURREALDB_URL` names a server to connect to, signing in with
+    /// `SURREALDB_USER` and `SURREALDB_PASS` and using `SURREALDB_NS` and
+    /// `SURREALDB_DB`. Without it, `SURREALDB_PATH` is `memory` (the default)
+    /// or a RocksDB data directory.
+    pub fn from_env() -> Result<Self> {
+        if let Some(url) = env_value("SURREALDB_URL") {
+            if !url.starts_with("ws://") && !url.starts_with("wss://") {
+                anyhow::bail!("SURREALDB_URL must be a ws:// or wss:// URL: {}", url);
+            }
+
+            let username = env_value("SURREALDB_USER");
+            let password = env_value("SURREALDB_PASS");
+            if username.is_some() != password.is_some() {
+                anyhow::bail!("SURREALDB_USER and SURREALDB_PASS must be set together");
+            }
+
+            return Ok(Self::Remote(RemoteStorage {
+                url,
+                username,
+                password,
+                namespace: env_value("SURREALDB_NS").unwrap_or_else(|| DEFAULT_NAMESPACE.to_string()),
+                database: env_value("SURREALDB_DB").unwrap_or_else(|| DEFAULT_DATABASE.to_string()),
+            }));
+        }
+
+        Ok(match env_value("SURREALDB_PATH") {
+            Some(path) if path != "memory" => Self::RocksDb(PathBuf::from(path)),
+            _ => Self::Memory,
+        })
+    }
+
+    /// The namespace and database the data is kept in
+    fn scope(&self) -> (&str, &str) {
+        match self {
+            Self::Remote(remote) => (&remote.namespace, &remote.database),
+            _ => (DEFAULT_NAMESPACE, DEFAULT_DATABASE),
+        }
+    }
+}
+
+impl fmt::Display for Storage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Memory => write!(f, "in memory"),
+            Self::RocksDb(path) => write!(f, "RocksDB at {}", path.display()),
+            Self::Remote(remote) => write!(f, "server at {} ({}/{})", remote.url, remote.namespace, remote.database),
+        }
+    }
+}
+
+/// A trimmed environment variable, `None` if unset or blank
+fn env_value(name: &str) -> Option<String> {
+    env::var(name).ok().map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// Initialize an in-memory SurrealDB database
 pub async fn init_db() -> Result<Database> {
-    info!("Initializing SurrealDB");
+    open(&Storage::Memory).await
+}
+
+/// Open the database, applying the schema migrations it hasn't seen yet.
+///
+/// A database migrated by a newer server is refused; see [`migrations::migrate`].
+pub async fn open(storage: &Storage) -> Result<Database> {
+    info!("Initializing SurrealDB {}", storage);
     
-    // Create an in-memory database
-    let db = Surreal::new::<Mem>(()).await?;
+    let db = match storage {
+        #[cfg(feature = "kv-mem")]
+        Storage::Memory => any::connect("mem://").await?,
+        #[cfg(not(feature = "kv-mem"))]
+        Storage::Memory => anyhow::bail!("SURREALDB_PATH is memory, but the server was built without the kv-mem feature"),
+        #[cfg(feature = "kv-rocksdb")]
+        Storage::RocksDb(path) => {
+            std::fs::create_dir_all(path)
+                .with_context(|| format!("Failed to create the data directory {}", path.display()))?;
+            any::connect(format!("rocksdb://{}", path.display()))
+                .await
+                .with_context(|| format!("Failed to open the database in {}", path.display()))?
+        }
+        #[cfg(not(feature = "kv-rocksdb"))]
+        Storage::RocksDb(_) => anyhow::bail!("SURREALDB_PATH is a data directory, but the server was built without the kv-rocksdb feature"),
+        #[cfg(feature = "remote")]
+        Storage::Remote(remote) => {
+            let db = any::connect(remote.url.as_str())
+                .await
+                .with_context(|| format!("Failed to connect to SurrealDB at {}", remote.url))?;
+            if let (Some(username), Some(password)) = (&remote.username, &remote.password) {
+                db.signin(Root { username, password })
+                    .await
+                    .with_context(|| format!("Failed to sign in to SurrealDB at {} as {}", remote.url, username))?;
+            }
+            db
+        }
+        #[cfg(not(feature = "remote"))]
+        Storage::Remote(_) => anyhow::bail!("SURREALDB_URL is set, but the server was built without the remote feature"),
+    };
     
     // Select a namespace and database
-    db.use_ns("youtube_commenter").use_db("main").await?;
+    let (namespace, database) = storage.scope();
+    db.use_ns(namespace).use_db(database).await?;
+    let db = Database(db);
+    
+    let applied = migrations::migrate(&db).await?;
+    if applied > 0 {
+        info!("Applied {} schema migrations", applied);
+    }
+    
+    info!("SurrealDB initialized successfully");
     
+    Ok(db)
+}
+
+/// Define the tables, fields and indexes as they were before migrations; the baseline migration.
+///
+/// Don't change it: schema changes go in a new migration in [`migrations::MIGRATIONS`].
+async fn define_schema(db: &Database) -> Result<()> {
     // Create schema for comments
     db.query("DEFINE TABLE comments SCHEMAFULL").await?;
     db.query(r#"
@@ -27,11 +275,20 @@ pub async fn init_db() -> Result<Database> {
         DEFINE FIELD author ON TABLE comments TYPE string;
         DEFINE FIELD author_channel_id ON TABLE comments TYPE string;
         DEFINE FIELD text ON TABLE comments TYPE string;
+        DEFINE FIELD text_original ON TABLE comments TYPE option<string>;
         DEFINE FIELD like_count ON TABLE comments TYPE int;
         DEFINE FIELD published_at ON TABLE comments TYPE datetime;
         DEFINE FIELD replies ON TABLE comments TYPE array;
+        DEFINE FIELD reply_count ON TABLE comments TYPE int DEFAULT 0;
         DEFINE FIELD replied_to ON TABLE comments TYPE bool;
-        DEFINE FIELD metadata ON TABLE comments TYPE object;
+        DEFINE FIELD triage ON TABLE comments TYPE string DEFAULT 'new';
+        DEFINE FIELD spam_review ON TABLE comments TYPE option<string>;
+        DEFINE FIELD highlight ON TABLE comments TYPE object DEFAULT {};
+        DEFINE FIELD highlight.pinned_at ON TABLE comments TYPE option<datetime>;
+        DEFINE FIELD highlight.highlighted_at ON TABLE comments TYPE option<datetime>;
+        DEFINE FIELD sentiment ON TABLE comments TYPE option<float>;
+        DEFINE FIELD timestamps ON TABLE comments TYPE array DEFAULT [];
+        DEFINE FIELD metadata ON TABLE comments FLEXIBLE TYPE object;
         DEFINE INDEX video_id_idx ON TABLE comments COLUMNS video_id;
         DEFINE INDEX comment_id_idx ON TABLE comments COLUMNS comment_id;
     "#).await?;
@@ -47,6 +304,9 @@ pub async fn init_db() -> Result<Database> {
         DEFINE FIELD updated_at ON TABLE users TYPE datetime;
         DEFINE FIELD preferences ON TABLE users TYPE object;
         DEFINE FIELD metadata ON TABLE users TYPE object;
+        DEFINE FIELD connection ON TABLE users TYPE object DEFAULT {};
+        DEFINE FIELD connection.disconnected_at ON TABLE users TYPE option<datetime>;
+        DEFINE FIELD connection.disconnect_reason ON TABLE users TYPE option<string>;
         DEFINE INDEX user_id_idx ON TABLE users COLUMNS id;
     "#).await?;
     
@@ -102,226 +362,2215 @@ pub async fn init_db() -> Result<Database> {
         DEFINE FIELD max_response_length ON TABLE ai_models TYPE int;
         DEFINE FIELD parameters ON TABLE ai_models TYPE object;
         DEFINE FIELD is_available ON TABLE ai_models TYPE bool;
+        DEFINE FIELD prompt_cost_per_1k ON TABLE ai_models TYPE float;
+        DEFINE FIELD completion_cost_per_1k ON TABLE ai_models TYPE float;
         DEFINE FIELD metadata ON TABLE ai_models TYPE object;
         DEFINE INDEX ai_model_id_idx ON TABLE ai_models COLUMNS model_id;
     "#).await?;
     
-    info!("SurrealDB initialized successfully");
+    // Create schema for videos
+    db.query("DEFINE TABLE videos SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD video_id ON TABLE videos TYPE string;
+        DEFINE FIELD user_id ON TABLE videos TYPE string;
+        DEFINE FIELD title ON TABLE videos TYPE string;
+        DEFINE FIELD description ON TABLE videos TYPE string;
+        DEFINE FIELD published_at ON TABLE videos TYPE datetime;
+        DEFINE FIELD thumbnail_url ON TABLE videos TYPE string;
+        DEFINE FIELD duration_secs ON TABLE videos TYPE option<int>;
+        DEFINE FIELD format ON TABLE videos TYPE string DEFAULT "long_form";
+        DEFINE FIELD monitor ON TABLE videos TYPE object;
+        DEFINE FIELD monitor.enabled ON TABLE videos TYPE bool;
+        DEFINE FIELD monitor.interval_secs ON TABLE videos TYPE int;
+        DEFINE FIELD monitor.auto_reply ON TABLE videos TYPE bool;
+        DEFINE FIELD monitor.customized ON TABLE videos TYPE bool;
+        DEFINE FIELD last_checked_at ON TABLE videos TYPE option<datetime>;
+        DEFINE FIELD collection_id ON TABLE videos TYPE option<string>;
+        DEFINE FIELD reply_defaults ON TABLE videos TYPE object DEFAULT {};
+        DEFINE FIELD reply_defaults.persona ON TABLE videos TYPE option<string>;
+        DEFINE FIELD reply_defaults.tone ON TABLE videos TYPE option<string>;
+        DEFINE FIELD metadata ON TABLE videos TYPE object;
+        DEFINE INDEX video_video_id_idx ON TABLE videos COLUMNS video_id UNIQUE;
+        DEFINE INDEX video_user_id_idx ON TABLE videos COLUMNS user_id;
+        DEFINE INDEX video_collection_id_idx ON TABLE videos COLUMNS collection_id;
+    "#).await?;
     
-    Ok(db)
+    // Create schema for video collections
+    db.query("DEFINE TABLE collections SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD collection_id ON TABLE collections TYPE string;
+        DEFINE FIELD user_id ON TABLE collections TYPE string;
+        DEFINE FIELD name ON TABLE collections TYPE string;
+        DEFINE FIELD defaults ON TABLE collections TYPE object;
+        DEFINE FIELD defaults.persona ON TABLE collections TYPE option<string>;
+        DEFINE FIELD defaults.tone ON TABLE collections TYPE option<string>;
+        DEFINE FIELD defaults.auto_reply ON TABLE collections TYPE option<bool>;
+        DEFINE FIELD defaults.monitor_enabled ON TABLE collections TYPE option<bool>;
+        DEFINE FIELD defaults.interval_secs ON TABLE collections TYPE option<int>;
+        DEFINE FIELD created_at ON TABLE collections TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE collections TYPE datetime;
+        DEFINE INDEX collection_collection_id_idx ON TABLE collections COLUMNS collection_id UNIQUE;
+        DEFINE INDEX collection_user_id_idx ON TABLE collections COLUMNS user_id;
+    "#).await?;
+    
+    // Create schema for background jobs
+    db.query("DEFINE TABLE jobs SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD job_id ON TABLE jobs TYPE string;
+        DEFINE FIELD user_id ON TABLE jobs TYPE string;
+        DEFINE FIELD kind ON TABLE jobs TYPE string;
+        DEFINE FIELD status ON TABLE jobs TYPE string;
+        DEFINE FIELD total ON TABLE jobs TYPE int;
+        DEFINE FIELD succeeded ON TABLE jobs TYPE int;
+        DEFINE FIELD failed ON TABLE jobs TYPE int;
+        DEFINE FIELD results ON TABLE jobs TYPE array;
+        DEFINE FIELD error ON TABLE jobs TYPE option<string>;
+        DEFINE FIELD created_at ON TABLE jobs TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE jobs TYPE datetime;
+        DEFINE FIELD finished_at ON TABLE jobs TYPE option<datetime>;
+        DEFINE INDEX job_id_idx ON TABLE jobs COLUMNS job_id UNIQUE;
+        DEFINE INDEX job_user_id_idx ON TABLE jobs COLUMNS user_id;
+    "#).await?;
+    
+    // Create schema for daily analytics rollups
+    db.query("DEFINE TABLE analytics_daily SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD user_id ON TABLE analytics_daily TYPE string;
+        DEFINE FIELD day ON TABLE analytics_daily TYPE string;
+        DEFINE FIELD comments_received ON TABLE analytics_daily TYPE int;
+        DEFINE FIELD replies_posted ON TABLE analytics_daily TYPE int;
+        DEFINE FIELD ai_replies ON TABLE analytics_daily TYPE int;
+        DEFINE FIELD manual_replies ON TABLE analytics_daily TYPE int;
+        DEFINE FIELD median_time_to_reply_secs ON TABLE analytics_daily TYPE option<int>;
+        DEFINE FIELD comments_per_video ON TABLE analytics_daily TYPE object;
+        DEFINE FIELD computed_at ON TABLE analytics_daily TYPE datetime;
+        DEFINE INDEX analytics_user_day_idx ON TABLE analytics_daily COLUMNS user_id, day UNIQUE;
+    "#).await?;
+    
+    // Create schema for keyword frequency stats
+    db.query("DEFINE TABLE keyword_stats SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD user_id ON TABLE keyword_stats TYPE string;
+        DEFINE FIELD video_id ON TABLE keyword_stats TYPE option<string>;
+        DEFINE FIELD keywords ON TABLE keyword_stats TYPE array;
+        DEFINE FIELD hashtags ON TABLE keyword_stats TYPE array;
+        DEFINE FIELD comments_analyzed ON TABLE keyword_stats TYPE int;
+        DEFINE FIELD computed_at ON TABLE keyword_stats TYPE datetime;
+        DEFINE INDEX keyword_stats_user_video_idx ON TABLE keyword_stats COLUMNS user_id, video_id;
+    "#).await?;
+    
+    // Create schema for AI provider usage
+    db.query("DEFINE TABLE ai_usage SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD usage_id ON TABLE ai_usage TYPE string;
+        DEFINE FIELD user_id ON TABLE ai_usage TYPE string;
+        DEFINE FIELD model ON TABLE ai_usage TYPE string;
+        DEFINE FIELD prompt_tokens ON TABLE ai_usage TYPE int;
+        DEFINE FIELD completion_tokens ON TABLE ai_usage TYPE int;
+        DEFINE FIELD total_tokens ON TABLE ai_usage TYPE int;
+        DEFINE FIELD latency_ms ON TABLE ai_usage TYPE int;
+        DEFINE FIELD cost_usd ON TABLE ai_usage TYPE float;
+        DEFINE FIELD success ON TABLE ai_usage TYPE bool;
+        DEFINE FIELD error ON TABLE ai_usage TYPE option<string>;
+        DEFINE FIELD created_at ON TABLE ai_usage TYPE datetime;
+        DEFINE INDEX ai_usage_user_id_idx ON TABLE ai_usage COLUMNS user_id;
+        DEFINE INDEX ai_usage_created_at_idx ON TABLE ai_usage COLUMNS created_at;
+    "#).await?;
+    
+    // Create schema for reply drafts awaiting review
+    db.query("DEFINE TABLE drafts SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD draft_id ON TABLE drafts TYPE string;
+        DEFINE FIELD user_id ON TABLE drafts TYPE string;
+        DEFINE FIELD video_id ON TABLE drafts TYPE string;
+        DEFINE FIELD comment_id ON TABLE drafts TYPE string;
+        DEFINE FIELD text ON TABLE drafts TYPE string;
+        DEFINE FIELD model ON TABLE drafts TYPE option<string>;
+        DEFINE FIELD status ON TABLE drafts TYPE string;
+        DEFINE FIELD created_at ON TABLE drafts TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE drafts TYPE datetime;
+        DEFINE INDEX drafts_user_status_idx ON TABLE drafts COLUMNS user_id, status;
+        DEFINE INDEX drafts_comment_id_idx ON TABLE drafts COLUMNS comment_id;
+    "#).await?;
+    
+    // Create schema for YouTube API quota usage per day
+    db.query("DEFINE TABLE quota_usage SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD day ON TABLE quota_usage TYPE string;
+        DEFINE FIELD units ON TABLE quota_usage TYPE int DEFAULT 0;
+    "#).await?;
+    
+    // Create schema for prompt templates and personas edited at runtime
+    db.query("DEFINE TABLE prompt_templates SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD kind ON TABLE prompt_templates TYPE string;
+        DEFINE FIELD name ON TABLE prompt_templates TYPE string;
+        DEFINE FIELD text ON TABLE prompt_templates TYPE string;
+        DEFINE FIELD updated_at ON TABLE prompt_templates TYPE datetime;
+        DEFINE INDEX prompt_templates_kind_name_idx ON TABLE prompt_templates COLUMNS kind, name UNIQUE;
+    "#).await?;
+    
+    // Create schema for comment filter rules
+    db.query("DEFINE TABLE filter_rules SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD rule_id ON TABLE filter_rules TYPE string;
+        DEFINE FIELD user_id ON TABLE filter_rules TYPE string;
+        DEFINE FIELD name ON TABLE filter_rules TYPE string;
+        DEFINE FIELD enabled ON TABLE filter_rules TYPE bool;
+        DEFINE FIELD conditions ON TABLE filter_rules TYPE object;
+        DEFINE FIELD conditions.keywords ON TABLE filter_rules TYPE array;
+        DEFINE FIELD conditions.text_pattern ON TABLE filter_rules TYPE option<string>;
+        DEFINE FIELD conditions.author_pattern ON TABLE filter_rules TYPE option<string>;
+        DEFINE FIELD conditions.contains_link ON TABLE filter_rules TYPE option<bool>;
+        DEFINE FIELD conditions.author_tags ON TABLE filter_rules TYPE array DEFAULT [];
+        DEFINE FIELD action ON TABLE filter_rules TYPE string;
+        DEFINE FIELD reply_text ON TABLE filter_rules TYPE option<string>;
+        DEFINE FIELD hit_count ON TABLE filter_rules TYPE int DEFAULT 0;
+        DEFINE FIELD last_hit_at ON TABLE filter_rules TYPE option<datetime>;
+        DEFINE FIELD created_at ON TABLE filter_rules TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE filter_rules TYPE datetime;
+        DEFINE INDEX filter_rules_rule_id_idx ON TABLE filter_rules COLUMNS rule_id UNIQUE;
+        DEFINE INDEX filter_rules_user_id_idx ON TABLE filter_rules COLUMNS user_id;
+    "#).await?;
+    
+    // Create schema for alert rules
+    db.query("DEFINE TABLE alert_rules SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD rule_id ON TABLE alert_rules TYPE string;
+        DEFINE FIELD user_id ON TABLE alert_rules TYPE string;
+        DEFINE FIELD name ON TABLE alert_rules TYPE string;
+        DEFINE FIELD enabled ON TABLE alert_rules TYPE bool;
+        DEFINE FIELD phrases ON TABLE alert_rules TYPE array DEFAULT [];
+        DEFINE FIELD questions ON TABLE alert_rules TYPE bool DEFAULT false;
+        DEFINE FIELD video_id ON TABLE alert_rules TYPE option<string>;
+        DEFINE FIELD hit_count ON TABLE alert_rules TYPE int DEFAULT 0;
+        DEFINE FIELD last_hit_at ON TABLE alert_rules TYPE option<datetime>;
+        DEFINE FIELD created_at ON TABLE alert_rules TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE alert_rules TYPE datetime;
+        DEFINE INDEX alert_rules_rule_id_idx ON TABLE alert_rules COLUMNS rule_id UNIQUE;
+        DEFINE INDEX alert_rules_user_id_idx ON TABLE alert_rules COLUMNS user_id;
+    "#).await?;
+    
+    // Create schema for organizations of several creators' channels
+    db.query("DEFINE TABLE organizations SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD org_id ON TABLE organizations TYPE string;
+        DEFINE FIELD name ON TABLE organizations TYPE string;
+        DEFINE FIELD admin_id ON TABLE organizations TYPE string;
+        DEFINE FIELD members ON TABLE organizations TYPE array DEFAULT [];
+        DEFINE FIELD members.* ON TABLE organizations TYPE string;
+        DEFINE FIELD invite_code ON TABLE organizations TYPE string;
+        DEFINE FIELD policy ON TABLE organizations TYPE object DEFAULT {};
+        DEFINE FIELD policy.persona ON TABLE organizations TYPE option<string>;
+        DEFINE FIELD policy.allow_links ON TABLE organizations TYPE bool DEFAULT true;
+        DEFINE FIELD policy.allow_mentions ON TABLE organizations TYPE bool DEFAULT true;
+        DEFINE FIELD policy.blocked_phrases ON TABLE organizations TYPE array DEFAULT [];
+        DEFINE FIELD created_at ON TABLE organizations TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE organizations TYPE datetime;
+        DEFINE INDEX organizations_org_id_idx ON TABLE organizations COLUMNS org_id UNIQUE;
+        DEFINE INDEX organizations_invite_code_idx ON TABLE organizations COLUMNS invite_code UNIQUE;
+    "#).await?;
+    
+    // Create schema for the outbox of replies waiting out their undo window
+    db.query("DEFINE TABLE outbox SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD queue_id ON TABLE outbox TYPE string;
+        DEFINE FIELD user_id ON TABLE outbox TYPE string;
+        DEFINE FIELD comment_id ON TABLE outbox TYPE string;
+        DEFINE FIELD reply_text ON TABLE outbox TYPE string;
+        DEFINE FIELD ai_generated ON TABLE outbox TYPE bool DEFAULT false;
+        DEFINE FIELD ai_model ON TABLE outbox TYPE option<string>;
+        DEFINE FIELD template_id ON TABLE outbox TYPE option<string>;
+        DEFINE FIELD status ON TABLE outbox TYPE string;
+        DEFINE FIELD send_at ON TABLE outbox TYPE datetime;
+        DEFINE FIELD created_at ON TABLE outbox TYPE datetime;
+        DEFINE FIELD reply_id ON TABLE outbox TYPE option<string>;
+        DEFINE FIELD error ON TABLE outbox TYPE option<string>;
+        DEFINE INDEX outbox_queue_id_idx ON TABLE outbox COLUMNS queue_id UNIQUE;
+        DEFINE INDEX outbox_status_idx ON TABLE outbox COLUMNS status;
+    "#).await?;
+    
+    // Create schema for commenter profiles, one per user and commenter
+    db.query("DEFINE TABLE commenter_profiles SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD user_id ON TABLE commenter_profiles TYPE string;
+        DEFINE FIELD channel_id ON TABLE commenter_profiles TYPE string;
+        DEFINE FIELD notes ON TABLE commenter_profiles TYPE option<string>;
+        DEFINE FIELD tags ON TABLE commenter_profiles TYPE array;
+        DEFINE FIELD updated_at ON TABLE commenter_profiles TYPE datetime;
+        DEFINE INDEX commenter_profiles_user_channel_idx ON TABLE commenter_profiles COLUMNS user_id, channel_id UNIQUE;
+    "#).await?;
+    
+    // Create schema for the inbox projection, one entry per comment kept up to date from events
+    db.query("DEFINE TABLE inbox_items SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD user_id ON TABLE inbox_items TYPE string;
+        DEFINE FIELD video_id ON TABLE inbox_items TYPE string;
+        DEFINE FIELD video_title ON TABLE inbox_items TYPE string;
+        DEFINE FIELD comment_id ON TABLE inbox_items TYPE string;
+        DEFINE FIELD author_channel_id ON TABLE inbox_items TYPE string;
+        DEFINE FIELD published_at ON TABLE inbox_items TYPE datetime;
+        DEFINE FIELD triage ON TABLE inbox_items TYPE string;
+        DEFINE FIELD replied_to ON TABLE inbox_items TYPE bool;
+        DEFINE FIELD hidden ON TABLE inbox_items TYPE bool;
+        DEFINE FIELD commenter_tags ON TABLE inbox_items TYPE array;
+        DEFINE FIELD commenter_notes ON TABLE inbox_items TYPE option<string>;
+        DEFINE FIELD last_interaction ON TABLE inbox_items TYPE option<string>;
+        DEFINE FIELD last_interaction_at ON TABLE inbox_items TYPE option<datetime>;
+        DEFINE FIELD comment ON TABLE inbox_items FLEXIBLE TYPE object;
+        DEFINE FIELD projected_at ON TABLE inbox_items TYPE datetime;
+        DEFINE INDEX inbox_items_comment_id_idx ON TABLE inbox_items COLUMNS comment_id UNIQUE;
+        DEFINE INDEX inbox_items_user_published_idx ON TABLE inbox_items COLUMNS user_id, published_at;
+        DEFINE INDEX inbox_items_video_id_idx ON TABLE inbox_items COLUMNS video_id;
+    "#).await?;
+    
+    // Create schema for per-user spam classifier settings
+    db.query("DEFINE TABLE spam_settings SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD user_id ON TABLE spam_settings TYPE string;
+        DEFINE FIELD threshold ON TABLE spam_settings TYPE float;
+        DEFINE FIELD confirmed ON TABLE spam_settings TYPE int DEFAULT 0;
+        DEFINE FIELD restored ON TABLE spam_settings TYPE int DEFAULT 0;
+        DEFINE FIELD updated_at ON TABLE spam_settings TYPE datetime;
+        DEFINE INDEX spam_settings_user_id_idx ON TABLE spam_settings COLUMNS user_id UNIQUE;
+    "#).await?;
+    
+    // Create schema for saved reply templates
+    db.query("DEFINE TABLE saved_replies SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD template_id ON TABLE saved_replies TYPE string;
+        DEFINE FIELD user_id ON TABLE saved_replies TYPE string;
+        DEFINE FIELD name ON TABLE saved_replies TYPE string;
+        DEFINE FIELD text ON TABLE saved_replies TYPE string;
+        DEFINE FIELD usage_count ON TABLE saved_replies TYPE int DEFAULT 0;
+        DEFINE FIELD last_used_at ON TABLE saved_replies TYPE option<datetime>;
+        DEFINE FIELD created_at ON TABLE saved_replies TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE saved_replies TYPE datetime;
+        DEFINE INDEX saved_replies_template_id_idx ON TABLE saved_replies COLUMNS template_id UNIQUE;
+        DEFINE INDEX saved_replies_user_id_idx ON TABLE saved_replies COLUMNS user_id;
+    "#).await?;
+    
+    // Create schema for reply tone presets, built in or created by users
+    db.query("DEFINE TABLE tone_presets SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD tone_id ON TABLE tone_presets TYPE string;
+        DEFINE FIELD user_id ON TABLE tone_presets TYPE option<string>;
+        DEFINE FIELD name ON TABLE tone_presets TYPE string;
+        DEFINE FIELD instructions ON TABLE tone_presets TYPE string;
+        DEFINE FIELD examples ON TABLE tone_presets TYPE array DEFAULT [];
+        DEFINE FIELD examples.* ON TABLE tone_presets TYPE string;
+        DEFINE FIELD temperature ON TABLE tone_presets TYPE option<float>;
+        DEFINE FIELD created_at ON TABLE tone_presets TYPE datetime;
+        DEFINE FIELD updated_at ON TABLE tone_presets TYPE datetime;
+        DEFINE INDEX tone_presets_tone_id_idx ON TABLE tone_presets COLUMNS tone_id UNIQUE;
+        DEFINE INDEX tone_presets_user_id_idx ON TABLE tone_presets COLUMNS user_id;
+    "#).await?;
+    
+    // Create schema for muted videos and commenters
+    db.query("DEFINE TABLE mutes SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD mute_id ON TABLE mutes TYPE string;
+        DEFINE FIELD user_id ON TABLE mutes TYPE string;
+        DEFINE FIELD kind ON TABLE mutes TYPE string;
+        DEFINE FIELD target_id ON TABLE mutes TYPE string;
+        DEFINE FIELD label ON TABLE mutes TYPE option<string>;
+        DEFINE FIELD reason ON TABLE mutes TYPE option<string>;
+        DEFINE FIELD created_at ON TABLE mutes TYPE datetime;
+        DEFINE INDEX mutes_mute_id_idx ON TABLE mutes COLUMNS mute_id UNIQUE;
+        DEFINE INDEX mutes_user_target_idx ON TABLE mutes COLUMNS user_id, kind, target_id UNIQUE;
+    "#).await?;
+    
+    // Create schema for the runtime settings, a single record changed through the admin API
+    db.query("DEFINE TABLE runtime_settings SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD log_filter ON TABLE runtime_settings TYPE option<string>;
+        DEFINE FIELD youtube_requests_per_sec ON TABLE runtime_settings TYPE option<int>;
+        DEFINE FIELD monitor_paused ON TABLE runtime_settings TYPE bool DEFAULT false;
+        DEFINE FIELD ai_enabled ON TABLE runtime_settings TYPE bool DEFAULT true;
+        DEFINE FIELD updated_at ON TABLE runtime_settings TYPE option<datetime>;
+    "#).await?;
+    
+    // Create schema for how far the interaction history was published to the event stream, a single record
+    db.query("DEFINE TABLE stream_cursor SCHEMAFULL").await?;
+    db.query(r#"
+        DEFINE FIELD published_until ON TABLE stream_cursor TYPE datetime;
+        DEFINE INDEX interaction_timestamp_idx ON TABLE interactions COLUMNS timestamp;
+    "#).await?;
+    
+    Ok(())
 }
 
 impl Database {
+    /// A query running `statements` as one transaction: either all of them apply, or none do.
+    ///
+    /// SurrealDB keeps a transaction within a single request, so the statements
+    /// are sent together; bind their parameters on the query. A failed statement
+    /// cancels the transaction without failing the request, so `check()` the
+    /// response to see it.
+    pub fn with_transaction(&self, statements: &str) -> Query<'_, Any> {
+        self.query(format!("BEGIN TRANSACTION; {} COMMIT TRANSACTION;", statements))
+    }
+    
     // Comment methods
     
-    /// Get comments for a video from the database
+    /// Get comments for a video from the database, or `None` if none are stored
     pub async fn get_comments(&self, video_id: &str) -> Result<Option<Vec<Comment>>> {
-        let result = self
+        let mut result = self
             .query("SELECT * FROM comments WHERE video_id = $video_id")
             .bind(("video_id", video_id))
             .await?;
         
-        let comments: Option<Vec<Comment>> = result.take(0)?;
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok((!comments.is_empty()).then_some(comments))
+    }
+    
+    /// Get up to `limit` of a video's comments whose IDs sort after `after`, ordered by ID
+    pub async fn get_comments_after(&self, video_id: &str, after: &str, limit: usize) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id = $video_id AND comment_id > $after ORDER BY comment_id LIMIT $limit")
+            .bind(("video_id", video_id))
+            .bind(("after", after))
+            .bind(("limit", limit))
+            .await?;
+        
+        let comments: Vec<Comment> = result.take(0)?;
         Ok(comments)
     }
     
-    /// Save comments for a video to the database
-    pub async fn save_comments(&self, video_id: &str, comments: &[Comment]) -> Result<()> {
-        for comment in comments {
-            self.create("comments")
-                .content(comment)
-                .await
-                .with_context(|| format!("Failed to save comment {}", comment.comment_id))?;
-        }
+    /// Stream the comments stored for a video in chunks of up to `chunk_size`, ordered by ID.
+    ///
+    /// Each chunk is queried only when the previous one has been consumed, so
+    /// memory stays bounded by the chunk size however many comments the video has.
+    pub fn stream_comments(&self, video_id: &str, chunk_size: usize) -> CommentChunks {
+        let db = self.clone();
+        let video_id = video_id.to_string();
+        let chunk_size = chunk_size.max(1);
         
-        Ok(())
+        // The state is the ID the next chunk starts after, `None` once a short chunk showed there is no more
+        stream::try_unfold(Some(String::new()), move |after| {
+            let db = db.clone();
+            let video_id = video_id.clone();
+            async move {
+                let Some(after) = after else {
+                    return Ok(None);
+                };
+                let chunk = db.get_comments_after(&video_id, &after, chunk_size).await?;
+                if chunk.is_empty() {
+                    return Ok(None);
+                }
+                let next = (chunk.len() == chunk_size).then(|| chunk[chunk.len() - 1].comment_id.clone());
+                Ok::<_, anyhow::Error>(Some((chunk, next)))
+            }
+        })
+        .boxed()
     }
     
-    /// Get a specific comment by ID
-    pub async fn get_comment(&self, comment_id: &str) -> Result<Option<Comment>> {
-        let result = self
-            .query("SELECT * FROM comments WHERE comment_id = $comment_id LIMIT 1")
-            .bind(("comment_id", comment_id))
+    /// Search the text of stored comments with the full-text index, best matches first
+    pub async fn search_comments(&self, query: &str, filters: &CommentSearchFilters) -> Result<Vec<CommentSearchHit>> {
+        let mut result = self
+            .query(
+                "SELECT *, search::score(1) AS score, search::highlight('<mark>', '</mark>', 1) AS highlight \
+                 FROM comments WHERE text @1@ $query AND video_id IN $video_ids \
+                 AND ($since = NONE OR published_at >= $since) \
+                 AND ($until = NONE OR published_at < $until) \
+                 AND ($triage = NONE OR triage = $triage) \
+                 ORDER BY score DESC LIMIT $limit",
+            )
+            .bind(("query", query))
+            .bind(("video_ids", &filters.video_ids))
+            .bind(("since", filters.since))
+            .bind(("until", filters.until))
+            .bind(("triage", filters.triage))
+            .bind(("limit", filters.limit))
             .await?;
         
-        let comment: Option<Comment> = result.take(0)?;
-        Ok(comment)
+        let hits: Vec<CommentSearchHit> = result.take(0)?;
+        Ok(hits)
     }
     
-    /// Update a comment's replied_to status
-    pub async fn mark_comment_replied(&self, comment_id: &str, replied: bool) -> Result<()> {
-        self.query("UPDATE comments SET replied_to = $replied WHERE comment_id = $comment_id")
-            .bind(("comment_id", comment_id))
-            .bind(("replied", replied))
+    /// Get comments for a set of videos published since a point in time
+    pub async fn get_comments_for_videos_since(&self, video_ids: &[String], since: DateTime<Utc>) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id IN $video_ids AND published_at >= $since ORDER BY published_at ASC")
+            .bind(("video_ids", video_ids))
+            .bind(("since", since))
             .await?;
         
-        Ok(())
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
     }
     
-    // User methods
-    
-    /// Create or update a user
-    pub async fn save_user(&self, user: &User) -> Result<()> {
-        self.query("DELETE FROM users WHERE id = $id")
-            .bind(("id", &user.id))
+    /// Get the most recent comments on a video that the user replied to or that have a follow-up, newest first
+    pub async fn get_answered_comments(&self, video_id: &str, limit: usize) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id = $video_id AND (replied_to = true OR follow_up != NONE) ORDER BY published_at DESC LIMIT $limit")
+            .bind(("video_id", video_id))
+            .bind(("limit", limit))
             .await?;
-            
-        self.create("users")
-            .content(user)
-            .await
-            .with_context(|| format!("Failed to save user {}", user.id))?;
         
-        Ok(())
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
     }
     
-    /// Get a user by ID
-    pub async fn get_user(&self, user_id: &str) -> Result<Option<User>> {
-        let result = self
-            .query("SELECT * FROM users WHERE id = $user_id LIMIT 1")
-            .bind(("user_id", user_id))
+    /// Get a page of the comments on a set of videos that haven't been replied to, newest first
+    pub async fn get_unanswered_comments(&self, video_ids: &[String], start: usize, limit: usize) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id IN $video_ids AND replied_to = false ORDER BY published_at DESC LIMIT $limit START $start")
+            .bind(("video_ids", video_ids))
+            .bind(("start", start))
+            .bind(("limit", limit))
             .await?;
         
-        let user: Option<User> = result.take(0)?;
-        Ok(user)
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
     }
     
-    // Auth token methods
-    
-    /// Save an auth token
-    pub async fn save_auth_token(&self, user_id: &str, token: &AuthToken) -> Result<()> {
-        // Delete existing tokens for this user
-        self.query("DELETE FROM auth_tokens WHERE user_id = $user_id")
-            .bind(("user_id", user_id))
+    /// Count comments on a set of videos that haven't been replied to
+    pub async fn count_unanswered_comments(&self, video_ids: &[String]) -> Result<usize> {
+        let mut result = self
+            .query("SELECT count() AS total FROM comments WHERE video_id IN $video_ids AND replied_to = false GROUP ALL")
+            .bind(("video_ids", video_ids))
             .await?;
         
-        // Create a new token record
-        self.create("auth_tokens")
-            .content((
-                "user_id", user_id,
-                "access_token", &token.access_token,
-                "refresh_token", &token.refresh_token,
-                "expires_at", &token.expires_at,
-                "token_type", &token.token_type,
-                "scopes", &token.scopes,
-            ))
-            .await
-            .with_context(|| format!("Failed to save auth token for user {}", user_id))?;
-        
-        Ok(())
+        let total: Option<usize> = result.take("total")?;
+        Ok(total.unwrap_or(0))
     }
     
-    /// Get auth token for a user
-    pub async fn get_auth_token(&self, user_id: &str) -> Result<Option<AuthToken>> {
-        let result = self
-            .query("SELECT * FROM auth_tokens WHERE user_id = $user_id LIMIT 1")
-            .bind(("user_id", user_id))
+    /// Count comments on a set of videos published before a point in time
+    pub async fn count_comments_before(&self, video_ids: &[String], before: DateTime<Utc>) -> Result<usize> {
+        let mut result = self
+            .query("SELECT count() AS total FROM comments WHERE video_id IN $video_ids AND published_at < $before GROUP ALL")
+            .bind(("video_ids", video_ids))
+            .bind(("before", before))
             .await?;
         
-        let token: Option<AuthToken> = result.take(0)?;
-        Ok(token)
+        let total: Option<usize> = result.take("total")?;
+        Ok(total.unwrap_or(0))
     }
     
-    // Session methods
-    
-    /// Create a new session
-    pub async fn create_session(&self, session: &Session) -> Result<()> {
-        self.create("sessions")
-            .content(session)
-            .await
-            .with_context(|| format!("Failed to create session {}", session.id))?;
+    /// Delete the comments on a set of videos published before a point in time, and their inbox
+    /// entries, returning how many comments.
+    ///
+    /// With `archive`, the comments are first copied to `archived_comments`, in the same transaction.
+    pub async fn prune_comments(&self, video_ids: &[String], before: DateTime<Utc>, archive: bool) -> Result<usize> {
+        let pruned = self.count_comments_before(video_ids, before).await?;
+        if pruned == 0 {
+            return Ok(0);
+        }
         
-        Ok(())
+        let copy = if archive {
+            "INSERT INTO archived_comments \
+             (SELECT *, time::now() AS archived_at OMIT id FROM comments WHERE video_id IN $video_ids AND published_at < $before);"
+        } else {
+            ""
+        };
+        self.with_transaction(&format!(
+            "{} \
+             DELETE inbox_items WHERE comment_id IN \
+             (SELECT VALUE comment_id FROM comments WHERE video_id IN $video_ids AND published_at < $before); \
+             DELETE comments WHERE video_id IN $video_ids AND published_at < $before;",
+            copy
+        ))
+        .bind(("video_ids", video_ids))
+        .bind(("before", before))
+        .await?
+        .check()
+        .context("Failed to prune comments")?;
+        
+        Ok(pruned)
     }
     
-    /// Get a session by ID
-    pub async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
-        let result = self
-            .query("SELECT * FROM sessions WHERE id = $session_id LIMIT 1")
-            .bind(("session_id", session_id))
+    /// Count comments on a set of videos published since a point in time
+    pub async fn count_comments_since(&self, video_ids: &[String], since: DateTime<Utc>) -> Result<usize> {
+        let mut result = self
+            .query("SELECT count() AS total FROM comments WHERE video_id IN $video_ids AND published_at >= $since GROUP ALL")
+            .bind(("video_ids", video_ids))
+            .bind(("since", since))
             .await?;
         
-        let session: Option<Session> = result.take(0)?;
-        Ok(session)
+        let total: Option<usize> = result.take("total")?;
+        Ok(total.unwrap_or(0))
     }
     
-    /// End a session
-    pub async fn end_session(&self, session_id: &str) -> Result<()> {
-        self.query("UPDATE sessions SET is_active = false WHERE id = $session_id")
-            .bind(("session_id", session_id))
+    /// Count the stored comments of each of a set of videos; videos without comments are left out
+    pub async fn count_comments_per_video(&self, video_ids: &[String]) -> Result<HashMap<String, usize>> {
+        let mut result = self
+            .query("SELECT video_id, count() AS comments FROM comments WHERE video_id IN $video_ids GROUP BY video_id")
+            .bind(("video_ids", video_ids))
             .await?;
         
-        Ok(())
+        let rows: Vec<VideoCountRow> = result.take(0)?;
+        Ok(rows.into_iter().map(|row| (row.video_id, row.comments)).collect())
     }
     
-    // Interaction history methods
+    /// Count comments for a set of videos in time buckets, per video and across all of them.
+    ///
+    /// `bucket` is a SurrealQL duration literal such as `1h` or `1d`.
+    pub async fn get_comment_volume(
+        &self,
+        video_ids: &[String],
+        since: DateTime<Utc>,
+        bucket: &str,
+    ) -> Result<(Vec<VideoVolumeRow>, Vec<VolumeBucket>)> {
+        let mut result = self
+            .query(format!(
+                "SELECT video_id, time::floor(<datetime> published_at, {bucket}) AS bucket, count() AS comments FROM comments \
+                 WHERE video_id IN $video_ids AND published_at >= $since GROUP BY video_id, bucket ORDER BY bucket ASC;\
+                 SELECT time::floor(<datetime> published_at, {bucket}) AS bucket, count() AS comments FROM comments \
+                 WHERE video_id IN $video_ids AND published_at >= $since GROUP BY bucket ORDER BY bucket ASC;"
+            ))
+            .bind(("video_ids", video_ids))
+            .bind(("since", since))
+            .await?;
+        
+        let per_video: Vec<VideoVolumeRow> = result.take(0)?;
+        let channel: Vec<VolumeBucket> = result.take(1)?;
+        Ok((per_video, channel))
+    }
     
-    /// Record an interaction
-    pub async fn record_interaction(&self, interaction: &InteractionRecord) -> Result<()> {
-        self.create("interactions")
-            .content(interaction)
-            .await
-            .with_context(|| format!("Failed to record interaction {}", interaction.id))?;
+    /// Save comments for a video to the database, updating the ones already stored.
+    ///
+    /// A stored comment gets the fetched text, like count, replies and metadata;
+    /// `replied_to`, triage, spam review, highlights, reactions, sentiment and metadata keys
+    /// the fetch doesn't set are kept.
+    pub async fn save_comments(&self, video_id: &str, comments: &[Comment]) -> Result<()> {
+        for comment in comments {
+            let mut result = self
+                .query("UPDATE comments MERGE $fetched WHERE comment_id = $comment_id")
+                .bind(("fetched", FetchedComment::from(comment)))
+                .bind(("comment_id", &comment.comment_id))
+                .await
+                .with_context(|| format!("Failed to update comment {}", comment.comment_id))?;
+            
+            let updated: Vec<Comment> = result.take(0)?;
+            if updated.is_empty() {
+                let _: Vec<Comment> = self.create("comments")
+                    .content(comment)
+                    .await
+                    .with_context(|| format!("Failed to save comment {} of video {}", comment.comment_id, video_id))?;
+            }
+        }
         
         Ok(())
     }
     
-    /// Get interactions for a user
-    pub async fn get_user_interactions(&self, user_id: &str, limit: usize) -> Result<Vec<InteractionRecord>> {
-        let result = self
-            .query("SELECT * FROM interactions WHERE user_id = $user_id ORDER BY timestamp DESC LIMIT $limit")
-            .bind(("user_id", user_id))
+    /// Get a commenter's comments on a set of videos, newest first
+    pub async fn get_comments_by_author(&self, video_ids: &[String], channel_id: &str, limit: usize) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id IN $video_ids AND author_channel_id = $channel_id ORDER BY published_at DESC LIMIT $limit")
+            .bind(("video_ids", video_ids))
+            .bind(("channel_id", channel_id))
             .bind(("limit", limit))
             .await?;
         
-        let interactions: Vec<InteractionRecord> = result.take(0)?;
-        Ok(interactions)
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
     }
     
-    /// Get interactions for a comment
-    pub async fn get_comment_interactions(&self, comment_id: &str) -> Result<Vec<InteractionRecord>> {
-        let result = self
-            .query("SELECT * FROM interactions WHERE comment_id = $comment_id ORDER BY timestamp ASC")
-            .bind(("comment_id", comment_id))
+    /// Group the comments on a set of videos by text fingerprint
+    pub async fn get_duplicate_groups(&self, video_ids: &[String]) -> Result<Vec<DuplicateGroup>> {
+        let mut result = self
+            .query(
+                "SELECT metadata.text_hash AS group_id, array::distinct(comment_id) AS comment_ids, \
+                 array::distinct(author_channel_id) AS authors, array::distinct(video_id) AS video_ids, \
+                 array::first(text) AS sample_text, \
+                 time::min(<datetime> published_at) AS first_seen, time::max(<datetime> published_at) AS last_seen \
+                 FROM comments WHERE video_id IN $video_ids AND metadata.text_hash != NONE GROUP BY group_id",
+            )
+            .bind(("video_ids", video_ids))
             .await?;
         
-        let interactions: Vec<InteractionRecord> = result.take(0)?;
-        Ok(interactions)
+        let rows: Vec<DuplicateGroupRow> = result.take(0)?;
+        Ok(rows
+            .into_iter()
+            .map(|row| DuplicateGroup {
+                group_id: row.group_id,
+                comments: row.comment_ids.len(),
+                authors: row.authors,
+                video_ids: row.video_ids,
+                sample_text: row.sample_text,
+                first_seen: row.first_seen,
+                last_seen: row.last_seen,
+            })
+            .collect())
     }
     
-    // AI model methods
+    /// Get the comments on a set of videos with a text fingerprint, oldest first
+    pub async fn get_duplicate_comments(&self, video_ids: &[String], text_hash: &str) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id IN $video_ids AND metadata.text_hash = $text_hash ORDER BY published_at ASC")
+            .bind(("video_ids", video_ids))
+            .bind(("text_hash", text_hash))
+            .await?;
+        
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
+    }
     
-    /// Save an AI model configuration
-    pub async fn save_ai_model(&self, model: &AiModelConfig) -> Result<()> {
-        self.query("DELETE FROM ai_models WHERE model_id = $model_id")
-            .bind(("model_id", &model.model_id))
+    /// Mark comments as spam, recording why in the `spam` metadata entry
+    pub async fn mark_comments_spam(&self, comment_ids: &[String], reason: &str) -> Result<()> {
+        self.query("UPDATE comments SET metadata.spam = $reason WHERE comment_id IN $comment_ids")
+            .bind(("comment_ids", comment_ids))
+            .bind(("reason", reason))
             .await?;
-            
-        self.create("ai_models")
-            .content(model)
-            .await
-            .with_context(|| format!("Failed to save AI model {}", model.model_id))?;
         
         Ok(())
     }
     
-    /// Get all available AI models
-    pub async fn get_available_ai_models(&self) -> Result<Vec<AiModelConfig>> {
-        let result = self
-            .query("SELECT * FROM ai_models WHERE is_available = true")
+    /// Get the comments on a set of videos marked as spam and not reviewed yet, newest first
+    pub async fn get_quarantined_comments(&self, video_ids: &[String]) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id IN $video_ids AND metadata.spam != NONE AND spam_review = NONE ORDER BY published_at DESC")
+            .bind(("video_ids", video_ids))
             .await?;
         
-        let models: Vec<AiModelConfig> = result.take(0)?;
-        Ok(models)
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
     }
     
-    /// Get an AI model by ID
-    pub async fn get_ai_model(&self, model_id: &str) -> Result<Option<AiModelConfig>> {
-        let result = self
-            .query("SELECT * FROM ai_models WHERE model_id = $model_id LIMIT 1")
-            .bind(("model_id", model_id))
+    /// Record the user's verdict on a quarantined comment; restored comments lose their `spam` entry
+    pub async fn set_spam_review(&self, comment_id: &str, review: SpamReview) -> Result<()> {
+        let query = match review {
+            SpamReview::Confirmed => "UPDATE comments SET spam_review = $review WHERE comment_id = $comment_id",
+            SpamReview::Restored => "UPDATE comments SET spam_review = $review, metadata.spam = NONE WHERE comment_id = $comment_id",
+        };
+        self.query(query)
+            .bind(("comment_id", comment_id))
+            .bind(("review", review))
             .await?;
         
-        let model: Option<AiModelConfig> = result.take(0)?;
-        Ok(model)
+        Ok(())
+    }
+    
+    /// Get a user's spam classifier settings
+    pub async fn get_spam_settings(&self, user_id: &str) -> Result<Option<SpamSettings>> {
+        let mut result = self
+            .query("SELECT * FROM spam_settings WHERE user_id = $user_id LIMIT 1")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let settings: Option<SpamSettings> = result.take(0)?;
+        Ok(settings)
+    }
+    
+    /// Save a user's spam classifier settings, replacing the previous ones
+    pub async fn save_spam_settings(&self, settings: &SpamSettings) -> Result<()> {
+        self.with_transaction("DELETE FROM spam_settings WHERE user_id = $user_id; CREATE spam_settings CONTENT $record;")
+            .bind(("user_id", &settings.user_id))
+            .bind(("record", settings))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save spam settings for user {}", settings.user_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a specific comment by ID
+    pub async fn get_comment(&self, comment_id: &str) -> Result<Option<Comment>> {
+        let mut result = queries::GET_COMMENT
+            .run(self, |q| q.bind(("comment_id", comment_id)))
+            .await?;
+        
+        let comment: Option<Comment> = result.take(0)?;
+        Ok(comment)
+    }
+    
+    /// Get the replied_to status and triage state of every stored comment on a video, by comment ID
+    pub async fn get_comment_states(&self, video_id: &str) -> Result<HashMap<String, CommentState>> {
+        let mut result = self
+            .query("SELECT comment_id, replied_to, triage, spam_review, highlight, reaction, follow_up FROM comments WHERE video_id = $video_id")
+            .bind(("video_id", video_id))
+            .await?;
+        
+        let rows: Vec<CommentStateRow> = result.take(0)?;
+        Ok(rows.into_iter().map(|row| (row.comment_id, row.state)).collect())
+    }
+    
+    /// Set a comment's triage state
+    pub async fn set_comment_triage(&self, comment_id: &str, triage: TriageState) -> Result<()> {
+        self.query("UPDATE comments SET triage = $triage WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("triage", triage))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Store a comment's edited text with what is derived from it
+    pub async fn update_comment_text(
+        &self,
+        comment_id: &str,
+        text: &str,
+        text_original: Option<&str>,
+        sentiment: f32,
+        timestamps: &[VideoTimestamp],
+    ) -> Result<()> {
+        self.query("UPDATE comments SET text = $text, text_original = $text_original, sentiment = $sentiment, timestamps = $timestamps WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("text", text))
+            .bind(("text_original", text_original))
+            .bind(("sentiment", sentiment))
+            .bind(("timestamps", timestamps))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Store a comment's current like count
+    pub async fn update_comment_likes(&self, comment_id: &str, like_count: i32) -> Result<()> {
+        self.query("UPDATE comments SET like_count = $like_count WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("like_count", like_count))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Record whether a comment is pinned or highlighted
+    pub async fn set_comment_highlight(&self, comment_id: &str, highlight: &HighlightState) -> Result<()> {
+        self.query("UPDATE comments SET highlight = $highlight WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("highlight", highlight))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Record whether a comment is hearted or liked
+    pub async fn set_comment_reaction(&self, comment_id: &str, reaction: &ReactionState) -> Result<()> {
+        self.query("UPDATE comments SET reaction = $reaction WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("reaction", reaction))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Flag a reply as a follow-up awaiting the user's answer, or clear the flag
+    pub async fn set_comment_follow_up(&self, comment_id: &str, follow_up: Option<&FollowUp>) -> Result<()> {
+        self.query("UPDATE comments SET follow_up = $follow_up WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("follow_up", follow_up))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Unpin every comment on a video; YouTube pins at most one
+    pub async fn clear_video_pins(&self, video_id: &str) -> Result<()> {
+        self.query("UPDATE comments SET highlight.pinned_at = NONE WHERE video_id = $video_id AND highlight.pinned_at != NONE")
+            .bind(("video_id", video_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the comments on a video whose replies haven't all been fetched
+    pub async fn get_comments_missing_replies(&self, video_id: &str) -> Result<Vec<Comment>> {
+        let mut result = self
+            .query("SELECT * FROM comments WHERE video_id = $video_id AND array::len(replies) < reply_count")
+            .bind(("video_id", video_id))
+            .await?;
+        
+        let comments: Vec<Comment> = result.take(0)?;
+        Ok(comments)
+    }
+    
+    /// Replace the stored replies of a comment
+    pub async fn update_comment_replies(&self, comment_id: &str, replies: &[Reply]) -> Result<()> {
+        self.query("UPDATE comments SET replies = $replies WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("replies", replies))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Update a comment's replied_to status; replying answers any follow-up
+    pub async fn mark_comment_replied(&self, comment_id: &str, replied: bool) -> Result<()> {
+        self.query("UPDATE comments SET replied_to = $replied, follow_up = IF $replied THEN NONE ELSE follow_up END WHERE comment_id = $comment_id")
+            .bind(("comment_id", comment_id))
+            .bind(("replied", replied))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // User methods
+    
+    /// Create or update a user
+    pub async fn save_user(&self, user: &User) -> Result<()> {
+        self.with_transaction("DELETE type::thing('users', $id); CREATE users CONTENT $record;")
+            .bind(("id", &user.id))
+            .bind(("record", user))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save user {}", user.id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a user by ID
+    pub async fn get_user(&self, user_id: &str) -> Result<Option<User>> {
+        let mut result = self
+            .query("SELECT * FROM type::thing('users', $user_id)")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let user: Option<User> = result.take(0)?;
+        Ok(user)
+    }
+    
+    /// Get the user whose notifications go to a Telegram chat
+    pub async fn get_user_by_telegram_chat(&self, chat_id: i64) -> Result<Option<User>> {
+        let mut result = self
+            .query("SELECT * FROM users WHERE preferences.notifications.telegram_chat_id = $chat_id LIMIT 1")
+            .bind(("chat_id", chat_id))
+            .await?;
+        
+        let user: Option<User> = result.take(0)?;
+        Ok(user)
+    }
+    
+    /// Get every user
+    pub async fn get_users(&self) -> Result<Vec<User>> {
+        let mut result = self.query("SELECT * FROM users").await?;
+        
+        let users: Vec<User> = result.take(0)?;
+        Ok(users)
+    }
+    
+    // Auth token methods
+    
+    /// Save an auth token
+    pub async fn save_auth_token(&self, user_id: &str, token: &AuthToken) -> Result<()> {
+        // Replace the user's token, keeping the old one if the new one can't be stored
+        self.with_transaction(
+            "DELETE FROM auth_tokens WHERE user_id = $user_id; \
+             CREATE auth_tokens SET user_id = $user_id, access_token = $new_token.access_token, \
+             refresh_token = $new_token.refresh_token, expires_at = $new_token.expires_at, \
+             token_type = $new_token.token_type, scopes = $new_token.scopes;",
+        )
+        .bind(("user_id", user_id))
+        // `$token` and `$auth` are reserved by SurrealDB
+        .bind(("new_token", token))
+        .await?
+        .check()
+        .with_context(|| format!("Failed to save auth token for user {}", user_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get auth token for a user
+    pub async fn get_auth_token(&self, user_id: &str) -> Result<Option<AuthToken>> {
+        let mut result = queries::GET_AUTH_TOKEN
+            .run(self, |q| q.bind(("user_id", user_id)))
+            .await?;
+        
+        let token: Option<AuthToken> = result.take(0)?;
+        Ok(token)
+    }
+    
+    // Session methods
+    
+    /// Create a new session
+    pub async fn create_session(&self, session: &Session) -> Result<()> {
+        let _: Vec<Session> = self.create("sessions")
+            .content(session)
+            .await
+            .with_context(|| format!("Failed to create session {}", session.id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a session by ID
+    pub async fn get_session(&self, session_id: &str) -> Result<Option<Session>> {
+        let mut result = queries::GET_SESSION
+            .run(self, |q| q.bind(("session_id", session_id)))
+            .await?;
+        
+        let session: Option<Session> = result.take(0)?;
+        Ok(session)
+    }
+    
+    /// End a session
+    pub async fn end_session(&self, session_id: &str) -> Result<()> {
+        self.query("UPDATE sessions SET is_active = false WHERE id = type::thing('sessions', $session_id)")
+            .bind(("session_id", session_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Interaction history methods
+    
+    /// Record an interaction
+    pub async fn record_interaction(&self, interaction: &InteractionRecord) -> Result<()> {
+        queries::RECORD_INTERACTION
+            .run(self, |q| q.bind(("interaction", interaction)))
+            .await
+            .and_then(|response| response.check())
+            .with_context(|| format!("Failed to record interaction {}", interaction.id))?;
+        
+        Ok(())
+    }
+    
+    /// Get interactions for a user, newest first; an empty `types` means every type
+    pub async fn get_user_interactions(&self, user_id: &str, types: &[InteractionType], limit: usize) -> Result<Vec<InteractionRecord>> {
+        let mut result = self
+            .query("SELECT * FROM interactions WHERE user_id = $user_id AND (array::len($types) = 0 OR interaction_type IN $types) ORDER BY timestamp DESC LIMIT $limit")
+            .bind(("user_id", user_id))
+            .bind(("types", types))
+            .bind(("limit", limit))
+            .await?;
+        
+        let interactions: Vec<InteractionRecord> = result.take(0)?;
+        Ok(interactions)
+    }
+    
+    /// Get interactions for a user since a point in time, oldest first
+    pub async fn get_user_interactions_since(&self, user_id: &str, since: DateTime<Utc>) -> Result<Vec<InteractionRecord>> {
+        let mut result = self
+            .query("SELECT * FROM interactions WHERE user_id = $user_id AND timestamp >= $since ORDER BY timestamp ASC")
+            .bind(("user_id", user_id))
+            .bind(("since", since))
+            .await?;
+        
+        let interactions: Vec<InteractionRecord> = result.take(0)?;
+        Ok(interactions)
+    }
+    
+    /// Get up to `limit` interactions of any user recorded after a point in time, oldest first
+    pub async fn get_interactions_after(&self, after: DateTime<Utc>, limit: usize) -> Result<Vec<InteractionRecord>> {
+        let mut result = self
+            .query("SELECT * FROM interactions WHERE timestamp > $after ORDER BY timestamp ASC LIMIT $limit")
+            .bind(("after", after))
+            .bind(("limit", limit))
+            .await?;
+        
+        let interactions: Vec<InteractionRecord> = result.take(0)?;
+        Ok(interactions)
+    }
+    
+    /// Get the interactions with a video's comments, newest first
+    pub async fn get_video_interactions(&self, video_id: &str) -> Result<Vec<InteractionRecord>> {
+        let mut result = self
+            .query("SELECT * FROM interactions WHERE video_id = $video_id ORDER BY timestamp DESC")
+            .bind(("video_id", video_id))
+            .await?;
+        
+        let interactions: Vec<InteractionRecord> = result.take(0)?;
+        Ok(interactions)
+    }
+    
+    /// Get interactions for a comment
+    pub async fn get_comment_interactions(&self, comment_id: &str) -> Result<Vec<InteractionRecord>> {
+        let mut result = self
+            .query("SELECT * FROM interactions WHERE comment_id = $comment_id ORDER BY timestamp ASC")
+            .bind(("comment_id", comment_id))
+            .await?;
+        
+        let interactions: Vec<InteractionRecord> = result.take(0)?;
+        Ok(interactions)
+    }
+    
+    /// Count a user's interactions recorded before a point in time
+    pub async fn count_interactions_before(&self, user_id: &str, before: DateTime<Utc>) -> Result<usize> {
+        let mut result = self
+            .query("SELECT count() AS total FROM interactions WHERE user_id = $user_id AND timestamp < $before GROUP ALL")
+            .bind(("user_id", user_id))
+            .bind(("before", before))
+            .await?;
+        
+        let total: Option<usize> = result.take("total")?;
+        Ok(total.unwrap_or(0))
+    }
+    
+    /// Delete a user's interactions recorded before a point in time, returning how many.
+    ///
+    /// With `archive`, they are first copied to `archived_interactions`, in the same transaction.
+    pub async fn prune_interactions(&self, user_id: &str, before: DateTime<Utc>, archive: bool) -> Result<usize> {
+        let pruned = self.count_interactions_before(user_id, before).await?;
+        if pruned == 0 {
+            return Ok(0);
+        }
+        
+        let copy = if archive {
+            "INSERT INTO archived_interactions \
+             (SELECT *, time::now() AS archived_at OMIT id FROM interactions WHERE user_id = $user_id AND timestamp < $before);"
+        } else {
+            ""
+        };
+        self.with_transaction(&format!("{} DELETE interactions WHERE user_id = $user_id AND timestamp < $before;", copy))
+        .bind(("user_id", user_id))
+        .bind(("before", before))
+        .await?
+        .check()
+        .with_context(|| format!("Failed to prune the interactions of user {}", user_id))?;
+        
+        Ok(pruned)
+    }
+    
+    // AI model methods
+    
+    /// Save an AI model configuration
+    pub async fn save_ai_model(&self, model: &AiModelConfig) -> Result<()> {
+        self.with_transaction("DELETE FROM ai_models WHERE model_id = $model_id; CREATE ai_models CONTENT $record;")
+            .bind(("model_id", &model.model_id))
+            .bind(("record", model))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save AI model {}", model.model_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get all available AI models
+    pub async fn get_available_ai_models(&self) -> Result<Vec<AiModelConfig>> {
+        let mut result = self
+            .query("SELECT * FROM ai_models WHERE is_available = true")
+            .await?;
+        
+        let models: Vec<AiModelConfig> = result.take(0)?;
+        Ok(models)
+    }
+    
+    /// Get an AI model by ID
+    pub async fn get_ai_model(&self, model_id: &str) -> Result<Option<AiModelConfig>> {
+        let mut result = self
+            .query("SELECT * FROM ai_models WHERE model_id = $model_id LIMIT 1")
+            .bind(("model_id", model_id))
+            .await?;
+        
+        let model: Option<AiModelConfig> = result.take(0)?;
+        Ok(model)
+    }
+    
+    /// Set the model a task uses by default, replacing its previous route
+    pub async fn save_model_route(&self, route: &ModelRoute) -> Result<()> {
+        self.with_transaction("DELETE FROM model_routing WHERE task = $task; CREATE model_routing CONTENT $record;")
+            .bind(("task", route.task))
+            .bind(("record", route))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save the model route of {:?}", route.task))?;
+        
+        Ok(())
+    }
+    
+    /// Get the model route of a task, if one is configured
+    pub async fn get_model_route(&self, task: AiTask) -> Result<Option<ModelRoute>> {
+        let mut result = self
+            .query("SELECT * FROM model_routing WHERE task = $task LIMIT 1")
+            .bind(("task", task))
+            .await?;
+        
+        let route: Option<ModelRoute> = result.take(0)?;
+        Ok(route)
+    }
+    
+    /// Get every configured model route
+    pub async fn get_model_routes(&self) -> Result<Vec<ModelRoute>> {
+        let mut result = self.query("SELECT * FROM model_routing").await?;
+        
+        let routes: Vec<ModelRoute> = result.take(0)?;
+        Ok(routes)
+    }
+    
+    /// Remove a task's model route, so it uses the built-in default again
+    pub async fn delete_model_route(&self, task: AiTask) -> Result<()> {
+        self.query("DELETE FROM model_routing WHERE task = $task")
+            .bind(("task", task))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Record a call to an AI provider
+    pub async fn record_ai_usage(&self, usage: &AiUsageRecord) -> Result<()> {
+        let _: Vec<AiUsageRecord> = self.create("ai_usage")
+            .content(usage)
+            .await
+            .with_context(|| format!("Failed to record AI usage {}", usage.usage_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get AI provider calls for a user since a point in time, oldest first
+    pub async fn get_ai_usage_since(&self, user_id: &str, since: DateTime<Utc>) -> Result<Vec<AiUsageRecord>> {
+        let mut result = self
+            .query("SELECT * FROM ai_usage WHERE user_id = $user_id AND created_at >= $since ORDER BY created_at ASC")
+            .bind(("user_id", user_id))
+            .bind(("since", since))
+            .await?;
+        
+        let usage: Vec<AiUsageRecord> = result.take(0)?;
+        Ok(usage)
+    }
+    
+    /// Sum the estimated AI spend of a user since a point in time (in USD)
+    pub async fn sum_ai_cost_since(&self, user_id: &str, since: DateTime<Utc>) -> Result<f64> {
+        let mut result = self
+            .query("SELECT math::sum(cost_usd) AS total FROM ai_usage WHERE user_id = $user_id AND created_at >= $since GROUP ALL")
+            .bind(("user_id", user_id))
+            .bind(("since", since))
+            .await?;
+        
+        let total: Option<f64> = result.take("total")?;
+        Ok(total.unwrap_or(0.0))
+    }
+    
+    // Draft methods
+    
+    /// Save a reply draft
+    pub async fn save_draft(&self, draft: &ReplyDraft) -> Result<()> {
+        let _: Vec<ReplyDraft> = self.create("drafts")
+            .content(draft)
+            .await
+            .with_context(|| format!("Failed to save draft {}", draft.draft_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a user's drafts awaiting review, oldest first
+    pub async fn get_pending_drafts(&self, user_id: &str) -> Result<Vec<ReplyDraft>> {
+        let mut result = self
+            .query("SELECT * FROM drafts WHERE user_id = $user_id AND status = $status ORDER BY created_at ASC")
+            .bind(("user_id", user_id))
+            .bind(("status", DraftStatus::Pending))
+            .await?;
+        
+        let drafts: Vec<ReplyDraft> = result.take(0)?;
+        Ok(drafts)
+    }
+    
+    /// Count a user's drafts awaiting review
+    pub async fn count_pending_drafts(&self, user_id: &str) -> Result<usize> {
+        let mut result = self
+            .query("SELECT count() AS total FROM drafts WHERE user_id = $user_id AND status = $status GROUP ALL")
+            .bind(("user_id", user_id))
+            .bind(("status", DraftStatus::Pending))
+            .await?;
+        
+        let total: Option<usize> = result.take("total")?;
+        Ok(total.unwrap_or(0))
+    }
+    
+    /// Mark the pending drafts for a comment as posted
+    pub async fn mark_comment_drafts_posted(&self, comment_id: &str) -> Result<()> {
+        self.query("UPDATE drafts SET status = $posted, updated_at = $now WHERE comment_id = $comment_id AND status = $pending")
+            .bind(("comment_id", comment_id))
+            .bind(("posted", DraftStatus::Posted))
+            .bind(("pending", DraftStatus::Pending))
+            .bind(("now", Utc::now()))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the newest pending draft for a comment
+    pub async fn get_latest_pending_draft(&self, comment_id: &str) -> Result<Option<ReplyDraft>> {
+        let mut result = self
+            .query("SELECT * FROM drafts WHERE comment_id = $comment_id AND status = $status ORDER BY created_at DESC LIMIT 1")
+            .bind(("comment_id", comment_id))
+            .bind(("status", DraftStatus::Pending))
+            .await?;
+        
+        let draft: Option<ReplyDraft> = result.take(0)?;
+        Ok(draft)
+    }
+    
+    /// Discard the pending drafts for a comment
+    pub async fn discard_comment_drafts(&self, comment_id: &str) -> Result<()> {
+        self.query("UPDATE drafts SET status = $discarded, updated_at = $now WHERE comment_id = $comment_id AND status = $pending")
+            .bind(("comment_id", comment_id))
+            .bind(("discarded", DraftStatus::Discarded))
+            .bind(("pending", DraftStatus::Pending))
+            .bind(("now", Utc::now()))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Quota methods
+    
+    /// Add units to the YouTube API quota used on a day
+    pub async fn add_quota_usage(&self, day: NaiveDate, units: u64) -> Result<()> {
+        self.query("UPDATE type::thing('quota_usage', $day) SET day = $day, units += $units")
+            .bind(("day", day.to_string()))
+            .bind(("units", units))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the YouTube API quota units used on a day
+    pub async fn get_quota_usage(&self, day: NaiveDate) -> Result<u64> {
+        let mut result = self
+            .query("SELECT VALUE units FROM type::thing('quota_usage', $day)")
+            .bind(("day", day.to_string()))
+            .await?;
+        
+        let units: Option<u64> = result.take(0)?;
+        Ok(units.unwrap_or(0))
+    }
+    
+    // Filter rule methods
+    
+    /// Create or replace a filter rule
+    pub async fn save_filter_rule(&self, rule: &FilterRule) -> Result<()> {
+        self.delete_filter_rule(&rule.rule_id).await?;
+        
+        let _: Vec<FilterRule> = self.create("filter_rules")
+            .content(rule)
+            .await
+            .with_context(|| format!("Failed to save filter rule {}", rule.rule_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a filter rule by ID
+    pub async fn get_filter_rule(&self, rule_id: &str) -> Result<Option<FilterRule>> {
+        let mut result = self
+            .query("SELECT * FROM filter_rules WHERE rule_id = $rule_id LIMIT 1")
+            .bind(("rule_id", rule_id))
+            .await?;
+        
+        let rule: Option<FilterRule> = result.take(0)?;
+        Ok(rule)
+    }
+    
+    /// Get a user's filter rules, oldest first
+    pub async fn get_filter_rules(&self, user_id: &str) -> Result<Vec<FilterRule>> {
+        let mut result = self
+            .query("SELECT * FROM filter_rules WHERE user_id = $user_id ORDER BY created_at ASC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let rules: Vec<FilterRule> = result.take(0)?;
+        Ok(rules)
+    }
+    
+    /// Delete a filter rule, if stored
+    pub async fn delete_filter_rule(&self, rule_id: &str) -> Result<()> {
+        self.query("DELETE FROM filter_rules WHERE rule_id = $rule_id")
+            .bind(("rule_id", rule_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Add to a filter rule's hit counter
+    pub async fn record_rule_hits(&self, rule_id: &str, hits: u64, at: DateTime<Utc>) -> Result<()> {
+        self.query("UPDATE filter_rules SET hit_count += $hits, last_hit_at = $at WHERE rule_id = $rule_id")
+            .bind(("rule_id", rule_id))
+            .bind(("hits", hits))
+            .bind(("at", at))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Alert rule methods
+    
+    /// Create or replace an alert rule
+    pub async fn save_alert_rule(&self, rule: &AlertRule) -> Result<()> {
+        self.delete_alert_rule(&rule.rule_id).await?;
+        
+        let _: Vec<AlertRule> = self.create("alert_rules")
+            .content(rule)
+            .await
+            .with_context(|| format!("Failed to save alert rule {}", rule.rule_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get an alert rule by ID
+    pub async fn get_alert_rule(&self, rule_id: &str) -> Result<Option<AlertRule>> {
+        let mut result = self
+            .query("SELECT * FROM alert_rules WHERE rule_id = $rule_id LIMIT 1")
+            .bind(("rule_id", rule_id))
+            .await?;
+        
+        let rule: Option<AlertRule> = result.take(0)?;
+        Ok(rule)
+    }
+    
+    /// Get a user's alert rules, oldest first
+    pub async fn get_alert_rules(&self, user_id: &str) -> Result<Vec<AlertRule>> {
+        let mut result = self
+            .query("SELECT * FROM alert_rules WHERE user_id = $user_id ORDER BY created_at ASC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let rules: Vec<AlertRule> = result.take(0)?;
+        Ok(rules)
+    }
+    
+    /// Delete an alert rule, if stored
+    pub async fn delete_alert_rule(&self, rule_id: &str) -> Result<()> {
+        self.query("DELETE FROM alert_rules WHERE rule_id = $rule_id")
+            .bind(("rule_id", rule_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Add to an alert rule's hit counter
+    pub async fn record_alert_hits(&self, rule_id: &str, hits: u64, at: DateTime<Utc>) -> Result<()> {
+        self.query("UPDATE alert_rules SET hit_count += $hits, last_hit_at = $at WHERE rule_id = $rule_id")
+            .bind(("rule_id", rule_id))
+            .bind(("hits", hits))
+            .bind(("at", at))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Organization methods
+    
+    /// Create or replace an organization
+    pub async fn save_organization(&self, org: &Organization) -> Result<()> {
+        self.with_transaction("DELETE FROM organizations WHERE org_id = $org_id; CREATE organizations CONTENT $record;")
+            .bind(("org_id", &org.org_id))
+            .bind(("record", org))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save organization {}", org.org_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get the organization a user belongs to, if any
+    pub async fn get_user_organization(&self, user_id: &str) -> Result<Option<Organization>> {
+        let mut result = self
+            .query("SELECT * FROM organizations WHERE members CONTAINS $user_id LIMIT 1")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let org: Option<Organization> = result.take(0)?;
+        Ok(org)
+    }
+    
+    /// Get the organization an invite code lets creators join
+    pub async fn get_organization_by_invite(&self, invite_code: &str) -> Result<Option<Organization>> {
+        let mut result = self
+            .query("SELECT * FROM organizations WHERE invite_code = $invite_code LIMIT 1")
+            .bind(("invite_code", invite_code))
+            .await?;
+        
+        let org: Option<Organization> = result.take(0)?;
+        Ok(org)
+    }
+    
+    // Outbox methods
+    
+    /// Queue a reply in the outbox
+    pub async fn queue_reply(&self, queued: &QueuedReply) -> Result<()> {
+        let _: Vec<QueuedReply> = self.create("outbox")
+            .content(queued)
+            .await
+            .with_context(|| format!("Failed to queue reply {}", queued.queue_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a queued reply by ID
+    pub async fn get_queued_reply(&self, queue_id: &str) -> Result<Option<QueuedReply>> {
+        let mut result = self
+            .query("SELECT * FROM outbox WHERE queue_id = $queue_id LIMIT 1")
+            .bind(("queue_id", queue_id))
+            .await?;
+        
+        let queued: Option<QueuedReply> = result.take(0)?;
+        Ok(queued)
+    }
+    
+    /// Get a user's replies still waiting to be sent, soonest first
+    pub async fn get_pending_replies(&self, user_id: &str) -> Result<Vec<QueuedReply>> {
+        let mut result = self
+            .query("SELECT * FROM outbox WHERE user_id = $user_id AND status = 'pending' ORDER BY send_at ASC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let queued: Vec<QueuedReply> = result.take(0)?;
+        Ok(queued)
+    }
+    
+    /// Move the pending replies due by `now` to `sending` and return them.
+    /// Replies of disconnected users stay pending until they reconnect.
+    ///
+    /// A single statement, so a reply cancelled at the same moment is either
+    /// claimed here or cancelled, never both.
+    pub async fn claim_due_replies(&self, now: DateTime<Utc>) -> Result<Vec<QueuedReply>> {
+        let mut result = self
+            .query(r#"
+                UPDATE outbox SET status = 'sending', claimed_at = $now
+                WHERE status = 'pending' AND send_at <= $now
+                    AND user_id NOTINSIDE (SELECT VALUE meta::id(id) FROM users WHERE connection.disconnected_at != NONE)
+                RETURN AFTER
+            "#)
+            .bind(("now", now))
+            .await?;
+        
+        let claimed: Vec<QueuedReply> = result.take(0)?;
+        Ok(claimed)
+    }
+    
+    /// Mark replies claimed for sending before `before` as failed, returning them.
+    ///
+    /// For replies whose sender stopped mid-send, e.g. in a crash. Replies claimed
+    /// before the claim time was recorded count as stale.
+    pub async fn fail_stale_sending_replies(&self, before: DateTime<Utc>, error: &str) -> Result<Vec<QueuedReply>> {
+        let mut result = self
+            .query(r#"
+                UPDATE outbox SET status = 'failed', error = $error
+                WHERE status = 'sending' AND (claimed_at = NONE OR claimed_at <= $before)
+                RETURN AFTER
+            "#)
+            .bind(("before", before))
+            .bind(("error", error))
+            .await?;
+        
+        let failed: Vec<QueuedReply> = result.take(0)?;
+        Ok(failed)
+    }
+    
+    /// Cancel a queued reply if it is still pending, returning whether it was
+    pub async fn cancel_queued_reply(&self, queue_id: &str) -> Result<bool> {
+        let mut result = self
+            .query("UPDATE outbox SET status = 'cancelled' WHERE queue_id = $queue_id AND status = 'pending' RETURN AFTER")
+            .bind(("queue_id", queue_id))
+            .await?;
+        
+        let cancelled: Vec<QueuedReply> = result.take(0)?;
+        Ok(!cancelled.is_empty())
+    }
+    
+    /// Record how sending a queued reply ended
+    pub async fn finish_queued_reply(
+        &self,
+        queue_id: &str,
+        status: QueueStatus,
+        reply_id: Option<String>,
+        error: Option<String>,
+    ) -> Result<()> {
+        self.query("UPDATE outbox SET status = $status, reply_id = $reply_id, error = $error WHERE queue_id = $queue_id")
+            .bind(("queue_id", queue_id))
+            .bind(("status", status))
+            .bind(("reply_id", reply_id))
+            .bind(("error", error))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Commenter profile methods
+    
+    /// Create or replace a commenter profile
+    pub async fn save_commenter_profile(&self, profile: &CommenterProfile) -> Result<()> {
+        self.with_transaction("DELETE FROM commenter_profiles WHERE user_id = $user_id AND channel_id = $channel_id; CREATE commenter_profiles CONTENT $record;")
+            .bind(("user_id", &profile.user_id))
+            .bind(("channel_id", &profile.channel_id))
+            .bind(("record", profile))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save commenter profile {}", profile.channel_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a user's profile of a commenter
+    pub async fn get_commenter_profile(&self, user_id: &str, channel_id: &str) -> Result<Option<CommenterProfile>> {
+        let mut result = self
+            .query("SELECT * FROM commenter_profiles WHERE user_id = $user_id AND channel_id = $channel_id LIMIT 1")
+            .bind(("user_id", user_id))
+            .bind(("channel_id", channel_id))
+            .await?;
+        
+        let profile: Option<CommenterProfile> = result.take(0)?;
+        Ok(profile)
+    }
+    
+    /// Get every commenter profile a user keeps
+    pub async fn get_commenter_profiles(&self, user_id: &str) -> Result<Vec<CommenterProfile>> {
+        let mut result = self
+            .query("SELECT * FROM commenter_profiles WHERE user_id = $user_id")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let profiles: Vec<CommenterProfile> = result.take(0)?;
+        Ok(profiles)
+    }
+    
+    // Inbox projection methods
+    
+    /// Replace the inbox entries of a video's comments
+    pub async fn replace_video_inbox_entries(&self, video_id: &str, entries: &[InboxEntry]) -> Result<()> {
+        self.with_transaction("DELETE FROM inbox_items WHERE video_id = $video_id; INSERT INTO inbox_items $entries;")
+            .bind(("video_id", video_id))
+            .bind(("entries", entries))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save the inbox entries of video {}", video_id))?;
+        
+        Ok(())
+    }
+    
+    /// Create or replace a comment's inbox entry
+    pub async fn save_inbox_entry(&self, entry: &InboxEntry) -> Result<()> {
+        self.replace_comment_inbox_entry(&entry.comment_id, Some(entry)).await
+    }
+    
+    /// Remove a comment's inbox entry, e.g. once the comment is gone
+    pub async fn delete_inbox_entry(&self, comment_id: &str) -> Result<()> {
+        self.replace_comment_inbox_entry(comment_id, None).await
+    }
+    
+    async fn replace_comment_inbox_entry(&self, comment_id: &str, entry: Option<&InboxEntry>) -> Result<()> {
+        let Some(entry) = entry else {
+            self.query("DELETE FROM inbox_items WHERE comment_id = $comment_id")
+                .bind(("comment_id", comment_id))
+                .await?;
+            return Ok(());
+        };
+        
+        self.with_transaction("DELETE FROM inbox_items WHERE comment_id = $comment_id; CREATE inbox_items CONTENT $record;")
+            .bind(("comment_id", comment_id))
+            .bind(("record", entry))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save inbox entry {}", comment_id))?;
+        
+        Ok(())
+    }
+    
+    /// Count a user's inbox entries not looked at yet and those with a follow-up, leaving out what the inbox hides
+    pub async fn count_inbox_attention(&self, user_id: &str) -> Result<(usize, usize)> {
+        let mut result = self
+            .query(
+                "SELECT count(triage = 'new') AS unread, count(comment.follow_up != NONE) AS follow_ups FROM inbox_items \
+                 WHERE user_id = $user_id AND hidden = false \
+                 AND video_id NOTINSIDE (SELECT VALUE target_id FROM mutes WHERE user_id = $user_id AND kind = 'video') \
+                 AND author_channel_id NOTINSIDE (SELECT VALUE target_id FROM mutes WHERE user_id = $user_id AND kind = 'commenter') \
+                 GROUP ALL",
+            )
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let unread: Option<usize> = result.take("unread")?;
+        let follow_ups: Option<usize> = result.take("follow_ups")?;
+        Ok((unread.unwrap_or(0), follow_ups.unwrap_or(0)))
+    }
+    
+    /// Copy a user's changed profile of a commenter into the inbox entries of their comments
+    pub async fn update_inbox_commenter(&self, profile: &CommenterProfile) -> Result<()> {
+        self.query("UPDATE inbox_items SET commenter_tags = $tags, commenter_notes = $notes WHERE user_id = $user_id AND author_channel_id = $channel_id")
+            .bind(("tags", &profile.tags))
+            .bind(("notes", &profile.notes))
+            .bind(("user_id", &profile.user_id))
+            .bind(("channel_id", &profile.channel_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get a user's inbox entries, newest first; unset filters match every entry
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_inbox_entries(
+        &self,
+        user_id: &str,
+        video_id: Option<&str>,
+        triage: Option<TriageState>,
+        tag: Option<&str>,
+        include_hidden: bool,
+        follow_ups_only: bool,
+        limit: usize,
+    ) -> Result<Vec<InboxEntry>> {
+        let mut result = queries::GET_INBOX
+            .run(self, |q| {
+                q.bind(("user_id", user_id))
+                    .bind(("video_id", video_id))
+                    .bind(("triage", triage))
+                    .bind(("tag", tag))
+                    .bind(("include_hidden", include_hidden))
+                    .bind(("follow_ups_only", follow_ups_only))
+                    .bind(("limit", limit))
+            })
+            .await?;
+        
+        let entries: Vec<InboxEntry> = result.take(0)?;
+        Ok(entries)
+    }
+    
+    // Saved reply methods
+    
+    /// Create or replace a saved reply template
+    pub async fn save_saved_reply(&self, template: &SavedReply) -> Result<()> {
+        self.delete_saved_reply(&template.template_id).await?;
+        
+        let _: Vec<SavedReply> = self.create("saved_replies")
+            .content(template)
+            .await
+            .with_context(|| format!("Failed to save reply template {}", template.template_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a saved reply template by ID
+    pub async fn get_saved_reply(&self, template_id: &str) -> Result<Option<SavedReply>> {
+        let mut result = self
+            .query("SELECT * FROM saved_replies WHERE template_id = $template_id LIMIT 1")
+            .bind(("template_id", template_id))
+            .await?;
+        
+        let template: Option<SavedReply> = result.take(0)?;
+        Ok(template)
+    }
+    
+    /// Get a user's saved reply templates, most used first
+    pub async fn get_saved_replies(&self, user_id: &str) -> Result<Vec<SavedReply>> {
+        let mut result = self
+            .query("SELECT * FROM saved_replies WHERE user_id = $user_id ORDER BY usage_count DESC, name ASC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let templates: Vec<SavedReply> = result.take(0)?;
+        Ok(templates)
+    }
+    
+    /// Delete a saved reply template, if stored
+    pub async fn delete_saved_reply(&self, template_id: &str) -> Result<()> {
+        self.query("DELETE FROM saved_replies WHERE template_id = $template_id")
+            .bind(("template_id", template_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Count a reply posted from a saved reply template
+    pub async fn record_saved_reply_use(&self, template_id: &str, at: DateTime<Utc>) -> Result<()> {
+        self.query("UPDATE saved_replies SET usage_count += 1, last_used_at = $at WHERE template_id = $template_id")
+            .bind(("template_id", template_id))
+            .bind(("at", at))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Runtime settings methods
+    
+    /// Store the runtime settings
+    pub async fn save_runtime_settings(&self, settings: &RuntimeSettings) -> Result<()> {
+        self.query("UPDATE type::thing('runtime_settings', 'current') CONTENT $settings")
+            .bind(("settings", settings))
+            .await
+            .context("Failed to save runtime settings")?;
+        
+        Ok(())
+    }
+    
+    /// Get the stored runtime settings, if they were ever changed
+    pub async fn get_runtime_settings(&self) -> Result<Option<RuntimeSettings>> {
+        let mut result = self
+            .query("SELECT * FROM type::thing('runtime_settings', 'current')")
+            .await?;
+        
+        let settings: Option<RuntimeSettings> = result.take(0)?;
+        Ok(settings)
+    }
+    
+    // Tone preset methods
+    
+    /// Create or replace a tone preset
+    pub async fn save_tone_preset(&self, tone: &TonePreset) -> Result<()> {
+        self.delete_tone_preset(&tone.tone_id).await?;
+        
+        let _: Vec<TonePreset> = self.create("tone_presets")
+            .content(tone)
+            .await
+            .with_context(|| format!("Failed to save tone {}", tone.tone_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a tone preset
+    pub async fn get_tone_preset(&self, tone_id: &str) -> Result<Option<TonePreset>> {
+        let mut result = self
+            .query("SELECT * FROM tone_presets WHERE tone_id = $tone_id LIMIT 1")
+            .bind(("tone_id", tone_id))
+            .await?;
+        
+        let tone: Option<TonePreset> = result.take(0)?;
+        Ok(tone)
+    }
+    
+    /// Get the built-in tone presets and the user's own, the built-ins first
+    pub async fn get_tone_presets(&self, user_id: &str) -> Result<Vec<TonePreset>> {
+        let mut result = self
+            .query("SELECT * FROM tone_presets WHERE user_id = NONE OR user_id = $user_id ORDER BY user_id, created_at")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let tones: Vec<TonePreset> = result.take(0)?;
+        Ok(tones)
+    }
+    
+    /// Delete a tone preset, if stored
+    pub async fn delete_tone_preset(&self, tone_id: &str) -> Result<()> {
+        self.query("DELETE FROM tone_presets WHERE tone_id = $tone_id")
+            .bind(("tone_id", tone_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Mute methods
+    
+    /// Store a new mute
+    pub async fn save_mute(&self, mute: &Mute) -> Result<()> {
+        let _: Vec<Mute> = self.create("mutes")
+            .content(mute)
+            .await
+            .with_context(|| format!("Failed to save mute {}", mute.mute_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a mute by ID
+    pub async fn get_mute(&self, mute_id: &str) -> Result<Option<Mute>> {
+        let mut result = self
+            .query("SELECT * FROM mutes WHERE mute_id = $mute_id LIMIT 1")
+            .bind(("mute_id", mute_id))
+            .await?;
+        
+        let mute: Option<Mute> = result.take(0)?;
+        Ok(mute)
+    }
+    
+    /// Get the user's mute of a video or commenter, if muted
+    pub async fn find_mute(&self, user_id: &str, kind: MuteKind, target_id: &str) -> Result<Option<Mute>> {
+        let mut result = self
+            .query("SELECT * FROM mutes WHERE user_id = $user_id AND kind = $kind AND target_id = $target_id LIMIT 1")
+            .bind(("user_id", user_id))
+            .bind(("kind", kind))
+            .bind(("target_id", target_id))
+            .await?;
+        
+        let mute: Option<Mute> = result.take(0)?;
+        Ok(mute)
+    }
+    
+    /// Get the videos and commenters a user muted, most recent first
+    pub async fn get_mutes(&self, user_id: &str) -> Result<Vec<Mute>> {
+        let mut result = self
+            .query("SELECT * FROM mutes WHERE user_id = $user_id ORDER BY created_at DESC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let mutes: Vec<Mute> = result.take(0)?;
+        Ok(mutes)
+    }
+    
+    /// Delete a mute, if stored
+    pub async fn delete_mute(&self, mute_id: &str) -> Result<()> {
+        self.query("DELETE FROM mutes WHERE mute_id = $mute_id")
+            .bind(("mute_id", mute_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Record how far a video's comments have been synced
+    pub async fn save_comment_sync_state(&self, state: &CommentSyncState) -> Result<()> {
+        self.query("UPDATE type::thing('comment_sync', $video_id) CONTENT $state")
+            .bind(("video_id", &state.video_id))
+            .bind(("state", state))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save the comment sync state of video {}", state.video_id))?;
+        
+        Ok(())
+    }
+    
+    /// How far a video's comments have been synced, if they ever were
+    pub async fn get_comment_sync_state(&self, video_id: &str) -> Result<Option<CommentSyncState>> {
+        let mut result = self
+            .query("SELECT * FROM type::thing('comment_sync', $video_id)")
+            .bind(("video_id", video_id))
+            .await?;
+        
+        let state: Option<CommentSyncState> = result.take(0)?;
+        Ok(state)
+    }
+    
+    /// Record how far the interaction history was published to the event stream
+    pub async fn save_stream_cursor(&self, cursor: &StreamCursor) -> Result<()> {
+        self.query("UPDATE type::thing('stream_cursor', 'current') CONTENT $cursor")
+            .bind(("cursor", cursor))
+            .await
+            .context("Failed to save the event stream cursor")?;
+        
+        Ok(())
+    }
+    
+    /// How far the interaction history was published to the event stream, if it ever was
+    pub async fn get_stream_cursor(&self) -> Result<Option<StreamCursor>> {
+        let mut result = self
+            .query("SELECT * FROM type::thing('stream_cursor', 'current')")
+            .await?;
+        
+        let cursor: Option<StreamCursor> = result.take(0)?;
+        Ok(cursor)
+    }
+    
+    // Prompt template methods
+    
+    /// Create or replace a prompt template
+    pub async fn save_prompt_template(&self, template: &PromptTemplate) -> Result<()> {
+        self.delete_prompt_template(template.kind, &template.name).await?;
+        
+        let _: Vec<PromptTemplate> = self.create("prompt_templates")
+            .content(template)
+            .await
+            .with_context(|| format!("Failed to save prompt template {}", template.name))?;
+        
+        Ok(())
+    }
+    
+    /// Get every stored prompt template
+    pub async fn get_prompt_templates(&self) -> Result<Vec<PromptTemplate>> {
+        let mut result = self
+            .query("SELECT * FROM prompt_templates ORDER BY kind, name")
+            .await?;
+        
+        let templates: Vec<PromptTemplate> = result.take(0)?;
+        Ok(templates)
+    }
+    
+    /// Delete a prompt template, if stored
+    pub async fn delete_prompt_template(&self, kind: PromptKind, name: &str) -> Result<()> {
+        self.query("DELETE FROM prompt_templates WHERE kind = $kind AND name = $name")
+            .bind(("kind", kind))
+            .bind(("name", name))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Video methods
+    
+    /// Create or update a video
+    pub async fn save_video(&self, video: &Video) -> Result<()> {
+        self.with_transaction("DELETE FROM videos WHERE video_id = $video_id; CREATE videos CONTENT $record;")
+            .bind(("video_id", &video.video_id))
+            .bind(("record", video))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save video {}", video.video_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a video by ID
+    pub async fn get_video(&self, video_id: &str) -> Result<Option<Video>> {
+        let mut result = self
+            .query("SELECT * FROM videos WHERE video_id = $video_id LIMIT 1")
+            .bind(("video_id", video_id))
+            .await?;
+        
+        let video: Option<Video> = result.take(0)?;
+        Ok(video)
+    }
+    
+    /// Get all stored videos for a user, newest first
+    pub async fn get_user_videos(&self, user_id: &str) -> Result<Vec<Video>> {
+        let mut result = self
+            .query("SELECT * FROM videos WHERE user_id = $user_id ORDER BY published_at DESC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let videos: Vec<Video> = result.take(0)?;
+        Ok(videos)
+    }
+    
+    /// Get a page of a user's stored videos, newest first, starting after `after`
+    pub async fn get_user_videos_page(&self, user_id: &str, after: Option<&VideoCursor>, limit: usize) -> Result<Vec<Video>> {
+        let mut result = match after {
+            Some(cursor) => {
+                self.query(
+                    "SELECT * FROM videos WHERE user_id = $user_id \
+                     AND (published_at < $published_at OR (published_at = $published_at AND video_id < $video_id)) \
+                     ORDER BY published_at DESC, video_id DESC LIMIT $limit",
+                )
+                .bind(("user_id", user_id))
+                .bind(("published_at", cursor.published_at))
+                .bind(("video_id", &cursor.video_id))
+                .bind(("limit", limit))
+                .await?
+            }
+            None => {
+                self.query("SELECT * FROM videos WHERE user_id = $user_id ORDER BY published_at DESC, video_id DESC LIMIT $limit")
+                    .bind(("user_id", user_id))
+                    .bind(("limit", limit))
+                    .await?
+            }
+        };
+        
+        let videos: Vec<Video> = result.take(0)?;
+        Ok(videos)
+    }
+    
+    /// When a user's videos were last refreshed from YouTube, if ever
+    pub async fn get_videos_synced_at(&self, user_id: &str) -> Result<Option<DateTime<Utc>>> {
+        let mut result = self
+            .query("SELECT synced_at FROM videos WHERE user_id = $user_id AND synced_at != NONE ORDER BY synced_at DESC LIMIT 1")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let synced_at: Option<DateTime<Utc>> = result.take("synced_at")?;
+        Ok(synced_at)
+    }
+    
+    /// Get the IDs of users that have at least one video
+    pub async fn get_video_owner_ids(&self) -> Result<Vec<String>> {
+        let mut result = self
+            .query("SELECT VALUE user_id FROM videos")
+            .await?;
+        
+        let mut user_ids: Vec<String> = result.take(0)?;
+        user_ids.sort();
+        user_ids.dedup();
+        Ok(user_ids)
+    }
+    
+    /// Get the IDs of users that have at least one monitored video
+    pub async fn get_monitored_user_ids(&self) -> Result<Vec<String>> {
+        let mut result = self
+            .query("SELECT VALUE user_id FROM videos WHERE monitor.enabled = true")
+            .await?;
+        
+        let mut user_ids: Vec<String> = result.take(0)?;
+        user_ids.sort();
+        user_ids.dedup();
+        Ok(user_ids)
+    }
+    
+    /// Get the IDs of users that posted a reply since the given time
+    pub async fn get_replying_user_ids(&self, since: DateTime<Utc>) -> Result<Vec<String>> {
+        let mut result = self
+            .query("SELECT VALUE user_id FROM interactions WHERE interaction_type = 'ReplyPosted' AND timestamp >= $since")
+            .bind(("since", since))
+            .await?;
+        
+        let mut user_ids: Vec<String> = result.take(0)?;
+        user_ids.sort();
+        user_ids.dedup();
+        Ok(user_ids)
+    }
+    
+    /// Update the monitor settings of a video
+    pub async fn update_video_monitor(&self, video_id: &str, monitor: &MonitorSettings) -> Result<()> {
+        self.query("UPDATE videos SET monitor = $monitor WHERE video_id = $video_id")
+            .bind(("video_id", video_id))
+            .bind(("monitor", monitor))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Record when the monitor last checked a video
+    pub async fn mark_video_checked(&self, video_id: &str, checked_at: DateTime<Utc>) -> Result<()> {
+        self.query("UPDATE videos SET last_checked_at = $checked_at WHERE video_id = $video_id")
+            .bind(("video_id", video_id))
+            .bind(("checked_at", checked_at))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Update the reply persona and tone of a video
+    pub async fn update_video_reply_defaults(&self, video_id: &str, reply_defaults: &ReplyDefaults) -> Result<()> {
+        self.query("UPDATE videos SET reply_defaults = $reply_defaults WHERE video_id = $video_id")
+            .bind(("video_id", video_id))
+            .bind(("reply_defaults", reply_defaults))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Move a video into a collection, or out of any with `None`
+    pub async fn set_video_collection(&self, video_id: &str, collection_id: Option<&str>) -> Result<()> {
+        self.query("UPDATE videos SET collection_id = $collection_id WHERE video_id = $video_id")
+            .bind(("video_id", video_id))
+            .bind(("collection_id", collection_id))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Get the videos of a collection, newest first
+    pub async fn get_collection_videos(&self, collection_id: &str) -> Result<Vec<Video>> {
+        let mut result = self
+            .query("SELECT * FROM videos WHERE collection_id = $collection_id ORDER BY published_at DESC")
+            .bind(("collection_id", collection_id))
+            .await?;
+        
+        let videos: Vec<Video> = result.take(0)?;
+        Ok(videos)
+    }
+    
+    // Collection methods
+    
+    /// Create or replace a video collection
+    pub async fn save_collection(&self, collection: &VideoCollection) -> Result<()> {
+        self.with_transaction("DELETE FROM collections WHERE collection_id = $collection_id; CREATE collections CONTENT $record;")
+            .bind(("collection_id", &collection.collection_id))
+            .bind(("record", collection))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save collection {}", collection.collection_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a video collection by ID
+    pub async fn get_collection(&self, collection_id: &str) -> Result<Option<VideoCollection>> {
+        let mut result = self
+            .query("SELECT * FROM collections WHERE collection_id = $collection_id LIMIT 1")
+            .bind(("collection_id", collection_id))
+            .await?;
+        
+        let collection: Option<VideoCollection> = result.take(0)?;
+        Ok(collection)
+    }
+    
+    /// Get a user's video collections by name
+    pub async fn get_collections(&self, user_id: &str) -> Result<Vec<VideoCollection>> {
+        let mut result = self
+            .query("SELECT * FROM collections WHERE user_id = $user_id ORDER BY name ASC")
+            .bind(("user_id", user_id))
+            .await?;
+        
+        let collections: Vec<VideoCollection> = result.take(0)?;
+        Ok(collections)
+    }
+    
+    /// Delete a video collection; its videos keep their settings but leave the collection
+    pub async fn delete_collection(&self, collection_id: &str) -> Result<()> {
+        self.with_transaction("UPDATE videos SET collection_id = NONE WHERE collection_id = $collection_id; DELETE FROM collections WHERE collection_id = $collection_id;")
+            .bind(("collection_id", collection_id))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to delete collection {}", collection_id))?;
+        
+        Ok(())
+    }
+    
+    // Job methods
+    
+    /// Create a new job
+    pub async fn create_job(&self, job: &Job) -> Result<()> {
+        let _: Vec<Job> = self.create("jobs")
+            .content(job)
+            .await
+            .with_context(|| format!("Failed to create job {}", job.job_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get a job by ID
+    pub async fn get_job(&self, job_id: &str) -> Result<Option<Job>> {
+        let mut result = self
+            .query("SELECT * FROM jobs WHERE job_id = $job_id LIMIT 1")
+            .bind(("job_id", job_id))
+            .await?;
+        
+        let job: Option<Job> = result.take(0)?;
+        Ok(job)
+    }
+    
+    /// Update a job's status, setting the finish time once it is no longer running
+    pub async fn set_job_status(&self, job_id: &str, status: JobStatus, error: Option<String>) -> Result<()> {
+        let now = Utc::now();
+        let finished_at = matches!(status, JobStatus::Completed | JobStatus::Failed).then_some(now);
+        
+        self.query("UPDATE jobs SET status = $status, error = $error, updated_at = $now, finished_at = $finished_at WHERE job_id = $job_id")
+            .bind(("job_id", job_id))
+            .bind(("status", status))
+            .bind(("error", error))
+            .bind(("now", now))
+            .bind(("finished_at", finished_at))
+            .await?;
+        
+        Ok(())
+    }
+    
+    /// Append an item result to a job and advance its counters
+    pub async fn record_job_item(&self, job_id: &str, item: &JobItemResult) -> Result<()> {
+        let (succeeded, failed) = if item.success { (1, 0) } else { (0, 1) };
+        
+        self.query("UPDATE jobs SET results += $item, succeeded += $succeeded, failed += $failed, updated_at = $now WHERE job_id = $job_id")
+            .bind(("job_id", job_id))
+            .bind(("item", item))
+            .bind(("succeeded", succeeded))
+            .bind(("failed", failed))
+            .bind(("now", Utc::now()))
+            .await?;
+        
+        Ok(())
+    }
+    
+    // Analytics methods
+    
+    /// Save a daily analytics rollup, replacing any previous one for the same user and day
+    pub async fn save_analytics_rollup(&self, rollup: &DailyRollup) -> Result<()> {
+        self.with_transaction("DELETE FROM analytics_daily WHERE user_id = $user_id AND day = $day; CREATE analytics_daily CONTENT $record;")
+            .bind(("user_id", &rollup.user_id))
+            .bind(("day", rollup.day))
+            .bind(("record", rollup))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save analytics rollup for {} on {}", rollup.user_id, rollup.day))?;
+        
+        Ok(())
+    }
+    
+    /// Get a user's stored daily analytics rollups for `from..=to`, oldest first
+    pub async fn get_analytics_rollups(&self, user_id: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyRollup>> {
+        let mut result = self
+            .query("SELECT * FROM analytics_daily WHERE user_id = $user_id AND day >= $from AND day <= $to ORDER BY day")
+            .bind(("user_id", user_id))
+            .bind(("from", from))
+            .bind(("to", to))
+            .await?;
+        
+        let rollups: Vec<DailyRollup> = result.take(0)?;
+        Ok(rollups)
+    }
+    
+    /// Save keyword stats, replacing any previous stats for the same user and video (or channel)
+    pub async fn save_keyword_stats(&self, stats: &KeywordStats) -> Result<()> {
+        self.with_transaction("DELETE FROM keyword_stats WHERE user_id = $user_id AND video_id = $video_id; CREATE keyword_stats CONTENT $record;")
+            .bind(("user_id", &stats.user_id))
+            .bind(("video_id", &stats.video_id))
+            .bind(("record", stats))
+            .await?
+            .check()
+            .with_context(|| format!("Failed to save keyword stats for user {}", stats.user_id))?;
+        
+        Ok(())
+    }
+    
+    /// Get keyword stats for a video, or for the whole channel if `video_id` is `None`
+    pub async fn get_keyword_stats(&self, user_id: &str, video_id: Option<&str>) -> Result<Option<KeywordStats>> {
+        let mut result = self
+            .query("SELECT * FROM keyword_stats WHERE user_id = $user_id AND video_id = $video_id LIMIT 1")
+            .bind(("user_id", user_id))
+            .bind(("video_id", video_id))
+            .await?;
+        
+        let stats: Option<KeywordStats> = result.take(0)?;
+        Ok(stats)
     }
 }
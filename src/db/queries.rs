@@ -0,0 +1,132 @@
+//! Named, parameterized statements for the hot database paths.
+//!
+//! Each statement is parsed once on first use and reused afterwards, instead of
+//! sending the SurrealQL string to be parsed again on every call. Calls, errors
+//! and cumulative latency are tracked per statement and exported on `/metrics`.
+
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use surrealdb::engine::any::Any;
+use surrealdb::method::Query;
+use surrealdb::sql;
+
+use super::Database;
+
+/// A SurrealQL statement parsed once and reused
+pub struct Statement {
+    name: &'static str,
+    sql: &'static str,
+    parsed: OnceLock<sql::Query>,
+    calls: AtomicU64,
+    errors: AtomicU64,
+    total_micros: AtomicU64,
+}
+
+impl Statement {
+    const fn new(name: &'static str, sql: &'static str) -> Self {
+        Self {
+            name,
+            sql,
+            parsed: OnceLock::new(),
+            calls: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            total_micros: AtomicU64::new(0),
+        }
+    }
+
+    /// The parsed statement; the SQL is a constant, so a parse failure is a bug
+    fn parsed(&self) -> sql::Query {
+        self.parsed
+            .get_or_init(|| {
+                sql::parse(self.sql).unwrap_or_else(|e| panic!("Invalid statement {}: {}", self.name, e))
+            })
+            .clone()
+    }
+
+    /// Run the statement with the parameters `bind` adds, recording its latency
+    pub async fn run<'a>(
+        &self,
+        db: &'a Database,
+        bind: impl FnOnce(Query<'a, Any>) -> Query<'a, Any>,
+    ) -> surrealdb::Result<surrealdb::Response> {
+        let start = Instant::now();
+        let result = bind(db.query(self.parsed())).await;
+
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(start.elapsed().as_micros() as u64, Ordering::Relaxed);
+        if result.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+
+        result
+    }
+}
+
+/// Look up a comment by its YouTube ID
+pub static GET_COMMENT: Statement =
+    Statement::new("get_comment", "SELECT * FROM comments WHERE comment_id = $comment_id LIMIT 1");
+
+/// Look up a user's OAuth token
+pub static GET_AUTH_TOKEN: Statement =
+    Statement::new("get_auth_token", "SELECT * FROM auth_tokens WHERE user_id = $user_id LIMIT 1");
+
+/// Look up a session by ID
+pub static GET_SESSION: Statement =
+    Statement::new("get_session", "SELECT * FROM type::thing('sessions', $session_id)");
+
+/// Store an interaction record
+pub static RECORD_INTERACTION: Statement =
+    Statement::new("record_interaction", "CREATE interactions CONTENT $interaction");
+
+/// List a user's inbox entries, filtered by video, triage state and commenter tag when set,
+/// leaving out muted videos and commenters
+pub static GET_INBOX: Statement = Statement::new(
+    "get_inbox",
+    "SELECT * FROM inbox_items WHERE user_id = $user_id \
+     AND ($video_id = NONE OR video_id = $video_id) \
+     AND ($triage = NONE OR triage = $triage) \
+     AND ($tag = NONE OR commenter_tags CONTAINS $tag) \
+     AND ($include_hidden OR hidden = false) \
+     AND (!$follow_ups_only OR comment.follow_up != NONE) \
+     AND video_id NOTINSIDE (SELECT VALUE target_id FROM mutes WHERE user_id = $user_id AND kind = 'video') \
+     AND author_channel_id NOTINSIDE (SELECT VALUE target_id FROM mutes WHERE user_id = $user_id AND kind = 'commenter') \
+     ORDER BY published_at DESC LIMIT $limit",
+);
+
+const STATEMENTS: [&Statement; 5] = [&GET_COMMENT, &GET_AUTH_TOKEN, &GET_SESSION, &RECORD_INTERACTION, &GET_INBOX];
+
+/// Render per-statement call counts, errors and latency in the Prometheus text format
+pub fn render_metrics() -> String {
+    let mut out = String::new();
+    out.push_str("# TYPE db_statement_calls_total counter\n");
+    out.push_str("# TYPE db_statement_errors_total counter\n");
+    out.push_str("# TYPE db_statement_duration_seconds_total counter\n");
+
+    for statement in STATEMENTS {
+        let _ = writeln!(out, "db_statement_calls_total{{statement=\"{}\"}} {}", statement.name, statement.calls.load(Ordering::Relaxed));
+        let _ = writeln!(out, "db_statement_errors_total{{statement=\"{}\"}} {}", statement.name, statement.errors.load(Ordering::Relaxed));
+        let _ = writeln!(
+            out,
+            "db_statement_duration_seconds_total{{statement=\"{}\"}} {}",
+            statement.name,
+            statement.total_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0
+        );
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_statements_parse() {
+        for statement in STATEMENTS {
+            assert!(sql::parse(statement.sql).is_ok(), "{} doesn't parse", statement.name);
+        }
+    }
+}
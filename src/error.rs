@@ -0,0 +1,174 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use tracing::{error, warn};
+
+use crate::i18n::{self, Locale, Message};
+
+/// Errors surfaced to API clients, each with its own HTTP status and error code.
+///
+/// Services still return `anyhow::Result` internally; they raise the variants that
+/// callers must tell apart (`return Err(AppError::NotFound(..).into())`), and the
+/// conversion from `anyhow::Error` recovers them at the handler boundary. Anything
+/// else becomes `Internal`.
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// The resource doesn't exist or belongs to another user
+    #[error("{0} not found")]
+    NotFound(String),
+
+    /// No valid session
+    #[error("Not signed in")]
+    Unauthorized,
+
+    /// Signed in, or not, but not allowed to do this
+    #[error("Forbidden")]
+    Forbidden,
+
+    /// The YouTube API quota is used up
+    #[error("YouTube quota exceeded: {0}")]
+    UpstreamQuota(String),
+
+    /// Google rejected the user's credentials; they need to sign in again
+    #[error("Google authorization failed: {0}")]
+    UpstreamAuth(String),
+
+    /// The request is malformed or out of range
+    #[error("{0}")]
+    Validation(String),
+
+    /// The request is well-formed but a value in it can't be understood, e.g. a link that isn't to a video
+    #[error("{0}")]
+    Unprocessable(String),
+
+    /// The request doesn't fit the resource's current state, e.g. a reply that was already sent
+    #[error("{0}")]
+    Conflict(String),
+
+    /// A database query failed
+    #[error("Database error: {0}")]
+    Db(#[from] surrealdb::Error),
+
+    /// The feature is switched off for now, e.g. by an operator
+    #[error("Temporarily unavailable: {0}")]
+    Unavailable(String),
+
+    /// The AI provider failed to generate a reply
+    #[error("AI provider error: {0}")]
+    AiProvider(String),
+
+    /// Any other failure
+    #[error(transparent)]
+    Internal(anyhow::Error),
+}
+
+/// Result type for handlers
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    /// HTTP status the error is answered with
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Unauthorized => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden => StatusCode::FORBIDDEN,
+            AppError::UpstreamQuota(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::UpstreamAuth(_) => StatusCode::UNAUTHORIZED,
+            AppError::Validation(_) => StatusCode::BAD_REQUEST,
+            AppError::Unprocessable(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::Db(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::AiProvider(_) => StatusCode::BAD_GATEWAY,
+        }
+    }
+
+    /// Stable, machine-readable error code for clients
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Unauthorized => "unauthorized",
+            AppError::Forbidden => "forbidden",
+            AppError::UpstreamQuota(_) => "upstream_quota",
+            AppError::UpstreamAuth(_) => "upstream_auth",
+            AppError::Validation(_) => "validation",
+            AppError::Unprocessable(_) => "unprocessable",
+            AppError::Conflict(_) => "conflict",
+            AppError::Db(_) => "database",
+            AppError::Unavailable(_) => "unavailable",
+            AppError::AiProvider(_) => "ai_provider",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    /// The message shown to clients, in their language.
+    ///
+    /// Internal details are never included; validation, unprocessable and conflict messages are English only.
+    pub fn client_message(&self, locale: Locale) -> String {
+        match self {
+            AppError::NotFound(what) => i18n::text(locale, Message::ErrorNotFound, &[("what", what)]),
+            AppError::Unauthorized => i18n::text(locale, Message::ErrorUnauthorized, &[]),
+            AppError::Forbidden => i18n::text(locale, Message::ErrorForbidden, &[]),
+            AppError::UpstreamQuota(detail) => i18n::text(locale, Message::ErrorUpstreamQuota, &[("detail", detail)]),
+            AppError::UpstreamAuth(detail) => i18n::text(locale, Message::ErrorUpstreamAuth, &[("detail", detail)]),
+            AppError::Validation(message) | AppError::Unprocessable(message) | AppError::Conflict(message) => message.clone(),
+            AppError::Unavailable(detail) => i18n::text(locale, Message::ErrorUnavailable, &[("detail", detail)]),
+            AppError::AiProvider(detail) => i18n::text(locale, Message::ErrorAiProvider, &[("detail", detail)]),
+            AppError::Db(_) | AppError::Internal(_) => i18n::text(locale, Message::ErrorInternal, &[]),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(error: anyhow::Error) -> Self {
+        // Downcasting also looks through `.context(..)` wrappers
+        let error = match error.downcast::<AppError>() {
+            Ok(app_error) => return app_error,
+            Err(error) => error,
+        };
+
+        match error.downcast::<surrealdb::Error>() {
+            Ok(db_error) => AppError::Db(db_error),
+            Err(error) => AppError::Internal(error),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+
+        // Internal details stay in the logs
+        if status.is_server_error() {
+            error!("{}: {:#}", self.code(), self);
+        } else {
+            warn!("{}: {}", self.code(), self);
+        }
+        let message = self.client_message(i18n::current_locale());
+
+        let body = json!({ "error": { "code": self.code(), "message": message } });
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_from_anyhow_recovers_variant() {
+        let error: anyhow::Error = AppError::UpstreamQuota("daily limit".to_string()).into();
+        let error = Err::<(), _>(error).context("Failed to fetch comments").unwrap_err();
+
+        let app_error = AppError::from(error);
+        assert_eq!(app_error.code(), "upstream_quota");
+        assert_eq!(app_error.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let other = AppError::from(anyhow::anyhow!("boom"));
+        assert_eq!(other.code(), "internal");
+    }
+}
@@ -0,0 +1,310 @@
+//! Translations of user-facing strings: notifications, digests and API error messages.
+//!
+//! Notifications use the recipient's `language` preference. API errors use the
+//! request's `Accept-Language` header, which [`scope_locale`] makes available to
+//! [`current_locale`] for the rest of the request. Missing or unsupported
+//! languages fall back to English.
+
+use axum::{extract::Request, http::header, middleware::Next, response::Response};
+use serde::{Deserialize, Serialize};
+
+/// A supported language for user-facing strings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+    Fr,
+    De,
+    Pt,
+}
+
+impl Locale {
+    /// Parse a language tag such as `es` or `pt-BR`; only the primary subtag is used
+    pub fn parse(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match primary.as_str() {
+            "en" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            "fr" => Some(Locale::Fr),
+            "de" => Some(Locale::De),
+            "pt" => Some(Locale::Pt),
+            _ => None,
+        }
+    }
+
+    /// The locale for an optional language preference
+    pub fn from_preference(language: Option<&str>) -> Self {
+        language.and_then(Locale::parse).unwrap_or_default()
+    }
+
+    /// The first supported language in an `Accept-Language` header, in the client's order of preference
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut languages: Vec<(&str, f32)> = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse().ok())
+                    .unwrap_or(1.0);
+                Some((tag, quality))
+            })
+            .collect();
+
+        // Stable, so equally weighted languages keep the header's order
+        languages.sort_by(|a, b| b.1.total_cmp(&a.1));
+        languages.into_iter().find_map(|(tag, _)| Locale::parse(tag)).unwrap_or_default()
+    }
+}
+
+/// A user-facing string; placeholders in braces are filled in by [`text`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    NewCommentSubject,
+    MoreCommentsSubject,
+    MoreCommentsBody,
+    QuestionsSubject,
+    QuestionsBody,
+    SpikeSubject,
+    SpikeBody,
+    AutoReplyFailedSubject,
+    AutoReplyFailedBody,
+    DigestSubject,
+    DigestBody,
+    AlertSubject,
+    AlertBody,
+    RepliesRemovedSubject,
+    RepliesRemovedBody,
+    ErrorNotFound,
+    ErrorUnauthorized,
+    ErrorForbidden,
+    ErrorUpstreamQuota,
+    ErrorUpstreamAuth,
+    ErrorUnavailable,
+    ErrorAiProvider,
+    ErrorInternal,
+}
+
+/// The catalog entry for a message
+fn template(locale: Locale, message: Message) -> &'static str {
+    use Locale::*;
+    use Message::*;
+
+    match (message, locale) {
+        (NewCommentSubject, En) => "New comment from {author} on \"{title}\"",
+        (NewCommentSubject, Es) => "Nuevo comentario de {author} en \"{title}\"",
+        (NewCommentSubject, Fr) => "Nouveau commentaire de {author} sur « {title} »",
+        (NewCommentSubject, De) => "Neuer Kommentar von {author} zu „{title}“",
+        (NewCommentSubject, Pt) => "Novo comentário de {author} em \"{title}\"",
+
+        (MoreCommentsSubject, En) => "{count} more new comments on \"{title}\"",
+        (MoreCommentsSubject, Es) => "{count} comentarios nuevos más en \"{title}\"",
+        (MoreCommentsSubject, Fr) => "{count} nouveaux commentaires de plus sur « {title} »",
+        (MoreCommentsSubject, De) => "{count} weitere neue Kommentare zu „{title}“",
+        (MoreCommentsSubject, Pt) => "Mais {count} comentários novos em \"{title}\"",
+
+        (MoreCommentsBody, En) => "Open the review queue to see them all.",
+        (MoreCommentsBody, Es) => "Abre la cola de revisión para verlos todos.",
+        (MoreCommentsBody, Fr) => "Ouvrez la file de relecture pour tous les voir.",
+        (MoreCommentsBody, De) => "Öffne die Prüfliste, um alle zu sehen.",
+        (MoreCommentsBody, Pt) => "Abra a fila de revisão para ver todos.",
+
+        (QuestionsSubject, En) => "{count} new question(s) on \"{title}\"",
+        (QuestionsSubject, Es) => "{count} pregunta(s) nueva(s) en \"{title}\"",
+        (QuestionsSubject, Fr) => "{count} nouvelle(s) question(s) sur « {title} »",
+        (QuestionsSubject, De) => "{count} neue Frage(n) zu „{title}“",
+        (QuestionsSubject, Pt) => "{count} pergunta(s) nova(s) em \"{title}\"",
+
+        (QuestionsBody, En) => "New comments on \"{title}\" are waiting for an answer:\n\n{questions}",
+        (QuestionsBody, Es) => "Hay comentarios nuevos en \"{title}\" esperando respuesta:\n\n{questions}",
+        (QuestionsBody, Fr) => "De nouveaux commentaires sur « {title} » attendent une réponse :\n\n{questions}",
+        (QuestionsBody, De) => "Neue Kommentare zu „{title}“ warten auf eine Antwort:\n\n{questions}",
+        (QuestionsBody, Pt) => "Novos comentários em \"{title}\" estão aguardando resposta:\n\n{questions}",
+
+        (SpikeSubject, En) => "Negative comments spiking on \"{title}\"",
+        (SpikeSubject, Es) => "Aumento de comentarios negativos en \"{title}\"",
+        (SpikeSubject, Fr) => "Pic de commentaires négatifs sur « {title} »",
+        (SpikeSubject, De) => "Häufung negativer Kommentare zu „{title}“",
+        (SpikeSubject, Pt) => "Aumento de comentários negativos em \"{title}\"",
+
+        (SpikeBody, En) => "{percent}% of the {count} newest comments on \"{title}\" are negative.",
+        (SpikeBody, Es) => "El {percent}% de los {count} comentarios más recientes en \"{title}\" son negativos.",
+        (SpikeBody, Fr) => "{percent} % des {count} commentaires les plus récents sur « {title} » sont négatifs.",
+        (SpikeBody, De) => "{percent} % der {count} neuesten Kommentare zu „{title}“ sind negativ.",
+        (SpikeBody, Pt) => "{percent}% dos {count} comentários mais recentes em \"{title}\" são negativos.",
+
+        (AutoReplyFailedSubject, En) => "A reply could not be posted",
+        (AutoReplyFailedSubject, Es) => "No se pudo publicar una respuesta",
+        (AutoReplyFailedSubject, Fr) => "Une réponse n'a pas pu être publiée",
+        (AutoReplyFailedSubject, De) => "Eine Antwort konnte nicht veröffentlicht werden",
+        (AutoReplyFailedSubject, Pt) => "Não foi possível publicar uma resposta",
+
+        (AutoReplyFailedBody, En) => "The reply to comment {comment_id} could not be posted: {reason}",
+        (AutoReplyFailedBody, Es) => "No se pudo publicar la respuesta al comentario {comment_id}: {reason}",
+        (AutoReplyFailedBody, Fr) => "La réponse au commentaire {comment_id} n'a pas pu être publiée : {reason}",
+        (AutoReplyFailedBody, De) => "Die Antwort auf Kommentar {comment_id} konnte nicht veröffentlicht werden: {reason}",
+        (AutoReplyFailedBody, Pt) => "Não foi possível publicar a resposta ao comentário {comment_id}: {reason}",
+
+        (DigestSubject, En) => "Your daily comment digest: {count} new comment(s)",
+        (DigestSubject, Es) => "Tu resumen diario de comentarios: {count} comentario(s) nuevo(s)",
+        (DigestSubject, Fr) => "Votre résumé quotidien : {count} nouveau(x) commentaire(s)",
+        (DigestSubject, De) => "Deine tägliche Kommentarübersicht: {count} neue(r) Kommentar(e)",
+        (DigestSubject, Pt) => "Seu resumo diário de comentários: {count} comentário(s) novo(s)",
+
+        (DigestBody, En) => "In the last 24 hours:\n\n- New comments: {new}\n- Comments without a reply: {unanswered}\n- Drafts waiting for review: {pending}",
+        (DigestBody, Es) => "En las últimas 24 horas:\n\n- Comentarios nuevos: {new}\n- Comentarios sin respuesta: {unanswered}\n- Borradores pendientes de revisión: {pending}",
+        (DigestBody, Fr) => "Au cours des dernières 24 heures :\n\n- Nouveaux commentaires : {new}\n- Commentaires sans réponse : {unanswered}\n- Brouillons à relire : {pending}",
+        (DigestBody, De) => "In den letzten 24 Stunden:\n\n- Neue Kommentare: {new}\n- Kommentare ohne Antwort: {unanswered}\n- Entwürfe zur Prüfung: {pending}",
+        (DigestBody, Pt) => "Nas últimas 24 horas:\n\n- Comentários novos: {new}\n- Comentários sem resposta: {unanswered}\n- Rascunhos aguardando revisão: {pending}",
+
+        (AlertSubject, En) => "Alert \"{rule}\": {author} on \"{title}\"",
+        (AlertSubject, Es) => "Alerta \"{rule}\": {author} en \"{title}\"",
+        (AlertSubject, Fr) => "Alerte « {rule} » : {author} sur « {title} »",
+        (AlertSubject, De) => "Alarm „{rule}“: {author} zu „{title}“",
+        (AlertSubject, Pt) => "Alerta \"{rule}\": {author} em \"{title}\"",
+
+        (AlertBody, En) => "{text}\n\nMatched: {trigger}",
+        (AlertBody, Es) => "{text}\n\nCoincidencia: {trigger}",
+        (AlertBody, Fr) => "{text}\n\nCorrespondance : {trigger}",
+        (AlertBody, De) => "{text}\n\nTreffer: {trigger}",
+        (AlertBody, Pt) => "{text}\n\nCorrespondência: {trigger}",
+
+        (RepliesRemovedSubject, En) => "{count} of your replies are no longer on YouTube",
+        (RepliesRemovedSubject, Es) => "{count} de tus respuestas ya no están en YouTube",
+        (RepliesRemovedSubject, Fr) => "{count} de vos réponses ne sont plus sur YouTube",
+        (RepliesRemovedSubject, De) => "{count} deiner Antworten sind nicht mehr auf YouTube",
+        (RepliesRemovedSubject, Pt) => "{count} das suas respostas não estão mais no YouTube",
+
+        (RepliesRemovedBody, En) => "YouTube no longer shows these replies, most likely because its spam filter removed them:\n\n{replies}\n\nReplies with links are removed most often. The comments are back in your inbox.",
+        (RepliesRemovedBody, Es) => "YouTube ya no muestra estas respuestas, probablemente porque su filtro de spam las eliminó:\n\n{replies}\n\nLas respuestas con enlaces se eliminan con más frecuencia. Los comentarios vuelven a estar en tu bandeja de entrada.",
+        (RepliesRemovedBody, Fr) => "YouTube n'affiche plus ces réponses, probablement supprimées par son filtre anti-spam :\n\n{replies}\n\nLes réponses contenant des liens sont le plus souvent supprimées. Les commentaires sont de retour dans votre boîte de réception.",
+        (RepliesRemovedBody, De) => "YouTube zeigt diese Antworten nicht mehr an, vermutlich hat der Spamfilter sie entfernt:\n\n{replies}\n\nAntworten mit Links werden am häufigsten entfernt. Die Kommentare sind wieder in deinem Posteingang.",
+        (RepliesRemovedBody, Pt) => "O YouTube não mostra mais estas respostas, provavelmente porque o filtro de spam as removeu:\n\n{replies}\n\nRespostas com links são removidas com mais frequência. Os comentários estão de volta na sua caixa de entrada.",
+
+        (ErrorNotFound, En) => "{what} not found",
+        (ErrorNotFound, Es) => "No se encontró {what}",
+        (ErrorNotFound, Fr) => "{what} introuvable",
+        (ErrorNotFound, De) => "{what} nicht gefunden",
+        (ErrorNotFound, Pt) => "{what} não encontrado",
+
+        (ErrorUnauthorized, En) => "Not signed in",
+        (ErrorUnauthorized, Es) => "No has iniciado sesión",
+        (ErrorUnauthorized, Fr) => "Vous n'êtes pas connecté",
+        (ErrorUnauthorized, De) => "Nicht angemeldet",
+        (ErrorUnauthorized, Pt) => "Você não está conectado",
+
+        (ErrorForbidden, En) => "Forbidden",
+        (ErrorForbidden, Es) => "Acceso denegado",
+        (ErrorForbidden, Fr) => "Accès refusé",
+        (ErrorForbidden, De) => "Zugriff verweigert",
+        (ErrorForbidden, Pt) => "Acesso negado",
+
+        (ErrorUpstreamQuota, En) => "YouTube quota exceeded: {detail}",
+        (ErrorUpstreamQuota, Es) => "Cuota de YouTube agotada: {detail}",
+        (ErrorUpstreamQuota, Fr) => "Quota YouTube dépassé : {detail}",
+        (ErrorUpstreamQuota, De) => "YouTube-Kontingent überschritten: {detail}",
+        (ErrorUpstreamQuota, Pt) => "Cota do YouTube excedida: {detail}",
+
+        (ErrorUpstreamAuth, En) => "Google authorization failed: {detail}",
+        (ErrorUpstreamAuth, Es) => "La autorización de Google falló, vuelve a iniciar sesión: {detail}",
+        (ErrorUpstreamAuth, Fr) => "L'autorisation Google a échoué, reconnectez-vous : {detail}",
+        (ErrorUpstreamAuth, De) => "Google-Autorisierung fehlgeschlagen, bitte erneut anmelden: {detail}",
+        (ErrorUpstreamAuth, Pt) => "A autorização do Google falhou, entre novamente: {detail}",
+
+        (ErrorUnavailable, En) => "Temporarily unavailable: {detail}",
+        (ErrorUnavailable, Es) => "No disponible temporalmente: {detail}",
+        (ErrorUnavailable, Fr) => "Temporairement indisponible : {detail}",
+        (ErrorUnavailable, De) => "Vorübergehend nicht verfügbar: {detail}",
+        (ErrorUnavailable, Pt) => "Temporariamente indisponível: {detail}",
+
+        (ErrorAiProvider, En) => "AI provider error: {detail}",
+        (ErrorAiProvider, Es) => "Error del proveedor de IA: {detail}",
+        (ErrorAiProvider, Fr) => "Erreur du fournisseur d'IA : {detail}",
+        (ErrorAiProvider, De) => "Fehler des KI-Anbieters: {detail}",
+        (ErrorAiProvider, Pt) => "Erro do provedor de IA: {detail}",
+
+        (ErrorInternal, En) => "Internal server error",
+        (ErrorInternal, Es) => "Error interno del servidor",
+        (ErrorInternal, Fr) => "Erreur interne du serveur",
+        (ErrorInternal, De) => "Interner Serverfehler",
+        (ErrorInternal, Pt) => "Erro interno do servidor",
+    }
+}
+
+/// A message in a locale, with `{name}` placeholders replaced by the given values
+pub fn text(locale: Locale, message: Message, args: &[(&str, &dyn std::fmt::Display)]) -> String {
+    args.iter().fold(template(locale, message).to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), &value.to_string())
+    })
+}
+
+/// English name of a language tag, for telling the AI which language to write in.
+///
+/// Unknown tags are passed through, so any language the model knows can be requested.
+pub fn language_name(tag: &str) -> &str {
+    match tag.trim().split(['-', '_']).next().unwrap_or_default().to_ascii_lowercase().as_str() {
+        "en" => "English",
+        "es" => "Spanish",
+        "fr" => "French",
+        "de" => "German",
+        "pt" => "Portuguese",
+        "it" => "Italian",
+        "nl" => "Dutch",
+        "pl" => "Polish",
+        "tr" => "Turkish",
+        "ru" => "Russian",
+        "ar" => "Arabic",
+        "hi" => "Hindi",
+        "bn" => "Bengali",
+        "ur" => "Urdu",
+        "id" => "Indonesian",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "zh" => "Chinese",
+        _ => tag,
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_LOCALE: Locale;
+}
+
+/// The locale of the request being handled, or English outside a request
+pub fn current_locale() -> Locale {
+    REQUEST_LOCALE.try_with(|locale| *locale).unwrap_or_default()
+}
+
+/// Middleware making the request's `Accept-Language` available to [`current_locale`]
+pub async fn scope_locale(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|v| v.to_str().ok())
+        .map(Locale::from_accept_language)
+        .unwrap_or_default();
+
+    REQUEST_LOCALE.scope(locale, next.run(request)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_negotiation() {
+        assert_eq!(Locale::parse("pt-BR"), Some(Locale::Pt));
+        assert_eq!(Locale::parse("ja"), None);
+        assert_eq!(Locale::from_accept_language("ja, fr-CH;q=0.8, de;q=0.9"), Locale::De);
+        assert_eq!(Locale::from_accept_language("ja"), Locale::En);
+        assert_eq!(Locale::from_preference(None), Locale::En);
+    }
+
+    #[test]
+    fn test_text_fills_placeholders() {
+        let text = text(Locale::Es, Message::NewCommentSubject, &[("author", &"Ana"), ("title", &"Drones")]);
+        assert_eq!(text, "Nuevo comentario de Ana en \"Drones\"");
+    }
+}
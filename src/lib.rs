@@ -0,0 +1,15 @@
+//! YouTube Commenter API server.
+//!
+//! The server binary lives in `main.rs`; the modules are exposed as a library so
+//! benchmarks and integration tests can exercise them directly.
+
+// `surrealdb::Error` is large and `AppError` wraps it; boxing it everywhere isn't worth it
+#![allow(clippy::result_large_err)]
+
+pub mod api;
+pub mod db;
+pub mod error;
+pub mod i18n;
+pub mod models;
+pub mod services;
+pub mod utils;
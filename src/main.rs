@@ -1,57 +1,177 @@
-mod api;
-mod db;
-mod models;
-mod services;
-mod utils;
-
 use anyhow::Result;
-use axum::{
-    routing::{get, post},
-    Router,
-};
 use dotenv::dotenv;
 use std::net::SocketAddr;
 use std::sync::Arc;
+use axum::http::HeaderName;
+use tower_http::catch_panic::CatchPanicLayer;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::{DefaultOnResponse, TraceLayer};
+use tower_http::LatencyUnit;
+use tracing::{info, Level};
 
+use youtube_commenter::{api, db, services, utils};
+use youtube_commenter::models::preflight::CheckStatus;
 use api::handlers::AppState;
-use services::{auth::AuthService, youtube::YouTubeService, ai::AiService};
+use utils::http::HttpConfig;
+use utils::error_reporting;
+use utils::http_log::HttpLog;
+use utils::logging::{self, REQUEST_ID_HEADER};
+use utils::upstream::Upstreams;
+use services::{auth::AuthService, youtube::YouTubeService, ai::AiService, jobs::JobService, analytics::AnalyticsService, collections::CollectionService, commenters::CommenterService, quota::QuotaTracker, dashboard::DashboardService, duplicates::DuplicateService, events::EventBus, inbox::InboxProjection, live::LiveFeed, model_catalog::ModelSeed, monitor::CommentMonitor, mutes::MuteService, notifications::NotificationService, outbox::Outbox, preflight::{Preflight, PreflightMode}, prompts::PromptLibrary, reply_checks::ReplyChecker, retention::{Pruner, RetentionConfig}, rules::RuleService, saved_replies::SavedReplyService, settings::SettingsService, sharing::ShareLinks, spam::SpamService, stream::StreamSink, tones::ToneService};
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Load environment variables
     dotenv().ok();
 
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Report errors to Sentry if configured; before logging so the tracing integration has a client
+    let _sentry_guard = error_reporting::init();
+    
+    // Initialize tracing; the guard flushes file output on shutdown
+    let _log_guard = logging::init()?;
 
     info!("Starting YouTube Commenter API server");
 
-    // Initialize database
-    let db = db::init_db().await?;
+    // Initialize database: on the server SURREALDB_URL names, else in memory or in the data directory SURREALDB_PATH names
+    let db = db::open(&db::Storage::from_env()?).await?;
+    
+    // One HTTP client for all outgoing requests, so connections are pooled and timeouts are consistent
+    let http_config = HttpConfig::from_env()?;
+    let http_client = http_config.build_client()?;
+    
+    // Per-upstream timeouts, retries and circuit breakers, so one slow API can't tie up every worker
+    let upstreams = Upstreams::from_env()?;
+    // Upstreams with their own proxy setting get their own client
+    let google_client = http_config.client_for(&http_client, &upstreams.google_oauth)?;
+    let youtube_client = http_config.client_for(&http_client, &upstreams.youtube)?;
+    let openai_client = http_config.client_for(&http_client, &upstreams.openai)?;
     
     // Initialize services
-    let auth_service = Arc::new(AuthService::new(db.clone())?);
-    let youtube_service = Arc::new(YouTubeService::new(db.clone(), auth_service.clone()));
-    let ai_service = Arc::new(AiService::new(db.clone()));
+    let notification_service = Arc::new(NotificationService::new(db.clone(), http_client.clone())?);
+    
+    // Check the configuration and what the services depend on, so a missing key shows up now rather than on first use
+    let preflight = Preflight { db: &db, openai_client: &openai_client, notifications: &notification_service }.run().await;
+    if preflight.has_failures() && PreflightMode::from_env()? == PreflightMode::Strict {
+        let failed: Vec<&str> = preflight.problems()
+            .filter(|check| check.status == CheckStatus::Failed)
+            .map(|check| check.name.as_str())
+            .collect();
+        anyhow::bail!("Preflight checks failed: {} (set PREFLIGHT_MODE=degraded to start anyway)", failed.join(", "));
+    }
+    
+    let auth_service = Arc::new(AuthService::new(db.clone(), google_client, upstreams.google_oauth.clone())?);
+    let quota_tracker = Arc::new(QuotaTracker::new(db.clone()));
+    
+    // Operator overrides stored through the admin API
+    let settings = Arc::new(SettingsService::new(db.clone()));
+    settings.load().await?;
+    let spam = Arc::new(SpamService::new(db.clone()));
+    // What services do, published per user for the event stream
+    let events = Arc::new(EventBus::new());
+    // The inbox read model follows everyone's events from the start
+    let inbox = Arc::new(InboxProjection::new(db.clone()));
+    inbox.clone().spawn(events.subscribe_all());
+    // Changes to stored comments and interactions, straight from the database
+    let live = Arc::new(LiveFeed::new(db.clone()));
+    live.clone().spawn();
+    let youtube_service = Arc::new(YouTubeService::new(
+        db.clone(),
+        youtube_client,
+        upstreams.youtube.clone(),
+        auth_service.clone(),
+        quota_tracker.clone(),
+        notification_service.clone(),
+        settings.clone(),
+        Arc::new(RuleService::new(db.clone())),
+        spam.clone(),
+        events.clone(),
+    ));
+    let prompt_library = Arc::new(PromptLibrary::new(db.clone()));
+    prompt_library.reload().await?;
+    let ai_service = Arc::new(AiService::new(
+        db.clone(),
+        openai_client,
+        upstreams.openai.clone(),
+        prompt_library.clone(),
+    ));
+    let job_service = Arc::new(JobService::new(db.clone()));
+    let analytics_service = Arc::new(AnalyticsService::new(db.clone()));
+    let dashboard_service = Arc::new(DashboardService::new(db.clone(), quota_tracker.clone()));
+    
+    // Store the AI models from AI_MODELS_FILE, or the built-in ones, keeping edits to those already stored
+    ai_service.seed_models(&ModelSeed::from_env()?).await?;
     
-    // Initialize default AI models
-    ai_service.init_default_models().await?;
+    // Store the built-in reply tones
+    let tones = Arc::new(ToneService::new(db.clone()));
+    tones.seed_builtin().await?;
+    
+    // Keep monitored users' tokens fresh in the background
+    auth_service.clone().spawn_token_refresher();
+    
+    // Send the daily digest emails
+    notification_service.clone().spawn_daily_digest();
+    
+    // Pick up prompt files edited in PROMPTS_DIR
+    prompt_library.clone().spawn_watcher();
+    
+    // Publish comment events to the broker STREAM_SINK selects, if any
+    if let Some(stream_sink) = StreamSink::from_env(db.clone()).await? {
+        Arc::new(stream_sink).spawn();
+    }
+    
+    // Confirm once an hour that the replies of the last week are still on YouTube
+    let reply_checker = Arc::new(ReplyChecker::new(
+        db.clone(),
+        youtube_service.clone(),
+        notification_service.clone(),
+        events.clone(),
+    ));
+    reply_checker.clone().spawn();
+    
+    // Prune interactions and comments older than their retention period once a day
+    let retention = Arc::new(Pruner::new(db.clone(), RetentionConfig::from_env()?));
+    retention.clone().spawn();
+    
+    // Watch the videos of every user with monitoring on for new comments
+    let monitor = Arc::new(CommentMonitor::new(db.clone(), youtube_service.clone()));
+    monitor.start_all().await?;
     
     // Create application state
     let app_state = AppState {
         db: db.clone(),
+        http_client: http_client.clone(),
+        upstreams: upstreams.clone(),
         auth_service: auth_service.clone(),
         youtube_service: youtube_service.clone(),
         ai_service: ai_service.clone(),
+        job_service: job_service.clone(),
+        analytics_service: analytics_service.clone(),
+        dashboard_service: dashboard_service.clone(),
+        notification_service: notification_service.clone(),
+        prompt_library: prompt_library.clone(),
+        http_log: Arc::new(HttpLog::from_env()),
+        settings: settings.clone(),
+        saved_replies: Arc::new(SavedReplyService::new(db.clone())),
+        duplicates: Arc::new(DuplicateService::new(db.clone())),
+        outbox: Arc::new(Outbox::new(db.clone())),
+        commenters: Arc::new(CommenterService::new(db.clone())),
+        spam,
+        collections: Arc::new(CollectionService::new(db.clone())),
+        events,
+        inbox,
+        preflight: Arc::new(preflight),
+        tones,
+        mutes: Arc::new(MuteService::new(db.clone())),
+        reply_checker,
+        retention,
+        live,
+        monitor,
+        share_links: Arc::new(ShareLinks::from_env()),
     };
+    
+    // Send replies from the outbox once their undo window is over
+    api::outbox::spawn_sender(app_state.clone());
 
     // Build our application with routes
     let cors = CorsLayer::new()
@@ -59,26 +179,26 @@ async fn main() -> Result<()> {
         .allow_methods(tower_http::cors::Any)
         .allow_headers(tower_http::cors::Any);
 
-    let app = Router::new()
-        .route("/", get(|| async { "YouTube Commenter API" }))
-        .route("/api/health", get(api::handlers::health_check))
-        .route("/api/auth/url", get(api::handlers::get_auth_url))
-        .route("/api/auth/callback", get(api::handlers::oauth_callback))
-        .route("/api/videos", get(api::handlers::get_videos))
-        .route("/api/comments/:video_id", get(api::handlers::get_comments))
-        .route("/api/reply/generate", post(api::handlers::generate_reply))
-        .route("/api/reply/post", post(api::handlers::post_reply))
-        .route("/api/history", get(api::handlers::get_history))
+    let app = api::router(app_state)
         .layer(cors)
-        .with_state(app_state);
+        // A panicking handler answers 500 instead of dropping the connection; the panic is still reported
+        .layer(CatchPanicLayer::new());
+    let app = error_reporting::layer(app)
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(logging::request_span)
+                .on_response(DefaultOnResponse::new().level(Level::INFO).latency_unit(LatencyUnit::Millis)),
+        )
+        .layer(PropagateRequestIdLayer::new(HeaderName::from_static(REQUEST_ID_HEADER)))
+        // Outermost, so the ID is set before the request span is created
+        .layer(SetRequestIdLayer::new(HeaderName::from_static(REQUEST_ID_HEADER), MakeRequestUuid));
 
     // Run the server
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     info!("Listening on {}", addr);
 
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
-        .await?;
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
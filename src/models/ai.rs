@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::models::video::VideoTimestamp;
+
 /// AI model configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiModelConfig {
@@ -26,10 +28,54 @@ pub struct AiModelConfig {
     /// Whether this model is currently available
     pub is_available: bool,
     
+    /// Price per 1,000 prompt tokens (in USD)
+    #[serde(default)]
+    pub prompt_cost_per_1k: f64,
+    
+    /// Price per 1,000 completion tokens (in USD)
+    #[serde(default)]
+    pub completion_cost_per_1k: f64,
+    
     /// Additional metadata
     pub metadata: HashMap<String, String>,
 }
 
+impl AiModelConfig {
+    /// Estimated cost of a request with the given token counts (in USD)
+    pub fn cost_for(&self, prompt_tokens: usize, completion_tokens: usize) -> f64 {
+        (prompt_tokens as f64 * self.prompt_cost_per_1k + completion_tokens as f64 * self.completion_cost_per_1k) / 1000.0
+    }
+}
+
+/// What a model is used for, each routed to its own default model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AiTask {
+    /// Drafting replies to comments
+    Reply,
+
+    /// Labelling comments, e.g. by sentiment or as spam
+    Classification,
+
+    /// Condensing many comments into a summary
+    Summarization,
+}
+
+impl AiTask {
+    pub const ALL: [AiTask; 3] = [AiTask::Reply, AiTask::Classification, AiTask::Summarization];
+}
+
+/// The model a task uses unless a request picks one, from the `model_routing` table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRoute {
+    pub task: AiTask,
+
+    /// ID of a model in `ai_models`
+    pub model_id: String,
+
+    pub updated_at: DateTime<Utc>,
+}
+
 /// AI model parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AiModelParameters {
@@ -49,9 +95,11 @@ pub struct AiModelParameters {
     pub max_tokens: usize,
     
     /// Stop sequences
+    #[serde(default)]
     pub stop: Vec<String>,
     
     /// Additional parameters
+    #[serde(default)]
     pub additional: HashMap<String, serde_json::Value>,
 }
 
@@ -73,17 +121,77 @@ pub struct ReplyGenerationRequest {
     /// Previous interactions with this commenter, if any
     pub previous_interactions: Vec<String>,
     
-    /// The tone to use for the reply
+    /// Replies already under the comment, oldest first, as `author: text`
+    #[serde(default)]
+    pub thread_replies: Vec<String>,
+    
+    /// The reply answering the user's own reply that this reply should answer, as `author: "text"`
+    #[serde(default)]
+    pub follow_up: Option<String>,
+    
+    /// Moments of the video the comment mentions
+    #[serde(default)]
+    pub timestamps: Vec<VideoTimestamp>,
+    
+    /// ID of the tone preset to write the reply in
     pub tone: String,
     
+    /// The persona to write as, if any
+    #[serde(default)]
+    pub persona: Option<String>,
+    
+    /// Language tag to write the reply in; the comment's own language if unset
+    #[serde(default)]
+    pub reply_language: Option<String>,
+    
+    /// Saved reply to personalize, keeping its structure, instead of writing freely
+    #[serde(default)]
+    pub template: Option<String>,
+    
+    /// The thread is heated, so the reply should calm it down rather than argue
+    #[serde(default)]
+    pub de_escalate: bool,
+    
     /// Additional instructions for the AI
     pub additional_instructions: Option<String>,
     
     /// Maximum length of the reply
     pub max_length: Option<usize>,
     
-    /// Model parameters to override
-    pub parameter_overrides: Option<HashMap<String, serde_json::Value>>,
+    /// Model and parameters to use instead of the task's defaults
+    pub parameter_overrides: Option<ParameterOverrides>,
+}
+
+/// What a single request changes about the model it's generated with; unset fields keep the model's own
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterOverrides {
+    /// ID of the model to use instead of the one routed to the task
+    pub model: Option<String>,
+    
+    /// Temperature for generation (0.0 to 2.0)
+    pub temperature: Option<f32>,
+    
+    /// Maximum number of tokens to generate, up to the model's maximum response length
+    pub max_tokens: Option<usize>,
+    
+    /// Top-p sampling (0.0 to 1.0)
+    pub top_p: Option<f32>,
+}
+
+impl ParameterOverrides {
+    /// Set the overridden parameters on a model's own
+    pub fn apply(&self, parameters: &mut AiModelParameters) {
+        if let Some(temperature) = self.temperature {
+            parameters.temperature = temperature;
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            parameters.max_tokens = max_tokens;
+        }
+        if let Some(top_p) = self.top_p {
+            parameters.top_p = top_p;
+        }
+    }
 }
 
 /// AI reply generation response
@@ -109,7 +217,7 @@ pub struct ReplyGenerationResponse {
 }
 
 /// AI usage statistics
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AiUsageStats {
     /// Number of prompt tokens
     pub prompt_tokens: usize,
@@ -123,3 +231,40 @@ pub struct AiUsageStats {
     /// Time taken to generate the reply (in milliseconds)
     pub generation_time_ms: u64,
 }
+
+/// A single call to an AI provider, recorded for cost and latency reporting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiUsageRecord {
+    /// Unique ID for this record
+    pub usage_id: String,
+    
+    /// The user the request was made for
+    pub user_id: String,
+    
+    /// The model used
+    pub model: String,
+    
+    /// Number of prompt tokens
+    pub prompt_tokens: usize,
+    
+    /// Number of completion tokens
+    pub completion_tokens: usize,
+    
+    /// Total tokens used
+    pub total_tokens: usize,
+    
+    /// Time taken by the provider (in milliseconds)
+    pub latency_ms: u64,
+    
+    /// Estimated cost of the request (in USD)
+    pub cost_usd: f64,
+    
+    /// Whether the request succeeded
+    pub success: bool,
+    
+    /// Error message, if the request failed
+    pub error: Option<String>,
+    
+    /// When the request was made
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A user-defined rule raising a high-priority notification for matching new comments
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    /// Unique ID for this rule
+    pub rule_id: String,
+
+    /// The user this rule belongs to
+    pub user_id: String,
+
+    /// Name shown in the rule list and in alerts
+    pub name: String,
+
+    /// Whether the rule is applied
+    pub enabled: bool,
+
+    /// Comments containing any of these words or phrases alert (case-insensitive), e.g. product names
+    #[serde(default)]
+    pub phrases: Vec<String>,
+
+    /// Comments phrased as questions alert
+    #[serde(default)]
+    pub questions: bool,
+
+    /// Only comments on this video alert; all of the user's videos if unset
+    #[serde(default)]
+    pub video_id: Option<String>,
+
+    /// How many new comments the rule has alerted about
+    #[serde(default)]
+    pub hit_count: u64,
+
+    /// When the rule last alerted
+    #[serde(default)]
+    pub last_hit_at: Option<DateTime<Utc>>,
+
+    /// When the rule was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the rule was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AlertRule {
+    /// Create a new enabled rule with no hits
+    pub fn new(user_id: &str, name: &str, phrases: Vec<String>, questions: bool, video_id: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            rule_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            enabled: true,
+            phrases,
+            questions,
+            video_id,
+            hit_count: 0,
+            last_hit_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use chrono::{DateTime, Datelike, Duration, DurationRound, NaiveDate, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models::video::VideoFormat;
+use crate::utils::time_zone;
+
+/// Engagement numbers for a single user and day, persisted so history stays cheap to query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyRollup {
+    /// The user these numbers are for
+    pub user_id: String,
+
+    /// The day, in the user's time zone, these numbers cover
+    pub day: NaiveDate,
+
+    /// New comments first seen on this day
+    pub comments_received: usize,
+
+    /// Replies posted on this day
+    pub replies_posted: usize,
+
+    /// Replies posted on this day that were AI-assisted
+    pub ai_replies: usize,
+
+    /// Replies posted on this day that were written manually
+    pub manual_replies: usize,
+
+    /// Median time from a comment being received to being replied to, for replies posted this day (in seconds)
+    pub median_time_to_reply_secs: Option<i64>,
+
+    /// New comments per video on this day
+    pub comments_per_video: HashMap<String, usize>,
+
+    /// When this rollup was computed
+    pub computed_at: DateTime<Utc>,
+}
+
+/// Engagement overview for a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsOverview {
+    /// First day of the period (inclusive)
+    pub from: NaiveDate,
+
+    /// Last day of the period (inclusive)
+    pub to: NaiveDate,
+
+    /// New comments received in the period
+    pub comments_received: usize,
+
+    /// Replies posted in the period
+    pub replies_posted: usize,
+
+    /// Share of comments received in the period that have been replied to (0.0 to 1.0)
+    pub reply_rate: f64,
+
+    /// Median time from a comment being received to being replied to (in seconds)
+    pub median_time_to_reply_secs: Option<i64>,
+
+    /// Average number of new comments per video per day
+    pub comments_per_video_per_day: f64,
+
+    /// Share of posted replies that were AI-assisted (0.0 to 1.0)
+    pub ai_reply_share: f64,
+
+    /// Share of posted replies that were written manually (0.0 to 1.0)
+    pub manual_reply_share: f64,
+
+    /// The daily rollups making up this period
+    pub daily: Vec<DailyRollup>,
+}
+
+/// Bucket size for time-series analytics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    Day,
+    Week,
+}
+
+impl Granularity {
+    /// Start of the bucket a timestamp falls into; days and weeks (starting on Monday) are the time zone's
+    pub fn bucket_start(&self, timestamp: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+        match self {
+            Granularity::Hour => timestamp
+                .duration_trunc(Duration::hours(1))
+                .unwrap_or(timestamp),
+            Granularity::Day => time_zone::start_of_day(time_zone::local_day(timestamp, tz), tz),
+            Granularity::Week => {
+                let day = time_zone::local_day(timestamp, tz);
+                time_zone::start_of_day(day - Duration::days(day.weekday().num_days_from_monday() as i64), tz)
+            }
+        }
+    }
+
+    /// The bucket size as a SurrealQL duration literal, for use with `time::floor`.
+    ///
+    /// Weeks have no literal because SurrealDB aligns them to the Unix epoch
+    /// (a Thursday) rather than to Monday.
+    pub fn surreal_duration(&self) -> Option<&'static str> {
+        match self {
+            Granularity::Hour => Some("1h"),
+            Granularity::Day => Some("1d"),
+            Granularity::Week => None,
+        }
+    }
+}
+
+/// Aggregated comment sentiment for one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentimentBucket {
+    /// Start of the bucket
+    pub start: DateTime<Utc>,
+
+    /// Number of comments published in the bucket
+    pub comments: usize,
+
+    /// Average sentiment score (-1.0 to 1.0)
+    pub average_sentiment: f64,
+
+    /// Number of positive comments
+    pub positive: usize,
+
+    /// Number of neutral comments
+    pub neutral: usize,
+
+    /// Number of negative comments
+    pub negative: usize,
+}
+
+/// Sentiment over time, channel-wide and per video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SentimentTrend {
+    /// Bucket size
+    pub granularity: Granularity,
+
+    /// Start of the period covered
+    pub from: DateTime<Utc>,
+
+    /// End of the period covered
+    pub to: DateTime<Utc>,
+
+    /// Buckets across all videos; buckets without comments are omitted
+    pub channel: Vec<SentimentBucket>,
+
+    /// Buckets per video ID; buckets without comments are omitted
+    pub videos: HashMap<String, Vec<SentimentBucket>>,
+}
+
+/// Number of comments in one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeBucket {
+    /// Start of the bucket
+    #[serde(alias = "bucket")]
+    pub start: DateTime<Utc>,
+
+    /// Number of comments published in the bucket
+    pub comments: usize,
+}
+
+/// One row of the per-video comment volume aggregate query
+#[derive(Debug, Clone, Deserialize)]
+pub struct VideoVolumeRow {
+    /// YouTube video ID
+    pub video_id: String,
+
+    /// Start of the bucket
+    pub bucket: DateTime<Utc>,
+
+    /// Number of comments published in the bucket
+    pub comments: usize,
+}
+
+/// Comment volume over time, channel-wide and per video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentVolume {
+    /// Bucket size
+    pub granularity: Granularity,
+
+    /// Start of the period covered
+    pub from: DateTime<Utc>,
+
+    /// End of the period covered
+    pub to: DateTime<Utc>,
+
+    /// Buckets across all videos; buckets without comments are omitted
+    pub channel: Vec<VolumeBucket>,
+
+    /// Buckets per video ID; buckets without comments are omitted
+    pub videos: HashMap<String, Vec<VolumeBucket>>,
+}
+
+/// How often a term appears
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermCount {
+    /// The keyword or hashtag
+    pub term: String,
+
+    /// Number of occurrences
+    pub count: usize,
+}
+
+/// Keyword and hashtag frequencies for a video, or for the whole channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordStats {
+    /// The user these stats are for
+    pub user_id: String,
+
+    /// The video these stats are for, or `None` for the whole channel
+    pub video_id: Option<String>,
+
+    /// Most frequent keywords
+    pub keywords: Vec<TermCount>,
+
+    /// Most frequent hashtags
+    pub hashtags: Vec<TermCount>,
+
+    /// Number of comments analyzed
+    pub comments_analyzed: usize,
+
+    /// When these stats were computed
+    pub computed_at: DateTime<Utc>,
+}
+
+/// AI usage, cost and latency for one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelUsage {
+    /// Model identifier
+    pub model: String,
+
+    /// Number of requests made
+    pub requests: usize,
+
+    /// Number of failed requests
+    pub errors: usize,
+
+    /// Share of requests that failed (0.0 to 1.0)
+    pub error_rate: f64,
+
+    /// Prompt tokens used
+    pub prompt_tokens: usize,
+
+    /// Completion tokens used
+    pub completion_tokens: usize,
+
+    /// Total tokens used
+    pub total_tokens: usize,
+
+    /// Estimated cost (in USD)
+    pub cost_usd: f64,
+
+    /// Median latency of successful requests (in milliseconds)
+    pub p50_latency_ms: Option<u64>,
+
+    /// 95th percentile latency of successful requests (in milliseconds)
+    pub p95_latency_ms: Option<u64>,
+}
+
+/// AI spend for one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCost {
+    /// The day, in the user's time zone
+    pub day: NaiveDate,
+
+    /// Number of requests made
+    pub requests: usize,
+
+    /// Estimated cost (in USD)
+    pub cost_usd: f64,
+}
+
+/// AI cost and latency report for a period
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiAnalytics {
+    /// First day of the period (inclusive)
+    pub from: NaiveDate,
+
+    /// Last day of the period (inclusive)
+    pub to: NaiveDate,
+
+    /// Number of requests made
+    pub requests: usize,
+
+    /// Share of requests that failed (0.0 to 1.0)
+    pub error_rate: f64,
+
+    /// Estimated total cost (in USD)
+    pub total_cost_usd: f64,
+
+    /// AI-assisted replies posted in the period
+    pub ai_replies_posted: usize,
+
+    /// Estimated cost per AI-assisted reply actually posted (in USD)
+    pub cost_per_posted_reply: Option<f64>,
+
+    /// Median latency of successful requests (in milliseconds)
+    pub p50_latency_ms: Option<u64>,
+
+    /// 95th percentile latency of successful requests (in milliseconds)
+    pub p95_latency_ms: Option<u64>,
+
+    /// Cost per day, oldest first
+    pub daily: Vec<DailyCost>,
+
+    /// Usage per model, most expensive first
+    pub models: Vec<ModelUsage>,
+}
+
+/// Engagement summary of one video, for side-by-side comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoComparison {
+    /// YouTube video ID
+    pub video_id: String,
+
+    /// Video title
+    pub title: String,
+
+    /// Whether the video is a Short
+    pub format: VideoFormat,
+
+    /// When the video was published
+    pub published_at: DateTime<Utc>,
+
+    /// Number of top-level comments
+    pub comments: usize,
+
+    /// Number of replies under those comments
+    pub replies: usize,
+
+    /// Average likes per top-level comment
+    pub average_likes: f64,
+
+    /// Average sentiment score (-1.0 to 1.0)
+    pub average_sentiment: f64,
+
+    /// Share of positive comments (0.0 to 1.0)
+    pub positive_share: f64,
+
+    /// Share of negative comments (0.0 to 1.0)
+    pub negative_share: f64,
+
+    /// Share of comments the user has replied to (0.0 to 1.0)
+    pub reply_rate: f64,
+
+    /// Number of comments the user hasn't replied to
+    pub unanswered: usize,
+}
+
+/// One day of the combined analytics export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyExportRow {
+    /// The day, in the user's time zone
+    pub day: NaiveDate,
+
+    /// New comments first seen on this day
+    pub comments_received: usize,
+
+    /// Replies posted on this day
+    pub replies_posted: usize,
+
+    /// Replies posted on this day that were AI-assisted
+    pub ai_replies: usize,
+
+    /// Replies posted on this day that were written manually
+    pub manual_replies: usize,
+
+    /// Median time to first reply for replies posted this day (in seconds)
+    pub median_time_to_reply_secs: Option<i64>,
+
+    /// Average sentiment of comments published this day
+    pub average_sentiment: Option<f64>,
+
+    /// Positive comments published this day
+    pub positive_comments: usize,
+
+    /// Negative comments published this day
+    pub negative_comments: usize,
+
+    /// AI requests made this day
+    pub ai_requests: usize,
+
+    /// Estimated AI spend this day (in USD)
+    pub ai_cost_usd: f64,
+}
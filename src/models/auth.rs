@@ -1,11 +1,17 @@
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::models::notification::NotificationSettings;
+use crate::models::tone::DEFAULT_TONE_ID;
+use crate::utils::time_zone;
+
 /// User model representing a YouTube account
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     /// User ID (YouTube channel ID)
+    #[serde(deserialize_with = "super::record_key")]
     pub id: String,
     
     /// User's display name
@@ -28,6 +34,30 @@ pub struct User {
     
     /// Additional metadata
     pub metadata: HashMap<String, String>,
+    
+    /// Whether Google still accepts the user's grant
+    #[serde(default)]
+    pub connection: GoogleConnection,
+}
+
+impl User {
+    /// Whether Google revoked or expired the user's grant, so nothing can be
+    /// done on their channel until they reconnect
+    pub fn is_disconnected(&self) -> bool {
+        self.connection.disconnected_at.is_some()
+    }
+}
+
+/// State of a user's Google grant
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GoogleConnection {
+    /// When refreshing the user's token was refused; unset while connected
+    #[serde(default)]
+    pub disconnected_at: Option<DateTime<Utc>>,
+    
+    /// Why Google refused, e.g. `invalid_grant`
+    #[serde(default)]
+    pub disconnect_reason: Option<String>,
 }
 
 /// User preferences
@@ -39,36 +69,222 @@ pub struct UserPreferences {
     /// The AI model to use for generating replies
     pub ai_model: String,
     
-    /// The tone to use for AI-generated replies
+    /// The tone preset AI-generated replies use unless the request or video sets one
     pub reply_tone: ReplyTone,
     
     /// Whether to enable real-time notifications
     pub enable_notifications: bool,
     
+    /// Which events to send notifications for
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    
     /// How frequently to check for new comments (in seconds)
     pub polling_interval: u32,
     
+    /// Language for notifications and digests (e.g. `es`); English if unset or unsupported
+    #[serde(default)]
+    pub language: Option<String>,
+    
+    /// Language AI replies are written in (e.g. `es`); the comment's own language if unset
+    #[serde(default)]
+    pub preferred_reply_language: Option<String>,
+    
+    /// IANA time zone (e.g. `Europe/Berlin`) days, digests and scheduled replies follow; UTC if unset
+    #[serde(default)]
+    pub time_zone: Option<String>,
+    
+    /// Whether and how AI-generated replies say so when posted
+    #[serde(default)]
+    pub ai_disclosure: AiDisclosure,
+    
+    /// What replies may contain, and the sign-off of generated replies
+    #[serde(default)]
+    pub reply_policy: ReplyPolicy,
+    
+    /// How long the user's interactions and comments are kept
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+    
     /// Additional preferences
     pub additional: HashMap<String, String>,
 }
 
-/// Tone options for AI-generated replies
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ReplyTone {
-    /// Professional and formal
-    Professional,
+impl UserPreferences {
+    /// The user's time zone; UTC if unset or no longer known
+    pub fn time_zone(&self) -> Tz {
+        self.time_zone.as_deref().and_then(time_zone::parse).unwrap_or(Tz::UTC)
+    }
+}
+
+/// A note added to AI-generated replies as they are posted, for transparent disclosure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AiDisclosure {
+    /// Whether the note is added
+    pub enabled: bool,
     
-    /// Friendly and casual
-    Friendly,
+    /// The note, e.g. "– replied with AI assist"
+    #[serde(default = "default_disclosure_text")]
+    pub text: String,
     
-    /// Enthusiastic and excited
-    Enthusiastic,
+    /// Where the note goes
+    #[serde(default)]
+    pub position: DisclosurePosition,
+}
+
+fn default_disclosure_text() -> String {
+    "– replied with AI assist".to_string()
+}
+
+impl Default for AiDisclosure {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            text: default_disclosure_text(),
+            position: DisclosurePosition::default(),
+        }
+    }
+}
+
+impl AiDisclosure {
+    /// Add the note to reply text, unless it is switched off or the text already has it
+    pub fn apply(&self, reply_text: &str) -> String {
+        let note = self.text.trim();
+        if !self.enabled || note.is_empty() || reply_text.contains(note) {
+            return reply_text.to_string();
+        }
+        
+        match self.position {
+            DisclosurePosition::Suffix => format!("{} {}", reply_text.trim_end(), note),
+            DisclosurePosition::Prefix => format!("{} {}", note, reply_text.trim_start()),
+        }
+    }
+}
+
+/// How many days the pruning job keeps records; unset periods fall back to the server's, and
+/// records are kept forever if neither sets one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Days interactions are kept after they were recorded
+    #[serde(default)]
+    pub interactions_days: Option<u32>,
     
-    /// Helpful and informative
-    Helpful,
+    /// Days comments are kept after they were published
+    #[serde(default)]
+    pub comments_days: Option<u32>,
+}
+
+impl RetentionPolicy {
+    /// This policy, with the periods it leaves unset taken from `defaults`
+    pub fn or(self, defaults: RetentionPolicy) -> RetentionPolicy {
+        RetentionPolicy {
+            interactions_days: self.interactions_days.or(defaults.interactions_days),
+            comments_days: self.comments_days.or(defaults.comments_days),
+        }
+    }
+}
+
+/// What the channel's replies may contain, checked before posting since YouTube
+/// often holds comments with links for review
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplyPolicy {
+    /// Whether replies may contain links
+    #[serde(default = "default_allowed")]
+    pub allow_links: bool,
+    
+    /// Whether replies may @-mention other channels
+    #[serde(default = "default_allowed")]
+    pub allow_mentions: bool,
+    
+    /// Sign-off added to generated replies, e.g. "– Numan"
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+fn default_allowed() -> bool {
+    true
+}
+
+impl Default for ReplyPolicy {
+    fn default() -> Self {
+        Self {
+            allow_links: true,
+            allow_mentions: true,
+            signature: None,
+        }
+    }
+}
+
+impl ReplyPolicy {
+    /// Instructions for the AI combining the request's own with what the policy forbids
+    pub fn instructions(&self, additional: Option<&str>) -> Option<String> {
+        let mut instructions: Vec<&str> = additional.map(str::trim).filter(|text| !text.is_empty()).into_iter().collect();
+        if !self.allow_links {
+            instructions.push("Do not include any links or web addresses.");
+        }
+        if !self.allow_mentions {
+            instructions.push("Do not @-mention anyone.");
+        }
+        
+        (!instructions.is_empty()).then(|| instructions.join(" "))
+    }
     
-    /// Custom tone with specific instructions
-    Custom(String),
+    /// Add the sign-off on its own line, unless there is none or the text already ends with it
+    pub fn sign(&self, reply_text: &str) -> String {
+        match self.signature.as_deref().map(str::trim) {
+            Some(signature) if !signature.is_empty() && !reply_text.trim_end().ends_with(signature) => {
+                format!("{}\n{}", reply_text.trim_end(), signature)
+            }
+            _ => reply_text.to_string(),
+        }
+    }
+}
+
+/// Where an AI disclosure note goes in a reply
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisclosurePosition {
+    /// After the reply text
+    #[default]
+    Suffix,
+    
+    /// Before the reply text, e.g. "[AI-assisted]"
+    Prefix,
+}
+
+/// The tone preset AI-generated replies are written in by default, by its ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(transparent)]
+pub struct ReplyTone(pub String);
+
+impl Default for ReplyTone {
+    fn default() -> Self {
+        Self(DEFAULT_TONE_ID.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for ReplyTone {
+    /// Accepts a tone ID, or a tone stored before tones were presets: the
+    /// built-in names map to their presets and a custom tone to the default
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Stored {
+            Id(String),
+            Custom {
+                #[serde(rename = "Custom")]
+                _custom: String,
+            },
+        }
+
+        Ok(match Stored::deserialize(deserializer)? {
+            Stored::Id(id) => match id.as_str() {
+                "Professional" | "Friendly" | "Enthusiastic" | "Helpful" => Self(id.to_lowercase()),
+                _ => Self(id),
+            },
+            Stored::Custom { .. } => Self::default(),
+        })
+    }
 }
 
 /// Authentication token for YouTube API
@@ -94,6 +310,7 @@ pub struct AuthToken {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     /// Session ID
+    #[serde(deserialize_with = "super::record_key")]
     pub id: String,
     
     /// User ID
@@ -114,3 +331,18 @@ pub struct Session {
     /// Whether this session is currently active
     pub is_active: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reply_tone_from_stored_preferences() {
+        let tone = |value| serde_json::from_value::<ReplyTone>(value).unwrap().0;
+        assert_eq!(tone(json!("Professional")), "professional");
+        assert_eq!(tone(json!({ "Custom": "Pirate speak" })), DEFAULT_TONE_ID);
+        assert_eq!(tone(json!("3f1c2d9e-tone")), "3f1c2d9e-tone");
+        assert_eq!(serde_json::to_value(ReplyTone::default()).unwrap(), json!("friendly"));
+    }
+}
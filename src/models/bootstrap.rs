@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use super::auth::{User, UserPreferences};
+use super::organization::{Organization, OrgPolicy};
+use super::settings::RuntimeSettings;
+
+/// What the user is to the app, which decides what the client offers them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// A creator managing their own channel
+    Creator,
+
+    /// A creator whose channel belongs to an organization
+    OrgMember,
+
+    /// The user who manages an organization
+    OrgAdmin,
+}
+
+impl Role {
+    /// The user's role given the organization they belong to, if any
+    pub fn of(user_id: &str, org: Option<&Organization>) -> Self {
+        match org {
+            Some(org) if org.is_admin(user_id) => Role::OrgAdmin,
+            Some(_) => Role::OrgMember,
+            None => Role::Creator,
+        }
+    }
+}
+
+/// What the user may do, so the client can hide what would be refused
+#[derive(Debug, Clone, Serialize)]
+pub struct Permissions {
+    /// Change the organization's policy and members
+    pub manage_organization: bool,
+
+    /// Pick a persona; organizations can fix one for every member
+    pub choose_persona: bool,
+
+    /// Put links in replies
+    pub post_links: bool,
+
+    /// @-mention other channels in replies
+    pub post_mentions: bool,
+
+    /// Generate replies with AI
+    pub generate_replies: bool,
+
+    /// Post to YouTube; not while Google rejects the user's grant
+    pub post_replies: bool,
+
+    /// Use the admin API, i.e. the request carried a valid `x-admin-token`
+    pub administer: bool,
+}
+
+impl Permissions {
+    pub fn new(
+        role: Role,
+        policy: &OrgPolicy,
+        preferences: &UserPreferences,
+        settings: &RuntimeSettings,
+        connected: bool,
+        administer: bool,
+    ) -> Self {
+        Self {
+            manage_organization: role == Role::OrgAdmin,
+            choose_persona: policy.persona.is_none(),
+            post_links: policy.allow_links && preferences.reply_policy.allow_links,
+            post_mentions: policy.allow_mentions && preferences.reply_policy.allow_mentions,
+            generate_replies: settings.ai_enabled,
+            post_replies: connected,
+            administer,
+        }
+    }
+}
+
+/// Switches the client adapts to
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureFlags {
+    /// Whether AI reply generation is available
+    pub ai_enabled: bool,
+
+    /// Whether replies are only recorded instead of posted
+    pub dry_run: bool,
+
+    /// Whether sensitive comment text is masked
+    pub mask_sensitive_text: bool,
+
+    /// Notification channels this server was built with, e.g. `email` and `telegram`
+    pub notification_channels: Vec<&'static str>,
+}
+
+/// Whether the user's videos are being watched for new comments
+#[derive(Debug, Clone, Serialize)]
+pub struct MonitorStatus {
+    /// Whether monitoring is paused for everyone
+    pub paused: bool,
+
+    /// Whether the user's monitor task is running
+    pub running: bool,
+
+    /// The user's videos with monitoring on
+    pub monitored_videos: usize,
+
+    /// When the monitor last checked one of the user's videos
+    pub last_checked_at: Option<DateTime<Utc>>,
+}
+
+/// Everything a client needs at startup, in one response
+#[derive(Debug, Clone, Serialize)]
+pub struct Bootstrap {
+    /// The user, preferences included
+    pub user: User,
+
+    /// Whether Google still accepts the user's grant
+    pub connected: bool,
+
+    /// Where to reconnect Google, if it doesn't
+    pub reconnect_url: Option<String>,
+
+    pub role: Role,
+
+    /// Name of the user's organization, if they belong to one
+    pub organization: Option<String>,
+
+    pub permissions: Permissions,
+    pub features: FeatureFlags,
+    pub monitor: MonitorStatus,
+
+    /// Comments in the inbox the user hasn't looked at yet
+    pub unread_count: usize,
+
+    /// Threads with a follow-up awaiting the user's answer
+    pub follow_up_count: usize,
+}
@@ -0,0 +1,93 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::video::Video;
+
+/// A named group of a user's videos, e.g. "Tutorials" or "Shorts", with settings for its videos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoCollection {
+    /// Unique ID for this collection
+    pub collection_id: String,
+
+    /// The user this collection belongs to
+    pub user_id: String,
+
+    /// Name shown in the collection list
+    pub name: String,
+
+    /// Settings given to videos as they are added
+    #[serde(default)]
+    pub defaults: CollectionDefaults,
+
+    /// When the collection was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the collection was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl VideoCollection {
+    /// Create a new, empty collection
+    pub fn new(user_id: &str, name: &str, defaults: CollectionDefaults) -> Self {
+        let now = Utc::now();
+        Self {
+            collection_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            defaults,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Settings a collection gives its videos; unset ones leave the video's own
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CollectionDefaults {
+    /// Persona replies are written as
+    #[serde(default)]
+    pub persona: Option<String>,
+
+    /// Tone of generated replies
+    #[serde(default)]
+    pub tone: Option<String>,
+
+    /// Whether new comments may be replied to automatically
+    #[serde(default)]
+    pub auto_reply: Option<bool>,
+
+    /// Whether the videos are monitored for new comments
+    #[serde(default)]
+    pub monitor_enabled: Option<bool>,
+
+    /// How frequently to check the videos for new comments (in seconds)
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+}
+
+impl CollectionDefaults {
+    /// Give a video these settings; monitor settings count as customized, so syncs keep them
+    pub fn apply(&self, video: &mut Video) {
+        if let Some(persona) = &self.persona {
+            video.reply_defaults.persona = Some(persona.clone());
+        }
+        if let Some(tone) = &self.tone {
+            video.reply_defaults.tone = Some(tone.clone());
+        }
+
+        let monitor = &mut video.monitor;
+        if let Some(auto_reply) = self.auto_reply {
+            monitor.auto_reply = auto_reply;
+            monitor.customized = true;
+        }
+        if let Some(enabled) = self.monitor_enabled {
+            monitor.enabled = enabled;
+            monitor.customized = true;
+        }
+        if let Some(interval_secs) = self.interval_secs {
+            monitor.interval_secs = interval_secs;
+            monitor.customized = true;
+        }
+    }
+}
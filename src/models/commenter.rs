@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Comment metadata key showing the commenter's tags, comma-separated
+pub const COMMENTER_TAGS_KEY: &str = "commenter_tags";
+
+/// Comment metadata key showing the private notes on the commenter
+pub const COMMENTER_NOTES_KEY: &str = "commenter_notes";
+
+/// A user's private notes and tags on someone who comments on their videos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommenterProfile {
+    /// The user keeping the profile
+    pub user_id: String,
+
+    /// YouTube channel ID of the commenter
+    pub channel_id: String,
+
+    /// Private notes on the commenter
+    #[serde(default)]
+    pub notes: Option<String>,
+
+    /// Tags such as `superfan`, `troll` or `sponsor lead`, lowercase
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// When the profile was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl CommenterProfile {
+    /// An empty profile
+    pub fn new(user_id: &str, channel_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            channel_id: channel_id.to_string(),
+            notes: None,
+            tags: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Whether the profile has the tag, ignoring case
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))
+    }
+}
+
+/// Change to a commenter profile; unset fields are kept
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommenterPatch {
+    /// New notes; `""` clears them
+    pub notes: Option<String>,
+
+    /// New tags, replacing the old ones
+    pub tags: Option<Vec<String>>,
+}
+
+/// One of a commenter's comments with the user's replies to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    /// The video commented on
+    pub video_id: String,
+
+    /// Title of the video, if stored
+    pub video_title: Option<String>,
+
+    /// The comment
+    pub comment_id: String,
+
+    /// The comment text
+    pub text: String,
+
+    /// When the comment was published
+    pub published_at: DateTime<Utc>,
+
+    /// The user's replies to the comment, oldest first
+    pub replies: Vec<PastReply>,
+}
+
+/// A reply the user posted to a commenter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PastReply {
+    /// YouTube ID of the reply, if known
+    pub reply_id: Option<String>,
+
+    /// The reply text
+    pub text: String,
+
+    /// When the reply was posted
+    pub posted_at: DateTime<Utc>,
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A reply answering the user's own reply in a thread, which needs the user's attention
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FollowUp {
+    /// ID of the reply following up
+    pub reply_id: String,
+
+    /// ID of the user's reply it follows
+    pub in_reply_to: String,
+
+    /// When a sync found it
+    pub detected_at: DateTime<Utc>,
+}
+
+/// One message of a thread, the top-level comment or a reply
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationMessage {
+    /// Comment or reply ID
+    pub id: String,
+
+    pub author: String,
+    pub author_channel_id: String,
+
+    /// Text as YouTube displays it
+    pub text: String,
+
+    pub published_at: DateTime<Utc>,
+
+    /// Whether the user wrote it
+    pub mine: bool,
+
+    /// Whether it is the follow-up awaiting the user's answer
+    pub follow_up: bool,
+}
+
+/// A comment thread as an exchange between the commenter and the user, oldest message first
+#[derive(Debug, Clone, Serialize)]
+pub struct Conversation {
+    pub video_id: String,
+    pub comment_id: String,
+
+    /// The follow-up awaiting the user's answer, if any
+    pub follow_up: Option<FollowUp>,
+
+    pub messages: Vec<ConversationMessage>,
+}
@@ -0,0 +1,103 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::rate_limit::RateLimitState;
+
+/// The handful of numbers a home screen needs, in one call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Dashboard {
+    /// Comments on the user's videos that haven't been replied to
+    pub unanswered_comments: usize,
+
+    /// Comments published today (UTC)
+    pub new_comments_today: usize,
+
+    /// Generated drafts waiting for review
+    pub pending_review: usize,
+
+    /// YouTube API quota units left today
+    pub quota_remaining: u64,
+
+    /// YouTube API daily quota
+    pub quota_limit: u64,
+
+    /// Estimated AI spend this calendar month (in USD)
+    pub monthly_ai_spend_usd: f64,
+
+    /// When these numbers were computed
+    pub computed_at: DateTime<Utc>,
+}
+
+/// How much YouTube and AI capacity is left, and what is already waiting to use it
+#[derive(Debug, Clone, Serialize)]
+pub struct Capacity {
+    /// YouTube API quota of the day (shared by all users)
+    pub youtube_quota: QuotaCapacity,
+
+    /// Estimated AI spend of the user
+    pub ai_spend: AiSpend,
+
+    /// The user's replies waiting in the outbox
+    pub outbox: OutboxBacklog,
+
+    /// The YouTube API rate limiter (shared by all users)
+    pub rate_limiter: RateLimitState,
+
+    /// Estimated quota a backfill of all the user's videos would use
+    pub backfill: BackfillEstimate,
+
+    /// When these numbers were computed
+    pub computed_at: DateTime<Utc>,
+}
+
+/// YouTube API quota usage of the day
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaCapacity {
+    /// Daily quota (in units)
+    pub limit: u64,
+
+    /// Units spent today
+    pub used: u64,
+
+    /// Units left today
+    pub remaining: u64,
+
+    /// When usage is counted from zero again (midnight UTC)
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Estimated AI spend (in USD)
+#[derive(Debug, Clone, Serialize)]
+pub struct AiSpend {
+    /// Spend today (UTC)
+    pub today_usd: f64,
+
+    /// Spend this calendar month
+    pub month_usd: f64,
+}
+
+/// Replies waiting out their undo window
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxBacklog {
+    /// Replies not sent yet
+    pub pending: usize,
+
+    /// When the next of them is sent
+    pub next_send_at: Option<DateTime<Utc>>,
+
+    /// Quota units sending them will use
+    pub quota_units: u64,
+}
+
+/// What a backfill of all stored videos would cost
+#[derive(Debug, Clone, Serialize)]
+pub struct BackfillEstimate {
+    /// Videos that would be synced
+    pub videos: usize,
+
+    /// Quota units of the comment thread pages, at least one per video; reply pages come on top
+    pub quota_units: u64,
+
+    /// Whether that fits in the quota left today
+    pub fits_remaining_quota: bool,
+}
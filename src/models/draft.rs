@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A generated reply waiting for the user to review and post it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplyDraft {
+    /// Unique ID for this draft
+    pub draft_id: String,
+
+    /// The user this draft belongs to
+    pub user_id: String,
+
+    /// The video ID
+    pub video_id: String,
+
+    /// The comment this draft replies to
+    pub comment_id: String,
+
+    /// The draft reply text
+    pub text: String,
+
+    /// The AI model that generated this draft, if any
+    pub model: Option<String>,
+
+    /// The persona the draft was written as, if any
+    #[serde(default)]
+    pub persona: Option<String>,
+
+    /// Review status
+    pub status: DraftStatus,
+
+    /// When the draft was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the draft was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ReplyDraft {
+    /// Create a new pending draft
+    pub fn new(user_id: &str, video_id: &str, comment_id: &str, text: &str, model: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            draft_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            video_id: video_id.to_string(),
+            comment_id: comment_id.to_string(),
+            text: text.to_string(),
+            model,
+            persona: None,
+            status: DraftStatus::Pending,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Review status of a draft
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DraftStatus {
+    /// Waiting for review
+    Pending,
+
+    /// A reply to the comment has been posted
+    Posted,
+
+    /// Rejected by the user
+    Discarded,
+}
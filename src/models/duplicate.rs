@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Metadata key holding a comment's text fingerprint
+pub const TEXT_HASH_KEY: &str = "text_hash";
+
+/// Near-identical comments, grouped by the fingerprint of their text
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// The shared text fingerprint
+    pub group_id: String,
+
+    /// Number of comments in the group
+    pub comments: usize,
+
+    /// Channel IDs of the commenters
+    pub authors: Vec<String>,
+
+    /// Videos the comments were posted on
+    pub video_ids: Vec<String>,
+
+    /// Text of one of the comments
+    pub sample_text: String,
+
+    /// When the first comment was published
+    pub first_seen: DateTime<Utc>,
+
+    /// When the latest comment was published
+    pub last_seen: DateTime<Utc>,
+}
@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+/// Something a service did for a user, published as it happens so clients can follow along
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UserEvent {
+    /// A video's comments were fetched from YouTube and stored
+    CommentsFetched {
+        video_id: String,
+
+        /// Comments fetched, new or not
+        comments: usize,
+
+        /// Comments not stored before, hidden ones excluded
+        new_comments: usize,
+    },
+
+    /// A commenter edited a stored comment, noticed when it was refreshed
+    CommentEdited {
+        video_id: String,
+        comment_id: String,
+
+        /// The comment's new text
+        text: String,
+    },
+
+    /// The user changed a stored comment, e.g. its triage state or whether it is spam
+    CommentChanged {
+        video_id: String,
+        comment_id: String,
+    },
+
+    /// The user changed their notes or tags on a commenter
+    CommenterUpdated {
+        channel_id: String,
+    },
+
+    /// An AI reply was generated and saved as a draft
+    ReplyGenerated {
+        video_id: String,
+        comment_id: String,
+        reply_text: String,
+        model: String,
+    },
+
+    /// A reply was posted to YouTube, or only recorded in a dry run
+    ReplyPosted {
+        comment_id: String,
+        reply_id: String,
+        dry_run: bool,
+    },
+}
+
+impl UserEvent {
+    /// Name of the event, e.g. for the `event:` field of server-sent events
+    pub fn name(&self) -> &'static str {
+        match self {
+            UserEvent::CommentsFetched { .. } => "comments_fetched",
+            UserEvent::CommentEdited { .. } => "comment_edited",
+            UserEvent::CommentChanged { .. } => "comment_changed",
+            UserEvent::CommenterUpdated { .. } => "commenter_updated",
+            UserEvent::ReplyGenerated { .. } => "reply_generated",
+            UserEvent::ReplyPosted { .. } => "reply_posted",
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Comment, InteractionRecord, InteractionType, TriageState};
+use super::commenter::CommenterProfile;
+use super::video::Video;
+
+/// A comment as the inbox shows it, kept up to date from the user's events.
+///
+/// Everything the inbox needs is copied in, so listing it is a single query
+/// instead of joining comments, commenter profiles and history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboxEntry {
+    /// The user whose video the comment is on
+    pub user_id: String,
+
+    pub video_id: String,
+
+    /// Title of the video the comment is on
+    pub video_title: String,
+
+    pub comment_id: String,
+
+    /// YouTube channel ID of the commenter
+    pub author_channel_id: String,
+
+    /// When the comment was published; the inbox is listed newest first
+    pub published_at: DateTime<Utc>,
+
+    pub triage: TriageState,
+
+    /// Whether the user replied to the comment
+    pub replied_to: bool,
+
+    /// Whether filter rules hid the comment or marked it as spam
+    pub hidden: bool,
+
+    /// The user's tags on the commenter
+    #[serde(default)]
+    pub commenter_tags: Vec<String>,
+
+    /// The user's notes on the commenter
+    #[serde(default)]
+    pub commenter_notes: Option<String>,
+
+    /// What the user last did with the comment
+    #[serde(default)]
+    pub last_interaction: Option<InteractionType>,
+
+    /// When the user last did something with the comment
+    #[serde(default)]
+    pub last_interaction_at: Option<DateTime<Utc>>,
+
+    pub comment: Comment,
+
+    /// When the entry was last brought up to date
+    pub projected_at: DateTime<Utc>,
+}
+
+impl InboxEntry {
+    /// The entry of a comment on the user's video, with the user's profile of its commenter
+    /// and the latest of the user's interactions with it
+    pub fn new(
+        video: &Video,
+        comment: Comment,
+        profile: Option<&CommenterProfile>,
+        last_interaction: Option<&InteractionRecord>,
+    ) -> Self {
+        Self {
+            user_id: video.user_id.clone(),
+            video_id: video.video_id.clone(),
+            video_title: video.title.clone(),
+            comment_id: comment.comment_id.clone(),
+            author_channel_id: comment.author_channel_id.clone(),
+            published_at: comment.published_at,
+            triage: comment.triage,
+            replied_to: comment.replied_to,
+            hidden: comment.is_hidden(),
+            commenter_tags: profile.map(|p| p.tags.clone()).unwrap_or_default(),
+            commenter_notes: profile.and_then(|p| p.notes.clone()),
+            last_interaction: last_interaction.map(|i| i.interaction_type.clone()),
+            last_interaction_at: last_interaction.map(|i| i.timestamp),
+            comment,
+            projected_at: Utc::now(),
+        }
+    }
+}
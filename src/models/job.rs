@@ -0,0 +1,153 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A long-running background operation whose progress can be polled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    /// Unique ID for this job
+    pub job_id: String,
+
+    /// The user who started this job
+    pub user_id: String,
+
+    /// What kind of operation this job performs
+    pub kind: JobKind,
+
+    /// Current status of the job
+    pub status: JobStatus,
+
+    /// Total number of items to process
+    pub total: usize,
+
+    /// Number of items processed successfully
+    pub succeeded: usize,
+
+    /// Number of items that failed
+    pub failed: usize,
+
+    /// Per-item results, in the order they finished
+    pub results: Vec<JobItemResult>,
+
+    /// Error that stopped the whole job, if any
+    pub error: Option<String>,
+
+    /// When the job was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the job was last updated
+    pub updated_at: DateTime<Utc>,
+
+    /// When the job finished, if it has
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl Job {
+    /// Create a new queued job
+    pub fn new(user_id: &str, kind: JobKind, total: usize) -> Self {
+        let now = Utc::now();
+        Self {
+            job_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            kind,
+            status: JobStatus::Queued,
+            total,
+            succeeded: 0,
+            failed: 0,
+            results: Vec::new(),
+            error: None,
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+        }
+    }
+
+    /// Whether the job has stopped processing items
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Completed | JobStatus::Failed)
+    }
+
+    /// Fraction of items processed so far, between 0.0 and 1.0
+    pub fn progress(&self) -> f64 {
+        if self.total == 0 {
+            return if self.is_finished() { 1.0 } else { 0.0 };
+        }
+        ((self.succeeded + self.failed) as f64 / self.total as f64).min(1.0)
+    }
+}
+
+/// Kinds of long-running operations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JobKind {
+    /// Fetch and store all comments for a set of videos
+    Backfill,
+
+    /// Generate AI replies for many comments
+    BatchGenerate,
+
+    /// Post many replies to YouTube
+    BulkPost,
+
+    /// Recompute keyword and hashtag frequencies from stored comments
+    KeywordAnalysis,
+
+    /// Answer a cluster of similar comments with personalized variants of one answer
+    ClusterReply,
+
+    /// Check that posted replies are still on YouTube
+    VerifyReplies,
+}
+
+/// Status of a job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    /// Created but not started yet
+    Queued,
+
+    /// Currently processing items
+    Running,
+
+    /// Finished; individual items may still have failed
+    Completed,
+
+    /// Stopped by an error before all items were processed
+    Failed,
+}
+
+/// Result of processing a single item in a job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobItemResult {
+    /// The item this result is for (video ID, comment ID, ...)
+    pub item_id: String,
+
+    /// Whether the item was processed successfully
+    pub success: bool,
+
+    /// Output of the item, if successful
+    pub output: Option<serde_json::Value>,
+
+    /// Error message, if the item failed
+    pub error: Option<String>,
+}
+
+impl JobItemResult {
+    /// A successful item result
+    pub fn success(item_id: &str, output: serde_json::Value) -> Self {
+        Self {
+            item_id: item_id.to_string(),
+            success: true,
+            output: Some(output),
+            error: None,
+        }
+    }
+
+    /// A failed item result
+    pub fn failure(item_id: &str, error: impl ToString) -> Self {
+        Self {
+            item_id: item_id.to_string(),
+            success: false,
+            output: None,
+            error: Some(error.to_string()),
+        }
+    }
+}
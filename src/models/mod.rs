@@ -1,12 +1,60 @@
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
+use surrealdb::sql::Thing;
 
+pub mod alert;
 pub mod auth;
 pub mod ai;
+pub mod bootstrap;
+pub mod video;
+pub mod job;
+pub mod analytics;
+pub mod collection;
+pub mod commenter;
+pub mod conversation;
+pub mod draft;
+pub mod duplicate;
+pub mod event;
+pub mod inbox;
+pub mod moderation;
+pub mod mute;
+pub mod dashboard;
+pub mod notification;
+pub mod organization;
+pub mod outbox;
+pub mod preflight;
+pub mod prompt;
+pub mod rule;
+pub mod saved_reply;
+pub mod search;
+pub mod settings;
+pub mod spam;
+pub mod stream;
+pub mod tone;
+
+/// Comment metadata key naming the source the comment was fetched from, e.g. `youtube`
+pub const SOURCE_KEY: &str = "source";
+
+/// Deserialize an `id` stored as the key of the record's ID, e.g. `user-1` of `users:⟨user-1⟩`.
+///
+/// Plain strings are taken as they are, so the models still read from JSON.
+pub fn record_key<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Key {
+        Plain(String),
+        Record(Thing),
+    }
+
+    Ok(match Key::deserialize(deserializer)? {
+        Key::Plain(key) => key,
+        Key::Record(thing) => thing.id.to_raw(),
+    })
+}
 
 /// Comment model representing a YouTube comment
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Comment {
     /// YouTube video ID
     pub video_id: String,
@@ -20,25 +68,235 @@ pub struct Comment {
     /// Author channel ID
     pub author_channel_id: String,
 
-    /// Comment text
+    /// Comment text as YouTube displays it, with HTML markup; shown in the UI
     pub text: String,
 
+    /// Comment text as the commenter wrote it, if YouTube provided it; given to the AI
+    #[serde(default)]
+    pub text_original: Option<String>,
+
     /// Number of likes
     pub like_count: i32,
 
     /// When the comment was published
     pub published_at: DateTime<Utc>,
 
-    /// Replies to this comment
+    /// Replies to this comment fetched so far; may be fewer than `reply_count`
     pub replies: Vec<Reply>,
 
+    /// Total number of replies on YouTube
+    #[serde(default)]
+    pub reply_count: i32,
+
     /// Whether this comment has been replied to by the user
     pub replied_to: bool,
 
+    /// Where the comment is in the user's triage workflow
+    #[serde(default)]
+    pub triage: TriageState,
+
+    /// The user's verdict if the comment was quarantined as spam and reviewed
+    #[serde(default)]
+    pub spam_review: Option<spam::SpamReview>,
+
+    /// Whether the user pinned or highlighted the comment on YouTube
+    #[serde(default)]
+    pub highlight: HighlightState,
+
+    /// Whether the user hearted or liked the comment on YouTube
+    #[serde(default)]
+    pub reaction: ReactionState,
+
+    /// A reply answering the user's own reply in the thread, until the user answers it
+    #[serde(default)]
+    pub follow_up: Option<conversation::FollowUp>,
+
+    /// Sentiment score between -1.0 (negative) and 1.0 (positive)
+    #[serde(default)]
+    pub sentiment: Option<f32>,
+
+    /// Moments of the video the comment mentions, in order of appearance
+    #[serde(default)]
+    pub timestamps: Vec<video::VideoTimestamp>,
+
     /// Metadata for the comment
     pub metadata: HashMap<String, String>,
 }
 
+impl Comment {
+    /// Whether every reply in the thread has been fetched
+    pub fn has_all_replies(&self) -> bool {
+        self.replies.len() as i32 >= self.reply_count
+    }
+
+    /// The text as the commenter wrote it, or as displayed if YouTube didn't provide that
+    pub fn original_text(&self) -> &str {
+        self.text_original.as_deref().unwrap_or(&self.text)
+    }
+
+    /// Whether a filter rule hid the comment or marked it as spam, keeping it out of the inbox
+    pub fn is_hidden(&self) -> bool {
+        self.metadata.contains_key(rule::RuleAction::Hide.metadata_key())
+            || self.metadata.contains_key(rule::RuleAction::MarkSpam.metadata_key())
+    }
+}
+
+/// Builds comments for tests and benchmarks.
+///
+/// Starts from a comment by "Viewer" (`UCviewer`) published now, with no text or replies.
+#[derive(Debug, Clone)]
+pub struct CommentBuilder(Comment);
+
+impl CommentBuilder {
+    pub fn new(video_id: &str, comment_id: &str) -> Self {
+        Self(Comment {
+            video_id: video_id.to_string(),
+            comment_id: comment_id.to_string(),
+            author: "Viewer".to_string(),
+            author_channel_id: "UCviewer".to_string(),
+            published_at: Utc::now(),
+            ..Default::default()
+        })
+    }
+
+    /// The author's display name
+    pub fn author(mut self, author: &str) -> Self {
+        self.0.author = author.to_string();
+        self
+    }
+
+    /// The author's channel ID
+    pub fn author_channel(mut self, channel_id: &str) -> Self {
+        self.0.author_channel_id = channel_id.to_string();
+        self
+    }
+
+    pub fn text(mut self, text: &str) -> Self {
+        self.0.text = text.to_string();
+        self
+    }
+
+    pub fn likes(mut self, like_count: i32) -> Self {
+        self.0.like_count = like_count;
+        self
+    }
+
+    pub fn sentiment(mut self, sentiment: f32) -> Self {
+        self.0.sentiment = Some(sentiment);
+        self
+    }
+
+    pub fn published_at(mut self, published_at: DateTime<Utc>) -> Self {
+        self.0.published_at = published_at;
+        self
+    }
+
+    /// The replies in the thread, all of them fetched
+    pub fn replies(mut self, replies: Vec<Reply>) -> Self {
+        self.0.reply_count = replies.len() as i32;
+        self.0.replies = replies;
+        self
+    }
+
+    pub fn build(self) -> Comment {
+        self.0
+    }
+}
+
+/// Workflow state of a comment in the inbox
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TriageState {
+    /// Not looked at yet
+    #[default]
+    New,
+
+    /// Needs a reply from the user
+    NeedsReply,
+
+    /// A reply is being drafted
+    InProgress,
+
+    /// Answered or otherwise handled
+    Done,
+
+    /// Deliberately left alone
+    Ignored,
+}
+
+impl TriageState {
+    /// The state as written in the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TriageState::New => "new",
+            TriageState::NeedsReply => "needs_reply",
+            TriageState::InProgress => "in_progress",
+            TriageState::Done => "done",
+            TriageState::Ignored => "ignored",
+        }
+    }
+
+    /// Whether the comment still needs attention
+    pub fn is_open(&self) -> bool {
+        matches!(self, TriageState::New | TriageState::NeedsReply | TriageState::InProgress)
+    }
+}
+
+/// When the user pinned or highlighted a comment, as they told us.
+///
+/// The API can't pin or highlight comments, so users do it in YouTube Studio and record it here.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HighlightState {
+    /// When the comment was pinned to the top of its video
+    #[serde(default)]
+    pub pinned_at: Option<DateTime<Utc>>,
+
+    /// When the comment was highlighted
+    #[serde(default)]
+    pub highlighted_at: Option<DateTime<Utc>>,
+}
+
+/// When the user hearted or liked a comment, as they told us.
+///
+/// The API can't heart or like comments either, so this is recorded the same way as highlights.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReactionState {
+    /// When the comment got the creator's heart
+    #[serde(default)]
+    pub hearted_at: Option<DateTime<Utc>>,
+
+    /// When the comment was liked from the channel
+    #[serde(default)]
+    pub liked_at: Option<DateTime<Utc>>,
+}
+
+/// What the user has done with a stored comment, kept when it is synced again
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CommentState {
+    /// Whether the comment has been replied to
+    pub replied_to: bool,
+
+    /// The comment's triage state
+    #[serde(default)]
+    pub triage: TriageState,
+
+    /// The user's verdict on the comment as spam, if reviewed
+    #[serde(default)]
+    pub spam_review: Option<spam::SpamReview>,
+
+    /// Whether the user pinned or highlighted the comment
+    #[serde(default)]
+    pub highlight: HighlightState,
+
+    /// Whether the user hearted or liked the comment
+    #[serde(default)]
+    pub reaction: ReactionState,
+
+    /// The follow-up awaiting the user's answer, if any
+    #[serde(default)]
+    pub follow_up: Option<conversation::FollowUp>,
+}
+
 /// Reply model representing a reply to a YouTube comment
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reply {
@@ -54,9 +312,13 @@ pub struct Reply {
     /// Author channel ID
     pub author_channel_id: String,
 
-    /// Reply text
+    /// Reply text as YouTube displays it, with HTML markup; shown in the UI
     pub text: String,
 
+    /// Reply text as written, if YouTube provided it; given to the AI
+    #[serde(default)]
+    pub text_original: Option<String>,
+
     /// Number of likes
     pub like_count: i32,
 
@@ -73,10 +335,18 @@ pub struct Reply {
     pub metadata: HashMap<String, String>,
 }
 
+impl Reply {
+    /// The text as written, or as displayed if YouTube didn't provide that
+    pub fn original_text(&self) -> &str {
+        self.text_original.as_deref().unwrap_or(&self.text)
+    }
+}
+
 /// Interaction history record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InteractionRecord {
     /// Unique ID for this interaction
+    #[serde(deserialize_with = "record_key")]
     pub id: String,
 
     /// The user who performed this interaction
@@ -102,7 +372,7 @@ pub struct InteractionRecord {
 }
 
 /// Types of interactions that can be recorded
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum InteractionType {
     /// A new comment was received
     CommentReceived,
@@ -110,15 +380,56 @@ pub enum InteractionType {
     /// A reply was generated by AI
     ReplyGenerated,
 
-    /// A reply was edited by the user
+    /// A reply draft was queued for review
+    DraftCreated,
+
+    /// A draft was posted as it was generated
+    DraftApproved,
+
+    /// A draft was edited by the user before it was posted; `data["draft_text"]` holds the original
     ReplyEdited,
 
     /// A reply was posted to YouTube
     ReplyPosted,
 
+    /// A queued reply was withdrawn before it was sent
+    ReplyDeleted,
+
+    /// A posted reply's text was corrected on YouTube; `data["previous_text"]` holds the text before
+    ReplyUpdated,
+
+    /// A posted reply is no longer on YouTube, most likely removed by its spam
+    /// filter; `data["reply_text"]` holds what was posted
+    ReplyRemoved,
+
+    /// Someone answered the user's reply in a thread; `data["reply_id"]` is their reply and
+    /// `data["in_reply_to"]` the user's
+    FollowUpReceived,
+
+    /// A comment was moderated; `data["action"]` is `rejected`, `held`, `published` or `restored`
+    CommentModerated,
+
+    /// A comment was reported to YouTube as spam
+    CommentMarkedSpam,
+
+    /// A comment's author was banned from the channel
+    CommenterBanned,
+
+    /// The user pinned or highlighted a comment, or undid it; `data["action"]` says which
+    CommentFeatured,
+
+    /// The user hearted or liked a comment, or undid it; `data["action"]` says which
+    CommentReacted,
+
+    /// An auto-reply rule matched a new comment but didn't post; `data["reason"]` says why
+    AutoReplySkipped,
+
     /// A comment or reply was viewed
     Viewed,
 
+    /// An action was only recorded instead of sent to YouTube; `data["action"]` says which
+    DryRun,
+
     /// Custom interaction type
     Custom(String),
 }
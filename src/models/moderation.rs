@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// Whether YouTube shows a comment, as `comments.setModerationStatus` sets it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationStatus {
+    /// Hidden until the creator reviews it
+    HeldForReview,
+
+    /// Shown publicly
+    Published,
+
+    /// Hidden for good
+    Rejected,
+}
+
+impl ModerationStatus {
+    /// The status as the YouTube API writes it
+    pub fn as_api(&self) -> &'static str {
+        match self {
+            ModerationStatus::HeldForReview => "heldForReview",
+            ModerationStatus::Published => "published",
+            ModerationStatus::Rejected => "rejected",
+        }
+    }
+}
+
+/// What the user does to a comment from the moderation endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationAction {
+    /// Hold it for review
+    Hold,
+
+    /// Publish a held or rejected comment
+    Publish,
+
+    /// Reject it, optionally banning its author
+    Reject,
+
+    /// Report it to YouTube as spam
+    Spam,
+}
+
+impl ModerationAction {
+    /// The status the action sets on YouTube; reporting spam leaves the status to YouTube
+    pub fn status(&self) -> Option<ModerationStatus> {
+        match self {
+            ModerationAction::Hold => Some(ModerationStatus::HeldForReview),
+            ModerationAction::Publish => Some(ModerationStatus::Published),
+            ModerationAction::Reject => Some(ModerationStatus::Rejected),
+            ModerationAction::Spam => None,
+        }
+    }
+
+    /// The action as written in the API and history
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModerationAction::Hold => "hold",
+            ModerationAction::Publish => "publish",
+            ModerationAction::Reject => "reject",
+            ModerationAction::Spam => "spam",
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// What a mute silences
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MuteKind {
+    /// Every comment on one of the user's videos
+    Video,
+
+    /// Every comment by a YouTube channel
+    Commenter,
+}
+
+/// A video or commenter the user never wants to hear about: their comments are
+/// left out of the inbox and notifications, and aren't monitored or auto-replied to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mute {
+    /// Unique ID for this mute
+    pub mute_id: String,
+
+    /// The user who muted it
+    pub user_id: String,
+
+    pub kind: MuteKind,
+
+    /// The video ID, or the commenter's YouTube channel ID
+    pub target_id: String,
+
+    /// The video's title or the commenter's name when muted, for the mute list
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// Why the user muted it, for their own reference
+    #[serde(default)]
+    pub reason: Option<String>,
+
+    /// When it was muted
+    pub created_at: DateTime<Utc>,
+}
+
+impl Mute {
+    /// Mute a video or commenter for a user
+    pub fn new(user_id: &str, kind: MuteKind, target_id: &str) -> Self {
+        Self {
+            mute_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            kind,
+            target_id: target_id.to_string(),
+            label: None,
+            reason: None,
+            created_at: Utc::now(),
+        }
+    }
+}
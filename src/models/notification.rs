@@ -0,0 +1,347 @@
+use serde::{Deserialize, Serialize};
+
+/// Every Slack incoming webhook URL starts with this; anything else is refused rather than requested
+pub const SLACK_WEBHOOK_PREFIX: &str = "https://hooks.slack.com/";
+
+/// Whether a URL is a Slack incoming webhook, so posting to it can't reach other hosts
+pub fn is_slack_webhook_url(url: &str) -> bool {
+    url.starts_with(SLACK_WEBHOOK_PREFIX) && !url.contains(char::is_whitespace)
+}
+
+/// Kinds of events a user can be notified about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationEvent {
+    /// A new comment was received
+    NewComment,
+
+    /// New comments asking a question that haven't been answered
+    UnansweredQuestion,
+
+    /// An unusually high share of negative comments in a batch
+    NegativeSentimentSpike,
+
+    /// A reply posted without the user watching (in bulk or automatically) failed
+    AutoReplyFailed,
+
+    /// The daily summary of activity
+    DailyDigest,
+
+    /// A new comment matched one of the user's alert rules
+    Alert,
+
+    /// Posted replies are no longer visible on YouTube, e.g. removed by its spam filter
+    ReplyRemoved,
+}
+
+/// How urgent a notification is
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationPriority {
+    #[default]
+    Normal,
+    High,
+}
+
+/// Per-event notification toggles, applied on top of `UserPreferences::enable_notifications`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationSettings {
+    /// Notify about each new comment as it arrives (chat channels only, unless routed otherwise)
+    #[serde(default = "default_true")]
+    pub new_comments: bool,
+
+    /// Notify about new unanswered questions
+    pub unanswered_questions: bool,
+
+    /// Notify about negative sentiment spikes
+    pub negative_sentiment_spikes: bool,
+
+    /// Notify about failed automatic replies
+    pub auto_reply_failures: bool,
+
+    /// Send the daily digest
+    pub daily_digest: bool,
+
+    /// Notify about comments matching alert rules, as they arrive
+    #[serde(default = "default_true")]
+    pub alerts: bool,
+
+    /// Notify about posted replies YouTube no longer shows
+    #[serde(default = "default_true")]
+    pub removed_replies: bool,
+
+    /// Slack incoming webhook URL to post notifications to
+    #[serde(default)]
+    pub slack_webhook_url: Option<String>,
+
+    /// Slack channel ID to post notifications to with the bot token, if no webhook is set
+    #[serde(default)]
+    pub slack_channel: Option<String>,
+
+    /// Telegram chat ID the bot sends notifications to
+    #[serde(default)]
+    pub telegram_chat_id: Option<i64>,
+
+    /// Matrix room ID the bot account sends notifications to, e.g. `!abc123:example.org`
+    #[serde(default)]
+    pub matrix_room_id: Option<String>,
+
+    /// Rules deciding which channels each notification goes to; empty uses the default routing
+    #[serde(default)]
+    pub routes: Vec<RoutingRule>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            new_comments: true,
+            unanswered_questions: true,
+            negative_sentiment_spikes: true,
+            auto_reply_failures: true,
+            daily_digest: true,
+            alerts: true,
+            removed_replies: true,
+            slack_webhook_url: None,
+            slack_channel: None,
+            telegram_chat_id: None,
+            matrix_room_id: None,
+            routes: Vec::new(),
+        }
+    }
+}
+
+impl NotificationSettings {
+    /// Whether notifications for an event are turned on
+    pub fn is_enabled(&self, event: NotificationEvent) -> bool {
+        match event {
+            NotificationEvent::NewComment => self.new_comments,
+            NotificationEvent::UnansweredQuestion => self.unanswered_questions,
+            NotificationEvent::NegativeSentimentSpike => self.negative_sentiment_spikes,
+            NotificationEvent::AutoReplyFailed => self.auto_reply_failures,
+            NotificationEvent::DailyDigest => self.daily_digest,
+            NotificationEvent::Alert => self.alerts,
+            NotificationEvent::ReplyRemoved => self.removed_replies,
+        }
+    }
+
+    /// The channels a notification should be delivered on.
+    ///
+    /// Without routing rules, everything goes to chat and everything but
+    /// individual comments goes to email. With rules, the channels of every
+    /// matching rule are used, and a notification no rule matches is dropped.
+    pub fn channels_for(&self, notification: &Notification) -> Vec<NotificationChannel> {
+        if self.routes.is_empty() {
+            return match notification.event {
+                NotificationEvent::NewComment => {
+                    vec![NotificationChannel::Slack, NotificationChannel::Telegram, NotificationChannel::Matrix]
+                }
+                _ => vec![
+                    NotificationChannel::Email,
+                    NotificationChannel::Slack,
+                    NotificationChannel::Telegram,
+                    NotificationChannel::Matrix,
+                ],
+            };
+        }
+
+        let mut channels = Vec::new();
+        for rule in self.routes.iter().filter(|rule| rule.matches(notification)) {
+            for channel in &rule.channels {
+                if !channels.contains(channel) {
+                    channels.push(*channel);
+                }
+            }
+        }
+        channels
+    }
+}
+
+/// A channel notifications can be delivered on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationChannel {
+    Email,
+    Slack,
+    Telegram,
+    Matrix,
+}
+
+/// Sends matching notifications to a set of channels
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    /// The event this rule applies to, or `None` for any event
+    #[serde(default)]
+    pub event: Option<NotificationEvent>,
+
+    /// Further conditions a notification must meet
+    #[serde(default)]
+    pub filter: RouteFilter,
+
+    /// Where matching notifications go
+    pub channels: Vec<NotificationChannel>,
+}
+
+impl RoutingRule {
+    /// Whether a notification matches this rule
+    pub fn matches(&self, notification: &Notification) -> bool {
+        self.event.is_none_or(|event| event == notification.event) && self.filter.matches(notification)
+    }
+}
+
+/// Conditions on a notification; unset conditions always match
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteFilter {
+    /// Only notifications about this video
+    #[serde(default)]
+    pub video_id: Option<String>,
+
+    /// Only comments at or below this sentiment score (-1.0 to 1.0), e.g. `-0.5` for urgent negative comments
+    #[serde(default)]
+    pub max_sentiment: Option<f32>,
+
+    /// Only notifications whose subject or body contains this text (case-insensitive)
+    #[serde(default)]
+    pub contains: Option<String>,
+
+    /// Only notifications of at least this priority, e.g. `high` for alerts
+    #[serde(default)]
+    pub min_priority: Option<NotificationPriority>,
+}
+
+impl RouteFilter {
+    /// Whether a notification meets every condition
+    pub fn matches(&self, notification: &Notification) -> bool {
+        if let Some(video_id) = &self.video_id {
+            if notification.video_id.as_ref() != Some(video_id) {
+                return false;
+            }
+        }
+
+        if let Some(max_sentiment) = self.max_sentiment {
+            match notification.sentiment {
+                Some(sentiment) if sentiment <= max_sentiment => {}
+                _ => return false,
+            }
+        }
+
+        if self.min_priority.is_some_and(|priority| notification.priority < priority) {
+            return false;
+        }
+
+        if let Some(text) = &self.contains {
+            let text = text.to_lowercase();
+            if !notification.subject.to_lowercase().contains(&text) && !notification.body.to_lowercase().contains(&text) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// A notification ready to be delivered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// The event this notification is about
+    pub event: NotificationEvent,
+
+    /// Short summary, used as the email subject
+    pub subject: String,
+
+    /// Plain-text body
+    pub body: String,
+
+    /// Where in the app to act on this notification
+    #[serde(default)]
+    pub link: Option<String>,
+
+    /// The comment this notification is about, so chat messages can offer quick actions
+    #[serde(default)]
+    pub comment_id: Option<String>,
+
+    /// The video this notification is about
+    #[serde(default)]
+    pub video_id: Option<String>,
+
+    /// Sentiment score of the comment this notification is about
+    #[serde(default)]
+    pub sentiment: Option<f32>,
+
+    /// How urgent the notification is
+    #[serde(default)]
+    pub priority: NotificationPriority,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_comment(sentiment: f32) -> Notification {
+        Notification {
+            event: NotificationEvent::NewComment,
+            subject: "New comment".to_string(),
+            body: "This is the worst tutorial".to_string(),
+            link: None,
+            comment_id: Some("c".to_string()),
+            video_id: Some("v".to_string()),
+            sentiment: Some(sentiment),
+            priority: NotificationPriority::Normal,
+        }
+    }
+
+    #[test]
+    fn test_is_slack_webhook_url() {
+        assert!(is_slack_webhook_url("https://hooks.slack.com/services/T000/B000/XXXX"));
+        assert!(!is_slack_webhook_url("http://hooks.slack.com/services/T000/B000/XXXX"));
+        assert!(!is_slack_webhook_url("https://hooks.slack.com.evil.example/services"));
+        assert!(!is_slack_webhook_url("http://169.254.169.254/latest/meta-data"));
+    }
+
+    #[test]
+    fn test_default_routing() {
+        let settings = NotificationSettings::default();
+        assert_eq!(
+            settings.channels_for(&new_comment(0.0)),
+            vec![NotificationChannel::Slack, NotificationChannel::Telegram, NotificationChannel::Matrix]
+        );
+    }
+
+    #[test]
+    fn test_routing_rules() {
+        let settings = NotificationSettings {
+            routes: vec![
+                RoutingRule {
+                    event: Some(NotificationEvent::NewComment),
+                    filter: RouteFilter {
+                        max_sentiment: Some(-0.5),
+                        ..Default::default()
+                    },
+                    channels: vec![NotificationChannel::Telegram],
+                },
+                RoutingRule {
+                    event: None,
+                    filter: RouteFilter {
+                        contains: Some("WORST".to_string()),
+                        ..Default::default()
+                    },
+                    channels: vec![NotificationChannel::Telegram, NotificationChannel::Email],
+                },
+            ],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            settings.channels_for(&new_comment(-0.8)),
+            vec![NotificationChannel::Telegram, NotificationChannel::Email]
+        );
+
+        let mut neutral = new_comment(0.0);
+        assert_eq!(settings.channels_for(&neutral), vec![NotificationChannel::Telegram, NotificationChannel::Email]);
+
+        // No rule matches
+        neutral.body = "Great tutorial".to_string();
+        assert!(settings.channels_for(&neutral).is_empty());
+    }
+}
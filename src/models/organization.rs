@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::Comment;
+use super::auth::ReplyPolicy;
+
+/// An agency managing several creators' channels.
+///
+/// Each member keeps their own data; the admin sees the members' unanswered
+/// comments together and sets rules every member's replies follow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Organization {
+    /// Unique ID for this organization
+    pub org_id: String,
+
+    /// Name shown to members, e.g. the agency's
+    pub name: String,
+
+    /// The user who created the organization and manages it
+    pub admin_id: String,
+
+    /// The users whose channels belong to the organization, the admin included
+    pub members: Vec<String>,
+
+    /// Code a creator signs in with to join; only shown to the admin
+    pub invite_code: String,
+
+    /// Rules every member's replies follow
+    #[serde(default)]
+    pub policy: OrgPolicy,
+
+    /// When the organization was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the organization or its policy was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Organization {
+    /// Create an organization whose only member is its admin
+    pub fn new(name: &str, admin_id: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            org_id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            admin_id: admin_id.to_string(),
+            members: vec![admin_id.to_string()],
+            invite_code: Uuid::new_v4().simple().to_string(),
+            policy: OrgPolicy::default(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether the user manages the organization
+    pub fn is_admin(&self, user_id: &str) -> bool {
+        self.admin_id == user_id
+    }
+}
+
+/// Org-wide persona and compliance rules, applied on top of each member's own settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrgPolicy {
+    /// Persona every generated reply is written as, whatever the video or request asks for
+    #[serde(default)]
+    pub persona: Option<String>,
+
+    /// Whether members' replies may contain links
+    #[serde(default = "default_allowed")]
+    pub allow_links: bool,
+
+    /// Whether members' replies may @-mention other channels
+    #[serde(default = "default_allowed")]
+    pub allow_mentions: bool,
+
+    /// Words or phrases no reply may contain (case-insensitive), e.g. competitors' names
+    #[serde(default)]
+    pub blocked_phrases: Vec<String>,
+}
+
+fn default_allowed() -> bool {
+    true
+}
+
+impl Default for OrgPolicy {
+    fn default() -> Self {
+        Self {
+            persona: None,
+            allow_links: true,
+            allow_mentions: true,
+            blocked_phrases: Vec::new(),
+        }
+    }
+}
+
+impl OrgPolicy {
+    /// A member's reply policy with what the organization forbids forbidden too
+    pub fn restrict(&self, mut policy: ReplyPolicy) -> ReplyPolicy {
+        policy.allow_links &= self.allow_links;
+        policy.allow_mentions &= self.allow_mentions;
+        policy
+    }
+
+    /// The first blocked phrase the text contains
+    pub fn blocked_phrase(&self, text: &str) -> Option<&str> {
+        let text = text.to_lowercase();
+        self.blocked_phrases
+            .iter()
+            .map(|phrase| phrase.trim())
+            .find(|phrase| !phrase.is_empty() && text.contains(&phrase.to_lowercase()))
+    }
+
+    /// Instructions for the AI combining the request's own with the blocked phrases
+    pub fn instructions(&self, additional: Option<&str>) -> Option<String> {
+        let phrases: Vec<&str> = self.blocked_phrases.iter().map(|p| p.trim()).filter(|p| !p.is_empty()).collect();
+        let blocked = (!phrases.is_empty()).then(|| format!("Never use these words or phrases: {}.", phrases.join(", ")));
+
+        match (additional.map(str::trim).filter(|text| !text.is_empty()), blocked) {
+            (Some(additional), Some(blocked)) => Some(format!("{} {}", additional, blocked)),
+            (additional, blocked) => additional.map(str::to_string).or(blocked),
+        }
+    }
+}
+
+/// An unanswered comment in the organization inbox, with the channel it was left on
+#[derive(Debug, Clone, Serialize)]
+pub struct InboxItem {
+    /// The member whose video the comment is on
+    pub user_id: String,
+
+    /// Title of the video the comment is on
+    pub video_title: String,
+
+    pub comment: Comment,
+}
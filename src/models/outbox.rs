@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A reply waiting in the outbox until its undo window is over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedReply {
+    /// Unique ID for this queued reply
+    pub queue_id: String,
+
+    /// The user posting the reply
+    pub user_id: String,
+
+    /// The comment being replied to
+    pub comment_id: String,
+
+    /// The reply text
+    pub reply_text: String,
+
+    /// Whether the reply was generated by AI
+    #[serde(default)]
+    pub ai_generated: bool,
+
+    /// The AI model used to generate the reply, if applicable
+    #[serde(default)]
+    pub ai_model: Option<String>,
+
+    /// Saved reply expanded instead of `reply_text` when sending
+    #[serde(default)]
+    pub template_id: Option<String>,
+
+    /// Where the reply is in the outbox
+    pub status: QueueStatus,
+
+    /// When the reply is sent to YouTube, unless cancelled first
+    pub send_at: DateTime<Utc>,
+
+    /// When the reply was queued
+    pub created_at: DateTime<Utc>,
+
+    /// When a sender claimed the reply for sending
+    #[serde(default)]
+    pub claimed_at: Option<DateTime<Utc>>,
+
+    /// YouTube ID of the posted reply, once sent
+    #[serde(default)]
+    pub reply_id: Option<String>,
+
+    /// Why sending failed, if it did
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl QueuedReply {
+    /// Queue a reply to be sent at `send_at`
+    pub fn new(user_id: &str, comment_id: &str, reply_text: &str, send_at: DateTime<Utc>) -> Self {
+        Self {
+            queue_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            comment_id: comment_id.to_string(),
+            reply_text: reply_text.to_string(),
+            ai_generated: false,
+            ai_model: None,
+            template_id: None,
+            status: QueueStatus::Pending,
+            send_at,
+            created_at: Utc::now(),
+            claimed_at: None,
+            reply_id: None,
+            error: None,
+        }
+    }
+}
+
+/// Status of a queued reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueStatus {
+    /// Waiting for the undo window to pass; can still be cancelled
+    Pending,
+
+    /// Being posted to YouTube
+    Sending,
+
+    /// Posted to YouTube
+    Sent,
+
+    /// Cancelled before it was sent
+    Cancelled,
+
+    /// Posting to YouTube failed
+    Failed,
+}
+
+impl QueueStatus {
+    /// The status as written in the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            QueueStatus::Pending => "pending",
+            QueueStatus::Sending => "sending",
+            QueueStatus::Sent => "sent",
+            QueueStatus::Cancelled => "cancelled",
+            QueueStatus::Failed => "failed",
+        }
+    }
+}
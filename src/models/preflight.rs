@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Outcome of one startup check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    /// Configured and reachable
+    Ok,
+
+    /// Works, but may fail later or is only partly available (e.g. an upstream that couldn't be reached)
+    Warning,
+
+    /// Misconfigured: whatever depends on it will fail
+    Failed,
+}
+
+/// One startup check, e.g. that the OpenAI key is accepted
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightCheck {
+    /// What was checked, e.g. `oauth`, `database`, `openai` or `notifications.slack`
+    pub name: String,
+
+    pub status: CheckStatus,
+
+    /// What is wrong and how to fix it; unset if the check passed
+    pub message: Option<String>,
+}
+
+impl PreflightCheck {
+    /// A passed check
+    pub fn ok(name: &str) -> Self {
+        Self { name: name.to_string(), status: CheckStatus::Ok, message: None }
+    }
+
+    /// A check that found a problem
+    pub fn problem(name: &str, status: CheckStatus, message: impl Into<String>) -> Self {
+        Self { name: name.to_string(), status, message: Some(message.into()) }
+    }
+}
+
+/// Results of the checks run when the server started
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+
+    /// When the checks ran; unset if they didn't
+    pub checked_at: Option<DateTime<Utc>>,
+}
+
+impl PreflightReport {
+    /// Checks that found a problem
+    pub fn problems(&self) -> impl Iterator<Item = &PreflightCheck> {
+        self.checks.iter().filter(|check| check.status != CheckStatus::Ok)
+    }
+
+    /// Whether a check failed outright
+    pub fn has_failures(&self) -> bool {
+        self.checks.iter().any(|check| check.status == CheckStatus::Failed)
+    }
+}
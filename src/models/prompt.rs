@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What a piece of prompt text is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptKind {
+    /// Base instructions opening every system message
+    System,
+
+    /// Instructions for a reply tone, named after the tone
+    Tone,
+
+    /// Who the AI writes as, chosen per request
+    Persona,
+}
+
+impl PromptKind {
+    /// Name of the directory holding this kind of prompt under `PROMPTS_DIR`
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            PromptKind::System => "system",
+            PromptKind::Tone => "tones",
+            PromptKind::Persona => "personas",
+        }
+    }
+}
+
+/// Prompt text edited through the admin API, overriding the built-in or on-disk text of the same kind and name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    /// What the text is used for
+    pub kind: PromptKind,
+
+    /// Tone or persona name; `default` for the system instructions
+    pub name: String,
+
+    /// The prompt text
+    pub text: String,
+
+    /// When the template was last edited
+    pub updated_at: DateTime<Utc>,
+}
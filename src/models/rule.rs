@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A user-defined rule applied to comments as they are saved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilterRule {
+    /// Unique ID for this rule
+    pub rule_id: String,
+
+    /// The user this rule belongs to
+    pub user_id: String,
+
+    /// Name shown in the rule list
+    pub name: String,
+
+    /// Whether the rule is applied
+    pub enabled: bool,
+
+    /// What a comment must look like for the rule to apply
+    pub conditions: RuleConditions,
+
+    /// What happens to matching comments
+    pub action: RuleAction,
+
+    /// Reply posted to matching comments by the `auto_reply` action
+    #[serde(default)]
+    pub reply_text: Option<String>,
+
+    /// How many new comments the rule has matched
+    #[serde(default)]
+    pub hit_count: u64,
+
+    /// When the rule last matched a new comment
+    #[serde(default)]
+    pub last_hit_at: Option<DateTime<Utc>>,
+
+    /// When the rule was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the rule was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl FilterRule {
+    /// Create a new enabled rule with no hits
+    pub fn new(user_id: &str, name: &str, conditions: RuleConditions, action: RuleAction, reply_text: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            rule_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            enabled: true,
+            conditions,
+            action,
+            reply_text,
+            hit_count: 0,
+            last_hit_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// Conditions of a rule; every condition that is set must hold
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RuleConditions {
+    /// The comment contains any of these words or phrases (case-insensitive)
+    #[serde(default)]
+    pub keywords: Vec<String>,
+
+    /// The comment text matches this regular expression
+    #[serde(default)]
+    pub text_pattern: Option<String>,
+
+    /// The author's display name matches this regular expression
+    #[serde(default)]
+    pub author_pattern: Option<String>,
+
+    /// The comment does (`true`) or doesn't (`false`) contain a link
+    #[serde(default)]
+    pub contains_link: Option<bool>,
+
+    /// The commenter has any of these tags in the user's commenter profiles
+    #[serde(default)]
+    pub author_tags: Vec<String>,
+}
+
+impl RuleConditions {
+    /// Whether no condition is set, which would match every comment
+    pub fn is_empty(&self) -> bool {
+        self.keywords.is_empty()
+            && self.text_pattern.is_none()
+            && self.author_pattern.is_none()
+            && self.contains_link.is_none()
+            && self.author_tags.is_empty()
+    }
+}
+
+/// What a rule does to matching comments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    /// Keep the comment out of the inbox
+    Hide,
+
+    /// Mark the comment as spam, which also hides it
+    MarkSpam,
+
+    /// Flag the comment for the user to look at
+    FlagForReview,
+
+    /// Post the rule's reply text to the comment
+    AutoReply,
+}
+
+impl RuleAction {
+    /// Comment metadata key recording that the action applied; the value is the rule ID
+    pub fn metadata_key(&self) -> &'static str {
+        match self {
+            RuleAction::Hide => "hidden",
+            RuleAction::MarkSpam => "spam",
+            RuleAction::FlagForReview => "flagged_for_review",
+            RuleAction::AutoReply => "auto_reply_rule",
+        }
+    }
+}
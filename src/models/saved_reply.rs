@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A canned reply a user saved for common comments, with `{{placeholder}}` variables
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedReply {
+    /// Unique ID for this template
+    pub template_id: String,
+
+    /// The user this template belongs to
+    pub user_id: String,
+
+    /// Name shown in the template list
+    pub name: String,
+
+    /// Reply text, with placeholders filled in when posting
+    pub text: String,
+
+    /// How many replies were posted from this template
+    #[serde(default)]
+    pub usage_count: u64,
+
+    /// When a reply was last posted from this template
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    /// When the template was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the template was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SavedReply {
+    /// Create a new, unused template
+    pub fn new(user_id: &str, name: &str, text: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            template_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            name: name.to_string(),
+            text: text.to_string(),
+            usage_count: 0,
+            last_used_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
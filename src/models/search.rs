@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Comment, TriageState};
+
+/// Narrows a full-text search over stored comments; unset filters match every comment
+#[derive(Debug, Clone, Default)]
+pub struct CommentSearchFilters {
+    /// Videos searched; the caller's own videos, never empty
+    pub video_ids: Vec<String>,
+
+    /// Only comments published at or after this time
+    pub since: Option<DateTime<Utc>>,
+
+    /// Only comments published before this time
+    pub until: Option<DateTime<Utc>>,
+
+    /// Only comments in this triage state
+    pub triage: Option<TriageState>,
+
+    /// Most hits returned
+    pub limit: usize,
+}
+
+/// A comment matching a search, with how well it matched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommentSearchHit {
+    #[serde(flatten)]
+    pub comment: Comment,
+
+    /// BM25 relevance; higher is a better match
+    pub score: f64,
+
+    /// The comment text with the matched words wrapped in `<mark>` tags
+    pub highlight: String,
+}
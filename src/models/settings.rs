@@ -0,0 +1,74 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Operator knobs that can be changed at runtime through the admin API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeSettings {
+    /// Log filter directives overriding `RUST_LOG`, e.g. `info,youtube_commenter=debug`
+    #[serde(default)]
+    pub log_filter: Option<String>,
+
+    /// YouTube API requests per second overriding `YOUTUBE_MAX_REQUESTS_PER_SEC`
+    #[serde(default)]
+    pub youtube_requests_per_sec: Option<u32>,
+
+    /// Whether comment monitoring is paused for everyone
+    #[serde(default)]
+    pub monitor_paused: bool,
+
+    /// Whether AI reply generation is available
+    #[serde(default = "default_ai_enabled")]
+    pub ai_enabled: bool,
+
+    /// Whether emails, phone numbers and masked words are masked in stored and exported comment text
+    #[serde(default)]
+    pub mask_sensitive_text: bool,
+
+    /// Whether replies and moderation actions are only logged and recorded instead of sent to YouTube
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// When the settings were last changed
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+fn default_ai_enabled() -> bool {
+    true
+}
+
+impl Default for RuntimeSettings {
+    fn default() -> Self {
+        Self {
+            log_filter: None,
+            youtube_requests_per_sec: None,
+            monitor_paused: false,
+            ai_enabled: true,
+            mask_sensitive_text: false,
+            dry_run: false,
+            updated_at: None,
+        }
+    }
+}
+
+/// A partial update of the runtime settings; omitted fields are left unchanged
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuntimeSettingsPatch {
+    /// New log filter; an empty string goes back to `RUST_LOG`
+    pub log_filter: Option<String>,
+
+    /// New YouTube request rate; `0` goes back to `YOUTUBE_MAX_REQUESTS_PER_SEC`
+    pub youtube_requests_per_sec: Option<u32>,
+
+    /// Pause or resume comment monitoring
+    pub monitor_paused: Option<bool>,
+
+    /// Turn AI reply generation on or off
+    pub ai_enabled: Option<bool>,
+
+    /// Turn masking of sensitive comment text on or off
+    pub mask_sensitive_text: Option<bool>,
+
+    /// Turn dry-run mode on or off for everyone
+    pub dry_run: Option<bool>,
+}
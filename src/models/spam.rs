@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Comment metadata key holding the spam classifier's score, `0.00` to `1.00`
+pub const SPAM_SCORE_KEY: &str = "spam_score";
+
+/// `spam` metadata value of comments the classifier flagged
+pub const CLASSIFIER_REASON: &str = "classifier";
+
+/// The user's verdict on a quarantined comment, kept when it is synced again
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpamReview {
+    /// Spam: rejected on YouTube and its author banned
+    Confirmed,
+
+    /// Not spam: back in the inbox and never flagged again
+    Restored,
+}
+
+/// A user's spam classifier, tuned by their reviews
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpamSettings {
+    /// The user the classifier belongs to
+    pub user_id: String,
+
+    /// Score from which comments are quarantined
+    pub threshold: f32,
+
+    /// Quarantined comments confirmed as spam
+    pub confirmed: u64,
+
+    /// Quarantined comments restored as not spam
+    pub restored: u64,
+
+    /// When the threshold last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl SpamSettings {
+    /// A classifier with no reviews yet
+    pub fn new(user_id: &str, threshold: f32) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            threshold,
+            confirmed: 0,
+            restored: 0,
+            updated_at: Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{Comment, InteractionRecord, Reply};
+
+/// A record published to the external event stream, for consumers such as a data warehouse.
+///
+/// Records are published at least once: one may be published again after a
+/// restart, so consumers should deduplicate on `event_id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct StreamEvent {
+    /// Unique ID of the event, stable if it is published again
+    pub event_id: String,
+
+    /// The user whose channel the event happened on
+    pub user_id: String,
+
+    /// When it happened
+    pub occurred_at: DateTime<Utc>,
+
+    #[serde(flatten)]
+    pub record: StreamRecord,
+}
+
+impl StreamEvent {
+    /// The last part of the subject or topic the event is published to
+    pub fn kind(&self) -> &'static str {
+        match self.record {
+            StreamRecord::Comment(_) => "comment",
+            StreamRecord::Reply(_) => "reply",
+            StreamRecord::Interaction(_) => "interaction",
+        }
+    }
+}
+
+/// What a stream event is about
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum StreamRecord {
+    /// A comment was received, or changed since it was last published
+    Comment(StreamComment),
+
+    /// A reply was posted
+    Reply(StreamReply),
+
+    /// Anything recorded in the interaction history
+    Interaction(InteractionRecord),
+}
+
+/// A comment as published to the stream, without what only the app needs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamComment {
+    pub comment_id: String,
+    pub video_id: String,
+    pub author: String,
+    pub author_channel_id: String,
+
+    /// Text as written, or as displayed if YouTube didn't provide that
+    pub text: String,
+
+    pub like_count: i32,
+    pub reply_count: i32,
+    pub published_at: DateTime<Utc>,
+
+    /// Sentiment score from -1.0 to 1.0, if analyzed
+    pub sentiment: Option<f32>,
+}
+
+impl From<&Comment> for StreamComment {
+    fn from(comment: &Comment) -> Self {
+        Self {
+            comment_id: comment.comment_id.clone(),
+            video_id: comment.video_id.clone(),
+            author: comment.author.clone(),
+            author_channel_id: comment.author_channel_id.clone(),
+            text: comment.original_text().to_string(),
+            like_count: comment.like_count,
+            reply_count: comment.reply_count,
+            published_at: comment.published_at,
+            sentiment: comment.sentiment,
+        }
+    }
+}
+
+/// A reply as published to the stream
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamReply {
+    pub reply_id: String,
+
+    /// The comment replied to
+    pub comment_id: String,
+
+    /// The video of the comment, if known
+    pub video_id: Option<String>,
+
+    pub author_channel_id: String,
+
+    /// Text as written, or as displayed if YouTube didn't provide that
+    pub text: String,
+
+    pub ai_generated: bool,
+    pub ai_model: Option<String>,
+    pub published_at: DateTime<Utc>,
+}
+
+impl StreamReply {
+    /// A reply stored with its comment
+    pub fn new(reply: &Reply, video_id: &str) -> Self {
+        Self {
+            reply_id: reply.reply_id.clone(),
+            comment_id: reply.parent_id.clone(),
+            video_id: Some(video_id.to_string()),
+            author_channel_id: reply.author_channel_id.clone(),
+            text: reply.original_text().to_string(),
+            ai_generated: reply.ai_generated,
+            ai_model: reply.ai_model.clone(),
+            published_at: reply.published_at,
+        }
+    }
+}
+
+/// How far the interaction history has been published to the stream
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StreamCursor {
+    /// Interactions recorded up to and including this time have been published
+    pub published_until: DateTime<Utc>,
+}
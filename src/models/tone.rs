@@ -0,0 +1,66 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// ID of the tone replies are written in unless a request, video or the user's preferences pick another
+pub const DEFAULT_TONE_ID: &str = "friendly";
+
+/// How AI replies sound: instructions for the AI, with example replies and a sampling temperature.
+///
+/// The built-in presets are shared by everyone; users can add their own.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TonePreset {
+    /// Unique ID for this tone; the built-ins' are their lowercase names, e.g. `friendly`
+    pub tone_id: String,
+
+    /// The user who created the tone; unset for the built-in presets
+    #[serde(default)]
+    pub user_id: Option<String>,
+
+    /// Name shown in the tone list
+    pub name: String,
+
+    /// How the AI should write in this tone
+    pub instructions: String,
+
+    /// Replies in this tone, shown to the AI as examples
+    #[serde(default)]
+    pub examples: Vec<String>,
+
+    /// Sampling temperature replies in this tone are generated with; the model's own if unset
+    #[serde(default)]
+    pub temperature: Option<f32>,
+
+    /// When the tone was created
+    pub created_at: DateTime<Utc>,
+
+    /// When the tone was last changed
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TonePreset {
+    /// Create a tone for a user
+    pub fn new(user_id: &str, name: &str, instructions: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            tone_id: Uuid::new_v4().to_string(),
+            user_id: Some(user_id.to_string()),
+            name: name.to_string(),
+            instructions: instructions.to_string(),
+            examples: Vec::new(),
+            temperature: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Whether the tone is one of the built-in presets
+    pub fn is_builtin(&self) -> bool {
+        self.user_id.is_none()
+    }
+
+    /// Whether a user can write in the tone: it is built in or theirs
+    pub fn is_visible_to(&self, user_id: &str) -> bool {
+        self.user_id.as_deref().is_none_or(|owner| owner == user_id)
+    }
+}
@@ -0,0 +1,376 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Shortest monitor interval a user may configure (in seconds)
+pub const MIN_MONITOR_INTERVAL_SECS: u64 = 60;
+
+/// Comment metadata key marking comments on a Short
+pub const SHORT_KEY: &str = "short";
+
+/// Longest video YouTube publishes as a Short (in seconds)
+pub const MAX_SHORT_DURATION_SECS: u64 = 180;
+
+/// Videos up to this long count as Shorts even without a `#shorts` tag (in seconds)
+const UNTAGGED_SHORT_DURATION_SECS: u64 = 60;
+
+/// A video on the user's channel, as stored in our database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Video {
+    /// YouTube video ID
+    pub video_id: String,
+
+    /// The user (channel owner) this video belongs to
+    pub user_id: String,
+
+    /// Video title
+    pub title: String,
+
+    /// Video description
+    pub description: String,
+
+    /// When the video was published
+    pub published_at: DateTime<Utc>,
+
+    /// URL to the video thumbnail
+    pub thumbnail_url: String,
+
+    /// Length of the video (in seconds), if YouTube reported it
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+
+    /// Whether the video is a Short
+    #[serde(default)]
+    pub format: VideoFormat,
+
+    /// Comment monitoring settings for this video
+    pub monitor: MonitorSettings,
+
+    /// When the monitor last checked this video for comments
+    pub last_checked_at: Option<DateTime<Utc>>,
+
+    /// When the video was last refreshed from YouTube
+    #[serde(default)]
+    pub synced_at: Option<DateTime<Utc>>,
+
+    /// The collection this video is in, if any
+    #[serde(default)]
+    pub collection_id: Option<String>,
+
+    /// Persona and tone for replies on this video, when a request doesn't set them
+    #[serde(default)]
+    pub reply_defaults: ReplyDefaults,
+
+    /// Additional metadata
+    pub metadata: HashMap<String, String>,
+}
+
+impl Video {
+    /// Whether the monitor should check this video for new comments now
+    pub fn is_monitor_due(&self, now: DateTime<Utc>) -> bool {
+        if !self.monitor.enabled {
+            return false;
+        }
+
+        match self.last_checked_at {
+            Some(last_checked) => now >= last_checked + Duration::seconds(self.monitor.interval_secs as i64),
+            None => true,
+        }
+    }
+}
+
+/// Where a page of a user's videos, newest first, ends; handed out as an opaque token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VideoCursor {
+    pub published_at: DateTime<Utc>,
+    pub video_id: String,
+}
+
+impl VideoCursor {
+    /// The position after a video
+    pub fn after(video: &Video) -> Self {
+        Self {
+            published_at: video.published_at,
+            video_id: video.video_id.clone(),
+        }
+    }
+
+    /// The cursor as a token for the next request
+    pub fn encode(&self) -> String {
+        let nanos = self.published_at.timestamp_nanos_opt().unwrap_or_default();
+        format!("{}.{}", nanos, self.video_id)
+    }
+
+    /// Read a token made by [`VideoCursor::encode`]
+    pub fn decode(token: &str) -> Option<Self> {
+        let (nanos, video_id) = token.split_once('.')?;
+        if video_id.is_empty() {
+            return None;
+        }
+        Some(Self {
+            published_at: Utc.timestamp_nanos(nanos.parse().ok()?),
+            video_id: video_id.to_string(),
+        })
+    }
+}
+
+/// Whether a video is a Short or a regular upload
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoFormat {
+    #[default]
+    LongForm,
+    Short,
+}
+
+impl VideoFormat {
+    /// Tell a Short from its length and tags.
+    ///
+    /// The API doesn't say whether a video is a Short, so a video is one if it
+    /// fits the Shorts length and is tagged `#shorts`, or is a minute or less.
+    /// Without a length only the tag counts; live streams report a length of zero.
+    pub fn detect(duration_secs: Option<u64>, title: &str, description: &str) -> Self {
+        let tagged = [title, description].iter().any(|text| text.to_lowercase().contains("#shorts"));
+
+        let short = match duration_secs.filter(|secs| *secs > 0) {
+            Some(secs) => secs <= UNTAGGED_SHORT_DURATION_SECS || (tagged && secs <= MAX_SHORT_DURATION_SECS),
+            None => tagged,
+        };
+
+        if short {
+            Self::Short
+        } else {
+            Self::LongForm
+        }
+    }
+}
+
+/// Parse an ISO 8601 duration as used by the YouTube API, e.g. `PT1M5S`, into seconds
+pub fn parse_duration(duration: &str) -> Option<u64> {
+    let mut rest = duration.strip_prefix('P')?;
+    let mut secs = 0;
+    let mut in_time = false;
+
+    while !rest.is_empty() {
+        if let Some(time) = rest.strip_prefix('T') {
+            in_time = true;
+            rest = time;
+            continue;
+        }
+
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let value: u64 = rest[..digits].parse().ok()?;
+        let unit = match (rest[digits..].chars().next()?, in_time) {
+            ('W', false) => 7 * 24 * 60 * 60,
+            ('D', false) => 24 * 60 * 60,
+            ('H', true) => 60 * 60,
+            ('M', true) => 60,
+            ('S', true) => 1,
+            _ => return None,
+        };
+        secs += value * unit;
+        rest = &rest[digits + 1..];
+    }
+
+    Some(secs)
+}
+
+/// Persona and tone used for a video's replies unless a request sets its own
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplyDefaults {
+    /// Persona to write as
+    #[serde(default)]
+    pub persona: Option<String>,
+
+    /// Tone of generated replies
+    #[serde(default)]
+    pub tone: Option<String>,
+}
+
+/// A moment of a video mentioned in a comment, e.g. "4:20"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VideoTimestamp {
+    /// Offset into the video (in seconds)
+    pub seconds: u32,
+
+    /// The timestamp as written in the comment
+    pub label: String,
+
+    /// Link opening the video at that moment
+    pub url: String,
+}
+
+/// Per-video comment monitoring settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorSettings {
+    /// Whether the video is monitored for new comments
+    pub enabled: bool,
+
+    /// How frequently to check the video for new comments (in seconds)
+    pub interval_secs: u64,
+
+    /// Whether new comments on this video may be replied to automatically
+    pub auto_reply: bool,
+
+    /// Whether the user has changed these settings; if not, they follow the video's age
+    pub customized: bool,
+}
+
+impl MonitorSettings {
+    /// Default settings for a video based on how long ago it was published.
+    ///
+    /// New uploads get most of the comments, so they are checked often; old
+    /// uploads are checked rarely, and videos older than a year are not
+    /// monitored at all unless the user turns it on.
+    pub fn for_video_age(published_at: DateTime<Utc>, now: DateTime<Utc>) -> Self {
+        let age = now - published_at;
+
+        let (enabled, interval_secs) = if age < Duration::days(2) {
+            (true, 5 * 60)
+        } else if age < Duration::days(14) {
+            (true, 30 * 60)
+        } else if age < Duration::days(90) {
+            (true, 6 * 60 * 60)
+        } else if age < Duration::days(365) {
+            (true, 24 * 60 * 60)
+        } else {
+            (false, 24 * 60 * 60)
+        };
+
+        Self {
+            enabled,
+            interval_secs,
+            auto_reply: false,
+            customized: false,
+        }
+    }
+}
+
+/// How often a video's comments are synced in full, to pick up new replies on older threads
+pub const FULL_SYNC_INTERVAL_DAYS: i64 = 7;
+
+/// How far a video's comments have been synced, so later syncs only fetch what is new
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CommentSyncState {
+    pub video_id: String,
+
+    /// When the newest comment synced so far was published
+    pub newest_published_at: Option<DateTime<Utc>>,
+
+    /// When every page of comments was last fetched
+    pub full_synced_at: Option<DateTime<Utc>>,
+}
+
+impl CommentSyncState {
+    /// Comments published after this time are all a sync needs; `None` when a full sync is due
+    pub fn fetch_since(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let full_sync_due = self
+            .full_synced_at
+            .is_none_or(|at| now - at >= Duration::days(FULL_SYNC_INTERVAL_DAYS));
+        if full_sync_due {
+            return None;
+        }
+        self.newest_published_at
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monitor_defaults_by_age() {
+        let now = Utc::now();
+
+        let fresh = MonitorSettings::for_video_age(now - Duration::hours(3), now);
+        assert!(fresh.enabled);
+        assert_eq!(fresh.interval_secs, 5 * 60);
+        assert!(!fresh.auto_reply);
+
+        let month_old = MonitorSettings::for_video_age(now - Duration::days(30), now);
+        assert!(month_old.enabled);
+        assert_eq!(month_old.interval_secs, 6 * 60 * 60);
+
+        let ancient = MonitorSettings::for_video_age(now - Duration::days(800), now);
+        assert!(!ancient.enabled);
+    }
+
+    #[test]
+    fn test_detect_shorts() {
+        assert_eq!(parse_duration("PT1M5S"), Some(65));
+        assert_eq!(parse_duration("P1DT2H"), Some(26 * 60 * 60));
+        assert_eq!(parse_duration("PT45"), None);
+        assert_eq!(parse_duration("1M5S"), None);
+
+        assert_eq!(VideoFormat::detect(Some(45), "Quick tip", ""), VideoFormat::Short);
+        assert_eq!(VideoFormat::detect(Some(150), "Quick tip #Shorts", ""), VideoFormat::Short);
+        assert_eq!(VideoFormat::detect(Some(150), "Quick tip", ""), VideoFormat::LongForm);
+        assert_eq!(VideoFormat::detect(Some(600), "Full tutorial", "#shorts version linked"), VideoFormat::LongForm);
+        assert_eq!(VideoFormat::detect(None, "Quick tip", "#shorts"), VideoFormat::Short);
+    }
+
+    #[test]
+    fn test_is_monitor_due() {
+        let now = Utc::now();
+        let mut video = Video {
+            video_id: "dQw4w9WgXcQ".to_string(),
+            user_id: "user".to_string(),
+            title: "Title".to_string(),
+            description: String::new(),
+            published_at: now - Duration::hours(1),
+            thumbnail_url: String::new(),
+            duration_secs: None,
+            format: VideoFormat::LongForm,
+            monitor: MonitorSettings::for_video_age(now - Duration::hours(1), now),
+            last_checked_at: None,
+            synced_at: None,
+            collection_id: None,
+            reply_defaults: ReplyDefaults::default(),
+            metadata: HashMap::new(),
+        };
+
+        // Never checked
+        assert!(video.is_monitor_due(now));
+
+        // Checked within the interval
+        video.last_checked_at = Some(now - Duration::minutes(1));
+        assert!(!video.is_monitor_due(now));
+
+        // Interval elapsed
+        video.last_checked_at = Some(now - Duration::minutes(6));
+        assert!(video.is_monitor_due(now));
+
+        // Disabled videos are never due
+        video.monitor.enabled = false;
+        assert!(!video.is_monitor_due(now));
+    }
+
+    #[test]
+    fn test_video_cursor_round_trip() {
+        let cursor = VideoCursor {
+            published_at: Utc::now(),
+            video_id: "a-b_c.d".to_string(),
+        };
+        assert_eq!(VideoCursor::decode(&cursor.encode()), Some(cursor));
+
+        assert_eq!(VideoCursor::decode("not-a-cursor"), None);
+        assert_eq!(VideoCursor::decode("123."), None);
+    }
+
+    #[test]
+    fn test_sync_fetches_since_newest_comment_until_full_sync_is_due() {
+        let now = Utc::now();
+        let newest = now - Duration::hours(3);
+        let mut state = CommentSyncState {
+            video_id: "v1".to_string(),
+            newest_published_at: Some(newest),
+            full_synced_at: Some(now - Duration::days(1)),
+        };
+        assert_eq!(state.fetch_since(now), Some(newest));
+
+        state.full_synced_at = Some(now - Duration::days(FULL_SYNC_INTERVAL_DAYS));
+        assert_eq!(state.fetch_since(now), None);
+
+        assert_eq!(CommentSyncState::default().fetch_since(now), None);
+    }
+}
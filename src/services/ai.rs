@@ -1,36 +1,49 @@
-use anyhow::{Context, Result};
+#[cfg(feature = "openai")]
+use anyhow::Context;
+use anyhow::Result;
+use async_trait::async_trait;
 use chrono::Utc;
 use reqwest::Client;
+#[cfg(feature = "openai")]
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tracing::{error, info};
 use uuid::Uuid;
 
 use crate::db::Database;
-use crate::models::ai::{AiModelConfig, AiModelParameters, ReplyGenerationRequest, ReplyGenerationResponse, AiUsageStats};
-use crate::models::auth::{User, ReplyTone};
+use crate::error::AppError;
+use crate::i18n;
+use crate::models::Reply;
+use crate::models::ai::{AiModelConfig, AiTask, ParameterOverrides, ReplyGenerationRequest, ReplyGenerationResponse, AiUsageStats, AiUsageRecord};
+use crate::services::condense;
+use crate::services::conversations::OwnReplies;
+use crate::services::model_catalog::{self, ModelSeed};
+use crate::services::prompts::PromptLibrary;
+use crate::utils::upstream::{Attempts, Upstream};
 
 /// OpenAI API response
+#[cfg(feature = "openai")]
 #[derive(Debug, Deserialize)]
 struct OpenAiResponse {
-    id: String,
     choices: Vec<OpenAiChoice>,
     usage: OpenAiUsage,
 }
 
+#[cfg(feature = "openai")]
 #[derive(Debug, Deserialize)]
 struct OpenAiChoice {
     message: OpenAiMessage,
-    finish_reason: String,
 }
 
+#[cfg(feature = "openai")]
 #[derive(Debug, Deserialize)]
 struct OpenAiMessage {
-    role: String,
     content: String,
 }
 
+#[cfg(feature = "openai")]
 #[derive(Debug, Deserialize)]
 struct OpenAiUsage {
     prompt_tokens: usize,
@@ -39,6 +52,7 @@ struct OpenAiUsage {
 }
 
 /// OpenAI API request
+#[cfg(feature = "openai")]
 #[derive(Debug, Serialize)]
 struct OpenAiRequest {
     model: String,
@@ -50,96 +64,223 @@ struct OpenAiRequest {
     presence_penalty: f32,
 }
 
+#[cfg(feature = "openai")]
 #[derive(Debug, Serialize)]
 struct OpenAiRequestMessage {
     role: String,
     content: String,
 }
 
+/// OpenAI embeddings request
+#[cfg(feature = "openai")]
+#[derive(Debug, Serialize)]
+struct OpenAiEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+/// OpenAI embeddings response
+#[cfg(feature = "openai")]
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[cfg(feature = "openai")]
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbedding {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// A completion from the AI provider
+struct Completion {
+    text: String,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+    total_tokens: usize,
+}
+
+/// OpenAI API base URL, unless `OPENAI_API_BASE_URL` is set (e.g. to a stub for load tests)
+const DEFAULT_OPENAI_API_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// The OpenAI API base URL requests are sent to
+pub fn api_base() -> String {
+    env::var("OPENAI_API_BASE_URL").unwrap_or_else(|_| DEFAULT_OPENAI_API_BASE_URL.to_string())
+}
+
+/// Most characters of the existing reply thread quoted in a prompt
+const THREAD_BUDGET_CHARS: usize = 2000;
+
+/// Longest single thread reply quoted in a prompt
+const THREAD_REPLY_CHARS: usize = 300;
+
+/// Token budget of replies on Shorts, unless the request sets its own
+pub const SHORTS_MAX_TOKENS: usize = 60;
+
+/// Token budget of replies de-escalating a heated thread; a long answer reads as defensive
+pub const DE_ESCALATION_MAX_TOKENS: usize = 80;
+
+/// Model recorded for a saved reply used as it is, when the AI couldn't personalize it
+pub const TEMPLATE_MODEL: &str = "template";
+
+/// Model a task uses when no route is configured for it in `model_routing`
+pub const DEFAULT_MODEL: &str = "gpt-3.5-turbo";
+
+/// Embedding model, unless `OPENAI_EMBEDDING_MODEL` is set
+#[cfg(feature = "openai")]
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Most texts embedded in one provider call
+#[cfg(feature = "openai")]
+const EMBEDDING_BATCH: usize = 100;
+
+/// The reply generation handlers rely on, so tests can swap in a fake
+#[async_trait]
+pub trait AiApi: Send + Sync {
+    /// Generate a reply to a comment on behalf of a user
+    async fn generate_reply(&self, user_id: &str, request: &ReplyGenerationRequest) -> Result<ReplyGenerationResponse>;
+    
+    /// Embed texts as vectors whose cosine similarity reflects how alike their meaning is
+    async fn embed(&self, user_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
 /// AI service for generating replies
+#[cfg_attr(not(feature = "openai"), allow(dead_code))]
 pub struct AiService {
     db: Database,
     client: Client,
+    upstream: Arc<Upstream>,
+    prompts: Arc<PromptLibrary>,
+    api_base: String,
 }
 
 impl AiService {
     /// Create a new AI service
-    pub fn new(db: Database) -> Self {
-        let client = Client::new();
-        Self { db, client }
+    pub fn new(db: Database, client: Client, upstream: Arc<Upstream>, prompts: Arc<PromptLibrary>) -> Self {
+        Self { db, client, upstream, prompts, api_base: api_base() }
+    }
+    
+    /// Store the models the seed names, merged with those already stored
+    pub async fn seed_models(&self, seed: &ModelSeed) -> Result<()> {
+        match seed {
+            ModelSeed::Off => info!("AI model seeding is off, keeping the stored models"),
+            ModelSeed::Builtin => self.init_default_models().await?,
+            ModelSeed::File(path) => {
+                let entries = model_catalog::load_catalog(path)?;
+                let count = entries.len();
+                for entry in entries {
+                    let stored = self.db.get_ai_model(&entry.model_id).await?;
+                    self.db.save_ai_model(&entry.merge(stored)).await?;
+                }
+                info!("Seeded {} AI models from {}", count, path.display());
+            }
+        }
+
+        Ok(())
     }
     
-    /// Initialize default AI models
+    /// Store the built-in AI models that aren't stored yet
     pub async fn init_default_models(&self) -> Result<()> {
-        // GPT-3.5 Turbo
-        let gpt35_turbo = AiModelConfig {
-            model_id: "gpt-3.5-turbo".to_string(),
-            name: "GPT-3.5 Turbo".to_string(),
-            description: "A good balance of quality and speed for most reply generation needs".to_string(),
-            max_context_length: 4096,
-            max_response_length: 1024,
-            parameters: AiModelParameters {
-                temperature: 0.7,
-                top_p: 1.0,
-                frequency_penalty: 0.0,
-                presence_penalty: 0.0,
-                max_tokens: 1024,
-                stop: vec![],
-                additional: Default::default(),
-            },
-            is_available: true,
-            metadata: Default::default(),
-        };
-        
-        // GPT-4
-        let gpt4 = AiModelConfig {
-            model_id: "gpt-4".to_string(),
-            name: "GPT-4".to_string(),
-            description: "Highest quality replies with better understanding of context and nuance".to_string(),
-            max_context_length: 8192,
-            max_response_length: 2048,
-            parameters: AiModelParameters {
-                temperature: 0.7,
-                top_p: 1.0,
-                frequency_penalty: 0.0,
-                presence_penalty: 0.0,
-                max_tokens: 2048,
-                stop: vec![],
-                additional: Default::default(),
-            },
-            is_available: true,
-            metadata: Default::default(),
-        };
-        
-        // Save models to database
-        self.db.save_ai_model(&gpt35_turbo).await?;
-        self.db.save_ai_model(&gpt4).await?;
+        for model in model_catalog::builtin_models() {
+            if self.db.get_ai_model(&model.model_id).await?.is_none() {
+                self.db.save_ai_model(&model).await?;
+            }
+        }
         
         info!("Initialized default AI models");
         
         Ok(())
     }
     
-    /// Generate a reply to a comment
-    pub async fn generate_reply(&self, request: &ReplyGenerationRequest) -> Result<ReplyGenerationResponse> {
-        // Get the AI model configuration
-        let model_id = if let Some(override_model) = request.parameter_overrides.as_ref().and_then(|p| p.get("model")) {
-            override_model.as_str().unwrap_or("gpt-3.5-turbo").to_string()
-        } else {
-            "gpt-3.5-turbo".to_string()
+    /// The model a task uses by default: the one routed to it in `model_routing`, else [`DEFAULT_MODEL`]
+    pub async fn model_for(&self, task: AiTask) -> Result<String> {
+        Ok(self.db.get_model_route(task).await?
+            .map(|route| route.model_id)
+            .unwrap_or_else(|| DEFAULT_MODEL.to_string()))
+    }
+    
+    /// Generate a reply to a comment on behalf of a user
+    pub async fn generate_reply(&self, user_id: &str, request: &ReplyGenerationRequest) -> Result<ReplyGenerationResponse> {
+        // Get the AI model configuration; a request may pick its own model
+        let overrides = request.parameter_overrides.clone().unwrap_or_default();
+        let model_id = match &overrides.model {
+            Some(model_id) => model_id.clone(),
+            None => self.model_for(AiTask::Reply).await?,
         };
         
-        let model = match self.db.get_ai_model(&model_id).await? {
+        let mut model = match self.db.get_ai_model(&model_id).await? {
             Some(m) => m,
-            None => anyhow::bail!("AI model {} not found", model_id),
+            None => return Err(AppError::NotFound(format!("AI model {}", model_id)).into()),
+        };
+        validate_overrides(&overrides, &model)?;
+        
+        // A tone deleted since it was picked falls back to a neutral one
+        let tone = self.db.get_tone_preset(&request.tone).await?.filter(|tone| tone.is_visible_to(user_id));
+        if let Some(temperature) = tone.as_ref().and_then(|tone| tone.temperature) {
+            model.parameters.temperature = temperature;
+        }
+        if let Some(max_length) = request.max_length {
+            model.parameters.max_tokens = max_length;
+        }
+        
+        // The request's own parameters win over the tone's and the video format's
+        overrides.apply(&mut model.parameters);
+        
+        // Build the prompt from the current templates, so edits apply without a restart
+        let prompts = self.prompts.current();
+        let system_message = prompts.system_message(tone.as_ref(), request.persona.as_deref());
+        
+        // Shorten what doesn't fit the model's context window, rather than have the provider refuse it
+        let mut request = request.clone();
+        let condensed = condense::fit_request(&mut request, condense::prompt_budget(&model, &system_message));
+        if !condensed.is_empty() {
+            info!("Condensed the {} of the prompt to fit model {}", condensed.join(", "), model.model_id);
+        }
+        let user_message = build_user_message(&request);
+        
+        // Send request to the provider
+        let start_time = std::time::Instant::now();
+        let result = self.complete(&model, system_message, user_message).await;
+        let generation_time = start_time.elapsed().as_millis() as u64;
+        
+        let completion = match result {
+            Ok(completion) => completion,
+            Err(e) => {
+                self.record_usage(user_id, &model, None, generation_time, Some(e.to_string())).await;
+                return Err(e);
+            }
         };
         
-        // Build the prompt
-        let system_message = self.build_system_message(&request.tone);
-        let user_message = self.build_user_message(request);
+        self.record_usage(user_id, &model, Some(&completion), generation_time, None).await;
         
-        // Create OpenAI request
-        let openai_request = OpenAiRequest {
+        let mut metadata = HashMap::from([("prompt_version".to_string(), prompts.version.to_string())]);
+        if !condensed.is_empty() {
+            metadata.insert(condense::CONDENSED_KEY.to_string(), condensed.join(","));
+        }
+        
+        // Create response
+        let response = ReplyGenerationResponse {
+            reply_text: completion.text,
+            alternatives: vec![],
+            model: model.model_id,
+            generated_at: Utc::now(),
+            metadata,
+            usage: AiUsageStats {
+                prompt_tokens: completion.prompt_tokens,
+                completion_tokens: completion.completion_tokens,
+                total_tokens: completion.total_tokens,
+                generation_time_ms: generation_time,
+            },
+        };
+        
+        Ok(response)
+    }
+    
+    /// Generate a completion with OpenAI's chat completions API
+    #[cfg(feature = "openai")]
+    async fn complete(&self, model: &AiModelConfig, system_message: String, user_message: String) -> Result<Completion> {
+        let request = OpenAiRequest {
             model: model.model_id.clone(),
             messages: vec![
                 OpenAiRequestMessage {
@@ -152,7 +293,7 @@ impl AiService {
                 },
             ],
             temperature: model.parameters.temperature,
-            max_tokens: request.max_length.unwrap_or(model.parameters.max_tokens),
+            max_tokens: model.parameters.max_tokens,
             top_p: model.parameters.top_p,
             frequency_penalty: model.parameters.frequency_penalty,
             presence_penalty: model.parameters.presence_penalty,
@@ -162,87 +303,254 @@ impl AiService {
         let api_key = env::var("OPENAI_API_KEY")
             .context("OPENAI_API_KEY environment variable not set")?;
         
-        // Send request to OpenAI
-        let start_time = std::time::Instant::now();
-        let response = self.client
-            .post("https://api.openai.com/v1/chat/completions")
+        let request = self.client
+            .post(format!("{}/chat/completions", self.api_base))
             .header("Authorization", format!("Bearer {}", api_key))
-            .json(&openai_request)
-            .send()
-            .await?;
-        
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("OpenAI API error: {}", error_text);
-            anyhow::bail!("Failed to generate reply: {}", error_text);
-        }
+            .json(&request);
+        let response = self.upstream
+            .call(request, Attempts::Retry, "Failed to generate reply", |_, message| AppError::AiProvider(message))
+            .await
+            .map_err(provider_error)?;
         
         let openai_response: OpenAiResponse = response.json().await?;
-        let generation_time = start_time.elapsed().as_millis() as u64;
         
         // Extract the generated reply
-        let reply_text = openai_response.choices.get(0)
+        let text = openai_response.choices.first()
             .map(|c| c.message.content.clone())
             .unwrap_or_default();
         
-        // Create response
-        let response = ReplyGenerationResponse {
-            reply_text,
-            alternatives: vec![],
-            model: model.model_id,
-            generated_at: Utc::now(),
-            metadata: HashMap::new(),
-            usage: AiUsageStats {
-                prompt_tokens: openai_response.usage.prompt_tokens,
-                completion_tokens: openai_response.usage.completion_tokens,
-                total_tokens: openai_response.usage.total_tokens,
-                generation_time_ms: generation_time,
-            },
-        };
-        
-        Ok(response)
+        Ok(Completion {
+            text,
+            prompt_tokens: openai_response.usage.prompt_tokens,
+            completion_tokens: openai_response.usage.completion_tokens,
+            total_tokens: openai_response.usage.total_tokens,
+        })
     }
     
-    /// Build the system message for the AI
-    fn build_system_message(&self, tone: &str) -> String {
-        let base_instructions = "You are an assistant helping a YouTube content creator respond to comments on their videos. \
-            Your goal is to write thoughtful, authentic replies that engage with the commenter and foster a positive community. \
-            Keep replies concise, friendly, and conversational. Avoid generic responses.";
+    /// Embed texts with OpenAI's embeddings API, in order
+    #[cfg(feature = "openai")]
+    pub async fn embed(&self, user_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let api_key = env::var("OPENAI_API_KEY")
+            .context("OPENAI_API_KEY environment variable not set")?;
+        let model = env::var("OPENAI_EMBEDDING_MODEL").unwrap_or_else(|_| DEFAULT_EMBEDDING_MODEL.to_string());
         
-        let tone_instructions = match tone {
-            "professional" => "Maintain a professional and informative tone. Be helpful and knowledgeable while remaining approachable.",
-            "friendly" => "Be warm, casual, and conversational. Use a friendly tone as if chatting with someone you know well.",
-            "enthusiastic" => "Be energetic and excited in your response. Show enthusiasm and appreciation for the commenter.",
-            "helpful" => "Focus on being as helpful as possible. Provide useful information and address any questions thoroughly.",
-            _ => "Use a balanced, friendly tone that's authentic and engaging.",
-        };
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(EMBEDDING_BATCH) {
+            let request = self.client
+                .post(format!("{}/embeddings", self.api_base))
+                .header("Authorization", format!("Bearer {}", api_key))
+                .json(&OpenAiEmbeddingRequest { model: &model, input: batch });
+            let response = self.upstream
+                .call(request, Attempts::Retry, "Failed to embed comments", |_, message| AppError::AiProvider(message))
+                .await
+                .map_err(provider_error)?;
+            
+            let mut response: OpenAiEmbeddingResponse = response.json().await?;
+            if response.data.len() != batch.len() {
+                return Err(AppError::AiProvider(format!("Expected {} embeddings, got {}", batch.len(), response.data.len())).into());
+            }
+            response.data.sort_by_key(|e| e.index);
+            embeddings.extend(response.data.into_iter().map(|e| e.embedding));
+        }
         
-        format!("{}\n\n{}", base_instructions, tone_instructions)
+        info!("Embedded {} texts for user {} with {}", texts.len(), user_id, model);
+        Ok(embeddings)
+    }
+    
+    /// No AI provider is compiled in
+    #[cfg(not(feature = "openai"))]
+    pub async fn embed(&self, _user_id: &str, _texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Err(AppError::AiProvider("No AI provider is enabled in this build".to_string()).into())
+    }
+    
+    /// No AI provider is compiled in
+    #[cfg(not(feature = "openai"))]
+    async fn complete(&self, _model: &AiModelConfig, _system_message: String, _user_message: String) -> Result<Completion> {
+        Err(AppError::AiProvider("No AI provider is enabled in this build".to_string()).into())
     }
     
-    /// Build the user message containing the comment to reply to
-    fn build_user_message(&self, request: &ReplyGenerationRequest) -> String {
-        let mut message = format!(
-            "Please write a reply to the following comment on my YouTube video titled \"{}\":\n\n",
-            request.video_title
-        );
+    /// Record the tokens, latency and cost of a provider call; failures are only logged
+    async fn record_usage(
+        &self,
+        user_id: &str,
+        model: &AiModelConfig,
+        usage: Option<&Completion>,
+        latency_ms: u64,
+        error: Option<String>,
+    ) {
+        let (prompt_tokens, completion_tokens, total_tokens) = usage
+            .map(|u| (u.prompt_tokens, u.completion_tokens, u.total_tokens))
+            .unwrap_or((0, 0, 0));
         
-        message.push_str(&format!("Comment from {}: \"{}\"\n\n", request.comment_author, request.comment_text));
+        let record = AiUsageRecord {
+            usage_id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            model: model.model_id.clone(),
+            prompt_tokens,
+            completion_tokens,
+            total_tokens,
+            latency_ms,
+            cost_usd: model.cost_for(prompt_tokens, completion_tokens),
+            success: error.is_none(),
+            error,
+            created_at: Utc::now(),
+        };
         
-        if !request.previous_interactions.is_empty() {
-            message.push_str("Previous interactions with this commenter:\n");
-            for interaction in &request.previous_interactions {
-                message.push_str(&format!("- {}\n", interaction));
-            }
-            message.push('\n');
+        if let Err(e) = self.db.record_ai_usage(&record).await {
+            error!("Error recording AI usage: {}", e);
         }
-        
-        if let Some(instructions) = &request.additional_instructions {
-            message.push_str(&format!("Additional instructions: {}\n\n", instructions));
+    }
+}
+
+#[async_trait]
+impl AiApi for AiService {
+    async fn generate_reply(&self, user_id: &str, request: &ReplyGenerationRequest) -> Result<ReplyGenerationResponse> {
+        AiService::generate_reply(self, user_id, request).await
+    }
+    
+    async fn embed(&self, user_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        AiService::embed(self, user_id, texts).await
+    }
+}
+
+/// A failed call to the provider; an open circuit stays `Unavailable`, so callers can tell and fall back, and error responses are already typed
+#[cfg(feature = "openai")]
+fn provider_error(e: anyhow::Error) -> AppError {
+    match e.downcast::<AppError>() {
+        Ok(e @ (AppError::Unavailable(_) | AppError::AiProvider(_))) => e,
+        Ok(e) => AppError::AiProvider(e.to_string()),
+        Err(e) => AppError::AiProvider(e.to_string()),
+    }
+}
+
+/// Check that a request's parameter overrides are within what the model accepts
+pub fn validate_overrides(overrides: &ParameterOverrides, model: &AiModelConfig) -> Result<()> {
+    if let Some(temperature) = overrides.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(AppError::Validation("temperature must be between 0 and 2".to_string()).into());
         }
-        
-        message.push_str("Write only the reply text without any additional formatting or explanation.");
-        
-        message
     }
+    
+    if let Some(top_p) = overrides.top_p {
+        if !(0.0..=1.0).contains(&top_p) {
+            return Err(AppError::Validation("top_p must be between 0 and 1".to_string()).into());
+        }
+    }
+    
+    if let Some(max_tokens) = overrides.max_tokens {
+        if max_tokens == 0 || max_tokens > model.max_response_length {
+            return Err(AppError::Validation(format!(
+                "max_tokens must be between 1 and {} for {}",
+                model.max_response_length, model.model_id
+            )).into());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Whether an error means the AI provider can't be reached for now, rather than that a request failed
+pub fn is_unavailable(e: &anyhow::Error) -> bool {
+    matches!(e.downcast_ref::<AppError>(), Some(AppError::Unavailable(_)))
+}
+
+/// A saved reply as it is, standing in for a generated reply while the AI is unavailable
+pub fn template_reply(template: &str) -> ReplyGenerationResponse {
+    ReplyGenerationResponse {
+        reply_text: template.to_string(),
+        alternatives: vec![],
+        model: TEMPLATE_MODEL.to_string(),
+        generated_at: Utc::now(),
+        metadata: HashMap::new(),
+        usage: AiUsageStats::default(),
+    }
+}
+
+/// The latest replies of a thread that fit the prompt budget, oldest first, as `author: "text"`;
+/// the user's own replies are attributed to "You"
+pub fn thread_context(replies: &[Reply], own: &OwnReplies) -> Vec<String> {
+    let mut sorted: Vec<&Reply> = replies.iter().collect();
+    sorted.sort_by_key(|reply| reply.published_at);
+    
+    let mut context = Vec::new();
+    let mut used = 0;
+    for reply in sorted.into_iter().rev() {
+        let text = reply.original_text();
+        let text: String = if text.chars().count() > THREAD_REPLY_CHARS {
+            format!("{}…", text.chars().take(THREAD_REPLY_CHARS).collect::<String>())
+        } else {
+            text.to_string()
+        };
+        let author = if own.contains(reply) { "You" } else { reply.author.as_str() };
+        let line = format!("{}: \"{}\"", author, text);
+        used += line.chars().count();
+        if used > THREAD_BUDGET_CHARS {
+            break;
+        }
+        context.push(line);
+    }
+    
+    context.reverse();
+    context
+}
+
+/// Build the user message containing the comment to reply to
+pub fn build_user_message(request: &ReplyGenerationRequest) -> String {
+    let mut message = format!(
+        "Please write a reply to the following comment on my YouTube video titled \"{}\":\n\n",
+        request.video_title
+    );
+    
+    message.push_str(&format!("Comment from {}: \"{}\"\n\n", request.comment_author, request.comment_text));
+    
+    if !request.previous_interactions.is_empty() {
+        message.push_str("Previous interactions with this commenter:\n");
+        for interaction in &request.previous_interactions {
+            message.push_str(&format!("- {}\n", interaction));
+        }
+        message.push('\n');
+    }
+    
+    if !request.timestamps.is_empty() {
+        let moments: Vec<String> = request.timestamps.iter().map(|t| format!("{} ({})", t.label, t.url)).collect();
+        message.push_str(&format!("The comment refers to these moments of the video: {}. ", moments.join(", ")));
+        message.push_str("Mention the timestamp when the reply is about what happens there.\n\n");
+    }
+    
+    if !request.thread_replies.is_empty() {
+        message.push_str("Replies already in this thread, oldest first:\n");
+        for reply in &request.thread_replies {
+            message.push_str(&format!("- {}\n", reply));
+        }
+        message.push_str("Build on this conversation: don't repeat what has already been answered, ");
+        message.push_str("and add to or correct it where that helps.\n\n");
+    }
+    
+    if let Some(follow_up) = &request.follow_up {
+        message.push_str(&format!("The commenter followed up on your reply: {}\n", follow_up));
+        message.push_str("Answer this follow-up in light of the whole exchange above, rather than the original comment alone.\n\n");
+    }
+    
+    if request.de_escalate {
+        message.push_str("The commenter is upset or hostile. De-escalate: keep the reply short and calm, ");
+        message.push_str("acknowledge any fair point, and don't argue, defend yourself, match their tone or answer insults.\n\n");
+    }
+    
+    if let Some(template) = &request.template {
+        message.push_str("Base the reply on this saved reply. Keep its structure, facts, links and calls to action, ");
+        message.push_str("and only adjust the wording so it answers this comment personally:\n");
+        message.push_str(&format!("\"{}\"\n\n", template));
+    }
+    
+    if let Some(instructions) = &request.additional_instructions {
+        message.push_str(&format!("Additional instructions: {}\n\n", instructions));
+    }
+    
+    if let Some(language) = &request.reply_language {
+        message.push_str(&format!("Write the reply in {}.\n\n", i18n::language_name(language)));
+    }
+    
+    message.push_str("Write only the reply text without any additional formatting or explanation.");
+    
+    message
 }
@@ -0,0 +1,75 @@
+use anyhow::Result;
+
+use crate::error::AppError;
+use crate::models::Comment;
+use crate::models::alert::AlertRule;
+
+/// Most alert rules a user can have
+pub const MAX_ALERT_RULES_PER_USER: usize = 50;
+
+/// Words a question usually starts with, in the supported languages
+const QUESTION_WORDS: &[&str] = &[
+    "how", "what", "why", "when", "where", "who", "which", "can", "could", "does", "do", "is", "are", "will",
+    "would", "should", "cómo", "qué", "por qué", "cuándo", "dónde", "pourquoi", "quand", "où", "wie", "warum",
+    "wann", "onde", "quando",
+];
+
+/// Check that an alert rule can be stored: it has a name and something to alert on
+pub fn validate(rule: &AlertRule) -> Result<()> {
+    if rule.name.trim().is_empty() {
+        return Err(AppError::Validation("Alert name must not be empty".to_string()).into());
+    }
+
+    if !rule.questions && rule.phrases.iter().all(|p| p.trim().is_empty()) {
+        return Err(AppError::Validation("An alert needs phrases or questions: true".to_string()).into());
+    }
+
+    Ok(())
+}
+
+/// Whether a comment asks something: it has a question mark or starts with a question word
+pub fn is_question(text: &str) -> bool {
+    if text.contains('?') || text.contains('¿') {
+        return true;
+    }
+
+    let text = text.trim_start().to_lowercase();
+    QUESTION_WORDS.iter().any(|word| {
+        text.strip_prefix(word)
+            .is_some_and(|rest| rest.chars().next().is_none_or(|c| !c.is_alphanumeric() && c != '\'' && c != '’'))
+    })
+}
+
+/// What made a comment match an alert rule: the phrase it contains, or `question`
+pub fn trigger(rule: &AlertRule, comment: &Comment) -> Option<String> {
+    if !rule.enabled || rule.video_id.as_ref().is_some_and(|id| *id != comment.video_id) {
+        return None;
+    }
+
+    let text = comment.text.to_lowercase();
+    let phrase = rule
+        .phrases
+        .iter()
+        .map(|p| p.trim())
+        .find(|p| !p.is_empty() && text.contains(&p.to_lowercase()));
+    if let Some(phrase) = phrase {
+        return Some(phrase.to_string());
+    }
+
+    (rule.questions && is_question(&comment.text)).then(|| "question".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_question() {
+        assert!(is_question("Does this work on Windows"));
+        assert!(is_question("¿Funciona en Windows?"));
+        assert!(is_question("which lens is that?"));
+        assert!(!is_question("Doesn't work for me"));
+        assert!(!is_question("Can't wait for part 2"));
+        assert!(!is_question("Great video"));
+    }
+}
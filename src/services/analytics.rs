@@ -0,0 +1,752 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tracing::info;
+
+use crate::db::Database;
+use crate::models::{Comment, InteractionRecord, InteractionType};
+use crate::models::ai::AiUsageRecord;
+use crate::models::analytics::{
+    AiAnalytics, AnalyticsOverview, CommentVolume, DailyCost, DailyExportRow, DailyRollup, Granularity, KeywordStats, ModelUsage,
+    SentimentBucket, SentimentTrend, VideoComparison, VolumeBucket,
+};
+use crate::models::video::{Video, VideoFormat};
+use crate::models::job::JobItemResult;
+use crate::services::jobs::JobHandle;
+use crate::services::keywords::TermCounter;
+use crate::services::sentiment::{self, SentimentLabel};
+use crate::utils::time_zone::{local_day, start_of_day};
+
+/// How far before the start of a period to look for the comments that replies
+/// in the period answer, so time-to-reply works for replies to older comments
+const REPLY_LOOKBACK_DAYS: i64 = 90;
+
+/// Number of top keywords and hashtags kept per video and for the channel
+const TOP_TERMS: usize = 100;
+
+/// Service computing engagement analytics from the interaction history
+pub struct AnalyticsService {
+    db: Database,
+}
+
+impl AnalyticsService {
+    /// Create a new analytics service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Compute the engagement overview for the last `days` days (including today, in
+    /// the user's time zone), optionally only for Shorts or only for long-form videos.
+    ///
+    /// The channel's daily rollups are read from storage for days that had ended when
+    /// they were computed; today's and any missing ones are computed and persisted.
+    /// Rollups of a single format are always computed and never stored.
+    pub async fn overview(&self, user_id: &str, days: u32, video_format: Option<VideoFormat>) -> Result<AnalyticsOverview> {
+        let tz = self.time_zone(user_id).await?;
+        let to = local_day(Utc::now(), tz);
+        let from = to - Duration::days(days.max(1) as i64 - 1);
+
+        let mut activity = self.load_activity(user_id, from, tz).await?;
+        let daily = match video_format {
+            Some(format) => {
+                let video_ids: HashSet<String> = self.user_video_ids(user_id, None, Some(format)).await?.into_iter().collect();
+                activity.retain_videos(&video_ids);
+                activity.daily_rollups(user_id, from, to)
+            }
+            None => self.channel_rollups(user_id, &activity, from, to).await?,
+        };
+
+        Ok(activity.overview(from, to, daily))
+    }
+
+    /// The channel's daily rollups for `from..=to`, computing and storing only those
+    /// not stored since their day ended
+    async fn channel_rollups(&self, user_id: &str, activity: &Activity, from: NaiveDate, to: NaiveDate) -> Result<Vec<DailyRollup>> {
+        let mut closed: HashMap<NaiveDate, DailyRollup> = self
+            .db
+            .get_analytics_rollups(user_id, from, to)
+            .await?
+            .into_iter()
+            .filter(|rollup| local_day(rollup.computed_at, activity.tz) > rollup.day)
+            .map(|rollup| (rollup.day, rollup))
+            .collect();
+
+        let computed_at = Utc::now();
+        let mut daily = Vec::new();
+        let mut computed = 0;
+        for day in from.iter_days().take_while(|day| *day <= to) {
+            let rollup = match closed.remove(&day) {
+                Some(rollup) => rollup,
+                None => {
+                    let rollup = activity.daily_rollup(user_id, day, computed_at);
+                    self.db.save_analytics_rollup(&rollup).await?;
+                    computed += 1;
+                    rollup
+                }
+            };
+            daily.push(rollup);
+        }
+
+        info!("Computed {} of {} daily analytics rollups for user {}", computed, daily.len(), user_id);
+        Ok(daily)
+    }
+
+    /// Compute comment sentiment over the last `days` days, channel-wide and per video.
+    ///
+    /// If `video_id` or `video_format` is given only the matching videos are included.
+    pub async fn sentiment_trend(
+        &self,
+        user_id: &str,
+        granularity: Granularity,
+        days: u32,
+        video_id: Option<&str>,
+        video_format: Option<VideoFormat>,
+    ) -> Result<SentimentTrend> {
+        let to = Utc::now();
+        let from = to - Duration::days(days.max(1) as i64);
+
+        let comments = self.load_comments(user_id, video_id, video_format, from).await?;
+        let tz = self.time_zone(user_id).await?;
+
+        let mut videos: HashMap<String, Vec<&Comment>> = HashMap::new();
+        for comment in &comments {
+            videos.entry(comment.video_id.clone()).or_default().push(comment);
+        }
+
+        Ok(SentimentTrend {
+            granularity,
+            from,
+            to,
+            channel: sentiment_buckets(comments.iter(), granularity, tz),
+            videos: videos
+                .into_iter()
+                .map(|(video_id, comments)| (video_id, sentiment_buckets(comments.into_iter(), granularity, tz)))
+                .collect(),
+        })
+    }
+
+    /// Count comments over the last `days` days in hourly or daily buckets,
+    /// channel-wide and per video, to show when the audience is active.
+    ///
+    /// If `video_id` or `video_format` is given only the matching videos are included.
+    pub async fn comment_volume(
+        &self,
+        user_id: &str,
+        granularity: Granularity,
+        days: u32,
+        video_id: Option<&str>,
+        video_format: Option<VideoFormat>,
+    ) -> Result<CommentVolume> {
+        let bucket = granularity
+            .surreal_duration()
+            .ok_or_else(|| anyhow::anyhow!("Comment volume supports hour and day granularity only"))?;
+
+        let to = Utc::now();
+        let from = to - Duration::days(days.max(1) as i64);
+
+        let video_ids = self.user_video_ids(user_id, video_id, video_format).await?;
+        let (rows, channel) = self.db.get_comment_volume(&video_ids, from, bucket).await?;
+
+        let mut videos: HashMap<String, Vec<VolumeBucket>> = HashMap::new();
+        for row in rows {
+            videos.entry(row.video_id).or_default().push(VolumeBucket {
+                start: row.bucket,
+                comments: row.comments,
+            });
+        }
+
+        Ok(CommentVolume {
+            granularity,
+            from,
+            to,
+            channel,
+            videos,
+        })
+    }
+
+    /// Report AI cost, token usage, latency and error rates for the last `days` days (including today)
+    pub async fn ai_usage(&self, user_id: &str, days: u32) -> Result<AiAnalytics> {
+        let tz = self.time_zone(user_id).await?;
+        let to = local_day(Utc::now(), tz);
+        let from = to - Duration::days(days.max(1) as i64 - 1);
+
+        let usage = self.db.get_ai_usage_since(user_id, start_of_day(from, tz)).await?;
+        let activity = self.load_activity(user_id, from, tz).await?;
+
+        let ai_replies_posted = activity
+            .replies
+            .values()
+            .filter(|r| r.ai_assisted && in_range(r.posted_at, from, to, tz))
+            .count();
+
+        Ok(ai_report(&usage, from, to, tz, ai_replies_posted))
+    }
+
+    /// One row per day for the last `days` days combining engagement, sentiment and AI spend,
+    /// for exporting to a spreadsheet
+    pub async fn daily_export(&self, user_id: &str, days: u32) -> Result<Vec<DailyExportRow>> {
+        let overview = self.overview(user_id, days, None).await?;
+        let sentiment = self.sentiment_trend(user_id, Granularity::Day, days, None, None).await?;
+        let ai = self.ai_usage(user_id, days).await?;
+        let tz = self.time_zone(user_id).await?;
+
+        let sentiment_by_day: HashMap<NaiveDate, &SentimentBucket> =
+            sentiment.channel.iter().map(|b| (local_day(b.start, tz), b)).collect();
+        let cost_by_day: HashMap<NaiveDate, &DailyCost> = ai.daily.iter().map(|d| (d.day, d)).collect();
+
+        Ok(overview
+            .daily
+            .iter()
+            .map(|rollup| {
+                let sentiment = sentiment_by_day.get(&rollup.day);
+                let cost = cost_by_day.get(&rollup.day);
+
+                DailyExportRow {
+                    day: rollup.day,
+                    comments_received: rollup.comments_received,
+                    replies_posted: rollup.replies_posted,
+                    ai_replies: rollup.ai_replies,
+                    manual_replies: rollup.manual_replies,
+                    median_time_to_reply_secs: rollup.median_time_to_reply_secs,
+                    average_sentiment: sentiment.map(|b| b.average_sentiment),
+                    positive_comments: sentiment.map_or(0, |b| b.positive),
+                    negative_comments: sentiment.map_or(0, |b| b.negative),
+                    ai_requests: cost.map_or(0, |d| d.requests),
+                    ai_cost_usd: cost.map_or(0.0, |d| d.cost_usd),
+                }
+            })
+            .collect())
+    }
+
+    /// Compare engagement, sentiment and reply rate of the user's videos side by side.
+    ///
+    /// Videos that don't belong to the user are skipped; the rest keep the requested order.
+    pub async fn compare_videos(&self, user_id: &str, video_ids: &[String]) -> Result<Vec<VideoComparison>> {
+        let mut comparisons = Vec::new();
+
+        for video_id in video_ids {
+            let video = match self.db.get_video(video_id).await? {
+                Some(video) if video.user_id == user_id => video,
+                _ => continue,
+            };
+
+            let comments = self.db.get_comments(video_id).await?.unwrap_or_default();
+            comparisons.push(compare_video(&video, &comments));
+        }
+
+        Ok(comparisons)
+    }
+
+    /// Recompute keyword and hashtag frequencies for each of the user's videos
+    /// and for the channel as a whole, reporting one job item per video
+    pub async fn refresh_keyword_stats(&self, user_id: &str, handle: &JobHandle) -> Result<()> {
+        let mut channel = TermCounter::default();
+        let mut channel_comments = 0;
+
+        for video_id in self.user_video_ids(user_id, None, None).await? {
+            let comments = self.db.get_comments(&video_id).await?.unwrap_or_default();
+
+            let mut counter = TermCounter::default();
+            for comment in &comments {
+                counter.add(&comment.text);
+            }
+
+            let stats = KeywordStats {
+                user_id: user_id.to_string(),
+                video_id: Some(video_id.clone()),
+                keywords: counter.top_keywords(TOP_TERMS),
+                hashtags: counter.top_hashtags(TOP_TERMS),
+                comments_analyzed: comments.len(),
+                computed_at: Utc::now(),
+            };
+
+            let result = match self.db.save_keyword_stats(&stats).await {
+                Ok(()) => JobItemResult::success(&video_id, serde_json::json!({ "comments": comments.len() })),
+                Err(e) => JobItemResult::failure(&video_id, e),
+            };
+            handle.record(result).await;
+
+            channel.merge(&counter);
+            channel_comments += comments.len();
+        }
+
+        self.db.save_keyword_stats(&KeywordStats {
+            user_id: user_id.to_string(),
+            video_id: None,
+            keywords: channel.top_keywords(TOP_TERMS),
+            hashtags: channel.top_hashtags(TOP_TERMS),
+            comments_analyzed: channel_comments,
+            computed_at: Utc::now(),
+        }).await?;
+
+        info!("Analyzed keywords in {} comments for user {}", channel_comments, user_id);
+
+        Ok(())
+    }
+
+    /// Get the stored keyword stats for a video, or for the channel if `video_id` is `None`
+    pub async fn keyword_stats(&self, user_id: &str, video_id: Option<&str>) -> Result<Option<KeywordStats>> {
+        self.db.get_keyword_stats(user_id, video_id).await
+    }
+
+    /// Number of stored videos for a user
+    pub async fn video_count(&self, user_id: &str) -> Result<usize> {
+        Ok(self.user_video_ids(user_id, None, None).await?.len())
+    }
+
+    /// Load the user's comments published since `since`, optionally for a single video or format
+    async fn load_comments(
+        &self,
+        user_id: &str,
+        video_id: Option<&str>,
+        video_format: Option<VideoFormat>,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Comment>> {
+        let video_ids = self.user_video_ids(user_id, video_id, video_format).await?;
+        self.db.get_comments_for_videos_since(&video_ids, since).await
+    }
+
+    /// IDs of the user's stored videos, optionally narrowed to a single video or format
+    async fn user_video_ids(&self, user_id: &str, video_id: Option<&str>, video_format: Option<VideoFormat>) -> Result<Vec<String>> {
+        Ok(self
+            .db
+            .get_user_videos(user_id)
+            .await?
+            .into_iter()
+            .filter(|v| video_format.is_none_or(|wanted| wanted == v.format))
+            .map(|v| v.video_id)
+            .filter(|id| video_id.is_none_or(|wanted| wanted == id))
+            .collect())
+    }
+
+    /// Load the activity relevant to a period starting at `from`
+    async fn load_activity(&self, user_id: &str, from: NaiveDate, tz: Tz) -> Result<Activity> {
+        let since = start_of_day(from, tz) - Duration::days(REPLY_LOOKBACK_DAYS);
+        let interactions = self.db.get_user_interactions_since(user_id, since).await?;
+        Ok(Activity::from_interactions(&interactions, tz))
+    }
+
+    /// The time zone the user's days are counted in
+    async fn time_zone(&self, user_id: &str) -> Result<Tz> {
+        Ok(self.db.get_user(user_id).await?.map_or(Tz::UTC, |user| user.preferences.time_zone()))
+    }
+}
+
+/// A comment as seen in the interaction history
+#[derive(Debug, Clone)]
+struct ReceivedComment {
+    video_id: String,
+    received_at: DateTime<Utc>,
+}
+
+/// A posted reply as seen in the interaction history
+#[derive(Debug, Clone)]
+struct PostedReply {
+    comment_id: String,
+    posted_at: DateTime<Utc>,
+    ai_assisted: bool,
+}
+
+/// Per-comment and per-reply activity derived from the raw interaction log.
+///
+/// The log contains duplicates (a comment is "received" on every sync, and a
+/// posted reply may be recorded more than once), so everything is keyed by
+/// comment or reply ID and the earliest occurrence wins. Days are the user's.
+#[derive(Debug)]
+struct Activity {
+    comments: HashMap<String, ReceivedComment>,
+    replies: HashMap<String, PostedReply>,
+    tz: Tz,
+}
+
+impl Activity {
+    fn from_interactions(interactions: &[InteractionRecord], tz: Tz) -> Self {
+        let mut activity = Self { comments: HashMap::new(), replies: HashMap::new(), tz };
+
+        for interaction in interactions {
+            match interaction.interaction_type {
+                InteractionType::CommentReceived => {
+                    let comment = activity
+                        .comments
+                        .entry(interaction.comment_id.clone())
+                        .or_insert_with(|| ReceivedComment {
+                            video_id: interaction.video_id.clone(),
+                            received_at: interaction.timestamp,
+                        });
+                    comment.received_at = comment.received_at.min(interaction.timestamp);
+                }
+                InteractionType::ReplyPosted => {
+                    let reply_id = interaction.reply_id.clone().unwrap_or_else(|| interaction.id.clone());
+                    let ai_assisted = interaction.data.contains_key("ai_model");
+                    let reply = activity
+                        .replies
+                        .entry(reply_id)
+                        .or_insert_with(|| PostedReply {
+                            comment_id: interaction.comment_id.clone(),
+                            posted_at: interaction.timestamp,
+                            ai_assisted,
+                        });
+                    reply.posted_at = reply.posted_at.min(interaction.timestamp);
+                    reply.ai_assisted |= ai_assisted;
+                }
+                _ => {}
+            }
+        }
+
+        activity
+    }
+
+    /// Keep only the comments on `video_ids` and the replies to them
+    fn retain_videos(&mut self, video_ids: &HashSet<String>) {
+        self.comments.retain(|_, comment| video_ids.contains(&comment.video_id));
+
+        let comments = &self.comments;
+        self.replies.retain(|_, reply| comments.contains_key(&reply.comment_id));
+    }
+
+    /// When each comment was first replied to
+    fn first_replies(&self) -> HashMap<&str, DateTime<Utc>> {
+        let mut first_replies: HashMap<&str, DateTime<Utc>> = HashMap::new();
+        for reply in self.replies.values() {
+            first_replies
+                .entry(reply.comment_id.as_str())
+                .and_modify(|t| *t = (*t).min(reply.posted_at))
+                .or_insert(reply.posted_at);
+        }
+        first_replies
+    }
+
+    /// Times to first reply (in seconds) for comments first replied to within `from..=to`
+    fn reply_times(&self, from: NaiveDate, to: NaiveDate) -> Vec<i64> {
+        self.first_replies()
+            .into_iter()
+            .filter(|(_, replied_at)| in_range(*replied_at, from, to, self.tz))
+            .filter_map(|(comment_id, replied_at)| {
+                let comment = self.comments.get(comment_id)?;
+                let secs = (replied_at - comment.received_at).num_seconds();
+                (secs >= 0).then_some(secs)
+            })
+            .collect()
+    }
+
+    fn daily_rollups(&self, user_id: &str, from: NaiveDate, to: NaiveDate) -> Vec<DailyRollup> {
+        let computed_at = Utc::now();
+
+        from.iter_days()
+            .take_while(|day| *day <= to)
+            .map(|day| self.daily_rollup(user_id, day, computed_at))
+            .collect()
+    }
+
+    fn daily_rollup(&self, user_id: &str, day: NaiveDate, computed_at: DateTime<Utc>) -> DailyRollup {
+        let mut comments_per_video: HashMap<String, usize> = HashMap::new();
+        for comment in self.comments.values().filter(|c| local_day(c.received_at, self.tz) == day) {
+            *comments_per_video.entry(comment.video_id.clone()).or_default() += 1;
+        }
+
+        let replies: Vec<&PostedReply> = self
+            .replies
+            .values()
+            .filter(|r| local_day(r.posted_at, self.tz) == day)
+            .collect();
+        let ai_replies = replies.iter().filter(|r| r.ai_assisted).count();
+
+        DailyRollup {
+            user_id: user_id.to_string(),
+            day,
+            comments_received: comments_per_video.values().sum(),
+            replies_posted: replies.len(),
+            ai_replies,
+            manual_replies: replies.len() - ai_replies,
+            median_time_to_reply_secs: median(self.reply_times(day, day)),
+            comments_per_video,
+            computed_at,
+        }
+    }
+
+    fn overview(&self, from: NaiveDate, to: NaiveDate, daily: Vec<DailyRollup>) -> AnalyticsOverview {
+        let received: Vec<(&String, &ReceivedComment)> = self
+            .comments
+            .iter()
+            .filter(|(_, c)| in_range(c.received_at, from, to, self.tz))
+            .collect();
+
+        let first_replies = self.first_replies();
+        let replied = received
+            .iter()
+            .filter(|(comment_id, _)| first_replies.contains_key(comment_id.as_str()))
+            .count();
+
+        let replies_posted: usize = daily.iter().map(|d| d.replies_posted).sum();
+        let ai_replies: usize = daily.iter().map(|d| d.ai_replies).sum();
+
+        let videos: HashSet<&str> = received.iter().map(|(_, c)| c.video_id.as_str()).collect();
+        let days = (to - from).num_days() + 1;
+
+        AnalyticsOverview {
+            from,
+            to,
+            comments_received: received.len(),
+            replies_posted,
+            reply_rate: ratio(replied, received.len()),
+            median_time_to_reply_secs: median(self.reply_times(from, to)),
+            comments_per_video_per_day: ratio(received.len(), videos.len() * days as usize),
+            ai_reply_share: ratio(ai_replies, replies_posted),
+            manual_reply_share: if replies_posted == 0 { 0.0 } else { 1.0 - ratio(ai_replies, replies_posted) },
+            daily,
+        }
+    }
+}
+
+/// Summarize a video's stored comments for comparison
+fn compare_video(video: &Video, comments: &[Comment]) -> VideoComparison {
+    let scores: Vec<f32> = comments
+        .iter()
+        .map(|c| c.sentiment.unwrap_or_else(|| sentiment::score(&c.text)))
+        .collect();
+    let count_label = |label| scores.iter().filter(|s| SentimentLabel::from_score(**s) == label).count();
+
+    let answered = comments.iter().filter(|c| c.replied_to).count();
+    let total_likes: i64 = comments.iter().map(|c| c.like_count as i64).sum();
+
+    VideoComparison {
+        video_id: video.video_id.clone(),
+        title: video.title.clone(),
+        format: video.format,
+        published_at: video.published_at,
+        comments: comments.len(),
+        replies: comments.iter().map(|c| c.replies.len()).sum(),
+        average_likes: if comments.is_empty() { 0.0 } else { total_likes as f64 / comments.len() as f64 },
+        average_sentiment: if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().map(|s| *s as f64).sum::<f64>() / scores.len() as f64
+        },
+        positive_share: ratio(count_label(SentimentLabel::Positive), scores.len()),
+        negative_share: ratio(count_label(SentimentLabel::Negative), scores.len()),
+        reply_rate: ratio(answered, comments.len()),
+        unanswered: comments.len() - answered,
+    }
+}
+
+/// Aggregate AI usage records into a cost and latency report
+fn ai_report(usage: &[AiUsageRecord], from: NaiveDate, to: NaiveDate, tz: Tz, ai_replies_posted: usize) -> AiAnalytics {
+    let mut daily: BTreeMap<NaiveDate, DailyCost> = BTreeMap::new();
+    let mut models: HashMap<&str, Vec<&AiUsageRecord>> = HashMap::new();
+
+    for record in usage {
+        let day = daily.entry(local_day(record.created_at, tz)).or_insert_with(|| DailyCost {
+            day: local_day(record.created_at, tz),
+            requests: 0,
+            cost_usd: 0.0,
+        });
+        day.requests += 1;
+        day.cost_usd += record.cost_usd;
+
+        models.entry(record.model.as_str()).or_default().push(record);
+    }
+
+    let mut models: Vec<ModelUsage> = models
+        .into_iter()
+        .map(|(model, records)| {
+            let errors = records.iter().filter(|r| !r.success).count();
+            let latencies = successful_latencies(records.iter().copied());
+
+            ModelUsage {
+                model: model.to_string(),
+                requests: records.len(),
+                errors,
+                error_rate: ratio(errors, records.len()),
+                prompt_tokens: records.iter().map(|r| r.prompt_tokens).sum(),
+                completion_tokens: records.iter().map(|r| r.completion_tokens).sum(),
+                total_tokens: records.iter().map(|r| r.total_tokens).sum(),
+                cost_usd: records.iter().map(|r| r.cost_usd).sum(),
+                p50_latency_ms: percentile(&latencies, 50.0),
+                p95_latency_ms: percentile(&latencies, 95.0),
+            }
+        })
+        .collect();
+    models.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+
+    let total_cost_usd: f64 = usage.iter().map(|r| r.cost_usd).sum();
+    let errors = usage.iter().filter(|r| !r.success).count();
+    let latencies = successful_latencies(usage.iter());
+
+    AiAnalytics {
+        from,
+        to,
+        requests: usage.len(),
+        error_rate: ratio(errors, usage.len()),
+        total_cost_usd,
+        ai_replies_posted,
+        cost_per_posted_reply: (ai_replies_posted > 0).then(|| total_cost_usd / ai_replies_posted as f64),
+        p50_latency_ms: percentile(&latencies, 50.0),
+        p95_latency_ms: percentile(&latencies, 95.0),
+        daily: daily.into_values().collect(),
+        models,
+    }
+}
+
+/// Sorted latencies of the successful requests
+fn successful_latencies<'a>(records: impl Iterator<Item = &'a AiUsageRecord>) -> Vec<u64> {
+    let mut latencies: Vec<u64> = records.filter(|r| r.success).map(|r| r.latency_ms).collect();
+    latencies.sort_unstable();
+    latencies
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[u64], pct: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// Group comments into time buckets with their aggregated sentiment, oldest first
+fn sentiment_buckets<'a>(comments: impl Iterator<Item = &'a Comment>, granularity: Granularity, tz: Tz) -> Vec<SentimentBucket> {
+    let mut buckets: BTreeMap<DateTime<Utc>, Vec<f32>> = BTreeMap::new();
+
+    for comment in comments {
+        // Comments stored before sentiment scoring was added are scored on the fly
+        let score = comment.sentiment.unwrap_or_else(|| sentiment::score(&comment.text));
+        buckets.entry(granularity.bucket_start(comment.published_at, tz)).or_default().push(score);
+    }
+
+    buckets
+        .into_iter()
+        .map(|(start, scores)| {
+            let count_label = |label| scores.iter().filter(|s| SentimentLabel::from_score(**s) == label).count();
+
+            SentimentBucket {
+                start,
+                comments: scores.len(),
+                average_sentiment: scores.iter().map(|s| *s as f64).sum::<f64>() / scores.len() as f64,
+                positive: count_label(SentimentLabel::Positive),
+                neutral: count_label(SentimentLabel::Neutral),
+                negative: count_label(SentimentLabel::Negative),
+            }
+        })
+        .collect()
+}
+
+fn in_range(timestamp: DateTime<Utc>, from: NaiveDate, to: NaiveDate, tz: Tz) -> bool {
+    let day = local_day(timestamp, tz);
+    day >= from && day <= to
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Median of a set of values, averaging the middle two for an even count
+fn median(mut values: Vec<i64>) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_unstable();
+    let mid = values.len() / 2;
+
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2)
+    } else {
+        Some(values[mid])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interaction(
+        interaction_type: InteractionType,
+        comment_id: &str,
+        reply_id: Option<&str>,
+        timestamp: DateTime<Utc>,
+        ai_model: Option<&str>,
+    ) -> InteractionRecord {
+        let mut data = HashMap::new();
+        if let Some(model) = ai_model {
+            data.insert("ai_model".to_string(), model.to_string());
+        }
+
+        InteractionRecord {
+            id: format!("{}-{:?}-{}", comment_id, reply_id, timestamp.timestamp()),
+            user_id: "user".to_string(),
+            video_id: "video".to_string(),
+            comment_id: comment_id.to_string(),
+            reply_id: reply_id.map(String::from),
+            interaction_type,
+            timestamp,
+            data,
+        }
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![5]), Some(5));
+        assert_eq!(median(vec![9, 1, 5]), Some(5));
+        assert_eq!(median(vec![4, 1, 3, 2]), Some(2));
+    }
+
+    #[test]
+    fn test_percentile() {
+        let latencies: Vec<u64> = (1..=20).map(|i| i * 100).collect();
+        assert_eq!(percentile(&latencies, 50.0), Some(1000));
+        assert_eq!(percentile(&latencies, 95.0), Some(1900));
+        assert_eq!(percentile(&[], 50.0), None);
+        assert_eq!(percentile(&[42], 95.0), Some(42));
+    }
+
+    #[test]
+    fn test_overview_dedupes_and_computes_rates() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let t0 = start_of_day(day, Tz::UTC);
+
+        let interactions = vec![
+            // c1 is seen on two syncs, and replied to with AI after an hour
+            interaction(InteractionType::CommentReceived, "c1", None, t0, None),
+            interaction(InteractionType::CommentReceived, "c1", None, t0 + Duration::hours(2), None),
+            interaction(InteractionType::ReplyPosted, "c1", Some("r1"), t0 + Duration::hours(1), None),
+            interaction(InteractionType::ReplyPosted, "c1", Some("r1"), t0 + Duration::hours(1), Some("gpt-4")),
+            // c2 is replied to manually after three hours
+            interaction(InteractionType::CommentReceived, "c2", None, t0, None),
+            interaction(InteractionType::ReplyPosted, "c2", Some("r2"), t0 + Duration::hours(3), None),
+            // c3 is never replied to
+            interaction(InteractionType::CommentReceived, "c3", None, t0, None),
+        ];
+
+        let activity = Activity::from_interactions(&interactions, Tz::UTC);
+        let daily = activity.daily_rollups("user", day, day);
+        let overview = activity.overview(day, day, daily);
+
+        assert_eq!(overview.comments_received, 3);
+        assert_eq!(overview.replies_posted, 2);
+        assert!((overview.reply_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(overview.median_time_to_reply_secs, Some(2 * 3600));
+        assert!((overview.ai_reply_share - 0.5).abs() < 1e-9);
+        assert!((overview.comments_per_video_per_day - 3.0).abs() < 1e-9);
+        assert_eq!(overview.daily[0].comments_per_video.get("video"), Some(&3));
+    }
+
+    #[test]
+    fn test_days_follow_time_zone() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        // Late on the 10th in UTC, but the 11th in Berlin
+        let late = "2024-03-10T23:30:00Z".parse().unwrap();
+        let interactions = vec![interaction(InteractionType::CommentReceived, "c1", None, late, None)];
+
+        let berlin = Activity::from_interactions(&interactions, chrono_tz::Europe::Berlin);
+        assert_eq!(berlin.daily_rollups("user", day, day)[0].comments_received, 1);
+        let utc = Activity::from_interactions(&interactions, Tz::UTC);
+        assert_eq!(utc.daily_rollups("user", day, day)[0].comments_received, 0);
+    }
+}
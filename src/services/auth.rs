@@ -1,13 +1,18 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, Duration, Utc};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::env;
-use tracing::{error, info};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::db::Database;
-use crate::models::auth::{AuthToken, Session, User, UserPreferences, ReplyTone};
+use crate::error::AppError;
+use crate::models::auth::{AuthToken, GoogleConnection, Session, User, UserPreferences, ReplyTone};
+use crate::utils::upstream::{Attempts, Upstream};
 
 /// YouTube OAuth2 configuration
 #[derive(Debug, Clone)]
@@ -23,6 +28,12 @@ pub struct OAuthConfig {
     
     /// OAuth scopes required
     pub scopes: Vec<String>,
+    
+    /// Endpoint tokens are exchanged and refreshed at
+    pub token_url: String,
+    
+    /// Endpoint the signed-in user's profile is read from
+    pub userinfo_url: String,
 }
 
 impl OAuthConfig {
@@ -41,6 +52,11 @@ impl OAuthConfig {
                 "https://www.googleapis.com/auth/userinfo.email".to_string(),
                 "https://www.googleapis.com/auth/userinfo.profile".to_string(),
             ],
+            // Overridable so load tests can point at a stub server
+            token_url: env::var("GOOGLE_OAUTH_TOKEN_URL")
+                .unwrap_or_else(|_| "https://oauth2.googleapis.com/token".to_string()),
+            userinfo_url: env::var("GOOGLE_USERINFO_URL")
+                .unwrap_or_else(|_| "https://www.googleapis.com/oauth2/v1/userinfo".to_string()),
         })
     }
     
@@ -73,22 +89,59 @@ struct UserInfoResponse {
     picture: Option<String>,
 }
 
+/// OAuth error Google answers a refresh with once the user revoked access or the grant expired
+const INVALID_GRANT: &str = "invalid_grant";
+
+/// Prefix of the OAuth `state` of a reconnect, followed by the user's ID
+pub const RECONNECT_STATE_PREFIX: &str = "reconnect.";
+
+/// How often the background refresher looks for tokens nearing expiry
+const TOKEN_REFRESH_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// How far ahead of expiry the background refresher renews tokens (in minutes).
+/// Must be larger than the check interval so no token expires between checks.
+const TOKEN_REFRESH_AHEAD_MINUTES: i64 = 15;
+
+/// The sign-in operations handlers rely on, so tests can swap in a fake
+#[async_trait]
+pub trait AuthApi: Send + Sync {
+    /// The Google consent screen URL to send the user to
+    fn get_authorization_url(&self) -> String;
+
+    /// Exchange an authorization code for tokens
+    async fn exchange_code(&self, code: &str) -> Result<AuthToken>;
+
+    /// Create or update the user the tokens belong to
+    async fn create_or_update_user(&self, token: &AuthToken) -> Result<User>;
+
+    /// The consent screen URL a disconnected user is sent to, to grant access again
+    fn get_reconnect_url(&self, user: &User) -> String;
+
+    /// Store the new tokens of an existing user who granted access again; tokens
+    /// of another Google account are refused
+    async fn reconnect_user(&self, user_id: &str, token: &AuthToken) -> Result<User>;
+
+    /// Start a session for a user
+    async fn create_session(&self, user_id: &str, ip_address: &str, user_agent: &str) -> Result<Session>;
+}
+
 /// Authentication service
 pub struct AuthService {
     db: Database,
     client: Client,
+    upstream: Arc<Upstream>,
     oauth_config: OAuthConfig,
 }
 
 impl AuthService {
     /// Create a new authentication service
-    pub fn new(db: Database) -> Result<Self> {
+    pub fn new(db: Database, client: Client, upstream: Arc<Upstream>) -> Result<Self> {
         let oauth_config = OAuthConfig::from_env()?;
-        let client = Client::new();
         
         Ok(Self {
             db,
             client,
+            upstream,
             oauth_config,
         })
     }
@@ -99,26 +152,28 @@ impl AuthService {
         self.oauth_config.authorization_url(&state)
     }
     
+    /// Generate the authorization URL a user reconnects through; the callback
+    /// finds the user again from the `state`
+    pub fn get_reconnect_url(&self, user: &User) -> String {
+        let state = format!("{}{}.{}", RECONNECT_STATE_PREFIX, user.id, Uuid::new_v4());
+        self.oauth_config.authorization_url(&state)
+    }
+    
     /// Exchange an authorization code for tokens
     pub async fn exchange_code(&self, code: &str) -> Result<AuthToken> {
-        let response = self.client
-            .post("https://oauth2.googleapis.com/token")
+        let request = self.client
+            .post(&self.oauth_config.token_url)
             .form(&[
                 ("client_id", &self.oauth_config.client_id),
                 ("client_secret", &self.oauth_config.client_secret),
                 ("code", &code.to_string()),
                 ("grant_type", &"authorization_code".to_string()),
                 ("redirect_uri", &self.oauth_config.redirect_uri),
-            ])
-            .send()
+            ]);
+        let response = self.upstream
+            .call(request, Attempts::Once, "Failed to exchange code for tokens", |_, message| AppError::UpstreamAuth(message))
             .await?;
         
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("OAuth token error: {}", error_text);
-            anyhow::bail!("Failed to exchange code for tokens: {}", error_text);
-        }
-        
         let token_response: OAuthTokenResponse = response.json().await?;
         
         // Convert to our AuthToken model
@@ -136,23 +191,25 @@ impl AuthService {
     
     /// Refresh an access token
     pub async fn refresh_token(&self, refresh_token: &str) -> Result<AuthToken> {
-        let response = self.client
-            .post("https://oauth2.googleapis.com/token")
+        let request = self.client
+            .post(&self.oauth_config.token_url)
             .form(&[
                 ("client_id", &self.oauth_config.client_id),
                 ("client_secret", &self.oauth_config.client_secret),
                 ("refresh_token", &refresh_token.to_string()),
                 ("grant_type", &"refresh_token".to_string()),
-            ])
-            .send()
+            ]);
+        // A revoked or expired grant needs the user to sign in again; anything else is on Google's side
+        let response = self.upstream
+            .call(request, Attempts::Retry, "Failed to refresh token", |status, message| {
+                if status.is_client_error() {
+                    AppError::UpstreamAuth(message)
+                } else {
+                    AppError::Internal(anyhow::anyhow!(message))
+                }
+            })
             .await?;
         
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("OAuth token refresh error: {}", error_text);
-            anyhow::bail!("Failed to refresh token: {}", error_text);
-        }
-        
         let token_response: OAuthTokenResponse = response.json().await?;
         
         // Convert to our AuthToken model
@@ -169,19 +226,14 @@ impl AuthService {
     }
     
     /// Get user information from Google
-    pub async fn get_user_info(&self, access_token: &str) -> Result<UserInfoResponse> {
-        let response = self.client
-            .get("https://www.googleapis.com/oauth2/v1/userinfo")
-            .header("Authorization", format!("Bearer {}", access_token))
-            .send()
+    async fn get_user_info(&self, access_token: &str) -> Result<UserInfoResponse> {
+        let request = self.client
+            .get(&self.oauth_config.userinfo_url)
+            .header("Authorization", format!("Bearer {}", access_token));
+        let response = self.upstream
+            .call(request, Attempts::Retry, "Failed to get user info", |_, message| AppError::Internal(anyhow::anyhow!(message)))
             .await?;
         
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("User info error: {}", error_text);
-            anyhow::bail!("Failed to get user info: {}", error_text);
-        }
-        
         let user_info: UserInfoResponse = response.json().await?;
         Ok(user_info)
     }
@@ -190,7 +242,28 @@ impl AuthService {
     pub async fn create_or_update_user(&self, token: &AuthToken) -> Result<User> {
         // Get user info from Google
         let user_info = self.get_user_info(&token.access_token).await?;
+        self.save_signed_in_user(user_info, token).await
+    }
+    
+    /// Store the new tokens of a user who granted access again after being disconnected
+    pub async fn reconnect_user(&self, user_id: &str, token: &AuthToken) -> Result<User> {
+        let user_info = self.get_user_info(&token.access_token).await?;
+        
+        // Reconnecting must not sign a different account in as this user
+        if user_info.id != user_id {
+            return Err(AppError::Validation("Sign in with the Google account you are reconnecting".to_string()).into());
+        }
+        if self.db.get_user(user_id).await?.is_none() {
+            return Err(AppError::NotFound(format!("User {}", user_id)).into());
+        }
         
+        let user = self.save_signed_in_user(user_info, token).await?;
+        info!("User {} reconnected their Google account", user_id);
+        Ok(user)
+    }
+    
+    /// Create or update the user Google signed in, who is connected from now on
+    async fn save_signed_in_user(&self, user_info: UserInfoResponse, token: &AuthToken) -> Result<User> {
         // Check if user exists
         let existing_user = self.db.get_user(&user_info.id).await?;
         
@@ -200,6 +273,7 @@ impl AuthService {
                 user.name = user_info.name;
                 user.email = user_info.email;
                 user.profile_picture_url = user_info.picture;
+                user.connection = GoogleConnection::default();
                 user.updated_at = Utc::now();
                 user
             },
@@ -216,12 +290,20 @@ impl AuthService {
                     preferences: UserPreferences {
                         enable_ai_replies: true,
                         ai_model: "gpt-3.5-turbo".to_string(),
-                        reply_tone: ReplyTone::Friendly,
+                        reply_tone: ReplyTone::default(),
                         enable_notifications: true,
+                        notifications: Default::default(),
                         polling_interval: 60,
+                        language: None,
+                        preferred_reply_language: None,
+                        time_zone: None,
+                        ai_disclosure: Default::default(),
+                        reply_policy: Default::default(),
+                        retention: Default::default(),
                         additional: Default::default(),
                     },
                     metadata: Default::default(),
+                    connection: Default::default(),
                 }
             }
         };
@@ -283,17 +365,142 @@ impl AuthService {
     pub async fn get_valid_access_token(&self, user_id: &str) -> Result<String> {
         let token = match self.db.get_auth_token(user_id).await? {
             Some(t) => t,
-            None => anyhow::bail!("No auth token found for user {}", user_id),
+            None => return Err(AppError::UpstreamAuth(format!("No auth token found for user {}", user_id)).into()),
         };
         
         // Check if token is expired or about to expire (within 5 minutes)
         if token.expires_at <= Utc::now() + Duration::minutes(5) {
+            // A refused grant stays refused, so don't ask Google again until the user reconnects
+            if self.is_disconnected(user_id).await? {
+                return Err(AppError::UpstreamAuth(format!(
+                    "Google access of user {} was revoked; reconnect through /api/auth/reconnect", user_id
+                )).into());
+            }
+            
             // Refresh the token
-            let new_token = self.refresh_token(&token.refresh_token).await?;
-            self.db.save_auth_token(user_id, &new_token).await?;
+            let new_token = self.refresh_user_token(user_id, &token.refresh_token).await?;
             Ok(new_token.access_token)
         } else {
             Ok(token.access_token)
         }
     }
+    
+    /// Refresh and store a user's token, marking the user as disconnected if Google refuses the grant
+    async fn refresh_user_token(&self, user_id: &str, refresh_token: &str) -> Result<AuthToken> {
+        match self.refresh_token(refresh_token).await {
+            Ok(new_token) => {
+                self.db.save_auth_token(user_id, &new_token).await?;
+                Ok(new_token)
+            }
+            Err(e) => {
+                if is_invalid_grant(&e) {
+                    self.disconnect(user_id, INVALID_GRANT).await?;
+                }
+                Err(e)
+            }
+        }
+    }
+    
+    /// Mark a user as disconnected, which pauses their monitors and outbox until they reconnect
+    pub async fn disconnect(&self, user_id: &str, reason: &str) -> Result<()> {
+        let mut user = match self.db.get_user(user_id).await? {
+            Some(user) if !user.is_disconnected() => user,
+            _ => return Ok(()),
+        };
+        
+        let now = Utc::now();
+        user.connection = GoogleConnection {
+            disconnected_at: Some(now),
+            disconnect_reason: Some(reason.to_string()),
+        };
+        user.updated_at = now;
+        self.db.save_user(&user).await?;
+        
+        warn!("Google refused the grant of user {} ({}), marked as disconnected", user_id, reason);
+        Ok(())
+    }
+    
+    /// Whether Google refused the user's grant since they last signed in
+    pub async fn is_disconnected(&self, user_id: &str) -> Result<bool> {
+        Ok(self.db.get_user(user_id).await?.is_some_and(|user| user.is_disconnected()))
+    }
+    
+    /// Refresh the tokens of users with active monitors that expire within `ahead`.
+    ///
+    /// Returns the number of tokens refreshed. A failure for one user doesn't
+    /// stop the others from being refreshed.
+    pub async fn refresh_expiring_tokens(&self, ahead: Duration) -> Result<usize> {
+        let deadline = Utc::now() + ahead;
+        let mut refreshed = 0;
+        
+        for user_id in self.db.get_monitored_user_ids().await? {
+            let token = match self.db.get_auth_token(&user_id).await? {
+                Some(t) => t,
+                None => continue,
+            };
+            
+            if token.expires_at > deadline || self.is_disconnected(&user_id).await? {
+                continue;
+            }
+            
+            match self.refresh_user_token(&user_id, &token.refresh_token).await {
+                Ok(_) => refreshed += 1,
+                Err(e) => {
+                    warn!("Failed to refresh token ahead of expiry for user {}: {}", user_id, e);
+                }
+            }
+        }
+        
+        Ok(refreshed)
+    }
+    
+    /// Spawn a background task that refreshes tokens before they expire, so
+    /// monitoring never has to wait on a refresh (or fail on clock skew) mid-sync
+    pub fn spawn_token_refresher(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TOKEN_REFRESH_CHECK_INTERVAL);
+            
+            loop {
+                interval.tick().await;
+                
+                match self.refresh_expiring_tokens(Duration::minutes(TOKEN_REFRESH_AHEAD_MINUTES)).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Refreshed {} auth tokens ahead of expiry", count),
+                    Err(e) => error!("Error refreshing auth tokens: {}", e),
+                }
+            }
+        })
+    }
+}
+
+#[async_trait]
+impl AuthApi for AuthService {
+    fn get_authorization_url(&self) -> String {
+        AuthService::get_authorization_url(self)
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<AuthToken> {
+        AuthService::exchange_code(self, code).await
+    }
+
+    async fn create_or_update_user(&self, token: &AuthToken) -> Result<User> {
+        AuthService::create_or_update_user(self, token).await
+    }
+
+    fn get_reconnect_url(&self, user: &User) -> String {
+        AuthService::get_reconnect_url(self, user)
+    }
+
+    async fn reconnect_user(&self, user_id: &str, token: &AuthToken) -> Result<User> {
+        AuthService::reconnect_user(self, user_id, token).await
+    }
+
+    async fn create_session(&self, user_id: &str, ip_address: &str, user_agent: &str) -> Result<Session> {
+        AuthService::create_session(self, user_id, ip_address, user_agent).await
+    }
+}
+
+/// Whether a refresh failed because Google no longer accepts the grant
+fn is_invalid_grant(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<AppError>(), Some(AppError::UpstreamAuth(message)) if message.contains(INVALID_GRANT))
 }
@@ -0,0 +1,125 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::models::Comment;
+use crate::services::ai::AiApi;
+
+/// Similarity two comments need to be grouped, unless the caller picks another
+pub const DEFAULT_THRESHOLD: f32 = 0.85;
+
+/// Most comments clustered at once, newest first
+pub const MAX_CLUSTERED: usize = 500;
+
+/// Comments on a video asking the same thing
+#[derive(Debug, Clone, Serialize)]
+pub struct CommentCluster {
+    /// The comments in the cluster, the most typical first
+    pub comment_ids: Vec<String>,
+
+    /// Text of the most typical comment
+    pub sample_text: String,
+
+    /// Average similarity of the comments to the cluster's centre
+    pub cohesion: f32,
+}
+
+/// Cosine similarity of two vectors, 0 if either is zero
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+/// Group vectors whose similarity to a group's centre is at least `threshold`.
+///
+/// Each vector joins the most similar group if it is close enough, or starts a
+/// new one; centres are the running mean of their members. Returns the groups
+/// of two or more, as indexes into `vectors`.
+pub fn cluster(vectors: &[Vec<f32>], threshold: f32) -> Vec<Vec<usize>> {
+    let mut centres: Vec<Vec<f32>> = Vec::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+
+    for (index, vector) in vectors.iter().enumerate() {
+        let best = centres
+            .iter()
+            .enumerate()
+            .map(|(group, centre)| (group, cosine(centre, vector)))
+            .filter(|(_, similarity)| *similarity >= threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        match best {
+            Some((group, _)) => {
+                let count = groups[group].len() as f32;
+                for (c, v) in centres[group].iter_mut().zip(vector) {
+                    *c = (*c * count + v) / (count + 1.0);
+                }
+                groups[group].push(index);
+            }
+            None => {
+                centres.push(vector.clone());
+                groups.push(vec![index]);
+            }
+        }
+    }
+
+    groups.into_iter().filter(|group| group.len() > 1).collect()
+}
+
+/// Cluster comments by the meaning of their text, largest clusters first
+pub async fn cluster_comments(ai: &dyn AiApi, user_id: &str, comments: &[Comment], threshold: f32) -> Result<Vec<CommentCluster>> {
+    if comments.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    let texts: Vec<String> = comments.iter().map(|c| c.text.clone()).collect();
+    let vectors = ai.embed(user_id, &texts).await?;
+
+    let mut clusters: Vec<CommentCluster> = cluster(&vectors, threshold)
+        .into_iter()
+        .map(|group| {
+            let dims = vectors[group[0]].len();
+            let mut centre = vec![0.0; dims];
+            for &i in &group {
+                for (c, v) in centre.iter_mut().zip(&vectors[i]) {
+                    *c += v / group.len() as f32;
+                }
+            }
+
+            let mut members: Vec<(usize, f32)> = group.iter().map(|&i| (i, cosine(&centre, &vectors[i]))).collect();
+            members.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let cohesion = members.iter().map(|(_, s)| s).sum::<f32>() / members.len() as f32;
+
+            CommentCluster {
+                sample_text: comments[members[0].0].text.clone(),
+                comment_ids: members.into_iter().map(|(i, _)| comments[i].comment_id.clone()).collect(),
+                cohesion,
+            }
+        })
+        .collect();
+
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.comment_ids.len()));
+    Ok(clusters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cluster() {
+        let vectors = vec![
+            vec![1.0, 0.0, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![0.95, 0.05, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![0.9, 0.1, 0.05],
+        ];
+        assert_eq!(cluster(&vectors, 0.9), vec![vec![0, 2, 4]]);
+        assert!(cluster(&vectors, 0.999).is_empty());
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
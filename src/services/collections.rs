@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::collection::VideoCollection;
+use crate::models::video::{Video, MIN_MONITOR_INTERVAL_SECS};
+
+/// Most collections a user can have
+pub const MAX_COLLECTIONS_PER_USER: usize = 50;
+
+/// Check that a collection can be stored: it has a name and a usable monitor interval
+pub fn validate(collection: &VideoCollection) -> Result<()> {
+    if collection.name.trim().is_empty() {
+        return Err(AppError::Validation("Collection name must not be empty".to_string()).into());
+    }
+
+    if collection.defaults.interval_secs.is_some_and(|secs| secs < MIN_MONITOR_INTERVAL_SECS) {
+        return Err(AppError::Validation(format!("interval_secs must be at least {}", MIN_MONITOR_INTERVAL_SECS)).into());
+    }
+
+    Ok(())
+}
+
+/// Groups a user's videos and hands a collection's defaults to the videos added to it
+pub struct CollectionService {
+    db: Database,
+}
+
+impl CollectionService {
+    /// Create a new collection service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// A collection of the user; other users' collections are reported as missing
+    pub async fn get(&self, user_id: &str, collection_id: &str) -> Result<VideoCollection> {
+        self.db
+            .get_collection(collection_id)
+            .await?
+            .filter(|collection| collection.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound(format!("Collection {}", collection_id)).into())
+    }
+
+    /// Add a user's video to a collection, moving it out of any other, and give it the collection's defaults.
+    ///
+    /// The defaults are copied once; changing the collection later leaves its
+    /// videos as they are, and the video's own settings can still be changed.
+    pub async fn add_video(&self, user_id: &str, collection_id: &str, video_id: &str) -> Result<Video> {
+        let collection = self.get(user_id, collection_id).await?;
+        let mut video = self.video(user_id, video_id).await?;
+
+        video.collection_id = Some(collection.collection_id.clone());
+        collection.defaults.apply(&mut video);
+
+        self.db.save_video(&video).await?;
+        Ok(video)
+    }
+
+    /// Take a video out of a collection; it keeps the settings it has
+    pub async fn remove_video(&self, user_id: &str, collection_id: &str, video_id: &str) -> Result<()> {
+        self.get(user_id, collection_id).await?;
+        let video = self.video(user_id, video_id).await?;
+        if video.collection_id.as_deref() != Some(collection_id) {
+            return Err(AppError::NotFound(format!("Video {} in collection {}", video_id, collection_id)).into());
+        }
+
+        self.db.set_video_collection(video_id, None).await
+    }
+
+    /// A video of the user; other users' videos are reported as missing
+    async fn video(&self, user_id: &str, video_id: &str) -> Result<Video> {
+        self.db
+            .get_video(video_id)
+            .await?
+            .filter(|video| video.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound(format!("Video {}", video_id)).into())
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::db::Database;
+use crate::models::{Comment, InteractionType};
+use crate::models::commenter::{Exchange, PastReply};
+
+/// Most exchanges returned by the history endpoint
+pub const MAX_HISTORY: usize = 50;
+
+/// Exchanges given to the AI as previous interactions
+const PROMPT_HISTORY: usize = 5;
+
+/// Longest comment or reply text quoted to the AI
+const PROMPT_TEXT_CHARS: usize = 200;
+
+/// Looks up what a user and a commenter said to each other before
+pub struct CommenterService {
+    db: Database,
+}
+
+impl CommenterService {
+    /// Create a new commenter service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// A commenter's comments on the user's videos and the user's replies, newest first.
+    ///
+    /// Replies come from the stored threads and from the replies posted through
+    /// the app, which may not have been synced back yet.
+    pub async fn history(&self, user_id: &str, channel_id: &str, limit: usize) -> Result<Vec<Exchange>> {
+        let titles: HashMap<String, String> = self
+            .db
+            .get_user_videos(user_id)
+            .await?
+            .into_iter()
+            .map(|v| (v.video_id, v.title))
+            .collect();
+        let video_ids: Vec<String> = titles.keys().cloned().collect();
+
+        let comments = self.db.get_comments_by_author(&video_ids, channel_id, limit.min(MAX_HISTORY)).await?;
+
+        let mut exchanges = Vec::with_capacity(comments.len());
+        for comment in comments {
+            let replies = self.replies(user_id, &comment).await?;
+            exchanges.push(Exchange {
+                video_title: titles.get(&comment.video_id).cloned(),
+                video_id: comment.video_id,
+                comment_id: comment.comment_id,
+                text: comment.text,
+                published_at: comment.published_at,
+                replies,
+            });
+        }
+
+        Ok(exchanges)
+    }
+
+    /// Earlier exchanges with a comment's author, summarized for the AI prompt
+    pub async fn previous_interactions(&self, user_id: &str, comment: &Comment) -> Result<Vec<String>> {
+        let history = self.history(user_id, &comment.author_channel_id, PROMPT_HISTORY + 1).await?;
+
+        Ok(history
+            .into_iter()
+            .filter(|exchange| exchange.comment_id != comment.comment_id)
+            .take(PROMPT_HISTORY)
+            .map(|exchange| {
+                let mut line = match &exchange.video_title {
+                    Some(title) => format!("On \"{}\" they wrote: \"{}\"", title, shorten(&exchange.text)),
+                    None => format!("They wrote: \"{}\"", shorten(&exchange.text)),
+                };
+                match exchange.replies.last() {
+                    Some(reply) => line.push_str(&format!("; I replied: \"{}\"", shorten(&reply.text))),
+                    None => line.push_str("; I didn't reply"),
+                }
+                line
+            })
+            .collect())
+    }
+
+    /// The user's replies to a comment, from the thread and the interaction history
+    async fn replies(&self, user_id: &str, comment: &Comment) -> Result<Vec<PastReply>> {
+        let mut replies: Vec<PastReply> = comment
+            .replies
+            .iter()
+            .filter(|reply| reply.author_channel_id == user_id)
+            .map(|reply| PastReply {
+                reply_id: Some(reply.reply_id.clone()),
+                text: reply.text.clone(),
+                posted_at: reply.published_at,
+            })
+            .collect();
+
+        for interaction in self.db.get_comment_interactions(&comment.comment_id).await? {
+            if interaction.user_id != user_id || !matches!(interaction.interaction_type, InteractionType::ReplyPosted) {
+                continue;
+            }
+            if replies.iter().any(|r| r.reply_id.is_some() && r.reply_id == interaction.reply_id) {
+                continue;
+            }
+            if let Some(text) = interaction.data.get("reply_text") {
+                replies.push(PastReply {
+                    reply_id: interaction.reply_id.clone(),
+                    text: text.clone(),
+                    posted_at: interaction.timestamp,
+                });
+            }
+        }
+
+        replies.sort_by_key(|reply| reply.posted_at);
+        Ok(replies)
+    }
+}
+
+/// Cut a text to [`PROMPT_TEXT_CHARS`] characters
+fn shorten(text: &str) -> String {
+    if text.chars().count() <= PROMPT_TEXT_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(PROMPT_TEXT_CHARS).collect();
+    format!("{}…", cut.trim_end())
+}
@@ -0,0 +1,144 @@
+//! Fitting oversized reply prompts into the model's context window.
+//!
+//! A very long comment, or a long history with its author, would otherwise be
+//! sent as it is and refused by the provider. Before a reply is generated, the
+//! parts of the request that can do with less are shortened in order of how
+//! little the reply loses: the oldest previous interactions, then the oldest
+//! thread replies, then the middle of the comment, keeping its opening and its
+//! end. The response's metadata says what was condensed.
+
+use crate::models::ai::{AiModelConfig, ReplyGenerationRequest};
+use crate::services::ai::build_user_message;
+
+/// Metadata key of a response whose prompt was condensed, listing what was shortened
+pub const CONDENSED_KEY: &str = "condensed";
+
+/// Rough characters per token, which errs on the side of a smaller prompt for English text
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Tokens kept free for the chat format's own overhead
+const OVERHEAD_TOKENS: usize = 50;
+
+/// Shortest a comment is cut to, however little room is left
+const MIN_COMMENT_CHARS: usize = 400;
+
+/// What replaces the middle of a cut comment
+const CUT_MARKER: &str = " […] ";
+
+/// Rough token count of a text
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// Tokens the user message may take: the context window less the system message and the reply
+pub fn prompt_budget(model: &AiModelConfig, system_message: &str) -> usize {
+    model
+        .max_context_length
+        .saturating_sub(model.parameters.max_tokens)
+        .saturating_sub(estimate_tokens(system_message))
+        .saturating_sub(OVERHEAD_TOKENS)
+}
+
+/// Shorten the request until its user message fits the budget, returning the fields that were shortened
+pub fn fit_request(request: &mut ReplyGenerationRequest, budget_tokens: usize) -> Vec<&'static str> {
+    let mut condensed = Vec::new();
+    let budget_chars = budget_tokens * CHARS_PER_TOKEN;
+    let excess = |request: &ReplyGenerationRequest| build_user_message(request).chars().count().saturating_sub(budget_chars);
+
+    // Previous interactions are newest first
+    if excess(request) > 0 && !request.previous_interactions.is_empty() {
+        while excess(request) > 0 && request.previous_interactions.pop().is_some() {}
+        condensed.push("previous_interactions");
+    }
+
+    // Thread replies are oldest first
+    if excess(request) > 0 && !request.thread_replies.is_empty() {
+        while excess(request) > 0 && !request.thread_replies.is_empty() {
+            request.thread_replies.remove(0);
+        }
+        condensed.push("thread_replies");
+    }
+
+    let over = excess(request);
+    if over > 0 {
+        let length = request.comment_text.chars().count();
+        let keep = length.saturating_sub(over + CUT_MARKER.chars().count()).max(MIN_COMMENT_CHARS);
+        if keep < length {
+            request.comment_text = cut_middle(&request.comment_text, keep);
+            condensed.push("comment");
+        }
+    }
+
+    condensed
+}
+
+/// The text cut to about `keep` characters, keeping its first two thirds and last third
+fn cut_middle(text: &str, keep: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let head = keep * 2 / 3;
+    let tail = keep - head;
+
+    let mut cut: String = chars[..head].iter().collect();
+    cut.push_str(CUT_MARKER);
+    cut.extend(&chars[chars.len() - tail..]);
+    cut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(comment_text: &str) -> ReplyGenerationRequest {
+        ReplyGenerationRequest {
+            comment_text: comment_text.to_string(),
+            comment_author: "Viewer".to_string(),
+            video_title: "Video".to_string(),
+            video_id: "v1".to_string(),
+            previous_interactions: Vec::new(),
+            thread_replies: Vec::new(),
+            follow_up: None,
+            timestamps: Vec::new(),
+            tone: "friendly".to_string(),
+            persona: None,
+            reply_language: None,
+            template: None,
+            de_escalate: false,
+            additional_instructions: None,
+            max_length: None,
+            parameter_overrides: None,
+        }
+    }
+
+    #[test]
+    fn test_request_within_budget_is_untouched() {
+        let mut short = request("Great video!");
+        short.previous_interactions = vec!["They wrote: \"Hi\"".to_string()];
+        assert!(fit_request(&mut short, 1000).is_empty());
+        assert_eq!(short.previous_interactions.len(), 1);
+    }
+
+    #[test]
+    fn test_history_goes_before_the_comment() {
+        let mut long = request("Why? ".repeat(100).trim());
+        long.previous_interactions = (0..50).map(|i| format!("They wrote: \"{}\"", "x".repeat(i + 100))).collect();
+        let budget = estimate_tokens(&build_user_message(&long)) - 1000;
+
+        assert_eq!(fit_request(&mut long, budget), vec!["previous_interactions"]);
+        assert!(!long.previous_interactions.is_empty());
+        // The newest interactions are kept
+        assert!(long.previous_interactions[0].contains(&"x".repeat(100)));
+        assert_eq!(long.comment_text.chars().count(), 499);
+    }
+
+    #[test]
+    fn test_long_comment_keeps_its_start_and_end() {
+        let text = format!("START {} END", "word ".repeat(20_000));
+        let mut long = request(&text);
+
+        assert_eq!(fit_request(&mut long, 1000), vec!["comment"]);
+        assert!(long.comment_text.starts_with("START"));
+        assert!(long.comment_text.ends_with("END"));
+        assert!(long.comment_text.contains(CUT_MARKER));
+        assert!(estimate_tokens(&build_user_message(&long)) <= 1000);
+    }
+}
@@ -0,0 +1,185 @@
+//! Threads as conversations between commenters and the user.
+//!
+//! A reply by someone else after the user's latest reply in a thread is a
+//! follow-up: the sync flags it, the comment goes back to the inbox as
+//! needing a reply, and the next generated reply answers it with the whole
+//! exchange in context.
+
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashSet;
+
+use crate::db::Database;
+use crate::models::conversation::{Conversation, ConversationMessage, FollowUp};
+use crate::models::{Comment, InteractionType, Reply};
+
+/// Tells the user's own replies apart from everyone else's
+#[derive(Debug, Clone, Default)]
+pub struct OwnReplies {
+    /// The user's channel, which their replies are posted from
+    channel_id: String,
+
+    /// Replies the user posted through the app
+    reply_ids: HashSet<String>,
+}
+
+impl OwnReplies {
+    pub fn new(channel_id: &str, reply_ids: impl IntoIterator<Item = String>) -> Self {
+        Self { channel_id: channel_id.to_string(), reply_ids: reply_ids.into_iter().collect() }
+    }
+
+    /// The user's replies on a video: those from their channel and those they posted through the app
+    pub async fn load(db: &Database, user_id: &str, video_id: &str) -> Result<Self> {
+        let reply_ids = db
+            .get_video_interactions(video_id)
+            .await?
+            .into_iter()
+            .filter(|interaction| interaction.user_id == user_id && interaction.interaction_type == InteractionType::ReplyPosted)
+            .filter_map(|interaction| interaction.reply_id)
+            .filter(|reply_id| !reply_id.is_empty());
+
+        Ok(Self::new(user_id, reply_ids))
+    }
+
+    /// Whether the user wrote a reply
+    pub fn contains(&self, reply: &Reply) -> bool {
+        reply.author_channel_id == self.channel_id || self.reply_ids.contains(&reply.reply_id)
+    }
+}
+
+/// The first reply by someone else after the user's latest reply in a thread, with the user's reply it follows
+pub fn detect_follow_up<'a>(comment: &'a Comment, own: &OwnReplies) -> Option<(&'a Reply, &'a Reply)> {
+    let mut replies: Vec<&Reply> = comment.replies.iter().collect();
+    replies.sort_by_key(|reply| reply.published_at);
+
+    let latest_own = replies.iter().rposition(|reply| own.contains(reply))?;
+    let follow_up = replies[latest_own + 1..].iter().find(|reply| !own.contains(reply))?;
+    Some((replies[latest_own], follow_up))
+}
+
+/// What a sync should do about a thread's follow-up
+#[derive(Debug, Clone, PartialEq)]
+pub enum FollowUpChange {
+    /// Someone answered the user since the last sync
+    New(FollowUp),
+
+    /// The user answered the follow-up
+    Answered,
+}
+
+/// How a thread's follow-up changed, judged on its replies; `None` if it didn't, or if not all replies are known
+pub fn follow_up_change(comment: &Comment, own: &OwnReplies) -> Option<FollowUpChange> {
+    if !comment.has_all_replies() {
+        return None;
+    }
+
+    match (detect_follow_up(comment, own), &comment.follow_up) {
+        (Some((_, reply)), Some(current)) if current.reply_id == reply.reply_id => None,
+        (Some((mine, reply)), _) => Some(FollowUpChange::New(FollowUp {
+            reply_id: reply.reply_id.clone(),
+            in_reply_to: mine.reply_id.clone(),
+            detected_at: Utc::now(),
+        })),
+        (None, Some(_)) => Some(FollowUpChange::Answered),
+        (None, None) => None,
+    }
+}
+
+/// A thread as an exchange, oldest message first
+pub fn conversation(comment: &Comment, replies: &[Reply], own: &OwnReplies) -> Conversation {
+    let follow_up_id = comment.follow_up.as_ref().map(|follow_up| follow_up.reply_id.as_str());
+
+    let mut messages = vec![ConversationMessage {
+        id: comment.comment_id.clone(),
+        author: comment.author.clone(),
+        author_channel_id: comment.author_channel_id.clone(),
+        text: comment.text.clone(),
+        published_at: comment.published_at,
+        mine: false,
+        follow_up: false,
+    }];
+
+    let mut sorted: Vec<&Reply> = replies.iter().collect();
+    sorted.sort_by_key(|reply| reply.published_at);
+    messages.extend(sorted.into_iter().map(|reply| ConversationMessage {
+        id: reply.reply_id.clone(),
+        author: reply.author.clone(),
+        author_channel_id: reply.author_channel_id.clone(),
+        text: reply.text.clone(),
+        published_at: reply.published_at,
+        mine: own.contains(reply),
+        follow_up: follow_up_id == Some(reply.reply_id.as_str()),
+    }));
+
+    Conversation {
+        video_id: comment.video_id.clone(),
+        comment_id: comment.comment_id.clone(),
+        follow_up: comment.follow_up.clone(),
+        messages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentBuilder;
+    use chrono::Duration;
+    use std::collections::HashMap;
+
+    const CREATOR: &str = "UCcreator";
+
+    fn reply(reply_id: &str, channel_id: &str, minutes: i64) -> Reply {
+        Reply {
+            reply_id: reply_id.to_string(),
+            parent_id: "c1".to_string(),
+            author: channel_id.to_string(),
+            author_channel_id: channel_id.to_string(),
+            text: String::new(),
+            text_original: None,
+            like_count: 0,
+            published_at: Utc::now() - Duration::hours(1) + Duration::minutes(minutes),
+            ai_generated: false,
+            ai_model: None,
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn thread(replies: Vec<Reply>) -> Comment {
+        let mut comment = CommentBuilder::new("v1", "c1")
+            .text("What camera?")
+            .published_at(Utc::now() - Duration::hours(2))
+            .replies(replies)
+            .build();
+        comment.replied_to = true;
+        comment
+    }
+
+    #[test]
+    fn test_follow_up_after_own_reply() {
+        let own = OwnReplies::new(CREATOR, Vec::new());
+        let comment = thread(vec![reply("r2", "UCviewer", 2), reply("r1", CREATOR, 1)]);
+
+        let (mine, follow_up) = detect_follow_up(&comment, &own).unwrap();
+        assert_eq!((mine.reply_id.as_str(), follow_up.reply_id.as_str()), ("r1", "r2"));
+        assert!(matches!(follow_up_change(&comment, &own), Some(FollowUpChange::New(f)) if f.in_reply_to == "r1"));
+    }
+
+    #[test]
+    fn test_no_follow_up_once_answered() {
+        // Replies posted through the app count as the user's whatever channel they show
+        let own = OwnReplies::new(CREATOR, vec!["r3".to_string()]);
+        let mut comment = thread(vec![reply("r1", CREATOR, 1), reply("r2", "UCviewer", 2), reply("r3", "UCother", 3)]);
+        assert!(detect_follow_up(&comment, &own).is_none());
+
+        comment.follow_up = Some(FollowUp { reply_id: "r2".to_string(), in_reply_to: "r1".to_string(), detected_at: Utc::now() });
+        assert_eq!(follow_up_change(&comment, &own), Some(FollowUpChange::Answered));
+    }
+
+    #[test]
+    fn test_no_follow_up_without_own_reply() {
+        let own = OwnReplies::new(CREATOR, Vec::new());
+        let comment = thread(vec![reply("r1", "UCviewer", 1)]);
+        assert!(detect_follow_up(&comment, &own).is_none());
+        assert!(follow_up_change(&comment, &own).is_none());
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::sync::Arc;
+
+use crate::db::Database;
+use crate::models::dashboard::{AiSpend, BackfillEstimate, Capacity, Dashboard, OutboxBacklog, QuotaCapacity};
+use crate::services::quota::{self, QuotaTracker};
+use crate::utils::cache::TtlCache;
+use crate::utils::rate_limit::RateLimitState;
+use crate::utils::time_zone;
+
+/// Comment threads per page of a comment sync
+const THREADS_PER_PAGE: usize = 100;
+
+/// How long a computed dashboard is served from the cache
+const CACHE_TTL: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Most users whose dashboards are cached at once
+const CACHE_CAPACITY: usize = 1000;
+
+/// Service computing the home screen numbers
+pub struct DashboardService {
+    db: Database,
+    quota: Arc<QuotaTracker>,
+    cache: TtlCache<String, Dashboard>,
+}
+
+impl DashboardService {
+    /// Create a new dashboard service
+    pub fn new(db: Database, quota: Arc<QuotaTracker>) -> Self {
+        Self {
+            db,
+            quota,
+            cache: TtlCache::new(CACHE_CAPACITY, CACHE_TTL),
+        }
+    }
+
+    /// Get the dashboard for a user, computing it at most once per cache period.
+    ///
+    /// Clients poll this endpoint, so each number comes from an aggregate
+    /// query and the result is cached briefly.
+    pub async fn get(&self, user_id: &str) -> Result<Dashboard> {
+        let key = user_id.to_string();
+        if let Some(dashboard) = self.cache.get(&key) {
+            return Ok(dashboard);
+        }
+
+        let dashboard = self.compute(user_id).await?;
+        self.cache.insert(key, dashboard.clone());
+
+        Ok(dashboard)
+    }
+
+    /// Get the capacity left for a user, uncached; `rate_limiter` is the YouTube limiter's current state
+    pub async fn capacity(&self, user_id: &str, rate_limiter: RateLimitState) -> Result<Capacity> {
+        let now = Utc::now();
+        // The quota is shared and counted in UTC days; spending follows the user's days
+        let start_of_quota_day = Utc.from_utc_datetime(&now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default());
+        let (start_of_today, start_of_month) = self.local_periods(user_id, now).await?;
+
+        let limit = self.quota.daily_limit();
+        let used = self.quota.used_today().await?;
+        let remaining = limit.saturating_sub(used);
+
+        let pending = self.db.get_pending_replies(user_id).await?;
+
+        let video_ids: Vec<String> = self
+            .db
+            .get_user_videos(user_id)
+            .await?
+            .into_iter()
+            .map(|v| v.video_id)
+            .collect();
+        let counts = self.db.count_comments_per_video(&video_ids).await?;
+        let backfill_units: u64 = video_ids
+            .iter()
+            .map(|id| counts.get(id).copied().unwrap_or(0).div_ceil(THREADS_PER_PAGE).max(1) as u64 * quota::LIST_COST)
+            .sum();
+
+        Ok(Capacity {
+            youtube_quota: QuotaCapacity {
+                limit,
+                used,
+                remaining,
+                resets_at: start_of_quota_day + Duration::days(1),
+            },
+            ai_spend: AiSpend {
+                today_usd: self.db.sum_ai_cost_since(user_id, start_of_today).await?,
+                month_usd: self.db.sum_ai_cost_since(user_id, start_of_month).await?,
+            },
+            outbox: OutboxBacklog {
+                pending: pending.len(),
+                next_send_at: pending.iter().map(|q| q.send_at).min(),
+                quota_units: pending.len() as u64 * quota::WRITE_COST,
+            },
+            rate_limiter,
+            backfill: BackfillEstimate {
+                videos: video_ids.len(),
+                quota_units: backfill_units,
+                fits_remaining_quota: backfill_units <= remaining,
+            },
+            computed_at: now,
+        })
+    }
+
+    /// Compute the dashboard for a user
+    async fn compute(&self, user_id: &str) -> Result<Dashboard> {
+        let now = Utc::now();
+        let (start_of_today, start_of_month) = self.local_periods(user_id, now).await?;
+
+        let video_ids: Vec<String> = self
+            .db
+            .get_user_videos(user_id)
+            .await?
+            .into_iter()
+            .map(|v| v.video_id)
+            .collect();
+
+        Ok(Dashboard {
+            unanswered_comments: self.db.count_unanswered_comments(&video_ids).await?,
+            new_comments_today: self.db.count_comments_since(&video_ids, start_of_today).await?,
+            pending_review: self.db.count_pending_drafts(user_id).await?,
+            quota_remaining: self.quota.remaining().await?,
+            quota_limit: self.quota.daily_limit(),
+            monthly_ai_spend_usd: self.db.sum_ai_cost_since(user_id, start_of_month).await?,
+            computed_at: now,
+        })
+    }
+
+    /// When today and this month started in the user's time zone
+    async fn local_periods(&self, user_id: &str, now: DateTime<Utc>) -> Result<(DateTime<Utc>, DateTime<Utc>)> {
+        let tz = self.db.get_user(user_id).await?.map_or(Tz::UTC, |user| user.preferences.time_zone());
+        let today = time_zone::local_day(now, tz);
+        Ok((time_zone::start_of_day(today, tz), time_zone::start_of_month(today, tz)))
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::collections::HashMap;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{InteractionRecord, InteractionType, Reply};
+use crate::models::settings::RuntimeSettings;
+
+/// Metadata key marking a reply that was only simulated
+pub const DRY_RUN_KEY: &str = "dry_run";
+
+/// Action of a reply that would have been posted
+pub const WOULD_REPLY: &str = "reply";
+
+/// Action of a posted reply whose text would have been corrected
+pub const WOULD_EDIT: &str = "edit_reply";
+
+/// Action of a comment that would have been rejected as spam
+pub const WOULD_REJECT: &str = "reject";
+
+/// Action of a comment that would have been moderated; `data["moderation"]` says how
+pub const WOULD_MODERATE: &str = "moderate";
+
+/// Whether an action should only be simulated: the request asks for it or dry-run mode is on for everyone
+pub fn is_dry_run(settings: &RuntimeSettings, requested: bool) -> bool {
+    requested || settings.dry_run
+}
+
+/// The reply that would have been posted, with a made-up ID
+pub fn simulated_reply(comment_id: &str, text: &str) -> Reply {
+    Reply {
+        reply_id: format!("dry-run-{}", Uuid::new_v4()),
+        parent_id: comment_id.to_string(),
+        author: String::new(),
+        author_channel_id: String::new(),
+        text: text.to_string(),
+        text_original: None,
+        like_count: 0,
+        published_at: Utc::now(),
+        ai_generated: false,
+        ai_model: None,
+        metadata: HashMap::from([(DRY_RUN_KEY.to_string(), "true".to_string())]),
+    }
+}
+
+/// Whether a reply was only simulated
+pub fn is_simulated(reply: &Reply) -> bool {
+    reply.metadata.contains_key(DRY_RUN_KEY)
+}
+
+/// Log and record an action that was simulated instead of sent to YouTube
+pub async fn record(db: &Database, user_id: &str, comment_id: &str, action: &str, mut data: HashMap<String, String>) -> Result<()> {
+    info!("Dry run: {} for comment {} of user {}: {:?}", action, comment_id, user_id, data);
+    data.insert("action".to_string(), action.to_string());
+
+    let interaction = InteractionRecord {
+        id: Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        video_id: String::new(),
+        comment_id: comment_id.to_string(),
+        reply_id: None,
+        interaction_type: InteractionType::DryRun,
+        timestamp: Utc::now(),
+        data,
+    };
+    db.record_interaction(&interaction).await
+}
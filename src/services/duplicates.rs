@@ -0,0 +1,101 @@
+use anyhow::Result;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::Comment;
+use crate::models::duplicate::DuplicateGroup;
+
+/// Shortest normalized text fingerprinted, so short comments like "First!" aren't grouped
+const MIN_FINGERPRINT_CHARS: usize = 16;
+
+/// Most groups listed at once, largest first
+const MAX_GROUPS: usize = 100;
+
+/// Fingerprint of a comment's text, equal for near-identical comments.
+///
+/// Case, punctuation, emoji and spacing are ignored, so copy-pasted spam with
+/// small variations gets the same fingerprint. Returns `None` for short texts.
+pub fn fingerprint(text: &str) -> Option<String> {
+    let normalized = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if normalized.chars().count() < MIN_FINGERPRINT_CHARS {
+        return None;
+    }
+
+    // FNV-1a, stable across builds unlike the std hasher
+    let hash = normalized.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    Some(format!("{:016x}", hash))
+}
+
+/// Finds and acts on groups of duplicate comments across a user's videos
+pub struct DuplicateService {
+    db: Database,
+}
+
+impl DuplicateService {
+    /// Create a new duplicate detection service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Groups of at least `min_comments` near-identical comments, largest first
+    pub async fn groups(&self, user_id: &str, min_comments: usize) -> Result<Vec<DuplicateGroup>> {
+        let video_ids = self.user_video_ids(user_id).await?;
+        let mut groups: Vec<DuplicateGroup> = self
+            .db
+            .get_duplicate_groups(&video_ids)
+            .await?
+            .into_iter()
+            .filter(|group| group.comments >= min_comments.max(2))
+            .collect();
+
+        groups.sort_by(|a, b| b.comments.cmp(&a.comments).then(b.last_seen.cmp(&a.last_seen)));
+        groups.truncate(MAX_GROUPS);
+        Ok(groups)
+    }
+
+    /// The comments of a group on the user's videos, oldest first
+    pub async fn members(&self, user_id: &str, group_id: &str) -> Result<Vec<Comment>> {
+        let video_ids = self.user_video_ids(user_id).await?;
+        let comments = self.db.get_duplicate_comments(&video_ids, group_id).await?;
+        if comments.is_empty() {
+            return Err(AppError::NotFound(format!("Duplicate group {}", group_id)).into());
+        }
+
+        Ok(comments)
+    }
+
+    /// Mark every comment of a group as spam, keeping them out of the inbox; returns the comments marked
+    pub async fn mark_spam(&self, user_id: &str, group_id: &str) -> Result<Vec<Comment>> {
+        let comments = self.members(user_id, group_id).await?;
+        let comment_ids: Vec<String> = comments.iter().map(|c| c.comment_id.clone()).collect();
+        self.db.mark_comments_spam(&comment_ids, &format!("duplicate:{}", group_id)).await?;
+        Ok(comments)
+    }
+
+    /// IDs of the user's stored videos
+    async fn user_video_ids(&self, user_id: &str) -> Result<Vec<String>> {
+        Ok(self.db.get_user_videos(user_id).await?.into_iter().map(|v| v.video_id).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_ignores_small_variations() {
+        let spam = fingerprint("Check out my channel for FREE giveaways!!!");
+        assert!(spam.is_some());
+        assert_eq!(spam, fingerprint("check out my channel   for free giveaways 🔥"));
+        assert_ne!(spam, fingerprint("Check out my channel for paid giveaways!"));
+        assert_eq!(fingerprint("First!"), None);
+    }
+}
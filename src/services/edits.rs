@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+/// How a posted reply differs from the draft it was edited from, by word
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplyDiff {
+    /// The edit in word-diff form: `[-removed-]` and `{+added+}` around changed words
+    pub diff: String,
+
+    /// Words in the reply that weren't in the draft
+    pub words_added: usize,
+
+    /// Words in the draft that didn't make it into the reply
+    pub words_removed: usize,
+
+    /// Share of all words that changed, from 0 (untouched) to 1 (rewritten)
+    pub edit_ratio: f64,
+}
+
+impl ReplyDiff {
+    /// Compare a draft with the text the user posted instead
+    pub fn between(draft: &str, edited: &str) -> Self {
+        let before: Vec<&str> = draft.split_whitespace().collect();
+        let after: Vec<&str> = edited.split_whitespace().collect();
+
+        // Longest common subsequence of words, filled from the end so the walk below goes forwards
+        let mut common = vec![vec![0usize; after.len() + 1]; before.len() + 1];
+        for i in (0..before.len()).rev() {
+            for j in (0..after.len()).rev() {
+                common[i][j] = if before[i] == after[j] {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+
+        let mut diff = WordDiff::default();
+        let (mut i, mut j) = (0, 0);
+        while i < before.len() || j < after.len() {
+            if i < before.len() && j < after.len() && before[i] == after[j] {
+                diff.keep(before[i]);
+                i += 1;
+                j += 1;
+            } else if j < after.len() && (i == before.len() || common[i][j + 1] >= common[i + 1][j]) {
+                diff.add(after[j]);
+                j += 1;
+            } else {
+                diff.remove(before[i]);
+                i += 1;
+            }
+        }
+
+        let total = before.len() + after.len();
+        let edit_ratio = if total == 0 {
+            0.0
+        } else {
+            (diff.added + diff.removed) as f64 / total as f64
+        };
+
+        Self {
+            words_added: diff.added,
+            words_removed: diff.removed,
+            diff: diff.finish(),
+            edit_ratio,
+        }
+    }
+
+    /// The diff as interaction data, next to the draft and reply texts
+    pub fn to_data(&self) -> HashMap<String, String> {
+        HashMap::from([
+            ("diff".to_string(), self.diff.clone()),
+            ("words_added".to_string(), self.words_added.to_string()),
+            ("words_removed".to_string(), self.words_removed.to_string()),
+            ("edit_ratio".to_string(), format!("{:.2}", self.edit_ratio)),
+        ])
+    }
+}
+
+/// Builds word-diff text, grouping runs of removed and added words
+#[derive(Default)]
+struct WordDiff {
+    out: Vec<String>,
+    removing: Vec<String>,
+    adding: Vec<String>,
+    added: usize,
+    removed: usize,
+}
+
+impl WordDiff {
+    fn keep(&mut self, word: &str) {
+        self.flush();
+        self.out.push(word.to_string());
+    }
+
+    fn add(&mut self, word: &str) {
+        self.adding.push(word.to_string());
+        self.added += 1;
+    }
+
+    fn remove(&mut self, word: &str) {
+        self.removing.push(word.to_string());
+        self.removed += 1;
+    }
+
+    fn flush(&mut self) {
+        if !self.removing.is_empty() {
+            self.out.push(format!("[-{}-]", self.removing.join(" ")));
+            self.removing.clear();
+        }
+        if !self.adding.is_empty() {
+            self.out.push(format!("{{+{}+}}", self.adding.join(" ")));
+            self.adding.clear();
+        }
+    }
+
+    fn finish(mut self) -> String {
+        self.flush();
+        self.out.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unchanged() {
+        let diff = ReplyDiff::between("Thanks for watching!", "Thanks  for watching!");
+        assert_eq!(diff.diff, "Thanks for watching!");
+        assert_eq!((diff.words_added, diff.words_removed), (0, 0));
+        assert_eq!(diff.edit_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_replaced_words() {
+        let diff = ReplyDiff::between("Thanks for watching the video!", "Thanks so much for watching!");
+        assert_eq!(diff.diff, "Thanks {+so much+} for [-watching the video!-] {+watching!+}");
+        assert_eq!((diff.words_added, diff.words_removed), (3, 3));
+        assert_eq!(diff.to_data()["edit_ratio"], "0.60");
+    }
+
+    #[test]
+    fn test_rewritten() {
+        let diff = ReplyDiff::between("Great point", "Agreed");
+        assert_eq!(diff.diff, "[-Great point-] {+Agreed+}");
+        assert_eq!(diff.edit_ratio, 1.0);
+    }
+}
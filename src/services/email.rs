@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use std::env;
+
+use crate::models::auth::User;
+use crate::models::notification::{Notification, NotificationChannel};
+use crate::services::notifications::Notifier;
+
+/// Default SMTP submission port (STARTTLS)
+const DEFAULT_SMTP_PORT: u16 = 587;
+
+/// SMTP configuration
+#[derive(Debug, Clone)]
+pub struct SmtpConfig {
+    /// SMTP server host
+    pub host: String,
+
+    /// SMTP server port
+    pub port: u16,
+
+    /// SMTP username, if the server requires authentication
+    pub username: Option<String>,
+
+    /// SMTP password, if the server requires authentication
+    pub password: Option<String>,
+
+    /// Sender address, e.g. `YouTube Commenter <noreply@example.com>`
+    pub from: String,
+}
+
+impl SmtpConfig {
+    /// Read the SMTP configuration from environment variables.
+    ///
+    /// Returns `None` if `SMTP_HOST` is not set, which disables email.
+    pub fn from_env() -> Result<Option<Self>> {
+        let host = match env::var("SMTP_HOST") {
+            Ok(host) => host,
+            Err(_) => return Ok(None),
+        };
+
+        let port = match env::var("SMTP_PORT") {
+            Ok(port) => port.parse().context("SMTP_PORT must be a port number")?,
+            Err(_) => DEFAULT_SMTP_PORT,
+        };
+
+        Ok(Some(Self {
+            host,
+            port,
+            username: env::var("SMTP_USERNAME").ok(),
+            password: env::var("SMTP_PASSWORD").ok(),
+            from: env::var("SMTP_FROM").context("SMTP_FROM environment variable not set")?,
+        }))
+    }
+}
+
+/// Sends notifications by email over SMTP
+pub struct EmailNotifier {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+impl EmailNotifier {
+    /// Create a new email notifier
+    pub fn new(config: SmtpConfig) -> Result<Self> {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)
+            .with_context(|| format!("Invalid SMTP host {}", config.host))?
+            .port(config.port);
+
+        if let (Some(username), Some(password)) = (config.username, config.password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        let from = config
+            .from
+            .parse()
+            .with_context(|| format!("Invalid SMTP_FROM address {}", config.from))?;
+
+        Ok(Self {
+            transport: builder.build(),
+            from,
+        })
+    }
+
+    /// Send a notification to an address
+    pub async fn send(&self, to: &str, notification: &Notification) -> Result<()> {
+        let to: Mailbox = to
+            .parse()
+            .with_context(|| format!("Invalid recipient address {}", to))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(to)
+            .subject(&notification.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(notification.body.clone())
+            .context("Failed to build notification email")?;
+
+        self.transport
+            .send(message)
+            .await
+            .context("Failed to send notification email")?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Email
+    }
+
+    async fn deliver(&self, user: &User, notification: &Notification) -> Result<bool> {
+        match &user.email {
+            Some(address) => {
+                self.send(address, notification).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn check(&self) -> Result<()> {
+        let connected = self.transport
+            .test_connection()
+            .await
+            .context("Failed to connect to the SMTP server")?;
+        if !connected {
+            anyhow::bail!("The SMTP server didn't accept the connection");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+use crate::models::event::UserEvent;
+
+/// Events buffered for each user; a subscriber further behind misses the oldest
+pub const CHANNEL_CAPACITY: usize = 256;
+
+/// Events of all users buffered for subscribers to every user's events
+pub const ALL_CHANNEL_CAPACITY: usize = 4096;
+
+/// Publishes what services do, per user, to whoever is listening right now.
+///
+/// Each user gets a broadcast channel when the first subscriber arrives, and loses
+/// it once the last one is gone. Events nobody is listening for are dropped, so
+/// publishing never waits and never fails.
+pub struct EventBus {
+    channels: Mutex<HashMap<String, broadcast::Sender<UserEvent>>>,
+    all: broadcast::Sender<(String, UserEvent)>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self {
+            channels: Mutex::default(),
+            all: broadcast::channel(ALL_CHANNEL_CAPACITY).0,
+        }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every user's events from now on, with the user they are for,
+    /// e.g. to keep a read model in step with them
+    pub fn subscribe_all(&self) -> broadcast::Receiver<(String, UserEvent)> {
+        self.all.subscribe()
+    }
+
+    /// Receive the user's events from now on
+    pub fn subscribe(&self, user_id: &str) -> broadcast::Receiver<UserEvent> {
+        let mut channels = self.channels.lock().unwrap();
+        channels
+            .entry(user_id.to_string())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Send an event to the user's subscribers, if any
+    pub fn publish(&self, user_id: &str, event: UserEvent) {
+        if self.all.receiver_count() > 0 {
+            let _ = self.all.send((user_id.to_string(), event.clone()));
+        }
+
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(sender) = channels.get(user_id) {
+            // Only fails without receivers, which means the last subscriber left
+            if sender.send(event).is_err() {
+                channels.remove(user_id);
+            }
+        }
+    }
+
+    /// How many subscribers the user has
+    pub fn subscribers(&self, user_id: &str) -> usize {
+        self.channels.lock().unwrap().get(user_id).map_or(0, |sender| sender.receiver_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn posted(reply_id: &str) -> UserEvent {
+        UserEvent::ReplyPosted { comment_id: "c1".to_string(), reply_id: reply_id.to_string(), dry_run: false }
+    }
+
+    #[test]
+    fn test_publish_per_user() {
+        let bus = EventBus::new();
+        bus.publish("u1", posted("r0"));
+
+        let mut u1 = bus.subscribe("u1");
+        let mut u2 = bus.subscribe("u2");
+        bus.publish("u1", posted("r1"));
+
+        assert_eq!(u1.try_recv().unwrap(), posted("r1"));
+        assert!(u1.try_recv().is_err());
+        assert!(u2.try_recv().is_err());
+
+        drop(u1);
+        bus.publish("u1", posted("r2"));
+        assert_eq!(bus.subscribers("u1"), 0);
+        assert!(bus.channels.lock().unwrap().get("u1").is_none());
+    }
+
+    #[test]
+    fn test_subscribe_all() {
+        let bus = EventBus::new();
+        let mut all = bus.subscribe_all();
+        bus.publish("u1", posted("r1"));
+        bus.publish("u2", posted("r2"));
+
+        assert_eq!(all.try_recv().unwrap(), ("u1".to_string(), posted("r1")));
+        assert_eq!(all.try_recv().unwrap(), ("u2".to_string(), posted("r2")));
+        assert_eq!(bus.subscribers("u1"), 0);
+    }
+}
@@ -0,0 +1,81 @@
+use std::cmp::Ordering;
+
+use crate::models::Comment;
+use crate::services::sentiment::{self, SentimentLabel};
+
+/// Fewest likes a comment needs to be suggested for pinning
+pub const MIN_CANDIDATE_LIKES: i32 = 3;
+
+/// Action of a comment pinned in YouTube Studio
+pub const PINNED: &str = "pinned";
+
+/// Action of a comment unpinned in YouTube Studio
+pub const UNPINNED: &str = "unpinned";
+
+/// Action of a comment highlighted in YouTube Studio
+pub const HIGHLIGHTED: &str = "highlighted";
+
+/// Action of a comment no longer highlighted
+pub const UNHIGHLIGHTED: &str = "unhighlighted";
+
+/// Action of a comment given the creator's heart in YouTube Studio
+pub const HEARTED: &str = "hearted";
+
+/// Action of a comment whose heart was taken back
+pub const UNHEARTED: &str = "unhearted";
+
+/// Action of a comment liked from the channel
+pub const LIKED: &str = "liked";
+
+/// Action of a comment no longer liked
+pub const UNLIKED: &str = "unliked";
+
+/// Where the user pins, highlights, hearts and likes a video's comments, which the API can't do
+pub fn studio_url(video_id: &str) -> String {
+    format!("https://studio.youtube.com/video/{}/comments", video_id)
+}
+
+/// Comments worth pinning: liked, positive, visible and not pinned yet, most liked first
+pub fn pin_candidates(comments: Vec<Comment>, limit: usize) -> Vec<Comment> {
+    let mut candidates: Vec<(f32, Comment)> = comments
+        .into_iter()
+        .filter(|c| c.like_count >= MIN_CANDIDATE_LIKES && c.highlight.pinned_at.is_none())
+        .filter(|c| !c.is_hidden())
+        .map(|c| (c.sentiment.unwrap_or_else(|| sentiment::score(&c.text)), c))
+        .filter(|(score, _)| SentimentLabel::from_score(*score) == SentimentLabel::Positive)
+        .collect();
+
+    // Ties in likes go to the warmer comment
+    candidates.sort_by(|(a_score, a), (b_score, b)| {
+        b.like_count.cmp(&a.like_count).then(b_score.partial_cmp(a_score).unwrap_or(Ordering::Equal))
+    });
+
+    candidates.into_iter().take(limit).map(|(_, comment)| comment).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentBuilder;
+    use chrono::Utc;
+
+    fn comment(comment_id: &str, likes: i32, sentiment: f32) -> Comment {
+        CommentBuilder::new("v", comment_id).likes(likes).sentiment(sentiment).build()
+    }
+
+    #[test]
+    fn test_pin_candidates() {
+        let mut pinned = comment("pinned", 90, 0.9);
+        pinned.highlight.pinned_at = Some(Utc::now());
+
+        let comments = vec![
+            comment("liked", 40, 0.5),
+            comment("angry", 80, -0.6),
+            comment("unliked", 1, 0.9),
+            comment("warmer", 40, 0.9),
+            pinned,
+        ];
+        let ids: Vec<String> = pin_candidates(comments, 5).into_iter().map(|c| c.comment_id).collect();
+        assert_eq!(ids, vec!["warmer", "liked"]);
+    }
+}
@@ -0,0 +1,88 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::{InteractionRecord, InteractionType};
+
+/// Action of a comment rejected on YouTube
+pub const MODERATION_REJECTED: &str = "rejected";
+
+/// Action of a quarantined comment put back in the inbox
+pub const MODERATION_RESTORED: &str = "restored";
+
+/// Action of a comment held for review on YouTube
+pub const MODERATION_HELD: &str = "held";
+
+/// Action of a held or rejected comment published on YouTube
+pub const MODERATION_PUBLISHED: &str = "published";
+
+/// An auto-reply skipped because the comment already has a reply
+pub const SKIP_ALREADY_REPLIED: &str = "already_replied";
+
+/// An auto-reply skipped because an earlier rule already replies to the comment
+pub const SKIP_ALREADY_AUTO_REPLIED: &str = "already_auto_replied";
+
+/// An auto-reply skipped because the user muted the video or the commenter
+pub const SKIP_MUTED: &str = "muted";
+
+/// An auto-reply skipped because the comment looks hostile, so the user answers it themselves
+pub const SKIP_HOSTILE: &str = "hostile";
+
+/// Record an action in the user's history.
+///
+/// History is a side effect of the action, so a failure is logged rather than returned.
+pub async fn record(
+    db: &Database,
+    user_id: &str,
+    video_id: &str,
+    comment_id: &str,
+    interaction_type: InteractionType,
+    data: HashMap<String, String>,
+) {
+    let interaction = InteractionRecord {
+        id: Uuid::new_v4().to_string(),
+        user_id: user_id.to_string(),
+        video_id: video_id.to_string(),
+        comment_id: comment_id.to_string(),
+        reply_id: None,
+        interaction_type,
+        timestamp: Utc::now(),
+        data,
+    };
+
+    if let Err(e) = db.record_interaction(&interaction).await {
+        error!("Error recording {:?} interaction for comment {}: {}", interaction.interaction_type, comment_id, e);
+    }
+}
+
+/// Parse a comma-separated list of interaction types, e.g. `DraftCreated,ReplyPosted`
+pub fn parse_types(list: &str) -> Result<Vec<InteractionType>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            serde_json::from_value(Value::String(name.to_string()))
+                .map_err(|_| AppError::Validation(format!("Unknown interaction type {:?}", name)).into())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_types() {
+        assert_eq!(
+            parse_types("DraftCreated, CommenterBanned,").unwrap(),
+            vec![InteractionType::DraftCreated, InteractionType::CommenterBanned],
+        );
+        assert!(parse_types("Custom").is_err());
+        assert!(parse_types("draft_created").is_err());
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::db::Database;
+use crate::models::commenter::CommenterProfile;
+use crate::models::event::UserEvent;
+use crate::models::inbox::InboxEntry;
+use crate::models::InteractionRecord;
+
+/// Keeps the inbox projection (`inbox_items`) in step with the users' events.
+///
+/// Entries are rebuilt from the comments, commenter profiles and history each
+/// event touches, so a missed or reordered event is corrected by the next one.
+pub struct InboxProjection {
+    db: Database,
+}
+
+impl InboxProjection {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Bring the projection up to date with an event of a user
+    pub async fn apply(&self, user_id: &str, event: &UserEvent) -> Result<()> {
+        match event {
+            UserEvent::CommentsFetched { video_id, .. } => self.project_video(user_id, video_id).await,
+            UserEvent::CommentEdited { comment_id, .. }
+            | UserEvent::CommentChanged { comment_id, .. }
+            | UserEvent::ReplyGenerated { comment_id, .. }
+            | UserEvent::ReplyPosted { comment_id, .. } => self.project_comment(user_id, comment_id).await,
+            UserEvent::CommenterUpdated { channel_id } => {
+                let profile = self.db.get_commenter_profile(user_id, channel_id).await?
+                    .unwrap_or_else(|| CommenterProfile::new(user_id, channel_id));
+                self.db.update_inbox_commenter(&profile).await
+            }
+        }
+    }
+
+    /// Rebuild the entries of every comment on a user's video
+    pub async fn project_video(&self, user_id: &str, video_id: &str) -> Result<()> {
+        let video = match self.db.get_video(video_id).await? {
+            Some(video) if video.user_id == user_id => video,
+            _ => return Ok(()),
+        };
+
+        let comments = self.db.get_comments(video_id).await?.unwrap_or_default();
+        let profiles = self.profiles(user_id).await?;
+
+        // Newest first, so the first one seen per comment is its latest
+        let mut last_interactions: HashMap<String, InteractionRecord> = HashMap::new();
+        for interaction in self.db.get_video_interactions(video_id).await? {
+            if interaction.user_id == user_id {
+                last_interactions.entry(interaction.comment_id.clone()).or_insert(interaction);
+            }
+        }
+
+        let entries: Vec<InboxEntry> = comments
+            .into_iter()
+            .map(|comment| {
+                let profile = profiles.get(&comment.author_channel_id);
+                let last_interaction = last_interactions.get(&comment.comment_id);
+                InboxEntry::new(&video, comment, profile, last_interaction)
+            })
+            .collect();
+
+        self.db.replace_video_inbox_entries(video_id, &entries).await
+    }
+
+    /// Rebuild the entry of one comment on a user's video
+    pub async fn project_comment(&self, user_id: &str, comment_id: &str) -> Result<()> {
+        let comment = match self.db.get_comment(comment_id).await? {
+            Some(comment) => comment,
+            None => return self.db.delete_inbox_entry(comment_id).await,
+        };
+        let video = match self.db.get_video(&comment.video_id).await? {
+            Some(video) if video.user_id == user_id => video,
+            _ => return Ok(()),
+        };
+
+        let profile = self.db.get_commenter_profile(user_id, &comment.author_channel_id).await?;
+        let interactions = self.db.get_comment_interactions(comment_id).await?;
+        let last_interaction = interactions.iter().rev().find(|interaction| interaction.user_id == user_id);
+
+        let entry = InboxEntry::new(&video, comment, profile.as_ref(), last_interaction);
+        self.db.save_inbox_entry(&entry).await
+    }
+
+    /// Rebuild the entries of every comment on the user's videos
+    pub async fn project_user(&self, user_id: &str) -> Result<usize> {
+        let videos = self.db.get_user_videos(user_id).await?;
+        for video in &videos {
+            self.project_video(user_id, &video.video_id).await?;
+        }
+        Ok(videos.len())
+    }
+
+    /// Apply events as they are published. Events missed while lagging behind
+    /// are made up for by rebuilding every user's entries.
+    pub fn spawn(self: Arc<Self>, mut events: broadcast::Receiver<(String, UserEvent)>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok((user_id, event)) => {
+                        if let Err(e) = self.apply(&user_id, &event).await {
+                            error!("Error projecting {} event of user {} into the inbox: {}", event.name(), user_id, e);
+                        }
+                    }
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("Inbox projection missed {} events, rebuilding it", missed);
+                        if let Err(e) = self.rebuild().await {
+                            error!("Error rebuilding the inbox projection: {}", e);
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        })
+    }
+
+    /// Rebuild every user's entries
+    async fn rebuild(&self) -> Result<()> {
+        let mut videos = 0;
+        for user_id in self.db.get_video_owner_ids().await? {
+            videos += self.project_user(&user_id).await?;
+        }
+        info!("Rebuilt the inbox projection of {} videos", videos);
+        Ok(())
+    }
+
+    async fn profiles(&self, user_id: &str) -> Result<HashMap<String, CommenterProfile>> {
+        Ok(self.db.get_commenter_profiles(user_id).await?
+            .into_iter()
+            .map(|profile| (profile.channel_id.clone(), profile))
+            .collect())
+    }
+}
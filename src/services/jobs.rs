@@ -0,0 +1,84 @@
+use anyhow::Result;
+use std::future::Future;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::models::job::{Job, JobItemResult, JobKind, JobStatus};
+
+/// Service for running long operations in the background and tracking their progress
+pub struct JobService {
+    db: Database,
+}
+
+impl JobService {
+    /// Create a new job service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Create a job and run `work` for it in a background task.
+    ///
+    /// Returns as soon as the job is stored, so callers can hand the job ID
+    /// back to the client immediately. `work` reports per-item results
+    /// through the [`JobHandle`]; if it returns an error the job is marked
+    /// as failed.
+    pub async fn start<F, Fut>(&self, user_id: &str, kind: JobKind, total: usize, work: F) -> Result<Job>
+    where
+        F: FnOnce(JobHandle) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let job = Job::new(user_id, kind, total);
+        self.db.create_job(&job).await?;
+
+        info!("Started {:?} job {} with {} items", job.kind, job.job_id, total);
+
+        let handle = JobHandle {
+            db: self.db.clone(),
+            job_id: job.job_id.clone(),
+        };
+
+        tokio::spawn(async move {
+            let db = handle.db.clone();
+            let job_id = handle.job_id.clone();
+
+            if let Err(e) = db.set_job_status(&job_id, JobStatus::Running, None).await {
+                error!("Error updating job {}: {}", job_id, e);
+            }
+
+            let (status, job_error) = match work(handle).await {
+                Ok(()) => (JobStatus::Completed, None),
+                Err(e) => {
+                    error!("Job {} failed: {}", job_id, e);
+                    (JobStatus::Failed, Some(e.to_string()))
+                }
+            };
+
+            if let Err(e) = db.set_job_status(&job_id, status, job_error).await {
+                error!("Error updating job {}: {}", job_id, e);
+            }
+        });
+
+        Ok(job)
+    }
+
+    /// Get a job by ID
+    pub async fn get_job(&self, job_id: &str) -> Result<Option<Job>> {
+        self.db.get_job(job_id).await
+    }
+}
+
+/// Handle given to a running job for reporting progress
+#[derive(Clone)]
+pub struct JobHandle {
+    db: Database,
+    job_id: String,
+}
+
+impl JobHandle {
+    /// Record the result of one item and advance the progress counters
+    pub async fn record(&self, result: JobItemResult) {
+        if let Err(e) = self.db.record_job_item(&self.job_id, &result).await {
+            error!("Error recording progress for job {}: {}", self.job_id, e);
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::util::Timeout;
+use std::env;
+use std::time::Duration;
+
+use crate::services::stream::StreamPublisher;
+
+/// How long a message may wait to be accepted by the brokers
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Publishes stream events to Kafka topics, keyed by user so each user's events stay in one partition
+pub struct KafkaPublisher {
+    producer: FutureProducer,
+}
+
+impl KafkaPublisher {
+    /// Create a producer for the brokers in `KAFKA_BROKERS` (`host:port`, comma-separated)
+    pub fn from_env() -> Result<Self> {
+        let brokers = env::var("KAFKA_BROKERS").context("STREAM_SINK is kafka, but KAFKA_BROKERS is not set")?;
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", &brokers)
+            .set("message.timeout.ms", DELIVERY_TIMEOUT.as_millis().to_string())
+            .set("enable.idempotence", "true")
+            .create()
+            .with_context(|| format!("Failed to create a Kafka producer for {}", brokers))?;
+
+        Ok(Self { producer })
+    }
+}
+
+#[async_trait]
+impl StreamPublisher for KafkaPublisher {
+    fn name(&self) -> &'static str {
+        "kafka"
+    }
+
+    async fn publish(&self, topic: &str, key: &str, payload: Vec<u8>) -> Result<()> {
+        let record = FutureRecord::to(topic).key(key).payload(&payload);
+        self.producer
+            .send(record, Timeout::After(DELIVERY_TIMEOUT))
+            .await
+            .map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
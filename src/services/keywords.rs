@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use crate::models::analytics::TermCount;
+
+/// Shortest word counted as a keyword
+const MIN_KEYWORD_LEN: usize = 3;
+
+/// Common words that say nothing about what a comment is about
+const STOPWORDS: &[&str] = &[
+    "about", "after", "all", "also", "and", "any", "are", "back", "because", "been", "but", "can",
+    "could", "did", "does", "doing", "don't", "dont", "even", "for", "from", "get", "got", "had",
+    "has", "have", "her", "here", "him", "his", "how", "i'm", "into", "its", "it's", "just", "know",
+    "like", "make", "more", "much", "not", "now", "one", "only", "other", "our", "out", "really",
+    "see", "she", "should", "some", "still", "than", "that", "that's", "the", "their", "them",
+    "then", "there", "these", "they", "this", "too", "very", "video", "want", "was", "way", "were",
+    "what", "when", "where", "which", "who", "why", "will", "with", "would", "you", "you're",
+    "your",
+];
+
+/// Terms found in a piece of text
+#[derive(Debug, Default, PartialEq)]
+pub struct Terms {
+    /// Lowercased keywords, stopwords removed
+    pub keywords: Vec<String>,
+
+    /// Lowercased hashtags, without the leading `#`
+    pub hashtags: Vec<String>,
+}
+
+/// Split comment text into keywords and hashtags
+pub fn extract_terms(text: &str) -> Terms {
+    let mut terms = Terms::default();
+
+    for raw in text.split_whitespace() {
+        // Links are noise for topic analysis
+        if raw.starts_with("http://") || raw.starts_with("https://") || raw.starts_with("www.") {
+            continue;
+        }
+
+        if let Some(tag) = raw.strip_prefix('#') {
+            let tag: String = tag
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .collect();
+            if !tag.is_empty() {
+                terms.hashtags.push(tag.to_lowercase());
+            }
+            continue;
+        }
+
+        for word in raw.split(|c: char| !c.is_alphanumeric() && c != '\'') {
+            let word = word.trim_matches('\'').to_lowercase();
+            if word.chars().count() < MIN_KEYWORD_LEN
+                || word.chars().all(|c| c.is_numeric())
+                || STOPWORDS.contains(&word.as_str())
+            {
+                continue;
+            }
+            terms.keywords.push(word);
+        }
+    }
+
+    terms
+}
+
+/// Running keyword and hashtag counts
+#[derive(Debug, Default, Clone)]
+pub struct TermCounter {
+    keywords: HashMap<String, usize>,
+    hashtags: HashMap<String, usize>,
+}
+
+impl TermCounter {
+    /// Count the terms of one comment
+    pub fn add(&mut self, text: &str) {
+        let terms = extract_terms(text);
+        for keyword in terms.keywords {
+            *self.keywords.entry(keyword).or_default() += 1;
+        }
+        for hashtag in terms.hashtags {
+            *self.hashtags.entry(hashtag).or_default() += 1;
+        }
+    }
+
+    /// Merge another counter into this one
+    pub fn merge(&mut self, other: &TermCounter) {
+        for (keyword, count) in &other.keywords {
+            *self.keywords.entry(keyword.clone()).or_default() += count;
+        }
+        for (hashtag, count) in &other.hashtags {
+            *self.hashtags.entry(hashtag.clone()).or_default() += count;
+        }
+    }
+
+    /// The `limit` most frequent keywords
+    pub fn top_keywords(&self, limit: usize) -> Vec<TermCount> {
+        top(&self.keywords, limit)
+    }
+
+    /// The `limit` most frequent hashtags
+    pub fn top_hashtags(&self, limit: usize) -> Vec<TermCount> {
+        top(&self.hashtags, limit)
+    }
+}
+
+/// Most frequent terms first, ties broken alphabetically so results are stable
+fn top(counts: &HashMap<String, usize>, limit: usize) -> Vec<TermCount> {
+    let mut terms: Vec<TermCount> = counts
+        .iter()
+        .map(|(term, count)| TermCount {
+            term: term.clone(),
+            count: *count,
+        })
+        .collect();
+
+    terms.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+    terms.truncate(limit);
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_terms() {
+        let terms = extract_terms("Loved the Rust tutorial! Can you do async next? #RustLang #100DaysOfCode https://example.com");
+
+        assert_eq!(terms.keywords, vec!["loved", "rust", "tutorial", "async", "next"]);
+        assert_eq!(terms.hashtags, vec!["rustlang", "100daysofcode"]);
+    }
+
+    #[test]
+    fn test_counter_top() {
+        let mut counter = TermCounter::default();
+        counter.add("rust is great, rust is fast");
+        counter.add("async rust please #rust");
+
+        let keywords = counter.top_keywords(2);
+        assert_eq!(keywords[0].term, "rust");
+        assert_eq!(keywords[0].count, 3);
+        assert_eq!(keywords[1].term, "async");
+
+        assert_eq!(counter.top_hashtags(10)[0].term, "rust");
+    }
+}
@@ -0,0 +1,130 @@
+//! Changes to stored comments and interactions, as they happen.
+//!
+//! The [`LiveFeed`] registers SurrealDB LIVE SELECT queries on the `comments`
+//! and `interactions` tables and republishes what they report on a broadcast
+//! channel, so websockets, notifiers and auto-replies can react to new data
+//! without polling. Changes nobody is listening for are dropped.
+
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+use std::time::Duration;
+use surrealdb::{Action, Notification};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::db::Database;
+use crate::models::{Comment, InteractionRecord};
+
+/// Changes buffered for subscribers; one further behind misses the oldest
+pub const CHANNEL_CAPACITY: usize = 1024;
+
+/// How long to wait before registering a live query again after it ended
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// What happened to a record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl From<Action> for ChangeAction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Create => ChangeAction::Created,
+            Action::Update => ChangeAction::Updated,
+            Action::Delete => ChangeAction::Deleted,
+            // Later SDK versions may add actions; treat them as updates
+            #[allow(unreachable_patterns)]
+            _ => ChangeAction::Updated,
+        }
+    }
+}
+
+/// A change to a stored record, with the record as it is now (or was, once deleted)
+#[derive(Debug, Clone)]
+pub enum RecordChange {
+    Comment { action: ChangeAction, comment: Box<Comment> },
+    Interaction { action: ChangeAction, interaction: Box<InteractionRecord> },
+}
+
+impl RecordChange {
+    pub fn action(&self) -> ChangeAction {
+        match self {
+            RecordChange::Comment { action, .. } | RecordChange::Interaction { action, .. } => *action,
+        }
+    }
+
+    /// The video the changed record belongs to
+    pub fn video_id(&self) -> &str {
+        match self {
+            RecordChange::Comment { comment, .. } => &comment.video_id,
+            RecordChange::Interaction { interaction, .. } => &interaction.video_id,
+        }
+    }
+}
+
+/// Publishes changes to the `comments` and `interactions` tables from live queries
+pub struct LiveFeed {
+    db: Database,
+    sender: broadcast::Sender<RecordChange>,
+}
+
+impl LiveFeed {
+    pub fn new(db: Database) -> Self {
+        Self { db, sender: broadcast::channel(CHANNEL_CAPACITY).0 }
+    }
+
+    /// Receive changes from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<RecordChange> {
+        self.sender.subscribe()
+    }
+
+    /// Spawn the background tasks following each table, registering a live query again whenever one ends
+    pub fn spawn(self: Arc<Self>) -> Vec<JoinHandle<()>> {
+        vec![
+            self.clone().follow("comments", |action, comment| RecordChange::Comment { action, comment: Box::new(comment) }),
+            self.follow("interactions", |action, interaction| RecordChange::Interaction { action, interaction: Box::new(interaction) }),
+        ]
+    }
+
+    fn follow<R>(self: Arc<Self>, table: &'static str, change: fn(ChangeAction, R) -> RecordChange) -> JoinHandle<()>
+    where
+        R: DeserializeOwned + Unpin + Send + Sync + 'static,
+    {
+        tokio::spawn(async move {
+            loop {
+                match self.db.select::<Vec<R>>(table).live().await {
+                    Ok(notifications) => {
+                        info!("Following changes to {}", table);
+                        self.forward(table, notifications, change).await;
+                        warn!("Live query on {} ended", table);
+                    }
+                    Err(e) => error!("Error registering the live query on {}: {}", table, e),
+                }
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        })
+    }
+
+    /// Publish the notifications of a live query until it ends
+    async fn forward<R>(
+        &self,
+        table: &str,
+        mut notifications: impl Stream<Item = surrealdb::Result<Notification<R>>> + Unpin,
+        change: fn(ChangeAction, R) -> RecordChange,
+    ) {
+        while let Some(notification) = notifications.next().await {
+            match notification {
+                Ok(notification) => {
+                    // Only fails without subscribers, which is fine
+                    let _ = self.sender.send(change(notification.action.into(), notification.data));
+                }
+                Err(e) => error!("Error reading a change to {}: {}", table, e),
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use regex::{Regex, RegexBuilder};
+use std::env;
+use std::sync::OnceLock;
+
+use crate::models::{Comment, Reply};
+
+/// Replacement for masked email addresses
+const EMAIL_MASK: &str = "[email]";
+
+/// Replacement for masked phone numbers
+const PHONE_MASK: &str = "[phone]";
+
+/// Fewest digits a number needs to be masked as a phone number, so years and prices are kept
+const MIN_PHONE_DIGITS: usize = 7;
+
+/// Words masked out of the box; `MASKED_WORDS` adds the team's own list, e.g. slurs
+const DEFAULT_MASKED_WORDS: &[&str] = &["fuck", "fucking", "shit", "bitch", "cunt", "asshole", "motherfucker"];
+
+fn email_pattern() -> &'static Regex {
+    static EMAIL: OnceLock<Regex> = OnceLock::new();
+    EMAIL.get_or_init(|| Regex::new(r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b").unwrap())
+}
+
+fn phone_pattern() -> &'static Regex {
+    static PHONE: OnceLock<Regex> = OnceLock::new();
+    PHONE.get_or_init(|| Regex::new(r"\+?\(?\d[\d ().-]{5,}\d").unwrap())
+}
+
+/// The masked words as one case-insensitive pattern, or `None` if the list is empty
+fn word_pattern() -> Option<&'static Regex> {
+    static WORDS: OnceLock<Option<Regex>> = OnceLock::new();
+    WORDS
+        .get_or_init(|| {
+            let extra = env::var("MASKED_WORDS").unwrap_or_default();
+            let words: Vec<String> = DEFAULT_MASKED_WORDS
+                .iter()
+                .map(|w| w.to_string())
+                .chain(extra.split(',').map(|w| w.trim().to_lowercase()))
+                .filter(|w| !w.is_empty())
+                .map(|w| regex::escape(&w))
+                .collect();
+            if words.is_empty() {
+                return None;
+            }
+            RegexBuilder::new(&format!(r"\b({})\b", words.join("|")))
+                .case_insensitive(true)
+                .build()
+                .ok()
+        })
+        .as_ref()
+}
+
+/// Mask email addresses, phone numbers and masked words in a text.
+///
+/// Emails and phone numbers are replaced with `[email]` and `[phone]`; masked
+/// words keep their first letter, e.g. `s***`.
+pub fn mask(text: &str) -> String {
+    let text = email_pattern().replace_all(text, EMAIL_MASK);
+
+    let text = phone_pattern().replace_all(&text, |caps: &regex::Captures| {
+        let number = &caps[0];
+        if number.chars().filter(char::is_ascii_digit).count() >= MIN_PHONE_DIGITS {
+            PHONE_MASK.to_string()
+        } else {
+            number.to_string()
+        }
+    });
+
+    match word_pattern() {
+        Some(words) => words
+            .replace_all(&text, |caps: &regex::Captures| {
+                let word = &caps[0];
+                let mut chars = word.chars();
+                let first = chars.next().map(String::from).unwrap_or_default();
+                format!("{}{}", first, "*".repeat(chars.count()))
+            })
+            .into_owned(),
+        None => text.into_owned(),
+    }
+}
+
+/// Mask the text of a comment and of its replies
+pub fn mask_comment(comment: &mut Comment) {
+    comment.text = mask(&comment.text);
+    comment.text_original = comment.text_original.as_deref().map(mask);
+    mask_replies(&mut comment.replies);
+}
+
+/// Mask the text of replies
+pub fn mask_replies(replies: &mut [Reply]) {
+    for reply in replies {
+        reply.text = mask(&reply.text);
+        reply.text_original = reply.text_original.as_deref().map(mask);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(mask("Mail me at jane.doe@example.com!"), "Mail me at [email]!");
+        assert_eq!(mask("Call +1 (555) 123-4567 now"), "Call [phone] now");
+        assert_eq!(mask("This is SHIT"), "This is S***");
+
+        // Years, prices and timestamps are not phone numbers
+        assert_eq!(mask("Back in 2019 it cost $1500, see 1:02:03"), "Back in 2019 it cost $1500, see 1:02:03");
+    }
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::{Client, Url};
+use serde_json::json;
+use std::env;
+use uuid::Uuid;
+
+use crate::models::auth::User;
+use crate::models::notification::{Notification, NotificationChannel};
+use crate::services::notifications::Notifier;
+
+/// Sends notifications to Matrix rooms through the client-server API
+pub struct MatrixNotifier {
+    client: Client,
+    homeserver: Url,
+    access_token: String,
+}
+
+impl MatrixNotifier {
+    /// Create a Matrix notifier from `MATRIX_HOMESERVER_URL` and `MATRIX_ACCESS_TOKEN`.
+    ///
+    /// Returns `None` if either is unset, which disables Matrix.
+    pub fn from_env(client: Client) -> Result<Option<Self>> {
+        let (homeserver, access_token) = match (env::var("MATRIX_HOMESERVER_URL"), env::var("MATRIX_ACCESS_TOKEN")) {
+            (Ok(homeserver), Ok(access_token)) => (homeserver, access_token),
+            _ => return Ok(None),
+        };
+
+        let homeserver = Url::parse(&homeserver)
+            .with_context(|| format!("Invalid MATRIX_HOMESERVER_URL {}", homeserver))?;
+
+        Ok(Some(Self {
+            client,
+            homeserver,
+            access_token,
+        }))
+    }
+
+    /// Send a notification to a room the bot account has joined
+    pub async fn send(&self, room_id: &str, notification: &Notification) -> Result<()> {
+        // Room IDs contain `!` and `:`, so they must go in as an encoded path segment
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("MATRIX_HOMESERVER_URL cannot be a base URL"))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "rooms", room_id, "send", "m.room.message"])
+            .push(&Uuid::new_v4().to_string());
+
+        let mut body = format!("{}\n\n{}", notification.subject, notification.body);
+        if let Some(link) = &notification.link {
+            body.push_str("\n\n");
+            body.push_str(link);
+        }
+
+        self.client
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(&json!({ "msgtype": "m.text", "body": body }))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("Matrix homeserver rejected the message for room {}", room_id))?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Matrix
+    }
+
+    async fn deliver(&self, user: &User, notification: &Notification) -> Result<bool> {
+        match &user.preferences.notifications.matrix_room_id {
+            Some(room_id) => {
+                self.send(room_id, notification).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn check(&self) -> Result<()> {
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .map_err(|_| anyhow::anyhow!("MATRIX_HOMESERVER_URL cannot be a base URL"))?
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3", "account", "whoami"]);
+
+        self.client
+            .get(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Matrix homeserver rejected MATRIX_ACCESS_TOKEN")?;
+
+        Ok(())
+    }
+}
@@ -1,3 +1,55 @@
 pub mod youtube;
 pub mod auth;
 pub mod ai;
+pub mod alerts;
+pub mod jobs;
+pub mod analytics;
+pub mod clustering;
+pub mod collections;
+pub mod commenters;
+pub mod condense;
+pub mod conversations;
+pub mod sentiment;
+pub mod keywords;
+pub mod live;
+pub mod monitor;
+pub mod masking;
+pub mod model_catalog;
+pub mod mutes;
+pub mod quota;
+pub mod reply_checks;
+pub mod retention;
+pub mod dashboard;
+pub mod dry_run;
+pub mod organizations;
+pub mod outbox;
+pub mod preflight;
+pub mod duplicates;
+pub mod edits;
+pub mod events;
+pub mod highlights;
+pub mod history;
+pub mod inbox;
+pub mod prompts;
+pub mod rules;
+pub mod saved_replies;
+pub mod settings;
+pub mod sharing;
+pub mod sources;
+pub mod spam;
+pub mod stream;
+pub mod timestamps;
+pub mod tones;
+#[cfg(feature = "email")]
+pub mod email;
+pub mod notifications;
+#[cfg(feature = "slack")]
+pub mod slack;
+#[cfg(feature = "telegram")]
+pub mod telegram;
+#[cfg(feature = "matrix")]
+pub mod matrix;
+#[cfg(feature = "nats")]
+pub mod nats;
+#[cfg(feature = "kafka")]
+pub mod kafka;
@@ -0,0 +1,235 @@
+//! The AI models stored at startup.
+//!
+//! By default the built-in OpenAI models are added when missing. `AI_MODELS_FILE`
+//! names a TOML or JSON catalog to seed from instead, and `AI_MODELS_SEED=off`
+//! skips seeding. Seeding merges with what is stored rather than replacing it:
+//! a catalog entry only overwrites the fields it sets, so edits made in the
+//! database survive restarts.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::models::ai::{AiModelConfig, AiModelParameters};
+use crate::services::ai::DEFAULT_MODEL;
+
+/// Context length of a new model whose entry doesn't set one
+const DEFAULT_CONTEXT_LENGTH: usize = 4096;
+
+/// Response length of a new model whose entry doesn't set one
+const DEFAULT_RESPONSE_LENGTH: usize = 1024;
+
+/// Where the models stored at startup come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelSeed {
+    /// The built-in models, added only when missing
+    Builtin,
+
+    /// A catalog file, merged into the stored models
+    File(PathBuf),
+
+    /// Nothing; the stored models are left as they are
+    Off,
+}
+
+impl ModelSeed {
+    /// Read `AI_MODELS_SEED` and `AI_MODELS_FILE`
+    pub fn from_env() -> Result<Self> {
+        match env::var("AI_MODELS_SEED").as_deref() {
+            Ok("off") => return Ok(ModelSeed::Off),
+            Err(_) | Ok("") | Ok("on") => {}
+            Ok(other) => anyhow::bail!("AI_MODELS_SEED must be on or off, not {}", other),
+        }
+
+        Ok(match env::var("AI_MODELS_FILE") {
+            Ok(path) if !path.is_empty() => ModelSeed::File(PathBuf::from(path)),
+            _ => ModelSeed::Builtin,
+        })
+    }
+}
+
+/// A model in a catalog file; only `model_id` is required
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelEntry {
+    pub model_id: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub max_context_length: Option<usize>,
+    pub max_response_length: Option<usize>,
+    pub parameters: Option<AiModelParameters>,
+    pub is_available: Option<bool>,
+    pub prompt_cost_per_1k: Option<f64>,
+    pub completion_cost_per_1k: Option<f64>,
+
+    /// Added to the stored metadata, replacing the keys it sets
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+impl ModelEntry {
+    /// The stored model with the fields this entry sets, or a new model from the entry
+    pub fn merge(self, stored: Option<AiModelConfig>) -> AiModelConfig {
+        let mut model = stored.unwrap_or_else(|| {
+            let max_response_length = self.max_response_length.unwrap_or(DEFAULT_RESPONSE_LENGTH);
+            AiModelConfig {
+                model_id: self.model_id.clone(),
+                name: self.model_id.clone(),
+                description: String::new(),
+                max_context_length: DEFAULT_CONTEXT_LENGTH,
+                max_response_length,
+                parameters: parameters(max_response_length),
+                is_available: true,
+                prompt_cost_per_1k: 0.0,
+                completion_cost_per_1k: 0.0,
+                metadata: HashMap::new(),
+            }
+        });
+
+        if let Some(name) = self.name {
+            model.name = name;
+        }
+        if let Some(description) = self.description {
+            model.description = description;
+        }
+        if let Some(max_context_length) = self.max_context_length {
+            model.max_context_length = max_context_length;
+        }
+        if let Some(max_response_length) = self.max_response_length {
+            model.max_response_length = max_response_length;
+        }
+        if let Some(parameters) = self.parameters {
+            model.parameters = parameters;
+        }
+        if let Some(is_available) = self.is_available {
+            model.is_available = is_available;
+        }
+        if let Some(cost) = self.prompt_cost_per_1k {
+            model.prompt_cost_per_1k = cost;
+        }
+        if let Some(cost) = self.completion_cost_per_1k {
+            model.completion_cost_per_1k = cost;
+        }
+        model.metadata.extend(self.metadata);
+        model
+    }
+}
+
+/// The models of a catalog file
+#[derive(Debug, Deserialize)]
+struct Catalog {
+    models: Vec<ModelEntry>,
+}
+
+/// Read a catalog: TOML if the file ends in `.toml`, otherwise JSON, each with a `models` list
+pub fn load_catalog(path: &Path) -> Result<Vec<ModelEntry>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read the model catalog {}", path.display()))?;
+    parse_catalog(&text, path.extension().is_some_and(|ext| ext == "toml"))
+        .with_context(|| format!("Invalid model catalog {}", path.display()))
+}
+
+fn parse_catalog(text: &str, toml: bool) -> Result<Vec<ModelEntry>> {
+    let catalog: Catalog = if toml { toml::from_str(text)? } else { serde_json::from_str(text)? };
+
+    if let Some(entry) = catalog.models.iter().find(|entry| entry.model_id.trim().is_empty()) {
+        anyhow::bail!("Every model needs a model_id, not {:?}", entry.model_id);
+    }
+    Ok(catalog.models)
+}
+
+/// Sampling parameters of a model without its own
+fn parameters(max_tokens: usize) -> AiModelParameters {
+    AiModelParameters {
+        temperature: 0.7,
+        top_p: 1.0,
+        frequency_penalty: 0.0,
+        presence_penalty: 0.0,
+        max_tokens,
+        stop: vec![],
+        additional: Default::default(),
+    }
+}
+
+/// The models the server knows without a catalog
+pub fn builtin_models() -> Vec<AiModelConfig> {
+    vec![
+        AiModelConfig {
+            model_id: DEFAULT_MODEL.to_string(),
+            name: "GPT-3.5 Turbo".to_string(),
+            description: "A good balance of quality and speed for most reply generation needs".to_string(),
+            max_context_length: 4096,
+            max_response_length: 1024,
+            parameters: parameters(1024),
+            is_available: true,
+            prompt_cost_per_1k: 0.0005,
+            completion_cost_per_1k: 0.0015,
+            metadata: Default::default(),
+        },
+        AiModelConfig {
+            model_id: "gpt-4".to_string(),
+            name: "GPT-4".to_string(),
+            description: "Highest quality replies with better understanding of context and nuance".to_string(),
+            max_context_length: 8192,
+            max_response_length: 2048,
+            parameters: parameters(2048),
+            is_available: true,
+            prompt_cost_per_1k: 0.03,
+            completion_cost_per_1k: 0.06,
+            metadata: Default::default(),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toml_and_json_catalogs() {
+        let toml = r#"
+            [[models]]
+            model_id = "gpt-4o-mini"
+            name = "GPT-4o mini"
+            max_context_length = 128000
+        "#;
+        let entries = parse_catalog(toml, true).unwrap();
+        assert_eq!(entries[0].model_id, "gpt-4o-mini");
+        assert_eq!(entries[0].max_context_length, Some(128000));
+
+        let json = r#"{"models": [{"model_id": "gpt-4o", "is_available": false}]}"#;
+        assert_eq!(parse_catalog(json, false).unwrap()[0].is_available, Some(false));
+
+        assert!(parse_catalog(r#"{"models": [{"model_id": " "}]}"#, false).is_err());
+    }
+
+    #[test]
+    fn test_entry_only_overwrites_what_it_sets() {
+        let mut stored = builtin_models().remove(1);
+        stored.description = "Edited in the database".to_string();
+        stored.metadata.insert("tier".to_string(), "premium".to_string());
+
+        let entry = ModelEntry {
+            model_id: "gpt-4".to_string(),
+            prompt_cost_per_1k: Some(0.01),
+            metadata: HashMap::from([("region".to_string(), "eu".to_string())]),
+            ..Default::default()
+        };
+        let merged = entry.merge(Some(stored));
+        assert_eq!(merged.prompt_cost_per_1k, 0.01);
+        assert_eq!(merged.completion_cost_per_1k, 0.06);
+        assert_eq!(merged.description, "Edited in the database");
+        assert_eq!(merged.metadata.len(), 2);
+    }
+
+    #[test]
+    fn test_new_model_from_entry() {
+        let entry = ModelEntry { model_id: "local-llm".to_string(), max_response_length: Some(512), ..Default::default() };
+        let model = entry.merge(None);
+        assert_eq!(model.name, "local-llm");
+        assert_eq!(model.max_context_length, DEFAULT_CONTEXT_LENGTH);
+        assert_eq!(model.parameters.max_tokens, 512);
+        assert!(model.is_available);
+    }
+}
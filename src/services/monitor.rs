@@ -0,0 +1,160 @@
+//! Per-user comment monitors.
+//!
+//! Each running monitor is a task that syncs the user's due videos every
+//! `polling_interval` seconds from their preferences. New comments go through
+//! the comment pipeline like any sync, so they are stored, recorded in the
+//! history and notified about; the monitor keeps count of them. Each video's
+//! own monitor settings still decide whether a pass syncs it.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::services::youtube::YouTubeApi;
+
+/// Shortest polling interval a monitor runs with, whatever the preferences say, to spare the API quota
+pub const MIN_POLLING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Where a user's monitor is at
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MonitorState {
+    /// Whether the monitor task is running
+    pub running: bool,
+
+    /// Seconds between passes
+    pub polling_interval_secs: u64,
+
+    pub started_at: Option<DateTime<Utc>>,
+
+    /// When the last pass finished
+    pub last_checked_at: Option<DateTime<Utc>>,
+
+    /// New comments the last pass found
+    pub last_new_comments: usize,
+
+    /// New comments found since the monitor started
+    pub total_new_comments: usize,
+
+    /// Why the last pass failed, if it did
+    pub last_error: Option<String>,
+}
+
+/// A user's running monitor task and what it reports
+struct Running {
+    handle: JoinHandle<()>,
+    state: Arc<Mutex<MonitorState>>,
+}
+
+/// Starts, stops and reports on each user's monitor task
+pub struct CommentMonitor {
+    db: Database,
+    youtube: Arc<dyn YouTubeApi>,
+    monitors: Mutex<HashMap<String, Running>>,
+}
+
+impl CommentMonitor {
+    pub fn new(db: Database, youtube: Arc<dyn YouTubeApi>) -> Self {
+        Self { db, youtube, monitors: Mutex::new(HashMap::new()) }
+    }
+
+    /// Start the user's monitor with their polling interval, restarting it if it runs, and return its state
+    pub async fn start(&self, user_id: &str) -> Result<MonitorState> {
+        let user = self.db.get_user(user_id).await?
+            .ok_or_else(|| AppError::NotFound("User".to_string()))?;
+        let interval = Duration::from_secs(user.preferences.polling_interval.into()).max(MIN_POLLING_INTERVAL);
+
+        let state = Arc::new(Mutex::new(MonitorState {
+            running: true,
+            polling_interval_secs: interval.as_secs(),
+            started_at: Some(Utc::now()),
+            ..Default::default()
+        }));
+        let handle = self.spawn_task(user_id.to_string(), interval, state.clone());
+
+        let previous = self.monitors.lock().unwrap().insert(user_id.to_string(), Running { handle, state: state.clone() });
+        if let Some(previous) = previous {
+            previous.handle.abort();
+        }
+
+        info!("Started the comment monitor of user {}, every {}s", user_id, interval.as_secs());
+        let state = state.lock().unwrap().clone();
+        Ok(state)
+    }
+
+    /// Stop the user's monitor, returning whether it was running
+    pub fn stop(&self, user_id: &str) -> bool {
+        let monitors = self.monitors.lock().unwrap();
+        let Some(running) = monitors.get(user_id) else {
+            return false;
+        };
+
+        running.handle.abort();
+        let mut state = running.state.lock().unwrap();
+        let was_running = state.running && !running.handle.is_finished();
+        state.running = false;
+        info!("Stopped the comment monitor of user {}", user_id);
+        was_running
+    }
+
+    /// The state of the user's monitor; a stopped one reports what it did while it ran
+    pub fn status(&self, user_id: &str) -> MonitorState {
+        let monitors = self.monitors.lock().unwrap();
+        match monitors.get(user_id) {
+            Some(running) => {
+                let mut state = running.state.lock().unwrap().clone();
+                state.running = state.running && !running.handle.is_finished();
+                state
+            }
+            None => MonitorState::default(),
+        }
+    }
+
+    /// Start the monitors of every user with a monitored video, e.g. at startup
+    pub async fn start_all(&self) -> Result<()> {
+        for user_id in self.db.get_monitored_user_ids().await? {
+            if let Err(e) = self.start(&user_id).await {
+                error!("Error starting the comment monitor of user {}: {}", user_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    fn spawn_task(&self, user_id: String, interval: Duration, state: Arc<Mutex<MonitorState>>) -> JoinHandle<()> {
+        let youtube = self.youtube.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            // A slow pass delays the next one rather than causing a burst
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+            loop {
+                interval.tick().await;
+
+                let result = youtube.check_new_comments(&user_id).await;
+                let mut state = state.lock().unwrap();
+                state.last_checked_at = Some(Utc::now());
+                match result {
+                    Ok(new_comments) => {
+                        if new_comments > 0 {
+                            info!("Comment monitor found {} new comments for user {}", new_comments, user_id);
+                        }
+                        state.last_new_comments = new_comments;
+                        state.total_new_comments += new_comments;
+                        state.last_error = None;
+                    }
+                    Err(e) => {
+                        error!("Error checking for new comments of user {}: {}", user_id, e);
+                        state.last_new_comments = 0;
+                        state.last_error = Some(e.to_string());
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use std::collections::HashSet;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::mute::{Mute, MuteKind};
+use crate::models::Comment;
+
+/// Most videos and commenters a user can mute
+pub const MAX_MUTES_PER_USER: usize = 1_000;
+
+/// Longest reason accepted for a mute
+const MAX_REASON_LENGTH: usize = 500;
+
+/// Check that a mute can be stored: it names a target and its reason isn't too long
+pub fn validate(mute: &Mute) -> Result<()> {
+    if mute.target_id.trim().is_empty() {
+        return Err(AppError::Validation("target_id must not be empty".to_string()).into());
+    }
+
+    if mute.reason.as_ref().is_some_and(|reason| reason.chars().count() > MAX_REASON_LENGTH) {
+        return Err(AppError::Validation(format!("reason must be at most {} characters", MAX_REASON_LENGTH)).into());
+    }
+
+    Ok(())
+}
+
+/// The videos and commenters a user muted, for checking comments against
+#[derive(Debug, Clone, Default)]
+pub struct MuteList {
+    videos: HashSet<String>,
+    commenters: HashSet<String>,
+}
+
+impl MuteList {
+    /// The user's mutes
+    pub async fn load(db: &Database, user_id: &str) -> Result<Self> {
+        Ok(Self::from_mutes(&db.get_mutes(user_id).await?))
+    }
+
+    pub fn from_mutes(mutes: &[Mute]) -> Self {
+        let mut list = Self::default();
+        for mute in mutes {
+            let targets = match mute.kind {
+                MuteKind::Video => &mut list.videos,
+                MuteKind::Commenter => &mut list.commenters,
+            };
+            targets.insert(mute.target_id.clone());
+        }
+        list
+    }
+
+    /// Whether nothing is muted
+    pub fn is_empty(&self) -> bool {
+        self.videos.is_empty() && self.commenters.is_empty()
+    }
+
+    /// Whether the video is muted
+    pub fn mutes_video(&self, video_id: &str) -> bool {
+        self.videos.contains(video_id)
+    }
+
+    /// Whether the comment is on a muted video or by a muted commenter
+    pub fn mutes(&self, comment: &Comment) -> bool {
+        self.mutes_video(&comment.video_id) || self.commenters.contains(&comment.author_channel_id)
+    }
+}
+
+/// Looks up the videos and commenters users muted
+pub struct MuteService {
+    db: Database,
+}
+
+impl MuteService {
+    /// Create a new mute service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// A mute of the user; other users' mutes are reported as missing
+    pub async fn get(&self, user_id: &str, mute_id: &str) -> Result<Mute> {
+        self.db
+            .get_mute(mute_id)
+            .await?
+            .filter(|mute| mute.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound(format!("Mute {}", mute_id)).into())
+    }
+
+    /// The name to show a new mute under: the video's title or the commenter's
+    /// latest name on the user's videos. Videos of other users are reported as missing.
+    pub async fn label(&self, user_id: &str, kind: MuteKind, target_id: &str) -> Result<Option<String>> {
+        match kind {
+            MuteKind::Video => match self.db.get_video(target_id).await? {
+                Some(video) if video.user_id == user_id => Ok(Some(video.title)),
+                _ => Err(AppError::NotFound(format!("Video {}", target_id)).into()),
+            },
+            MuteKind::Commenter => {
+                let video_ids: Vec<String> = self.db.get_user_videos(user_id).await?
+                    .into_iter()
+                    .map(|video| video.video_id)
+                    .collect();
+                if video_ids.is_empty() {
+                    return Ok(None);
+                }
+                let comments = self.db.get_comments_by_author(&video_ids, target_id, 1).await?;
+                Ok(comments.into_iter().next().map(|comment| comment.author))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentBuilder;
+
+    fn comment(video_id: &str, author_channel_id: &str) -> Comment {
+        CommentBuilder::new(video_id, "c").author_channel(author_channel_id).text("Nice").build()
+    }
+
+    #[test]
+    fn test_mute_list() {
+        let list = MuteList::from_mutes(&[
+            Mute::new("user-1", MuteKind::Video, "v1"),
+            Mute::new("user-1", MuteKind::Commenter, "troll"),
+        ]);
+
+        assert!(list.mutes_video("v1"));
+        assert!(list.mutes(&comment("v1", "fan")));
+        assert!(list.mutes(&comment("v2", "troll")));
+        assert!(!list.mutes(&comment("v2", "fan")));
+        assert!(MuteList::default().is_empty());
+    }
+}
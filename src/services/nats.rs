@@ -0,0 +1,43 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use std::env;
+
+use crate::services::stream::StreamPublisher;
+
+/// Server connected to unless `NATS_URL` is set
+const DEFAULT_NATS_URL: &str = "nats://localhost:4222";
+
+/// Publishes stream events to NATS subjects
+pub struct NatsPublisher {
+    client: async_nats::Client,
+}
+
+impl NatsPublisher {
+    /// Connect to the server at `NATS_URL`
+    pub async fn from_env() -> Result<Self> {
+        let url = env::var("NATS_URL").unwrap_or_else(|_| DEFAULT_NATS_URL.to_string());
+        let client = async_nats::connect(&url)
+            .await
+            .with_context(|| format!("Failed to connect to NATS at {}", url))?;
+
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl StreamPublisher for NatsPublisher {
+    fn name(&self) -> &'static str {
+        "nats"
+    }
+
+    async fn publish(&self, subject: &str, _key: &str, payload: Vec<u8>) -> Result<()> {
+        // A connection keeps the order subjects are published in, so the key isn't needed
+        self.client.publish(subject.to_string(), payload.into()).await?;
+        Ok(())
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.client.flush().await?;
+        Ok(())
+    }
+}
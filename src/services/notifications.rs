@@ -0,0 +1,549 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Duration, NaiveDate, Timelike, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::i18n::{self, Locale, Message};
+use crate::models::Comment;
+use crate::models::alert::AlertRule;
+use crate::models::auth::User;
+use crate::models::notification::{Notification, NotificationChannel, NotificationEvent, NotificationPriority};
+#[cfg(feature = "email")]
+use crate::services::email::{EmailNotifier, SmtpConfig};
+#[cfg(feature = "matrix")]
+use crate::services::matrix::MatrixNotifier;
+#[cfg(feature = "slack")]
+use crate::services::slack::SlackNotifier;
+#[cfg(feature = "telegram")]
+use crate::services::telegram::TelegramNotifier;
+use crate::services::alerts;
+use crate::services::mutes::MuteList;
+use crate::services::sentiment::SentimentLabel;
+
+/// Hour of the day, in each user's time zone, the daily digest is sent at, unless `DAILY_DIGEST_HOUR` is set
+const DEFAULT_DIGEST_HOUR: u32 = 8;
+
+/// How often the digest scheduler checks whether it is time to send
+const DIGEST_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Fewest new comments in a batch for a negative share to count as a spike
+const SPIKE_MIN_COMMENTS: usize = 5;
+
+/// Share of negative comments in a batch (0.0 to 1.0) that counts as a spike
+const SPIKE_NEGATIVE_SHARE: f64 = 0.4;
+
+/// Most questions quoted in a single notification
+const MAX_QUOTED_QUESTIONS: usize = 5;
+
+/// Most removed replies quoted in a single notification
+const MAX_QUOTED_REPLIES: usize = 5;
+
+/// Most comments posted individually per batch; the rest are summarized in one message
+const MAX_NEW_COMMENT_NOTIFICATIONS: usize = 20;
+
+/// Most alerts sent per batch, so a flood of matching comments can't flood the user
+const MAX_ALERT_NOTIFICATIONS: usize = 20;
+
+/// Base URL of the app, used to link notifications to the review queue
+const DEFAULT_APP_BASE_URL: &str = "http://localhost:3000";
+
+/// A backend delivering notifications on one channel
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// The channel this backend delivers on
+    fn channel(&self) -> NotificationChannel;
+
+    /// Deliver a notification to a user.
+    ///
+    /// Returns `false` if the user has no address on this channel.
+    async fn deliver(&self, user: &User, notification: &Notification) -> Result<bool>;
+
+    /// Check at startup that the backend is reachable and accepts its credentials
+    async fn check(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Service deciding which notifications to send and dispatching them to the notifier backends
+pub struct NotificationService {
+    db: Database,
+    notifiers: Vec<Arc<dyn Notifier>>,
+    #[cfg(feature = "telegram")]
+    telegram: Option<Arc<TelegramNotifier>>,
+    app_base_url: String,
+    digest_hour: u32,
+}
+
+impl NotificationService {
+    /// Create a new notification service; email, Telegram and Matrix are disabled if not configured.
+    ///
+    /// Only the backends whose cargo features are enabled are available.
+    #[cfg_attr(not(feature = "telegram"), allow(unused_variables))]
+    pub fn new(db: Database, client: Client) -> Result<Self> {
+        #[allow(unused_mut)]
+        let mut notifiers: Vec<Arc<dyn Notifier>> = Vec::new();
+
+        #[cfg(feature = "slack")]
+        notifiers.push(Arc::new(SlackNotifier::from_env(client.clone())));
+
+        #[cfg(feature = "email")]
+        match SmtpConfig::from_env()? {
+            Some(config) => notifiers.push(Arc::new(EmailNotifier::new(config)?)),
+            None => tracing::warn!("SMTP_HOST not set, email notifications are disabled"),
+        }
+
+        #[cfg(feature = "matrix")]
+        if let Some(matrix) = MatrixNotifier::from_env(client.clone())? {
+            notifiers.push(Arc::new(matrix));
+        }
+
+        #[cfg(feature = "telegram")]
+        let telegram = TelegramNotifier::from_env(client).map(Arc::new);
+        #[cfg(feature = "telegram")]
+        if let Some(telegram) = &telegram {
+            notifiers.push(telegram.clone());
+        }
+
+        let digest_hour = env::var("DAILY_DIGEST_HOUR")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|hour| *hour < 24)
+            .unwrap_or(DEFAULT_DIGEST_HOUR);
+
+        let app_base_url = env::var("APP_BASE_URL")
+            .unwrap_or_else(|_| DEFAULT_APP_BASE_URL.to_string())
+            .trim_end_matches('/')
+            .to_string();
+
+        Ok(Self {
+            db,
+            notifiers,
+            #[cfg(feature = "telegram")]
+            telegram,
+            app_base_url,
+            digest_hour,
+        })
+    }
+
+    /// Send a notification to a user on the channels their routing selects, if
+    /// their preferences allow it. A failing channel doesn't keep the others from sending.
+    ///
+    /// Returns whether the notification was sent on at least one channel.
+    pub async fn notify(&self, user_id: &str, notification: &Notification) -> Result<bool> {
+        let user = match self.db.get_user(user_id).await? {
+            Some(user) => user,
+            None => return Ok(false),
+        };
+
+        let preferences = &user.preferences;
+        if !preferences.enable_notifications || !preferences.notifications.is_enabled(notification.event) {
+            return Ok(false);
+        }
+
+        let channels = preferences.notifications.channels_for(notification);
+        let mut sent = false;
+
+        for notifier in self.notifiers.iter().filter(|n| channels.contains(&n.channel())) {
+            match notifier.deliver(&user, notification).await {
+                Ok(delivered) => sent |= delivered,
+                Err(e) => error!("Error delivering {:?} notification to user {}: {}", notifier.channel(), user_id, e),
+            }
+        }
+
+        if sent {
+            info!("Sent {:?} notification to user {}", notification.event, user_id);
+        }
+
+        Ok(sent)
+    }
+
+    /// Check every configured backend, see [`Notifier::check`]
+    pub async fn check_notifiers(&self) -> Vec<(NotificationChannel, Result<()>)> {
+        let mut results = Vec::new();
+        for notifier in &self.notifiers {
+            results.push((notifier.channel(), notifier.check().await));
+        }
+        results
+    }
+
+    /// The Telegram bot, if one is configured
+    #[cfg(feature = "telegram")]
+    pub fn telegram(&self) -> Option<&TelegramNotifier> {
+        self.telegram.as_deref()
+    }
+
+    /// Notify about newly ingested comments: alerts, the comments themselves, unanswered questions and negative spikes.
+    /// Comments on muted videos and by muted commenters are left out.
+    ///
+    /// Failures are only logged so ingestion never fails because of a notification.
+    pub async fn comments_received(&self, user_id: &str, video_id: &str, comments: &[Comment]) {
+        let mutes = match MuteList::load(&self.db, user_id).await {
+            Ok(mutes) => mutes,
+            Err(e) => {
+                error!("Error loading the mutes of user {}: {}", user_id, e);
+                MuteList::default()
+            }
+        };
+        let comments: Vec<Comment> = comments.iter().filter(|c| !mutes.mutes(c)).cloned().collect();
+        let comments = comments.as_slice();
+        if comments.is_empty() {
+            return;
+        }
+
+        let title = match self.db.get_video(video_id).await {
+            Ok(Some(video)) => video.title,
+            _ => video_id.to_string(),
+        };
+        let locale = self.locale_for(user_id).await;
+
+        if let Err(e) = self.send_alerts(user_id, video_id, &title, locale, comments).await {
+            error!("Error sending alerts to user {}: {}", user_id, e);
+        }
+
+        for comment in comments.iter().take(MAX_NEW_COMMENT_NOTIFICATIONS) {
+            let notification = Notification {
+                event: NotificationEvent::NewComment,
+                subject: i18n::text(
+                    locale,
+                    Message::NewCommentSubject,
+                    &[("author", &comment.author), ("title", &title)],
+                ),
+                body: comment.text.clone(),
+                link: Some(self.review_link(video_id, Some(&comment.comment_id))),
+                comment_id: Some(comment.comment_id.clone()),
+                video_id: Some(video_id.to_string()),
+                sentiment: comment.sentiment,
+                priority: NotificationPriority::Normal,
+            };
+            self.notify_logged(user_id, &notification).await;
+        }
+
+        if comments.len() > MAX_NEW_COMMENT_NOTIFICATIONS {
+            let notification = Notification {
+                event: NotificationEvent::NewComment,
+                subject: i18n::text(
+                    locale,
+                    Message::MoreCommentsSubject,
+                    &[("count", &(comments.len() - MAX_NEW_COMMENT_NOTIFICATIONS)), ("title", &title)],
+                ),
+                body: i18n::text(locale, Message::MoreCommentsBody, &[]),
+                link: Some(self.review_link(video_id, None)),
+                comment_id: None,
+                video_id: Some(video_id.to_string()),
+                sentiment: None,
+                priority: NotificationPriority::Normal,
+            };
+            self.notify_logged(user_id, &notification).await;
+        }
+
+        let questions: Vec<&Comment> = comments
+            .iter()
+            .filter(|c| !c.replied_to && alerts::is_question(&c.text))
+            .collect();
+
+        if !questions.is_empty() {
+            let quoted: Vec<String> = questions
+                .iter()
+                .take(MAX_QUOTED_QUESTIONS)
+                .map(|c| format!("- {}: {}", c.author, c.text))
+                .collect();
+
+            let notification = Notification {
+                event: NotificationEvent::UnansweredQuestion,
+                subject: i18n::text(
+                    locale,
+                    Message::QuestionsSubject,
+                    &[("count", &questions.len()), ("title", &title)],
+                ),
+                body: i18n::text(
+                    locale,
+                    Message::QuestionsBody,
+                    &[("title", &title), ("questions", &quoted.join("\n"))],
+                ),
+                link: Some(self.review_link(video_id, None)),
+                comment_id: None,
+                video_id: Some(video_id.to_string()),
+                sentiment: None,
+                priority: NotificationPriority::Normal,
+            };
+            self.notify_logged(user_id, &notification).await;
+        }
+
+        if let Some(share) = negative_spike(comments) {
+            let notification = Notification {
+                event: NotificationEvent::NegativeSentimentSpike,
+                subject: i18n::text(locale, Message::SpikeSubject, &[("title", &title)]),
+                body: i18n::text(
+                    locale,
+                    Message::SpikeBody,
+                    &[
+                        ("percent", &format!("{:.0}", share * 100.0)),
+                        ("count", &comments.len()),
+                        ("title", &title),
+                    ],
+                ),
+                link: Some(self.review_link(video_id, None)),
+                comment_id: None,
+                video_id: Some(video_id.to_string()),
+                sentiment: None,
+                priority: NotificationPriority::Normal,
+            };
+            self.notify_logged(user_id, &notification).await;
+        }
+    }
+
+    /// Send a high-priority notification for each comment matching one of the user's alert rules.
+    ///
+    /// Alerts go out as comments arrive, whatever the user's other notification
+    /// settings, and each comment alerts at most once, for the first rule it matches.
+    async fn send_alerts(&self, user_id: &str, video_id: &str, title: &str, locale: Locale, comments: &[Comment]) -> Result<()> {
+        let rules: Vec<AlertRule> = self.db.get_alert_rules(user_id).await?.into_iter().filter(|r| r.enabled).collect();
+        if rules.is_empty() {
+            return Ok(());
+        }
+
+        let mut hits: HashMap<&str, u64> = HashMap::new();
+        let mut sent = 0;
+
+        for comment in comments {
+            let matched = rules.iter().find_map(|rule| alerts::trigger(rule, comment).map(|trigger| (rule, trigger)));
+            let Some((rule, trigger)) = matched else {
+                continue;
+            };
+            *hits.entry(rule.rule_id.as_str()).or_default() += 1;
+
+            if sent >= MAX_ALERT_NOTIFICATIONS {
+                continue;
+            }
+            sent += 1;
+
+            let notification = Notification {
+                event: NotificationEvent::Alert,
+                subject: i18n::text(
+                    locale,
+                    Message::AlertSubject,
+                    &[("rule", &rule.name), ("author", &comment.author), ("title", &title)],
+                ),
+                body: i18n::text(locale, Message::AlertBody, &[("text", &comment.text), ("trigger", &trigger)]),
+                link: Some(self.review_link(video_id, Some(&comment.comment_id))),
+                comment_id: Some(comment.comment_id.clone()),
+                video_id: Some(video_id.to_string()),
+                sentiment: comment.sentiment,
+                priority: NotificationPriority::High,
+            };
+            self.notify_logged(user_id, &notification).await;
+        }
+
+        let now = Utc::now();
+        for (rule_id, count) in hits {
+            info!("Alert rule {} matched {} new comments", rule_id, count);
+            self.db.record_alert_hits(rule_id, count, now).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Notify that a reply posted without the user watching failed
+    pub async fn auto_reply_failed(&self, user_id: &str, comment_id: &str, reason: &str) {
+        let locale = self.locale_for(user_id).await;
+        let notification = Notification {
+            event: NotificationEvent::AutoReplyFailed,
+            subject: i18n::text(locale, Message::AutoReplyFailedSubject, &[]),
+            body: i18n::text(
+                locale,
+                Message::AutoReplyFailedBody,
+                &[("comment_id", &comment_id), ("reason", &reason)],
+            ),
+            link: None,
+            comment_id: Some(comment_id.to_string()),
+            video_id: None,
+            sentiment: None,
+            priority: NotificationPriority::Normal,
+        };
+        self.notify_logged(user_id, &notification).await;
+    }
+
+    /// Notify that replies the user posted are no longer on YouTube, quoting the first few
+    pub async fn replies_removed(&self, user_id: &str, count: usize, texts: &[String]) {
+        let locale = self.locale_for(user_id).await;
+        let quoted: Vec<String> = texts.iter().take(MAX_QUOTED_REPLIES).map(|text| format!("- {}", text)).collect();
+
+        let notification = Notification {
+            event: NotificationEvent::ReplyRemoved,
+            subject: i18n::text(locale, Message::RepliesRemovedSubject, &[("count", &count)]),
+            body: i18n::text(locale, Message::RepliesRemovedBody, &[("replies", &quoted.join("\n"))]),
+            link: Some(format!("{}/review", self.app_base_url)),
+            comment_id: None,
+            video_id: None,
+            sentiment: None,
+            priority: NotificationPriority::High,
+        };
+        self.notify_logged(user_id, &notification).await;
+    }
+
+    /// Send the daily digest to every user with monitored videos for whom it is the digest hour
+    /// and who hasn't had one today, both in their time zone.
+    ///
+    /// `last_sent` keeps the local day each user last got one. Returns the number of digests sent.
+    pub async fn send_due_digests(&self, now: DateTime<Utc>, last_sent: &mut HashMap<String, NaiveDate>) -> Result<usize> {
+        let mut sent = 0;
+
+        for user_id in self.db.get_monitored_user_ids().await? {
+            let Some(user) = self.db.get_user(&user_id).await? else {
+                continue;
+            };
+            let Some(today) = digest_due(now, user.preferences.time_zone(), self.digest_hour, last_sent.get(&user_id)) else {
+                continue;
+            };
+            last_sent.insert(user_id.clone(), today);
+
+            match self.send_digest(&user_id, now).await {
+                Ok(true) => sent += 1,
+                Ok(false) => {}
+                Err(e) => error!("Error sending daily digest to user {}: {}", user_id, e),
+            }
+        }
+
+        Ok(sent)
+    }
+
+    /// Send a user the digest of the day before `now`, returning whether any channel took it
+    pub async fn send_digest(&self, user_id: &str, now: DateTime<Utc>) -> Result<bool> {
+        // Muted videos aren't counted
+        let mutes = MuteList::load(&self.db, user_id).await?;
+        let video_ids: Vec<String> = self
+            .db
+            .get_user_videos(user_id)
+            .await?
+            .into_iter()
+            .map(|v| v.video_id)
+            .filter(|video_id| !mutes.mutes_video(video_id))
+            .collect();
+
+        let new_comments = self.db.count_comments_since(&video_ids, now - Duration::days(1)).await?;
+        let unanswered = self.db.count_unanswered_comments(&video_ids).await?;
+        let pending_review = self.db.count_pending_drafts(user_id).await?;
+        let locale = self.locale_for(user_id).await;
+
+        let notification = Notification {
+            event: NotificationEvent::DailyDigest,
+            subject: i18n::text(locale, Message::DigestSubject, &[("count", &new_comments)]),
+            body: i18n::text(
+                locale,
+                Message::DigestBody,
+                &[("new", &new_comments), ("unanswered", &unanswered), ("pending", &pending_review)],
+            ),
+            link: Some(format!("{}/review", self.app_base_url)),
+            comment_id: None,
+            video_id: None,
+            sentiment: None,
+            priority: NotificationPriority::Normal,
+        };
+
+        self.notify(user_id, &notification).await
+    }
+
+    /// Spawn a background task sending each user the daily digest once a day
+    pub fn spawn_daily_digest(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DIGEST_CHECK_INTERVAL);
+            let mut last_sent: HashMap<String, NaiveDate> = HashMap::new();
+
+            loop {
+                interval.tick().await;
+
+                match self.send_due_digests(Utc::now(), &mut last_sent).await {
+                    Ok(0) => {}
+                    Ok(count) => info!("Sent {} daily digests", count),
+                    Err(e) => error!("Error sending daily digests: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Link to the review queue for a video, optionally focused on one comment
+    fn review_link(&self, video_id: &str, comment_id: Option<&str>) -> String {
+        match comment_id {
+            Some(comment_id) => format!("{}/review?video_id={}&comment_id={}", self.app_base_url, video_id, comment_id),
+            None => format!("{}/review?video_id={}", self.app_base_url, video_id),
+        }
+    }
+
+    /// The language a user's notifications are written in
+    async fn locale_for(&self, user_id: &str) -> Locale {
+        match self.db.get_user(user_id).await {
+            Ok(Some(user)) => Locale::from_preference(user.preferences.language.as_deref()),
+            _ => Locale::default(),
+        }
+    }
+
+    /// Send a notification, logging rather than returning failures
+    async fn notify_logged(&self, user_id: &str, notification: &Notification) {
+        if let Err(e) = self.notify(user_id, notification).await {
+            error!("Error sending {:?} notification to user {}: {}", notification.event, user_id, e);
+        }
+    }
+}
+
+/// The negative share of a batch of comments, if it is large enough to count as a spike
+fn negative_spike(comments: &[Comment]) -> Option<f64> {
+    if comments.len() < SPIKE_MIN_COMMENTS {
+        return None;
+    }
+
+    let negative = comments
+        .iter()
+        .filter(|c| c.sentiment.map(SentimentLabel::from_score) == Some(SentimentLabel::Negative))
+        .count();
+
+    let share = negative as f64 / comments.len() as f64;
+    (share >= SPIKE_NEGATIVE_SHARE).then_some(share)
+}
+
+/// The local day to send a user's digest for, if it is the digest hour in their time zone and they haven't had it yet
+fn digest_due(now: DateTime<Utc>, tz: Tz, hour: u32, last_sent: Option<&NaiveDate>) -> Option<NaiveDate> {
+    let local = now.with_timezone(&tz);
+    let today = local.date_naive();
+    (local.hour() == hour && last_sent != Some(&today)).then_some(today)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentBuilder;
+
+    fn comment(sentiment: f32) -> Comment {
+        CommentBuilder::new("v", "c").sentiment(sentiment).build()
+    }
+
+    #[test]
+    fn test_negative_spike() {
+        // Too few comments to call it a spike
+        assert_eq!(negative_spike(&[comment(-1.0), comment(-1.0)]), None);
+
+        let mut batch = vec![comment(-1.0), comment(-0.5), comment(0.0), comment(0.8), comment(1.0)];
+        assert_eq!(negative_spike(&batch), Some(0.4));
+
+        batch.push(comment(0.5));
+        assert_eq!(negative_spike(&batch), None);
+    }
+
+    #[test]
+    fn test_digest_follows_time_zone() {
+        let now: DateTime<Utc> = "2024-06-01T06:10:00Z".parse().unwrap();
+        let today = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+
+        // 8:10 in Berlin, but only 6:10 in UTC
+        assert_eq!(digest_due(now, chrono_tz::Europe::Berlin, 8, None), Some(today));
+        assert_eq!(digest_due(now, Tz::UTC, 8, None), None);
+        // Once a day
+        assert_eq!(digest_due(now, chrono_tz::Europe::Berlin, 8, Some(&today)), None);
+    }
+}
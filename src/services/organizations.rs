@@ -0,0 +1,89 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::organization::{InboxItem, Organization, OrgPolicy};
+use crate::services::{masking, mutes::MuteList};
+
+/// Most creator accounts an organization can have, the admin included
+pub const MAX_ORG_MEMBERS: usize = 50;
+
+/// Most comments the organization inbox lists at once
+pub const MAX_INBOX_LIMIT: usize = 500;
+
+/// Most blocked phrases an organization policy can have
+pub const MAX_BLOCKED_PHRASES: usize = 100;
+
+/// Check that an organization can be stored: it has a name and a policy within limits
+pub fn validate(org: &Organization) -> Result<()> {
+    if org.name.trim().is_empty() {
+        return Err(AppError::Validation("Organization name must not be empty".to_string()).into());
+    }
+
+    if org.policy.blocked_phrases.len() > MAX_BLOCKED_PHRASES {
+        return Err(AppError::Validation(format!("At most {} blocked phrases are allowed", MAX_BLOCKED_PHRASES)).into());
+    }
+
+    Ok(())
+}
+
+/// The policy of the user's organization; one that allows everything if they aren't in one
+pub async fn policy(db: &Database, user_id: &str) -> Result<OrgPolicy> {
+    Ok(db.get_user_organization(user_id).await?.map(|org| org.policy).unwrap_or_default())
+}
+
+/// The members' unanswered comments across their channels, newest first.
+///
+/// Each member's videos are looked up by their own user ID, so the inbox only
+/// ever holds comments on channels of the organization. Hidden comments and
+/// what members muted are left out, and sensitive text is masked if `mask` is set.
+pub async fn inbox(db: &Database, org: &Organization, limit: usize, mask: bool) -> Result<Vec<InboxItem>> {
+    // Each member's mutes apply to their own channel
+    let mut videos = HashMap::new();
+    let mut mutes = HashMap::new();
+    for member in &org.members {
+        let member_mutes = MuteList::load(db, member).await?;
+        for video in db.get_user_videos(member).await? {
+            if !member_mutes.mutes_video(&video.video_id) {
+                videos.insert(video.video_id.clone(), video);
+            }
+        }
+        mutes.insert(member.clone(), member_mutes);
+    }
+    if videos.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // Hidden and muted comments are filtered out here, so pages are read until enough are left
+    let video_ids: Vec<String> = videos.keys().cloned().collect();
+    let mut items = Vec::new();
+    let mut start = 0;
+    while items.len() < limit {
+        let page = db.get_unanswered_comments(&video_ids, start, limit).await?;
+        let page_len = page.len();
+        start += page_len;
+
+        items.extend(page.into_iter().filter(|comment| !comment.is_hidden()).filter_map(|mut comment| {
+            let video = videos.get(&comment.video_id)?;
+            if mutes.get(&video.user_id).is_some_and(|mutes| mutes.mutes(&comment)) {
+                return None;
+            }
+            if mask {
+                masking::mask_comment(&mut comment);
+            }
+            Some(InboxItem {
+                user_id: video.user_id.clone(),
+                video_title: video.title.clone(),
+                comment,
+            })
+        }));
+
+        if page_len < limit {
+            break;
+        }
+    }
+
+    items.truncate(limit);
+    Ok(items)
+}
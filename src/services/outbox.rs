@@ -0,0 +1,110 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::env;
+use std::time::Duration;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::outbox::{QueueStatus, QueuedReply};
+
+/// Longest undo window an operator can configure
+const MAX_SEND_DELAY: Duration = Duration::from_secs(3600);
+
+/// How often the sender looks for replies whose undo window is over
+pub const SEND_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Replies still being sent this long after they were due were interrupted, as sending takes seconds
+const INTERRUPTED_SEND_AFTER: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Error recorded on a reply whose sending was interrupted
+pub const INTERRUPTED_SEND_ERROR: &str =
+    "Sending was interrupted by a server restart; the reply may or may not have been posted, so check the thread before posting it again";
+
+/// Holds posted replies for an undo window before they are sent to YouTube
+pub struct Outbox {
+    db: Database,
+    delay: Duration,
+}
+
+impl Outbox {
+    /// Create an outbox, reading the undo window in seconds from `REPLY_SEND_DELAY_SECS` (0, the default, sends at once)
+    pub fn new(db: Database) -> Self {
+        let delay = env::var("REPLY_SEND_DELAY_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(Duration::ZERO);
+
+        Self::with_delay(db, delay)
+    }
+
+    /// Create an outbox with the given undo window
+    pub fn with_delay(db: Database, delay: Duration) -> Self {
+        Self { db, delay: delay.min(MAX_SEND_DELAY) }
+    }
+
+    /// Whether replies are held at all; if not, they are posted straight away
+    pub fn is_delayed(&self) -> bool {
+        !self.delay.is_zero()
+    }
+
+    /// Queue a reply, setting when it will be sent
+    pub async fn enqueue(&self, mut queued: QueuedReply) -> Result<QueuedReply> {
+        queued.send_at = Utc::now() + chrono::Duration::from_std(self.delay)?;
+        self.db.queue_reply(&queued).await?;
+        Ok(queued)
+    }
+
+    /// Queue a reply to be sent at a given time, or once its undo window is over if that is later
+    pub async fn schedule(&self, mut queued: QueuedReply, send_at: DateTime<Utc>) -> Result<QueuedReply> {
+        queued.send_at = send_at.max(Utc::now() + chrono::Duration::from_std(self.delay)?);
+        self.db.queue_reply(&queued).await?;
+        Ok(queued)
+    }
+
+    /// A user's replies still in their undo window
+    pub async fn pending(&self, user_id: &str) -> Result<Vec<QueuedReply>> {
+        self.db.get_pending_replies(user_id).await
+    }
+
+    /// Cancel a user's queued reply, returning it; fails with a conflict if it is already being sent
+    pub async fn cancel(&self, user_id: &str, queue_id: &str) -> Result<QueuedReply> {
+        let queued = self
+            .db
+            .get_queued_reply(queue_id)
+            .await?
+            .filter(|queued| queued.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound(format!("Queued reply {}", queue_id)))?;
+
+        if !self.db.cancel_queued_reply(queue_id).await? {
+            let status = self.db.get_queued_reply(queue_id).await?.map_or(queued.status, |q| q.status);
+            return Err(AppError::Conflict(format!("Reply {} is no longer pending ({})", queue_id, status.as_str())).into());
+        }
+
+        Ok(queued)
+    }
+
+    /// Claim the replies whose undo window is over, for sending
+    pub async fn claim_due(&self) -> Result<Vec<QueuedReply>> {
+        self.db.claim_due_replies(Utc::now()).await
+    }
+
+    /// Mark the replies a stopped server was sending as failed, returning them.
+    ///
+    /// Whether YouTube got them is unknown, so they aren't retried, which could post
+    /// them twice. Replies claimed recently are left alone, as another instance
+    /// sharing the database may still be sending them.
+    pub async fn fail_interrupted(&self) -> Result<Vec<QueuedReply>> {
+        self.db.fail_stale_sending_replies(Utc::now() - INTERRUPTED_SEND_AFTER, INTERRUPTED_SEND_ERROR).await
+    }
+
+    /// Record that a claimed reply was posted
+    pub async fn mark_sent(&self, queue_id: &str, reply_id: &str) -> Result<()> {
+        self.db.finish_queued_reply(queue_id, QueueStatus::Sent, Some(reply_id.to_string()), None).await
+    }
+
+    /// Record that posting a claimed reply failed
+    pub async fn mark_failed(&self, queue_id: &str, error: &str) -> Result<()> {
+        self.db.finish_queued_reply(queue_id, QueueStatus::Failed, None, Some(error.to_string())).await
+    }
+}
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use chrono::Utc;
+#[cfg(feature = "openai")]
+use reqwest::StatusCode;
+use reqwest::{Client, Url};
+use std::env;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::db::Database;
+use crate::models::preflight::{CheckStatus, PreflightCheck, PreflightReport};
+use crate::services::auth::OAuthConfig;
+use crate::services::notifications::NotificationService;
+
+/// Suffix of the client IDs Google issues for OAuth apps
+const GOOGLE_CLIENT_ID_SUFFIX: &str = ".apps.googleusercontent.com";
+
+/// How long a check waits for an upstream to answer
+#[cfg_attr(not(feature = "openai"), allow(dead_code))]
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What to do when a check fails, from `PREFLIGHT_MODE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreflightMode {
+    /// Refuse to start (`strict`, the default)
+    Strict,
+
+    /// Start anyway and report the problems in `/api/health` (`degraded`)
+    Degraded,
+}
+
+impl PreflightMode {
+    /// Read `PREFLIGHT_MODE`
+    pub fn from_env() -> Result<Self> {
+        match env::var("PREFLIGHT_MODE").as_deref() {
+            Err(_) | Ok("") | Ok("strict") => Ok(PreflightMode::Strict),
+            Ok("degraded") => Ok(PreflightMode::Degraded),
+            Ok(other) => anyhow::bail!("PREFLIGHT_MODE must be strict or degraded, not {}", other),
+        }
+    }
+}
+
+/// Checks the configuration and the services the server depends on before it starts
+pub struct Preflight<'a> {
+    pub db: &'a Database,
+
+    /// Client OpenAI requests go through
+    pub openai_client: &'a Client,
+
+    pub notifications: &'a NotificationService,
+}
+
+impl Preflight<'_> {
+    /// Run every check and log the problems found.
+    ///
+    /// Notification backends are optional, so their problems are only warnings.
+    pub async fn run(&self) -> PreflightReport {
+        let mut checks = vec![check_oauth(), self.check_database().await];
+
+        #[cfg(feature = "openai")]
+        checks.push(self.check_openai().await);
+
+        for (channel, result) in self.notifications.check_notifiers().await {
+            let name = format!("notifications.{:?}", channel).to_lowercase();
+            checks.push(match result {
+                Ok(()) => PreflightCheck::ok(&name),
+                Err(e) => PreflightCheck::problem(&name, CheckStatus::Warning, format!("{:#}", e)),
+            });
+        }
+
+        for check in &checks {
+            let message = check.message.as_deref().unwrap_or_default();
+            match check.status {
+                CheckStatus::Ok => info!("Preflight check {} passed", check.name),
+                CheckStatus::Warning => warn!("Preflight check {}: {}", check.name, message),
+                CheckStatus::Failed => error!("Preflight check {} failed: {}", check.name, message),
+            }
+        }
+
+        PreflightReport { checks, checked_at: Some(Utc::now()) }
+    }
+
+    async fn check_database(&self) -> PreflightCheck {
+        match self.db.query("INFO FOR DB").await.and_then(|response| response.check()) {
+            Ok(_) => PreflightCheck::ok("database"),
+            Err(e) => PreflightCheck::problem("database", CheckStatus::Failed, format!("Database not reachable: {}", e)),
+        }
+    }
+
+    /// Whether `OPENAI_API_KEY` is set and accepted; an OpenAI that can't be reached is only a warning
+    #[cfg(feature = "openai")]
+    async fn check_openai(&self) -> PreflightCheck {
+        let api_key = match env::var("OPENAI_API_KEY") {
+            Ok(key) if !key.is_empty() => key,
+            _ => {
+                return PreflightCheck::problem(
+                    "openai",
+                    CheckStatus::Failed,
+                    "OPENAI_API_KEY is not set, so replies can't be generated",
+                )
+            }
+        };
+
+        let response = self.openai_client
+            .get(format!("{}/models", crate::services::ai::api_base()))
+            .bearer_auth(api_key)
+            .timeout(CHECK_TIMEOUT)
+            .send()
+            .await;
+
+        match response {
+            Ok(response) if response.status().is_success() => PreflightCheck::ok("openai"),
+            Ok(response) if matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => {
+                PreflightCheck::problem(
+                    "openai",
+                    CheckStatus::Failed,
+                    format!("OPENAI_API_KEY was rejected ({})", response.status()),
+                )
+            }
+            Ok(response) => PreflightCheck::problem(
+                "openai",
+                CheckStatus::Warning,
+                format!("Couldn't verify OPENAI_API_KEY: OpenAI answered {}", response.status()),
+            ),
+            Err(e) => PreflightCheck::problem("openai", CheckStatus::Warning, format!("Couldn't reach OpenAI: {}", e)),
+        }
+    }
+}
+
+/// Whether the OAuth app is configured; nothing is sent to Google
+fn check_oauth() -> PreflightCheck {
+    let config = match OAuthConfig::from_env() {
+        Ok(config) => config,
+        Err(e) => return PreflightCheck::problem("oauth", CheckStatus::Failed, e.to_string()),
+    };
+
+    if Url::parse(&config.redirect_uri).is_err() {
+        return PreflightCheck::problem(
+            "oauth",
+            CheckStatus::Failed,
+            format!("YOUTUBE_OAUTH_REDIRECT_URI {} is not an absolute URL", config.redirect_uri),
+        );
+    }
+    if !config.client_id.ends_with(GOOGLE_CLIENT_ID_SUFFIX) {
+        return PreflightCheck::problem(
+            "oauth",
+            CheckStatus::Warning,
+            format!("YOUTUBE_OAUTH_CLIENT_ID doesn't end with {}; Google will likely reject it", GOOGLE_CLIENT_ID_SUFFIX),
+        );
+    }
+
+    PreflightCheck::ok("oauth")
+}
@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::models::prompt::{PromptKind, PromptTemplate};
+use crate::models::tone::TonePreset;
+
+/// How often `PROMPTS_DIR` is checked for edited files
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Name of the system instructions
+pub const DEFAULT_SYSTEM_PROMPT: &str = "default";
+
+const BUILTIN_SYSTEM: &str = "You are an assistant helping a YouTube content creator respond to comments on their videos. \
+    Your goal is to write thoughtful, authentic replies that engage with the commenter and foster a positive community. \
+    Keep replies concise, friendly, and conversational. Avoid generic responses.";
+
+/// Persona replies on Shorts are written as unless the request or video sets one
+pub const SHORTS_PERSONA: &str = "shorts";
+
+const BUILTIN_PERSONAS: [(&str, &str); 1] = [
+    (SHORTS_PERSONA, "You are replying to a comment on a YouTube Short. Shorts viewers scroll fast and write briefly, \
+        often in emoji: answer in one short sentence, and match their emoji if they used some."),
+];
+
+/// Instructions for tones that no longer exist
+const FALLBACK_TONE: &str = "Use a balanced, friendly tone that's authentic and engaging.";
+
+/// An immutable snapshot of the prompt texts, tagged with the version it was loaded as
+#[derive(Debug, Clone)]
+pub struct PromptSet {
+    /// Incremented on every reload, so generations can be traced to the prompts they used
+    pub version: u64,
+    system: String,
+    tones: HashMap<String, String>,
+    personas: HashMap<String, String>,
+}
+
+impl PromptSet {
+    /// The prompts compiled into the binary
+    pub fn builtin() -> Self {
+        Self {
+            version: 0,
+            system: BUILTIN_SYSTEM.to_string(),
+            tones: HashMap::new(),
+            personas: BUILTIN_PERSONAS.iter().map(|(name, text)| (name.to_string(), text.to_string())).collect(),
+        }
+    }
+
+    /// Override a text; later layers win
+    fn apply(&mut self, kind: PromptKind, name: &str, text: String) {
+        match kind {
+            PromptKind::System if name == DEFAULT_SYSTEM_PROMPT => self.system = text,
+            PromptKind::System => {}
+            PromptKind::Tone => {
+                self.tones.insert(name.to_string(), text);
+            }
+            PromptKind::Persona => {
+                self.personas.insert(name.to_string(), text);
+            }
+        }
+    }
+
+    /// Build the system message for a tone preset, written as the persona if one is given and known
+    pub fn system_message(&self, tone: Option<&TonePreset>, persona: Option<&str>) -> String {
+        let mut tone_instructions = match tone {
+            Some(tone) => self.tones.get(&tone.tone_id).unwrap_or(&tone.instructions).clone(),
+            None => FALLBACK_TONE.to_string(),
+        };
+        if let Some(tone) = tone.filter(|tone| !tone.examples.is_empty()) {
+            tone_instructions.push_str("\n\nReplies in this tone look like:");
+            for example in &tone.examples {
+                tone_instructions.push_str(&format!("\n- {}", example));
+            }
+        }
+
+        match persona.and_then(|name| self.personas.get(name)) {
+            Some(persona) => format!("{}\n\n{}\n\n{}", self.system, persona, tone_instructions),
+            None => format!("{}\n\n{}", self.system, tone_instructions),
+        }
+    }
+}
+
+/// The current prompt texts: built-ins, overridden by files in `PROMPTS_DIR`, overridden by
+/// templates edited through the admin API. Tone texts replace the instructions of the tone preset with the same ID.
+///
+/// Generations take a snapshot with [`PromptLibrary::current`], so an edit applies to the
+/// next generation without a restart and never to one already running. `PROMPTS_DIR` holds
+/// `system/default.txt`, `tones/<tone>.txt` and `personas/<name>.txt`.
+pub struct PromptLibrary {
+    db: Database,
+    dir: Option<PathBuf>,
+    current: RwLock<Arc<PromptSet>>,
+    version: AtomicU64,
+    disk_stamp: Mutex<Option<DiskStamp>>,
+}
+
+/// Changes whenever a prompt file is added, removed or modified
+#[derive(Debug, Clone, PartialEq)]
+struct DiskStamp {
+    files: usize,
+    latest: Option<SystemTime>,
+}
+
+impl PromptLibrary {
+    /// Create a library holding the built-in prompts; call [`PromptLibrary::reload`] to load the overrides
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            dir: env::var("PROMPTS_DIR").ok().map(PathBuf::from),
+            current: RwLock::new(Arc::new(PromptSet::builtin())),
+            version: AtomicU64::new(0),
+            disk_stamp: Mutex::new(None),
+        }
+    }
+
+    /// The prompts to use for a generation
+    pub fn current(&self) -> Arc<PromptSet> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Rebuild the prompts from disk and the database, returning the new version
+    pub async fn reload(&self) -> Result<u64> {
+        let mut prompts = PromptSet::builtin();
+
+        if let Some(dir) = self.dir.clone() {
+            let (files, stamp) = tokio::task::spawn_blocking(move || read_prompt_dir(&dir)).await??;
+            for (kind, name, text) in files {
+                prompts.apply(kind, &name, text);
+            }
+            *self.disk_stamp.lock().unwrap() = Some(stamp);
+        }
+
+        for template in self.db.get_prompt_templates().await? {
+            prompts.apply(template.kind, &template.name, template.text);
+        }
+
+        prompts.version = self.version.fetch_add(1, Ordering::Relaxed) + 1;
+        let version = prompts.version;
+        *self.current.write().unwrap() = Arc::new(prompts);
+
+        info!("Loaded prompt templates version {}", version);
+        Ok(version)
+    }
+
+    /// Store an edited template and apply it, returning the new version
+    pub async fn save(&self, kind: PromptKind, name: &str, text: &str) -> Result<u64> {
+        let template = PromptTemplate {
+            kind,
+            name: name.to_string(),
+            text: text.to_string(),
+            updated_at: Utc::now(),
+        };
+        self.db.save_prompt_template(&template).await?;
+        self.reload().await
+    }
+
+    /// Remove an edited template, falling back to the on-disk or built-in text, and return the new version
+    pub async fn delete(&self, kind: PromptKind, name: &str) -> Result<u64> {
+        self.db.delete_prompt_template(kind, name).await?;
+        self.reload().await
+    }
+
+    /// The templates edited through the admin API
+    pub async fn templates(&self) -> Result<Vec<PromptTemplate>> {
+        self.db.get_prompt_templates().await
+    }
+
+    /// Spawn a background task reloading the prompts when files in `PROMPTS_DIR` change
+    pub fn spawn_watcher(self: Arc<Self>) -> Option<JoinHandle<()>> {
+        let dir = self.dir.clone()?;
+
+        Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(DISK_CHECK_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let scan_dir = dir.clone();
+                let stamp = match tokio::task::spawn_blocking(move || disk_stamp(&scan_dir))
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|stamp| stamp)
+                {
+                    Ok(stamp) => stamp,
+                    Err(e) => {
+                        error!("Error checking prompt directory {}: {}", dir.display(), e);
+                        continue;
+                    }
+                };
+
+                if self.disk_stamp.lock().unwrap().as_ref() == Some(&stamp) {
+                    continue;
+                }
+
+                if let Err(e) = self.reload().await {
+                    error!("Error reloading prompt templates: {}", e);
+                }
+            }
+        }))
+    }
+}
+
+/// The `.txt` files of each kind's directory, as (kind, file stem, path)
+fn prompt_files(dir: &Path) -> Result<Vec<(PromptKind, String, PathBuf)>> {
+    let mut files = Vec::new();
+
+    for kind in [PromptKind::System, PromptKind::Tone, PromptKind::Persona] {
+        let kind_dir = dir.join(kind.dir_name());
+        if !kind_dir.is_dir() {
+            continue;
+        }
+
+        for entry in std::fs::read_dir(&kind_dir).with_context(|| format!("Failed to read {}", kind_dir.display()))? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("txt") {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|s| s.to_str()) {
+                files.push((kind, name.to_string(), path.clone()));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+fn disk_stamp(dir: &Path) -> Result<DiskStamp> {
+    let files = prompt_files(dir)?;
+    let latest = files
+        .iter()
+        .filter_map(|(_, _, path)| path.metadata().and_then(|m| m.modified()).ok())
+        .max();
+
+    Ok(DiskStamp { files: files.len(), latest })
+}
+
+/// A prompt read from disk, as (kind, file stem, text)
+type PromptFile = (PromptKind, String, String);
+
+fn read_prompt_dir(dir: &Path) -> Result<(Vec<PromptFile>, DiskStamp)> {
+    let stamp = disk_stamp(dir)?;
+
+    let mut prompts = Vec::new();
+    for (kind, name, path) in prompt_files(dir)? {
+        let text = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        prompts.push((kind, name, text.trim().to_string()));
+    }
+
+    Ok((prompts, stamp))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::tones;
+
+    #[test]
+    fn test_overrides_and_personas() {
+        let mut prompts = PromptSet::builtin();
+        let friendly = tones::builtin_tones().into_iter().find(|tone| tone.tone_id == "friendly").unwrap();
+        assert!(prompts.system_message(Some(&friendly), None).contains("warm, casual"));
+        assert!(prompts.system_message(None, None).contains(FALLBACK_TONE));
+
+        prompts.apply(PromptKind::Tone, "friendly", "Be chill.".to_string());
+        prompts.apply(PromptKind::Persona, "numan", "You write as Numan, a drone filmmaker.".to_string());
+
+        let message = prompts.system_message(Some(&friendly), Some("numan"));
+        assert!(message.starts_with(BUILTIN_SYSTEM));
+        assert!(message.contains("drone filmmaker"));
+        assert!(message.ends_with("Be chill."));
+
+        // Unknown personas are ignored rather than failing the generation
+        assert!(!prompts.system_message(Some(&friendly), Some("nobody")).contains("drone filmmaker"));
+    }
+
+    #[test]
+    fn test_tone_examples() {
+        let prompts = PromptSet::builtin();
+        let mut tone = TonePreset::new("u1", "Pirate", "Talk like a pirate.");
+        tone.examples = vec!["Arr, thanks for watching!".to_string()];
+
+        let message = prompts.system_message(Some(&tone), None);
+        assert!(message.contains("Talk like a pirate."));
+        assert!(message.ends_with("- Arr, thanks for watching!"));
+    }
+}
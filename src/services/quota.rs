@@ -0,0 +1,62 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::env;
+use tracing::error;
+
+use crate::db::Database;
+
+/// Default YouTube Data API daily quota of a Google Cloud project (in units)
+const DEFAULT_DAILY_QUOTA: u64 = 10_000;
+
+/// Quota cost of a list call (commentThreads, comments, channels, videos)
+pub const LIST_COST: u64 = 1;
+
+/// Quota cost of a search call
+pub const SEARCH_COST: u64 = 100;
+
+/// Quota cost of a write call (insert, update, moderation)
+pub const WRITE_COST: u64 = 50;
+
+/// Tracks YouTube Data API quota usage per day.
+///
+/// The quota belongs to the Google Cloud project rather than to a user, so
+/// usage is tracked across all users. Days are counted in UTC, while YouTube
+/// resets quotas at midnight Pacific time, so the numbers are an estimate.
+pub struct QuotaTracker {
+    db: Database,
+    daily_limit: u64,
+}
+
+impl QuotaTracker {
+    /// Create a new quota tracker, reading the daily limit from `YOUTUBE_DAILY_QUOTA`
+    pub fn new(db: Database) -> Self {
+        let daily_limit = env::var("YOUTUBE_DAILY_QUOTA")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_DAILY_QUOTA);
+
+        Self { db, daily_limit }
+    }
+
+    /// The daily quota (in units)
+    pub fn daily_limit(&self) -> u64 {
+        self.daily_limit
+    }
+
+    /// Record units spent on an API call; failures are only logged
+    pub async fn record(&self, units: u64) {
+        if let Err(e) = self.db.add_quota_usage(Utc::now().date_naive(), units).await {
+            error!("Error recording YouTube quota usage: {}", e);
+        }
+    }
+
+    /// Units spent today
+    pub async fn used_today(&self) -> Result<u64> {
+        self.db.get_quota_usage(Utc::now().date_naive()).await
+    }
+
+    /// Units left today
+    pub async fn remaining(&self) -> Result<u64> {
+        Ok(self.daily_limit.saturating_sub(self.used_today().await?))
+    }
+}
@@ -0,0 +1,222 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::event::UserEvent;
+use crate::models::{InteractionRecord, InteractionType, TriageState};
+use crate::services::events::EventBus;
+use crate::services::notifications::NotificationService;
+use crate::services::rules::link_pattern;
+use crate::services::youtube::YouTubeApi;
+
+/// Replies are checked for this long after they were posted; YouTube's filters act within hours
+const CHECK_WINDOW: Duration = Duration::days(7);
+
+/// How often every user's recent replies are checked
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// A reply the user posted that hasn't been found missing yet
+#[derive(Debug, Clone)]
+pub struct PostedReply {
+    pub reply_id: String,
+    pub comment_id: String,
+    pub text: Option<String>,
+    pub ai_model: Option<String>,
+    pub posted_at: DateTime<Utc>,
+}
+
+/// What a check found about one posted reply
+#[derive(Debug, Clone, Serialize)]
+pub struct ReplyCheck {
+    pub reply_id: String,
+    pub comment_id: String,
+
+    /// Whether YouTube still shows the reply
+    pub visible: bool,
+}
+
+/// Confirms that posted replies are still on YouTube.
+///
+/// YouTube's spam filter often removes replies with links, AI-written ones
+/// included, without telling anyone. A reply found missing is recorded as
+/// `ReplyRemoved` in the history, its comment is put back in the inbox as
+/// unanswered, and the user is notified.
+pub struct ReplyChecker {
+    db: Database,
+    youtube: Arc<dyn YouTubeApi>,
+    notifications: Arc<NotificationService>,
+    events: Arc<EventBus>,
+}
+
+impl ReplyChecker {
+    pub fn new(
+        db: Database,
+        youtube: Arc<dyn YouTubeApi>,
+        notifications: Arc<NotificationService>,
+        events: Arc<EventBus>,
+    ) -> Self {
+        Self { db, youtube, notifications, events }
+    }
+
+    /// The replies the user posted in the last week that haven't been found missing
+    pub async fn pending(&self, user_id: &str) -> Result<Vec<PostedReply>> {
+        let interactions = self.db.get_user_interactions_since(user_id, Utc::now() - CHECK_WINDOW).await?;
+
+        let removed: HashSet<&str> = interactions
+            .iter()
+            .filter(|interaction| interaction.interaction_type == InteractionType::ReplyRemoved)
+            .filter_map(|interaction| interaction.reply_id.as_deref())
+            .collect();
+
+        // A post is recorded more than once; keep the record that has the text
+        let mut posted: Vec<PostedReply> = Vec::new();
+        let mut positions: HashMap<&str, usize> = HashMap::new();
+        for interaction in &interactions {
+            if interaction.interaction_type != InteractionType::ReplyPosted {
+                continue;
+            }
+            let reply_id = match interaction.reply_id.as_deref() {
+                Some(reply_id) if !reply_id.is_empty() && !removed.contains(reply_id) => reply_id,
+                _ => continue,
+            };
+
+            let reply = PostedReply {
+                reply_id: reply_id.to_string(),
+                comment_id: interaction.comment_id.clone(),
+                text: interaction.data.get("reply_text").cloned(),
+                ai_model: interaction.data.get("ai_model").cloned(),
+                posted_at: interaction.timestamp,
+            };
+            match positions.get(reply_id) {
+                Some(&position) if posted[position].text.is_none() => posted[position] = reply,
+                Some(_) => {}
+                None => {
+                    positions.insert(reply_id, posted.len());
+                    posted.push(reply);
+                }
+            }
+        }
+
+        Ok(posted)
+    }
+
+    /// Check the user's posted replies against YouTube and act on the ones that are gone
+    pub async fn check(&self, user_id: &str, replies: &[PostedReply]) -> Result<Vec<ReplyCheck>> {
+        let reply_ids: Vec<String> = replies.iter().map(|reply| reply.reply_id.clone()).collect();
+        let visible = self.youtube.visible_comments(user_id, &reply_ids).await?;
+
+        let checks: Vec<ReplyCheck> = replies
+            .iter()
+            .map(|reply| ReplyCheck {
+                reply_id: reply.reply_id.clone(),
+                comment_id: reply.comment_id.clone(),
+                visible: visible.contains(&reply.reply_id),
+            })
+            .collect();
+
+        let removed: Vec<&PostedReply> = replies.iter().filter(|reply| !visible.contains(&reply.reply_id)).collect();
+        if removed.is_empty() {
+            return Ok(checks);
+        }
+        warn!("{} of {} replies of user {} are no longer on YouTube", removed.len(), replies.len(), user_id);
+
+        // Comments still answered by another reply stay answered
+        let answered: HashSet<&str> = checks
+            .iter()
+            .filter(|check| check.visible)
+            .map(|check| check.comment_id.as_str())
+            .collect();
+
+        for reply in &removed {
+            self.record_removed(user_id, reply, !answered.contains(reply.comment_id.as_str())).await?;
+        }
+
+        let texts: Vec<String> = removed.iter().filter_map(|reply| reply.text.clone()).collect();
+        self.notifications.replies_removed(user_id, removed.len(), &texts).await;
+
+        Ok(checks)
+    }
+
+    /// Check the user's posted replies that haven't been found missing yet
+    pub async fn check_user(&self, user_id: &str) -> Result<Vec<ReplyCheck>> {
+        let pending = self.pending(user_id).await?;
+        self.check(user_id, &pending).await
+    }
+
+    /// Record a reply found missing and, if it was the comment's answer, reopen the comment
+    async fn record_removed(&self, user_id: &str, reply: &PostedReply, reopen: bool) -> Result<()> {
+        let comment = self.db.get_comment(&reply.comment_id).await?;
+        let video_id = comment.as_ref().map(|comment| comment.video_id.clone()).unwrap_or_default();
+
+        let mut data = HashMap::from([("posted_at".to_string(), reply.posted_at.to_rfc3339())]);
+        if let Some(text) = &reply.text {
+            data.insert("has_link".to_string(), link_pattern().is_match(text).to_string());
+            data.insert("reply_text".to_string(), text.clone());
+        }
+        if let Some(ai_model) = &reply.ai_model {
+            data.insert("ai_model".to_string(), ai_model.clone());
+        }
+
+        self.db.record_interaction(&InteractionRecord {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            video_id: video_id.clone(),
+            comment_id: reply.comment_id.clone(),
+            reply_id: Some(reply.reply_id.clone()),
+            interaction_type: InteractionType::ReplyRemoved,
+            timestamp: Utc::now(),
+            data,
+        }).await?;
+
+        if reopen && comment.is_some() {
+            self.db.mark_comment_replied(&reply.comment_id, false).await?;
+            self.db.set_comment_triage(&reply.comment_id, TriageState::New).await?;
+            self.events.publish(user_id, UserEvent::CommentChanged {
+                video_id,
+                comment_id: reply.comment_id.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Spawn a background task checking every user's recent replies once an hour
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                let user_ids = match self.db.get_replying_user_ids(Utc::now() - CHECK_WINDOW).await {
+                    Ok(user_ids) => user_ids,
+                    Err(e) => {
+                        error!("Error listing users with recent replies: {}", e);
+                        continue;
+                    }
+                };
+
+                for user_id in user_ids {
+                    // Users who must reconnect can't be checked until they do
+                    if matches!(self.db.get_user(&user_id).await, Ok(Some(user)) if user.is_disconnected()) {
+                        continue;
+                    }
+
+                    match self.check_user(&user_id).await {
+                        Ok(checks) => {
+                            let removed = checks.iter().filter(|check| !check.visible).count();
+                            info!("Checked {} replies of user {}, {} removed", checks.len(), user_id, removed);
+                        }
+                        Err(e) => error!("Error checking the replies of user {}: {}", user_id, e),
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,190 @@
+//! Pruning of old interactions and comments.
+//!
+//! Each user's [`RetentionPolicy`] says how many days their interactions and
+//! comments are kept, falling back to the server's `RETENTION_INTERACTIONS_DAYS`
+//! and `RETENTION_COMMENTS_DAYS`; without either, records are kept forever.
+//! `RETENTION_MODE` decides whether older records are deleted, archived or
+//! only counted.
+
+use anyhow::{Context, Result};
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::models::auth::RetentionPolicy;
+
+/// How often the pruning job runs
+const PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// What happens to records older than their retention period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneMode {
+    /// They are deleted
+    #[default]
+    Delete,
+
+    /// They are moved to `archived_interactions` and `archived_comments`
+    Archive,
+
+    /// They are only counted and logged
+    DryRun,
+}
+
+/// The server's retention settings
+#[derive(Debug, Clone, Default)]
+pub struct RetentionConfig {
+    /// Periods for users who haven't set their own
+    pub defaults: RetentionPolicy,
+
+    pub mode: PruneMode,
+}
+
+impl RetentionConfig {
+    /// Read `RETENTION_INTERACTIONS_DAYS`, `RETENTION_COMMENTS_DAYS` and `RETENTION_MODE`
+    pub fn from_env() -> Result<Self> {
+        let mode = match env::var("RETENTION_MODE").as_deref() {
+            Err(_) | Ok("") | Ok("delete") => PruneMode::Delete,
+            Ok("archive") => PruneMode::Archive,
+            Ok("dry_run") => PruneMode::DryRun,
+            Ok(other) => anyhow::bail!("RETENTION_MODE must be delete, archive or dry_run, not {}", other),
+        };
+
+        Ok(Self {
+            defaults: RetentionPolicy {
+                interactions_days: days_from_env("RETENTION_INTERACTIONS_DAYS")?,
+                comments_days: days_from_env("RETENTION_COMMENTS_DAYS")?,
+            },
+            mode,
+        })
+    }
+}
+
+/// A retention period from the environment, if set
+fn days_from_env(name: &str) -> Result<Option<u32>> {
+    match env::var(name) {
+        Ok(days) if !days.is_empty() => {
+            let days: u32 = days.parse().with_context(|| format!("{} must be a number of days", name))?;
+            if days == 0 {
+                anyhow::bail!("{} must be at least 1", name);
+            }
+            Ok(Some(days))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// What a run pruned for one user, or would have in a dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct PruneReport {
+    pub user_id: String,
+    pub mode: PruneMode,
+
+    /// Interactions pruned
+    pub interactions: usize,
+
+    /// Comments pruned
+    pub comments: usize,
+}
+
+/// Removes records older than their retention period
+pub struct Pruner {
+    db: Database,
+    config: RetentionConfig,
+}
+
+impl Pruner {
+    pub fn new(db: Database, config: RetentionConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// The mode runs use unless they ask for a dry run
+    pub fn mode(&self) -> PruneMode {
+        self.config.mode
+    }
+
+    /// Prune one user's records under their policy, with the server's periods where it sets none
+    pub async fn prune_user(&self, user_id: &str, policy: RetentionPolicy, mode: PruneMode) -> Result<PruneReport> {
+        let policy = policy.or(self.config.defaults);
+        let now = Utc::now();
+        let archive = mode == PruneMode::Archive;
+        let mut report = PruneReport { user_id: user_id.to_string(), mode, interactions: 0, comments: 0 };
+
+        if let Some(days) = policy.interactions_days {
+            let before = now - Duration::days(days.into());
+            report.interactions = match mode {
+                PruneMode::DryRun => self.db.count_interactions_before(user_id, before).await?,
+                _ => self.db.prune_interactions(user_id, before, archive).await?,
+            };
+        }
+
+        if let Some(days) = policy.comments_days {
+            let before = now - Duration::days(days.into());
+            let video_ids: Vec<String> = self.db.get_user_videos(user_id).await?.into_iter().map(|v| v.video_id).collect();
+            if !video_ids.is_empty() {
+                report.comments = match mode {
+                    PruneMode::DryRun => self.db.count_comments_before(&video_ids, before).await?,
+                    _ => self.db.prune_comments(&video_ids, before, archive).await?,
+                };
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Prune every user's records, returning what was pruned for the users something was pruned for
+    pub async fn run(&self, mode: PruneMode) -> Result<Vec<PruneReport>> {
+        let users = self.db.get_users().await?;
+
+        let mut reports = Vec::new();
+        for user in users {
+            match self.prune_user(&user.id, user.preferences.retention, mode).await {
+                Ok(report) if report.interactions > 0 || report.comments > 0 => {
+                    let verb = match mode {
+                        PruneMode::Delete => "Deleted",
+                        PruneMode::Archive => "Archived",
+                        PruneMode::DryRun => "Would prune",
+                    };
+                    info!(
+                        "{} {} interactions and {} comments of user {}",
+                        verb, report.interactions, report.comments, user.id
+                    );
+                    reports.push(report);
+                }
+                Ok(_) => {}
+                Err(e) => error!("Error pruning the records of user {}: {}", user.id, e),
+            }
+        }
+
+        let interactions: usize = reports.iter().map(|report| report.interactions).sum();
+        let comments: usize = reports.iter().map(|report| report.comments).sum();
+        info!(
+            "Retention run ({:?}) pruned {} interactions and {} comments of {} users",
+            mode,
+            interactions,
+            comments,
+            reports.len()
+        );
+
+        Ok(reports)
+    }
+
+    /// Spawn a background task pruning every user's records once a day
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                if let Err(e) = self.run(self.config.mode).await {
+                    error!("Error pruning old records: {}", e);
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,281 @@
+use anyhow::Result;
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::Comment;
+use crate::models::rule::{FilterRule, RuleAction};
+use crate::services::{history, sentiment};
+
+/// Largest compiled size of a rule's regular expression, so a rule can't exhaust memory
+const MAX_PATTERN_SIZE: usize = 1 << 20;
+
+/// Longest reply text an auto-reply rule can post (YouTube's comment limit)
+pub(crate) const MAX_REPLY_LENGTH: usize = 10_000;
+
+/// Most rules a user can have
+pub const MAX_RULES_PER_USER: usize = 100;
+
+/// Links in comment text: URLs, `www.` hosts and bare domains on common TLDs
+pub(crate) fn link_pattern() -> &'static Regex {
+    static LINK: OnceLock<Regex> = OnceLock::new();
+    LINK.get_or_init(|| {
+        Regex::new(r"(?i)(https?://|www\.)\S+|\b[a-z0-9-]+\.(com|net|org|io|ly|gg|me|co|tv|xyz)\b").unwrap()
+    })
+}
+
+/// @-mentions of channels in comment text, e.g. `@Numan` but not `me@example.com`
+pub(crate) fn mention_pattern() -> &'static Regex {
+    static MENTION: OnceLock<Regex> = OnceLock::new();
+    MENTION.get_or_init(|| Regex::new(r"(^|[^\w@])@[\w.-]+").unwrap())
+}
+
+/// A rule with its patterns compiled
+struct CompiledRule {
+    rule: FilterRule,
+    keywords: Vec<String>,
+    text_pattern: Option<Regex>,
+    author_pattern: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn compile(rule: FilterRule) -> Result<Self> {
+        let keywords = rule
+            .conditions
+            .keywords
+            .iter()
+            .map(|k| k.trim().to_lowercase())
+            .filter(|k| !k.is_empty())
+            .collect();
+        let text_pattern = rule.conditions.text_pattern.as_deref().map(compile_pattern).transpose()?;
+        let author_pattern = rule.conditions.author_pattern.as_deref().map(compile_pattern).transpose()?;
+
+        Ok(Self { rule, keywords, text_pattern, author_pattern })
+    }
+
+    /// Whether the comment matches; `author_tags` are the tags on the commenter's profile
+    fn matches(&self, comment: &Comment, author_tags: &[String]) -> bool {
+        if !self.keywords.is_empty() {
+            let text = comment.text.to_lowercase();
+            if !self.keywords.iter().any(|k| text.contains(k)) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.text_pattern {
+            if !pattern.is_match(&comment.text) {
+                return false;
+            }
+        }
+
+        if let Some(pattern) = &self.author_pattern {
+            if !pattern.is_match(&comment.author) {
+                return false;
+            }
+        }
+
+        if let Some(contains_link) = self.rule.conditions.contains_link {
+            if link_pattern().is_match(&comment.text) != contains_link {
+                return false;
+            }
+        }
+
+        let wanted = &self.rule.conditions.author_tags;
+        if !wanted.is_empty() && !wanted.iter().any(|tag| author_tags.iter().any(|t| t.eq_ignore_ascii_case(tag.trim()))) {
+            return false;
+        }
+
+        true
+    }
+}
+
+fn compile_pattern(pattern: &str) -> Result<Regex> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(MAX_PATTERN_SIZE)
+        .build()
+        .map_err(|e| AppError::Validation(format!("Invalid pattern {:?}: {}", pattern, e)).into())
+}
+
+/// Check that a rule can be stored: it has a condition, its patterns compile and auto-replies have text
+pub fn validate(rule: &FilterRule) -> Result<()> {
+    if rule.name.trim().is_empty() {
+        return Err(AppError::Validation("Rule name must not be empty".to_string()).into());
+    }
+
+    if rule.conditions.is_empty() {
+        return Err(AppError::Validation("A rule needs at least one condition".to_string()).into());
+    }
+
+    match (rule.action, rule.reply_text.as_deref().map(str::trim)) {
+        (RuleAction::AutoReply, None | Some("")) => {
+            return Err(AppError::Validation("auto_reply rules need a reply_text".to_string()).into());
+        }
+        (_, Some(text)) if text.chars().count() > MAX_REPLY_LENGTH => {
+            return Err(AppError::Validation(format!("reply_text must be at most {} characters", MAX_REPLY_LENGTH)).into());
+        }
+        _ => {}
+    }
+
+    CompiledRule::compile(rule.clone()).map(|_| ())
+}
+
+/// A reply an auto-reply rule wants posted
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutoReply {
+    pub comment_id: String,
+    pub rule_id: String,
+    pub text: String,
+
+    /// Why the reply must not be posted, if it mustn't
+    pub skipped: Option<&'static str>,
+}
+
+/// Applies users' filter rules to comments as they are saved
+pub struct RuleService {
+    db: Database,
+}
+
+impl RuleService {
+    /// Create a new rule service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Apply a user's enabled rules to comments about to be saved.
+    ///
+    /// Matching comments get a metadata entry per action (`hidden`, `spam`,
+    /// `flagged_for_review`, `auto_reply_rule`) holding the rule ID. Re-synced
+    /// comments are matched again so the entries survive, but only comments in
+    /// `new_ids` count as hits and get auto-replies. Returns the replies to post,
+    /// at most one per comment, along with the skipped ones so they can be recorded.
+    pub async fn apply(&self, user_id: &str, comments: &mut [Comment], new_ids: &HashSet<String>) -> Result<Vec<AutoReply>> {
+        let rules: Vec<CompiledRule> = self
+            .db
+            .get_filter_rules(user_id)
+            .await?
+            .into_iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| {
+                let rule_id = rule.rule_id.clone();
+                CompiledRule::compile(rule)
+                    .map_err(|e| error!("Skipping filter rule {}: {}", rule_id, e))
+                    .ok()
+            })
+            .collect();
+
+        if rules.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Tags are only needed when a rule asks for them
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        if rules.iter().any(|r| !r.rule.conditions.author_tags.is_empty()) {
+            for profile in self.db.get_commenter_profiles(user_id).await? {
+                tags.insert(profile.channel_id, profile.tags);
+            }
+        }
+
+        let mut hits: HashMap<&str, u64> = HashMap::new();
+        let mut auto_replies = Vec::new();
+
+        for comment in comments.iter_mut() {
+            let is_new = new_ids.contains(&comment.comment_id);
+            let mut auto_replied = false;
+
+            let author_tags = tags.get(&comment.author_channel_id).map(Vec::as_slice).unwrap_or_default();
+            let matched: Vec<&FilterRule> = rules.iter().filter(|r| r.matches(comment, author_tags)).map(|r| &r.rule).collect();
+            for rule in matched {
+                comment.metadata.insert(rule.action.metadata_key().to_string(), rule.rule_id.clone());
+
+                if !is_new {
+                    continue;
+                }
+                *hits.entry(rule.rule_id.as_str()).or_default() += 1;
+
+                if rule.action == RuleAction::AutoReply {
+                    if let Some(text) = &rule.reply_text {
+                        let skipped = if comment.replied_to {
+                            Some(history::SKIP_ALREADY_REPLIED)
+                        } else if auto_replied {
+                            Some(history::SKIP_ALREADY_AUTO_REPLIED)
+                        } else if sentiment::is_hostile(comment.original_text()) {
+                            Some(history::SKIP_HOSTILE)
+                        } else {
+                            None
+                        };
+                        auto_replied |= skipped.is_none();
+                        auto_replies.push(AutoReply {
+                            comment_id: comment.comment_id.clone(),
+                            rule_id: rule.rule_id.clone(),
+                            text: text.clone(),
+                            skipped,
+                        });
+                    }
+                }
+            }
+        }
+
+        let now = Utc::now();
+        for (rule_id, count) in hits {
+            info!("Filter rule {} matched {} new comments", rule_id, count);
+            if let Err(e) = self.db.record_rule_hits(rule_id, count, now).await {
+                error!("Error recording hits of filter rule {}: {}", rule_id, e);
+            }
+        }
+
+        Ok(auto_replies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentBuilder;
+    use crate::models::rule::RuleConditions;
+
+    fn comment(author: &str, text: &str) -> Comment {
+        CommentBuilder::new("v", "c").author(author).text(text).build()
+    }
+
+    fn rule(conditions: RuleConditions) -> CompiledRule {
+        CompiledRule::compile(FilterRule::new("u", "r", conditions, RuleAction::Hide, None)).unwrap()
+    }
+
+    #[test]
+    fn test_conditions_must_all_hold() {
+        let giveaway = rule(RuleConditions {
+            keywords: vec!["Giveaway".to_string()],
+            contains_link: Some(true),
+            ..Default::default()
+        });
+        assert!(giveaway.matches(&comment("a", "FREE giveaway at bit.ly/xyz"), &[]));
+        assert!(!giveaway.matches(&comment("a", "Is there a giveaway?"), &[]));
+        assert!(!giveaway.matches(&comment("a", "Visit www.example.com"), &[]));
+
+        let bots = rule(RuleConditions { author_pattern: Some(r"^crypto.*\d{3,}$".to_string()), ..Default::default() });
+        assert!(bots.matches(&comment("CryptoKing4821", "hi"), &[]));
+        assert!(!bots.matches(&comment("Viewer", "hi"), &[]));
+
+        let trolls = rule(RuleConditions { author_tags: vec!["Troll".to_string()], ..Default::default() });
+        assert!(trolls.matches(&comment("a", "hi"), &["troll".to_string()]));
+        assert!(!trolls.matches(&comment("a", "hi"), &["superfan".to_string()]));
+    }
+
+    #[test]
+    fn test_validate() {
+        let empty = FilterRule::new("u", "r", RuleConditions::default(), RuleAction::Hide, None);
+        assert!(validate(&empty).is_err());
+
+        let conditions = RuleConditions { text_pattern: Some("(unclosed".to_string()), ..Default::default() };
+        assert!(validate(&FilterRule::new("u", "r", conditions, RuleAction::Hide, None)).is_err());
+
+        let conditions = RuleConditions { keywords: vec!["link?".to_string()], ..Default::default() };
+        assert!(validate(&FilterRule::new("u", "r", conditions.clone(), RuleAction::AutoReply, None)).is_err());
+        assert!(validate(&FilterRule::new("u", "r", conditions, RuleAction::AutoReply, Some("In the description!".to_string()))).is_ok());
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use chrono::Utc;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use tracing::error;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::saved_reply::SavedReply;
+use crate::services::rules::MAX_REPLY_LENGTH;
+
+/// Variables a saved reply can use
+pub const PLACEHOLDERS: &[&str] = &["author", "video_title", "timestamp"];
+
+/// Most saved replies a user can have
+pub const MAX_SAVED_REPLIES_PER_USER: usize = 200;
+
+/// `{{name}}` placeholders, spaces inside the braces allowed
+fn placeholder_pattern() -> &'static Regex {
+    static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+    PLACEHOLDER.get_or_init(|| Regex::new(r"\{\{\s*([A-Za-z_]+)\s*\}\}").unwrap())
+}
+
+/// Check that a template can be stored: it has a name and text, and only known placeholders
+pub fn validate(template: &SavedReply) -> Result<()> {
+    if template.name.trim().is_empty() {
+        return Err(AppError::Validation("Template name must not be empty".to_string()).into());
+    }
+
+    if template.text.trim().is_empty() {
+        return Err(AppError::Validation("Template text must not be empty".to_string()).into());
+    }
+
+    if template.text.chars().count() > MAX_REPLY_LENGTH {
+        return Err(AppError::Validation(format!("Template text must be at most {} characters", MAX_REPLY_LENGTH)).into());
+    }
+
+    for captures in placeholder_pattern().captures_iter(&template.text) {
+        let name = &captures[1];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(AppError::Validation(format!(
+                "Unknown placeholder {{{{{}}}}}; use one of {}",
+                name,
+                PLACEHOLDERS.join(", ")
+            ))
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Fill in a template's placeholders; ones without a value are left as written
+pub fn expand(text: &str, vars: &HashMap<&str, String>) -> String {
+    placeholder_pattern()
+        .replace_all(text, |captures: &Captures| match vars.get(&captures[1]) {
+            Some(value) => value.clone(),
+            None => captures[0].to_string(),
+        })
+        .into_owned()
+}
+
+/// Turns saved replies into reply text for a comment
+pub struct SavedReplyService {
+    db: Database,
+}
+
+impl SavedReplyService {
+    /// Create a new saved reply service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// A template of the user; other users' templates are reported as missing
+    pub async fn get(&self, user_id: &str, template_id: &str) -> Result<SavedReply> {
+        self.db
+            .get_saved_reply(template_id)
+            .await?
+            .filter(|template| template.user_id == user_id)
+            .ok_or_else(|| AppError::NotFound(format!("Template {}", template_id)).into())
+    }
+
+    /// Expand a user's template for a stored comment.
+    ///
+    /// `{{author}}` is the comment's author, `{{video_title}}` the title of its
+    /// video and `{{timestamp}}` when the comment was published.
+    pub async fn render(&self, user_id: &str, template_id: &str, comment_id: &str) -> Result<String> {
+        let template = self.get(user_id, template_id).await?;
+        let comment = self
+            .db
+            .get_comment(comment_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Comment {}", comment_id)))?;
+        let video_title = self
+            .db
+            .get_video(&comment.video_id)
+            .await?
+            .map(|video| video.title)
+            .unwrap_or_else(|| "YouTube Video".to_string());
+
+        let vars = HashMap::from([
+            ("author", comment.author),
+            ("video_title", video_title),
+            ("timestamp", comment.published_at.format("%Y-%m-%d %H:%M UTC").to_string()),
+        ]);
+        Ok(expand(&template.text, &vars))
+    }
+
+    /// Count a reply posted from a template; failing to count doesn't fail the reply
+    pub async fn record_use(&self, template_id: &str) {
+        if let Err(e) = self.db.record_saved_reply_use(template_id, Utc::now()).await {
+            error!("Error recording use of reply template {}: {}", template_id, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        let vars = HashMap::from([("author", "Sam".to_string()), ("video_title", "Drone tour".to_string())]);
+        assert_eq!(
+            expand("Thanks {{author}}, glad you liked {{ video_title }}! {{timestamp}}", &vars),
+            "Thanks Sam, glad you liked Drone tour! {{timestamp}}"
+        );
+    }
+
+    #[test]
+    fn test_validate_placeholders() {
+        assert!(validate(&SavedReply::new("u", "Thanks", "Thanks {{author}}!")).is_ok());
+        assert!(validate(&SavedReply::new("u", "Thanks", "Thanks {{name}}!")).is_err());
+        assert!(validate(&SavedReply::new("u", "Thanks", "  ")).is_err());
+    }
+}
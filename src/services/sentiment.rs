@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::Comment;
+
+/// Scores at or above this are considered positive, at or below its negation negative
+const LABEL_THRESHOLD: f32 = 0.2;
+
+/// Scores at or below this make a text addressed to the creator hostile
+const HOSTILE_THRESHOLD: f32 = -0.6;
+
+const POSITIVE_WORDS: &[&str] = &[
+    "amazing", "awesome", "beautiful", "best", "brilliant", "clear", "cool", "enjoyed", "excellent",
+    "fantastic", "favorite", "favourite", "fun", "genius", "good", "great", "helpful", "helped",
+    "incredible", "inspiring", "interesting", "legend", "love", "loved", "lovely", "nice", "perfect",
+    "thank", "thanks", "useful", "wonderful", "wow",
+];
+
+const NEGATIVE_WORDS: &[&str] = &[
+    "annoying", "awful", "bad", "boring", "broken", "clickbait", "confusing", "disappointed",
+    "disappointing", "dislike", "fake", "garbage", "hate", "hated", "horrible", "misleading",
+    "scam", "sucks", "stupid", "terrible", "trash", "ugly", "useless", "waste", "worse", "worst",
+    "wrong",
+];
+
+/// Insults that make a comment hostile whatever else it says
+const INSULTS: &[&str] = &[
+    "clown", "dumb", "fraud", "grifter", "idiot", "idiots", "liar", "loser", "moron", "morons",
+    "pathetic", "shill", "stfu", "stupid",
+];
+
+const SECOND_PERSON: &[&str] = &["you", "your", "you're", "youre", "yours", "u", "ur"];
+
+const NEGATIONS: &[&str] = &["not", "no", "never", "dont", "don't", "isnt", "isn't", "wasnt", "wasn't", "cant", "can't"];
+
+/// Coarse sentiment label
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SentimentLabel {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl SentimentLabel {
+    /// Label for a sentiment score
+    pub fn from_score(score: f32) -> Self {
+        if score >= LABEL_THRESHOLD {
+            SentimentLabel::Positive
+        } else if score <= -LABEL_THRESHOLD {
+            SentimentLabel::Negative
+        } else {
+            SentimentLabel::Neutral
+        }
+    }
+}
+
+/// Score the sentiment of a text between -1.0 (negative) and 1.0 (positive).
+///
+/// This runs on every ingested comment, so it is deliberately cheap: word
+/// lists with basic negation handling rather than a model call.
+pub fn score(text: &str) -> f32 {
+    let words = words(text);
+
+    let mut total = 0i32;
+    let mut hits = 0i32;
+
+    for (i, word) in words.iter().enumerate() {
+        let polarity = if POSITIVE_WORDS.contains(&word.as_str()) {
+            1
+        } else if NEGATIVE_WORDS.contains(&word.as_str()) {
+            -1
+        } else {
+            continue;
+        };
+
+        // "not good", "never boring": a negation in the two preceding words flips the polarity
+        let negated = words[i.saturating_sub(2)..i]
+            .iter()
+            .any(|w| NEGATIONS.contains(&w.as_str()));
+
+        total += if negated { -polarity } else { polarity };
+        hits += 1;
+    }
+
+    if hits == 0 {
+        0.0
+    } else {
+        total as f32 / hits as f32
+    }
+}
+
+/// Whether a text insults someone, or is strongly negative and said to the creator
+pub fn is_hostile(text: &str) -> bool {
+    let words = words(text);
+    words.iter().any(|w| INSULTS.contains(&w.as_str()))
+        || (score(text) <= HOSTILE_THRESHOLD && words.iter().any(|w| SECOND_PERSON.contains(&w.as_str())))
+}
+
+/// Whether a thread needs a careful answer: the comment, or the follow-up the reply would answer, is hostile
+pub fn is_heated(comment: &Comment) -> bool {
+    let follow_up = comment.follow_up.as_ref()
+        .and_then(|follow_up| comment.replies.iter().find(|reply| reply.reply_id == follow_up.reply_id));
+    is_hostile(comment.original_text()) || follow_up.is_some_and(|reply| is_hostile(reply.original_text()))
+}
+
+/// The lowercased words of a text, without surrounding punctuation
+fn words(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|w| {
+            w.trim_matches(|c: char| !c.is_alphanumeric() && c != '\'')
+                .to_lowercase()
+        })
+        .filter(|w| !w.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score() {
+        assert!(score("This is an amazing video, thanks!") > 0.9);
+        assert!(score("Worst clickbait ever. Total waste of time") < -0.9);
+        assert_eq!(score("What camera do you use?"), 0.0);
+
+        // Negation flips polarity
+        assert!(score("This was not good") < 0.0);
+        assert!(score("Never boring, always a treat") > 0.0);
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(SentimentLabel::from_score(0.8), SentimentLabel::Positive);
+        assert_eq!(SentimentLabel::from_score(0.0), SentimentLabel::Neutral);
+        assert_eq!(SentimentLabel::from_score(-0.5), SentimentLabel::Negative);
+    }
+
+    #[test]
+    fn test_hostile() {
+        assert!(is_hostile("You're a liar and everyone knows it"));
+        assert!(is_hostile("Your videos are garbage. Worst channel ever"));
+        // Criticism of the video alone isn't hostile
+        assert!(!is_hostile("The audio was bad in this one"));
+        assert!(!is_hostile("Worst clickbait ever"));
+        assert!(!is_hostile("Thanks, you explained it well"));
+    }
+}
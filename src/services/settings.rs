@@ -0,0 +1,107 @@
+use anyhow::Result;
+use chrono::Utc;
+use std::sync::{Arc, RwLock};
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::settings::{RuntimeSettings, RuntimeSettingsPatch};
+use crate::utils::logging;
+
+/// Highest YouTube request rate an operator can set (requests per second)
+const MAX_YOUTUBE_REQUESTS_PER_SEC: u32 = 1000;
+
+/// Holds the runtime settings, persisted so they survive a restart.
+///
+/// Services read [`SettingsService::current`] whenever they act, so a change applies
+/// from the next request, sync or generation.
+pub struct SettingsService {
+    db: Database,
+    current: RwLock<Arc<RuntimeSettings>>,
+
+    /// Held for a whole update, so concurrent changes apply one after the other instead of overwriting each other
+    update_lock: Mutex<()>,
+}
+
+impl SettingsService {
+    /// Create a service holding the defaults; call [`SettingsService::load`] to read the stored settings
+    pub fn new(db: Database) -> Self {
+        Self {
+            db,
+            current: RwLock::new(Arc::new(RuntimeSettings::default())),
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    /// The settings in effect
+    pub fn current(&self) -> Arc<RuntimeSettings> {
+        self.current.read().unwrap().clone()
+    }
+
+    /// Read the stored settings and apply them
+    pub async fn load(&self) -> Result<()> {
+        if let Some(settings) = self.db.get_runtime_settings().await? {
+            logging::set_filter(settings.log_filter.as_deref())?;
+            info!("Loaded runtime settings changed at {:?}", settings.updated_at);
+            *self.current.write().unwrap() = Arc::new(settings);
+        }
+
+        Ok(())
+    }
+
+    /// Validate, store and apply a change, returning the new settings.
+    ///
+    /// Every field is checked before anything is applied, so a rejected change leaves
+    /// the settings in effect as they were.
+    pub async fn update(&self, patch: RuntimeSettingsPatch) -> Result<Arc<RuntimeSettings>> {
+        let _guard = self.update_lock.lock().await;
+        let mut settings = (*self.current()).clone();
+
+        let mut log_filter = None;
+        if let Some(directives) = patch.log_filter {
+            let directives = Some(directives.trim().to_string()).filter(|f| !f.is_empty());
+            log_filter = Some(logging::parse_filter(directives.as_deref())?);
+            settings.log_filter = directives;
+        }
+
+        if let Some(rate) = patch.youtube_requests_per_sec {
+            if rate > MAX_YOUTUBE_REQUESTS_PER_SEC {
+                return Err(AppError::Validation(format!(
+                    "youtube_requests_per_sec must be at most {}",
+                    MAX_YOUTUBE_REQUESTS_PER_SEC
+                ))
+                .into());
+            }
+            settings.youtube_requests_per_sec = Some(rate).filter(|rate| *rate > 0);
+        }
+
+        if let Some(paused) = patch.monitor_paused {
+            settings.monitor_paused = paused;
+        }
+
+        if let Some(enabled) = patch.ai_enabled {
+            settings.ai_enabled = enabled;
+        }
+
+        if let Some(mask) = patch.mask_sensitive_text {
+            settings.mask_sensitive_text = mask;
+        }
+
+        if let Some(dry_run) = patch.dry_run {
+            settings.dry_run = dry_run;
+        }
+
+        settings.updated_at = Some(Utc::now());
+        self.db.save_runtime_settings(&settings).await?;
+
+        if let Some(filter) = log_filter {
+            logging::apply_filter(filter)?;
+        }
+
+        info!("Runtime settings changed: {:?}", settings);
+        let settings = Arc::new(settings);
+        *self.current.write().unwrap() = settings.clone();
+        Ok(settings)
+    }
+}
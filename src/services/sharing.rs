@@ -0,0 +1,163 @@
+//! Signed, expiring links to a comment thread.
+//!
+//! A share link lets someone without an account read one thread and the
+//! user's draft reply to it. Nothing is stored: the link carries the comment,
+//! the user and the expiry, signed with HMAC-SHA256 under `SHARE_LINK_SECRET`,
+//! so changing the secret revokes every link handed out.
+
+use anyhow::Result;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::env;
+use tracing::warn;
+use uuid::Uuid;
+
+/// How long a link works unless the request says otherwise
+pub const DEFAULT_SHARE_HOURS: u32 = 72;
+
+/// Longest a link may work
+pub const MAX_SHARE_HOURS: u32 = 30 * 24;
+
+/// Where links point unless `APP_BASE_URL` is set
+const DEFAULT_APP_BASE_URL: &str = "http://localhost:3000";
+
+/// What a share link grants access to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareClaims {
+    /// The user who shared the thread
+    #[serde(rename = "u")]
+    pub user_id: String,
+
+    /// The thread's top-level comment
+    #[serde(rename = "c")]
+    pub comment_id: String,
+
+    /// When the link stops working, as a Unix timestamp
+    #[serde(rename = "e")]
+    pub expires_at: i64,
+}
+
+impl ShareClaims {
+    pub fn expires_at(&self) -> DateTime<Utc> {
+        Utc.timestamp_opt(self.expires_at, 0).single().unwrap_or_default()
+    }
+}
+
+/// A link as handed to the user
+#[derive(Debug, Clone, Serialize)]
+pub struct ShareLink {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Signs share links and checks the ones presented
+pub struct ShareLinks {
+    secret: Vec<u8>,
+    base_url: String,
+}
+
+impl ShareLinks {
+    pub fn new(secret: impl Into<Vec<u8>>, base_url: &str) -> Self {
+        Self { secret: secret.into(), base_url: base_url.trim_end_matches('/').to_string() }
+    }
+
+    /// Sign with `SHARE_LINK_SECRET`, pointing links at `APP_BASE_URL`.
+    ///
+    /// Without a secret a random one is used, so links stop working when the server restarts.
+    pub fn from_env() -> Self {
+        let secret = match env::var("SHARE_LINK_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+            _ => {
+                warn!("SHARE_LINK_SECRET is not set; share links will stop working when the server restarts");
+                [Uuid::new_v4().as_bytes().as_slice(), Uuid::new_v4().as_bytes().as_slice()].concat()
+            }
+        };
+        let base_url = env::var("APP_BASE_URL").unwrap_or_else(|_| DEFAULT_APP_BASE_URL.to_string());
+        Self::new(secret, &base_url)
+    }
+
+    /// A link to the thread of one of the user's comments, working for the given number of hours
+    pub fn create(&self, user_id: &str, comment_id: &str, hours: u32) -> Result<ShareLink> {
+        let expires_at = Utc::now() + Duration::hours(hours.into());
+        let claims = ShareClaims {
+            user_id: user_id.to_string(),
+            comment_id: comment_id.to_string(),
+            expires_at: expires_at.timestamp(),
+        };
+
+        Ok(ShareLink {
+            url: format!("{}/share/{}", self.base_url, self.sign(&claims)?),
+            expires_at: claims.expires_at(),
+        })
+    }
+
+    /// The token of a link: the claims and their signature, each base64url-encoded
+    pub fn sign(&self, claims: &ShareClaims) -> Result<String> {
+        let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(claims)?);
+        let signature = URL_SAFE_NO_PAD.encode(self.mac(&payload).finalize().into_bytes());
+        Ok(format!("{}.{}", payload, signature))
+    }
+
+    /// The claims of a token, if it was signed with this secret and hasn't expired
+    pub fn verify(&self, token: &str, now: DateTime<Utc>) -> Option<ShareClaims> {
+        let (payload, signature) = token.split_once('.')?;
+        let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+        // Compared in constant time
+        self.mac(payload).verify_slice(&signature).ok()?;
+
+        let claims: ShareClaims = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).ok()?).ok()?;
+        (claims.expires_at > now.timestamp()).then_some(claims)
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC takes keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claims(expires_at: DateTime<Utc>) -> ShareClaims {
+        ShareClaims { user_id: "user-1".to_string(), comment_id: "c1".to_string(), expires_at: expires_at.timestamp() }
+    }
+
+    #[test]
+    fn test_signed_token_verifies_until_expiry() {
+        let links = ShareLinks::new("secret", "http://localhost:3000/");
+        let now = Utc::now();
+        let token = links.sign(&claims(now + Duration::hours(1))).unwrap();
+
+        assert_eq!(links.verify(&token, now), Some(claims(now + Duration::hours(1))));
+        assert_eq!(links.verify(&token, now + Duration::hours(2)), None);
+    }
+
+    #[test]
+    fn test_tampered_or_foreign_token_is_rejected() {
+        let links = ShareLinks::new("secret", "http://localhost:3000");
+        let now = Utc::now();
+        let token = links.sign(&claims(now + Duration::hours(1))).unwrap();
+
+        let mut other = claims(now + Duration::hours(1));
+        other.comment_id = "c2".to_string();
+        let forged_payload = links.sign(&other).unwrap();
+        let tampered = format!("{}.{}", forged_payload.split_once('.').unwrap().0, token.split_once('.').unwrap().1);
+        assert_eq!(links.verify(&tampered, now), None);
+
+        assert_eq!(ShareLinks::new("other secret", "http://localhost:3000").verify(&token, now), None);
+        assert_eq!(links.verify("not-a-token", now), None);
+    }
+
+    #[test]
+    fn test_link_points_at_share_page() {
+        let link = ShareLinks::new("secret", "https://app.example.com/").create("user-1", "c1", 24).unwrap();
+        assert!(link.url.starts_with("https://app.example.com/share/"));
+        assert!(link.expires_at > Utc::now() + Duration::hours(23));
+    }
+}
@@ -0,0 +1,157 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::env;
+
+use crate::models::auth::User;
+use crate::models::notification::{is_slack_webhook_url, Notification, NotificationChannel, SLACK_WEBHOOK_PREFIX};
+use crate::services::notifications::Notifier;
+
+/// Where a Slack message is sent
+#[derive(Debug, Clone)]
+pub enum SlackTarget {
+    /// An incoming webhook URL, which is bound to a channel when it is created
+    Webhook(String),
+
+    /// A channel ID, posted to with the bot token
+    Channel(String),
+}
+
+/// Posts notifications to Slack through incoming webhooks or a bot
+pub struct SlackNotifier {
+    client: Client,
+    bot_token: Option<String>,
+}
+
+impl SlackNotifier {
+    /// Create a new Slack notifier, reading the optional bot token from `SLACK_BOT_TOKEN`
+    pub fn from_env(client: Client) -> Self {
+        Self {
+            client,
+            bot_token: env::var("SLACK_BOT_TOKEN").ok(),
+        }
+    }
+
+    /// Post a notification to a webhook or channel
+    pub async fn send(&self, target: &SlackTarget, notification: &Notification) -> Result<()> {
+        match target {
+            SlackTarget::Webhook(url) => {
+                // Saved URLs are checked, but one stored before that must not reach other hosts either
+                if !is_slack_webhook_url(url) {
+                    anyhow::bail!("Slack webhook URL must start with {}", SLACK_WEBHOOK_PREFIX);
+                }
+
+                self.client
+                    .post(url)
+                    .json(&message(notification))
+                    .send()
+                    .await?
+                    .error_for_status()
+                    .context("Slack webhook rejected the message")?;
+            }
+            SlackTarget::Channel(channel) => {
+                let token = self
+                    .bot_token
+                    .as_ref()
+                    .context("SLACK_BOT_TOKEN environment variable not set")?;
+
+                let mut body = message(notification);
+                body["channel"] = json!(channel);
+
+                // The Web API reports most errors with a 200 status and `ok: false`
+                let response: SlackApiResponse = self.client
+                    .post("https://slack.com/api/chat.postMessage")
+                    .bearer_auth(token)
+                    .json(&body)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                if !response.ok {
+                    anyhow::bail!("Slack API error: {}", response.error.unwrap_or_default());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Slack
+    }
+
+    /// Prefers the user's webhook over posting to their channel with the bot
+    async fn deliver(&self, user: &User, notification: &Notification) -> Result<bool> {
+        let settings = &user.preferences.notifications;
+        let target = match (&settings.slack_webhook_url, &settings.slack_channel) {
+            (Some(url), _) => SlackTarget::Webhook(url.clone()),
+            (None, Some(channel)) => SlackTarget::Channel(channel.clone()),
+            (None, None) => return Ok(false),
+        };
+
+        self.send(&target, notification).await?;
+        Ok(true)
+    }
+
+    /// Webhooks belong to users, so only the bot token can be checked
+    async fn check(&self) -> Result<()> {
+        let token = match &self.bot_token {
+            Some(token) => token,
+            None => return Ok(()),
+        };
+
+        let response: SlackApiResponse = self.client
+            .post("https://slack.com/api/auth.test")
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if !response.ok {
+            anyhow::bail!("SLACK_BOT_TOKEN was rejected: {}", response.error.unwrap_or_default());
+        }
+        Ok(())
+    }
+}
+
+/// Build a Block Kit message, with a button to the notification's link if it has one
+fn message(notification: &Notification) -> Value {
+    let mut blocks = vec![
+        json!({
+            "type": "section",
+            "text": { "type": "mrkdwn", "text": format!("*{}*\n{}", notification.subject, notification.body) },
+        }),
+    ];
+
+    if let Some(link) = &notification.link {
+        blocks.push(json!({
+            "type": "actions",
+            "elements": [{
+                "type": "button",
+                "text": { "type": "plain_text", "text": "Open in review queue" },
+                "url": link,
+            }],
+        }));
+    }
+
+    json!({
+        // Shown in desktop and mobile push notifications
+        "text": notification.subject,
+        "blocks": blocks,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct SlackApiResponse {
+    ok: bool,
+    error: Option<String>,
+}
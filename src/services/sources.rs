@@ -0,0 +1,282 @@
+//! Where comments come from, and what happens to them on the way in.
+//!
+//! A [`CommentSource`] only fetches comments and posts replies. Everything
+//! else — keeping what the user did with stored comments, filter rules, the
+//! spam classifier, masking, history, notifications and auto-replies — is the
+//! [`CommentPipeline`]'s job, so a new source feeds the same inbox, generation
+//! and analytics as YouTube videos do.
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::TryStreamExt;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::db::Database;
+use crate::models::{Comment, InteractionRecord, video::CommentSyncState, InteractionType, Reply, SOURCE_KEY, TriageState, conversation::FollowUp, event::UserEvent};
+use crate::services::{conversations::{self, FollowUpChange, OwnReplies}, dry_run, events::EventBus, history, masking, mutes::MuteList, notifications::NotificationService, rules::RuleService, settings::SettingsService, spam::SpamService};
+
+/// Pages of comments, each requested only when the previous one is consumed
+pub type CommentPages<'a> = BoxStream<'a, Result<Vec<Comment>>>;
+
+/// Something comments can be fetched from and replied to on
+#[async_trait]
+pub trait CommentSource: Send + Sync {
+    /// Short name stored in each comment's metadata under [`SOURCE_KEY`], e.g. `youtube`
+    fn name(&self) -> &'static str;
+
+    /// Stream the comments on a target, e.g. a video; each comment's `video_id` is the target's ID.
+    ///
+    /// With `since`, only comments published after it are needed, so a source listing
+    /// comments newest first can stop once it reaches older ones. Returning more is fine.
+    async fn comment_pages<'a>(&'a self, user_id: &'a str, target_id: &'a str, since: Option<DateTime<Utc>>) -> Result<CommentPages<'a>>;
+
+    /// Post a reply to one of the source's comments
+    async fn post_reply(&self, user_id: &str, comment_id: &str, text: &str) -> Result<Reply>;
+}
+
+/// What a sync of one target brought in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IngestSummary {
+    /// Comments fetched, new or not
+    pub fetched: usize,
+
+    /// Comments not stored before, hidden and spam ones aside
+    pub new_comments: usize,
+}
+
+/// Takes comments from any source into storage, the same way for all of them
+pub struct CommentPipeline {
+    db: Database,
+    notifications: Arc<NotificationService>,
+    settings: Arc<SettingsService>,
+    rules: Arc<RuleService>,
+    spam: Arc<SpamService>,
+    events: Arc<EventBus>,
+}
+
+impl CommentPipeline {
+    pub fn new(
+        db: Database,
+        notifications: Arc<NotificationService>,
+        settings: Arc<SettingsService>,
+        rules: Arc<RuleService>,
+        spam: Arc<SpamService>,
+        events: Arc<EventBus>,
+    ) -> Self {
+        Self { db, notifications, settings, rules, spam, events }
+    }
+
+    /// Sync a target's comments from the source, returning how many were fetched and how many are new.
+    ///
+    /// Pages are persisted and published as they arrive, and the next page isn't
+    /// requested until the previous one is stored, so memory stays bounded by the
+    /// page size however many comments the target has. Only comments newer than the
+    /// last sync are asked for, except for a full sync every few days; the sync
+    /// state only advances once every page is stored.
+    pub async fn ingest(&self, source: &dyn CommentSource, user_id: &str, target_id: &str) -> Result<IngestSummary> {
+        // What the user did with the comments already stored, which also tells new comments apart
+        let stored = self.db.get_comment_states(target_id).await?;
+        let mutes = MuteList::load(&self.db, user_id).await?;
+        let own = OwnReplies::load(&self.db, user_id, target_id).await?;
+
+        let started_at = Utc::now();
+        let mut sync_state = self.db.get_comment_sync_state(target_id).await?.unwrap_or_else(|| CommentSyncState {
+            video_id: target_id.to_string(),
+            ..Default::default()
+        });
+        let since = sync_state.fetch_since(started_at);
+
+        let mut pages = source.comment_pages(user_id, target_id, since).await?;
+
+        let mut total = 0;
+        let mut new_total = 0;
+        while let Some(mut comments) = pages.try_next().await? {
+            sync_state.newest_published_at = sync_state.newest_published_at.max(comments.iter().map(|c| c.published_at).max());
+
+            let mut new_ids = HashSet::new();
+            for comment in &mut comments {
+                comment.metadata.entry(SOURCE_KEY.to_string()).or_insert_with(|| source.name().to_string());
+                match stored.get(&comment.comment_id) {
+                    Some(state) => {
+                        comment.replied_to = state.replied_to;
+                        comment.triage = state.triage;
+                        comment.spam_review = state.spam_review;
+                        comment.highlight = state.highlight;
+                        comment.reaction = state.reaction;
+                        comment.follow_up = state.follow_up.clone();
+                    }
+                    None => {
+                        new_ids.insert(comment.comment_id.clone());
+                    }
+                }
+            }
+
+            // Someone answering the user's reply puts the thread back in front of them
+            let mut follow_ups = Vec::new();
+            for comment in &mut comments {
+                match conversations::follow_up_change(comment, &own) {
+                    Some(FollowUpChange::New(follow_up)) => {
+                        comment.follow_up = Some(follow_up.clone());
+                        comment.replied_to = false;
+                        comment.triage = TriageState::NeedsReply;
+                        follow_ups.push((comment.comment_id.clone(), Some(follow_up)));
+                    }
+                    Some(FollowUpChange::Answered) => {
+                        comment.follow_up = None;
+                        follow_ups.push((comment.comment_id.clone(), None));
+                    }
+                    None => {}
+                }
+            }
+
+            // Apply the user's filter rules; a broken rule shouldn't stop the sync
+            let auto_replies = match self.rules.apply(user_id, &mut comments, &new_ids).await {
+                Ok(auto_replies) => auto_replies,
+                Err(e) => {
+                    error!("Error applying filter rules for {} {}: {}", source.name(), target_id, e);
+                    Vec::new()
+                }
+            };
+
+            // Quarantine what looks like spam, keeping the user's verdicts
+            if let Err(e) = self.spam.classify(user_id, &mut comments).await {
+                error!("Error classifying spam for {} {}: {}", source.name(), target_id, e);
+            }
+
+            // Rules, fingerprints and the classifier saw the original text; nothing after this point does
+            if self.settings.current().mask_sensitive_text {
+                comments.iter_mut().for_each(masking::mask_comment);
+            }
+
+            // Hidden and spam comments don't notify
+            let new_comments: Vec<Comment> = comments
+                .iter()
+                .filter(|c| new_ids.contains(&c.comment_id) && !c.is_hidden())
+                .cloned()
+                .collect();
+
+            // Save comments to database
+            self.db.save_comments(target_id, &comments).await?;
+            for (comment_id, follow_up) in &follow_ups {
+                self.store_follow_up(user_id, target_id, comment_id, follow_up.as_ref()).await?;
+            }
+
+            // Record interaction for each comment
+            for comment in &comments {
+                let interaction = InteractionRecord {
+                    id: Uuid::new_v4().to_string(),
+                    user_id: user_id.to_string(),
+                    video_id: target_id.to_string(),
+                    comment_id: comment.comment_id.clone(),
+                    reply_id: None,
+                    interaction_type: InteractionType::CommentReceived,
+                    timestamp: Utc::now(),
+                    data: HashMap::new(),
+                };
+
+                self.db.record_interaction(&interaction).await?;
+            }
+
+            self.notifications.comments_received(user_id, target_id, &new_comments).await;
+            new_total += new_comments.len();
+
+            let muted: HashSet<&str> = comments
+                .iter()
+                .filter(|c| mutes.mutes(c))
+                .map(|c| c.comment_id.as_str())
+                .collect();
+
+            let simulate = self.settings.current().dry_run;
+            for auto_reply in auto_replies {
+                let skipped = match auto_reply.skipped {
+                    Some(reason) => Some(reason),
+                    None if muted.contains(auto_reply.comment_id.as_str()) => Some(history::SKIP_MUTED),
+                    None => None,
+                };
+                if let Some(reason) = skipped {
+                    let data = HashMap::from([
+                        ("rule_id".to_string(), auto_reply.rule_id.clone()),
+                        ("reason".to_string(), reason.to_string()),
+                    ]);
+                    history::record(&self.db, user_id, target_id, &auto_reply.comment_id, InteractionType::AutoReplySkipped, data).await;
+
+                    // A hostile comment is left for the user to answer, whatever the rules say
+                    if reason == history::SKIP_HOSTILE {
+                        if let Err(e) = self.db.set_comment_triage(&auto_reply.comment_id, TriageState::NeedsReply).await {
+                            error!("Error escalating comment {}: {}", auto_reply.comment_id, e);
+                        }
+                    }
+                    continue;
+                }
+
+                if simulate {
+                    let data = HashMap::from([
+                        ("reply_text".to_string(), auto_reply.text.clone()),
+                        ("rule_id".to_string(), auto_reply.rule_id.clone()),
+                    ]);
+                    if let Err(e) = dry_run::record(&self.db, user_id, &auto_reply.comment_id, dry_run::WOULD_REPLY, data).await {
+                        error!("Error recording dry-run auto-reply to comment {}: {}", auto_reply.comment_id, e);
+                    }
+                    continue;
+                }
+
+                info!("Filter rule {} auto-replying to comment {}", auto_reply.rule_id, auto_reply.comment_id);
+                if let Err(e) = source.post_reply(user_id, &auto_reply.comment_id, &auto_reply.text).await {
+                    error!("Error auto-replying to comment {}: {}", auto_reply.comment_id, e);
+                    self.notifications.auto_reply_failed(user_id, &auto_reply.comment_id, &e.to_string()).await;
+                }
+            }
+
+            total += comments.len();
+        }
+
+        if since.is_none() {
+            sync_state.full_synced_at = Some(started_at);
+        }
+        self.db.save_comment_sync_state(&sync_state).await?;
+
+        info!(
+            "Fetched {} comments from {} {} ({})",
+            total,
+            source.name(),
+            target_id,
+            if since.is_some() { "incremental" } else { "full" }
+        );
+        self.events.publish(user_id, UserEvent::CommentsFetched {
+            video_id: target_id.to_string(),
+            comments: total,
+            new_comments: new_total,
+        });
+
+        Ok(IngestSummary { fetched: total, new_comments: new_total })
+    }
+
+    /// Store a comment's new follow-up, sending it back to the inbox, or clear an answered one
+    async fn store_follow_up(&self, user_id: &str, video_id: &str, comment_id: &str, follow_up: Option<&FollowUp>) -> Result<()> {
+        self.db.set_comment_follow_up(comment_id, follow_up).await?;
+
+        if let Some(follow_up) = follow_up {
+            info!("Reply {} follows up on reply {} to comment {}", follow_up.reply_id, follow_up.in_reply_to, comment_id);
+            self.db.mark_comment_replied(comment_id, false).await?;
+            self.db.set_comment_triage(comment_id, TriageState::NeedsReply).await?;
+
+            let data = HashMap::from([
+                ("reply_id".to_string(), follow_up.reply_id.clone()),
+                ("in_reply_to".to_string(), follow_up.in_reply_to.clone()),
+            ]);
+            history::record(&self.db, user_id, video_id, comment_id, InteractionType::FollowUpReceived, data).await;
+        }
+
+        self.events.publish(user_id, UserEvent::CommentChanged {
+            video_id: video_id.to_string(),
+            comment_id: comment_id.to_string(),
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use anyhow::Result;
+use chrono::Utc;
+use tracing::info;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::Comment;
+use crate::models::rule::RuleAction;
+use crate::models::spam::{SpamReview, SpamSettings, CLASSIFIER_REASON, SPAM_SCORE_KEY};
+use crate::services::rules::link_pattern;
+
+/// Score from which comments are quarantined until reviews tune it
+pub const DEFAULT_THRESHOLD: f32 = 0.7;
+
+/// Lowest threshold reviews can tune down to
+const MIN_THRESHOLD: f32 = 0.4;
+
+/// Highest threshold reviews can tune up to
+const MAX_THRESHOLD: f32 = 0.95;
+
+/// How far one review moves the threshold
+const THRESHOLD_STEP: f32 = 0.02;
+
+/// Phrases common in promotional and scam comments
+const SPAM_PHRASES: &[&str] = &[
+    "check out my channel", "check my channel", "sub to me", "subscribe to my", "sub4sub", "free giveaway",
+    "whatsapp", "telegram", "crypto", "bitcoin", "forex", "investment", "passive income", "dm me", "text me",
+    "promo code", "click the link", "earn $",
+];
+
+/// How spammy a comment looks, from 0 to 1, from its links, phrases, contact details and shouting
+pub fn score(comment: &Comment) -> f32 {
+    let text = comment.text.to_lowercase();
+    let mut score: f32 = 0.0;
+
+    if link_pattern().is_match(&comment.text) {
+        score += 0.35;
+    }
+
+    match SPAM_PHRASES.iter().filter(|phrase| text.contains(*phrase)).count() {
+        0 => {}
+        1 => score += 0.3,
+        _ => score += 0.45,
+    }
+
+    // A phone number
+    let longest_number = text
+        .split(|c: char| !c.is_ascii_digit() && !" -+()".contains(c))
+        .map(|run| run.chars().filter(char::is_ascii_digit).count())
+        .max();
+    if longest_number.unwrap_or(0) >= 7 {
+        score += 0.25;
+    }
+
+    // Shouting
+    let letters: Vec<char> = comment.text.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.len() >= 10 && letters.iter().filter(|c| c.is_uppercase()).count() * 10 >= letters.len() * 7 {
+        score += 0.1;
+    }
+
+    // Mostly emoji and symbols
+    let chars = comment.text.chars().filter(|c| !c.is_whitespace()).count();
+    let symbols = comment.text.chars().filter(|c| !c.is_whitespace() && !c.is_alphanumeric()).count();
+    if chars >= 10 && symbols * 10 >= chars * 3 {
+        score += 0.1;
+    }
+
+    // Bot-like names such as "CryptoKing4821"
+    if comment.author.chars().rev().take_while(char::is_ascii_digit).count() >= 3 {
+        score += 0.1;
+    }
+
+    score.min(1.0)
+}
+
+/// Quarantines comments that look like spam and learns from the user's reviews
+pub struct SpamService {
+    db: Database,
+}
+
+impl SpamService {
+    /// Create a new spam service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// A user's classifier settings, the defaults if they never reviewed a comment
+    pub async fn settings(&self, user_id: &str) -> Result<SpamSettings> {
+        Ok(self.db.get_spam_settings(user_id).await?.unwrap_or_else(|| SpamSettings::new(user_id, DEFAULT_THRESHOLD)))
+    }
+
+    /// Score comments about to be saved and quarantine those at or above the user's threshold.
+    ///
+    /// Every comment gets a `spam_score` metadata entry; quarantined ones a `spam`
+    /// entry of `classifier`. Reviewed comments keep the user's verdict: restored
+    /// ones are never flagged again, whatever the rules or the classifier say.
+    pub async fn classify(&self, user_id: &str, comments: &mut [Comment]) -> Result<()> {
+        let threshold = self.settings(user_id).await?.threshold;
+        let spam_key = RuleAction::MarkSpam.metadata_key();
+
+        for comment in comments.iter_mut() {
+            match comment.spam_review {
+                Some(SpamReview::Restored) => {
+                    comment.metadata.remove(spam_key);
+                }
+                Some(SpamReview::Confirmed) => {
+                    comment.metadata.entry(spam_key.to_string()).or_insert_with(|| "review".to_string());
+                }
+                None => {
+                    let score = score(comment);
+                    comment.metadata.insert(SPAM_SCORE_KEY.to_string(), format!("{:.2}", score));
+                    if score >= threshold {
+                        comment.metadata.entry(spam_key.to_string()).or_insert_with(|| CLASSIFIER_REASON.to_string());
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The user's comments marked as spam by the classifier, filter rules or duplicate detection and not reviewed yet
+    pub async fn quarantine(&self, user_id: &str) -> Result<Vec<Comment>> {
+        let video_ids: Vec<String> = self
+            .db
+            .get_user_videos(user_id)
+            .await?
+            .into_iter()
+            .map(|v| v.video_id)
+            .collect();
+
+        self.db.get_quarantined_comments(&video_ids).await
+    }
+
+    /// A comment of the user waiting for review; other users' comments are reported as missing
+    pub async fn quarantined(&self, user_id: &str, comment_id: &str) -> Result<Comment> {
+        let comment = self.db.get_comment(comment_id).await?
+            .ok_or_else(|| AppError::NotFound(format!("Comment {}", comment_id)))?;
+        match self.db.get_video(&comment.video_id).await? {
+            Some(video) if video.user_id == user_id => {}
+            _ => return Err(AppError::NotFound(format!("Comment {}", comment_id)).into()),
+        }
+
+        if comment.spam_review.is_some() || !comment.metadata.contains_key(RuleAction::MarkSpam.metadata_key()) {
+            return Err(AppError::Conflict(format!("Comment {} is not in quarantine", comment_id)).into());
+        }
+        Ok(comment)
+    }
+
+    /// Put a quarantined comment back in the inbox as not spam.
+    ///
+    /// If the classifier flagged it, the threshold is raised above its score so
+    /// comments like it are let through.
+    pub async fn restore(&self, user_id: &str, comment_id: &str) -> Result<Comment> {
+        let mut comment = self.quarantined(user_id, comment_id).await?;
+        self.db.set_spam_review(comment_id, SpamReview::Restored).await?;
+
+        let mut settings = self.settings(user_id).await?;
+        settings.restored += 1;
+        if is_classifier_flag(&comment) {
+            let score = comment_score(&comment);
+            settings.threshold = (settings.threshold.max(score) + THRESHOLD_STEP).min(MAX_THRESHOLD);
+        }
+        self.save_settings(settings).await?;
+
+        comment.metadata.remove(RuleAction::MarkSpam.metadata_key());
+        comment.spam_review = Some(SpamReview::Restored);
+        Ok(comment)
+    }
+
+    /// Record that a quarantined comment is spam, once it was rejected on YouTube.
+    ///
+    /// If a filter rule or duplicate detection caught it but the classifier scored
+    /// it below the threshold, the threshold is lowered so comments like it are caught.
+    pub async fn confirm(&self, user_id: &str, mut comment: Comment) -> Result<Comment> {
+        self.db.set_spam_review(&comment.comment_id, SpamReview::Confirmed).await?;
+
+        let mut settings = self.settings(user_id).await?;
+        settings.confirmed += 1;
+        if !is_classifier_flag(&comment) && comment_score(&comment) < settings.threshold {
+            settings.threshold = (settings.threshold - THRESHOLD_STEP).max(MIN_THRESHOLD);
+        }
+        self.save_settings(settings).await?;
+
+        comment.spam_review = Some(SpamReview::Confirmed);
+        Ok(comment)
+    }
+
+    async fn save_settings(&self, mut settings: SpamSettings) -> Result<()> {
+        info!("Spam threshold of user {} is now {:.2}", settings.user_id, settings.threshold);
+        settings.updated_at = Utc::now();
+        self.db.save_spam_settings(&settings).await
+    }
+}
+
+/// Whether the classifier, rather than a rule or duplicate detection, flagged the comment
+fn is_classifier_flag(comment: &Comment) -> bool {
+    comment.metadata.get(RuleAction::MarkSpam.metadata_key()).map(String::as_str) == Some(CLASSIFIER_REASON)
+}
+
+/// The stored classifier score of a comment, scored now if it has none
+fn comment_score(comment: &Comment) -> f32 {
+    comment
+        .metadata
+        .get(SPAM_SCORE_KEY)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| score(comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CommentBuilder;
+
+    fn comment(author: &str, text: &str) -> Comment {
+        CommentBuilder::new("v", "c").author(author).text(text).build()
+    }
+
+    #[test]
+    fn test_score() {
+        assert!(score(&comment("Viewer", "Great explanation of lifetimes, thanks!")) < 0.2);
+        assert!(score(&comment("CryptoKing4821", "Earn $500 a day with crypto, WhatsApp +1 555 123 4567")) >= DEFAULT_THRESHOLD);
+        assert!(score(&comment("Viewer", "Check out my channel bit.ly/xyz")) > 0.6);
+    }
+}
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use chrono::Utc;
+use std::env;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::db::Database;
+use crate::models::stream::{StreamComment, StreamCursor, StreamEvent, StreamRecord, StreamReply};
+use crate::models::{InteractionRecord, InteractionType};
+#[cfg(feature = "kafka")]
+use crate::services::kafka::KafkaPublisher;
+#[cfg(feature = "nats")]
+use crate::services::nats::NatsPublisher;
+use crate::utils::cache::TtlCache;
+
+/// Subjects (NATS) or topics (Kafka) start with this, unless `STREAM_SUBJECT_PREFIX` is set
+const DEFAULT_SUBJECT_PREFIX: &str = "youtube_commenter";
+
+/// How often the sink looks for newly recorded interactions
+const PUBLISH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Most interactions published per batch
+const BATCH_SIZE: usize = 500;
+
+/// Comments remembered as published unchanged, so syncing them again doesn't republish them
+const PUBLISHED_COMMENTS_CAPACITY: usize = 100_000;
+
+/// How long a comment is remembered as published; an unchanged comment is republished after this
+const PUBLISHED_COMMENTS_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+/// A message broker stream events are published to
+#[async_trait]
+pub trait StreamPublisher: Send + Sync {
+    /// Name of the broker, for logs
+    fn name(&self) -> &'static str;
+
+    /// Publish a message to a subject or topic; the key keeps one user's messages in order
+    async fn publish(&self, subject: &str, key: &str, payload: Vec<u8>) -> Result<()>;
+
+    /// Wait until the messages published so far are accepted by the broker
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Publishes normalized comment, reply and interaction events to NATS or Kafka,
+/// for teams feeding their own data warehouse or ML pipelines.
+///
+/// Follows the interaction history, which every sync and reply is recorded in,
+/// and keeps how far it got in the database so nothing recorded while the
+/// broker is unreachable or the server is down is lost.
+pub struct StreamSink {
+    db: Database,
+    publisher: Box<dyn StreamPublisher>,
+    prefix: String,
+    published_comments: TtlCache<String, String>,
+}
+
+impl StreamSink {
+    pub fn new(db: Database, publisher: Box<dyn StreamPublisher>, prefix: &str) -> Self {
+        Self {
+            db,
+            publisher,
+            prefix: prefix.trim_end_matches('.').to_string(),
+            published_comments: TtlCache::new(PUBLISHED_COMMENTS_CAPACITY, PUBLISHED_COMMENTS_TTL),
+        }
+    }
+
+    /// Create the sink `STREAM_SINK` selects (`nats` or `kafka`), connecting to its broker.
+    ///
+    /// Returns `None` if `STREAM_SINK` is unset. Only the brokers whose cargo
+    /// features are enabled are available.
+    #[cfg_attr(not(any(feature = "nats", feature = "kafka")), allow(unreachable_code, unused_variables))]
+    pub async fn from_env(db: Database) -> Result<Option<Self>> {
+        let sink = match env::var("STREAM_SINK") {
+            Ok(sink) if !sink.is_empty() => sink,
+            _ => return Ok(None),
+        };
+        let prefix = env::var("STREAM_SUBJECT_PREFIX").unwrap_or_else(|_| DEFAULT_SUBJECT_PREFIX.to_string());
+
+        let publisher: Box<dyn StreamPublisher> = match sink.as_str() {
+            #[cfg(feature = "nats")]
+            "nats" => Box::new(NatsPublisher::from_env().await?),
+            #[cfg(feature = "kafka")]
+            "kafka" => Box::new(KafkaPublisher::from_env()?),
+            #[cfg(not(feature = "nats"))]
+            "nats" => anyhow::bail!("STREAM_SINK is nats, but the server was built without the nats feature"),
+            #[cfg(not(feature = "kafka"))]
+            "kafka" => anyhow::bail!("STREAM_SINK is kafka, but the server was built without the kafka feature"),
+            other => anyhow::bail!("STREAM_SINK must be nats or kafka, not {}", other),
+        };
+
+        info!("Publishing comment events to {} under {}", publisher.name(), prefix);
+        Ok(Some(Self::new(db, publisher, &prefix)))
+    }
+
+    /// Publish the interactions recorded since the last batch, with the comments
+    /// and replies they are about.
+    ///
+    /// Returns the number of events published. The cursor only moves past
+    /// interactions whose events were all accepted, so a failed batch is retried.
+    pub async fn publish_pending(&self) -> Result<usize> {
+        let cursor = match self.db.get_stream_cursor().await? {
+            Some(cursor) => cursor,
+            None => {
+                // Start from now rather than publishing the whole history
+                let cursor = StreamCursor { published_until: Utc::now() };
+                self.db.save_stream_cursor(&cursor).await?;
+                cursor
+            }
+        };
+
+        let interactions = self.db.get_interactions_after(cursor.published_until, BATCH_SIZE).await?;
+        let last = match interactions.last() {
+            Some(last) => last.timestamp,
+            None => return Ok(0),
+        };
+
+        let mut published = 0;
+        let mut comments = Vec::new();
+        for interaction in &interactions {
+            for event in self.events(interaction).await? {
+                let payload = serde_json::to_vec(&event)?;
+                let subject = format!("{}.{}", self.prefix, event.kind());
+                self.publisher.publish(&subject, &event.user_id, payload).await
+                    .with_context(|| format!("Failed to publish to {}", subject))?;
+
+                if let StreamRecord::Comment(comment) = &event.record {
+                    comments.push((comment.comment_id.clone(), comment.text.clone()));
+                }
+                published += 1;
+            }
+        }
+        self.publisher.flush().await?;
+
+        // Only remember comments as published once the broker has them
+        for (comment_id, text) in comments {
+            self.published_comments.insert(comment_id, text);
+        }
+        self.db.save_stream_cursor(&StreamCursor { published_until: last }).await?;
+        Ok(published)
+    }
+
+    /// The events an interaction is published as: itself, after the comment it
+    /// received or the reply it posted
+    async fn events(&self, interaction: &InteractionRecord) -> Result<Vec<StreamEvent>> {
+        let event = |suffix: &str, record: StreamRecord| StreamEvent {
+            event_id: format!("{}.{}", interaction.id, suffix),
+            user_id: interaction.user_id.clone(),
+            occurred_at: interaction.timestamp,
+            record,
+        };
+
+        let mut events = Vec::new();
+        match interaction.interaction_type {
+            InteractionType::CommentReceived => {
+                if let Some(comment) = self.db.get_comment(&interaction.comment_id).await? {
+                    let comment = StreamComment::from(&comment);
+                    if self.published_comments.get(&comment.comment_id).as_ref() != Some(&comment.text) {
+                        events.push(event("comment", StreamRecord::Comment(comment)));
+                    }
+                }
+            }
+            InteractionType::ReplyPosted => {
+                if let Some(reply) = self.posted_reply(interaction).await? {
+                    events.push(event("reply", StreamRecord::Reply(reply)));
+                }
+            }
+            _ => {}
+        }
+        events.push(event("interaction", StreamRecord::Interaction(interaction.clone())));
+        Ok(events)
+    }
+
+    /// The reply an interaction posted, as stored with its comment or else as recorded
+    async fn posted_reply(&self, interaction: &InteractionRecord) -> Result<Option<StreamReply>> {
+        let reply_id = match &interaction.reply_id {
+            Some(reply_id) if !reply_id.is_empty() => reply_id,
+            _ => return Ok(None),
+        };
+
+        let comment = self.db.get_comment(&interaction.comment_id).await?;
+        if let Some(comment) = &comment {
+            if let Some(reply) = comment.replies.iter().find(|reply| reply.reply_id == *reply_id) {
+                return Ok(Some(StreamReply::new(reply, &comment.video_id)));
+            }
+        }
+
+        let text = match interaction.data.get("reply_text") {
+            Some(text) => text.clone(),
+            None => return Ok(None),
+        };
+        let ai_model = interaction.data.get("ai_model").cloned();
+        Ok(Some(StreamReply {
+            reply_id: reply_id.clone(),
+            comment_id: interaction.comment_id.clone(),
+            video_id: comment.map(|comment| comment.video_id),
+            author_channel_id: interaction.user_id.clone(),
+            text,
+            ai_generated: ai_model.is_some(),
+            ai_model,
+            published_at: interaction.timestamp,
+        }))
+    }
+
+    /// Spawn a background task publishing new interactions every few seconds
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PUBLISH_INTERVAL);
+
+            loop {
+                interval.tick().await;
+
+                // Catch up in full batches before waiting again
+                loop {
+                    match self.publish_pending().await {
+                        Ok(count) if count >= BATCH_SIZE => continue,
+                        Ok(_) => break,
+                        Err(e) => {
+                            error!("Error publishing comment events to {}: {:#}", self.publisher.name(), e);
+                            break;
+                        }
+                    }
+                }
+            }
+        })
+    }
+}
+
@@ -0,0 +1,266 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::env;
+use tracing::warn;
+
+use crate::models::auth::User;
+use crate::models::notification::{Notification, NotificationChannel};
+use crate::services::notifications::Notifier;
+
+/// Quick action attached to a comment message as an inline button
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelegramAction {
+    /// Generate a reply draft
+    Generate,
+
+    /// Post the pending draft
+    ApprovePost,
+
+    /// Post the pending draft to a hostile comment, after being asked to confirm
+    ConfirmPost,
+
+    /// Discard the pending drafts and stop asking
+    Ignore,
+}
+
+impl TelegramAction {
+    /// Prefix used in the button's callback data
+    fn prefix(&self) -> &'static str {
+        match self {
+            TelegramAction::Generate => "gen",
+            TelegramAction::ApprovePost => "post",
+            TelegramAction::ConfirmPost => "confirm",
+            TelegramAction::Ignore => "ignore",
+        }
+    }
+
+    /// The button label
+    fn label(&self) -> &'static str {
+        match self {
+            TelegramAction::Generate => "Generate",
+            TelegramAction::ApprovePost => "Approve & Post",
+            TelegramAction::ConfirmPost => "Post anyway",
+            TelegramAction::Ignore => "Ignore",
+        }
+    }
+
+    /// Callback data for this action on a comment
+    pub fn callback_data(&self, comment_id: &str) -> String {
+        format!("{}:{}", self.prefix(), comment_id)
+    }
+
+    /// Parse callback data back into an action and comment ID
+    pub fn parse(data: &str) -> Option<(Self, &str)> {
+        let (prefix, comment_id) = data.split_once(':')?;
+        let action = match prefix {
+            "gen" => TelegramAction::Generate,
+            "post" => TelegramAction::ApprovePost,
+            "confirm" => TelegramAction::ConfirmPost,
+            "ignore" => TelegramAction::Ignore,
+            _ => return None,
+        };
+        (!comment_id.is_empty()).then_some((action, comment_id))
+    }
+}
+
+/// Sends notifications through a Telegram bot
+pub struct TelegramNotifier {
+    client: Client,
+    bot_token: String,
+    webhook_secret: Option<String>,
+}
+
+impl TelegramNotifier {
+    /// Create a Telegram notifier from `TELEGRAM_BOT_TOKEN` and `TELEGRAM_WEBHOOK_SECRET`.
+    ///
+    /// Returns `None` if no bot token is set, which disables Telegram. Without a
+    /// secret the bot still sends notifications, but the webhook refuses every request.
+    pub fn from_env(client: Client) -> Option<Self> {
+        let bot_token = env::var("TELEGRAM_BOT_TOKEN").ok()?;
+        let webhook_secret = env::var("TELEGRAM_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty());
+        if webhook_secret.is_none() {
+            warn!("TELEGRAM_WEBHOOK_SECRET is not set, so the Telegram webhook refuses every request");
+        }
+        Some(Self { client, bot_token, webhook_secret })
+    }
+
+    /// Whether a webhook request carries the configured secret token; never without a secret
+    pub fn verify_webhook(&self, secret_header: Option<&str>) -> bool {
+        match (&self.webhook_secret, secret_header) {
+            (Some(secret), Some(header)) => constant_time_eq(secret.as_bytes(), header.as_bytes()),
+            _ => false,
+        }
+    }
+
+    /// Send a notification to a chat, with quick actions if it is about a comment
+    pub async fn send(&self, chat_id: i64, notification: &Notification) -> Result<()> {
+        let actions: &[TelegramAction] = match &notification.comment_id {
+            Some(_) => &[TelegramAction::Generate, TelegramAction::Ignore],
+            None => &[],
+        };
+
+        self.send_message(chat_id, &format_text(notification), notification.comment_id.as_deref(), actions)
+            .await
+    }
+
+    /// Send a text message, optionally with action buttons for a comment
+    pub async fn send_message(
+        &self,
+        chat_id: i64,
+        text: &str,
+        comment_id: Option<&str>,
+        actions: &[TelegramAction],
+    ) -> Result<()> {
+        let mut body = json!({
+            "chat_id": chat_id,
+            "text": text,
+            "disable_web_page_preview": true,
+        });
+
+        if let Some(comment_id) = comment_id {
+            if !actions.is_empty() {
+                let buttons: Vec<Value> = actions
+                    .iter()
+                    .map(|action| json!({ "text": action.label(), "callback_data": action.callback_data(comment_id) }))
+                    .collect();
+                body["reply_markup"] = json!({ "inline_keyboard": [buttons] });
+            }
+        }
+
+        self.call("sendMessage", &body).await
+    }
+
+    /// Acknowledge a button press, showing a short toast to the user
+    pub async fn answer_callback(&self, callback_query_id: &str, text: &str) -> Result<()> {
+        self.call(
+            "answerCallbackQuery",
+            &json!({ "callback_query_id": callback_query_id, "text": text }),
+        )
+        .await
+    }
+
+    /// Call a Bot API method
+    async fn call(&self, method: &str, body: &Value) -> Result<()> {
+        let response: TelegramApiResponse = self.client
+            .post(format!("https://api.telegram.org/bot{}/{}", self.bot_token, method))
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("Failed to call Telegram {}", method))?
+            .json()
+            .await?;
+
+        if !response.ok {
+            anyhow::bail!("Telegram API error: {}", response.description.unwrap_or_default());
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn channel(&self) -> NotificationChannel {
+        NotificationChannel::Telegram
+    }
+
+    async fn deliver(&self, user: &User, notification: &Notification) -> Result<bool> {
+        match user.preferences.notifications.telegram_chat_id {
+            Some(chat_id) => {
+                self.send(chat_id, notification).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn check(&self) -> Result<()> {
+        self.call("getMe", &json!({})).await
+    }
+}
+
+/// Plain-text message body for a notification
+fn format_text(notification: &Notification) -> String {
+    let mut text = format!("{}\n\n{}", notification.subject, notification.body);
+    if let Some(link) = &notification.link {
+        text.push_str("\n\n");
+        text.push_str(link);
+    }
+    text
+}
+
+#[derive(Debug, Deserialize)]
+struct TelegramApiResponse {
+    ok: bool,
+    description: Option<String>,
+}
+
+/// An incoming Telegram update, limited to the parts the bot handles
+#[derive(Debug, Deserialize)]
+pub struct TelegramUpdate {
+    pub callback_query: Option<TelegramCallbackQuery>,
+}
+
+/// A press on an inline button
+#[derive(Debug, Deserialize)]
+pub struct TelegramCallbackQuery {
+    pub id: String,
+    pub message: Option<TelegramMessage>,
+    pub data: Option<String>,
+}
+
+/// The message a button was attached to
+#[derive(Debug, Deserialize)]
+pub struct TelegramMessage {
+    pub chat: TelegramChat,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramChat {
+    pub id: i64,
+}
+
+/// Compare two byte strings without returning early at the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notifier(webhook_secret: Option<&str>) -> TelegramNotifier {
+        TelegramNotifier {
+            client: Client::new(),
+            bot_token: "token".to_string(),
+            webhook_secret: webhook_secret.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_verify_webhook() {
+        let telegram = notifier(Some("s3cret"));
+        assert!(telegram.verify_webhook(Some("s3cret")));
+        assert!(!telegram.verify_webhook(Some("s3cre")));
+        assert!(!telegram.verify_webhook(Some("s3creT")));
+        assert!(!telegram.verify_webhook(None));
+
+        // Without a secret, forged callbacks can't be told apart, so nothing gets through
+        assert!(!notifier(None).verify_webhook(None));
+        assert!(!notifier(None).verify_webhook(Some("")));
+    }
+
+    #[test]
+    fn test_callback_data_round_trip() {
+        for action in [TelegramAction::Generate, TelegramAction::ApprovePost, TelegramAction::ConfirmPost, TelegramAction::Ignore] {
+            let data = action.callback_data("Ugx123");
+            assert_eq!(TelegramAction::parse(&data), Some((action, "Ugx123")));
+        }
+
+        assert_eq!(TelegramAction::parse("gen:"), None);
+        assert_eq!(TelegramAction::parse("delete:Ugx123"), None);
+    }
+}
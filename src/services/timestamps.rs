@@ -0,0 +1,77 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::models::video::VideoTimestamp;
+
+/// Most timestamps kept per comment, so timestamp lists don't bloat the record
+const MAX_TIMESTAMPS: usize = 10;
+
+/// `mm:ss` and `h:mm:ss` timestamps
+fn timestamp_pattern() -> &'static Regex {
+    static TIMESTAMP: OnceLock<Regex> = OnceLock::new();
+    TIMESTAMP.get_or_init(|| Regex::new(r"(?:(\d{1,2}):)?(\d{1,3}):(\d{2})").unwrap())
+}
+
+/// Link opening a video at an offset
+pub fn deep_link(video_id: &str, seconds: u32) -> String {
+    format!("https://www.youtube.com/watch?v={}&t={}s", video_id, seconds)
+}
+
+/// Find the video timestamps mentioned in a comment, without repeats.
+///
+/// Seconds must be below 60, and so must minutes when hours are given;
+/// anything else (e.g. "1:75") is not a timestamp, and neither are matches
+/// inside longer numbers like versions or ratios ("1.2:30", "10:20:30:40").
+pub fn parse(text: &str, video_id: &str) -> Vec<VideoTimestamp> {
+    let mut timestamps: Vec<VideoTimestamp> = Vec::new();
+
+    for captures in timestamp_pattern().captures_iter(text) {
+        let matched = captures.get(0).unwrap();
+        let before = text[..matched.start()].chars().next_back();
+        let after = text[matched.end()..].chars().next();
+        if before.is_some_and(|c| c.is_ascii_digit() || c == ':' || c == '.') || after.is_some_and(|c| c.is_ascii_digit() || c == ':') {
+            continue;
+        }
+
+        let hours: u32 = captures.get(1).map_or(0, |h| h.as_str().parse().unwrap_or(0));
+        let minutes: u32 = captures[2].parse().unwrap_or(0);
+        let seconds: u32 = captures[3].parse().unwrap_or(0);
+        if seconds >= 60 || (captures.get(1).is_some() && minutes >= 60) {
+            continue;
+        }
+
+        let offset = hours * 3600 + minutes * 60 + seconds;
+        if timestamps.iter().any(|t| t.seconds == offset) {
+            continue;
+        }
+
+        timestamps.push(VideoTimestamp {
+            seconds: offset,
+            label: matched.as_str().to_string(),
+            url: deep_link(video_id, offset),
+        });
+        if timestamps.len() == MAX_TIMESTAMPS {
+            break;
+        }
+    }
+
+    timestamps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let found = parse("At 4:20 the drone flips, and again at 1:02:03 (and 4:20!)", "abc");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].seconds, 260);
+        assert_eq!(found[0].label, "4:20");
+        assert_eq!(found[0].url, "https://www.youtube.com/watch?v=abc&t=260s");
+        assert_eq!(found[1].seconds, 3723);
+
+        assert!(parse("Score was 3:75, version 1.2:30, build 10:20:30:40", "abc").is_empty());
+        assert_eq!(parse("0:05", "abc")[0].seconds, 5);
+    }
+}
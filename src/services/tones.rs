@@ -0,0 +1,121 @@
+use anyhow::Result;
+use chrono::Utc;
+use tracing::info;
+
+use crate::db::Database;
+use crate::error::AppError;
+use crate::models::tone::TonePreset;
+use crate::services::rules::MAX_REPLY_LENGTH;
+
+/// The presets everyone can use, as (ID, name, instructions)
+const BUILTIN_TONES: [(&str, &str, &str); 4] = [
+    ("professional", "Professional", "Maintain a professional and informative tone. Be helpful and knowledgeable while remaining approachable."),
+    ("friendly", "Friendly", "Be warm, casual, and conversational. Use a friendly tone as if chatting with someone you know well."),
+    ("enthusiastic", "Enthusiastic", "Be energetic and excited in your response. Show enthusiasm and appreciation for the commenter."),
+    ("helpful", "Helpful", "Focus on being as helpful as possible. Provide useful information and address any questions thoroughly."),
+];
+
+/// Most tones a user can create
+pub const MAX_TONES_PER_USER: usize = 50;
+
+/// Longest tone instructions accepted
+const MAX_INSTRUCTIONS_LENGTH: usize = 2_000;
+
+/// Most example replies a tone can have
+const MAX_EXAMPLES: usize = 5;
+
+/// The built-in presets, as they are seeded
+pub fn builtin_tones() -> Vec<TonePreset> {
+    let now = Utc::now();
+    BUILTIN_TONES
+        .iter()
+        .map(|(tone_id, name, instructions)| TonePreset {
+            tone_id: tone_id.to_string(),
+            user_id: None,
+            name: name.to_string(),
+            instructions: instructions.to_string(),
+            examples: Vec::new(),
+            temperature: None,
+            created_at: now,
+            updated_at: now,
+        })
+        .collect()
+}
+
+/// Check that a tone can be stored: it has a name and instructions, a few
+/// examples of reply length and a temperature the models accept
+pub fn validate(tone: &TonePreset) -> Result<()> {
+    if tone.name.trim().is_empty() {
+        return Err(AppError::Validation("Tone name must not be empty".to_string()).into());
+    }
+
+    if tone.instructions.trim().is_empty() {
+        return Err(AppError::Validation("Tone instructions must not be empty".to_string()).into());
+    }
+
+    if tone.instructions.chars().count() > MAX_INSTRUCTIONS_LENGTH {
+        return Err(AppError::Validation(format!("Tone instructions must be at most {} characters", MAX_INSTRUCTIONS_LENGTH)).into());
+    }
+
+    if tone.examples.len() > MAX_EXAMPLES {
+        return Err(AppError::Validation(format!("A tone can have at most {} examples", MAX_EXAMPLES)).into());
+    }
+
+    if tone.examples.iter().any(|example| example.trim().is_empty() || example.chars().count() > MAX_REPLY_LENGTH) {
+        return Err(AppError::Validation(format!("Examples must be between 1 and {} characters", MAX_REPLY_LENGTH)).into());
+    }
+
+    if let Some(temperature) = tone.temperature {
+        if !(0.0..=2.0).contains(&temperature) {
+            return Err(AppError::Validation("temperature must be between 0 and 2".to_string()).into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Looks up the tones users write replies in
+pub struct ToneService {
+    db: Database,
+}
+
+impl ToneService {
+    /// Create a new tone service
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Store the built-in presets, updating those whose text changed and keeping when they were created
+    pub async fn seed_builtin(&self) -> Result<()> {
+        for mut tone in builtin_tones() {
+            if let Some(stored) = self.db.get_tone_preset(&tone.tone_id).await? {
+                if (&stored.name, &stored.instructions) == (&tone.name, &tone.instructions) {
+                    continue;
+                }
+                tone.created_at = stored.created_at;
+            }
+            self.db.save_tone_preset(&tone).await?;
+        }
+
+        info!("Seeded the built-in reply tones");
+        Ok(())
+    }
+
+    /// A tone the user can write in; other users' tones are reported as missing
+    pub async fn get(&self, user_id: &str, tone_id: &str) -> Result<TonePreset> {
+        self.db
+            .get_tone_preset(tone_id)
+            .await?
+            .filter(|tone| tone.is_visible_to(user_id))
+            .ok_or_else(|| AppError::NotFound(format!("Tone {}", tone_id)).into())
+    }
+
+    /// A tone the user created, for changing it; the built-in presets can't be changed
+    pub async fn get_own(&self, user_id: &str, tone_id: &str) -> Result<TonePreset> {
+        let tone = self.get(user_id, tone_id).await?;
+        if tone.is_builtin() {
+            return Err(AppError::Forbidden.into());
+        }
+        Ok(tone)
+    }
+}
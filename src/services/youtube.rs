@@ -1,140 +1,215 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use reqwest::Client;
+use futures::{Stream, StreamExt, TryStreamExt};
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
-use std::{env, collections::HashMap, time::Duration};
-use tokio::time;
+use std::{env, collections::{HashMap, HashSet}, sync::Arc, time::Duration};
 use tracing::{error, info, warn};
 use uuid::Uuid;
 
 use crate::db::Database;
-use crate::models::{Comment, Reply, InteractionRecord, InteractionType};
-use crate::services::auth::AuthService;
+use crate::error::AppError;
+use crate::models::{Comment, Reply, event::UserEvent, moderation::ModerationStatus, InteractionRecord, InteractionType, TriageState, duplicate::TEXT_HASH_KEY, video::{parse_duration, Video, VideoFormat, MonitorSettings, ReplyDefaults, SHORT_KEY}};
+use crate::services::{auth::AuthService, duplicates, events::EventBus, mutes::MuteList, notifications::NotificationService, quota::{self, QuotaTracker}, rules::RuleService, sentiment, sources::{CommentPages, CommentPipeline, CommentSource}, spam::SpamService, masking, settings::SettingsService, timestamps};
+use crate::utils::cache::TtlCache;
+use crate::utils::rate_limit::{RateLimitState, RateLimiter};
+use crate::utils::upstream::{Attempts, Upstream};
 
-/// YouTube service for interacting with the YouTube API
-pub struct YouTubeService {
-    db: Database,
-    client: Client,
-    auth_service: AuthService,
-}
+/// Most users whose channel ID and video list are cached
+const CHANNEL_CACHE_CAPACITY: usize = 1000;
 
-impl YouTubeService {
-    /// Create a new YouTube service
-    pub fn new(db: Database, auth_service: AuthService) -> Self {
-        let client = Client::new();
-        Self { db, client, auth_service }
-    }
+/// How long a user's channel ID is cached; it never changes for an account
+const CHANNEL_ID_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
 
-    /// Fetch comments for a YouTube video
-    pub async fn fetch_comments(&self, user_id: &str, video_id: &str) -> Result<Vec<Comment>> {
-        info!("Fetching comments for video: {}", video_id);
+/// How long a user's video list is cached, so repeated page loads don't repeat 100-unit searches
+const VIDEO_LIST_CACHE_TTL: Duration = Duration::from_secs(5 * 60);
 
-        // Get a valid access token
-        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+/// YouTube API requests per second across all users, unless `YOUTUBE_MAX_REQUESTS_PER_SEC` is set
+const DEFAULT_MAX_REQUESTS_PER_SEC: u32 = 10;
 
-        // Fetch comment threads
-        let comment_threads = self.fetch_comment_threads(video_id, &access_token).await?;
+/// Videos the monitor syncs at once, unless `MONITOR_SYNC_CONCURRENCY` is set
+const DEFAULT_SYNC_CONCURRENCY: usize = 4;
 
-        // Convert to our Comment model
-        let mut comments = Vec::new();
+/// Comments published this recently are refreshed between syncs, as they are the ones still being edited and liked
+const REFRESH_WINDOW: chrono::Duration = chrono::Duration::days(7);
 
-        for thread in comment_threads {
-            let comment_id = thread.id;
-            let snippet = thread.snippet.top_level_comment.snippet;
+/// Most comments refreshed per user and pass, newest first
+const MAX_REFRESHED_COMMENTS: usize = 200;
 
-            // Fetch replies if there are any
-            let replies = if thread.snippet.total_reply_count > 0 {
-                self.fetch_replies(&comment_id, &access_token).await?
-            } else {
-                Vec::new()
-            };
+/// Comment IDs per `comments.list` request, the API's maximum
+const REFRESH_BATCH_SIZE: usize = 50;
 
-            comments.push(Comment {
-                video_id: video_id.to_string(),
-                comment_id,
-                author: snippet.author_display_name,
-                author_channel_id: snippet.author_channel_id.value,
-                text: snippet.text_display,
-                like_count: snippet.like_count,
-                published_at: snippet.published_at,
-                replies,
-                replied_to: false, // Will be updated from database
-                metadata: HashMap::new(),
-            });
-        }
+/// Quota units left for other work before refreshing is skipped for the day
+const REFRESH_QUOTA_RESERVE: u64 = 2000;
 
-        info!("Fetched {} comments with replies", comments.len());
+/// Most threads the user answered that an incremental sync checks for new replies, newest first
+const MAX_ANSWERED_THREADS: usize = 200;
 
-        // Update replied_to status from database
-        for comment in &mut comments {
-            if let Some(db_comment) = self.db.get_comment(&comment.comment_id).await? {
-                comment.replied_to = db_comment.replied_to;
-            }
-        }
+/// YouTube Data API base URL, unless `YOUTUBE_API_BASE_URL` is set (e.g. to a stub for load tests)
+const DEFAULT_API_BASE_URL: &str = "https://www.googleapis.com/youtube/v3";
 
-        // Save comments to database
-        self.db.save_comments(video_id, &comments).await?;
+/// The YouTube operations handlers rely on, so tests can swap in a fake
+#[async_trait]
+pub trait YouTubeApi: Send + Sync {
+    /// Sync a video's comments and return every stored comment
+    async fn fetch_comments(&self, user_id: &str, video_id: &str) -> Result<Vec<Comment>>;
 
-        // Record interaction for each comment
-        for comment in &comments {
-            let interaction = InteractionRecord {
-                id: Uuid::new_v4().to_string(),
-                user_id: user_id.to_string(),
-                video_id: video_id.to_string(),
-                comment_id: comment.comment_id.clone(),
-                reply_id: None,
-                interaction_type: InteractionType::CommentReceived,
-                timestamp: Utc::now(),
-                data: HashMap::new(),
-            };
+    /// Sync a video's comments, returning how many were fetched
+    async fn sync_comments(&self, user_id: &str, video_id: &str) -> Result<usize>;
 
-            self.db.record_interaction(&interaction).await?;
-        }
+    /// Get all replies in a thread, or `None` if the comment is unknown
+    async fn get_thread_replies(&self, user_id: &str, comment_id: &str) -> Result<Option<Vec<Reply>>>;
 
-        Ok(comments)
-    }
+    /// Fetch the missing replies of a video's threads, returning the number of threads completed
+    async fn backfill_replies(&self, user_id: &str, video_id: &str) -> Result<usize>;
 
-    /// Fetch comment threads from YouTube API
-    async fn fetch_comment_threads(
-        &self,
-        video_id: &str,
-        access_token: &str,
-    ) -> Result<Vec<YouTubeCommentThread>> {
-        let mut all_threads = Vec::new();
-        let mut page_token = None;
+    /// Post a reply to a comment
+    async fn post_reply(&self, user_id: &str, comment_id: &str, text: &str) -> Result<Reply>;
 
-        loop {
-            let url = format!(
-                "https://www.googleapis.com/youtube/v3/commentThreads?part=snippet&videoId={}&maxResults=100{}",
-                video_id,
-                page_token.map_or(String::new(), |token| format!("&pageToken={}", token))
-            );
+    /// Replace the text of one of the user's posted replies
+    async fn update_reply(&self, user_id: &str, reply_id: &str, parent_id: &str, text: &str) -> Result<Reply>;
 
-            let response = self.client
-                .get(&url)
-                .header("Authorization", format!("Bearer {}", access_token))
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_text = response.text().await?;
-                error!("YouTube API error: {}", error_text);
-                anyhow::bail!("YouTube API error: {}", error_text);
-            }
+    /// Get the videos on the user's channel
+    async fn get_channel_videos(&self, user_id: &str) -> Result<Vec<YouTubeVideo>>;
 
-            let response_data: YouTubeCommentThreadResponse = response.json().await?;
+    /// Reject a comment as spam so it is no longer shown, optionally banning its author from the channel
+    async fn reject_comment(&self, user_id: &str, comment_id: &str, ban_author: bool) -> Result<()>;
 
-            all_threads.extend(response_data.items);
+    /// Hold, publish or reject a comment; only a rejection can ban the author from the channel
+    async fn set_moderation_status(&self, user_id: &str, comment_id: &str, status: ModerationStatus, ban_author: bool) -> Result<()>;
 
-            // Check if there are more pages
-            if let Some(token) = response_data.next_page_token {
-                page_token = Some(token);
-            } else {
-                break;
-            }
+    /// Report a comment to YouTube as spam
+    async fn mark_as_spam(&self, user_id: &str, comment_id: &str) -> Result<()>;
+
+    /// Sync the user's monitored videos that are due, returning how many comments are new
+    async fn check_new_comments(&self, user_id: &str) -> Result<usize>;
+
+    /// The ones among the given comments and replies that are still publicly visible
+    async fn visible_comments(&self, user_id: &str, comment_ids: &[String]) -> Result<HashSet<String>>;
+
+    /// The state of the YouTube API rate limiter
+    async fn rate_limit(&self) -> RateLimitState;
+}
+
+/// YouTube service for interacting with the YouTube API
+pub struct YouTubeService {
+    db: Database,
+    client: Client,
+    upstream: Arc<Upstream>,
+    auth_service: Arc<AuthService>,
+    quota: Arc<QuotaTracker>,
+    settings: Arc<SettingsService>,
+    events: Arc<EventBus>,
+    pipeline: CommentPipeline,
+    channel_ids: TtlCache<String, String>,
+    videos: TtlCache<String, Vec<YouTubeVideo>>,
+    rate_limiter: RateLimiter,
+    default_rate: u32,
+    sync_concurrency: usize,
+    api_base: String,
+}
+
+impl YouTubeService {
+    /// Create a new YouTube service
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        db: Database,
+        client: Client,
+        upstream: Arc<Upstream>,
+        auth_service: Arc<AuthService>,
+        quota: Arc<QuotaTracker>,
+        notifications: Arc<NotificationService>,
+        settings: Arc<SettingsService>,
+        rules: Arc<RuleService>,
+        spam: Arc<SpamService>,
+        events: Arc<EventBus>,
+    ) -> Self {
+        let default_rate = env_or("YOUTUBE_MAX_REQUESTS_PER_SEC", DEFAULT_MAX_REQUESTS_PER_SEC);
+        let pipeline = CommentPipeline::new(db.clone(), notifications, settings.clone(), rules, spam, events.clone());
+        Self {
+            db,
+            client,
+            upstream,
+            auth_service,
+            quota,
+            settings,
+            events,
+            pipeline,
+            channel_ids: TtlCache::new(CHANNEL_CACHE_CAPACITY, CHANNEL_ID_CACHE_TTL),
+            videos: TtlCache::new(CHANNEL_CACHE_CAPACITY, VIDEO_LIST_CACHE_TTL),
+            rate_limiter: RateLimiter::new(default_rate),
+            default_rate,
+            sync_concurrency: env_or("MONITOR_SYNC_CONCURRENCY", DEFAULT_SYNC_CONCURRENCY).max(1),
+            api_base: env::var("YOUTUBE_API_BASE_URL").unwrap_or_else(|_| DEFAULT_API_BASE_URL.to_string()),
         }
+    }
 
-        Ok(all_threads)
+    /// Wait for the rate limiter and record the quota cost of an API call about to be made
+    async fn before_request(&self, cost: u64) {
+        // An operator may have changed the rate at runtime
+        let rate = self.settings.current().youtube_requests_per_sec.unwrap_or(self.default_rate);
+        self.rate_limiter.set_rate(rate);
+        self.rate_limiter.acquire().await;
+        self.quota.record(cost).await;
+    }
+
+    /// Fetch comments for a YouTube video, returning every stored comment once the sync is done
+    pub async fn fetch_comments(&self, user_id: &str, video_id: &str) -> Result<Vec<Comment>> {
+        self.sync_comments(user_id, video_id).await?;
+        Ok(self.db.get_comments(video_id).await?.unwrap_or_default())
+    }
+
+    /// Sync a video's comments from YouTube, returning how many were fetched.
+    ///
+    /// See [`CommentPipeline::ingest`] for what happens to them.
+    pub async fn sync_comments(&self, user_id: &str, video_id: &str) -> Result<usize> {
+        info!("Fetching comments for video: {}", video_id);
+        Ok(self.pipeline.ingest(self, user_id, video_id).await?.fetched)
+    }
+
+    /// Stream comment thread pages from the YouTube API, requesting each page only when the previous one is consumed
+    ///
+    /// Threads come newest first, so with `since` paging stops after the page reaching back to it.
+    fn comment_thread_pages<'a>(
+        &'a self,
+        video_id: &'a str,
+        access_token: String,
+        since: Option<DateTime<Utc>>,
+    ) -> impl Stream<Item = Result<Vec<YouTubeCommentThread>>> + 'a {
+        // The state is the token of the next page to request, or `None` once the last page is done
+        futures::stream::try_unfold(Some(None), move |page_token: Option<Option<String>>| {
+            let access_token = access_token.clone();
+            async move {
+                let page_token = match page_token {
+                    Some(page_token) => page_token,
+                    None => return Ok::<_, anyhow::Error>(None),
+                };
+
+                let url = format!(
+                    "{}/commentThreads?part=snippet,replies&videoId={}&maxResults=100&order=time{}",
+                    self.api_base,
+                    video_id,
+                    page_token.map_or(String::new(), |token| format!("&pageToken={}", token))
+                );
+
+                self.before_request(quota::LIST_COST).await;
+
+                let request = self.client
+                    .get(&url)
+                    .header("Authorization", format!("Bearer {}", access_token));
+                let response = self.upstream.call(request, Attempts::Retry, "Failed to get comments", api_error).await?;
+
+                let response_data: YouTubeCommentThreadResponse = response.json().await?;
+
+                // Check if there are more pages, and if they can hold anything not synced yet
+                let reached_since = since.is_some_and(|since| {
+                    response_data.items.iter().any(|thread| thread.snippet.top_level_comment.snippet.published_at <= since)
+                });
+                let next = if reached_since { None } else { response_data.next_page_token.map(Some) };
+                Ok(Some((response_data.items, next)))
+            }
+        })
     }
 
     /// Fetch replies to a comment from YouTube API
@@ -148,43 +223,22 @@ impl YouTubeService {
 
         loop {
             let url = format!(
-                "https://www.googleapis.com/youtube/v3/comments?part=snippet&parentId={}&maxResults=100{}",
+                "{}/comments?part=snippet&parentId={}&maxResults=100{}",
+                self.api_base,
                 comment_id,
                 page_token.map_or(String::new(), |token| format!("&pageToken={}", token))
             );
 
-            let response = self.client
+            self.before_request(quota::LIST_COST).await;
+
+            let request = self.client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", access_token))
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_text = response.text().await?;
-                error!("YouTube API error: {}", error_text);
-                anyhow::bail!("YouTube API error: {}", error_text);
-            }
+                .header("Authorization", format!("Bearer {}", access_token));
+            let response = self.upstream.call(request, Attempts::Retry, "Failed to get replies", api_error).await?;
 
             let response_data: YouTubeCommentResponse = response.json().await?;
 
-            let replies: Vec<Reply> = response_data
-                .items
-                .into_iter()
-                .map(|item| Reply {
-                    reply_id: item.id,
-                    parent_id: comment_id.to_string(),
-                    author: item.snippet.author_display_name,
-                    author_channel_id: item.snippet.author_channel_id.value,
-                    text: item.snippet.text_display,
-                    like_count: item.snippet.like_count,
-                    published_at: item.snippet.published_at,
-                    ai_generated: false,
-                    ai_model: None,
-                    metadata: HashMap::new(),
-                })
-                .collect();
-
-            all_replies.extend(replies);
+            all_replies.extend(response_data.items.into_iter().map(|item| to_reply(item, comment_id)));
 
             // Check if there are more pages
             if let Some(token) = response_data.next_page_token {
@@ -197,6 +251,213 @@ impl YouTubeService {
         Ok(all_replies)
     }
 
+    /// Get all replies in a thread, fetching them from YouTube if only the inline ones are stored.
+    ///
+    /// Returns `None` if the comment is not in the database.
+    pub async fn get_thread_replies(&self, user_id: &str, comment_id: &str) -> Result<Option<Vec<Reply>>> {
+        let comment = match self.db.get_comment(comment_id).await? {
+            Some(comment) => comment,
+            None => return Ok(None),
+        };
+
+        if comment.has_all_replies() {
+            return Ok(Some(comment.replies));
+        }
+
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+        let mut replies = self.fetch_replies(comment_id, &access_token).await?;
+        if self.settings.current().mask_sensitive_text {
+            masking::mask_replies(&mut replies);
+        }
+        self.db.update_comment_replies(comment_id, &replies).await?;
+
+        Ok(Some(replies))
+    }
+
+    /// Fetch the missing replies of every thread on a video that has more replies than stored.
+    ///
+    /// Meant to run in the background after a sync. Returns the number of threads completed.
+    pub async fn backfill_replies(&self, user_id: &str, video_id: &str) -> Result<usize> {
+        let incomplete = self.db.get_comments_missing_replies(video_id).await?;
+        if incomplete.is_empty() {
+            return Ok(0);
+        }
+
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+
+        for comment in &incomplete {
+            let mut replies = self.fetch_replies(&comment.comment_id, &access_token).await?;
+            if self.settings.current().mask_sensitive_text {
+                masking::mask_replies(&mut replies);
+            }
+            self.db.update_comment_replies(&comment.comment_id, &replies).await?;
+        }
+
+        info!("Backfilled replies for {} threads on video {}", incomplete.len(), video_id);
+
+        Ok(incomplete.len())
+    }
+
+    /// Re-fetch recently published comments on a user's videos and store their current text and like count.
+    ///
+    /// A low-priority pass for videos that weren't synced anyway: it is skipped when
+    /// quota runs low, and costs one unit per 50 comments. Edited comments are
+    /// published as [`UserEvent::CommentEdited`]. Returns the number of comments edited.
+    pub async fn refresh_active_comments(&self, user_id: &str, video_ids: &[String]) -> Result<usize> {
+        if video_ids.is_empty() || self.quota.remaining().await? < REFRESH_QUOTA_RESERVE {
+            return Ok(0);
+        }
+
+        let mut stored = self.db.get_comments_for_videos_since(video_ids, Utc::now() - REFRESH_WINDOW).await?;
+        stored.sort_by_key(|c| std::cmp::Reverse(c.published_at));
+        stored.truncate(MAX_REFRESHED_COMMENTS);
+        if stored.is_empty() {
+            return Ok(0);
+        }
+
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+        let mask = self.settings.current().mask_sensitive_text;
+        let stored: HashMap<String, Comment> = stored.into_iter().map(|c| (c.comment_id.clone(), c)).collect();
+        let ids: Vec<&String> = stored.keys().collect();
+
+        let mut edited = 0;
+        for batch in ids.chunks(REFRESH_BATCH_SIZE) {
+            for item in self.fetch_comments_by_id(batch, &access_token).await? {
+                let Some(comment) = stored.get(&item.id) else {
+                    continue;
+                };
+
+                // Stored text may be masked, so the fresh text is compared masked too
+                let snippet = item.snippet;
+                let text = if mask { masking::mask(&snippet.text_display) } else { snippet.text_display.clone() };
+                if text != comment.text {
+                    let sentiment = sentiment::score(&snippet.text_display);
+                    let timestamps = timestamps::parse(&snippet.text_display, &comment.video_id);
+                    let text_original = snippet.text_original.as_deref().map(|t| if mask { masking::mask(t) } else { t.to_string() });
+                    self.db.update_comment_text(&comment.comment_id, &text, text_original.as_deref(), sentiment, &timestamps).await?;
+                    edited += 1;
+
+                    self.events.publish(user_id, UserEvent::CommentEdited {
+                        video_id: comment.video_id.clone(),
+                        comment_id: comment.comment_id.clone(),
+                        text,
+                    });
+                }
+                if snippet.like_count != comment.like_count {
+                    self.db.update_comment_likes(&comment.comment_id, snippet.like_count).await?;
+                }
+            }
+        }
+
+        info!("Refreshed {} comments for user {}, {} edited", stored.len(), user_id, edited);
+
+        Ok(edited)
+    }
+
+    /// Re-fetch the threads the user answered on a video whose reply count changed since they were stored.
+    ///
+    /// An incremental sync stops before older threads, so this is what picks up new
+    /// replies to the user between full syncs: one quota unit per 50 threads, plus
+    /// fetching all replies of each changed thread with more than come inline.
+    async fn answered_threads(&self, video_id: &str, access_token: &str) -> Result<Vec<Comment>> {
+        let answered = self.db.get_answered_comments(video_id, MAX_ANSWERED_THREADS).await?;
+        let reply_counts: HashMap<&str, i32> = answered.iter().map(|c| (c.comment_id.as_str(), c.reply_count)).collect();
+        let ids: Vec<&String> = answered.iter().map(|c| &c.comment_id).collect();
+
+        let mut changed = Vec::new();
+        for batch in ids.chunks(REFRESH_BATCH_SIZE) {
+            for thread in self.fetch_threads_by_id(batch, access_token).await? {
+                if reply_counts.get(thread.id.as_str()) == Some(&thread.snippet.total_reply_count) {
+                    continue;
+                }
+
+                // Follow-ups are only judged on threads with every reply known
+                let mut comment = to_comment(thread, video_id);
+                if !comment.has_all_replies() {
+                    comment.replies = self.fetch_replies(&comment.comment_id, access_token).await?;
+                }
+                changed.push(comment);
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// Fetch comment threads by ID with their inline replies; deleted ones are left out of the response
+    async fn fetch_threads_by_id(&self, comment_ids: &[&String], access_token: &str) -> Result<Vec<YouTubeCommentThread>> {
+        let ids: Vec<&str> = comment_ids.iter().map(|id| id.as_str()).collect();
+        let url = format!("{}/commentThreads?part=snippet,replies&id={}", self.api_base, ids.join(","));
+
+        self.before_request(quota::LIST_COST).await;
+
+        let request = self.client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", access_token));
+        let response = self.upstream.call(request, Attempts::Retry, "Failed to look up comment threads", api_error).await?;
+
+        let response_data: YouTubeCommentThreadResponse = response.json().await?;
+        Ok(response_data.items)
+    }
+
+    /// Fetch comments by ID; deleted ones are left out of the response
+    async fn fetch_comments_by_id(&self, comment_ids: &[&String], access_token: &str) -> Result<Vec<YouTubeCommentItem>> {
+        let ids: Vec<&str> = comment_ids.iter().map(|id| id.as_str()).collect();
+        let url = format!("{}/comments?part=snippet&id={}", self.api_base, ids.join(","));
+
+        self.before_request(quota::LIST_COST).await;
+
+        let request = self.client
+            .get(&url)
+            .header("Authorization", format!("Bearer {}", access_token));
+        let response = self.upstream.call(request, Attempts::Retry, "Failed to look up comments", api_error).await?;
+
+        let response_data: YouTubeCommentResponse = response.json().await?;
+        Ok(response_data.items)
+    }
+
+    /// Reject a comment as spam so it is no longer shown, optionally banning its author from the channel
+    pub async fn reject_comment(&self, user_id: &str, comment_id: &str, ban_author: bool) -> Result<()> {
+        self.set_moderation_status(user_id, comment_id, ModerationStatus::Rejected, ban_author).await
+    }
+
+    /// Hold, publish or reject a comment; only a rejection can ban the author from the channel
+    pub async fn set_moderation_status(&self, user_id: &str, comment_id: &str, status: ModerationStatus, ban_author: bool) -> Result<()> {
+        info!("Setting the moderation status of comment {} to {}", comment_id, status.as_api());
+
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+
+        self.before_request(quota::WRITE_COST).await;
+
+        let mut query = vec![("id", comment_id), ("moderationStatus", status.as_api())];
+        if status == ModerationStatus::Rejected {
+            query.push(("banAuthor", if ban_author { "true" } else { "false" }));
+        }
+        let request = self.client
+            .post(format!("{}/comments/setModerationStatus", self.api_base))
+            .query(&query)
+            .header("Authorization", format!("Bearer {}", access_token));
+        self.upstream.call(request, Attempts::Once, "Failed to moderate comment", api_error).await?;
+
+        Ok(())
+    }
+
+    /// Report a comment to YouTube as spam
+    pub async fn mark_as_spam(&self, user_id: &str, comment_id: &str) -> Result<()> {
+        info!("Marking comment {} as spam", comment_id);
+
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+
+        self.before_request(quota::WRITE_COST).await;
+
+        let request = self.client
+            .post(format!("{}/comments/markAsSpam", self.api_base))
+            .query(&[("id", comment_id)])
+            .header("Authorization", format!("Bearer {}", access_token));
+        self.upstream.call(request, Attempts::Once, "Failed to mark comment as spam", api_error).await?;
+
+        Ok(())
+    }
+
     /// Post a reply to a comment
     pub async fn post_reply(&self, user_id: &str, comment_id: &str, text: &str) -> Result<Reply> {
         info!("Posting reply to comment: {}", comment_id);
@@ -213,19 +474,14 @@ impl YouTubeService {
         });
 
         // Send the request
-        let response = self.client
-            .post("https://www.googleapis.com/youtube/v3/comments?part=snippet")
+        self.before_request(quota::WRITE_COST).await;
+
+        let request = self.client
+            .post(format!("{}/comments?part=snippet", self.api_base))
             .header("Authorization", format!("Bearer {}", access_token))
             .header("Content-Type", "application/json")
-            .json(&request_body)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("YouTube API error: {}", error_text);
-            anyhow::bail!("Failed to post reply: {}", error_text);
-        }
+            .json(&request_body);
+        let response = self.upstream.call(request, Attempts::Once, "Failed to post reply", api_error).await?;
 
         let response_data: YouTubeCommentItem = response.json().await?;
 
@@ -236,6 +492,7 @@ impl YouTubeService {
             author: response_data.snippet.author_display_name,
             author_channel_id: response_data.snippet.author_channel_id.value,
             text: response_data.snippet.text_display,
+            text_original: response_data.snippet.text_original.or_else(|| Some(text.to_string())),
             like_count: response_data.snippet.like_count,
             published_at: response_data.snippet.published_at,
             ai_generated: false, // This will be set by the caller if needed
@@ -243,8 +500,9 @@ impl YouTubeService {
             metadata: HashMap::new(),
         };
 
-        // Mark the comment as replied to
+        // Mark the comment as replied to, which also completes its triage
         self.db.mark_comment_replied(comment_id, true).await?;
+        self.db.set_comment_triage(comment_id, TriageState::Done).await?;
 
         // Record the interaction
         let interaction = InteractionRecord {
@@ -263,35 +521,79 @@ impl YouTubeService {
         Ok(reply)
     }
 
-    /// Get videos for a channel
-    pub async fn get_channel_videos(&self, user_id: &str) -> Result<Vec<YouTubeVideo>> {
-        info!("Fetching videos for channel: {}", user_id);
+    /// Replace the text of one of the user's posted replies.
+    ///
+    /// YouTube keeps the reply's ID and position in the thread and marks it as edited.
+    pub async fn update_reply(&self, user_id: &str, reply_id: &str, parent_id: &str, text: &str) -> Result<Reply> {
+        info!("Updating reply: {}", reply_id);
 
-        // Get a valid access token
         let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+        let request_body = serde_json::json!({
+            "id": reply_id,
+            "snippet": {
+                "textOriginal": text
+            }
+        });
 
-        // First, get the channel ID for the authenticated user
-        let url = "https://www.googleapis.com/youtube/v3/channels?part=id&mine=true";
+        self.before_request(quota::WRITE_COST).await;
 
-        let response = self.client
-            .get(url)
+        let request = self.client
+            .put(format!("{}/comments?part=snippet", self.api_base))
             .header("Authorization", format!("Bearer {}", access_token))
-            .send()
-            .await?;
+            .header("Content-Type", "application/json")
+            .json(&request_body);
+        let response = self.upstream.call(request, Attempts::Once, "Failed to update reply", api_error).await?;
+
+        let mut reply = to_reply(response.json().await?, parent_id);
+        reply.text_original.get_or_insert_with(|| text.to_string());
+        Ok(reply)
+    }
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            error!("YouTube API error: {}", error_text);
-            anyhow::bail!("Failed to get channel ID: {}", error_text);
+    /// The ones among the given comments and replies that are still publicly visible.
+    ///
+    /// Removed ones aren't returned at all, and those held for review or marked
+    /// as likely spam come back with a moderation status other than `published`.
+    pub async fn visible_comments(&self, user_id: &str, comment_ids: &[String]) -> Result<HashSet<String>> {
+        let mut visible = HashSet::new();
+        if comment_ids.is_empty() {
+            return Ok(visible);
         }
 
-        let channel_response: YouTubeChannelResponse = response.json().await?;
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+        let ids: Vec<&String> = comment_ids.iter().collect();
+        for batch in ids.chunks(REFRESH_BATCH_SIZE) {
+            visible.extend(
+                self.fetch_comments_by_id(batch, &access_token)
+                    .await?
+                    .into_iter()
+                    .filter(|item| item.snippet.moderation_status.as_deref().is_none_or(|status| status == "published"))
+                    .map(|item| item.id),
+            );
+        }
 
-        if channel_response.items.is_empty() {
-            anyhow::bail!("No channel found for the authenticated user");
+        Ok(visible)
+    }
+
+    /// Get videos for a channel, served from a short-lived cache when possible
+    pub async fn get_channel_videos(&self, user_id: &str) -> Result<Vec<YouTubeVideo>> {
+        let cache_key = user_id.to_string();
+        if let Some(videos) = self.videos.get(&cache_key) {
+            return Ok(videos);
         }
 
-        let channel_id = &channel_response.items[0].id;
+        info!("Fetching videos for channel: {}", user_id);
+
+        // Get a valid access token
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+
+        let channel_id = match self.channel_ids.get(&cache_key) {
+            Some(channel_id) => channel_id,
+            None => {
+                let channel_id = self.fetch_channel_id(&access_token).await?;
+                self.channel_ids.insert(cache_key.clone(), channel_id.clone());
+                channel_id
+            }
+        };
 
         // Now get the videos for this channel
         let mut all_videos = Vec::new();
@@ -299,22 +601,18 @@ impl YouTubeService {
 
         loop {
             let url = format!(
-                "https://www.googleapis.com/youtube/v3/search?part=snippet&channelId={}&maxResults=50&order=date&type=video{}",
+                "{}/search?part=snippet&channelId={}&maxResults=50&order=date&type=video{}",
+                self.api_base,
                 channel_id,
                 page_token.map_or(String::new(), |token| format!("&pageToken={}", token))
             );
 
-            let response = self.client
+            self.before_request(quota::SEARCH_COST).await;
+
+            let request = self.client
                 .get(&url)
-                .header("Authorization", format!("Bearer {}", access_token))
-                .send()
-                .await?;
-
-            if !response.status().is_success() {
-                let error_text = response.text().await?;
-                error!("YouTube API error: {}", error_text);
-                anyhow::bail!("Failed to get videos: {}", error_text);
-            }
+                .header("Authorization", format!("Bearer {}", access_token));
+            let response = self.upstream.call(request, Attempts::Retry, "Failed to get videos", api_error).await?;
 
             let video_response: YouTubeVideoSearchResponse = response.json().await?;
 
@@ -325,6 +623,7 @@ impl YouTubeService {
                     description: item.snippet.description,
                     published_at: item.snippet.published_at,
                     thumbnail_url: item.snippet.thumbnails.default.url,
+                    duration_secs: None,
                 });
             }
 
@@ -335,129 +634,480 @@ impl YouTubeService {
                 break;
             }
 
-            // Don't hit rate limits
-            time::sleep(Duration::from_millis(100)).await;
         }
 
+        // Search results have no length, which tells Shorts apart; without it only tags do
+        match self.fetch_durations(&access_token, &all_videos).await {
+            Ok(durations) => {
+                for video in &mut all_videos {
+                    video.duration_secs = durations.get(&video.id).copied();
+                }
+            }
+            Err(e) => error!("Error fetching video durations for channel {}: {}", channel_id, e),
+        }
+
+        // Keep the stored video metadata (and monitor settings) up to date
+        self.sync_videos(user_id, &all_videos).await?;
+
+        self.videos.insert(cache_key, all_videos.clone());
+
         Ok(all_videos)
     }
 
-    /// Start monitoring for new comments
-    pub async fn start_comment_monitor(&self, user_id: &str, polling_interval: u64) -> Result<()> {
-        info!("Starting comment monitor for user: {}", user_id);
+    /// Get the length (in seconds) of each video, up to 50 per request
+    async fn fetch_durations(&self, access_token: &str, videos: &[YouTubeVideo]) -> Result<HashMap<String, u64>> {
+        let mut durations = HashMap::new();
+
+        for batch in videos.chunks(50) {
+            let ids: Vec<&str> = batch.iter().map(|video| video.id.as_str()).collect();
+            let url = format!("{}/videos?part=contentDetails&id={}", self.api_base, ids.join(","));
+
+            self.before_request(quota::LIST_COST).await;
+
+            let request = self.client
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", access_token));
+            let response = self.upstream.call(request, Attempts::Retry, "Failed to get video details", api_error).await?;
+
+            let details: YouTubeVideoListResponse = response.json().await?;
+            for item in details.items {
+                if let Some(secs) = parse_duration(&item.content_details.duration) {
+                    durations.insert(item.id, secs);
+                }
+            }
+        }
+
+        Ok(durations)
+    }
+
+    /// Get the channel ID of the authenticated user
+    async fn fetch_channel_id(&self, access_token: &str) -> Result<String> {
+        let url = format!("{}/channels?part=id&mine=true", self.api_base);
 
-        // Get the user's videos
-        let videos = self.get_channel_videos(user_id).await?;
+        self.before_request(quota::LIST_COST).await;
+
+        let request = self.client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", access_token));
+        let response = self.upstream.call(request, Attempts::Retry, "Failed to get channel ID", api_error).await?;
+
+        let channel_response: YouTubeChannelResponse = response.json().await?;
+
+        if channel_response.items.is_empty() {
+            anyhow::bail!("No channel found for the authenticated user");
+        }
+
+        Ok(channel_response.items[0].id.clone())
+    }
+
+    /// Store fetched videos, keeping any monitor settings the user has customized
+    async fn sync_videos(&self, user_id: &str, videos: &[YouTubeVideo]) -> Result<()> {
+        let now = Utc::now();
+
+        for video in videos {
+            let existing = self.db.get_video(&video.id).await?;
+
+            // A failed length lookup shouldn't undo an earlier detection
+            let duration_secs = video.duration_secs.or_else(|| existing.as_ref().and_then(|v| v.duration_secs));
+
+            let (monitor, last_checked_at, collection_id, reply_defaults, metadata) = match existing {
+                Some(existing) if existing.monitor.customized => (
+                    existing.monitor,
+                    existing.last_checked_at,
+                    existing.collection_id,
+                    existing.reply_defaults,
+                    existing.metadata,
+                ),
+                Some(existing) => (
+                    // Defaults follow the video's age until the user changes them
+                    MonitorSettings::for_video_age(video.published_at, now),
+                    existing.last_checked_at,
+                    existing.collection_id,
+                    existing.reply_defaults,
+                    existing.metadata,
+                ),
+                None => (
+                    MonitorSettings::for_video_age(video.published_at, now),
+                    None,
+                    None,
+                    ReplyDefaults::default(),
+                    HashMap::new(),
+                ),
+            };
+
+            self.db.save_video(&Video {
+                video_id: video.id.clone(),
+                user_id: user_id.to_string(),
+                title: video.title.clone(),
+                description: video.description.clone(),
+                published_at: video.published_at,
+                thumbnail_url: video.thumbnail_url.clone(),
+                duration_secs,
+                format: VideoFormat::detect(duration_secs, &video.title, &video.description),
+                monitor,
+                last_checked_at,
+                synced_at: Some(now),
+                collection_id,
+                reply_defaults,
+                metadata,
+            }).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sync the user's monitored videos that are due, returning how many comments are new.
+    ///
+    /// Nothing is synced while monitoring is paused or the user must reconnect Google.
+    pub async fn check_new_comments(&self, user_id: &str) -> Result<usize> {
+        if self.settings.current().monitor_paused {
+            info!("Comment monitoring is paused, skipping user: {}", user_id);
+            return Ok(0);
+        }
+        if self.db.get_user(user_id).await?.is_some_and(|user| user.is_disconnected()) {
+            info!("User {} must reconnect their Google account, skipping their comment monitor", user_id);
+            return Ok(0);
+        }
+
+        // Refresh the user's videos, then use the stored copies with their monitor settings
+        self.get_channel_videos(user_id).await?;
+        let videos = self.db.get_user_videos(user_id).await?;
 
         if videos.is_empty() {
             warn!("No videos found for user: {}", user_id);
-            return Ok(());
+            return Ok(0);
         }
 
-        info!("Found {} videos to monitor", videos.len());
-
-        // Initial fetch of all comments
-        for video in &videos {
-            info!("Initial fetch of comments for video: {}", video.id);
-            match self.fetch_comments(user_id, &video.id).await {
-                Ok(comments) => {
-                    info!("Fetched {} comments for video: {}", comments.len(), video.id);
+        // Only check videos that are enabled, not muted and whose interval has elapsed
+        let mutes = MuteList::load(&self.db, user_id).await?;
+        let now = Utc::now();
+        let due: Vec<Video> = videos
+            .iter()
+            .filter(|v| v.is_monitor_due(now) && !mutes.mutes_video(&v.video_id))
+            .cloned()
+            .collect();
+
+        info!("Found {} videos, {} due for monitoring", videos.len(), due.len());
+
+        // Videos about to be synced will be up to date; the others may have edits and new likes
+        let idle: Vec<String> = videos
+            .iter()
+            .filter(|v| v.monitor.enabled && !v.is_monitor_due(now) && !mutes.mutes_video(&v.video_id))
+            .map(|v| v.video_id.clone())
+            .collect();
+
+        // Sync several videos at once; the shared rate limiter keeps the total request rate in bounds
+        let new_comments = futures::stream::iter(due)
+            .map(|video| async move {
+                info!("Fetching comments for video: {}", video.video_id);
+                match self.pipeline.ingest(self, user_id, &video.video_id).await {
+                    Ok(summary) => {
+                        info!("Fetched {} comments for video: {}, {} new", summary.fetched, video.video_id, summary.new_comments);
+                        if let Err(e) = self.db.mark_video_checked(&video.video_id, Utc::now()).await {
+                            error!("Error marking video {} checked: {}", video.video_id, e);
+                        }
+                        summary.new_comments
+                    }
+                    Err(e) => {
+                        error!("Error fetching comments for video {}: {}", video.video_id, e);
+                        0
+                    }
                 }
-                Err(e) => {
-                    error!("Error fetching comments for video {}: {}", video.id, e);
+            })
+            .buffer_unordered(self.sync_concurrency)
+            .fold(0, |total, count| async move { total + count })
+            .await;
+
+        // Low priority, so only once the syncs are done
+        if let Err(e) = self.refresh_active_comments(user_id, &idle).await {
+            error!("Error refreshing comments for user {}: {}", user_id, e);
+        }
+
+        Ok(new_comments)
+    }
+}
+
+#[async_trait]
+impl YouTubeApi for YouTubeService {
+    async fn fetch_comments(&self, user_id: &str, video_id: &str) -> Result<Vec<Comment>> {
+        YouTubeService::fetch_comments(self, user_id, video_id).await
+    }
+
+    async fn sync_comments(&self, user_id: &str, video_id: &str) -> Result<usize> {
+        YouTubeService::sync_comments(self, user_id, video_id).await
+    }
+
+    async fn get_thread_replies(&self, user_id: &str, comment_id: &str) -> Result<Option<Vec<Reply>>> {
+        YouTubeService::get_thread_replies(self, user_id, comment_id).await
+    }
+
+    async fn backfill_replies(&self, user_id: &str, video_id: &str) -> Result<usize> {
+        YouTubeService::backfill_replies(self, user_id, video_id).await
+    }
+
+    async fn post_reply(&self, user_id: &str, comment_id: &str, text: &str) -> Result<Reply> {
+        YouTubeService::post_reply(self, user_id, comment_id, text).await
+    }
+
+    async fn update_reply(&self, user_id: &str, reply_id: &str, parent_id: &str, text: &str) -> Result<Reply> {
+        YouTubeService::update_reply(self, user_id, reply_id, parent_id, text).await
+    }
+
+    async fn get_channel_videos(&self, user_id: &str) -> Result<Vec<YouTubeVideo>> {
+        YouTubeService::get_channel_videos(self, user_id).await
+    }
+
+    async fn reject_comment(&self, user_id: &str, comment_id: &str, ban_author: bool) -> Result<()> {
+        YouTubeService::reject_comment(self, user_id, comment_id, ban_author).await
+    }
+
+    async fn set_moderation_status(&self, user_id: &str, comment_id: &str, status: ModerationStatus, ban_author: bool) -> Result<()> {
+        YouTubeService::set_moderation_status(self, user_id, comment_id, status, ban_author).await
+    }
+
+    async fn mark_as_spam(&self, user_id: &str, comment_id: &str) -> Result<()> {
+        YouTubeService::mark_as_spam(self, user_id, comment_id).await
+    }
+
+    async fn check_new_comments(&self, user_id: &str) -> Result<usize> {
+        YouTubeService::check_new_comments(self, user_id).await
+    }
+
+    async fn visible_comments(&self, user_id: &str, comment_ids: &[String]) -> Result<HashSet<String>> {
+        YouTubeService::visible_comments(self, user_id, comment_ids).await
+    }
+
+    async fn rate_limit(&self) -> RateLimitState {
+        self.rate_limiter.state().await
+    }
+}
+
+/// Comments on the user's videos
+#[async_trait]
+impl CommentSource for YouTubeService {
+    fn name(&self) -> &'static str {
+        "youtube"
+    }
+
+    async fn comment_pages<'a>(&'a self, user_id: &'a str, video_id: &'a str, since: Option<DateTime<Utc>>) -> Result<CommentPages<'a>> {
+        let access_token = self.auth_service.get_valid_access_token(user_id).await?;
+        let is_short = self.db.get_video(video_id).await?.is_some_and(|video| video.format == VideoFormat::Short);
+
+        let threads = self
+            .comment_thread_pages(video_id, access_token.clone(), since)
+            .map_ok(move |threads| threads.into_iter().map(|thread| to_comment(thread, video_id)).collect::<Vec<_>>());
+
+        // Paging stopped before older threads, so the ones the user answered are checked on their own
+        let answered = futures::stream::once(async move {
+            match since {
+                Some(_) => self.answered_threads(video_id, &access_token).await,
+                None => Ok(Vec::new()),
+            }
+        })
+        .try_filter(|comments| futures::future::ready(!comments.is_empty()));
+
+        let pages = threads.chain(answered).map_ok(move |mut comments| {
+            if is_short {
+                for comment in &mut comments {
+                    comment.metadata.insert(SHORT_KEY.to_string(), "true".to_string());
                 }
             }
+            comments
+        });
+        Ok(pages.boxed())
+    }
 
-            // Don't hit rate limits
-            time::sleep(Duration::from_millis(500)).await;
+    async fn post_reply(&self, user_id: &str, comment_id: &str, text: &str) -> Result<Reply> {
+        YouTubeService::post_reply(self, user_id, comment_id, text).await
+    }
+}
+
+/// Classify a failed YouTube API response so quota and credential problems reach the client as such
+fn api_error(status: StatusCode, message: String) -> AppError {
+    match status {
+        StatusCode::UNAUTHORIZED => AppError::UpstreamAuth(message),
+        StatusCode::FORBIDDEN
+            if ["quotaExceeded", "dailyLimitExceeded", "rateLimitExceeded"]
+                .iter()
+                .any(|reason| message.contains(reason)) =>
+        {
+            AppError::UpstreamQuota(message)
         }
+        StatusCode::NOT_FOUND => AppError::NotFound("YouTube video or comment".to_string()),
+        _ => AppError::Internal(anyhow::anyhow!(message)),
+    }
+}
+
+/// Read a setting from the environment, falling back to a default if unset or invalid
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
 
-        // TODO: Implement continuous monitoring in a separate task
-        // This would typically be done with tokio::spawn and a loop with delay
+/// Convert a YouTube comment thread into our Comment model
+fn to_comment(thread: YouTubeCommentThread, video_id: &str) -> Comment {
+    let comment_id = thread.id;
+    let snippet = thread.snippet.top_level_comment.snippet;
+
+    // Keep the replies YouTube includes inline; the rest are fetched when the thread is opened
+    let replies: Vec<Reply> = thread
+        .replies
+        .map(|r| r.comments)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|item| to_reply(item, &comment_id))
+        .collect();
+
+    let sentiment = sentiment::score(&snippet.text_display);
+    let timestamps = timestamps::parse(&snippet.text_display, video_id);
+    let mut metadata = HashMap::new();
+    if let Some(text_hash) = duplicates::fingerprint(&snippet.text_display) {
+        metadata.insert(TEXT_HASH_KEY.to_string(), text_hash);
+    }
 
-        Ok(())
+    Comment {
+        video_id: video_id.to_string(),
+        comment_id,
+        author: snippet.author_display_name,
+        author_channel_id: snippet.author_channel_id.value,
+        text: snippet.text_display,
+        text_original: snippet.text_original,
+        like_count: snippet.like_count,
+        published_at: snippet.published_at,
+        replies,
+        reply_count: thread.snippet.total_reply_count,
+        sentiment: Some(sentiment),
+        timestamps,
+        metadata,
+        // The user's state, e.g. `replied_to` and `triage`, is updated from the database by the caller
+        ..Default::default()
+    }
+}
+
+/// Convert a YouTube reply into our Reply model
+fn to_reply(item: YouTubeCommentItem, parent_id: &str) -> Reply {
+    Reply {
+        reply_id: item.id,
+        parent_id: parent_id.to_string(),
+        author: item.snippet.author_display_name,
+        author_channel_id: item.snippet.author_channel_id.value,
+        text: item.snippet.text_display,
+        text_original: item.snippet.text_original,
+        like_count: item.snippet.like_count,
+        published_at: item.snippet.published_at,
+        ai_generated: false,
+        ai_model: None,
+        metadata: HashMap::new(),
     }
 }
 
 // YouTube API response models
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeCommentThreadResponse {
     items: Vec<YouTubeCommentThread>,
     next_page_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeCommentThread {
     id: String,
     snippet: YouTubeCommentThreadSnippet,
+    /// Up to five replies, present when `part=replies` is requested
+    #[serde(default)]
+    replies: Option<YouTubeCommentThreadReplies>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeCommentThreadReplies {
+    comments: Vec<YouTubeCommentItem>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeCommentThreadSnippet {
     total_reply_count: i32,
     top_level_comment: YouTubeComment,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeCommentResponse {
     items: Vec<YouTubeCommentItem>,
     next_page_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeCommentItem {
     id: String,
     snippet: YouTubeCommentSnippet,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeComment {
     snippet: YouTubeCommentSnippet,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeCommentSnippet {
     author_display_name: String,
     author_channel_id: YouTubeChannelId,
     text_display: String,
+    /// Only returned to authorized users, such as the channel owner
+    #[serde(default)]
+    text_original: Option<String>,
     like_count: i32,
     published_at: DateTime<Utc>,
+    /// Only returned to the channel owner, for comments that aren't simply published
+    #[serde(default)]
+    moderation_status: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeChannelId {
     value: String,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeChannelResponse {
     items: Vec<YouTubeChannelItem>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeChannelItem {
     id: String,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeVideoSearchResponse {
     items: Vec<YouTubeVideoSearchItem>,
     next_page_token: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeVideoSearchItem {
     id: YouTubeVideoId,
     snippet: YouTubeVideoSnippet,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeVideoId {
     video_id: String,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeVideoSnippet {
     title: String,
     description: String,
@@ -466,11 +1116,32 @@ struct YouTubeVideoSnippet {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeVideoListResponse {
+    items: Vec<YouTubeVideoDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeVideoDetails {
+    id: String,
+    content_details: YouTubeContentDetails,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct YouTubeContentDetails {
+    duration: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeThumbnails {
     default: YouTubeThumbnail,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct YouTubeThumbnail {
     url: String,
 }
@@ -492,4 +1163,43 @@ pub struct YouTubeVideo {
 
     /// URL to the video thumbnail
     pub thumbnail_url: String,
+
+    /// Length of the video (in seconds), if known
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comment_threads_parse_camel_case() {
+        let body = r#"{
+            "items": [{
+                "id": "thread1",
+                "snippet": {
+                    "totalReplyCount": 3,
+                    "topLevelComment": {
+                        "snippet": {
+                            "authorDisplayName": "Viewer",
+                            "authorChannelId": { "value": "UC123" },
+                            "textDisplay": "Great video",
+                            "likeCount": 7,
+                            "publishedAt": "2024-01-01T00:00:00Z"
+                        }
+                    }
+                }
+            }],
+            "nextPageToken": "page2"
+        }"#;
+
+        let response: YouTubeCommentThreadResponse = serde_json::from_str(body).unwrap();
+
+        assert_eq!(response.next_page_token.as_deref(), Some("page2"));
+        let snippet = &response.items[0].snippet;
+        assert_eq!(snippet.total_reply_count, 3);
+        assert_eq!(snippet.top_level_comment.snippet.author_display_name, "Viewer");
+        assert_eq!(snippet.top_level_comment.snippet.like_count, 7);
+    }
 }
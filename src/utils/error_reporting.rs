@@ -0,0 +1,155 @@
+//! Optional Sentry error reporting, compiled in with the `sentry` feature.
+//!
+//! Without the feature every function here is a no-op, so callers don't need
+//! their own `cfg` attributes.
+
+use axum::Router;
+#[cfg(feature = "sentry")]
+use axum::{extract::Request, middleware::Next, response::Response};
+#[cfg(feature = "sentry")]
+use sentry::protocol::Event;
+#[cfg(feature = "sentry")]
+use std::borrow::Cow;
+#[cfg(feature = "sentry")]
+use std::env;
+#[cfg(feature = "sentry")]
+use std::sync::Arc;
+
+#[cfg(feature = "sentry")]
+use crate::utils::logging::REQUEST_ID_HEADER;
+
+/// Request headers never sent to Sentry
+#[cfg(feature = "sentry")]
+const SECRET_HEADERS: [&str; 5] = [
+    "authorization",
+    "cookie",
+    "x-session-id",
+    "x-telegram-bot-api-secret-token",
+    "proxy-authorization",
+];
+
+/// Initialize Sentry error reporting if `SENTRY_DSN` is set.
+///
+/// `SENTRY_ENVIRONMENT` names the deployment. Panics and `error!` events are
+/// reported with the request ID attached; credentials and query strings are
+/// removed before anything is sent. Keep the returned guard alive for the life
+/// of the process so queued events are flushed on shutdown.
+#[cfg(feature = "sentry")]
+pub fn init() -> Option<sentry::ClientInitGuard> {
+    let dsn = env::var("SENTRY_DSN").ok()?;
+
+    let guard = sentry::init((
+        dsn,
+        sentry::ClientOptions {
+            release: sentry::release_name!(),
+            environment: env::var("SENTRY_ENVIRONMENT").ok().map(Cow::Owned),
+            send_default_pii: false,
+            before_send: Some(Arc::new(|event| Some(scrub(event)))),
+            ..Default::default()
+        },
+    ));
+
+    Some(guard)
+}
+
+/// Sentry isn't compiled in
+#[cfg(not(feature = "sentry"))]
+pub fn init() -> Option<()> {
+    None
+}
+
+/// Add the Sentry middleware: a hub per request, request details on reports and the request ID tag
+#[cfg(feature = "sentry")]
+pub fn layer(router: Router) -> Router {
+    router
+        .layer(axum::middleware::from_fn(tag_request))
+        .layer(sentry::integrations::tower::SentryHttpLayer::new())
+        .layer(sentry::integrations::tower::NewSentryLayer::<Request>::new_from_top())
+}
+
+/// Sentry isn't compiled in, so there is no middleware to add
+#[cfg(not(feature = "sentry"))]
+pub fn layer(router: Router) -> Router {
+    router
+}
+
+/// Tag the request's Sentry scope with its request ID, so reports can be matched to logs
+#[cfg(feature = "sentry")]
+async fn tag_request(request: Request, next: Next) -> Response {
+    if let Some(request_id) = request.headers().get(REQUEST_ID_HEADER).and_then(|v| v.to_str().ok()) {
+        sentry::configure_scope(|scope| scope.set_tag("request_id", request_id));
+    }
+
+    next.run(request).await
+}
+
+/// Record an upstream failure as a breadcrumb, giving later error reports the upstream context
+#[cfg(feature = "sentry")]
+pub fn upstream_failure(upstream: &str, status: Option<u16>, latency_ms: u64, attempt: u32) {
+    let mut data = sentry::protocol::Map::new();
+    data.insert("upstream".to_string(), upstream.into());
+    data.insert("status".to_string(), status.into());
+    data.insert("latency_ms".to_string(), latency_ms.into());
+    data.insert("attempt".to_string(), attempt.into());
+
+    sentry::add_breadcrumb(sentry::Breadcrumb {
+        category: Some("upstream".to_string()),
+        message: Some(format!("{} call failed", upstream)),
+        level: sentry::Level::Warning,
+        data,
+        ..Default::default()
+    });
+}
+
+/// Sentry isn't compiled in
+#[cfg(not(feature = "sentry"))]
+pub fn upstream_failure(_upstream: &str, _status: Option<u16>, _latency_ms: u64, _attempt: u32) {}
+
+/// Remove credentials from an event before it leaves the process
+#[cfg(feature = "sentry")]
+fn scrub(mut event: Event<'static>) -> Event<'static> {
+    if let Some(request) = event.request.as_mut() {
+        request
+            .headers
+            .retain(|name, _| !SECRET_HEADERS.contains(&name.to_ascii_lowercase().as_str()));
+
+        // OAuth codes and share tokens travel in query strings
+        request.query_string = None;
+        request.cookies = None;
+
+        if let Some(url) = request.url.as_mut() {
+            url.set_query(None);
+        }
+    }
+
+    event
+}
+
+#[cfg(all(test, feature = "sentry"))]
+mod tests {
+    use super::*;
+    use sentry::protocol::Request as SentryRequest;
+
+    #[test]
+    fn test_scrub_removes_credentials() {
+        let mut headers = std::collections::BTreeMap::new();
+        headers.insert("X-Session-Id".to_string(), "secret".to_string());
+        headers.insert("Accept".to_string(), "application/json".to_string());
+
+        let event = Event {
+            request: Some(SentryRequest {
+                url: "http://localhost:3000/api/auth/callback?code=secret".parse().ok(),
+                query_string: Some("code=secret".to_string()),
+                headers,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let request = scrub(event).request.unwrap();
+        assert_eq!(request.headers.len(), 1);
+        assert!(request.headers.contains_key("Accept"));
+        assert_eq!(request.query_string, None);
+        assert_eq!(request.url.unwrap().query(), None);
+    }
+}
@@ -0,0 +1,232 @@
+//! Request/response body logging for debugging, switched on per route at runtime.
+//!
+//! Routes are named by their pattern (`/api/reply/generate`, `/api/comments/:video_id`),
+//! or `*` for every route. `HTTP_LOG_ROUTES` sets the initial list; the admin API
+//! changes it without a restart. Credentials are redacted before anything is logged:
+//! secret headers, secret query parameters and secret fields anywhere in JSON bodies.
+//! Other bodies are logged by size only.
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::env;
+use std::sync::{Arc, RwLock};
+use tracing::info;
+
+/// Largest body that is buffered for logging; larger bodies are logged by size only
+const MAX_LOGGED_BODY_BYTES: usize = 16 * 1024;
+
+/// Route name enabling logging for every route
+pub const ALL_ROUTES: &str = "*";
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Headers whose values are never logged
+const SECRET_HEADERS: [&str; 7] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-session-id",
+    "x-admin-token",
+    "x-telegram-bot-api-secret-token",
+];
+
+/// Header, query parameter or JSON field names holding credentials (matched case-insensitively)
+fn is_secret_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    SECRET_HEADERS.contains(&name.as_str())
+        || name == "code"
+        || name == "session_id"
+        || name.contains("token")
+        || name.contains("secret")
+        || name.contains("password")
+        || name.contains("api_key")
+        || name.contains("apikey")
+}
+
+/// The routes bodies are currently logged for
+pub struct HttpLog {
+    routes: RwLock<BTreeSet<String>>,
+}
+
+impl HttpLog {
+    /// Read the initial routes from `HTTP_LOG_ROUTES` (comma-separated, empty by default)
+    pub fn from_env() -> Self {
+        let routes = env::var("HTTP_LOG_ROUTES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|route| !route.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        Self { routes: RwLock::new(routes) }
+    }
+
+    /// The routes bodies are logged for
+    pub fn routes(&self) -> Vec<String> {
+        self.routes.read().unwrap().iter().cloned().collect()
+    }
+
+    /// Turn logging on or off for a route
+    pub fn set(&self, route: &str, enabled: bool) {
+        let mut routes = self.routes.write().unwrap();
+        if enabled {
+            routes.insert(route.to_string());
+        } else {
+            routes.remove(route);
+        }
+    }
+
+    fn is_enabled(&self, route: &str) -> bool {
+        let routes = self.routes.read().unwrap();
+        routes.contains(ALL_ROUTES) || routes.contains(route)
+    }
+}
+
+/// Middleware logging the redacted request and response of enabled routes
+pub async fn log_bodies(State(http_log): State<Arc<HttpLog>>, request: Request, next: Next) -> Response {
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) if http_log.is_enabled(path.as_str()) => path.as_str().to_string(),
+        _ => return next.run(request).await,
+    };
+
+    let (parts, body) = request.into_parts();
+    let (request_body, body) = capture(&parts.headers, body).await;
+    info!(
+        route = %route,
+        method = %parts.method,
+        query = %redact_query(parts.uri.query().unwrap_or_default()),
+        headers = %redact_headers(&parts.headers),
+        body = %request_body,
+        "HTTP request"
+    );
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let (parts, body) = response.into_parts();
+    let (response_body, body) = capture(&parts.headers, body).await;
+    info!(
+        route = %route,
+        status = parts.status.as_u16(),
+        headers = %redact_headers(&parts.headers),
+        body = %response_body,
+        "HTTP response"
+    );
+
+    Response::from_parts(parts, body)
+}
+
+/// Describe a body for the log, buffering it only if it's small, known-size JSON.
+///
+/// Returns the description and a body to pass on in place of the original.
+async fn capture(headers: &HeaderMap, body: Body) -> (String, Body) {
+    let is_json = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("application/json"));
+    let size = body.size_hint().exact().map(|size| size as usize);
+
+    match size {
+        Some(0) => ("<empty>".to_string(), body),
+        // Streamed bodies such as exports are passed through untouched
+        Some(size) if is_json && size <= MAX_LOGGED_BODY_BYTES => match axum::body::to_bytes(body, size).await {
+            Ok(bytes) => (redact_body(&bytes), Body::from(bytes)),
+            Err(e) => (format!("<unreadable: {}>", e), Body::empty()),
+        },
+        Some(size) => (format!("<{} bytes>", size), body),
+        None => ("<streamed>".to_string(), body),
+    }
+}
+
+fn redact_headers(headers: &HeaderMap) -> String {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_secret_name(name.as_str()) {
+                REDACTED
+            } else {
+                value.to_str().unwrap_or("<binary>")
+            };
+            format!("{}: {}", name, value)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if is_secret_name(name) => format!("{}={}", name, REDACTED),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn redact_body(bytes: &Bytes) -> String {
+    match serde_json::from_slice::<Value>(bytes) {
+        Ok(mut value) => {
+            redact_json(&mut value);
+            value.to_string()
+        }
+        Err(_) => format!("<{} bytes, not JSON>", bytes.len()),
+    }
+}
+
+fn redact_json(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields.iter_mut() {
+                if is_secret_name(name) {
+                    *field = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(field);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_json),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_redaction() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-session-id", HeaderValue::from_static("session-1"));
+        headers.insert("Authorization", HeaderValue::from_static("Bearer abc"));
+        headers.insert("content-type", HeaderValue::from_static("application/json"));
+        let headers = redact_headers(&headers);
+        assert!(!headers.contains("session-1") && !headers.contains("abc"));
+        assert!(headers.contains("content-type: application/json"));
+
+        assert_eq!(redact_query("code=4/abc&state=xyz"), "code=[REDACTED]&state=xyz");
+
+        let body = Bytes::from(
+            json!({
+                "comment_id": "c1",
+                "user": { "refresh_token": "r", "openai_api_key": "k" },
+                "items": [{ "client_secret": "s" }]
+            })
+            .to_string(),
+        );
+        let body = redact_body(&body);
+        assert!(body.contains("\"comment_id\":\"c1\""));
+        assert!(!body.contains("\"r\"") && !body.contains("\"k\"") && !body.contains("\"s\""));
+    }
+}
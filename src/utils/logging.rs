@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::sync::OnceLock;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Layer, Registry};
+
+use crate::error::AppError;
+
+/// Header carrying the request ID, set on incoming requests that lack one and echoed on responses
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// File name prefix for rotated log files
+const LOG_FILE_PREFIX: &str = "youtube-commenter.log";
+
+/// Swaps the log filter of the running subscriber
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// The filter directives from `RUST_LOG`, or `info`
+fn default_filter() -> String {
+    env::var("RUST_LOG").unwrap_or_else(|_| "info".into())
+}
+
+/// Initialize logging from the environment.
+///
+/// - `RUST_LOG` sets the filter (default `info`)
+/// - `LOG_FORMAT=json` emits one JSON object per line, with span fields such as the
+///   request ID and user ID on every event, for shipping to Loki or Elastic
+/// - `LOG_DIR` writes to files in that directory instead of stdout, rotated
+///   according to `LOG_ROTATION` (`daily` by default, `hourly` or `never`)
+///
+/// The returned guard flushes buffered file output when dropped, so keep it alive
+/// for the life of the process.
+pub fn init() -> Result<Option<WorkerGuard>> {
+    let (filter, filter_handle) = reload::Layer::new(EnvFilter::new(default_filter()));
+
+    let (writer, guard) = match env::var("LOG_DIR") {
+        Ok(dir) => {
+            let rotation = match env::var("LOG_ROTATION").as_deref() {
+                Ok("hourly") => Rotation::HOURLY,
+                Ok("never") => Rotation::NEVER,
+                Ok("daily") | Err(_) => Rotation::DAILY,
+                Ok(other) => anyhow::bail!("LOG_ROTATION has an invalid value: {}", other),
+            };
+
+            let appender = RollingFileAppender::new(rotation, &dir, LOG_FILE_PREFIX);
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (BoxMakeWriter::new(writer), Some(guard))
+        }
+        Err(_) => (BoxMakeWriter::new(std::io::stdout), None),
+    };
+
+    let layer = match env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_writer(writer)
+            .boxed(),
+        Ok("text") | Err(_) => tracing_subscriber::fmt::layer().with_writer(writer).boxed(),
+        Ok(other) => anyhow::bail!("LOG_FORMAT has an invalid value: {}", other),
+    };
+
+    let subscriber = tracing_subscriber::registry().with(filter).with(layer);
+
+    // Turns `error!` events into Sentry reports and other events into breadcrumbs; a no-op without a DSN
+    #[cfg(feature = "sentry")]
+    let subscriber = subscriber.with(sentry::integrations::tracing::layer());
+
+    subscriber.try_init().context("Failed to initialize logging")?;
+    let _ = FILTER_HANDLE.set(filter_handle);
+
+    Ok(guard)
+}
+
+/// Replace the log filter without a restart; `None` goes back to `RUST_LOG`.
+///
+/// Invalid directives are rejected with a validation error. Before [`init`] the
+/// directives are only checked.
+pub fn set_filter(directives: Option<&str>) -> Result<()> {
+    apply_filter(parse_filter(directives)?)
+}
+
+/// Parse filter directives, `None` meaning `RUST_LOG`, failing with a validation error if they are invalid
+pub fn parse_filter(directives: Option<&str>) -> Result<EnvFilter> {
+    let directives = directives.map(str::to_string).unwrap_or_else(default_filter);
+    Ok(EnvFilter::try_new(&directives)
+        .map_err(|e| AppError::Validation(format!("Invalid log filter {:?}: {}", directives, e)))?)
+}
+
+/// Make a parsed filter the running subscriber's; does nothing before [`init`]
+pub fn apply_filter(filter: EnvFilter) -> Result<()> {
+    if let Some(handle) = FILTER_HANDLE.get() {
+        handle.reload(filter).context("Failed to replace the log filter")?;
+    }
+
+    Ok(())
+}
+
+/// Span wrapping each request, so every event logged while handling it carries the request and user IDs
+pub fn request_span<B>(request: &axum::http::Request<B>) -> tracing::Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("-");
+    let user_id = crate::api::get_user_id_from_headers(request.headers());
+
+    tracing::info_span!(
+        "request",
+        request_id,
+        user_id = user_id.as_deref().unwrap_or("-"),
+        method = %request.method(),
+        path = %request.uri().path(),
+    )
+}
@@ -1,28 +1,60 @@
+pub mod cache;
+pub mod error_reporting;
+pub mod http;
+pub mod http_log;
+pub mod logging;
+pub mod rate_limit;
+pub mod time_zone;
+pub mod upstream;
+
+/// Hosts serving YouTube videos at `/watch?v=ID` and `/shorts/ID`-style paths
+const YOUTUBE_HOSTS: &[&str] = &[
+    "youtube.com",
+    "www.youtube.com",
+    "m.youtube.com",
+    "music.youtube.com",
+    "www.youtube-nocookie.com",
+];
+
+/// Paths whose next segment is the video ID, e.g. `/shorts/ID`
+const ID_PATH_PREFIXES: &[&str] = &["shorts", "embed", "live", "v"];
+
+/// Whether a string has the shape of a YouTube video ID: 11 letters, digits, `-` or `_`
+fn is_video_id(candidate: &str) -> bool {
+    candidate.len() == 11 && candidate.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
 /// Extract YouTube video ID from a URL
 pub fn extract_video_id(input: &str) -> Option<String> {
+    let input = input.trim();
+    
     // Handle direct video IDs (11 characters)
-    if input.len() == 11 && !input.contains('/') && !input.contains('?') {
+    if is_video_id(input) {
         return Some(input.to_string());
     }
     
-    // Try to parse as URL
-    if let Ok(url) = url::Url::parse(input) {
+    // Try to parse as URL, adding the scheme people leave out when pasting
+    let url = url::Url::parse(input)
+        .or_else(|_| url::Url::parse(&format!("https://{}", input)))
+        .ok()?;
+    let host = url.host_str()?;
+    let mut segments = url.path_segments()?.filter(|segment| !segment.is_empty());
+    
+    let candidate = if host == "youtu.be" {
         // Handle youtu.be format
-        if url.host_str() == Some("youtu.be") {
-            return url.path_segments()
-                .and_then(|segments| segments.last())
-                .map(|s| s.to_string());
-        }
-        
-        // Handle youtube.com format
-        if url.host_str() == Some("youtube.com") || url.host_str() == Some("www.youtube.com") {
-            return url.query_pairs()
-                .find(|(key, _)| key == "v")
-                .map(|(_, value)| value.to_string());
+        segments.next().map(|s| s.to_string())
+    } else if YOUTUBE_HOSTS.contains(&host) {
+        // Handle youtube.com formats: /watch?v=ID and /shorts/ID, /embed/ID, /live/ID
+        match segments.next() {
+            Some("watch") => url.query_pairs().find(|(key, _)| key == "v").map(|(_, value)| value.to_string()),
+            Some(prefix) if ID_PATH_PREFIXES.contains(&prefix) => segments.next().map(|s| s.to_string()),
+            _ => None,
         }
-    }
+    } else {
+        None
+    };
     
-    None
+    candidate.filter(|candidate| is_video_id(candidate))
 }
 
 #[cfg(test)]
@@ -49,7 +81,21 @@ mod tests {
             Some("dQw4w9WgXcQ".to_string())
         );
         
+        // Other youtube.com formats, with and without the scheme
+        for url in [
+            "https://m.youtube.com/watch?v=dQw4w9WgXcQ&t=42s",
+            "https://www.youtube.com/shorts/dQw4w9WgXcQ",
+            "https://www.youtube.com/embed/dQw4w9WgXcQ",
+            "https://www.youtube.com/live/dQw4w9WgXcQ?si=abc",
+            "youtu.be/dQw4w9WgXcQ?si=abc",
+            " www.youtube.com/watch?v=dQw4w9WgXcQ ",
+        ] {
+            assert_eq!(extract_video_id(url), Some("dQw4w9WgXcQ".to_string()), "{}", url);
+        }
+        
         // Invalid input
         assert_eq!(extract_video_id("not-a-video-id"), None);
+        assert_eq!(extract_video_id("https://www.youtube.com/@channel"), None);
+        assert_eq!(extract_video_id("https://vimeo.com/dQw4w9WgXcQ"), None);
     }
 }
@@ -0,0 +1,85 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Spaces out calls so they never exceed a rate, however many tasks share the limiter
+pub struct RateLimiter {
+    interval_nanos: AtomicU64,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    /// Create a limiter allowing `per_second` calls per second (at least one)
+    pub fn new(per_second: u32) -> Self {
+        let limiter = Self {
+            interval_nanos: AtomicU64::new(0),
+            next_slot: Mutex::new(Instant::now()),
+        };
+        limiter.set_rate(per_second);
+        limiter
+    }
+
+    /// Change the rate (at least one call per second); applies from the next call
+    pub fn set_rate(&self, per_second: u32) {
+        let interval = Duration::from_secs(1) / per_second.max(1);
+        self.interval_nanos.store(interval.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Wait until the next call is allowed
+    pub async fn acquire(&self) {
+        let interval = Duration::from_nanos(self.interval_nanos.load(Ordering::Relaxed));
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(Instant::now());
+            *next_slot = slot + interval;
+            slot
+        };
+
+        tokio::time::sleep_until(slot).await;
+    }
+
+    /// The current rate and how long a call made now would wait
+    pub async fn state(&self) -> RateLimitState {
+        let interval = Duration::from_nanos(self.interval_nanos.load(Ordering::Relaxed));
+        let wait = self.next_slot.lock().await.saturating_duration_since(Instant::now());
+
+        RateLimitState {
+            requests_per_sec: (Duration::from_secs(1).as_nanos() / interval.as_nanos().max(1)) as u32,
+            wait_ms: wait.as_millis() as u64,
+        }
+    }
+}
+
+/// A snapshot of a rate limiter
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RateLimitState {
+    /// Calls allowed per second
+    pub requests_per_sec: u32,
+
+    /// How long a call made now would wait for its slot (in milliseconds)
+    pub wait_ms: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_spaces_calls() {
+        let limiter = RateLimiter::new(100);
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+
+        // The first call goes through immediately, the next two wait 10ms each
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        let state = limiter.state().await;
+        assert_eq!(state.requests_per_sec, 100);
+        assert!(state.wait_ms <= 10);
+    }
+}
@@ -0,0 +1,70 @@
+//! Days and clock times in a user's time zone.
+//!
+//! Timestamps are stored in UTC; what counts as "today", when a day's
+//! analytics start and when a reply scheduled for 9:00 goes out depend on the
+//! user's time zone.
+
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// An IANA time zone such as `Europe/Berlin`
+pub fn parse(name: &str) -> Option<Tz> {
+    name.trim().parse().ok()
+}
+
+/// The day a moment falls on in the time zone
+pub fn local_day(timestamp: DateTime<Utc>, tz: Tz) -> NaiveDate {
+    timestamp.with_timezone(&tz).date_naive()
+}
+
+/// The moment a day starts in the time zone
+pub fn start_of_day(day: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    to_utc(day.and_hms_opt(0, 0, 0).expect("midnight is a valid time"), tz)
+}
+
+/// The moment the month of a day starts in the time zone
+pub fn start_of_month(day: NaiveDate, tz: Tz) -> DateTime<Utc> {
+    start_of_day(day - Duration::days(day.day0().into()), tz)
+}
+
+/// A local date and time as a moment.
+///
+/// A time the clocks skip is moved an hour later, and a time they repeat is
+/// taken the first time round.
+pub fn to_utc(local: NaiveDateTime, tz: Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(t) | LocalResult::Ambiguous(t, _) => t.with_timezone(&Utc),
+        LocalResult::None => to_utc(local + Duration::hours(1), tz),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(text: &str) -> DateTime<Utc> {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_local_day_and_its_start() {
+        let berlin = parse("Europe/Berlin").unwrap();
+        let day = local_day(utc("2024-03-10T23:30:00Z"), berlin);
+        assert_eq!(day, NaiveDate::from_ymd_opt(2024, 3, 11).unwrap());
+        assert_eq!(start_of_day(day, berlin), utc("2024-03-10T23:00:00Z"));
+        assert_eq!(start_of_month(day, berlin), utc("2024-02-29T23:00:00Z"));
+
+        assert_eq!(parse("Mars/Olympus_Mons"), None);
+    }
+
+    #[test]
+    fn test_clock_changes() {
+        let berlin = parse("Europe/Berlin").unwrap();
+        // 02:30 doesn't exist on the last Sunday of March
+        let skipped = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(to_utc(skipped, berlin), utc("2024-03-31T01:30:00Z"));
+        // and happens twice on the last Sunday of October
+        let repeated = NaiveDate::from_ymd_opt(2024, 10, 27).unwrap().and_hms_opt(2, 30, 0).unwrap();
+        assert_eq!(to_utc(repeated, berlin), utc("2024-10-27T00:30:00Z"));
+    }
+}
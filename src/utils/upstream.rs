@@ -0,0 +1,465 @@
+use anyhow::Result;
+use reqwest::{RequestBuilder, Response, StatusCode};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+
+use crate::error::AppError;
+use crate::utils::error_reporting;
+use crate::utils::http::{env_parse, ProxyOverride};
+
+/// Timeout, retry and circuit-breaker settings for one upstream API
+#[derive(Debug, Clone)]
+pub struct UpstreamConfig {
+    /// Timeout for each attempt, including reading the response
+    pub timeout: Duration,
+
+    /// Retries after a failed attempt (connection error, timeout, 429 or 5xx)
+    pub max_retries: u32,
+
+    /// Wait before the first retry; doubled for each further retry
+    pub retry_backoff: Duration,
+
+    /// Consecutive failed calls that open the circuit
+    pub failure_threshold: u32,
+
+    /// How long an open circuit rejects calls before letting a probe through
+    pub open_duration: Duration,
+
+    /// Proxy for this upstream's requests, if not the one every request goes through
+    pub proxy: ProxyOverride,
+}
+
+impl UpstreamConfig {
+    /// Read the configuration from `<PREFIX>_TIMEOUT_SECS`, `<PREFIX>_MAX_RETRIES`,
+    /// `<PREFIX>_BREAKER_FAILURES`, `<PREFIX>_BREAKER_OPEN_SECS` and `<PREFIX>_PROXY_URL`,
+    /// falling back to the defaults with the given timeout
+    pub fn from_env(prefix: &str, default_timeout: Duration) -> Result<Self> {
+        Ok(Self {
+            timeout: env_parse::<u64>(&format!("{}_TIMEOUT_SECS", prefix))?
+                .map(Duration::from_secs)
+                .unwrap_or(default_timeout),
+            max_retries: env_parse(&format!("{}_MAX_RETRIES", prefix))?.unwrap_or(2),
+            retry_backoff: Duration::from_millis(500),
+            failure_threshold: env_parse(&format!("{}_BREAKER_FAILURES", prefix))?.unwrap_or(5),
+            open_duration: env_parse::<u64>(&format!("{}_BREAKER_OPEN_SECS", prefix))?
+                .map(Duration::from_secs)
+                .unwrap_or(Duration::from_secs(30)),
+            proxy: ProxyOverride::from_env(prefix),
+        })
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// A single probe call is in flight after the circuit was open
+    HalfOpen,
+    /// Calls are rejected without reaching the upstream
+    Open,
+}
+
+impl CircuitState {
+    /// Gauge value exported in metrics
+    fn as_gauge(self) -> u8 {
+        match self {
+            CircuitState::Closed => 0,
+            CircuitState::HalfOpen => 1,
+            CircuitState::Open => 2,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BreakerInner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+}
+
+/// Stops calling an upstream after repeated failures, so callers fail fast instead of piling up on timeouts
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    inner: Mutex<BreakerInner>,
+    failure_threshold: u32,
+    open_duration: Duration,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            inner: Mutex::new(BreakerInner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            }),
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+        }
+    }
+
+    /// Permission for a call, if it may go through; an open circuit lets one probe through once it has cooled down
+    pub fn allow(&self) -> Option<Permit<'_>> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+
+        let probe = match inner.state {
+            CircuitState::Closed => false,
+            CircuitState::HalfOpen => return None,
+            CircuitState::Open => {
+                if inner.opened_at.is_some_and(|at| at.elapsed() < self.open_duration) {
+                    return None;
+                }
+                inner.state = CircuitState::HalfOpen;
+                true
+            }
+        };
+        Some(Permit { breaker: self, probe, reported: false })
+    }
+
+    /// Record a successful call, closing the circuit
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    /// Record a failed call, opening the circuit if the threshold is reached or the probe failed
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures += 1;
+
+        if inner.state == CircuitState::HalfOpen || inner.consecutive_failures >= self.failure_threshold {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Current state
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner()).state
+    }
+
+    /// Reopen the circuit after a probe that never reported, so another is let through once it cools down again
+    fn abandon_probe(&self) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner.state == CircuitState::HalfOpen {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+/// A call let through by a [`CircuitBreaker`], to be reported as a success or a failure.
+///
+/// A probe can be dropped mid-flight, e.g. when the client disconnects; if it
+/// never reports, dropping it reopens the circuit rather than leaving it half-open for good.
+#[derive(Debug)]
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    reported: bool,
+}
+
+impl Permit<'_> {
+    /// Report a successful call
+    pub fn success(mut self) {
+        self.reported = true;
+        self.breaker.record_success();
+    }
+
+    /// Report a failed call
+    pub fn failure(mut self) {
+        self.reported = true;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.reported {
+            self.breaker.abandon_probe();
+        }
+    }
+}
+
+/// Whether a call may be repeated after a failed attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attempts {
+    /// Retried: reads and other requests that are safe to repeat
+    Retry,
+
+    /// Sent once: writes that must not be repeated, e.g. posting a reply
+    Once,
+}
+
+/// An upstream API with its own timeout, retries and circuit breaker
+#[derive(Debug)]
+pub struct Upstream {
+    name: &'static str,
+    config: UpstreamConfig,
+    breaker: CircuitBreaker,
+    requests: AtomicU64,
+    failures: AtomicU64,
+    rejected: AtomicU64,
+    /// Error responses handed to callers, by status
+    error_responses: Mutex<BTreeMap<u16, u64>>,
+}
+
+impl Upstream {
+    /// Create an upstream with a closed circuit
+    pub fn new(name: &'static str, config: UpstreamConfig) -> Self {
+        Self {
+            name,
+            breaker: CircuitBreaker::new(config.failure_threshold, config.open_duration),
+            config,
+            requests: AtomicU64::new(0),
+            failures: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            error_responses: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Send a request and hand back its response if it succeeded.
+    ///
+    /// An error response is logged with its body, counted by status and turned
+    /// into the caller's error by `classify`, which gets the status and
+    /// `"<action>: <body>"`, e.g. `"Failed to post reply: ..."`.
+    pub async fn call(
+        &self,
+        request: RequestBuilder,
+        attempts: Attempts,
+        action: &str,
+        classify: impl FnOnce(StatusCode, String) -> AppError,
+    ) -> Result<Response> {
+        let response = match attempts {
+            Attempts::Retry => self.send(request).await?,
+            Attempts::Once => self.send_once(request).await?,
+        };
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        *self.error_responses.lock().unwrap_or_else(|e| e.into_inner()).entry(status.as_u16()).or_default() += 1;
+        error!(upstream = self.name, status = status.as_u16(), "{}: {}", action, body);
+        Err(classify(status, format!("{}: {}", action, body)).into())
+    }
+
+    /// Send a request, retrying failed attempts.
+    ///
+    /// Only use this for requests that are safe to repeat; see [`Upstream::send_once`].
+    /// Error responses that aren't worth retrying (e.g. 400 or 403) are returned for
+    /// the caller to handle as before.
+    pub async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        self.send_with_retries(request, self.config.max_retries).await
+    }
+
+    /// Send a request without retrying, for writes that must not be repeated (e.g. posting a reply)
+    pub async fn send_once(&self, request: RequestBuilder) -> Result<Response> {
+        self.send_with_retries(request, 0).await
+    }
+
+    /// Current state of this upstream's circuit breaker
+    pub fn state(&self) -> CircuitState {
+        self.breaker.state()
+    }
+
+    /// How this upstream's requests are proxied
+    pub fn proxy(&self) -> &ProxyOverride {
+        &self.config.proxy
+    }
+
+    /// The setting overriding this upstream's proxy, e.g. `OPENAI_PROXY_URL`
+    pub fn proxy_variable(&self) -> String {
+        format!("{}_PROXY_URL", self.name.to_uppercase())
+    }
+
+    async fn send_with_retries(&self, mut request: RequestBuilder, max_retries: u32) -> Result<Response> {
+        let Some(permit) = self.breaker.allow() else {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            return Err(AppError::Unavailable(format!("{} is unavailable (circuit open after repeated failures)", self.name)).into());
+        };
+
+        let mut backoff = self.config.retry_backoff;
+        let mut attempt = 0;
+
+        loop {
+            // Keep a copy for the next attempt; bodies that can't be cloned (streams) aren't retried
+            let (current, spare) = if attempt < max_retries {
+                match request.try_clone() {
+                    Some(clone) => (clone, Some(request)),
+                    None => (request, None),
+                }
+            } else {
+                (request, None)
+            };
+
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            let start = Instant::now();
+            let result = current.timeout(self.config.timeout).send().await;
+            info!(
+                upstream = self.name,
+                status = result.as_ref().map(|r| r.status().as_u16()).unwrap_or(0),
+                latency_ms = start.elapsed().as_millis() as u64,
+                attempt = attempt + 1,
+                "Upstream call"
+            );
+
+            let failed = match &result {
+                Ok(response) => is_retryable_status(response.status()),
+                Err(_) => true,
+            };
+
+            if !failed {
+                permit.success();
+                return Ok(result?);
+            }
+
+            self.failures.fetch_add(1, Ordering::Relaxed);
+            error_reporting::upstream_failure(
+                self.name,
+                result.as_ref().ok().map(|r| r.status().as_u16()),
+                start.elapsed().as_millis() as u64,
+                attempt + 1,
+            );
+
+            match spare {
+                Some(spare) => {
+                    warn!("{} request failed (attempt {}), retrying in {:?}", self.name, attempt + 1, backoff);
+                    tokio::time::sleep(backoff).await;
+                    request = spare;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                None => {
+                    // Out of retries: hand back the last outcome for the caller to report
+                    permit.failure();
+                    return Ok(result?);
+                }
+            }
+        }
+    }
+
+    /// Write this upstream's metrics in the Prometheus text format
+    fn write_metrics(&self, out: &mut String) {
+        let _ = writeln!(out, "upstream_circuit_state{{upstream=\"{}\"}} {}", self.name, self.breaker.state().as_gauge());
+        let _ = writeln!(out, "upstream_requests_total{{upstream=\"{}\"}} {}", self.name, self.requests.load(Ordering::Relaxed));
+        let _ = writeln!(out, "upstream_failures_total{{upstream=\"{}\"}} {}", self.name, self.failures.load(Ordering::Relaxed));
+        let _ = writeln!(out, "upstream_rejected_total{{upstream=\"{}\"}} {}", self.name, self.rejected.load(Ordering::Relaxed));
+        for (status, count) in self.error_responses.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let _ = writeln!(out, "upstream_error_responses_total{{upstream=\"{}\",status=\"{}\"}} {}", self.name, status, count);
+        }
+    }
+}
+
+/// The upstream APIs the server calls, each configured and broken independently
+#[derive(Debug, Clone)]
+pub struct Upstreams {
+    /// YouTube Data API (`YOUTUBE_*` settings)
+    pub youtube: Arc<Upstream>,
+
+    /// Google OAuth token and user info endpoints (`GOOGLE_OAUTH_*` settings)
+    pub google_oauth: Arc<Upstream>,
+
+    /// OpenAI chat completions (`OPENAI_*` settings)
+    pub openai: Arc<Upstream>,
+}
+
+impl Upstreams {
+    /// Read every upstream's configuration from the environment
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            youtube: Arc::new(Upstream::new("youtube", UpstreamConfig::from_env("YOUTUBE", Duration::from_secs(15))?)),
+            google_oauth: Arc::new(Upstream::new(
+                "google_oauth",
+                UpstreamConfig::from_env("GOOGLE_OAUTH", Duration::from_secs(10))?,
+            )),
+            // Completions are slow to generate, so they get the longest timeout
+            openai: Arc::new(Upstream::new("openai", UpstreamConfig::from_env("OPENAI", Duration::from_secs(60))?)),
+        })
+    }
+
+    /// Render breaker states (0 closed, 1 half-open, 2 open), call counters and error responses by status in the Prometheus text format
+    pub fn render_metrics(&self) -> String {
+        let mut out = String::new();
+        out.push_str("# TYPE upstream_circuit_state gauge\n");
+        out.push_str("# TYPE upstream_requests_total counter\n");
+        out.push_str("# TYPE upstream_failures_total counter\n");
+        out.push_str("# TYPE upstream_rejected_total counter\n");
+        out.push_str("# TYPE upstream_error_responses_total counter\n");
+
+        for upstream in [&self.youtube, &self.google_oauth, &self.openai] {
+            upstream.write_metrics(&mut out);
+        }
+
+        out
+    }
+}
+
+fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_opens_and_probes() {
+        let breaker = CircuitBreaker::new(2, Duration::ZERO);
+        assert!(breaker.allow().is_some());
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // Cooled down: one probe goes through, others wait for its outcome
+        let probe = breaker.allow().unwrap();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allow().is_none());
+
+        // A failed probe reopens the circuit, a successful one closes it
+        probe.failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        breaker.allow().unwrap().success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_open_circuit_rejects_until_cooled_down() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker.record_failure();
+        assert!(breaker.allow().is_none());
+    }
+
+    #[test]
+    fn test_dropped_probe_reopens_the_circuit() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        breaker.record_failure();
+        std::thread::sleep(Duration::from_millis(30));
+
+        // The probe's caller goes away before it reports
+        drop(breaker.allow().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(breaker.allow().is_none());
+
+        // Cooled down again: the next probe goes through
+        std::thread::sleep(Duration::from_millis(30));
+        breaker.allow().unwrap().success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // Dropping a call while the circuit is closed changes nothing
+        drop(breaker.allow().unwrap());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
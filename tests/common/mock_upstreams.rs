@@ -0,0 +1,183 @@
+//! The real services pointed at a wiremock server standing in for Google OAuth,
+//! the YouTube Data API and OpenAI.
+
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use wiremock::MockServer;
+
+use youtube_commenter::db::{self, Database};
+use youtube_commenter::models::auth::AuthToken;
+use youtube_commenter::services::ai::AiService;
+use youtube_commenter::services::auth::AuthService;
+use youtube_commenter::services::events::EventBus;
+use youtube_commenter::services::notifications::NotificationService;
+use youtube_commenter::services::prompts::PromptLibrary;
+use youtube_commenter::services::quota::QuotaTracker;
+use youtube_commenter::services::rules::RuleService;
+use youtube_commenter::services::settings::SettingsService;
+use youtube_commenter::services::spam::SpamService;
+use youtube_commenter::services::youtube::YouTubeService;
+use youtube_commenter::utils::upstream::{Upstream, UpstreamConfig, Upstreams};
+
+use super::USER_ID;
+
+/// Access token of a signed-in user whose token is still valid
+pub const ACCESS_TOKEN: &str = "valid-access-token";
+
+/// Refresh token stored for the signed-in user
+pub const REFRESH_TOKEN: &str = "valid-refresh-token";
+
+/// The services read their base URLs from the environment when created, and
+/// tests run in parallel, so setting the variables and creating the services
+/// must happen together
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+pub struct MockUpstreams {
+    pub server: MockServer,
+    pub db: Database,
+    pub upstreams: Upstreams,
+    pub auth: Arc<AuthService>,
+    pub youtube: YouTubeService,
+    pub ai: AiService,
+    pub events: Arc<EventBus>,
+}
+
+impl MockUpstreams {
+    /// Start a mock server and create the services against it, with one retry and no real backoff
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let db = db::init_db().await.expect("Failed to initialize database");
+        let client = reqwest::Client::new();
+
+        let config = UpstreamConfig {
+            timeout: std::time::Duration::from_secs(5),
+            max_retries: 1,
+            retry_backoff: std::time::Duration::from_millis(10),
+            failure_threshold: 5,
+            open_duration: std::time::Duration::from_secs(30),
+            proxy: Default::default(),
+        };
+        let upstreams = Upstreams {
+            youtube: Arc::new(Upstream::new("youtube", config.clone())),
+            google_oauth: Arc::new(Upstream::new("google_oauth", config.clone())),
+            openai: Arc::new(Upstream::new("openai", config)),
+        };
+
+        let events = Arc::new(EventBus::new());
+        let (auth, youtube, ai) = {
+            let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            std::env::set_var("YOUTUBE_OAUTH_CLIENT_ID", "test-client");
+            std::env::set_var("YOUTUBE_OAUTH_CLIENT_SECRET", "test-secret");
+            std::env::set_var("YOUTUBE_OAUTH_REDIRECT_URI", "http://localhost:3000/api/auth/callback");
+            std::env::set_var("OPENAI_API_KEY", "test-openai-key");
+            std::env::set_var("GOOGLE_OAUTH_TOKEN_URL", format!("{}/token", server.uri()));
+            std::env::set_var("GOOGLE_USERINFO_URL", format!("{}/oauth2/v1/userinfo", server.uri()));
+            std::env::set_var("YOUTUBE_API_BASE_URL", format!("{}/youtube/v3", server.uri()));
+            std::env::set_var("OPENAI_API_BASE_URL", format!("{}/v1", server.uri()));
+
+            let auth = Arc::new(
+                AuthService::new(db.clone(), client.clone(), upstreams.google_oauth.clone())
+                    .expect("Failed to create auth service"),
+            );
+            let notifications = Arc::new(
+                NotificationService::new(db.clone(), client.clone()).expect("Failed to create notification service"),
+            );
+            let youtube = YouTubeService::new(
+                db.clone(),
+                client.clone(),
+                upstreams.youtube.clone(),
+                auth.clone(),
+                Arc::new(QuotaTracker::new(db.clone())),
+                notifications,
+                Arc::new(SettingsService::new(db.clone())),
+                Arc::new(RuleService::new(db.clone())),
+                Arc::new(SpamService::new(db.clone())),
+                events.clone(),
+            );
+            let prompts = Arc::new(PromptLibrary::new(db.clone()));
+            let ai = AiService::new(db.clone(), client, upstreams.openai.clone(), prompts);
+            (auth, youtube, ai)
+        };
+
+        ai.init_default_models().await.expect("Failed to store AI models");
+
+        Self { server, db, upstreams, auth, youtube, ai, events }
+    }
+
+    /// Store tokens for [`USER_ID`] that expire after `expires_in`
+    pub async fn sign_in(&self, expires_in: Duration) {
+        let token = AuthToken {
+            access_token: ACCESS_TOKEN.to_string(),
+            refresh_token: REFRESH_TOKEN.to_string(),
+            expires_at: Utc::now() + expires_in,
+            token_type: "Bearer".to_string(),
+            scopes: vec!["https://www.googleapis.com/auth/youtube.force-ssl".to_string()],
+        };
+        self.db.save_auth_token(USER_ID, &token).await.expect("Failed to store token");
+    }
+}
+
+/// A token endpoint response
+pub fn token_response(access_token: &str) -> Value {
+    json!({
+        "access_token": access_token,
+        "refresh_token": "new-refresh-token",
+        "expires_in": 3600,
+        "token_type": "Bearer",
+        "scope": "https://www.googleapis.com/auth/youtube.force-ssl",
+    })
+}
+
+/// A comment resource as the YouTube API returns it
+pub fn comment_item(id: &str, text: &str) -> Value {
+    json!({
+        "id": id,
+        "snippet": {
+            "authorDisplayName": "Viewer",
+            "authorChannelId": { "value": "UCviewer" },
+            "textDisplay": text,
+            "likeCount": 2,
+            "publishedAt": "2024-01-01T00:00:00Z",
+        },
+    })
+}
+
+/// A comment thread with `total_reply_count` replies, none of them inline
+pub fn comment_thread(id: &str, text: &str, total_reply_count: i32) -> Value {
+    json!({
+        "id": id,
+        "snippet": {
+            "totalReplyCount": total_reply_count,
+            "topLevelComment": { "snippet": comment_item(id, text)["snippet"] },
+        },
+    })
+}
+
+/// A list response with an optional next page
+pub fn page(items: Vec<Value>, next_page_token: Option<&str>) -> Value {
+    json!({ "items": items, "nextPageToken": next_page_token })
+}
+
+/// An OpenAI chat completion
+pub fn chat_completion(content: &str) -> Value {
+    json!({
+        "id": "chatcmpl-1",
+        "choices": [{
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop",
+        }],
+        "usage": { "prompt_tokens": 120, "completion_tokens": 12, "total_tokens": 132 },
+    })
+}
+
+/// A YouTube API error body
+pub fn youtube_error(code: u16, reason: &str) -> Value {
+    json!({
+        "error": {
+            "code": code,
+            "message": reason,
+            "errors": [{ "reason": reason, "domain": "youtube.commentThread" }],
+        },
+    })
+}
@@ -0,0 +1,600 @@
+//! Fakes for the upstream-facing services and a fixture builder for handler tests.
+//!
+//! The fakes stand in for Google, YouTube and OpenAI; everything else runs for
+//! real against a fresh in-memory database per test.
+
+#![allow(dead_code)]
+
+pub mod mock_upstreams;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use axum::body::{Body, Bytes};
+use axum::http::{header, HeaderMap, Method, Request, StatusCode};
+use chrono::{Duration, Utc};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tower::ServiceExt;
+
+use youtube_commenter::api::{self, AppState};
+use youtube_commenter::db::{self, Database};
+use youtube_commenter::error::AppError;
+use youtube_commenter::models::ai::{AiUsageStats, ReplyGenerationRequest, ReplyGenerationResponse};
+use youtube_commenter::models::auth::{AuthToken, ReplyTone, Session, User, UserPreferences};
+use youtube_commenter::models::moderation::ModerationStatus;
+use youtube_commenter::models::video::{MonitorSettings, ReplyDefaults, Video, VideoFormat};
+use youtube_commenter::models::preflight::{PreflightCheck, PreflightReport};
+use youtube_commenter::models::{Comment, CommentBuilder, Reply};
+use youtube_commenter::services::ai::AiApi;
+use youtube_commenter::services::analytics::AnalyticsService;
+use youtube_commenter::services::auth::AuthApi;
+use youtube_commenter::services::collections::CollectionService;
+use youtube_commenter::services::commenters::CommenterService;
+use youtube_commenter::services::dashboard::DashboardService;
+use youtube_commenter::services::duplicates::DuplicateService;
+use youtube_commenter::services::events::EventBus;
+use youtube_commenter::services::inbox::InboxProjection;
+use youtube_commenter::services::live::LiveFeed;
+use youtube_commenter::services::monitor::CommentMonitor;
+use youtube_commenter::services::sharing::ShareLinks;
+use youtube_commenter::services::jobs::JobService;
+use youtube_commenter::services::mutes::MuteService;
+use youtube_commenter::services::notifications::NotificationService;
+use youtube_commenter::services::outbox::Outbox;
+use youtube_commenter::services::prompts::PromptLibrary;
+use youtube_commenter::services::reply_checks::ReplyChecker;
+use youtube_commenter::services::retention::{Pruner, RetentionConfig};
+use youtube_commenter::services::rules::RuleService;
+use youtube_commenter::services::quota::QuotaTracker;
+use youtube_commenter::services::saved_replies::SavedReplyService;
+use youtube_commenter::services::settings::SettingsService;
+use youtube_commenter::services::spam::SpamService;
+use youtube_commenter::services::tones::ToneService;
+use youtube_commenter::services::youtube::{YouTubeApi, YouTubeVideo};
+use youtube_commenter::utils::http_log::HttpLog;
+use youtube_commenter::utils::rate_limit::RateLimitState;
+use youtube_commenter::utils::upstream::Upstreams;
+
+/// The user the fixtures belong to
+pub const USER_ID: &str = "user-1";
+
+/// Authorization code the fake auth service rejects
+pub const BAD_CODE: &str = "bad-code";
+
+/// Text of every reply the fake AI generates
+pub const AI_REPLY: &str = "Thanks for watching!";
+
+/// Model the fake AI reports
+pub const AI_MODEL: &str = "fake-model";
+
+/// Google sign-in without Google
+pub struct FakeAuth {
+    db: Database,
+}
+
+#[async_trait]
+impl AuthApi for FakeAuth {
+    fn get_authorization_url(&self) -> String {
+        "https://accounts.example.com/o/oauth2/auth".to_string()
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<AuthToken> {
+        if code == BAD_CODE {
+            return Err(AppError::UpstreamAuth("invalid_grant".to_string()).into());
+        }
+
+        Ok(AuthToken {
+            access_token: format!("access-{}", code),
+            refresh_token: format!("refresh-{}", code),
+            expires_at: Utc::now() + Duration::hours(1),
+            token_type: "Bearer".to_string(),
+            scopes: vec!["https://www.googleapis.com/auth/youtube.force-ssl".to_string()],
+        })
+    }
+
+    async fn create_or_update_user(&self, token: &AuthToken) -> Result<User> {
+        let user = user(USER_ID);
+        self.db.save_user(&user).await?;
+        self.db.save_auth_token(&user.id, token).await?;
+        Ok(user)
+    }
+
+    fn get_reconnect_url(&self, user: &User) -> String {
+        format!("https://accounts.example.com/o/oauth2/auth?state=reconnect.{}", user.id)
+    }
+
+    async fn reconnect_user(&self, user_id: &str, token: &AuthToken) -> Result<User> {
+        let mut user = self.db.get_user(user_id).await?
+            .ok_or_else(|| AppError::NotFound(format!("User {}", user_id)))?;
+        user.connection = Default::default();
+        self.db.save_user(&user).await?;
+        self.db.save_auth_token(&user.id, token).await?;
+        Ok(user)
+    }
+
+    async fn create_session(&self, user_id: &str, ip_address: &str, user_agent: &str) -> Result<Session> {
+        let now = Utc::now();
+        let session = Session {
+            id: format!("session-{}", user_id),
+            user_id: user_id.to_string(),
+            created_at: now,
+            expires_at: now + Duration::days(7),
+            ip_address: ip_address.to_string(),
+            user_agent: user_agent.to_string(),
+            is_active: true,
+        };
+        self.db.create_session(&session).await?;
+        Ok(session)
+    }
+}
+
+/// YouTube serving canned comments and videos, recording the replies it is asked to post
+pub struct FakeYouTube {
+    db: Database,
+    comments: HashMap<String, Vec<Comment>>,
+    videos: Vec<YouTubeVideo>,
+    posted: Mutex<Vec<Reply>>,
+    rejected: Mutex<Vec<(String, bool)>>,
+    moderated: Mutex<Vec<(String, &'static str)>>,
+    updated: Mutex<Vec<(String, String)>>,
+    removed: Mutex<HashSet<String>>,
+}
+
+impl FakeYouTube {
+    /// The replies posted so far
+    pub fn posted(&self) -> Vec<Reply> {
+        self.posted.lock().unwrap().clone()
+    }
+
+    /// Stop showing a posted reply, as YouTube's spam filter does
+    pub fn remove(&self, reply_id: &str) {
+        self.removed.lock().unwrap().insert(reply_id.to_string());
+    }
+
+    /// The comments rejected so far, with whether their author was banned
+    pub fn rejected(&self) -> Vec<(String, bool)> {
+        self.rejected.lock().unwrap().clone()
+    }
+
+    /// The comments moderated so far, with the status set or `markAsSpam`
+    pub fn moderated(&self) -> Vec<(String, &'static str)> {
+        self.moderated.lock().unwrap().clone()
+    }
+
+    /// The replies corrected so far, with their new text
+    pub fn updated(&self) -> Vec<(String, String)> {
+        self.updated.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl YouTubeApi for FakeYouTube {
+    async fn fetch_comments(&self, user_id: &str, video_id: &str) -> Result<Vec<Comment>> {
+        self.sync_comments(user_id, video_id).await?;
+        Ok(self.db.get_comments(video_id).await?.unwrap_or_default())
+    }
+
+    async fn sync_comments(&self, user_id: &str, video_id: &str) -> Result<usize> {
+        let mut comments = self
+            .comments
+            .get(video_id)
+            .cloned()
+            .ok_or_else(|| AppError::NotFound("YouTube video or comment".to_string()))?;
+
+        // Like the real sync, new comments go through the user's filter rules
+        let stored = self.db.get_comments(video_id).await?.unwrap_or_default();
+        let new_ids = comments
+            .iter()
+            .map(|c| c.comment_id.clone())
+            .filter(|id| !stored.iter().any(|c| &c.comment_id == id))
+            .collect();
+        RuleService::new(self.db.clone()).apply(user_id, &mut comments, &new_ids).await?;
+
+        self.db.save_comments(video_id, &comments).await?;
+        Ok(comments.len())
+    }
+
+    async fn get_thread_replies(&self, _user_id: &str, comment_id: &str) -> Result<Option<Vec<Reply>>> {
+        Ok(self.db.get_comment(comment_id).await?.map(|c| c.replies))
+    }
+
+    async fn backfill_replies(&self, _user_id: &str, _video_id: &str) -> Result<usize> {
+        Ok(0)
+    }
+
+    async fn post_reply(&self, _user_id: &str, comment_id: &str, text: &str) -> Result<Reply> {
+        let mut posted = self.posted.lock().unwrap();
+        let reply = reply(comment_id, &format!("{}.reply-{}", comment_id, posted.len()), text);
+        posted.push(reply.clone());
+        Ok(reply)
+    }
+
+    async fn update_reply(&self, _user_id: &str, reply_id: &str, parent_id: &str, text: &str) -> Result<Reply> {
+        self.updated.lock().unwrap().push((reply_id.to_string(), text.to_string()));
+        Ok(reply(parent_id, reply_id, text))
+    }
+
+    async fn get_channel_videos(&self, _user_id: &str) -> Result<Vec<YouTubeVideo>> {
+        Ok(self.videos.clone())
+    }
+
+    async fn reject_comment(&self, _user_id: &str, comment_id: &str, ban_author: bool) -> Result<()> {
+        self.rejected.lock().unwrap().push((comment_id.to_string(), ban_author));
+        Ok(())
+    }
+
+    async fn set_moderation_status(&self, _user_id: &str, comment_id: &str, status: ModerationStatus, ban_author: bool) -> Result<()> {
+        if status == ModerationStatus::Rejected {
+            self.rejected.lock().unwrap().push((comment_id.to_string(), ban_author));
+        }
+        self.moderated.lock().unwrap().push((comment_id.to_string(), status.as_api()));
+        Ok(())
+    }
+
+    async fn mark_as_spam(&self, _user_id: &str, comment_id: &str) -> Result<()> {
+        self.moderated.lock().unwrap().push((comment_id.to_string(), "markAsSpam"));
+        Ok(())
+    }
+
+    async fn check_new_comments(&self, _user_id: &str) -> Result<usize> {
+        let mut new_comments = 0;
+        for (video_id, comments) in &self.comments {
+            let stored = self.db.get_comment_states(video_id).await?;
+            new_comments += comments.iter().filter(|c| !stored.contains_key(&c.comment_id)).count();
+            self.db.save_comments(video_id, comments).await?;
+        }
+        Ok(new_comments)
+    }
+
+    async fn visible_comments(&self, _user_id: &str, comment_ids: &[String]) -> Result<HashSet<String>> {
+        let removed = self.removed.lock().unwrap();
+        Ok(comment_ids.iter().filter(|id| !removed.contains(*id)).cloned().collect())
+    }
+
+    async fn rate_limit(&self) -> RateLimitState {
+        RateLimitState { requests_per_sec: 10, wait_ms: 0 }
+    }
+}
+
+/// An AI that always gives the same reply, or is unavailable as if its circuit were open
+#[derive(Default)]
+pub struct FakeAi {
+    unavailable: bool,
+}
+
+#[async_trait]
+impl AiApi for FakeAi {
+    async fn generate_reply(&self, _user_id: &str, _request: &ReplyGenerationRequest) -> Result<ReplyGenerationResponse> {
+        if self.unavailable {
+            return Err(AppError::Unavailable("openai is unavailable".to_string()).into());
+        }
+        Ok(ReplyGenerationResponse {
+            reply_text: AI_REPLY.to_string(),
+            alternatives: Vec::new(),
+            model: AI_MODEL.to_string(),
+            generated_at: Utc::now(),
+            metadata: HashMap::new(),
+            usage: AiUsageStats {
+                prompt_tokens: 40,
+                completion_tokens: 8,
+                total_tokens: 48,
+                generation_time_ms: 5,
+            },
+        })
+    }
+
+    /// Bag of words hashed into a few dimensions, so shared words make texts similar
+    async fn embed(&self, _user_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let mut vector = vec![0.0; 64];
+                for word in text.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+                    let hash = word.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64));
+                    vector[(hash % 64) as usize] += 1.0;
+                }
+                vector
+            })
+            .collect())
+    }
+}
+
+/// Builds a [`TestApp`] with the given data stored or served upstream
+#[derive(Default)]
+pub struct TestAppBuilder {
+    stored_videos: Vec<Video>,
+    stored_comments: Vec<(String, Vec<Comment>)>,
+    upstream_comments: HashMap<String, Vec<Comment>>,
+    upstream_videos: Vec<YouTubeVideo>,
+    reply_delay: std::time::Duration,
+    preflight: PreflightReport,
+    ai_unavailable: bool,
+}
+
+impl TestAppBuilder {
+    /// Report these startup check results in the health check
+    pub fn preflight(mut self, checks: Vec<PreflightCheck>) -> Self {
+        self.preflight = PreflightReport { checks, checked_at: Some(Utc::now()) };
+        self
+    }
+
+    /// Have the fake AI fail as if the provider's circuit were open
+    pub fn ai_unavailable(mut self) -> Self {
+        self.ai_unavailable = true;
+        self
+    }
+
+    /// Hold posted replies in the outbox for an undo window
+    pub fn reply_delay(mut self, delay: std::time::Duration) -> Self {
+        self.reply_delay = delay;
+        self
+    }
+
+    /// Store a video of [`USER_ID`] in the database
+    pub fn video(mut self, video_id: &str) -> Self {
+        self.stored_videos.push(video(USER_ID, video_id));
+        self
+    }
+
+    /// Store a Short belonging to the test user
+    pub fn short(mut self, video_id: &str) -> Self {
+        let mut short = video(USER_ID, video_id);
+        short.duration_secs = Some(30);
+        short.format = VideoFormat::Short;
+        self.stored_videos.push(short);
+        self
+    }
+
+    /// Store a video belonging to someone else
+    pub fn foreign_video(mut self, user_id: &str, video_id: &str) -> Self {
+        self.stored_videos.push(video(user_id, video_id));
+        self
+    }
+
+    /// Store comments in the database
+    pub fn comments(mut self, video_id: &str, comments: Vec<Comment>) -> Self {
+        self.stored_comments.push((video_id.to_string(), comments));
+        self
+    }
+
+    /// Have the fake YouTube serve comments for a video
+    pub fn upstream_comments(mut self, video_id: &str, comments: Vec<Comment>) -> Self {
+        self.upstream_comments.insert(video_id.to_string(), comments);
+        self
+    }
+
+    /// Have the fake YouTube list a video on the channel
+    pub fn upstream_video(mut self, video_id: &str) -> Self {
+        self.upstream_videos.push(YouTubeVideo {
+            id: video_id.to_string(),
+            title: format!("Video {}", video_id),
+            description: String::new(),
+            published_at: Utc::now() - Duration::days(3),
+            thumbnail_url: format!("https://i.ytimg.com/vi/{}/default.jpg", video_id),
+            duration_secs: Some(600),
+        });
+        self
+    }
+
+    pub async fn build(self) -> TestApp {
+        let db = db::init_db().await.expect("Failed to initialize database");
+        for video in &self.stored_videos {
+            db.save_video(video).await.expect("Failed to store video");
+        }
+        for (video_id, comments) in &self.stored_comments {
+            db.save_comments(video_id, comments).await.expect("Failed to store comments");
+        }
+
+        let http_client = reqwest::Client::new();
+        let quota = Arc::new(QuotaTracker::new(db.clone()));
+        let youtube = Arc::new(FakeYouTube {
+            db: db.clone(),
+            comments: self.upstream_comments,
+            videos: self.upstream_videos,
+            posted: Mutex::new(Vec::new()),
+            rejected: Mutex::new(Vec::new()),
+            moderated: Mutex::new(Vec::new()),
+            updated: Mutex::new(Vec::new()),
+            removed: Mutex::new(HashSet::new()),
+        });
+
+        let events = Arc::new(EventBus::new());
+        let inbox = Arc::new(InboxProjection::new(db.clone()));
+        inbox.clone().spawn(events.subscribe_all());
+        let live = Arc::new(LiveFeed::new(db.clone()));
+        live.clone().spawn();
+
+        let notification_service = Arc::new(
+            NotificationService::new(db.clone(), http_client.clone()).expect("Failed to create notification service"),
+        );
+        let reply_checker = Arc::new(ReplyChecker::new(
+            db.clone(),
+            youtube.clone(),
+            notification_service.clone(),
+            events.clone(),
+        ));
+
+        let tones = Arc::new(ToneService::new(db.clone()));
+        tones.seed_builtin().await.expect("Failed to seed tones");
+
+        let state = AppState {
+            db: db.clone(),
+            http_client: http_client.clone(),
+            upstreams: Upstreams::from_env().expect("Failed to configure upstreams"),
+            auth_service: Arc::new(FakeAuth { db: db.clone() }),
+            youtube_service: youtube.clone(),
+            ai_service: Arc::new(FakeAi { unavailable: self.ai_unavailable }),
+            job_service: Arc::new(JobService::new(db.clone())),
+            analytics_service: Arc::new(AnalyticsService::new(db.clone())),
+            dashboard_service: Arc::new(DashboardService::new(db.clone(), quota)),
+            notification_service,
+            prompt_library: Arc::new(PromptLibrary::new(db.clone())),
+            http_log: Arc::new(HttpLog::from_env()),
+            settings: Arc::new(SettingsService::new(db.clone())),
+            saved_replies: Arc::new(SavedReplyService::new(db.clone())),
+            duplicates: Arc::new(DuplicateService::new(db.clone())),
+            outbox: Arc::new(Outbox::with_delay(db.clone(), self.reply_delay)),
+            commenters: Arc::new(CommenterService::new(db.clone())),
+            spam: Arc::new(SpamService::new(db.clone())),
+            collections: Arc::new(CollectionService::new(db.clone())),
+            events: events.clone(),
+            inbox: inbox.clone(),
+            preflight: Arc::new(self.preflight),
+            tones,
+            mutes: Arc::new(MuteService::new(db.clone())),
+            reply_checker,
+            retention: Arc::new(Pruner::new(db.clone(), RetentionConfig::default())),
+            live,
+            monitor: Arc::new(CommentMonitor::new(db.clone(), youtube.clone())),
+            share_links: Arc::new(ShareLinks::new("test secret", "http://localhost:3000")),
+        };
+
+        TestApp { state, db, youtube }
+    }
+}
+
+/// The API router over fakes and an in-memory database
+pub struct TestApp {
+    pub state: AppState,
+    pub db: Database,
+    pub youtube: Arc<FakeYouTube>,
+}
+
+impl TestApp {
+    pub fn builder() -> TestAppBuilder {
+        TestAppBuilder::default()
+    }
+
+    /// Send a request signed in as [`USER_ID`]
+    pub async fn get(&self, uri: &str) -> TestResponse {
+        self.send(Method::GET, uri, Some(USER_ID), None).await
+    }
+
+    /// Send a JSON body signed in as [`USER_ID`]
+    pub async fn post(&self, uri: &str, body: Value) -> TestResponse {
+        self.send(Method::POST, uri, Some(USER_ID), Some(body)).await
+    }
+
+    /// Send a request, optionally signed in and with a JSON body
+    pub async fn send(&self, method: Method, uri: &str, user_id: Option<&str>, body: Option<Value>) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(user_id) = user_id {
+            request = request.header("x-session-id", user_id);
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .expect("Invalid request");
+
+        let response = api::router(self.state.clone()).oneshot(request).await.expect("Router failed");
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.expect("Failed to read body");
+
+        TestResponse { status, headers, body }
+    }
+}
+
+/// A buffered response
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!("Response is not JSON ({}): {}", e, String::from_utf8_lossy(&self.body))
+        })
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// The `error.code` of an error response
+    pub fn error_code(&self) -> String {
+        self.json()["error"]["code"].as_str().unwrap_or_default().to_string()
+    }
+}
+
+/// A user with default preferences
+pub fn user(user_id: &str) -> User {
+    let now = Utc::now();
+    User {
+        id: user_id.to_string(),
+        name: "Test Creator".to_string(),
+        email: Some("creator@example.com".to_string()),
+        profile_picture_url: None,
+        created_at: now,
+        updated_at: now,
+        preferences: UserPreferences {
+            enable_ai_replies: true,
+            ai_model: "gpt-3.5-turbo".to_string(),
+            reply_tone: ReplyTone::default(),
+            enable_notifications: false,
+            notifications: Default::default(),
+            polling_interval: 60,
+            language: None,
+            preferred_reply_language: None,
+            time_zone: None,
+            ai_disclosure: Default::default(),
+            reply_policy: Default::default(),
+            retention: Default::default(),
+            additional: Default::default(),
+        },
+        metadata: Default::default(),
+        connection: Default::default(),
+    }
+}
+
+/// A video published three days ago with the default monitor settings
+pub fn video(user_id: &str, video_id: &str) -> Video {
+    let published_at = Utc::now() - Duration::days(3);
+    Video {
+        video_id: video_id.to_string(),
+        user_id: user_id.to_string(),
+        title: format!("Video {}", video_id),
+        description: String::new(),
+        published_at,
+        thumbnail_url: format!("https://i.ytimg.com/vi/{}/default.jpg", video_id),
+        duration_secs: Some(600),
+        format: VideoFormat::LongForm,
+        monitor: MonitorSettings::for_video_age(published_at, Utc::now()),
+        last_checked_at: None,
+        synced_at: None,
+        collection_id: None,
+        reply_defaults: ReplyDefaults::default(),
+        metadata: HashMap::new(),
+    }
+}
+
+/// An unanswered top-level comment
+pub fn comment(video_id: &str, comment_id: &str, text: &str) -> Comment {
+    CommentBuilder::new(video_id, comment_id)
+        .text(text)
+        .published_at(Utc::now() - Duration::hours(1))
+        .build()
+}
+
+/// A reply in a comment thread
+pub fn reply(parent_id: &str, reply_id: &str, text: &str) -> Reply {
+    Reply {
+        reply_id: reply_id.to_string(),
+        parent_id: parent_id.to_string(),
+        author: "Test Creator".to_string(),
+        author_channel_id: "UCcreator".to_string(),
+        text: text.to_string(),
+        text_original: None,
+        like_count: 0,
+        published_at: Utc::now(),
+        ai_generated: false,
+        ai_model: None,
+        metadata: HashMap::new(),
+    }
+}
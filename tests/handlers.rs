@@ -0,0 +1,1897 @@
+//! Handler tests for every API route, over fake upstream services.
+
+mod common;
+
+use axum::http::{Method, StatusCode};
+use futures::TryStreamExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+use youtube_commenter::models::TriageState;
+use youtube_commenter::models::analytics::DailyRollup;
+use youtube_commenter::models::conversation::FollowUp;
+use youtube_commenter::models::draft::ReplyDraft;
+use youtube_commenter::models::duplicate::TEXT_HASH_KEY;
+use youtube_commenter::models::outbox::{QueueStatus, QueuedReply};
+use youtube_commenter::models::preflight::{CheckStatus, PreflightCheck};
+use youtube_commenter::models::settings::RuntimeSettingsPatch;
+use youtube_commenter::services::duplicates;
+use youtube_commenter::services::live::RecordChange;
+use youtube_commenter::services::outbox::INTERRUPTED_SEND_ERROR;
+use youtube_commenter::services::retention::PruneMode;
+
+use common::{comment, user, TestApp, AI_MODEL, AI_REPLY, BAD_CODE, USER_ID};
+
+/// Poll a job until it has stopped running
+async fn wait_for_job(app: &TestApp, job_id: &str) -> Value {
+    for _ in 0..50 {
+        let job = app.get(&format!("/api/jobs/{}", job_id)).await.json();
+        if job["status"] == "Completed" || job["status"] == "Failed" {
+            return job;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Job {} didn't finish", job_id);
+}
+
+/// The inbox listed at `uri` once `ready` holds for it, waiting for the projection to catch up
+async fn inbox_when(app: &TestApp, uri: &str, ready: impl Fn(&[Value]) -> bool) -> Vec<Value> {
+    for _ in 0..100 {
+        let entries = app.get(uri).await.json().as_array().unwrap().clone();
+        if ready(&entries) {
+            return entries;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("{} didn't catch up", uri);
+}
+
+/// The user's history entries of the comma-separated types
+async fn history_of_type(app: &TestApp, types: &str) -> Vec<Value> {
+    app.get(&format!("/api/history?type={}", types)).await.json().as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn test_root() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.send(Method::GET, "/", None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.text(), "YouTube Commenter API");
+}
+
+#[tokio::test]
+async fn test_health_check() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.send(Method::GET, "/api/health", None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!({ "status": "ok" }));
+}
+
+#[tokio::test]
+async fn test_health_check_reports_preflight_problems() {
+    let app = TestApp::builder()
+        .preflight(vec![
+            PreflightCheck::ok("database"),
+            PreflightCheck::problem("openai", CheckStatus::Failed, "OPENAI_API_KEY is not set"),
+            PreflightCheck::problem("notifications.slack", CheckStatus::Warning, "SLACK_BOT_TOKEN was rejected"),
+        ])
+        .build()
+        .await;
+
+    let response = app.send(Method::GET, "/api/health", None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["status"], "degraded");
+    let problems: Vec<&str> = body["problems"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+    assert_eq!(problems, ["openai", "notifications.slack"]);
+    assert_eq!(body["problems"][0]["status"], "failed");
+}
+
+#[tokio::test]
+async fn test_metrics() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.send(Method::GET, "/metrics", None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains("upstream_circuit_state"));
+    assert!(response.text().contains("db_statement_calls_total"));
+}
+
+#[tokio::test]
+async fn test_auth_url() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.send(Method::GET, "/api/auth/url", None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json()["url"].as_str().unwrap().starts_with("https://accounts.example.com"));
+}
+
+#[tokio::test]
+async fn test_oauth_callback_creates_session() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.send(Method::GET, "/api/auth/callback?code=abc&state=xyz", None, None).await;
+    assert!(response.status.is_redirection());
+    assert_eq!(
+        response.headers["location"],
+        format!("/auth/success?session_id=session-{}", USER_ID).as_str()
+    );
+    assert!(app.db.get_user(USER_ID).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_oauth_callback_rejected_code() {
+    let app = TestApp::builder().build().await;
+
+    let uri = format!("/api/auth/callback?code={}&state=xyz", BAD_CODE);
+    let response = app.send(Method::GET, &uri, None, None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.error_code(), "upstream_auth");
+}
+
+#[tokio::test]
+async fn test_me_of_connected_user() {
+    let app = TestApp::builder().build().await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let response = app.get("/api/me").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["user"]["id"], USER_ID);
+    assert_eq!(body["connected"], true);
+    assert!(body["reconnect_url"].is_null());
+}
+
+#[tokio::test]
+async fn test_me_of_disconnected_user() {
+    let app = TestApp::builder().build().await;
+    let mut disconnected = user(USER_ID);
+    disconnected.connection.disconnected_at = Some(chrono::Utc::now());
+    disconnected.connection.disconnect_reason = Some("invalid_grant".to_string());
+    app.db.save_user(&disconnected).await.unwrap();
+
+    let body = app.get("/api/me").await.json();
+    assert_eq!(body["connected"], false);
+    assert_eq!(body["user"]["connection"]["disconnect_reason"], "invalid_grant");
+    assert!(body["reconnect_url"].as_str().unwrap().contains(&format!("state=reconnect.{}", USER_ID)));
+}
+
+#[tokio::test]
+async fn test_bootstrap() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Nice"), comment("v1", "c2", "Great")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+    app.post("/api/inbox/rebuild", json!({})).await;
+
+    let response = app.get("/api/bootstrap").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = response.json();
+    assert_eq!(body["user"]["id"], USER_ID);
+    assert_eq!(body["connected"], true);
+    assert_eq!(body["role"], "creator");
+    assert_eq!(body["permissions"]["manage_organization"], false);
+    assert_eq!(body["permissions"]["administer"], false);
+    assert_eq!(body["features"]["ai_enabled"], true);
+    assert_eq!(body["unread_count"], 2);
+    assert_eq!(body["follow_up_count"], 0);
+
+    let response = app.send(Method::GET, "/api/bootstrap", None, None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_comment_monitor_start_and_stop() {
+    let app = TestApp::builder()
+        .video("v1")
+        .upstream_comments("v1", vec![comment("v1", "c1", "Nice"), comment("v1", "c2", "Great")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+    assert_eq!(app.get("/api/monitor").await.json()["running"], false);
+
+    let response = app.post("/api/monitor", json!({})).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["running"], true);
+    assert_eq!(response.json()["polling_interval_secs"], 60);
+
+    // The first pass runs right away
+    let mut status = app.get("/api/monitor").await.json();
+    for _ in 0..50 {
+        if !status["last_checked_at"].is_null() {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        status = app.get("/api/monitor").await.json();
+    }
+    assert_eq!(status["last_new_comments"], 2);
+    assert_eq!(app.db.get_comments("v1").await.unwrap().unwrap().len(), 2);
+
+    let response = app.send(Method::DELETE, "/api/monitor", Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["running"], false);
+    assert_eq!(response.json()["total_new_comments"], 2);
+}
+
+#[tokio::test]
+async fn test_oauth_callback_reconnects_existing_user() {
+    let app = TestApp::builder().build().await;
+    let mut disconnected = user(USER_ID);
+    disconnected.connection.disconnected_at = Some(chrono::Utc::now());
+    app.db.save_user(&disconnected).await.unwrap();
+
+    let response = app.get("/api/auth/reconnect").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json()["url"].as_str().unwrap().contains(USER_ID));
+
+    let uri = format!("/api/auth/callback?code=abc&state=reconnect.{}.nonce", USER_ID);
+    let response = app.send(Method::GET, &uri, None, None).await;
+    assert!(response.status.is_redirection());
+    assert!(!app.db.get_user(USER_ID).await.unwrap().unwrap().is_disconnected());
+    assert_eq!(app.db.get_auth_token(USER_ID).await.unwrap().unwrap().access_token, "access-abc");
+}
+
+#[tokio::test]
+async fn test_transaction_rolls_back() {
+    let app = TestApp::builder().build().await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let response = app.db
+        .with_transaction("DELETE FROM users WHERE id = $id; THROW 'failed';")
+        .bind(("id", USER_ID))
+        .await
+        .unwrap();
+    assert!(response.check().is_err());
+    assert!(app.db.get_user(USER_ID).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_live_feed_publishes_comment_changes() {
+    let app = TestApp::builder().build().await;
+    let mut changes = app.state.live.subscribe();
+
+    // The live query is registered in the background, so store the comment until it is seen
+    let mut stored = comment("v1", "c1", "Nice");
+    for attempt in 0..50 {
+        stored.like_count = attempt;
+        app.db.save_comments("v1", &[stored.clone()]).await.unwrap();
+        if let Ok(Ok(change)) = tokio::time::timeout(Duration::from_millis(100), changes.recv()).await {
+            assert_eq!(change.video_id(), "v1");
+            assert!(matches!(change, RecordChange::Comment { ref comment, .. } if comment.comment_id == "c1"));
+            return;
+        }
+    }
+    panic!("The live feed didn't publish the comment");
+}
+
+#[tokio::test]
+async fn test_requires_session() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.send(Method::GET, "/api/videos", None, None).await;
+    assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(response.error_code(), "unauthorized");
+}
+
+#[tokio::test]
+async fn test_get_videos() {
+    let app = TestApp::builder().video("v1").video("v2").video("v3").build().await;
+
+    let response = app.get("/api/videos?limit=2").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let page = response.json();
+    assert_eq!(page["videos"].as_array().unwrap().len(), 2);
+    let cursor = page["next_cursor"].as_str().unwrap().to_string();
+
+    let response = app.get(&format!("/api/videos?limit=2&cursor={}", cursor)).await;
+    let page = response.json();
+    assert_eq!(page["videos"].as_array().unwrap().len(), 1);
+    assert!(page["next_cursor"].is_null());
+
+    let response = app.get("/api/videos?cursor=garbage").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_video_monitor() {
+    let app = TestApp::builder().video("v1").foreign_video("someone-else", "v2").build().await;
+
+    let response = app.get("/api/videos/v1/monitor").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["enabled"], true);
+
+    let response = app.get("/api/videos/v2/monitor").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_video_monitor() {
+    let app = TestApp::builder().video("v1").build().await;
+
+    let body = json!({ "interval_secs": 600, "auto_reply": true });
+    let response = app.send(Method::PUT, "/api/videos/v1/monitor", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["interval_secs"], 600);
+    assert_eq!(response.json()["customized"], true);
+
+    let body = json!({ "interval_secs": 5 });
+    let response = app.send(Method::PUT, "/api/videos/v1/monitor", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.error_code(), "validation");
+}
+
+#[tokio::test]
+async fn test_video_collections() {
+    let app = TestApp::builder().video("v1").video("v2").foreign_video("someone-else", "v3").build().await;
+
+    let body = json!({ "name": "Shorts", "defaults": { "interval_secs": 30 } });
+    let response = app.post("/api/collections", body).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let body = json!({ "name": "Shorts", "defaults": { "tone": "playful", "auto_reply": true, "interval_secs": 600 } });
+    let response = app.post("/api/collections", body).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let collection_id = response.json()["collection_id"].as_str().unwrap().to_string();
+
+    let uri = format!("/api/collections/{}/videos/v1", collection_id);
+    let response = app.send(Method::PUT, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let monitor = app.get("/api/videos/v1/monitor").await.json();
+    assert_eq!(monitor["auto_reply"], true);
+    assert_eq!(monitor["interval_secs"], 600);
+    assert_eq!(monitor["customized"], true);
+    assert_eq!(app.get("/api/videos/v1/reply-defaults").await.json()["tone"], "playful");
+
+    // Other users' videos can't be added
+    let uri = format!("/api/collections/{}/videos/v3", collection_id);
+    let response = app.send(Method::PUT, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // The video's own settings override the collection's
+    let body = json!({ "persona": "host", "tone": "formal" });
+    let response = app.send(Method::PUT, "/api/videos/v1/reply-defaults", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.get("/api/videos/v1/reply-defaults").await.json()["tone"], "formal");
+
+    let response = app.get(&format!("/api/collections/{}", collection_id)).await;
+    assert_eq!(response.json()["name"], "Shorts");
+    assert_eq!(response.json()["videos"].as_array().unwrap().len(), 1);
+
+    let uri = format!("/api/collections/{}", collection_id);
+    let response = app.send(Method::DELETE, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    let video = app.db.get_video("v1").await.unwrap().unwrap();
+    assert_eq!(video.collection_id, None);
+    assert!(video.monitor.auto_reply);
+}
+
+#[tokio::test]
+async fn test_get_comments_from_database() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "First!")])
+        .build()
+        .await;
+
+    let response = app.get("/api/comments/v1").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()[0]["comment_id"], "c1");
+}
+
+#[tokio::test]
+async fn test_get_comments_by_video_url() {
+    let app = TestApp::builder()
+        .video("dQw4w9WgXcQ")
+        .comments("dQw4w9WgXcQ", vec![comment("dQw4w9WgXcQ", "c1", "First!")])
+        .build()
+        .await;
+
+    let response = app.get("/api/comments/https%3A%2F%2Fyoutu.be%2FdQw4w9WgXcQ%3Fsi%3Dabc").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()[0]["comment_id"], "c1");
+
+    let response = app.get("/api/comments/https%3A%2F%2Fvimeo.com%2F123").await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.json()["error"]["code"], "unprocessable");
+    assert!(response.json()["error"]["message"].as_str().unwrap().contains("youtu.be"));
+
+    // A token that is neither YouTube's ID shape nor a stored video is refused, not looked up
+    let response = app.get("/api/comments/some-typo").await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(response.json()["error"]["message"].as_str().unwrap().contains("11-character"));
+}
+
+#[tokio::test]
+async fn test_stream_comments_in_chunks() {
+    let comments: Vec<_> = (1..=5).map(|i| comment("v1", &format!("c{}", i), "Hi")).collect();
+    let app = TestApp::builder().video("v1").comments("v1", comments).build().await;
+
+    let chunks: Vec<Vec<_>> = app.db.stream_comments("v1", 2).try_collect().await.unwrap();
+    assert_eq!(chunks.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 1]);
+    assert_eq!(chunks[2][0].comment_id, "c5");
+
+    let response = app.get("/api/comments/v1?format=ndjson").await;
+    assert_eq!(response.text().lines().count(), 5);
+}
+
+#[tokio::test]
+async fn test_search_comments() {
+    let mut older = comment("v2", "c3", "The blender review was great");
+    older.published_at -= chrono::Duration::days(30);
+    let app = TestApp::builder()
+        .video("v1")
+        .video("v2")
+        .comments("v1", vec![comment("v1", "c1", "Which blender is this?"), comment("v1", "c2", "Nice video")])
+        .comments("v2", vec![older])
+        .comments("v3", vec![comment("v3", "c4", "Blender on someone else's video")])
+        .build()
+        .await;
+
+    let hits = app.get("/api/comments/search?q=Blenders").await.json();
+    let mut ids: Vec<&str> = hits.as_array().unwrap().iter().map(|h| h["comment_id"].as_str().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, ["c1", "c3"]);
+    assert!(hits[0]["highlight"].as_str().unwrap().contains("<mark>"));
+
+    let hits = app.get("/api/comments/search?q=blender&video_id=v1").await.json();
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+    let since = (chrono::Utc::now() - chrono::Duration::days(7)).format("%Y-%m-%dT%H:%M:%SZ");
+    let hits = app.get(&format!("/api/comments/search?q=blender&since={}", since)).await.json();
+    assert_eq!(hits[0]["comment_id"], "c1");
+    assert_eq!(hits.as_array().unwrap().len(), 1);
+
+    assert_eq!(app.get("/api/comments/search?q=%20").await.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_comments_from_youtube() {
+    let app = TestApp::builder()
+        .video("v1")
+        .upstream_comments("v1", vec![comment("v1", "c1", "First!"), comment("v1", "c2", "Second")])
+        .build()
+        .await;
+
+    let response = app.get("/api/comments/v1?format=csv").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.headers["content-type"].to_str().unwrap().starts_with("text/csv"));
+    assert_eq!(app.db.get_comments("v1").await.unwrap().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_export_masks_sensitive_text() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Collab? Write to jane.doe@example.com")])
+        .build()
+        .await;
+    let patch = RuntimeSettingsPatch { mask_sensitive_text: Some(true), ..Default::default() };
+    app.state.settings.update(patch).await.unwrap();
+
+    let response = app.get("/api/comments/v1?format=csv").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.text().contains("Collab? Write to [email]"));
+    assert!(!response.text().contains("jane.doe"));
+}
+
+#[tokio::test]
+async fn test_settings_update_applies_all_or_nothing() {
+    let app = TestApp::builder().build().await;
+
+    let patch = RuntimeSettingsPatch {
+        log_filter: Some("debug".to_string()),
+        youtube_requests_per_sec: Some(5000),
+        ..Default::default()
+    };
+    assert!(app.state.settings.update(patch).await.is_err());
+    assert_eq!(app.state.settings.current().log_filter, None);
+    assert!(app.state.db.get_runtime_settings().await.unwrap().is_none());
+
+    // Concurrent changes to different fields both survive
+    let paused = RuntimeSettingsPatch { monitor_paused: Some(true), ..Default::default() };
+    let dry_run = RuntimeSettingsPatch { dry_run: Some(true), ..Default::default() };
+    let (a, b) = tokio::join!(app.state.settings.update(paused), app.state.settings.update(dry_run));
+    a.unwrap();
+    b.unwrap();
+    let current = app.state.settings.current();
+    assert!(current.monitor_paused);
+    assert!(current.dry_run);
+}
+
+#[tokio::test]
+async fn test_commenter_tags_filter_inbox() {
+    let mut fan = comment("v1", "c2", "Watched it three times!");
+    fan.author_channel_id = "UCfan".to_string();
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Nice"), fan])
+        .build()
+        .await;
+
+    let body = json!({ "notes": "Comments on every upload", "tags": ["Superfan", "superfan ", "sponsor lead"] });
+    let response = app.send(Method::PATCH, "/api/commenters/UCfan", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["tags"], json!(["superfan", "sponsor lead"]));
+
+    let response = app.get("/api/comments/v1?tag=superfan").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let comments = response.json();
+    assert_eq!(comments.as_array().unwrap().len(), 1);
+    assert_eq!(comments[0]["metadata"]["commenter_tags"], "superfan,sponsor lead");
+    assert_eq!(comments[0]["metadata"]["commenter_notes"], "Comments on every upload");
+}
+
+#[tokio::test]
+async fn test_commenter_history() {
+    let mut earlier = comment("v1", "c1", "Love the drone shots");
+    let mut reply = common::reply("c1", "c1.r1", "Thanks, more coming soon!");
+    reply.author_channel_id = USER_ID.to_string();
+    earlier.replies.push(reply);
+    earlier.replies.push(common::reply("c1", "c1.r2", "Same here"));
+    let app = TestApp::builder()
+        .video("v1")
+        .video("v2")
+        .comments("v1", vec![earlier])
+        .comments("v2", vec![comment("v2", "c2", "Back again!")])
+        .build()
+        .await;
+
+    let response = app.get("/api/commenters/UCviewer/history").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let history = response.json();
+    assert_eq!(history.as_array().unwrap().len(), 2);
+    let earlier = history.as_array().unwrap().iter().find(|e| e["comment_id"] == "c1").unwrap();
+    assert_eq!(earlier["replies"].as_array().unwrap().len(), 1);
+    assert_eq!(earlier["replies"][0]["text"], "Thanks, more coming soon!");
+}
+
+#[tokio::test]
+async fn test_filter_rules_hide_comments() {
+    let app = TestApp::builder()
+        .video("v1")
+        .upstream_comments("v1", vec![comment("v1", "c1", "Great video"), comment("v1", "c2", "Free giveaway at bit.ly/x")])
+        .build()
+        .await;
+
+    let body = json!({ "name": "Giveaways", "conditions": { "keywords": [] }, "action": "hide" });
+    let response = app.post("/api/rules", body).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(response.error_code(), "validation");
+
+    let body = json!({ "name": "Giveaways", "conditions": { "keywords": ["giveaway"], "contains_link": true }, "action": "hide" });
+    let response = app.post("/api/rules", body).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let rule_id = response.json()["rule_id"].as_str().unwrap().to_string();
+
+    let response = app.get("/api/comments/v1").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json().as_array().unwrap().len(), 1);
+    assert_eq!(response.json()[0]["comment_id"], "c1");
+
+    let response = app.get("/api/comments/v1?include_hidden=true").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 2);
+
+    let response = app.get("/api/rules").await;
+    assert_eq!(response.json()[0]["hit_count"], 1);
+
+    let response = app.send(Method::DELETE, &format!("/api/rules/{}", rule_id), Some("someone-else"), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_duplicate_comments_marked_spam() {
+    let spam = |comment_id: &str, text: &str| {
+        let mut spam = comment("v1", comment_id, text);
+        spam.author_channel_id = format!("UCbot{}", comment_id);
+        spam.metadata.insert(TEXT_HASH_KEY.to_string(), duplicates::fingerprint(text).unwrap());
+        spam
+    };
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![
+            spam("c1", "Check out my channel for FREE giveaways!"),
+            spam("c2", "check out my channel for free giveaways"),
+            spam("c3", "Check out my channel for free giveaways!!! 🔥"),
+            comment("v1", "c4", "Great video"),
+        ])
+        .build()
+        .await;
+
+    let response = app.get("/api/duplicates").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let groups = response.json();
+    assert_eq!(groups.as_array().unwrap().len(), 1);
+    assert_eq!(groups[0]["comments"], 3);
+    let group_id = groups[0]["group_id"].as_str().unwrap().to_string();
+
+    let response = app.post(&format!("/api/duplicates/{}/spam", group_id), json!({})).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["marked"], 3);
+
+    let response = app.get("/api/comments/v1").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_cluster_reply() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![
+            comment("v1", "c1", "What camera do you use?"),
+            comment("v1", "c2", "what camera did you use"),
+            comment("v1", "c3", "Nice video"),
+        ])
+        .build()
+        .await;
+
+    let response = app.get("/api/videos/v1/clusters?threshold=0.7").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let clusters = response.json();
+    assert_eq!(clusters.as_array().unwrap().len(), 1);
+    assert_eq!(clusters[0]["comment_ids"].as_array().unwrap().len(), 2);
+
+    let body = json!({ "comment_ids": clusters[0]["comment_ids"] });
+    let response = app.post("/api/clusters/reply", body).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["succeeded"], 2);
+    assert_eq!(app.youtube.posted().len(), 2);
+}
+
+#[tokio::test]
+async fn test_spam_quarantine_review() {
+    let flagged = |comment_id: &str, reason: &str, score: &str| {
+        let mut flagged = comment("v1", comment_id, "Check out my channel");
+        flagged.metadata.insert("spam".to_string(), reason.to_string());
+        flagged.metadata.insert("spam_score".to_string(), score.to_string());
+        flagged
+    };
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![flagged("c1", "classifier", "0.75"), flagged("c2", "rule-1", "0.30")])
+        .build()
+        .await;
+
+    let response = app.get("/api/quarantine").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 2);
+
+    // A false positive of the classifier raises its threshold above the comment's score
+    let response = app.post("/api/quarantine/c1/restore", json!({})).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["spam_review"], "restored");
+    let threshold = app.get("/api/quarantine/classifier").await.json()["threshold"].as_f64().unwrap();
+    assert!((threshold - 0.77).abs() < 1e-3);
+
+    // Spam the classifier missed lowers it again
+    let response = app.post("/api/quarantine/c2/confirm", json!({})).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.youtube.rejected(), vec![("c2".to_string(), true)]);
+    let threshold = app.get("/api/quarantine/classifier").await.json()["threshold"].as_f64().unwrap();
+    assert!((threshold - 0.75).abs() < 1e-3);
+
+    assert!(app.get("/api/quarantine").await.json().as_array().unwrap().is_empty());
+    let response = app.post("/api/quarantine/c1/restore", json!({})).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_inbox_projection() {
+    let mut fan = comment("v1", "c2", "Watched it three times!");
+    fan.author_channel_id = "UCfan".to_string();
+    fan.published_at -= chrono::Duration::days(1);
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Nice"), fan])
+        .build()
+        .await;
+
+    // Comments stored without an event are projected by a rebuild
+    assert_eq!(app.get("/api/inbox").await.json(), json!([]));
+    let response = app.post("/api/inbox/rebuild", json!({})).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["videos"], 1);
+
+    let entries = app.get("/api/inbox").await.json();
+    let ids: Vec<&str> = entries.as_array().unwrap().iter().map(|e| e["comment_id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["c1", "c2"]);
+    assert_eq!(entries[0]["video_title"], "Video v1");
+    assert_eq!(entries[0]["comment"]["text"], "Nice");
+
+    // Changes reach the projection through events
+    let body = json!({ "tags": ["superfan"] });
+    app.send(Method::PATCH, "/api/commenters/UCfan", Some(USER_ID), Some(body)).await;
+    let entries = inbox_when(&app, "/api/inbox?tag=superfan", |entries| entries.len() == 1).await;
+    assert_eq!(entries[0]["comment_id"], "c2");
+
+    let body = json!({ "state": "ignored" });
+    app.send(Method::PUT, "/api/threads/c1/triage", Some(USER_ID), Some(body)).await;
+    let entries = inbox_when(&app, "/api/inbox?triage=ignored", |entries| entries.len() == 1).await;
+    assert_eq!(entries[0]["comment_id"], "c1");
+
+    assert!(app.send(Method::GET, "/api/inbox", Some("someone-else"), None).await.json().as_array().unwrap().is_empty());
+    assert_eq!(app.get("/api/inbox?limit=0").await.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_mutes() {
+    let mut troll = comment("v1", "c2", "First!");
+    troll.author = "Troll".to_string();
+    troll.author_channel_id = "UCtroll".to_string();
+    let app = TestApp::builder()
+        .video("v1")
+        .video("v2")
+        .comments("v1", vec![comment("v1", "c1", "Nice"), troll])
+        .comments("v2", vec![comment("v2", "c3", "Great tips")])
+        .build()
+        .await;
+    app.post("/api/inbox/rebuild", json!({})).await;
+    assert_eq!(app.get("/api/inbox").await.json().as_array().unwrap().len(), 3);
+
+    let response = app.post("/api/mutes", json!({ "kind": "commenter", "target_id": "UCtroll", "reason": "Spams every upload" })).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    assert_eq!(response.json()["label"], "Troll");
+    let mute_id = response.json()["mute_id"].as_str().unwrap().to_string();
+    let response = app.post("/api/mutes", json!({ "kind": "commenter", "target_id": "UCtroll" })).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let response = app.post("/api/mutes", json!({ "kind": "video", "target_id": "v2" })).await;
+    assert_eq!(response.json()["label"], "Video v2");
+    let response = app.post("/api/mutes", json!({ "kind": "video", "target_id": "someone-elses" })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let entries = app.get("/api/inbox").await.json();
+    let ids: Vec<&str> = entries.as_array().unwrap().iter().map(|e| e["comment_id"].as_str().unwrap()).collect();
+    assert_eq!(ids, ["c1"]);
+
+    assert_eq!(app.get("/api/mutes").await.json().as_array().unwrap().len(), 2);
+    let videos = app.get("/api/mutes?kind=video").await.json();
+    assert_eq!(videos.as_array().unwrap().len(), 1);
+    assert_eq!(videos[0]["target_id"], "v2");
+
+    // Unmuting brings the comments back; other users can't unmute
+    let uri = format!("/api/mutes/{}", mute_id);
+    let response = app.send(Method::DELETE, &uri, Some("someone-else"), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.send(Method::DELETE, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    assert_eq!(app.get("/api/inbox").await.json().as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_comment_triage() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Nice"), comment("v1", "c2", "When is part two?")])
+        .build()
+        .await;
+
+    let body = json!({ "state": "ignored" });
+    let response = app.send(Method::PUT, "/api/threads/c1/triage", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["triage"], "ignored");
+
+    let response = app.get("/api/comments/v1?triage=new").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 1);
+    assert_eq!(response.json()[0]["comment_id"], "c2");
+
+    let body = json!({ "state": "snoozed" });
+    let response = app.send(Method::PUT, "/api/threads/c1/triage", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_comment_highlights() {
+    let liked = |comment_id: &str, text: &str, likes: i32| {
+        let mut comment = comment("v1", comment_id, text);
+        comment.like_count = likes;
+        comment
+    };
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![
+            liked("c1", "Love this, great video!", 20),
+            liked("c2", "Amazing work, thank you!", 10),
+            liked("c3", "This is terrible and boring", 30),
+        ])
+        .build()
+        .await;
+
+    let response = app.get("/api/videos/v1/pin-candidates").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let ids: Vec<Value> = response.json().as_array().unwrap().iter().map(|c| c["comment_id"].clone()).collect();
+    assert_eq!(ids, vec![json!("c1"), json!("c2")]);
+
+    let body = json!({ "pinned": true });
+    let response = app.send(Method::PUT, "/api/threads/c1/highlight", Some(USER_ID), Some(body.clone())).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json()["comment"]["highlight"]["pinned_at"].is_string());
+    assert!(response.json()["reminder"].as_str().unwrap().contains("YouTube Studio"));
+    assert_eq!(response.json()["studio_url"], "https://studio.youtube.com/video/v1/comments");
+
+    // Pinning another comment unpins the first
+    let response = app.send(Method::PUT, "/api/threads/c2/highlight", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let comments = app.get("/api/comments/v1").await.json();
+    let pinned: Vec<&Value> = comments.as_array().unwrap().iter().filter(|c| c["highlight"]["pinned_at"].is_string()).collect();
+    assert_eq!(pinned.len(), 1);
+    assert_eq!(pinned[0]["comment_id"], "c2");
+
+    let body = json!({ "highlighted": true });
+    app.send(Method::PUT, "/api/threads/c1/highlight", Some(USER_ID), Some(body)).await;
+
+    let response = app.get("/api/videos/v1/pin-candidates").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 1);
+    assert_eq!(response.json()[0]["comment_id"], "c1");
+
+    let actions: Vec<Value> = history_of_type(&app, "CommentFeatured").await.iter().map(|i| i["data"]["action"].clone()).collect();
+    assert_eq!(actions, vec![json!("highlighted"), json!("pinned"), json!("pinned")]);
+
+    let response = app.send(Method::PUT, "/api/threads/missing/highlight", Some(USER_ID), Some(json!({ "pinned": true }))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_react_to_comment() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Best tutorial on the topic")])
+        .build()
+        .await;
+
+    let body = json!({ "hearted": true, "liked": true });
+    let response = app.send(Method::PUT, "/api/comments/c1/react", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json()["comment"]["reaction"]["hearted_at"].is_string());
+    assert!(response.json()["reminder"].as_str().unwrap().contains("hearted and liked"));
+
+    // Fetching the comment again keeps the reaction, and each can be taken back on its own
+    app.state.db.save_comments("v1", &[comment("v1", "c1", "Best tutorial on the topic")]).await.unwrap();
+    let response = app.send(Method::PUT, "/api/comments/c1/react", Some(USER_ID), Some(json!({ "hearted": false }))).await;
+    assert!(response.json()["comment"]["reaction"]["hearted_at"].is_null());
+    assert!(response.json()["comment"]["reaction"]["liked_at"].is_string());
+
+    let actions: Vec<Value> = history_of_type(&app, "CommentReacted").await.iter().map(|i| i["data"]["action"].clone()).collect();
+    assert_eq!(actions, vec![json!("unhearted"), json!("liked"), json!("hearted")]);
+
+    let response = app.send(Method::PUT, "/api/comments/c1/react", Some("someone-else"), Some(json!({ "liked": true }))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_moderate_comments() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "First!"), comment("v1", "c2", "Free followers here")])
+        .build()
+        .await;
+
+    let response = app.post("/api/comments/c1/moderate", json!({ "action": "hold" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["triage"], "ignored");
+    let response = app.post("/api/comments/c2/moderate", json!({ "action": "spam" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.youtube.moderated(), vec![("c1".to_string(), "heldForReview"), ("c2".to_string(), "markAsSpam")]);
+
+    let types: Vec<Value> = history_of_type(&app, "CommentModerated,CommentMarkedSpam").await.iter().map(|i| i["interaction_type"].clone()).collect();
+    assert_eq!(types, vec![json!("CommentMarkedSpam"), json!("CommentModerated")]);
+
+    // Only rejecting a comment can ban its author
+    let response = app.post("/api/comments/c1/moderate", json!({ "action": "hold", "ban_author": true })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let response = app.send(Method::POST, "/api/comments/c1/moderate", Some("someone-else"), Some(json!({ "action": "reject" }))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_update_posted_reply() {
+    let mut parent = comment("v1", "c1", "How did you film this?");
+    let mut mine = common::reply("c1", "c1.r1", "With a drnoe");
+    mine.author_channel_id = USER_ID.to_string();
+    parent.replies.push(mine);
+    parent.replies.push(common::reply("c1", "c1.r2", "Cool!"));
+    let app = TestApp::builder().video("v1").comments("v1", vec![parent]).build().await;
+
+    let response = app.send(Method::PATCH, "/api/reply/c1.r1", Some(USER_ID), Some(json!({ "reply_text": "With a drone" }))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["text"], "With a drone");
+    assert_eq!(app.youtube.updated(), vec![("c1.r1".to_string(), "With a drone".to_string())]);
+    assert_eq!(app.get("/api/threads/c1/replies").await.json()[0]["text"], "With a drone");
+
+    let history = history_of_type(&app, "ReplyUpdated").await;
+    assert_eq!(history[0]["reply_id"], "c1.r1");
+    assert_eq!(history[0]["data"]["previous_text"], "With a drnoe");
+    assert_eq!(history[0]["data"]["diff"], "With a [-drnoe-] {+drone+}");
+
+    // Only the user's own replies can be edited, and not to nothing
+    let response = app.send(Method::PATCH, "/api/reply/c1.r2", Some(USER_ID), Some(json!({ "reply_text": "Very cool!" }))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.send(Method::PATCH, "/api/reply/c1.r1", Some("someone-else"), Some(json!({ "reply_text": "Hacked" }))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.send(Method::PATCH, "/api/reply/c1.r1", Some(USER_ID), Some(json!({ "reply_text": " " }))).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    assert_eq!(app.youtube.updated().len(), 1);
+}
+
+#[tokio::test]
+async fn test_get_thread_replies() {
+    let mut parent = comment("v1", "c1", "How did you film this?");
+    parent.replies.push(common::reply("c1", "c1.r1", "With a drone"));
+    let app = TestApp::builder().video("v1").comments("v1", vec![parent]).build().await;
+
+    let response = app.get("/api/threads/c1/replies").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()[0]["text"], "With a drone");
+
+    let response = app.get("/api/threads/missing/replies").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_conversation() {
+    let mut parent = comment("v1", "c1", "How did you film this?");
+    let mut mine = common::reply("c1", "c1.r1", "With a drone");
+    mine.author_channel_id = USER_ID.to_string();
+    let mut follow_up = common::reply("c1", "c1.r2", "Which drone?");
+    follow_up.author_channel_id = "UCviewer".to_string();
+    follow_up.published_at = mine.published_at + chrono::Duration::minutes(5);
+    parent.replies = vec![follow_up, mine];
+    parent.reply_count = 2;
+    parent.follow_up = Some(FollowUp { reply_id: "c1.r2".to_string(), in_reply_to: "c1.r1".to_string(), detected_at: chrono::Utc::now() });
+    let app = TestApp::builder().video("v1").comments("v1", vec![parent]).build().await;
+
+    let response = app.get("/api/threads/c1/conversation").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let conversation = response.json();
+    assert_eq!(conversation["follow_up"]["in_reply_to"], "c1.r1");
+    let messages: Vec<(&str, bool, bool)> = conversation["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| (m["id"].as_str().unwrap(), m["mine"].as_bool().unwrap(), m["follow_up"].as_bool().unwrap()))
+        .collect();
+    assert_eq!(messages, vec![("c1", false, false), ("c1.r1", true, false), ("c1.r2", false, true)]);
+
+    let response = app.get("/api/threads/missing/conversation").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_share_link_renders_thread_and_draft() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Is <this> real?")])
+        .build()
+        .await;
+    app.db.save_draft(&ReplyDraft::new(USER_ID, "v1", "c1", "Yes, filmed in one take", None)).await.unwrap();
+
+    let response = app.post("/api/comments/c1/share", json!({ "expires_in_hours": 24 })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let url = response.json()["url"].as_str().unwrap().to_string();
+    let path = url.strip_prefix("http://localhost:3000").unwrap();
+
+    // No account needed to read it
+    let response = app.send(Method::GET, path, None, None).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let page = response.text();
+    assert!(page.contains("Is &lt;this&gt; real?"));
+    assert!(page.contains("Yes, filmed in one take"));
+
+    let tampered = format!("{}x", path);
+    assert_eq!(app.send(Method::GET, &tampered, None, None).await.status, StatusCode::NOT_FOUND);
+
+    let response = app.send(Method::POST, "/api/comments/c1/share", Some("someone-else"), Some(json!({}))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.post("/api/comments/c1/share", json!({ "expires_in_hours": 0 })).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_generate_reply() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json(), json!({ "reply_text": AI_REPLY, "model": AI_MODEL, "degraded": false, "hostile": false }));
+
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "missing" })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_generate_reply_switched_off() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    let patch = RuntimeSettingsPatch { ai_enabled: Some(false), ..Default::default() };
+    app.state.settings.update(patch).await.unwrap();
+
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.error_code(), "unavailable");
+}
+
+#[tokio::test]
+async fn test_post_reply() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+
+    let body = json!({ "comment_id": "c1", "reply_text": "Thank you!", "ai_generated": true, "ai_model": AI_MODEL });
+    let response = app.post("/api/reply/post", body).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["ai_model"], AI_MODEL);
+    assert_eq!(app.youtube.posted()[0].text, "Thank you!");
+}
+
+#[tokio::test]
+async fn test_reply_to_hostile_comment_needs_confirmation() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "You're a liar and a fraud")])
+        .build()
+        .await;
+
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["hostile"], true);
+
+    let response = app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": AI_REPLY })).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(app.youtube.posted().is_empty());
+
+    let body = json!({ "comment_id": "c1", "reply_text": AI_REPLY, "confirm_hostile": true });
+    assert_eq!(app.post("/api/reply/post", body).await.status, StatusCode::OK);
+    assert_eq!(app.youtube.posted().len(), 1);
+}
+
+#[tokio::test]
+async fn test_slack_webhook_must_point_at_slack() {
+    let app = TestApp::builder().build().await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    for url in ["http://169.254.169.254/latest/meta-data", "https://hooks.slack.com.example.org/services/x"] {
+        let response = app.send(Method::PUT, "/api/preferences/slack", Some(USER_ID), Some(json!({ "slack_webhook_url": url }))).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+    let user = app.state.db.get_user(USER_ID).await.unwrap().unwrap();
+    assert_eq!(user.preferences.notifications.slack_webhook_url, None);
+
+    let url = "https://hooks.slack.com/services/T000/B000/XXXX";
+    let response = app.send(Method::PUT, "/api/preferences/slack", Some(USER_ID), Some(json!({ "slack_webhook_url": url }))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let user = app.state.db.get_user(USER_ID).await.unwrap().unwrap();
+    assert_eq!(user.preferences.notifications.slack_webhook_url.as_deref(), Some(url));
+}
+
+#[tokio::test]
+async fn test_reply_scheduled_in_time_zone() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let response = app.send(Method::PUT, "/api/preferences/time-zone", Some(USER_ID), Some(json!({ "time_zone": "Asia/Tokyo" }))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let response = app.send(Method::PUT, "/api/preferences/time-zone", Some(USER_ID), Some(json!({ "time_zone": "Mars/Olympus_Mons" }))).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // 9:00 in Tokyo is midnight UTC
+    let body = json!({ "comment_id": "c1", "reply_text": "Out on Friday!", "send_at": "2099-06-01T09:00:00" });
+    let response = app.post("/api/reply/post", body).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    assert_eq!(response.json()["send_at"], "2099-06-01T00:00:00Z");
+    assert!(app.youtube.posted().is_empty());
+
+    let body = json!({ "comment_id": "c1", "reply_text": "Too late", "send_at": "2020-06-01T09:00:00" });
+    assert_eq!(app.post("/api/reply/post", body).await.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_ai_disclosure() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video"), comment("v1", "c2", "Nice edit")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let body = json!({ "enabled": true, "text": "(AI-assisted)" });
+    let response = app.send(Method::PUT, "/api/preferences/ai-disclosure", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let body = json!({ "comment_id": "c1", "reply_text": "Glad it helped!", "ai_generated": true, "ai_model": AI_MODEL });
+    let response = app.post("/api/reply/post", body).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    // Replies the user wrote are left alone
+    app.post("/api/reply/post", json!({ "comment_id": "c2", "reply_text": "Thanks!" })).await;
+
+    let texts: Vec<String> = app.youtube.posted().into_iter().map(|r| r.text).collect();
+    assert_eq!(texts, vec!["Glad it helped! (AI-assisted)", "Thanks!"]);
+}
+
+#[tokio::test]
+async fn test_retention_prunes_old_comments() {
+    let mut old = comment("v1", "c1", "First!");
+    old.published_at -= chrono::Duration::days(100);
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![old, comment("v1", "c2", "Still here")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    // Nothing is pruned until a period is set
+    assert!(app.state.retention.run(PruneMode::Delete).await.unwrap().is_empty());
+
+    let response = app.send(Method::PUT, "/api/preferences/retention", Some(USER_ID), Some(json!({ "comments_days": 0 }))).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    let response = app.send(Method::PUT, "/api/preferences/retention", Some(USER_ID), Some(json!({ "comments_days": 30 }))).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let reports = app.state.retention.run(PruneMode::DryRun).await.unwrap();
+    assert_eq!(reports[0].comments, 1);
+    assert_eq!(app.db.get_comments("v1").await.unwrap().unwrap().len(), 2);
+
+    let reports = app.state.retention.run(PruneMode::Delete).await.unwrap();
+    assert_eq!((reports[0].interactions, reports[0].comments), (0, 1));
+    let comments = app.db.get_comments("v1").await.unwrap().unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].comment_id, "c2");
+}
+
+#[tokio::test]
+async fn test_reply_policy() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let body = json!({ "allow_links": false, "allow_mentions": false, "signature": "www.example.com" });
+    let response = app.send(Method::PUT, "/api/preferences/reply-policy", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let body = json!({ "allow_links": false, "allow_mentions": false, "signature": " – Numan " });
+    let response = app.send(Method::PUT, "/api/preferences/reply-policy", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["signature"], "– Numan");
+
+    // Generated replies sign off
+    let generated = app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await.json();
+    let signed = format!("{}\n– Numan", AI_REPLY);
+    assert_eq!(generated["reply_text"], signed);
+
+    // Links and mentions are refused before reaching YouTube
+    for text in ["Gear list at bit.ly/gear", "Ask @DroneGuy, he knows"] {
+        let response = app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": text })).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+    assert!(app.youtube.posted().is_empty());
+
+    let response = app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": &signed })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.youtube.posted()[0].text, signed);
+}
+
+#[tokio::test]
+async fn test_organization() {
+    let app = TestApp::builder()
+        .video("v1")
+        .foreign_video("creator-2", "v2")
+        .foreign_video("outsider", "v3")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .comments("v2", vec![comment("v2", "c2", "Which camera is this?")])
+        .comments("v3", vec![comment("v3", "c3", "Not in the agency")])
+        .build()
+        .await;
+
+    let response = app.post("/api/org", json!({ "name": "Agency" })).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let invite_code = response.json()["invite_code"].as_str().unwrap().to_string();
+    assert_eq!(app.post("/api/org", json!({ "name": "Again" })).await.status, StatusCode::CONFLICT);
+
+    let body = json!({ "invite_code": "wrong" });
+    let response = app.send(Method::POST, "/api/org/join", Some("creator-2"), Some(body)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let body = json!({ "invite_code": invite_code });
+    let response = app.send(Method::POST, "/api/org/join", Some("creator-2"), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["members"], json!([USER_ID, "creator-2"]));
+
+    // Only the admin sees the invite code and the shared inbox
+    let response = app.send(Method::GET, "/api/org", Some("creator-2"), None).await;
+    assert_eq!(response.json()["invite_code"], "");
+    let response = app.send(Method::GET, "/api/org/inbox", Some("creator-2"), None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let inbox = app.get("/api/org/inbox").await.json();
+    let mut ids: Vec<&str> = inbox.as_array().unwrap().iter().map(|i| i["comment"]["comment_id"].as_str().unwrap()).collect();
+    ids.sort();
+    assert_eq!(ids, vec!["c1", "c2"]);
+    assert_eq!(app.get("/api/org/inbox?limit=1").await.json().as_array().unwrap().len(), 1);
+
+    // Compliance rules apply to every member's replies
+    let body = json!({ "blocked_phrases": [" GoPro ", ""], "allow_links": false });
+    let response = app.send(Method::PUT, "/api/org/policy", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["blocked_phrases"], json!(["GoPro"]));
+
+    for text in ["It's a gopro", "Details at example.com"] {
+        let body = json!({ "comment_id": "c2", "reply_text": text });
+        let response = app.send(Method::POST, "/api/reply/post", Some("creator-2"), Some(body)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+    assert!(app.youtube.posted().is_empty());
+
+    let response = app.send(Method::DELETE, &format!("/api/org/members/{}", USER_ID), Some("creator-2"), None).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    let response = app.send(Method::DELETE, "/api/org/members/creator-2", Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let inbox = app.get("/api/org/inbox").await.json();
+    assert_eq!(inbox.as_array().unwrap().len(), 1);
+    assert_eq!(inbox[0]["comment"]["comment_id"], "c1");
+}
+
+#[tokio::test]
+async fn test_events_published() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    let mut events = app.state.events.subscribe(USER_ID);
+    let mut others = app.state.events.subscribe("someone-else");
+
+    app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+    app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": AI_REPLY })).await;
+
+    let event = serde_json::to_value(events.try_recv().unwrap()).unwrap();
+    assert_eq!(event["type"], "reply_generated");
+    assert_eq!(event["reply_text"], AI_REPLY);
+    let event = serde_json::to_value(events.try_recv().unwrap()).unwrap();
+    assert_eq!(event["type"], "reply_posted");
+    assert_eq!(event["comment_id"], "c1");
+    assert_eq!(event["dry_run"], false);
+    assert!(others.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_cancel_queued_reply() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .reply_delay(Duration::from_secs(60))
+        .build()
+        .await;
+
+    let response = app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": "Oops, wrong video" })).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+    assert_eq!(response.json()["status"], "pending");
+    let queue_id = response.json()["queue_id"].as_str().unwrap().to_string();
+    assert!(app.youtube.posted().is_empty());
+
+    let response = app.get("/api/reply/queue").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 1);
+
+    let uri = format!("/api/reply/queue/{}", queue_id);
+    let response = app.send(Method::DELETE, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+
+    let response = app.send(Method::DELETE, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.error_code(), "conflict");
+    assert!(app.youtube.posted().is_empty());
+}
+
+#[tokio::test]
+async fn test_interrupted_send_fails() {
+    let app = TestApp::builder().build().await;
+
+    // One left mid-send by a stopped server, one another instance may still be sending
+    let now = chrono::Utc::now();
+    let mut interrupted = QueuedReply::new(USER_ID, "c1", "Thanks!", now - chrono::Duration::hours(2));
+    interrupted.status = QueueStatus::Sending;
+    interrupted.claimed_at = Some(now - chrono::Duration::hours(1));
+    app.state.db.queue_reply(&interrupted).await.unwrap();
+
+    // Due long ago but only just claimed, so it isn't stale
+    let sending = QueuedReply::new(USER_ID, "c2", "Glad it helped", now - chrono::Duration::hours(1));
+    app.state.db.queue_reply(&sending).await.unwrap();
+    let claimed = app.state.outbox.claim_due().await.unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert!(claimed[0].claimed_at.is_some());
+
+    let failed = app.state.outbox.fail_interrupted().await.unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].queue_id, interrupted.queue_id);
+
+    let stored = app.state.db.get_queued_reply(&interrupted.queue_id).await.unwrap().unwrap();
+    assert_eq!(stored.status, QueueStatus::Failed);
+    assert_eq!(stored.error.as_deref(), Some(INTERRUPTED_SEND_ERROR));
+    let stored = app.state.db.get_queued_reply(&sending.queue_id).await.unwrap().unwrap();
+    assert_eq!(stored.status, QueueStatus::Sending);
+    assert!(app.youtube.posted().is_empty());
+}
+
+#[tokio::test]
+async fn test_post_saved_reply() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Where is this?")])
+        .build()
+        .await;
+
+    let body = json!({ "name": "Location", "text": "Hi {{nickname}}" });
+    let response = app.post("/api/saved-replies", body).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let body = json!({ "name": "Location", "text": "Hi {{author}}, it's in the description!" });
+    let response = app.post("/api/saved-replies", body).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let template_id = response.json()["template_id"].as_str().unwrap().to_string();
+
+    let response = app.post("/api/reply/post", json!({ "comment_id": "c1", "template_id": template_id })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.youtube.posted()[0].text, "Hi Viewer, it's in the description!");
+
+    let response = app.get("/api/saved-replies").await;
+    assert_eq!(response.json()[0]["usage_count"], 1);
+}
+
+#[tokio::test]
+async fn test_ai_unavailable() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Where is this?"), comment("v1", "c2", "Nice")])
+        .ai_unavailable()
+        .build()
+        .await;
+
+    let body = json!({ "name": "Location", "text": "Hi {{author}}, it's in the description!" });
+    let template_id = app.post("/api/saved-replies", body).await.json()["template_id"].as_str().unwrap().to_string();
+
+    // Saved replies are used as they are
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c1", "template_id": template_id })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["reply_text"], "Hi Viewer, it's in the description!");
+    assert_eq!(response.json()["degraded"], true);
+
+    // Free generation is unavailable, not an internal error
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c2" })).await;
+    assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.error_code(), "unavailable");
+
+    // Manual replies and the inbox don't need the AI
+    let response = app.post("/api/reply/post", json!({ "comment_id": "c2", "reply_text": "Thanks!" })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(app.get("/api/inbox").await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_tones() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let response = app.get("/api/tones").await;
+    assert_eq!(response.json().as_array().unwrap().len(), 4);
+
+    let body = json!({ "name": "Dry wit", "instructions": "Be understated and a little sarcastic.", "temperature": 3.0 });
+    let response = app.post("/api/tones", body).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let body = json!({ "name": "Dry wit", "instructions": "Be understated and a little sarcastic.", "examples": ["Bold of you."] });
+    let response = app.post("/api/tones", body).await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let tone_id = response.json()["tone_id"].as_str().unwrap().to_string();
+
+    let uri = format!("/api/tones/{}", tone_id);
+    let body = json!({ "name": "Dry wit", "instructions": "Be understated.", "temperature": 0.9 });
+    let response = app.send(Method::PUT, &uri, Some(USER_ID), Some(body.clone())).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["temperature"], 0.9);
+
+    // Other users can neither see nor change it, and nobody can change the built-ins
+    let response = app.send(Method::PUT, &uri, Some("user-2"), Some(body.clone())).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.send(Method::PUT, "/api/tones/friendly", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c1", "tone": tone_id })).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c1", "tone": "sarcastic" })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.send(Method::PUT, "/api/preferences/tone", Some(USER_ID), Some(json!({ "tone_id": tone_id }))).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let preferences = app.db.get_user(USER_ID).await.unwrap().unwrap().preferences;
+    assert_eq!(preferences.reply_tone.0, tone_id);
+
+    // Deleting the preferred tone goes back to the default one
+    let response = app.send(Method::DELETE, &uri, Some(USER_ID), None).await;
+    assert_eq!(response.status, StatusCode::NO_CONTENT);
+    let preferences = app.db.get_user(USER_ID).await.unwrap().unwrap().preferences;
+    assert_eq!(preferences.reply_tone.0, "friendly");
+    assert_eq!(app.get("/api/tones").await.json().as_array().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_batch_generate_replies() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video"), comment("v1", "c2", "Nice edit")])
+        .build()
+        .await;
+
+    let response = app.post("/api/reply/generate/batch", json!({ "comment_ids": ["c1", "c2", "missing"] })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.post("/api/reply/generate/batch", json!({ "comment_ids": ["c1", "c2"] })).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["succeeded"], 2);
+    assert_eq!(job["failed"], 0);
+}
+
+#[tokio::test]
+async fn test_dry_run() {
+    let mut spam = comment("v1", "c2", "Check out my channel");
+    spam.metadata.insert("spam".to_string(), "classifier".to_string());
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video"), spam])
+        .build()
+        .await;
+
+    let body = json!({ "comment_id": "c1", "reply_text": "Thank you!", "dry_run": true });
+    let response = app.post("/api/reply/post", body).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["dry_run"], true);
+    assert!(app.youtube.posted().is_empty());
+    assert!(!app.db.get_comment("c1").await.unwrap().unwrap().replied_to);
+
+    // Switched on for everyone, moderation is only recorded too
+    let patch = RuntimeSettingsPatch { dry_run: Some(true), ..Default::default() };
+    app.state.settings.update(patch).await.unwrap();
+    let response = app.post("/api/quarantine/c2/confirm", json!({})).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(app.youtube.rejected().is_empty());
+
+    let history = app.get("/api/history").await.json();
+    let actions: Vec<&str> = history
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|i| i["interaction_type"] == "DryRun")
+        .map(|i| i["data"]["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions.len(), 2);
+    assert!(actions.contains(&"reply") && actions.contains(&"reject"));
+}
+
+#[tokio::test]
+async fn test_bulk_post_replies() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video"), comment("v1", "c2", "Nice edit")])
+        .build()
+        .await;
+
+    let body = json!({ "replies": [
+        { "comment_id": "c1", "reply_text": "Thanks!" },
+        { "comment_id": "c2", "reply_text": "Glad you liked it" },
+    ] });
+    let response = app.post("/api/reply/post/batch", body).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["succeeded"], 2);
+    assert_eq!(app.youtube.posted().len(), 2);
+}
+
+#[tokio::test]
+async fn test_start_backfill() {
+    let app = TestApp::builder()
+        .video("v1")
+        .video("v2")
+        .upstream_comments("v1", vec![comment("v1", "c1", "First!")])
+        .build()
+        .await;
+
+    let response = app.post("/api/backfill", json!({})).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+
+    // v2 has no comments upstream, so only v1 succeeds
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["total"], 2);
+    assert_eq!(job["succeeded"], 1);
+    assert_eq!(job["failed"], 1);
+}
+
+#[tokio::test]
+async fn test_other_users_comments_are_not_found() {
+    let app = TestApp::builder()
+        .video("v1")
+        .foreign_video("someone-else", "v2")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .comments("v2", vec![comment("v2", "c2", "Which camera is this?")])
+        .build()
+        .await;
+
+    assert_eq!(app.get("/api/comments/v2").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/threads/c2/replies").await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/threads/c2/conversation").await.status, StatusCode::NOT_FOUND);
+
+    let response = app.send(Method::PUT, "/api/threads/c2/triage", Some(USER_ID), Some(json!({ "state": "done" }))).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.db.get_comment("c2").await.unwrap().unwrap().triage, TriageState::New);
+
+    let response = app.post("/api/reply/generate", json!({ "comment_id": "c2" })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.post("/api/reply/generate/batch", json!({ "comment_ids": ["c1", "c2"] })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.post("/api/reply/post", json!({ "comment_id": "c2", "reply_text": "Thanks!" })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let body = json!({ "comment_id": "c2", "reply_text": "Thanks!", "send_at": "2099-06-01T09:00:00" });
+    assert_eq!(app.post("/api/reply/post", body).await.status, StatusCode::NOT_FOUND);
+    let body = json!({ "replies": [
+        { "comment_id": "c1", "reply_text": "Thanks!" },
+        { "comment_id": "c2", "reply_text": "Thanks!" },
+    ] });
+    assert_eq!(app.post("/api/reply/post/batch", body).await.status, StatusCode::NOT_FOUND);
+    assert!(app.youtube.posted().is_empty());
+
+    let response = app.post("/api/backfill", json!({ "video_ids": ["v1", "v2"] })).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_verify_replies() {
+    // Answered, as the real YouTube service marks comments when posting
+    let mut answered = vec![comment("v1", "c1", "Where can I buy this?"), comment("v1", "c2", "Nice")];
+    for comment in &mut answered {
+        comment.replied_to = true;
+    }
+    let app = TestApp::builder().video("v1").comments("v1", answered).build().await;
+    app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": "Here: https://example.com/shop" })).await;
+    app.post("/api/reply/post", json!({ "comment_id": "c2", "reply_text": "Thanks!" })).await;
+    let removed = app.youtube.posted()[0].reply_id.clone();
+    app.youtube.remove(&removed);
+
+    let response = app.post("/api/reply/verify", json!({})).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["total"], 2);
+    assert_eq!(job["succeeded"], 2);
+
+    // The removed reply is in the history and its comment is unanswered again
+    let history = history_of_type(&app, "ReplyRemoved").await;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["reply_id"], removed.as_str());
+    assert_eq!(history[0]["data"]["has_link"], "true");
+    assert!(!app.db.get_comment("c1").await.unwrap().unwrap().replied_to);
+    assert!(app.db.get_comment("c2").await.unwrap().unwrap().replied_to);
+
+    // Replies found missing aren't checked again
+    let response = app.post("/api/reply/verify", json!({})).await;
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["total"], 1);
+}
+
+#[tokio::test]
+async fn test_get_job() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.post("/api/reply/post/batch", json!({ "replies": [] })).await;
+    let job_id = response.json()["job_id"].as_str().unwrap().to_string();
+
+    let job = wait_for_job(&app, &job_id).await;
+    assert_eq!(job["progress"], 1.0);
+
+    let response = app.send(Method::GET, &format!("/api/jobs/{}", job_id), Some("someone-else"), None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_get_history() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+
+    let response = app.get("/api/history?limit=10").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()[0]["comment_id"], "c1");
+}
+
+#[tokio::test]
+async fn test_history_filter_by_type() {
+    let mut spam = comment("v1", "c3", "Check out my channel");
+    spam.metadata.insert("spam".to_string(), "classifier".to_string());
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video"), comment("v1", "c2", "Nice"), spam])
+        .build()
+        .await;
+    app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+    app.post("/api/reply/generate", json!({ "comment_id": "c2" })).await;
+    app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": AI_REPLY })).await;
+    app.post("/api/reply/post", json!({ "comment_id": "c2", "reply_text": "Thanks a lot!" })).await;
+    app.post("/api/quarantine/c3/confirm", json!({})).await;
+
+    assert_eq!(history_of_type(&app, "DraftCreated").await.len(), 2);
+
+    let reviewed = history_of_type(&app, "DraftApproved,ReplyEdited").await;
+    assert_eq!(reviewed.len(), 2);
+    let edited = reviewed.iter().find(|i| i["interaction_type"] == "ReplyEdited").unwrap();
+    assert_eq!(edited["comment_id"], "c2");
+    assert_eq!(edited["data"]["draft_text"], AI_REPLY);
+    assert_eq!(edited["data"]["diff"], "Thanks [-for watching!-] {+a lot!+}");
+    assert_eq!(edited["data"]["words_added"], "2");
+
+    let moderated = history_of_type(&app, "CommentModerated,CommenterBanned").await;
+    assert_eq!(moderated.len(), 2);
+    assert!(moderated.iter().any(|i| i["data"]["channel_id"] == "UCviewer"));
+
+    let response = app.get("/api/history?type=Unknown").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_pending_drafts() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+    app.post("/api/reply/generate", json!({ "comment_id": "c1" })).await;
+
+    let response = app.get("/api/drafts").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()[0]["text"], AI_REPLY);
+}
+
+#[tokio::test]
+async fn test_get_dashboard() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+
+    let response = app.get("/api/dashboard").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["unanswered_comments"], 1);
+}
+
+#[tokio::test]
+async fn test_get_capacity() {
+    let app = TestApp::builder()
+        .video("v1")
+        .video("v2")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .reply_delay(Duration::from_secs(60))
+        .build()
+        .await;
+
+    app.post("/api/reply/post", json!({ "comment_id": "c1", "reply_text": "Thanks!" })).await;
+
+    let response = app.get("/api/status/capacity").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let capacity = response.json();
+    assert_eq!(capacity["youtube_quota"]["limit"], 10_000);
+    assert_eq!(capacity["outbox"]["pending"], 1);
+    assert_eq!(capacity["outbox"]["quota_units"], 50);
+    assert_eq!(capacity["rate_limiter"]["requests_per_sec"], 10);
+    assert_eq!(capacity["backfill"]["videos"], 2);
+    assert_eq!(capacity["backfill"]["quota_units"], 2);
+    assert_eq!(capacity["backfill"]["fits_remaining_quota"], true);
+}
+
+#[cfg(feature = "telegram")]
+#[tokio::test]
+async fn test_telegram_webhook_not_configured() {
+    let app = TestApp::builder().build().await;
+
+    let body = json!({ "update_id": 1 });
+    let response = app.send(Method::POST, "/api/telegram/webhook", None, Some(body)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_analytics_overview() {
+    let app = TestApp::builder().video("v1").build().await;
+
+    let response = app.get("/api/analytics/overview?days=7").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.json().is_object());
+}
+
+#[tokio::test]
+async fn test_analytics_overview_reuses_closed_days() {
+    let app = TestApp::builder().video("v1").build().await;
+
+    let now = chrono::Utc::now();
+    let today = now.date_naive();
+    let rollup = |day: chrono::NaiveDate, replies_posted: usize, computed_at: chrono::DateTime<chrono::Utc>| DailyRollup {
+        user_id: USER_ID.to_string(),
+        day,
+        comments_received: 0,
+        replies_posted,
+        ai_replies: 0,
+        manual_replies: replies_posted,
+        median_time_to_reply_secs: None,
+        comments_per_video: Default::default(),
+        computed_at,
+    };
+    // Computed after its day ended, so it is kept
+    let yesterday = today - chrono::Duration::days(1);
+    app.db.save_analytics_rollup(&rollup(yesterday, 42, now)).await.unwrap();
+    // Computed while its day was still going, so it is computed again
+    let earlier = today - chrono::Duration::days(2);
+    let midday = earlier.and_hms_opt(12, 0, 0).unwrap().and_utc();
+    app.db.save_analytics_rollup(&rollup(earlier, 7, midday)).await.unwrap();
+
+    let response = app.get("/api/analytics/overview?days=7").await;
+    assert_eq!(response.status, StatusCode::OK);
+    let daily = response.json()["daily"].as_array().unwrap().clone();
+    assert_eq!(daily.len(), 7);
+    let replies_on = |day: chrono::NaiveDate| {
+        daily.iter().find(|d| d["day"] == day.to_string()).unwrap()["replies_posted"].clone()
+    };
+    assert_eq!(replies_on(yesterday), 42);
+    assert_eq!(replies_on(earlier), 0);
+
+    let stored = app.db.get_analytics_rollups(USER_ID, earlier, earlier).await.unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].replies_posted, 0);
+}
+
+#[tokio::test]
+async fn test_analytics_sentiment() {
+    let app = TestApp::builder().video("v1").build().await;
+
+    let response = app.get("/api/analytics/sentiment?granularity=week").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_analytics_volume() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .build()
+        .await;
+
+    let response = app.get("/api/analytics/volume?video_id=v1").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_analytics_separate_shorts() {
+    let app = TestApp::builder()
+        .video("v1")
+        .short("s1")
+        .comments("v1", vec![comment("v1", "c1", "Great video")])
+        .comments("s1", vec![comment("s1", "c2", "😂😂"), comment("s1", "c3", "🔥")])
+        .build()
+        .await;
+
+    let volume = app.get("/api/analytics/volume?video_format=short").await.json();
+    assert_eq!(volume["videos"].as_object().unwrap().keys().collect::<Vec<_>>(), vec!["s1"]);
+    let volume = app.get("/api/analytics/volume?video_format=long_form").await.json();
+    assert_eq!(volume["videos"].as_object().unwrap().keys().collect::<Vec<_>>(), vec!["v1"]);
+
+    let compared = app.get("/api/analytics/compare?videos=v1,s1").await.json();
+    let formats: Vec<&str> = compared.as_array().unwrap().iter().map(|v| v["format"].as_str().unwrap()).collect();
+    assert_eq!(formats, vec!["long_form", "short"]);
+}
+
+#[tokio::test]
+async fn test_analytics_keywords() {
+    let app = TestApp::builder().video("v1").build().await;
+
+    // Not computed until a refresh job has run
+    let response = app.get("/api/analytics/keywords").await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_analytics_keywords_refresh() {
+    let app = TestApp::builder()
+        .video("v1")
+        .comments("v1", vec![comment("v1", "c1", "Loved the #drone shots")])
+        .build()
+        .await;
+
+    let response = app.post("/api/analytics/keywords/refresh", json!({})).await;
+    assert_eq!(response.status, StatusCode::ACCEPTED);
+
+    let job = wait_for_job(&app, response.json()["job_id"].as_str().unwrap()).await;
+    assert_eq!(job["status"], "Completed");
+
+    let response = app.get("/api/analytics/keywords").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_analytics_ai_usage() {
+    let app = TestApp::builder().build().await;
+
+    let response = app.get("/api/analytics/ai").await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_analytics_compare() {
+    let app = TestApp::builder().video("v1").video("v2").build().await;
+
+    let response = app.get("/api/analytics/compare?videos=v1,v2").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json().as_array().unwrap().len(), 2);
+
+    let response = app.get("/api/analytics/compare?videos=").await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_export_analytics() {
+    let app = TestApp::builder().video("v1").build().await;
+
+    let response = app.get("/api/export/analytics?days=3&format=ndjson").await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.text().lines().count(), 3);
+}
+
+#[tokio::test]
+async fn test_admin_requires_token() {
+    let app = TestApp::builder().build().await;
+
+    let response = app
+        .send(Method::PUT, "/api/admin/prompts/tone/friendly", Some(USER_ID), Some(json!({ "text": "Be chill." })))
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert_eq!(response.error_code(), "forbidden");
+}
+
+#[tokio::test]
+async fn test_update_language_preferences() {
+    let app = TestApp::builder().build().await;
+    app.db.save_user(&user(USER_ID)).await.unwrap();
+
+    let body = json!({ "language": "es", "preferred_reply_language": "es-MX" });
+    let response = app.send(Method::PUT, "/api/preferences/language", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let preferences = app.db.get_user(USER_ID).await.unwrap().unwrap().preferences;
+    assert_eq!(preferences.language.as_deref(), Some("es"));
+    assert_eq!(preferences.preferred_reply_language.as_deref(), Some("es-MX"));
+
+    let body = json!({ "language": "klingon", "preferred_reply_language": null });
+    let response = app.send(Method::PUT, "/api/preferences/language", Some(USER_ID), Some(body)).await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
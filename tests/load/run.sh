@@ -0,0 +1,53 @@
+#!/usr/bin/env bash
+# Drive the API with oha against stubbed YouTube/OpenAI upstreams.
+#
+# Requires oha (`cargo install oha`). Tune with DURATION, CONCURRENCY and
+# STUB_COMMENT_PAGES; results are written to target/load/.
+set -euo pipefail
+
+cd "$(dirname "$0")/../.."
+
+DURATION="${DURATION:-30s}"
+CONCURRENCY="${CONCURRENCY:-50}"
+STUB_PORT="${STUB_PORT:-8089}"
+APP="http://127.0.0.1:3000"
+STUB="http://127.0.0.1:${STUB_PORT}"
+OUT="target/load"
+
+mkdir -p "$OUT"
+cargo build --release --bin youtube-commenter --example stub_upstreams
+
+cleanup() { kill $(jobs -p) 2>/dev/null || true; }
+trap cleanup EXIT
+
+STUB_PORT="$STUB_PORT" target/release/examples/stub_upstreams &
+
+YOUTUBE_OAUTH_CLIENT_ID=load \
+YOUTUBE_OAUTH_CLIENT_SECRET=load \
+YOUTUBE_OAUTH_REDIRECT_URI="$APP/api/auth/callback" \
+GOOGLE_OAUTH_TOKEN_URL="$STUB/token" \
+GOOGLE_USERINFO_URL="$STUB/oauth2/v1/userinfo" \
+YOUTUBE_API_BASE_URL="$STUB/youtube/v3" \
+OPENAI_API_BASE_URL="$STUB/v1" \
+OPENAI_API_KEY=load \
+YOUTUBE_MAX_REQUESTS_PER_SEC=1000 \
+RUST_LOG=warn \
+target/release/youtube-commenter &
+
+until curl -sf "$APP/api/health" >/dev/null; do sleep 0.2; done
+
+# Sign in through the stubbed OAuth flow to get a session
+SESSION=$(curl -s -o /dev/null -w '%{redirect_url}' "$APP/api/auth/callback?code=load" | sed 's/.*session_id=//')
+HEADER="x-session-id: $SESSION"
+
+# Sync path: the first request fetches from the stub, the rest read from the database
+curl -sf -H "$HEADER" "$APP/api/comments/video-0" >/dev/null
+oha -z "$DURATION" -c "$CONCURRENCY" --no-tui -j -H "$HEADER" \
+    "$APP/api/comments/video-0" > "$OUT/comments.json"
+
+# Generation path: prompt building, the stubbed completion and usage recording
+oha -z "$DURATION" -c "$CONCURRENCY" --no-tui -j -H "$HEADER" \
+    -m POST -T application/json -d '{"comment_id":"video-0-thread-0"}' \
+    "$APP/api/reply/generate" > "$OUT/generate.json"
+
+echo "Results written to $OUT/"
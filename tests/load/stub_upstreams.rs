@@ -0,0 +1,151 @@
+//! Stub Google OAuth, YouTube Data API and OpenAI endpoints for load tests.
+//!
+//! Responses are canned and instant, so a load run measures this server rather
+//! than the upstreams. Point the app at it with the variables `run.sh` sets.
+//!
+//! `STUB_PORT` (default 8089) sets the port, `STUB_COMMENT_PAGES` (default 3)
+//! how many 100-comment pages each video has.
+
+use axum::{
+    extract::Query,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+
+const PAGE_SIZE: usize = 100;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageParams {
+    video_id: Option<String>,
+    page_token: Option<String>,
+}
+
+fn comment_pages() -> usize {
+    std::env::var("STUB_COMMENT_PAGES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3)
+}
+
+fn comment_snippet(author: &str, text: &str) -> Value {
+    json!({
+        "authorDisplayName": author,
+        "authorChannelId": { "value": format!("channel-{}", author) },
+        "textDisplay": text,
+        "likeCount": 1,
+        "publishedAt": "2024-01-01T00:00:00Z",
+    })
+}
+
+async fn token() -> Json<Value> {
+    Json(json!({
+        "access_token": "stub-access-token",
+        "refresh_token": "stub-refresh-token",
+        "expires_in": 3600,
+        "token_type": "Bearer",
+        "scope": "https://www.googleapis.com/auth/youtube.force-ssl",
+    }))
+}
+
+async fn userinfo() -> Json<Value> {
+    Json(json!({
+        "id": "load-test-user",
+        "email": "load@example.com",
+        "name": "Load Test",
+        "picture": null,
+    }))
+}
+
+async fn comment_threads(Query(params): Query<PageParams>) -> Json<Value> {
+    let video_id = params.video_id.unwrap_or_default();
+    let page: usize = params.page_token.and_then(|t| t.parse().ok()).unwrap_or(0);
+
+    let items: Vec<Value> = (0..PAGE_SIZE)
+        .map(|i| {
+            let n = page * PAGE_SIZE + i;
+            json!({
+                "id": format!("{}-thread-{}", video_id, n),
+                "snippet": {
+                    "totalReplyCount": 0,
+                    "topLevelComment": {
+                        "snippet": comment_snippet(&format!("viewer{}", n), "Great video, what camera do you use?"),
+                    },
+                },
+            })
+        })
+        .collect();
+
+    let next_page_token = (page + 1 < comment_pages()).then(|| (page + 1).to_string());
+    Json(json!({ "items": items, "nextPageToken": next_page_token }))
+}
+
+async fn list_replies() -> Json<Value> {
+    Json(json!({ "items": [], "nextPageToken": null }))
+}
+
+async fn insert_reply() -> Json<Value> {
+    Json(json!({
+        "id": "stub-reply",
+        "snippet": comment_snippet("creator", "Thanks for watching!"),
+    }))
+}
+
+async fn channels() -> Json<Value> {
+    Json(json!({ "items": [{ "id": "stub-channel" }] }))
+}
+
+async fn search() -> Json<Value> {
+    let items: Vec<Value> = (0..10)
+        .map(|i| {
+            json!({
+                "id": { "videoId": format!("video-{}", i) },
+                "snippet": {
+                    "title": format!("Stub video {}", i),
+                    "description": "",
+                    "publishedAt": "2024-01-01T00:00:00Z",
+                    "thumbnails": { "default": { "url": "https://example.com/thumb.jpg" } },
+                },
+            })
+        })
+        .collect();
+
+    Json(json!({ "items": items, "nextPageToken": null }))
+}
+
+async fn chat_completions() -> Json<Value> {
+    Json(json!({
+        "id": "stub-completion",
+        "choices": [{
+            "message": { "role": "assistant", "content": "Thanks so much for watching!" },
+            "finish_reason": "stop",
+        }],
+        "usage": { "prompt_tokens": 120, "completion_tokens": 12, "total_tokens": 132 },
+    }))
+}
+
+#[tokio::main]
+async fn main() {
+    let port: u16 = std::env::var("STUB_PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8089);
+
+    let app = Router::new()
+        .route("/token", post(token))
+        .route("/oauth2/v1/userinfo", get(userinfo))
+        .route("/youtube/v3/commentThreads", get(comment_threads))
+        .route("/youtube/v3/comments", get(list_replies).post(insert_reply))
+        .route("/youtube/v3/channels", get(channels))
+        .route("/youtube/v3/search", get(search))
+        .route("/v1/chat/completions", post(chat_completions));
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    println!("Stub upstreams listening on {}", addr);
+
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    axum::serve(listener, app).await.unwrap();
+}
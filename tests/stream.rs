@@ -0,0 +1,106 @@
+//! Tests of the sink publishing comment events to a message broker, over a recording publisher.
+
+mod common;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use youtube_commenter::db;
+use youtube_commenter::models::{InteractionRecord, InteractionType};
+use youtube_commenter::services::stream::{StreamPublisher, StreamSink};
+
+use common::{comment, USER_ID};
+
+/// Records the messages published as (subject, key, payload)
+#[derive(Clone, Default)]
+struct RecordingPublisher {
+    published: Arc<Mutex<Vec<(String, String, Value)>>>,
+}
+
+#[async_trait]
+impl StreamPublisher for RecordingPublisher {
+    fn name(&self) -> &'static str {
+        "recording"
+    }
+
+    async fn publish(&self, subject: &str, key: &str, payload: Vec<u8>) -> Result<()> {
+        let payload = serde_json::from_slice(&payload)?;
+        self.published.lock().unwrap().push((subject.to_string(), key.to_string(), payload));
+        Ok(())
+    }
+}
+
+impl RecordingPublisher {
+    fn take(&self) -> Vec<(String, String, Value)> {
+        std::mem::take(&mut *self.published.lock().unwrap())
+    }
+}
+
+fn interaction(comment_id: &str, interaction_type: InteractionType, data: HashMap<String, String>) -> InteractionRecord {
+    InteractionRecord {
+        id: uuid::Uuid::new_v4().to_string(),
+        user_id: USER_ID.to_string(),
+        video_id: "v1".to_string(),
+        comment_id: comment_id.to_string(),
+        reply_id: (interaction_type == InteractionType::ReplyPosted).then(|| "r1".to_string()),
+        interaction_type,
+        timestamp: Utc::now(),
+        data,
+    }
+}
+
+#[tokio::test]
+async fn test_publishes_comments_and_interactions() {
+    let db = db::init_db().await.unwrap();
+    let publisher = RecordingPublisher::default();
+    let sink = StreamSink::new(db.clone(), Box::new(publisher.clone()), "yc");
+
+    // The history from before the sink first ran isn't published
+    db.record_interaction(&interaction("old", InteractionType::CommentReceived, HashMap::new())).await.unwrap();
+    assert_eq!(sink.publish_pending().await.unwrap(), 0);
+
+    db.save_comments("v1", &[comment("v1", "c1", "First!")]).await.unwrap();
+    db.record_interaction(&interaction("c1", InteractionType::CommentReceived, HashMap::new())).await.unwrap();
+    assert_eq!(sink.publish_pending().await.unwrap(), 2);
+
+    let published = publisher.take();
+    assert_eq!(published[0].0, "yc.comment");
+    assert_eq!(published[0].1, USER_ID);
+    assert_eq!(published[0].2["type"], "comment");
+    assert_eq!(published[0].2["data"]["text"], "First!");
+    assert_eq!(published[1].0, "yc.interaction");
+    assert_eq!(published[1].2["data"]["interaction_type"], "CommentReceived");
+
+    // Receiving the comment again unchanged only publishes the interaction
+    db.record_interaction(&interaction("c1", InteractionType::CommentReceived, HashMap::new())).await.unwrap();
+    assert_eq!(sink.publish_pending().await.unwrap(), 1);
+    assert_eq!(publisher.take()[0].0, "yc.interaction");
+    assert_eq!(sink.publish_pending().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_publishes_posted_replies() {
+    let db = db::init_db().await.unwrap();
+    let publisher = RecordingPublisher::default();
+    let sink = StreamSink::new(db.clone(), Box::new(publisher.clone()), "yc");
+    sink.publish_pending().await.unwrap();
+
+    db.save_comments("v1", &[comment("v1", "c1", "First!")]).await.unwrap();
+    let data = HashMap::from([
+        ("reply_text".to_string(), "Thanks!".to_string()),
+        ("ai_model".to_string(), "gpt-3.5-turbo".to_string()),
+    ]);
+    db.record_interaction(&interaction("c1", InteractionType::ReplyPosted, data)).await.unwrap();
+    assert_eq!(sink.publish_pending().await.unwrap(), 2);
+
+    let published = publisher.take();
+    assert_eq!(published[0].0, "yc.reply");
+    let reply = &published[0].2["data"];
+    assert_eq!(reply["reply_id"], "r1");
+    assert_eq!(reply["video_id"], "v1");
+    assert_eq!(reply["text"], "Thanks!");
+    assert_eq!(reply["ai_generated"], true);
+}
@@ -0,0 +1,767 @@
+//! End-to-end tests of the upstream clients against wiremock servers emulating
+//! Google OAuth, the YouTube Data API and OpenAI.
+
+mod common;
+
+use chrono::Duration;
+use serde_json::json;
+use wiremock::matchers::{body_partial_json, body_string_contains, header, method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, ResponseTemplate};
+
+use common::mock_upstreams::{
+    chat_completion, comment_item, comment_thread, page, token_response, youtube_error, MockUpstreams, ACCESS_TOKEN,
+    REFRESH_TOKEN,
+};
+use common::{comment, user, USER_ID};
+use youtube_commenter::error::AppError;
+use youtube_commenter::models::ai::{ParameterOverrides, ReplyGenerationRequest};
+use youtube_commenter::models::alert::AlertRule;
+use youtube_commenter::models::video::{VideoFormat, SHORT_KEY};
+use youtube_commenter::utils::upstream::CircuitState;
+
+const BEARER: &str = "Bearer valid-access-token";
+
+fn reply_request() -> ReplyGenerationRequest {
+    ReplyGenerationRequest {
+        comment_text: "What camera do you use?".to_string(),
+        comment_author: "Viewer".to_string(),
+        video_title: "Drone tour".to_string(),
+        video_id: "v1".to_string(),
+        previous_interactions: Vec::new(),
+        thread_replies: Vec::new(),
+        follow_up: None,
+        timestamps: Vec::new(),
+        tone: "friendly".to_string(),
+        persona: None,
+        reply_language: None,
+        template: None,
+        de_escalate: false,
+        additional_instructions: None,
+        max_length: None,
+        parameter_overrides: None,
+    }
+}
+
+#[tokio::test]
+async fn test_sync_comments_follows_pages() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param("videoId", "v1"))
+        .and(query_param_is_missing("pageToken"))
+        .and(header("authorization", BEARER))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![comment_thread("c1", "First!", 0), comment_thread("c2", "Second", 0)],
+            Some("page-2"),
+        )))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param("pageToken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c3", "Third", 0)], None)))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let count = mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(mock.db.get_comments("v1").await.unwrap().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_sync_comments_stops_at_synced_comments() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    let mut newer = comment_thread("c0", "Just watched", 0);
+    newer["snippet"]["topLevelComment"]["snippet"]["publishedAt"] = json!("2024-02-01T00:00:00Z");
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param_is_missing("pageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c1", "First!", 0)], Some("page-2"))))
+        .up_to_n_times(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param_is_missing("pageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![newer, comment_thread("c1", "First!", 0)],
+            Some("page-2"),
+        )))
+        .mount(&mock.server)
+        .await;
+    // Only the first, full sync gets this far
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param("pageToken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c2", "Second", 0)], None)))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    assert_eq!(mock.youtube.sync_comments(USER_ID, "v1").await.unwrap(), 2);
+    assert_eq!(mock.youtube.sync_comments(USER_ID, "v1").await.unwrap(), 2);
+
+    assert_eq!(mock.db.get_comments("v1").await.unwrap().unwrap().len(), 3);
+    let state = mock.db.get_comment_sync_state("v1").await.unwrap().unwrap();
+    assert_eq!(state.newest_published_at.unwrap().to_rfc3339(), "2024-02-01T00:00:00+00:00");
+    assert!(state.full_synced_at.is_some());
+}
+
+#[tokio::test]
+async fn test_incremental_sync_checks_answered_threads_for_replies() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param("videoId", "v1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c1", "First!", 0)], None)))
+        .mount(&mock.server)
+        .await;
+    let mut answered = comment_thread("c1", "First!", 1);
+    answered["replies"] = json!({ "comments": [comment_item("c1.r1", "Thanks!")] });
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(query_param("id", "c1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![answered], None)))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    // The first sync is a full one, so only the second looks the answered thread up
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+    mock.db.mark_comment_replied("c1", true).await.unwrap();
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+
+    let stored = mock.db.get_comment("c1").await.unwrap().unwrap();
+    assert_eq!(stored.reply_count, 1);
+    assert_eq!(stored.replies.len(), 1);
+    assert!(stored.replied_to);
+}
+
+#[tokio::test]
+async fn test_sync_comments_again_updates_stored() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c1", "Frist!", 0)], None)))
+        .up_to_n_times(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c1", "First!", 0)], None)))
+        .mount(&mock.server)
+        .await;
+
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+    mock.db.mark_comment_replied("c1", true).await.unwrap();
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+
+    let comments = mock.db.get_comments("v1").await.unwrap().unwrap();
+    assert_eq!(comments.len(), 1);
+    assert_eq!(comments[0].text, "First!");
+    assert!(comments[0].replied_to);
+}
+
+#[tokio::test]
+async fn test_sync_comments_parses_timestamps() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![comment_thread("c1", "The landing at 3:07 was insane", 0)],
+            None,
+        )))
+        .mount(&mock.server)
+        .await;
+
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+    let comment = mock.db.get_comment("c1").await.unwrap().unwrap();
+    assert_eq!(comment.timestamps.len(), 1);
+    assert_eq!(comment.timestamps[0].seconds, 187);
+    assert_eq!(comment.timestamps[0].url, "https://www.youtube.com/watch?v=v1&t=187s");
+}
+
+#[tokio::test]
+async fn test_sync_comments_keeps_original_text() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    let mut thread = comment_thread("c1", "Watch <a href=\"https://www.youtube.com/watch?v=v1&amp;t=187\">3:07</a> &amp; more", 0);
+    thread["snippet"]["topLevelComment"]["snippet"]["textOriginal"] = json!("Watch 3:07 & more");
+    thread["replies"] = json!({ "comments": [comment_item("c1.r1", "Agreed")] });
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![thread], None)))
+        .mount(&mock.server)
+        .await;
+
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+    let comment = mock.db.get_comment("c1").await.unwrap().unwrap();
+    assert!(comment.text.contains("<a href="));
+    assert_eq!(comment.text_original.as_deref(), Some("Watch 3:07 & more"));
+    assert_eq!(comment.original_text(), "Watch 3:07 & more");
+
+    // Without the original, the displayed text stands in
+    assert_eq!(comment.replies[0].text_original, None);
+    assert_eq!(comment.replies[0].original_text(), "Agreed");
+}
+
+#[tokio::test]
+async fn test_sync_comments_counts_alerts() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    let product = AlertRule::new(USER_ID, "Product", vec!["SkyCam".to_string()], false, None);
+    let mut questions = AlertRule::new(USER_ID, "Questions", Vec::new(), true, None);
+    questions.created_at = product.created_at + Duration::seconds(1);
+    mock.db.save_alert_rule(&product).await.unwrap();
+    mock.db.save_alert_rule(&questions).await.unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![
+                comment_thread("c1", "Is the skycam worth it?", 0),
+                comment_thread("c2", "How long did this take", 0),
+                comment_thread("c3", "Beautiful shots", 0),
+            ],
+            None,
+        )))
+        .mount(&mock.server)
+        .await;
+
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+
+    // Each comment alerts once, for the first rule it matches
+    let rules = mock.db.get_alert_rules(USER_ID).await.unwrap();
+    let hits: Vec<(&str, u64)> = rules.iter().map(|r| (r.name.as_str(), r.hit_count)).collect();
+    assert_eq!(hits, vec![("Product", 1), ("Questions", 1)]);
+}
+
+#[tokio::test]
+async fn test_channel_videos_detect_shorts() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    let search_item = |id: &str, title: &str| {
+        json!({
+            "id": { "videoId": id },
+            "snippet": {
+                "title": title,
+                "description": "",
+                "publishedAt": "2024-05-01T12:00:00Z",
+                "thumbnails": { "default": { "url": format!("https://i.ytimg.com/vi/{}/default.jpg", id) } },
+            },
+        })
+    };
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/channels"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "items": [{ "id": "UCchannel" }] })))
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/search"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "items": [search_item("s1", "Landing in 30 seconds #shorts"), search_item("v1", "Full drone tour")],
+        })))
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/videos"))
+        .and(query_param("id", "s1,v1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "items": [
+            { "id": "s1", "contentDetails": { "duration": "PT1M30S" } },
+            { "id": "v1", "contentDetails": { "duration": "PT12M3S" } },
+        ] })))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c1", "🔥🔥🔥", 0)], None)))
+        .mount(&mock.server)
+        .await;
+
+    let videos = mock.youtube.get_channel_videos(USER_ID).await.unwrap();
+    assert_eq!(videos[0].duration_secs, Some(90));
+
+    let short = mock.db.get_video("s1").await.unwrap().unwrap();
+    assert_eq!(short.format, VideoFormat::Short);
+    assert_eq!(mock.db.get_video("v1").await.unwrap().unwrap().format, VideoFormat::LongForm);
+
+    // Comments on a Short are tagged as such
+    mock.youtube.sync_comments(USER_ID, "s1").await.unwrap();
+    let comments = mock.db.get_comments("s1").await.unwrap().unwrap();
+    assert_eq!(comments[0].metadata.get(SHORT_KEY).map(String::as_str), Some("true"));
+}
+
+#[tokio::test]
+async fn test_refresh_active_comments() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    let mut old = comment("v1", "c3", "Too old to refresh");
+    old.published_at -= Duration::days(30);
+    mock.db.save_comments("v1", &[comment("v1", "c1", "Frist!"), comment("v1", "c2", "Nice"), old]).await.unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/comments"))
+        .and(query_param("part", "snippet"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![comment_item("c1", "First! (edited)"), comment_item("c2", "Nice")],
+            None,
+        )))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let mut events = mock.events.subscribe(USER_ID);
+    let edited = mock.youtube.refresh_active_comments(USER_ID, &["v1".to_string()]).await.unwrap();
+    assert_eq!(edited, 1);
+
+    let c1 = mock.db.get_comment("c1").await.unwrap().unwrap();
+    assert_eq!(c1.text, "First! (edited)");
+    let c2 = mock.db.get_comment("c2").await.unwrap().unwrap();
+    assert_eq!((c2.text.as_str(), c2.like_count), ("Nice", 2));
+
+    let event = serde_json::to_value(events.try_recv().unwrap()).unwrap();
+    assert_eq!(event, json!({ "type": "comment_edited", "video_id": "v1", "comment_id": "c1", "text": "First! (edited)" }));
+    assert!(events.try_recv().is_err());
+
+    let requests = mock.server.received_requests().await.unwrap();
+    let ids = requests[0].url.query_pairs().find(|(k, _)| k == "id").unwrap().1.to_string();
+    let mut ids: Vec<&str> = ids.split(',').collect();
+    ids.sort();
+    assert_eq!(ids, vec!["c1", "c2"]);
+}
+
+#[tokio::test]
+async fn test_thread_replies_follow_pages() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    let mut parent = comment("v1", "c1", "How did you film this?");
+    parent.reply_count = 3;
+    mock.db.save_comments("v1", &[parent]).await.unwrap();
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/comments"))
+        .and(query_param("parentId", "c1"))
+        .and(query_param_is_missing("pageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![comment_item("c1.r1", "Drone"), comment_item("c1.r2", "Which one?")],
+            Some("page-2"),
+        )))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/comments"))
+        .and(query_param("pageToken", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_item("c1.r3", "Mavic")], None)))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let replies = mock.youtube.get_thread_replies(USER_ID, "c1").await.unwrap().unwrap();
+    assert_eq!(replies.len(), 3);
+    assert_eq!(replies[2].parent_id, "c1");
+
+    // Stored now, so opening the thread again doesn't call YouTube
+    let replies = mock.youtube.get_thread_replies(USER_ID, "c1").await.unwrap().unwrap();
+    assert_eq!(replies.len(), 3);
+}
+
+#[tokio::test]
+async fn test_youtube_error_codes() {
+    let cases = [
+        (403, "quotaExceeded", "upstream_quota"),
+        (401, "authError", "upstream_auth"),
+        (404, "videoNotFound", "not_found"),
+        (400, "badRequest", "internal"),
+    ];
+
+    for (status, reason, code) in cases {
+        let mock = MockUpstreams::start().await;
+        mock.sign_in(Duration::hours(1)).await;
+
+        Mock::given(method("GET"))
+            .and(path("/youtube/v3/commentThreads"))
+            .respond_with(ResponseTemplate::new(status).set_body_json(youtube_error(status, reason)))
+            .expect(1)
+            .mount(&mock.server)
+            .await;
+
+        let error = mock.youtube.sync_comments(USER_ID, "v1").await.unwrap_err();
+        assert!(error.to_string().contains("Failed to get comments") || status == 404, "{}", error);
+        assert_eq!(AppError::from(error).code(), code, "HTTP {} {}", status, reason);
+
+        let metric = format!("upstream_error_responses_total{{upstream=\"youtube\",status=\"{}\"}} 1", status);
+        assert!(mock.upstreams.render_metrics().contains(&metric), "{}", metric);
+    }
+}
+
+#[tokio::test]
+async fn test_server_errors_are_retried() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![comment_thread("c1", "First!", 0)], None)))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    assert_eq!(mock.youtube.sync_comments(USER_ID, "v1").await.unwrap(), 1);
+    assert_eq!(mock.upstreams.youtube.state(), CircuitState::Closed);
+}
+
+#[tokio::test]
+async fn test_post_reply_is_not_retried() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+    mock.db.save_comments("v1", &[comment("v1", "c1", "Great video")]).await.unwrap();
+
+    Mock::given(method("POST"))
+        .and(path("/youtube/v3/comments"))
+        .and(body_partial_json(json!({ "snippet": { "parentId": "c1", "textOriginal": "Thanks!" } })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(comment_item("c1.r1", "Thanks!")))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let reply = mock.youtube.post_reply(USER_ID, "c1", "Thanks!").await.unwrap();
+    assert_eq!(reply.reply_id, "c1.r1");
+    assert!(mock.db.get_comment("c1").await.unwrap().unwrap().replied_to);
+
+    Mock::given(method("POST"))
+        .and(path("/youtube/v3/comments"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    assert!(mock.youtube.post_reply(USER_ID, "c1", "Thanks again!").await.is_err());
+}
+
+#[tokio::test]
+async fn test_spam_is_quarantined_and_rejected() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(
+            vec![
+                comment_thread("c1", "Earn $500 a day with crypto, check out my channel bit.ly/xyz", 0),
+                comment_thread("c2", "Loved the drone shots", 0),
+            ],
+            None,
+        )))
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/youtube/v3/comments/setModerationStatus"))
+        .and(query_param("id", "c1"))
+        .and(query_param("moderationStatus", "rejected"))
+        .and(query_param("banAuthor", "true"))
+        .and(header("authorization", BEARER))
+        .respond_with(ResponseTemplate::new(204))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+    let spam = mock.db.get_comment("c1").await.unwrap().unwrap();
+    assert_eq!(spam.metadata["spam"], "classifier");
+    assert!(!mock.db.get_comment("c2").await.unwrap().unwrap().metadata.contains_key("spam"));
+
+    mock.youtube.reject_comment(USER_ID, "c1", true).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_expired_token_is_refreshed() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::minutes(-1)).await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("grant_type=refresh_token"))
+        .and(body_string_contains(format!("refresh_token={}", REFRESH_TOKEN)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(token_response("refreshed-token")))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/youtube/v3/commentThreads"))
+        .and(header("authorization", "Bearer refreshed-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![], None)))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    mock.youtube.sync_comments(USER_ID, "v1").await.unwrap();
+
+    let token = mock.db.get_auth_token(USER_ID).await.unwrap().unwrap();
+    assert_eq!(token.access_token, "refreshed-token");
+    // Google doesn't send a new refresh token on refresh, so the original is kept
+    assert_eq!(token.refresh_token, REFRESH_TOKEN);
+}
+
+#[tokio::test]
+async fn test_revoked_refresh_token() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::minutes(-1)).await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "invalid_grant" })))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let error = mock.auth.get_valid_access_token(USER_ID).await.unwrap_err();
+    assert_eq!(AppError::from(error).code(), "upstream_auth");
+}
+
+#[tokio::test]
+async fn test_revoked_refresh_token_disconnects_user() {
+    let mock = MockUpstreams::start().await;
+    mock.db.save_user(&user(USER_ID)).await.unwrap();
+    mock.sign_in(Duration::minutes(-1)).await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "invalid_grant" })))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    mock.auth.get_valid_access_token(USER_ID).await.unwrap_err();
+    let stored = mock.db.get_user(USER_ID).await.unwrap().unwrap();
+    assert!(stored.is_disconnected());
+    assert_eq!(stored.connection.disconnect_reason.as_deref(), Some("invalid_grant"));
+
+    // Google isn't asked again until the user reconnects
+    let error = mock.auth.get_valid_access_token(USER_ID).await.unwrap_err();
+    assert_eq!(AppError::from(error).code(), "upstream_auth");
+}
+
+#[tokio::test]
+async fn test_valid_token_is_not_refreshed() {
+    let mock = MockUpstreams::start().await;
+    mock.sign_in(Duration::hours(1)).await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(token_response("unused")))
+        .expect(0)
+        .mount(&mock.server)
+        .await;
+
+    assert_eq!(mock.auth.get_valid_access_token(USER_ID).await.unwrap(), ACCESS_TOKEN);
+}
+
+#[tokio::test]
+async fn test_sign_in() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("grant_type=authorization_code"))
+        .and(body_string_contains("code=auth-code"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(token_response("new-access-token")))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/oauth2/v1/userinfo"))
+        .and(header("authorization", "Bearer new-access-token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "google-user",
+            "email": "creator@example.com",
+            "name": "Creator",
+            "picture": null,
+        })))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let token = mock.auth.exchange_code("auth-code").await.unwrap();
+    let user = mock.auth.create_or_update_user(&token).await.unwrap();
+    assert_eq!(user.id, "google-user");
+    assert_eq!(mock.db.get_auth_token("google-user").await.unwrap().unwrap().access_token, "new-access-token");
+}
+
+#[tokio::test]
+async fn test_rejected_authorization_code() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({ "error": "invalid_grant" })))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let error = mock.auth.exchange_code("stale-code").await.unwrap_err();
+    assert_eq!(AppError::from(error).code(), "upstream_auth");
+}
+
+#[cfg(feature = "openai")]
+#[tokio::test]
+async fn test_generate_reply() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer test-openai-key"))
+        .and(body_partial_json(json!({ "model": "gpt-3.5-turbo" })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(chat_completion("A DJI Mavic 3!")))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let response = mock.ai.generate_reply(USER_ID, &reply_request()).await.unwrap();
+    assert_eq!(response.reply_text, "A DJI Mavic 3!");
+    assert_eq!(response.usage.total_tokens, 132);
+
+    let usage = mock.db.get_ai_usage_since(USER_ID, chrono::Utc::now() - Duration::hours(1)).await.unwrap();
+    assert_eq!(usage.len(), 1);
+    assert!(usage[0].success);
+}
+
+#[cfg(feature = "openai")]
+#[tokio::test]
+async fn test_generate_reply_from_template() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_string_contains("Base the reply on this saved reply"))
+        .and(body_string_contains("Thanks Viewer! My gear list is in the description."))
+        .respond_with(ResponseTemplate::new(200).set_body_json(chat_completion("Thanks Viewer! It's a DJI Mavic 3, full gear list in the description.")))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let mut request = reply_request();
+    request.template = Some("Thanks Viewer! My gear list is in the description.".to_string());
+    let response = mock.ai.generate_reply(USER_ID, &request).await.unwrap();
+    assert!(response.reply_text.contains("gear list"));
+}
+
+#[cfg(feature = "openai")]
+#[tokio::test]
+async fn test_generate_reply_builds_on_thread() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_string_contains("Replies already in this thread"))
+        .and(body_string_contains("Looks like a Mavic 3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(chat_completion("Good eye, it's the Mavic 3 Pro!")))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let mut request = reply_request();
+    request.thread_replies = vec!["Someone else: \"Looks like a Mavic 3\"".to_string()];
+    let response = mock.ai.generate_reply(USER_ID, &request).await.unwrap();
+    assert!(response.reply_text.contains("Mavic 3 Pro"));
+}
+
+#[cfg(feature = "openai")]
+#[tokio::test]
+async fn test_generate_reply_with_parameter_overrides() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_partial_json(json!({ "model": "gpt-4", "temperature": 0.25, "max_tokens": 200, "top_p": 0.5 })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(chat_completion("A DJI Mavic 3!")))
+        .expect(1)
+        .mount(&mock.server)
+        .await;
+
+    let mut request = reply_request();
+    request.max_length = Some(60);
+    request.parameter_overrides = Some(ParameterOverrides {
+        model: Some("gpt-4".to_string()),
+        temperature: Some(0.25),
+        max_tokens: Some(200),
+        top_p: Some(0.5),
+    });
+    let response = mock.ai.generate_reply(USER_ID, &request).await.unwrap();
+    assert_eq!(response.model, "gpt-4");
+
+    // gpt-3.5-turbo answers with at most 1024 tokens
+    request.parameter_overrides = Some(ParameterOverrides { max_tokens: Some(2048), ..Default::default() });
+    let error = mock.ai.generate_reply(USER_ID, &request).await.unwrap_err();
+    assert!(matches!(error.downcast_ref::<AppError>(), Some(AppError::Validation(_))));
+
+    request.parameter_overrides = Some(ParameterOverrides { top_p: Some(1.5), ..Default::default() });
+    assert!(mock.ai.generate_reply(USER_ID, &request).await.is_err());
+}
+
+#[cfg(feature = "openai")]
+#[tokio::test]
+async fn test_ai_provider_error() {
+    let mock = MockUpstreams::start().await;
+
+    // Retried once, then reported
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("overloaded"))
+        .expect(2)
+        .mount(&mock.server)
+        .await;
+
+    let error = mock.ai.generate_reply(USER_ID, &reply_request()).await.unwrap_err();
+    assert_eq!(AppError::from(error).code(), "ai_provider");
+
+    let usage = mock.db.get_ai_usage_since(USER_ID, chrono::Utc::now() - Duration::hours(1)).await.unwrap();
+    assert!(!usage[0].success);
+}
+
+#[cfg(feature = "openai")]
+#[tokio::test]
+async fn test_open_ai_circuit_is_unavailable() {
+    let mock = MockUpstreams::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(500).set_body_string("overloaded"))
+        .mount(&mock.server)
+        .await;
+
+    // Enough failed calls open the circuit, after which calls don't reach OpenAI
+    for _ in 0..5 {
+        mock.ai.generate_reply(USER_ID, &reply_request()).await.unwrap_err();
+    }
+    assert_eq!(mock.upstreams.openai.state(), CircuitState::Open);
+
+    let error = mock.ai.generate_reply(USER_ID, &reply_request()).await.unwrap_err();
+    assert_eq!(AppError::from(error).code(), "unavailable");
+}